@@ -0,0 +1,430 @@
+//! 待审核请求队列
+//!
+//! Agent 发起、需要用户批准的请求（工具调用、Skill 脚本运行、补丁、后续任务）共用同一套队列：
+//! - 请求状态变化时通过队列的事件推送到前端，由用户在对话页审核
+//! - 工具调用等待审核结果期间结束（超时、对话被取消）时，请求自动标记为过期
+//! - 每个会话同时进行中的请求数有上限，过期或被放弃的请求不占名额
+//! - 已结束的请求保留 [`FINISHED_RETENTION`] 后清理
+//!
+//! 请求只保存在内存中，应用重启后不会恢复。
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::oneshot;
+
+/// 已结束的请求保留时间
+pub const FINISHED_RETENTION: Duration = Duration::from_secs(3600);
+
+/// 队列中的请求
+pub trait ApprovalItem: Clone + Serialize + Send + Sync + 'static {
+    fn id(&self) -> &str;
+
+    /// 所属会话（定时任务等无会话的请求为空）
+    fn session_id(&self) -> Option<&str>;
+
+    fn created_at(&self) -> DateTime<Utc>;
+
+    /// 是否等待用户批准
+    fn is_pending(&self) -> bool;
+
+    /// 是否仍在进行中（计入会话上限，不会被清理）
+    fn is_active(&self) -> bool {
+        self.is_pending()
+    }
+
+    /// 记录用户的审核结果
+    fn set_reviewed(&mut self, approved: bool);
+
+    /// 等待审核结果的调用已结束（超时或被取消）
+    fn set_expired(&mut self);
+}
+
+struct Entry<T> {
+    item: T,
+    /// 等待审核结果的调用
+    responder: Option<oneshot::Sender<bool>>,
+    /// 请求结束的时间（用于清理）
+    finished_at: Option<DateTime<Utc>>,
+}
+
+impl<T: ApprovalItem> Entry<T> {
+    fn touch(&mut self) {
+        if self.item.is_active() {
+            self.finished_at = None;
+        } else if self.finished_at.is_none() {
+            self.finished_at = Some(Utc::now());
+        }
+    }
+}
+
+/// 待审核请求队列（克隆后共享同一份请求表）
+pub struct ApprovalQueue<T> {
+    entries: Arc<RwLock<HashMap<String, Entry<T>>>>,
+    /// 状态变化事件名（载荷为请求本身）
+    event: &'static str,
+    /// 请求类型名称（用于错误信息）
+    kind: &'static str,
+    /// 每个会话同时进行中的请求上限
+    max_active_per_session: usize,
+    /// 推送状态变化事件（未设置时不推送）
+    app_handle: Arc<RwLock<Option<AppHandle>>>,
+}
+
+impl<T> Clone for ApprovalQueue<T> {
+    fn clone(&self) -> Self {
+        Self {
+            entries: self.entries.clone(),
+            event: self.event,
+            kind: self.kind,
+            max_active_per_session: self.max_active_per_session,
+            app_handle: self.app_handle.clone(),
+        }
+    }
+}
+
+impl<T: ApprovalItem> ApprovalQueue<T> {
+    pub fn new(event: &'static str, kind: &'static str, max_active_per_session: usize) -> Self {
+        Self {
+            entries: Arc::default(),
+            event,
+            kind,
+            max_active_per_session,
+            app_handle: Arc::default(),
+        }
+    }
+
+    /// 设置推送状态变化事件的应用句柄
+    pub fn set_app_handle(&self, app_handle: AppHandle) {
+        *self.app_handle.write() = Some(app_handle);
+    }
+
+    fn emit(&self, item: &T) {
+        if let Some(app_handle) = self.app_handle.read().as_ref() {
+            if let Err(e) = app_handle.emit(self.event, item) {
+                tracing::warn!("[ApprovalQueue] 推送{}状态失败: {}", self.kind, e);
+            }
+        }
+    }
+
+    fn insert(&self, item: T, responder: Option<oneshot::Sender<bool>>) -> Result<T, String> {
+        let mut entries = self.entries.write();
+        prune(&mut entries, Utc::now());
+        if let Some(session_id) = item.session_id() {
+            let active = entries
+                .values()
+                .filter(|e| e.item.session_id() == Some(session_id) && e.item.is_active())
+                .count();
+            if active >= self.max_active_per_session {
+                return Err(format!(
+                    "当前会话已有 {} 个未完成的{}，达到上限",
+                    active, self.kind
+                ));
+            }
+        }
+        let mut entry = Entry {
+            item: item.clone(),
+            responder,
+            finished_at: None,
+        };
+        entry.touch();
+        entries.insert(item.id().to_string(), entry);
+        drop(entries);
+        self.emit(&item);
+        Ok(item)
+    }
+
+    /// 添加请求，由用户稍后审核
+    pub fn submit(&self, item: T) -> Result<T, String> {
+        self.insert(item, None)
+    }
+
+    /// 添加请求并等待审核结果
+    pub fn request(&self, item: T) -> Result<(T, PendingApproval<T>), String> {
+        let (tx, rx) = oneshot::channel();
+        let item = self.insert(item, Some(tx))?;
+        let pending = PendingApproval {
+            queue: self.clone(),
+            id: item.id().to_string(),
+            receiver: Some(rx),
+        };
+        Ok((item, pending))
+    }
+
+    /// 批准或拒绝待批准的请求（等待结果的调用已结束时标记为过期并返回错误）
+    pub fn review(&self, id: &str, approved: bool) -> Result<T, String> {
+        let (item, delivered) = {
+            let mut entries = self.entries.write();
+            let entry = entries
+                .get_mut(id)
+                .ok_or_else(|| format!("{}不存在: {}", self.kind, id))?;
+            if !entry.item.is_pending() {
+                return Err(format!("{}不在待批准状态", self.kind));
+            }
+            let delivered = entry
+                .responder
+                .take()
+                .is_none_or(|tx| tx.send(approved).is_ok());
+            if delivered {
+                entry.item.set_reviewed(approved);
+            } else {
+                entry.item.set_expired();
+            }
+            entry.touch();
+            (entry.item.clone(), delivered)
+        };
+        self.emit(&item);
+        if delivered {
+            Ok(item)
+        } else {
+            Err("调用已结束，请求已过期".to_string())
+        }
+    }
+
+    /// 修改请求并推送状态变化
+    pub fn update(&self, id: &str, f: impl FnOnce(&mut T)) -> Option<T> {
+        let item = {
+            let mut entries = self.entries.write();
+            let entry = entries.get_mut(id)?;
+            f(&mut entry.item);
+            entry.touch();
+            entry.item.clone()
+        };
+        self.emit(&item);
+        Some(item)
+    }
+
+    /// 修改请求，`f` 返回错误时不推送（请求不存在时返回错误）
+    pub fn try_update(
+        &self,
+        id: &str,
+        f: impl FnOnce(&mut T) -> Result<(), String>,
+    ) -> Result<T, String> {
+        let item = {
+            let mut entries = self.entries.write();
+            let entry = entries
+                .get_mut(id)
+                .ok_or_else(|| format!("{}不存在: {}", self.kind, id))?;
+            f(&mut entry.item)?;
+            entry.touch();
+            entry.item.clone()
+        };
+        self.emit(&item);
+        Ok(item)
+    }
+
+    /// 修改所有满足条件的请求，返回被修改的请求
+    pub fn update_where(&self, filter: impl Fn(&T) -> bool, mut f: impl FnMut(&mut T)) -> Vec<T> {
+        let changed: Vec<T> = {
+            let mut entries = self.entries.write();
+            entries
+                .values_mut()
+                .filter(|e| filter(&e.item))
+                .map(|entry| {
+                    f(&mut entry.item);
+                    entry.touch();
+                    entry.item.clone()
+                })
+                .collect()
+        };
+        for item in &changed {
+            self.emit(item);
+        }
+        changed
+    }
+
+    /// 将仍在等待批准的请求标记为过期
+    pub fn expire(&self, id: &str) {
+        let item = {
+            let mut entries = self.entries.write();
+            let Some(entry) = entries.get_mut(id) else {
+                return;
+            };
+            if !entry.item.is_pending() {
+                return;
+            }
+            entry.item.set_expired();
+            entry.responder = None;
+            entry.touch();
+            entry.item.clone()
+        };
+        self.emit(&item);
+    }
+
+    /// 拒绝会话的所有待批准请求（删除会话时调用）
+    pub fn reject_session(&self, session_id: &str) {
+        let rejected: Vec<T> = {
+            let mut entries = self.entries.write();
+            entries
+                .values_mut()
+                .filter(|e| e.item.session_id() == Some(session_id) && e.item.is_pending())
+                .map(|entry| {
+                    if let Some(tx) = entry.responder.take() {
+                        let _ = tx.send(false);
+                    }
+                    entry.item.set_reviewed(false);
+                    entry.touch();
+                    entry.item.clone()
+                })
+                .collect()
+        };
+        for item in &rejected {
+            self.emit(item);
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<T> {
+        self.entries.read().get(id).map(|e| e.item.clone())
+    }
+
+    /// 列出请求（按创建时间排序），可按会话过滤
+    pub fn list(&self, session_id: Option<&str>) -> Vec<T> {
+        let mut items: Vec<T> = self
+            .entries
+            .read()
+            .values()
+            .filter(|e| session_id.is_none_or(|sid| e.item.session_id() == Some(sid)))
+            .map(|e| e.item.clone())
+            .collect();
+        items.sort_by_key(|item| item.created_at());
+        items
+    }
+}
+
+/// 删除结束超过 [`FINISHED_RETENTION`] 的请求
+fn prune<T>(entries: &mut HashMap<String, Entry<T>>, now: DateTime<Utc>) {
+    let retention = ChronoDuration::from_std(FINISHED_RETENTION).unwrap_or_default();
+    entries.retain(|_, e| e.finished_at.is_none_or(|at| now - at < retention));
+}
+
+/// 等待审核结果的请求
+///
+/// 在得到结果前被丢弃（超时、对话被取消）时，请求标记为过期，不再占用会话名额。
+pub struct PendingApproval<T: ApprovalItem> {
+    queue: ApprovalQueue<T>,
+    id: String,
+    receiver: Option<oneshot::Receiver<bool>>,
+}
+
+impl<T: ApprovalItem> PendingApproval<T> {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// 等待审核结果，超时返回 None
+    pub async fn wait(mut self, timeout: Duration) -> Option<bool> {
+        let receiver = self.receiver.take()?;
+        match tokio::time::timeout(timeout, receiver).await {
+            Ok(Ok(approved)) => Some(approved),
+            Ok(Err(_)) => Some(false),
+            Err(_) => None,
+        }
+    }
+}
+
+impl<T: ApprovalItem> Drop for PendingApproval<T> {
+    fn drop(&mut self) {
+        self.queue.expire(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    struct Item {
+        id: String,
+        session_id: String,
+        created_at: DateTime<Utc>,
+        status: &'static str,
+    }
+
+    impl Item {
+        fn new(id: &str) -> Self {
+            Self {
+                id: id.to_string(),
+                session_id: "s1".to_string(),
+                created_at: Utc::now(),
+                status: "pending",
+            }
+        }
+    }
+
+    impl ApprovalItem for Item {
+        fn id(&self) -> &str {
+            &self.id
+        }
+        fn session_id(&self) -> Option<&str> {
+            Some(&self.session_id)
+        }
+        fn created_at(&self) -> DateTime<Utc> {
+            self.created_at
+        }
+        fn is_pending(&self) -> bool {
+            self.status == "pending"
+        }
+        fn set_reviewed(&mut self, approved: bool) {
+            self.status = if approved { "approved" } else { "rejected" };
+        }
+        fn set_expired(&mut self) {
+            self.status = "expired";
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_and_review() {
+        let queue = ApprovalQueue::new("test", "请求", 2);
+        let (item, pending) = queue.request(Item::new("a")).unwrap();
+        assert_eq!(queue.review(&item.id, true).unwrap().status, "approved");
+        assert_eq!(pending.wait(Duration::from_secs(1)).await, Some(true));
+        assert!(queue.review("a", false).is_err());
+        assert!(queue.review("missing", true).is_err());
+
+        // 等待超时后请求过期
+        let (_, pending) = queue.request(Item::new("b")).unwrap();
+        assert_eq!(pending.wait(Duration::from_millis(10)).await, None);
+        assert_eq!(queue.get("b").unwrap().status, "expired");
+        assert!(queue.review("b", true).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_requests_free_their_slot() {
+        let queue = ApprovalQueue::new("test", "请求", 2);
+        let (_, first) = queue.request(Item::new("a")).unwrap();
+        let (_, _second) = queue.request(Item::new("b")).unwrap();
+        assert!(queue.request(Item::new("c")).is_err());
+
+        // 等待中的调用被取消（future 被丢弃）时请求过期并释放名额
+        let waiting = tokio::spawn(first.wait(Duration::from_secs(60)));
+        tokio::task::yield_now().await;
+        waiting.abort();
+        let _ = waiting.await;
+        assert_eq!(queue.get("a").unwrap().status, "expired");
+        assert!(queue.request(Item::new("c")).is_ok());
+
+        queue.reject_session("s1");
+        assert!(queue.list(Some("s1")).iter().all(|i| !i.is_pending()));
+        assert!(queue.list(Some("s2")).is_empty());
+    }
+
+    #[test]
+    fn test_prune_finished_requests() {
+        let queue = ApprovalQueue::new("test", "请求", 5);
+        queue.submit(Item::new("a")).unwrap();
+        queue.submit(Item::new("b")).unwrap();
+        queue.review("a", false).unwrap();
+
+        let later = Utc::now() + ChronoDuration::from_std(FINISHED_RETENTION).unwrap();
+        prune(
+            &mut queue.entries.write(),
+            later + ChronoDuration::seconds(1),
+        );
+        let ids: Vec<String> = queue.list(None).into_iter().map(|i| i.id).collect();
+        assert_eq!(ids, vec!["b"]);
+    }
+}
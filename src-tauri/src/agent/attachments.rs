@@ -0,0 +1,364 @@
+//! 文件附件处理
+//!
+//! 从 PDF、纯文本、CSV 等附件中提取文本（自动检测编码），
+//! 按 token 预算截断后拼接到用户消息前作为上下文。
+//! 支持文档块的模型（Claude、Gemini）直接接收 PDF 原文，不做本地文本提取。
+
+use crate::agent::types::{AttachmentData, AttachmentInfo, DocumentData};
+use crate::telemetry::TokenEstimator;
+use base64::Engine;
+use once_cell::sync::Lazy;
+use std::path::Path;
+
+/// 所有附件合计的 token 预算
+pub const DEFAULT_ATTACHMENT_TOKEN_BUDGET: u32 = 32_000;
+
+/// 单个附件大小上限（20MB）
+const MAX_ATTACHMENT_SIZE: usize = 20 * 1024 * 1024;
+
+static TOKEN_ESTIMATOR: Lazy<TokenEstimator> = Lazy::new(TokenEstimator::default);
+
+/// 可以原生文档块发送的 MIME 类型
+const NATIVE_DOCUMENT_MEDIA_TYPES: &[&str] = &["application/pdf"];
+
+/// 模型是否支持原生文档块
+pub fn supports_native_documents(model: &str) -> bool {
+    let model = model.to_lowercase();
+    model.contains("claude") || model.contains("gemini")
+}
+
+/// 拆分附件：PDF 在模型支持（或附件指定 native）时作为原生文档，其余附件走文本提取
+///
+/// 返回（原生文档、原生文档的附件元数据、需要提取文本的附件）
+pub fn split_native_documents(
+    attachments: &[AttachmentData],
+    model: &str,
+) -> Result<(Vec<DocumentData>, Vec<AttachmentInfo>, Vec<AttachmentData>), String> {
+    let model_supported = supports_native_documents(model);
+    let mut documents = Vec::new();
+    let mut infos = Vec::new();
+    let mut rest = Vec::new();
+
+    for attachment in attachments {
+        let media_type = media_type_of(attachment);
+        let native = NATIVE_DOCUMENT_MEDIA_TYPES.contains(&media_type.as_str())
+            && attachment.native.unwrap_or(model_supported);
+        if !native {
+            rest.push(attachment.clone());
+            continue;
+        }
+
+        let bytes = read_bytes(attachment)?;
+        infos.push(AttachmentInfo {
+            name: attachment.name.clone(),
+            media_type: media_type.clone(),
+            size: bytes.len() as u64,
+            tokens: 0,
+            truncated: false,
+            path: None,
+        });
+        documents.push(DocumentData {
+            name: attachment.name.clone(),
+            data: base64::engine::general_purpose::STANDARD.encode(&bytes),
+            media_type,
+        });
+    }
+
+    Ok((documents, infos, rest))
+}
+
+/// 附件读取结果
+struct LoadedAttachment {
+    info: AttachmentInfo,
+    text: String,
+}
+
+/// 将附件内容拼接到用户消息前，返回新的消息文本和附件元数据
+pub fn prepare_message_with_attachments(
+    message: &str,
+    attachments: &[AttachmentData],
+    model: &str,
+    token_budget: u32,
+) -> Result<(String, Vec<AttachmentInfo>), String> {
+    if attachments.is_empty() {
+        return Ok((message.to_string(), Vec::new()));
+    }
+
+    let per_attachment_budget = token_budget / attachments.len() as u32;
+    let mut context = String::new();
+    let mut infos = Vec::with_capacity(attachments.len());
+
+    for attachment in attachments {
+        let loaded = load_attachment(attachment, model, per_attachment_budget)?;
+        context.push_str(&format!(
+            "<attachment name=\"{}\" media_type=\"{}\"{}>\n{}\n</attachment>\n\n",
+            loaded.info.name,
+            loaded.info.media_type,
+            if loaded.info.truncated {
+                " truncated=\"true\""
+            } else {
+                ""
+            },
+            loaded.text
+        ));
+        infos.push(loaded.info);
+    }
+
+    context.push_str(message);
+    Ok((context, infos))
+}
+
+/// 读取附件原始内容
+fn read_bytes(attachment: &AttachmentData) -> Result<Vec<u8>, String> {
+    let bytes = match (&attachment.path, &attachment.data) {
+        (Some(path), _) => {
+            let size = std::fs::metadata(path)
+                .map_err(|e| format!("读取附件失败 {}: {}", attachment.name, e))?
+                .len() as usize;
+            check_size(&attachment.name, size)?;
+            std::fs::read(path).map_err(|e| format!("读取附件失败 {}: {}", attachment.name, e))?
+        }
+        (None, Some(data)) => base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .map_err(|e| format!("附件 {} base64 解码失败: {}", attachment.name, e))?,
+        (None, None) => return Err(format!("附件 {} 缺少 path 或 data", attachment.name)),
+    };
+    check_size(&attachment.name, bytes.len())?;
+    Ok(bytes)
+}
+
+fn media_type_of(attachment: &AttachmentData) -> String {
+    attachment
+        .media_type
+        .clone()
+        .unwrap_or_else(|| guess_media_type(&attachment.name).to_string())
+}
+
+fn load_attachment(
+    attachment: &AttachmentData,
+    model: &str,
+    token_budget: u32,
+) -> Result<LoadedAttachment, String> {
+    let bytes = read_bytes(attachment)?;
+    let media_type = media_type_of(attachment);
+    let text = extract_text(&attachment.name, &media_type, &bytes)?;
+
+    let (text, tokens, truncated) = truncate_to_budget(text.trim(), model, token_budget);
+
+    Ok(LoadedAttachment {
+        info: AttachmentInfo {
+            name: attachment.name.clone(),
+            media_type,
+            size: bytes.len() as u64,
+            tokens,
+            truncated,
+            path: None,
+        },
+        text,
+    })
+}
+
+/// 提取附件文本（PDF 解析，其余按文本解码）
+fn extract_text(name: &str, media_type: &str, bytes: &[u8]) -> Result<String, String> {
+    if media_type == "application/pdf" {
+        pdf_extract::extract_text_from_mem(bytes)
+            .map_err(|e| format!("解析 PDF 附件 {} 失败: {}", name, e))
+    } else if media_type.starts_with("image/") {
+        Err(format!("图片附件 {} 请通过 images 字段发送", name))
+    } else if bytes.contains(&0) {
+        Err(format!("不支持的二进制附件: {} ({})", name, media_type))
+    } else {
+        Ok(decode_text(bytes))
+    }
+}
+
+/// 将 base64 文档转换为文本上下文，供不支持文档块的上游（如 Kiro）使用
+///
+/// 提取失败时返回说明文字，不中断请求
+pub fn document_as_text(name: Option<&str>, media_type: &str, data: &str) -> String {
+    let name = name.unwrap_or("document");
+    let text = base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .map_err(|e| format!("附件 {} base64 解码失败: {}", name, e))
+        .and_then(|bytes| {
+            check_size(name, bytes.len())?;
+            extract_text(name, media_type, &bytes)
+        });
+    match text {
+        Ok(text) => format!(
+            "<attachment name=\"{}\" media_type=\"{}\">\n{}\n</attachment>\n\n",
+            name,
+            media_type,
+            text.trim()
+        ),
+        Err(e) => {
+            tracing::warn!("[Attachments] 文档文本提取失败: {}", e);
+            format!("[文档附件 {} 无法提取文本]\n\n", name)
+        }
+    }
+}
+
+fn check_size(name: &str, size: usize) -> Result<(), String> {
+    if size > MAX_ATTACHMENT_SIZE {
+        return Err(format!("附件过大: {} ({} MB)", name, size / 1024 / 1024));
+    }
+    Ok(())
+}
+
+/// 根据扩展名推断 MIME 类型
+pub(crate) fn guess_media_type(name: &str) -> &'static str {
+    let ext = Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "pdf" => "application/pdf",
+        "csv" => "text/csv",
+        "md" | "markdown" => "text/markdown",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "html" | "htm" => "text/html",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        _ => "text/plain",
+    }
+}
+
+/// 解码文本，优先 UTF-8（去除 BOM），否则自动检测编码（如 GBK、Shift_JIS）
+fn decode_text(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return text.to_string();
+    }
+
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, true);
+    let (text, _, _) = encoding.decode(bytes);
+    text.into_owned()
+}
+
+/// 按 token 预算截断文本，返回（文本、token 数、是否截断）
+fn truncate_to_budget(text: &str, model: &str, budget: u32) -> (String, u32, bool) {
+    let tokens = TOKEN_ESTIMATOR.estimate(text, Some(model));
+    if tokens <= budget {
+        return (text.to_string(), tokens, false);
+    }
+
+    // 按比例估算可保留的字符数，再逐步收缩直到满足预算
+    let chars: Vec<char> = text.chars().collect();
+    let mut keep = (chars.len() as u64 * budget as u64 / tokens as u64) as usize;
+    loop {
+        let candidate: String = chars[..keep].iter().collect();
+        let candidate_tokens = TOKEN_ESTIMATOR.estimate(&candidate, Some(model));
+        if candidate_tokens <= budget || keep == 0 {
+            return (candidate, candidate_tokens, true);
+        }
+        keep = keep * 9 / 10;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inline(name: &str, content: &[u8]) -> AttachmentData {
+        AttachmentData {
+            name: name.to_string(),
+            path: None,
+            data: Some(base64::engine::general_purpose::STANDARD.encode(content)),
+            media_type: None,
+            native: None,
+        }
+    }
+
+    #[test]
+    fn test_decode_text_detects_gbk() {
+        let (gbk, _, _) = encoding_rs::GBK.encode("你好，世界");
+        assert_eq!(decode_text(&gbk), "你好，世界");
+        assert_eq!(decode_text("\u{feff}abc".as_bytes()), "abc");
+    }
+
+    #[test]
+    fn test_prepare_message_with_csv_attachment() {
+        let (message, infos) = prepare_message_with_attachments(
+            "总结这个表格",
+            &[inline("data.csv", b"name,age\nalice,30\n")],
+            "gpt-4",
+            DEFAULT_ATTACHMENT_TOKEN_BUDGET,
+        )
+        .unwrap();
+
+        assert!(message.starts_with("<attachment name=\"data.csv\" media_type=\"text/csv\">"));
+        assert!(message.contains("alice,30"));
+        assert!(message.ends_with("总结这个表格"));
+        assert_eq!(infos[0].media_type, "text/csv");
+        assert!(!infos[0].truncated);
+    }
+
+    #[test]
+    fn test_attachment_truncated_to_budget() {
+        let content = "lorem ipsum dolor sit amet ".repeat(500);
+        let (_, infos) = prepare_message_with_attachments(
+            "",
+            &[inline("notes.txt", content.as_bytes())],
+            "gpt-4",
+            100,
+        )
+        .unwrap();
+
+        assert!(infos[0].truncated);
+        assert!(infos[0].tokens <= 100);
+    }
+
+    #[test]
+    fn test_split_native_documents_by_model() {
+        let pdf = inline("paper.pdf", b"%PDF-1.7 ...");
+        let csv = inline("data.csv", b"a,b\n");
+
+        let (documents, infos, rest) =
+            split_native_documents(&[pdf.clone(), csv.clone()], "claude-sonnet-4-5").unwrap();
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].media_type, "application/pdf");
+        assert_eq!(documents[0].data, pdf.data.clone().unwrap());
+        assert_eq!(infos[0].name, "paper.pdf");
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].name, "data.csv");
+
+        let (documents, _, rest) = split_native_documents(&[pdf.clone()], "gpt-4").unwrap();
+        assert!(documents.is_empty());
+        assert_eq!(rest.len(), 1);
+
+        let forced = AttachmentData {
+            native: Some(false),
+            ..pdf
+        };
+        let (documents, _, _) = split_native_documents(&[forced], "claude-sonnet-4-5").unwrap();
+        assert!(documents.is_empty());
+    }
+
+    #[test]
+    fn test_document_as_text_falls_back_to_placeholder() {
+        let data = base64::engine::general_purpose::STANDARD.encode("第一章\n内容");
+        let text = document_as_text(Some("notes.txt"), "text/plain", &data);
+        assert!(text.starts_with("<attachment name=\"notes.txt\" media_type=\"text/plain\">"));
+        assert!(text.contains("第一章\n内容"));
+
+        let broken = document_as_text(Some("paper.pdf"), "application/pdf", "bm90IGEgcGRm");
+        assert_eq!(broken, "[文档附件 paper.pdf 无法提取文本]\n\n");
+    }
+
+    #[test]
+    fn test_rejects_binary_and_images() {
+        assert!(
+            prepare_message_with_attachments("", &[inline("a.bin", &[0, 1, 2])], "gpt-4", 100)
+                .is_err()
+        );
+        assert!(
+            prepare_message_with_attachments("", &[inline("a.png", b"x")], "gpt-4", 100).is_err()
+        );
+    }
+}
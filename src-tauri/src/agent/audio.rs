@@ -0,0 +1,145 @@
+//! 音频输入预处理
+//!
+//! 前端可以只传本地音频路径，由后端读取文件、校验格式和大小并编码为 base64，
+//! 作为 `input_audio` 内容部分发送给支持音频输入的模型（如 gpt-4o-audio-preview）。
+
+use crate::agent::types::AudioData;
+use base64::Engine;
+
+/// 音频文件大小上限（20MB）
+const MAX_AUDIO_FILE_SIZE: u64 = 20 * 1024 * 1024;
+
+/// 支持的音频格式（OpenAI `input_audio` 接受 wav 和 mp3）
+pub const SUPPORTED_AUDIO_FORMATS: &[&str] = &["wav", "mp3"];
+
+/// 解析音频列表：带 path 的音频读取文件，所有音频校验格式后编码为 base64
+pub fn resolve_audio(audio: Option<&[AudioData]>) -> Result<Option<Vec<AudioData>>, String> {
+    match audio {
+        Some(list) if !list.is_empty() => list
+            .iter()
+            .map(resolve_clip)
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// 解析单段音频
+fn resolve_clip(clip: &AudioData) -> Result<AudioData, String> {
+    let (bytes, name) = match clip.path.as_deref() {
+        Some(path) => {
+            let size = std::fs::metadata(path)
+                .map_err(|e| format!("读取音频失败 {}: {}", path, e))?
+                .len();
+            if size > MAX_AUDIO_FILE_SIZE {
+                return Err(format!("音频过大: {} ({} MB)", path, size / 1024 / 1024));
+            }
+            let bytes = std::fs::read(path).map_err(|e| format!("读取音频失败 {}: {}", path, e))?;
+            (bytes, path)
+        }
+        None if clip.data.is_empty() => return Err("音频缺少 path 或 data".to_string()),
+        None => {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(&clip.data)
+                .map_err(|e| format!("音频 base64 解码失败: {}", e))?;
+            if bytes.len() as u64 > MAX_AUDIO_FILE_SIZE {
+                return Err(format!("音频过大 ({} MB)", bytes.len() / 1024 / 1024));
+            }
+            (bytes, "inline")
+        }
+    };
+
+    let format = sniff_format(&bytes).ok_or_else(|| {
+        format!(
+            "不支持的音频格式: {}（支持 {}）",
+            name,
+            SUPPORTED_AUDIO_FORMATS.join("、")
+        )
+    })?;
+    let data = match clip.path {
+        Some(_) => base64::engine::general_purpose::STANDARD.encode(&bytes),
+        None => clip.data.clone(),
+    };
+    Ok(AudioData {
+        data,
+        format: format.to_string(),
+        path: None,
+    })
+}
+
+/// 按文件头识别音频格式（不信任扩展名和前端传入的格式）
+fn sniff_format(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WAVE" {
+        return Some("wav");
+    }
+    // ID3 标签，或 MPEG 帧同步字加 Layer III 标记
+    // （AAC ADTS 同样以 0xFFF 开头，但 layer 位为 00）
+    if bytes.starts_with(b"ID3")
+        || (bytes.len() >= 2 && bytes[0] == 0xFF && bytes[1] & 0xE6 == 0xE2)
+    {
+        return Some("mp3");
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wav_bytes() -> Vec<u8> {
+        let mut bytes = b"RIFF\x24\x00\x00\x00WAVEfmt ".to_vec();
+        bytes.extend_from_slice(&[0u8; 32]);
+        bytes
+    }
+
+    #[test]
+    fn test_resolve_path_and_inline_audio() {
+        let file = tempfile::Builder::new().suffix(".wav").tempfile().unwrap();
+        std::fs::write(file.path(), wav_bytes()).unwrap();
+        let inline = base64::engine::general_purpose::STANDARD.encode(b"ID3\x04\x00rest");
+
+        let resolved = resolve_audio(Some(&[
+            AudioData {
+                data: String::new(),
+                format: String::new(),
+                path: Some(file.path().to_string_lossy().to_string()),
+            },
+            AudioData {
+                data: inline.clone(),
+                // 以文件头为准
+                format: "wav".to_string(),
+                path: None,
+            },
+        ]))
+        .unwrap()
+        .unwrap();
+        assert_eq!(resolved[0].format, "wav");
+        assert!(resolved[0].path.is_none());
+        assert!(!resolved[0].data.is_empty());
+        assert_eq!(resolved[1].format, "mp3");
+        assert_eq!(resolved[1].data, inline);
+    }
+
+    #[test]
+    fn test_rejects_unknown_audio() {
+        let data = base64::engine::general_purpose::STANDARD.encode(b"OggS not supported");
+        let clip = AudioData {
+            data,
+            format: "ogg".to_string(),
+            path: None,
+        };
+        assert!(resolve_audio(Some(&[clip])).is_err());
+        assert!(resolve_audio(Some(&[])).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_sniff_mpeg_frames() {
+        // MPEG-1 Layer III 帧头
+        assert_eq!(sniff_format(&[0xFF, 0xFB, 0x90, 0x00]), Some("mp3"));
+        // MPEG-2 Layer III 帧头
+        assert_eq!(sniff_format(&[0xFF, 0xF3, 0x48, 0xC4]), Some("mp3"));
+        // AAC ADTS 帧头
+        assert_eq!(sniff_format(&[0xFF, 0xF1, 0x50, 0x80]), None);
+        assert_eq!(sniff_format(&[0xFF, 0xF9, 0x50, 0x80]), None);
+    }
+}
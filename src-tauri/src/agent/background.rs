@@ -0,0 +1,192 @@
+//! 后台任务
+//!
+//! 摘要、标题生成、后续问题建议、记忆提取、Skill 生成等后台操作统一通过
+//! [`BackgroundModelConfig`] 指定的模型执行，与对话模型分离以控制成本。
+
+use crate::agent::types::AgentMessage;
+use crate::agent::NativeAgentState;
+use crate::config::BackgroundModelConfig;
+
+/// 对话记录最大字符数（超出时保留最近的内容）
+const MAX_TRANSCRIPT_CHARS: usize = 24_000;
+
+/// 后台任务类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackgroundTask {
+    /// 会话摘要
+    Summarize,
+    /// 会话标题
+    Title,
+    /// 后续问题建议
+    FollowUps,
+    /// 记忆提取
+    ExtractMemories,
+    /// 从会话生成 SKILL.md
+    CreateSkill,
+}
+
+impl BackgroundTask {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Summarize => "summarize",
+            Self::Title => "title",
+            Self::FollowUps => "follow_ups",
+            Self::ExtractMemories => "extract_memories",
+            Self::CreateSkill => "create_skill",
+        }
+    }
+
+    fn system_prompt(&self) -> &'static str {
+        match self {
+            Self::Summarize => {
+                "You summarize conversations. Write a concise summary of the conversation below, \
+                 keeping key facts, decisions, code identifiers and open questions. \
+                 Reply in the same language as the conversation."
+            }
+            Self::Title => {
+                "Generate a short title (at most 8 words) for the conversation below. \
+                 Reply with the title only, without quotes or punctuation at the end, \
+                 in the same language as the conversation."
+            }
+            Self::FollowUps => {
+                "Suggest 3 short follow-up questions the user might ask next, based on the \
+                 conversation below. Reply with one question per line, without numbering, \
+                 in the same language as the conversation."
+            }
+            Self::ExtractMemories => {
+                "Extract durable facts about the user worth remembering across sessions \
+                 (preferences, environment, project conventions) from the conversation below. \
+                 Reply with one short standalone sentence per line. Skip secrets and transient \
+                 details. Reply with NONE if there is nothing worth remembering."
+            }
+            Self::CreateSkill => {
+                "Distill the conversation below into a reusable skill for an AI agent, written as \
+                 a SKILL.md file. Start with YAML frontmatter between --- lines containing `name` \
+                 and `description` (one sentence saying what the skill does and when to use it). \
+                 Then write step-by-step instructions generalized from what worked in the \
+                 conversation, followed by an `## Example` section with a short example request \
+                 and the expected approach. Leave out details specific to this one conversation \
+                 and any secrets. Reply with the file content only, in the same language as the \
+                 conversation."
+            }
+        }
+    }
+}
+
+/// 将会话消息渲染为纯文本对话记录（跳过工具消息）
+pub fn render_transcript(messages: &[AgentMessage]) -> String {
+    let mut transcript = String::new();
+    for msg in messages {
+        let role = match msg.role.as_str() {
+            "user" => "User",
+            "assistant" => "Assistant",
+            _ => continue,
+        };
+        let text = msg.content.as_text();
+        if text.trim().is_empty() {
+            continue;
+        }
+        transcript.push_str(&format!("{}: {}\n\n", role, text.trim()));
+    }
+
+    let chars: Vec<char> = transcript.chars().collect();
+    if chars.len() > MAX_TRANSCRIPT_CHARS {
+        chars[chars.len() - MAX_TRANSCRIPT_CHARS..].iter().collect()
+    } else {
+        transcript
+    }
+}
+
+/// 将多行输出解析为列表（去除编号、项目符号和空行）
+pub fn parse_lines(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(|line| strip_list_marker(line.trim()).trim().to_string())
+        .filter(|line| !line.is_empty() && !line.eq_ignore_ascii_case("none"))
+        .collect()
+}
+
+/// 去掉行首的列表标记：`1.` / `2)` / `3、` 形式的编号（后面不是数字），或后跟空白的 `-` / `*` / `•`
+///
+/// 只去掉明确的标记，`2024 年计划`、`3.5 版本` 这类以数字开头的内容保持不变。
+fn strip_list_marker(line: &str) -> &str {
+    if let Some(rest) = line.strip_prefix(['-', '*', '•']) {
+        if rest.is_empty() || rest.starts_with(char::is_whitespace) {
+            return rest;
+        }
+        return line;
+    }
+
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 || digits > 3 {
+        return line;
+    }
+    match line[digits..].strip_prefix(['.', ')', '、']) {
+        Some(rest) if !rest.starts_with(|c: char| c.is_ascii_digit()) => rest,
+        _ => line,
+    }
+}
+
+/// 使用后台模型执行任务
+pub async fn run_background_task(
+    agent_state: &NativeAgentState,
+    config: &BackgroundModelConfig,
+    task: BackgroundTask,
+    messages: &[AgentMessage],
+) -> Result<String, String> {
+    let transcript = render_transcript(messages);
+    if transcript.is_empty() {
+        return Err("会话中没有可处理的内容".to_string());
+    }
+
+    tracing::info!(
+        "[Background] 执行后台任务: task={}, model={:?}",
+        task.name(),
+        config.model
+    );
+
+    let output = agent_state
+        .complete(
+            task.system_prompt(),
+            &transcript,
+            config.model.clone(),
+            Some(config.max_tokens),
+            config.temperature,
+        )
+        .await?;
+    Ok(output.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::test_support::text_message as msg;
+
+    #[test]
+    fn test_render_transcript_skips_tool_messages() {
+        let transcript = render_transcript(&[
+            msg("user", "列出文件"),
+            msg("tool", "a.txt\nb.txt"),
+            msg("assistant", "有两个文件"),
+        ]);
+        assert_eq!(transcript, "User: 列出文件\n\nAssistant: 有两个文件\n\n");
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        let lines = parse_lines("1. 如何部署？\n- 如何测试？\n\n• 如何回滚？\nNONE");
+        assert_eq!(lines, vec!["如何部署？", "如何测试？", "如何回滚？"]);
+
+        let lines = parse_lines("2024 年的计划？\n3.5 版本有什么变化？\n12) -1 表示什么？\n2、如何升级？\n*重点*是什么？");
+        assert_eq!(
+            lines,
+            vec![
+                "2024 年的计划？",
+                "3.5 版本有什么变化？",
+                "-1 表示什么？",
+                "如何升级？",
+                "*重点*是什么？"
+            ]
+        );
+    }
+}
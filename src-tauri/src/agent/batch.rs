@@ -0,0 +1,429 @@
+//! 批量提示词
+//!
+//! 对数据集的每一行渲染提示词模板（`{{列名}}` 占位符，纯文本行使用 `{{input}}`），
+//! 以有限并发通过原生 Agent 执行（不创建会话），逐项推送 [`BATCH_PROGRESS_EVENT`] 进度，
+//! 结果连同原始列写入 CSV 或 JSONL 文件。
+//!
+//! 输入可以直接传入（对象或字符串数组），也可以从 CSV（首行为表头）、JSON 数组或 JSONL 文件读取。
+
+use crate::agent::native_agent::NativeAgentState;
+use crate::agent::prompt_vars::expand_with;
+use crate::agent::types::{NativeChatRequest, TokenUsage};
+use futures::StreamExt;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// 推送到前端的进度事件名
+pub const BATCH_PROGRESS_EVENT: &str = "agent-batch-progress";
+
+/// 单批最多的行数
+pub const MAX_BATCH_ITEMS: usize = 1000;
+
+/// 最大并发数
+pub const MAX_BATCH_CONCURRENCY: usize = 8;
+
+/// 一行输入（列名 -> 值，保持列顺序）
+pub type BatchRow = IndexMap<String, String>;
+
+/// 结果文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchOutputFormat {
+    Csv,
+    Jsonl,
+}
+
+impl BatchOutputFormat {
+    /// 按扩展名判断格式（`.csv` 为 CSV，其他为 JSONL）
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => Self::Csv,
+            _ => Self::Jsonl,
+        }
+    }
+}
+
+/// 单项结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchItemResult {
+    /// 行号（从 0 开始）
+    pub index: usize,
+    pub input: BatchRow,
+    pub prompt: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
+    pub duration_ms: u64,
+}
+
+/// 批量执行进度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchProgress {
+    pub batch_id: String,
+    /// 刚完成的行号
+    pub index: usize,
+    /// 已完成数量
+    pub completed: usize,
+    pub total: usize,
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 批量执行结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchRunResult {
+    pub batch_id: String,
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// 结果文件路径
+    pub output_path: String,
+}
+
+fn value_to_string(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// 将 JSON 值转换为输入行（对象按字段展开，其他值作为 `input` 列）
+pub fn row_from_value(value: &serde_json::Value) -> BatchRow {
+    match value {
+        serde_json::Value::Object(map) => map
+            .iter()
+            .map(|(k, v)| (k.clone(), value_to_string(v)))
+            .collect(),
+        other => BatchRow::from([("input".to_string(), value_to_string(other))]),
+    }
+}
+
+/// 解析 CSV（首行为表头，支持双引号转义和字段内换行）
+pub fn parse_csv(text: &str) -> Result<Vec<BatchRow>, String> {
+    let mut records: Vec<Vec<String>> = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => record.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("CSV 格式错误: 引号未闭合".to_string());
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records.retain(|r| !(r.len() == 1 && r[0].is_empty()));
+
+    let mut records = records.into_iter();
+    let header: Vec<String> = records
+        .next()
+        .ok_or_else(|| "CSV 文件为空".to_string())?
+        .into_iter()
+        .map(|h| h.trim().to_string())
+        .collect();
+    records
+        .enumerate()
+        .map(|(i, values)| {
+            if values.len() != header.len() {
+                return Err(format!(
+                    "CSV 第 {} 行有 {} 列，表头有 {} 列",
+                    i + 2,
+                    values.len(),
+                    header.len()
+                ));
+            }
+            Ok(header.iter().cloned().zip(values).collect())
+        })
+        .collect()
+}
+
+/// 从文件读取输入（按扩展名识别 CSV、JSONL，其他按 JSON 数组解析）
+pub fn load_input_file(path: &Path) -> Result<Vec<BatchRow>, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("读取输入文件失败 {}: {}", path.display(), e))?;
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match ext.as_str() {
+        "csv" => parse_csv(&text),
+        "jsonl" | "ndjson" => text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(i, line)| {
+                serde_json::from_str(line)
+                    .map(|v| row_from_value(&v))
+                    .map_err(|e| format!("JSONL 第 {} 行解析失败: {}", i + 1, e))
+            })
+            .collect(),
+        _ => {
+            let values: Vec<serde_json::Value> = serde_json::from_str(&text)
+                .map_err(|e| format!("JSON 解析失败（需要数组）: {}", e))?;
+            Ok(values.iter().map(row_from_value).collect())
+        }
+    }
+}
+
+/// 用行数据渲染提示词模板（未知占位符原样保留）
+pub fn render_prompt(template: &str, row: &BatchRow) -> String {
+    let variables: HashMap<&str, String> =
+        row.iter().map(|(k, v)| (k.as_str(), v.clone())).collect();
+    expand_with(template, &variables)
+}
+
+/// 校验输入规模
+pub fn validate_batch(rows: &[BatchRow], template: &str) -> Result<(), String> {
+    if template.trim().is_empty() {
+        return Err("提示词模板不能为空".to_string());
+    }
+    if rows.is_empty() {
+        return Err("没有输入数据".to_string());
+    }
+    if rows.len() > MAX_BATCH_ITEMS {
+        return Err(format!(
+            "输入 {} 行，超过单批上限 {}",
+            rows.len(),
+            MAX_BATCH_ITEMS
+        ));
+    }
+    Ok(())
+}
+
+/// 以有限并发执行，每完成一项回调一次，返回按行号排序的结果
+pub async fn run_batch(
+    agent_state: &NativeAgentState,
+    template: &str,
+    rows: Vec<BatchRow>,
+    model: Option<String>,
+    concurrency: usize,
+    mut on_item: impl FnMut(&BatchItemResult, usize),
+) -> Vec<BatchItemResult> {
+    let concurrency = concurrency.clamp(1, MAX_BATCH_CONCURRENCY);
+    let tasks = rows.into_iter().enumerate().map(|(index, input)| {
+        let prompt = render_prompt(template, &input);
+        let model = model.clone();
+        async move {
+            let started = Instant::now();
+            let request = NativeChatRequest {
+                session_id: None,
+                message: prompt.clone(),
+                model,
+                images: None,
+                audio: None,
+                attachments: None,
+                context: None,
+                stream: false,
+            };
+            let (output, error, usage) = match agent_state.chat(request).await {
+                Ok(response) if response.success => (Some(response.content), None, response.usage),
+                Ok(response) => (None, response.error, None),
+                Err(e) => (None, Some(e.to_string()), None),
+            };
+            BatchItemResult {
+                index,
+                input,
+                prompt,
+                output,
+                error,
+                usage,
+                duration_ms: started.elapsed().as_millis() as u64,
+            }
+        }
+    });
+
+    let mut stream = futures::stream::iter(tasks).buffer_unordered(concurrency);
+    let mut results = Vec::new();
+    while let Some(result) = stream.next().await {
+        results.push(result);
+        on_item(results.last().unwrap(), results.len());
+    }
+    results.sort_by_key(|r| r.index);
+    results
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// 渲染结果文件内容
+pub fn format_results(results: &[BatchItemResult], format: BatchOutputFormat) -> String {
+    match format {
+        BatchOutputFormat::Jsonl => results
+            .iter()
+            .filter_map(|r| serde_json::to_string(r).ok())
+            .map(|line| line + "\n")
+            .collect(),
+        BatchOutputFormat::Csv => {
+            // 输入列取所有行的并集（按首次出现顺序）
+            let mut columns: Vec<&str> = Vec::new();
+            for result in results {
+                for key in result.input.keys() {
+                    if !columns.contains(&key.as_str()) {
+                        columns.push(key);
+                    }
+                }
+            }
+            let mut header = vec!["index"];
+            header.extend(&columns);
+            header.extend([
+                "output",
+                "error",
+                "input_tokens",
+                "output_tokens",
+                "duration_ms",
+            ]);
+            let mut out = header
+                .iter()
+                .map(|h| csv_field(h))
+                .collect::<Vec<_>>()
+                .join(",");
+            out.push('\n');
+            for r in results {
+                let mut fields = vec![r.index.to_string()];
+                fields.extend(
+                    columns
+                        .iter()
+                        .map(|c| r.input.get(*c).cloned().unwrap_or_default()),
+                );
+                fields.push(r.output.clone().unwrap_or_default());
+                fields.push(r.error.clone().unwrap_or_default());
+                fields.push(
+                    r.usage
+                        .as_ref()
+                        .map(|u| u.input_tokens.to_string())
+                        .unwrap_or_default(),
+                );
+                fields.push(
+                    r.usage
+                        .as_ref()
+                        .map(|u| u.output_tokens.to_string())
+                        .unwrap_or_default(),
+                );
+                fields.push(r.duration_ms.to_string());
+                out.push_str(
+                    &fields
+                        .iter()
+                        .map(|f| csv_field(f))
+                        .collect::<Vec<_>>()
+                        .join(","),
+                );
+                out.push('\n');
+            }
+            out
+        }
+    }
+}
+
+/// 默认结果路径（~/.proxycast/batch/<批次 ID>.jsonl）
+pub fn default_output_path(batch_id: &str) -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or_else(|| "无法获取用户 home 目录".to_string())?;
+    Ok(home
+        .join(".proxycast")
+        .join("batch")
+        .join(format!("{}.jsonl", batch_id)))
+}
+
+/// 写入结果文件
+pub fn write_results(
+    path: &Path,
+    results: &[BatchItemResult],
+    format: BatchOutputFormat,
+) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("创建输出目录失败: {}", e))?;
+    }
+    std::fs::write(path, format_results(results, format))
+        .map_err(|e| format!("写入结果文件失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv_and_render() {
+        let rows = parse_csv("name,text\r\nalice,\"hello, \"\"world\"\"\"\nbob,\"line1\nline2\"\n")
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["text"], "hello, \"world\"");
+        assert_eq!(rows[1]["text"], "line1\nline2");
+        assert_eq!(
+            render_prompt("Translate for {{ name }}: {{text}} {{missing}}", &rows[0]),
+            "Translate for alice: hello, \"world\" {{missing}}"
+        );
+
+        assert!(parse_csv("a,b\n1\n").unwrap_err().contains("第 2 行"));
+        assert!(parse_csv("a\n\"open\n").is_err());
+
+        let row = row_from_value(&serde_json::json!("plain text"));
+        assert_eq!(render_prompt("Q: {{input}}", &row), "Q: plain text");
+    }
+
+    #[test]
+    fn test_format_results_csv() {
+        let results = vec![
+            BatchItemResult {
+                index: 0,
+                input: BatchRow::from([("q".to_string(), "a,b".to_string())]),
+                prompt: "a,b".to_string(),
+                output: Some("ok".to_string()),
+                error: None,
+                usage: Some(TokenUsage {
+                    input_tokens: 3,
+                    output_tokens: 1,
+                }),
+                duration_ms: 12,
+            },
+            BatchItemResult {
+                index: 1,
+                input: BatchRow::from([("q".to_string(), "c".to_string())]),
+                prompt: "c".to_string(),
+                output: None,
+                error: Some("API 错误 (429)".to_string()),
+                usage: None,
+                duration_ms: 5,
+            },
+        ];
+        let csv = format_results(&results, BatchOutputFormat::Csv);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "index,q,output,error,input_tokens,output_tokens,duration_ms"
+        );
+        assert_eq!(lines[1], "0,\"a,b\",ok,,3,1,12");
+        assert_eq!(lines[2], "1,c,,API 错误 (429),,,5");
+
+        let jsonl = format_results(&results, BatchOutputFormat::Jsonl);
+        assert_eq!(jsonl.lines().count(), 2);
+    }
+}
@@ -0,0 +1,271 @@
+//! 上下文超限时的模型回退
+//!
+//! 发送请求前按约 4 字符 ≈ 1 token 估算提示词大小，加上为输出预留的 token 后超过会话模型的
+//! 上下文窗口时，按配置顺序换用第一个能容纳的更大上下文模型。只影响本次请求，会话模型不变；
+//! 换用结果通过 `context_fallback` 流式事件和响应字段告知前端。
+//!
+//! 流式对话每次请求前还会推送 `context_usage` 事件（估算的提示词 token 数与模型上下文窗口），
+//! 用于前端显示上下文占用并在接近上限时提醒。
+//!
+//! 估算不准、上游仍返回上下文超长错误时，按 [`compact_history`] 丢弃最早的对话轮次后重试一次。
+//! 只压缩本次发送的历史，会话中保存的历史保持不变；流式对话通过 `context_compacted` 事件告知前端。
+
+use crate::agent::session_lint::{estimate_tokens, message_tokens};
+use crate::agent::types::{AgentMessage, StreamEvent};
+use crate::config::ContextFallbackConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 常见模型的上下文窗口（按前缀匹配，取最长的匹配前缀）
+const KNOWN_CONTEXT_WINDOWS: &[(&str, u32)] = &[
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4.5", 128_000),
+    ("gpt-4o", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4-1106", 128_000),
+    ("gpt-4-0125", 128_000),
+    ("gpt-4-vision", 128_000),
+    ("gpt-4-32k", 32_768),
+    ("gpt-4", 8_192),
+    ("gpt-3.5-turbo", 16_385),
+    ("gpt-5", 400_000),
+    ("o1", 200_000),
+    ("o3", 200_000),
+    ("o4-mini", 200_000),
+    ("claude", 200_000),
+    ("gemini-1.5-pro", 2_097_152),
+    ("gemini", 1_048_576),
+    ("deepseek", 65_536),
+    ("qwen", 131_072),
+];
+
+/// 一次上下文回退
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContextFallback {
+    /// 会话模型
+    pub from: String,
+    /// 本次请求实际使用的模型
+    pub to: String,
+    /// 估算的提示词 token 数
+    pub estimated_tokens: u32,
+    /// 会话模型的上下文窗口
+    pub context_window: u32,
+}
+
+impl ContextFallback {
+    pub fn into_event(self) -> StreamEvent {
+        StreamEvent::ContextFallback {
+            from: self.from,
+            to: self.to,
+            estimated_tokens: self.estimated_tokens,
+            context_window: self.context_window,
+        }
+    }
+}
+
+/// 模型的上下文窗口（配置覆盖优先；未知模型返回 None）
+pub fn context_window(model: &str, overrides: &HashMap<String, u32>) -> Option<u32> {
+    if let Some(window) = overrides.get(model) {
+        return Some(*window);
+    }
+    // 去掉 `openai/` 之类的前缀
+    let name = model.rsplit('/').next().unwrap_or(model).to_lowercase();
+    KNOWN_CONTEXT_WINDOWS
+        .iter()
+        .filter(|(prefix, _)| name.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, window)| *window)
+}
+
+/// 估算请求的提示词 token 数（系统提示词、历史消息和本次输入）
+pub fn estimate_prompt_tokens(
+    system_prompt: Option<&str>,
+    messages: &[AgentMessage],
+    pending: &str,
+) -> u32 {
+    system_prompt.map(estimate_tokens).unwrap_or(0)
+        + messages.iter().map(message_tokens).sum::<u32>()
+        + estimate_tokens(pending)
+}
+
+/// 上下文用量事件（模型的上下文窗口未知时返回 None）
+pub fn usage_event(
+    model: &str,
+    used_tokens: u32,
+    overrides: &HashMap<String, u32>,
+) -> Option<StreamEvent> {
+    let max_tokens = context_window(model, overrides)?;
+    Some(StreamEvent::ContextUsage {
+        used_tokens,
+        max_tokens,
+    })
+}
+
+/// 判断是否需要回退，返回回退结果（未启用、模型窗口未知、放得下或没有合适的模型时返回 None）
+pub fn plan_fallback(
+    model: &str,
+    estimated_tokens: u32,
+    config: &ContextFallbackConfig,
+) -> Option<ContextFallback> {
+    if !config.enabled {
+        return None;
+    }
+    let window = context_window(model, &config.context_windows)?;
+    let needed = estimated_tokens.saturating_add(config.reserve_output_tokens);
+    if needed <= window {
+        return None;
+    }
+    let target = config.models.iter().find(|candidate| {
+        candidate.as_str() != model
+            && context_window(candidate, &config.context_windows).is_some_and(|w| w >= needed)
+    })?;
+    Some(ContextFallback {
+        from: model.to_string(),
+        to: target.clone(),
+        estimated_tokens,
+        context_window: window,
+    })
+}
+
+/// 上下文超长时压缩历史：丢弃最早的对话轮次，使剩余历史不超过原估算 token 数的一半
+///
+/// 只在用户消息处截断，不会拆开工具调用和工具结果。`pending_turn` 表示本轮用户消息尚未写入历史，
+/// 此时可以丢弃全部历史；否则至少保留最后一轮。返回压缩后的历史和丢弃的消息数，无法压缩时返回 None
+pub fn compact_history(
+    history: &[AgentMessage],
+    pending_turn: bool,
+) -> Option<(Vec<AgentMessage>, usize)> {
+    let tokens: Vec<u32> = history.iter().map(message_tokens).collect();
+    let target = tokens.iter().sum::<u32>() / 2;
+
+    let mut cuts: Vec<usize> = history
+        .iter()
+        .enumerate()
+        .filter(|(i, msg)| *i > 0 && msg.role == "user")
+        .map(|(i, _)| i)
+        .collect();
+    if pending_turn && !history.is_empty() {
+        cuts.push(history.len());
+    }
+
+    let cut = cuts
+        .iter()
+        .copied()
+        .find(|&i| tokens[i..].iter().sum::<u32>() <= target)
+        .or_else(|| cuts.last().copied())?;
+    Some((history[cut..].to_vec(), cut))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::test_support::text_message as msg;
+
+    fn config() -> ContextFallbackConfig {
+        ContextFallbackConfig {
+            enabled: true,
+            models: vec!["gpt-4o".to_string(), "gemini-2.5-pro".to_string()],
+            reserve_output_tokens: 4096,
+            context_windows: HashMap::from([("my-local".to_string(), 4096)]),
+            compact_on_overflow: true,
+        }
+    }
+
+    #[test]
+    fn test_compact_history() {
+        let long = "x".repeat(400);
+        let history = vec![
+            msg("user", &long),
+            msg("assistant", &long),
+            msg("user", &long),
+            msg("tool", &long),
+            msg("assistant", "ok"),
+            msg("user", "latest"),
+            msg("assistant", "done"),
+        ];
+        // 丢弃前两轮后剩余历史不超过一半
+        let (compacted, removed) = compact_history(&history, false).unwrap();
+        assert_eq!(removed, 5);
+        assert_eq!(compacted[0].role, "user");
+        assert_eq!(compacted.len(), 2);
+
+        // 最后一轮仍然过大时，只保留最后一轮
+        let history = vec![msg("user", "a"), msg("user", &long), msg("tool", &long)];
+        let (compacted, removed) = compact_history(&history, false).unwrap();
+        assert_eq!((compacted.len(), removed), (2, 1));
+
+        // 本轮消息尚未写入历史时可以丢弃全部历史
+        let (compacted, removed) = compact_history(&[msg("user", &long)], true).unwrap();
+        assert!(compacted.is_empty());
+        assert_eq!(removed, 1);
+
+        // 只有一轮且本轮已在历史中时无法压缩
+        assert!(compact_history(&[msg("user", &long)], false).is_none());
+        assert!(compact_history(&[], true).is_none());
+    }
+
+    #[test]
+    fn test_context_window_lookup() {
+        let overrides = config().context_windows;
+        assert_eq!(context_window("gpt-4o-mini", &overrides), Some(128_000));
+        assert_eq!(context_window("gpt-4-0613", &overrides), Some(8_192));
+        assert_eq!(context_window("gpt-4", &overrides), Some(8_192));
+        // 更长的前缀优先于通用的 gpt-4
+        assert_eq!(context_window("gpt-4.5-preview", &overrides), Some(128_000));
+        assert_eq!(context_window("gpt-4.1-mini", &overrides), Some(1_047_576));
+        assert_eq!(
+            context_window("gpt-4-turbo-2024-04-09", &overrides),
+            Some(128_000)
+        );
+        assert_eq!(
+            context_window("gpt-4-1106-preview", &overrides),
+            Some(128_000)
+        );
+        assert_eq!(
+            context_window("gpt-4-0125-preview", &overrides),
+            Some(128_000)
+        );
+        assert_eq!(context_window("gpt-4-32k-0613", &overrides), Some(32_768));
+        assert_eq!(
+            context_window("gemini-1.5-pro-latest", &overrides),
+            Some(2_097_152)
+        );
+        assert_eq!(
+            context_window("anthropic/claude-sonnet-4-5", &overrides),
+            Some(200_000)
+        );
+        assert_eq!(context_window("my-local", &overrides), Some(4096));
+        assert_eq!(context_window("unknown-model", &overrides), None);
+        assert_eq!(
+            usage_event("my-local", 1000, &overrides),
+            Some(StreamEvent::ContextUsage {
+                used_tokens: 1000,
+                max_tokens: 4096,
+            })
+        );
+        assert_eq!(usage_event("unknown-model", 1000, &overrides), None);
+    }
+
+    #[test]
+    fn test_plan_fallback() {
+        let config = config();
+        // 放得下时不回退
+        assert_eq!(plan_fallback("gpt-4", 2_000, &config), None);
+
+        let fallback = plan_fallback("gpt-4", 6_000, &config).unwrap();
+        assert_eq!(fallback.to, "gpt-4o");
+        assert_eq!(fallback.context_window, 8_192);
+
+        // 跳过放不下的候选
+        let fallback = plan_fallback("gpt-4", 500_000, &config).unwrap();
+        assert_eq!(fallback.to, "gemini-2.5-pro");
+
+        assert_eq!(plan_fallback("gpt-4", 5_000_000, &config), None);
+        assert_eq!(plan_fallback("unknown-model", 500_000, &config), None);
+        let disabled = ContextFallbackConfig {
+            enabled: false,
+            ..config
+        };
+        assert_eq!(plan_fallback("gpt-4", 6_000, &disabled), None);
+    }
+}
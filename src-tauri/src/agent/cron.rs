@@ -0,0 +1,203 @@
+//! Cron 表达式
+//!
+//! 支持标准 5 字段格式 `分 时 日 月 周`：
+//! - `*`、单个数字、范围 `1-5`、列表 `1,15`、步长 `*/15` 和 `8-18/2`
+//! - 周字段 0 和 7 都表示周日
+//! - 日和周都不是 `*` 时任一满足即匹配（与 Vixie cron 一致）
+//!
+//! 时间按本地时间（不含时区）计算，由调用方负责时区转换。
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike};
+
+/// 向后查找下一次执行时间的最大跨度（天）
+const MAX_SEARCH_DAYS: i64 = 366 * 5;
+
+/// 解析后的 cron 表达式（每个字段用位图表示允许的取值）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// 日字段是否为 `*`
+    any_day: bool,
+    /// 周字段是否为 `*`
+    any_weekday: bool,
+}
+
+/// 解析单个字段，返回允许取值的位图
+fn parse_field(expr: &str, name: &str, min: u32, max: u32) -> Result<u64, String> {
+    let parse_num = |s: &str| -> Result<u32, String> {
+        let n: u32 = s
+            .parse()
+            .map_err(|_| format!("{}字段包含无效的数字: {}", name, s))?;
+        if n < min || n > max {
+            return Err(format!("{}字段取值 {} 超出范围 {}-{}", name, n, min, max));
+        }
+        Ok(n)
+    };
+
+    let mut bits = 0u64;
+    for part in expr.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| format!("{}字段包含无效的步长: {}", name, part))?;
+                if step == 0 {
+                    return Err(format!("{}字段的步长不能为 0", name));
+                }
+                (range, Some(step))
+            }
+            None => (part, None),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_num(start)?, parse_num(end)?)
+        } else {
+            let start = parse_num(range)?;
+            // `5/15` 表示从 5 开始每 15 个单位
+            (start, if step.is_some() { max } else { start })
+        };
+        if start > end {
+            return Err(format!("{}字段的范围无效: {}", name, range));
+        }
+        for value in (start..=end).step_by(step.unwrap_or(1) as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+fn has(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+impl CronSchedule {
+    /// 解析 5 字段 cron 表达式
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!(
+                "cron 表达式需要 5 个字段（分 时 日 月 周），实际为 {} 个: {}",
+                fields.len(),
+                expr
+            ));
+        }
+        let mut weekdays = parse_field(fields[4], "周", 0, 7)?;
+        if has(weekdays, 7) {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            minutes: parse_field(fields[0], "分钟", 0, 59)?,
+            hours: parse_field(fields[1], "小时", 0, 23)?,
+            days: parse_field(fields[2], "日", 1, 31)?,
+            months: parse_field(fields[3], "月", 1, 12)?,
+            weekdays,
+            any_day: fields[2] == "*",
+            any_weekday: fields[4] == "*",
+        })
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if !has(self.months, date.month()) {
+            return false;
+        }
+        let day = has(self.days, date.day());
+        let weekday = has(self.weekdays, date.weekday().num_days_from_sunday());
+        if self.any_day || self.any_weekday {
+            day && weekday
+        } else {
+            day || weekday
+        }
+    }
+
+    /// 指定时间（精确到分钟）是否匹配
+    pub fn matches(&self, time: NaiveDateTime) -> bool {
+        self.matches_date(time.date())
+            && has(self.hours, time.hour())
+            && has(self.minutes, time.minute())
+    }
+
+    /// 严格晚于 `after` 的下一次执行时间
+    ///
+    /// 表达式永远不会匹配（例如 `0 0 31 2 *`）时返回 `None`
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = after + Duration::days(MAX_SEARCH_DAYS);
+        while time <= limit {
+            if !self.matches_date(time.date()) {
+                time = time.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if !has(self.hours, time.hour()) {
+                time = time.with_minute(0)? + Duration::hours(1);
+            } else if !has(self.minutes, time.minute()) {
+                time += Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_match() {
+        let workdays = CronSchedule::parse("0 9 * * 1-5").unwrap();
+        // 2026-03-02 是周一
+        assert!(workdays.matches(at("2026-03-02 09:00")));
+        assert!(!workdays.matches(at("2026-03-01 09:00")));
+        assert!(!workdays.matches(at("2026-03-02 09:01")));
+
+        let stepped = CronSchedule::parse("*/15 8-18/2 1,15 * *").unwrap();
+        assert!(stepped.matches(at("2026-03-15 10:45")));
+        assert!(!stepped.matches(at("2026-03-15 11:45")));
+
+        // 日和周都指定时任一满足即可；7 也表示周日
+        let either = CronSchedule::parse("0 0 1 * 7").unwrap();
+        assert!(either.matches(at("2026-03-01 00:00")));
+        assert!(either.matches(at("2026-03-08 00:00")));
+        assert!(!either.matches(at("2026-03-09 00:00")));
+
+        assert!(CronSchedule::parse("0 9 * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("0 18-9 * * *").is_err());
+    }
+
+    #[test]
+    fn test_next_after() {
+        let schedule = CronSchedule::parse("30 9 * * 1-5").unwrap();
+        // 周五 10:00 之后的下一次是周一 9:30
+        assert_eq!(
+            schedule.next_after(at("2026-03-06 10:00")),
+            Some(at("2026-03-09 09:30"))
+        );
+        // 严格晚于给定时间
+        assert_eq!(
+            schedule.next_after(at("2026-03-09 09:30")),
+            Some(at("2026-03-10 09:30"))
+        );
+
+        let leap = CronSchedule::parse("0 0 29 2 *").unwrap();
+        assert_eq!(
+            leap.next_after(at("2026-03-01 00:00")),
+            Some(at("2028-02-29 00:00"))
+        );
+        assert_eq!(
+            CronSchedule::parse("0 0 31 2 *")
+                .unwrap()
+                .next_after(at("2026-01-01 00:00")),
+            None
+        );
+    }
+}
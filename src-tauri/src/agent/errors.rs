@@ -0,0 +1,502 @@
+//! Agent 错误类型
+//!
+//! - [`AgentError`]: Agent 命令返回的错误，序列化时带错误码，前端据此分支处理和本地化
+//! - [`classify_provider_error`]: 将上游 Provider 返回的常见错误（API Key 无效、额度不足、
+//!   模型不存在、上下文超长、内容过滤、服务过载、限流）映射为带类型的错误码和建议操作，
+//!   便于前端提供一键修复
+//! - [`classify_stream_error`]: 识别流式响应中途返回的错误事件
+
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
+use thiserror::Error;
+
+/// Agent 错误
+///
+/// 序列化为 `{ "code": "...", "message": "...", ... }`，
+/// 其中 `upstream` 额外带 `status` 和翻译后的 `detail`，`session_not_found` 额外带 `session_id`。
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum AgentError {
+    /// Agent 未初始化
+    #[error("Agent 未初始化")]
+    NotInitialized,
+
+    /// API Server 未运行
+    #[error("ProxyCast API Server 未运行，请先启动服务器")]
+    ServerNotRunning,
+
+    /// API Server 未配置 API Key
+    #[error("ProxyCast API Server 未配置 API Key")]
+    MissingApiKey,
+
+    /// 上游返回错误状态码
+    #[error("API 错误 ({status}): {body}")]
+    Upstream { status: u16, body: String },
+
+    /// 上游在流式响应中途返回错误或终止回复（内容过滤、服务过载等）
+    #[error("API 错误: {}", .0.message)]
+    Stream(ProviderErrorDetail),
+
+    /// 网络错误（连接失败、超时等）
+    #[error("网络错误: {0}")]
+    Network(String),
+
+    /// 响应解析失败
+    #[error("解析响应失败: {0}")]
+    Parse(String),
+
+    /// 会话不存在
+    #[error("会话不存在: {0}")]
+    SessionNotFound(String),
+
+    /// 请求已取消
+    #[error("请求已取消")]
+    Cancelled,
+
+    /// 请求参数无效
+    #[error("{0}")]
+    InvalidRequest(String),
+
+    /// 其他错误
+    #[error("{0}")]
+    Other(String),
+}
+
+impl AgentError {
+    /// 上游错误的分类（其他错误返回 None）
+    pub fn provider_detail(&self) -> Option<ProviderErrorDetail> {
+        match self {
+            AgentError::Upstream { status, body } => {
+                Some(classify_provider_error(Some(*status), body))
+            }
+            AgentError::Stream(detail) => Some(detail.clone()),
+            _ => None,
+        }
+    }
+
+    /// 错误码
+    pub fn code(&self) -> &'static str {
+        match self {
+            AgentError::NotInitialized => "not_initialized",
+            AgentError::ServerNotRunning => "server_not_running",
+            AgentError::MissingApiKey => "missing_api_key",
+            AgentError::Upstream { .. } | AgentError::Stream(_) => "upstream",
+            AgentError::Network(_) => "network",
+            AgentError::Parse(_) => "parse",
+            AgentError::SessionNotFound(_) => "session_not_found",
+            AgentError::Cancelled => "cancelled",
+            AgentError::InvalidRequest(_) => "invalid_request",
+            AgentError::Other(_) => "other",
+        }
+    }
+
+    /// 从 reqwest 错误转换（区分解析错误和网络错误）
+    pub fn from_reqwest(error: reqwest::Error) -> Self {
+        if error.is_decode() {
+            AgentError::Parse(error.to_string())
+        } else {
+            AgentError::Network(error.to_string())
+        }
+    }
+}
+
+impl Serialize for AgentError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("code", self.code())?;
+        map.serialize_entry("message", &self.to_string())?;
+        match self {
+            AgentError::Upstream { status, .. } => {
+                map.serialize_entry("status", status)?;
+                map.serialize_entry("detail", &self.provider_detail())?;
+            }
+            AgentError::Stream(detail) => {
+                map.serialize_entry("detail", detail)?;
+            }
+            AgentError::SessionNotFound(session_id) => {
+                map.serialize_entry("session_id", session_id)?;
+            }
+            _ => {}
+        }
+        map.end()
+    }
+}
+
+/// 尚未迁移到 [`AgentError`] 的内部函数仍返回字符串错误
+impl From<String> for AgentError {
+    fn from(message: String) -> Self {
+        AgentError::Other(message)
+    }
+}
+
+impl From<AgentError> for String {
+    fn from(error: AgentError) -> Self {
+        error.to_string()
+    }
+}
+
+/// 错误类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentErrorCode {
+    /// API Key 无效或已过期
+    InvalidApiKey,
+    /// 额度或余额不足
+    InsufficientQuota,
+    /// 模型不存在或无权访问
+    ModelNotFound,
+    /// 超出模型上下文长度
+    ContextLengthExceeded,
+    /// 请求或回复触发内容过滤
+    ContentFilter,
+    /// 上游服务过载
+    Overloaded,
+    /// 请求过于频繁
+    RateLimit,
+    /// 其他错误
+    Unknown,
+}
+
+/// 建议操作（前端据此渲染一键修复按钮）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentErrorAction {
+    /// 打开 Provider 设置
+    OpenSettings,
+    /// 切换模型
+    SwitchModel,
+    /// 压缩会话
+    CompactSession,
+    /// 稍后重试
+    Retry,
+}
+
+/// 翻译后的 Provider 错误
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderErrorDetail {
+    /// 错误类型
+    pub code: AgentErrorCode,
+    /// HTTP 状态码
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// 上游返回的原始错误信息
+    pub message: String,
+    /// 面向用户的处理建议
+    pub suggestion: String,
+    /// 建议操作
+    pub actions: Vec<AgentErrorAction>,
+}
+
+/// 从错误响应体中提取 code、type 和 message 字段
+fn extract_error_fields(body: &str) -> (String, String) {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(body) else {
+        return (String::new(), body.to_string());
+    };
+    let error = value.get("error").unwrap_or(&value);
+    let field = |name: &str| {
+        error
+            .get(name)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let identifiers = format!("{} {}", field("code"), field("type"));
+    let message = match field("message") {
+        m if m.is_empty() => body.to_string(),
+        m => m,
+    };
+    (identifiers, message)
+}
+
+/// 根据 HTTP 状态码和响应体翻译 Provider 错误
+pub fn classify_provider_error(status: Option<u16>, body: &str) -> ProviderErrorDetail {
+    let (identifiers, message) = extract_error_fields(body);
+    let haystack = format!("{} {}", identifiers, message).to_lowercase();
+    let has = |patterns: &[&str]| patterns.iter().any(|p| haystack.contains(p));
+
+    let code = if has(&[
+        "context_length_exceeded",
+        "maximum context length",
+        "prompt is too long",
+        "context window",
+    ]) {
+        AgentErrorCode::ContextLengthExceeded
+    } else if has(&[
+        "insufficient_quota",
+        "exceeded your current quota",
+        "billing",
+        "credit balance",
+        "余额不足",
+    ]) || status == Some(402)
+    {
+        AgentErrorCode::InsufficientQuota
+    } else if has(&[
+        "invalid_api_key",
+        "incorrect api key",
+        "invalid x-api-key",
+        "authentication_error",
+        "unauthorized",
+    ]) || status == Some(401)
+    {
+        AgentErrorCode::InvalidApiKey
+    } else if has(&[
+        "model_not_found",
+        "not_found_error",
+        "unknown model",
+        "model not found",
+    ]) || ((status == Some(404) || haystack.contains("does not exist"))
+        && haystack.contains("model"))
+    {
+        AgentErrorCode::ModelNotFound
+    } else if has(&[
+        "content_filter",
+        "content_policy",
+        "content management policy",
+        "safety system",
+    ]) {
+        AgentErrorCode::ContentFilter
+    } else if has(&["overloaded", "server is busy", "服务繁忙"])
+        || matches!(status, Some(503) | Some(529))
+    {
+        AgentErrorCode::Overloaded
+    } else if has(&["rate_limit", "rate limit", "too many requests"]) || status == Some(429) {
+        AgentErrorCode::RateLimit
+    } else {
+        AgentErrorCode::Unknown
+    };
+
+    provider_error_detail(code, status, message)
+}
+
+/// 按错误类型补充处理建议和建议操作
+fn provider_error_detail(
+    code: AgentErrorCode,
+    status: Option<u16>,
+    message: String,
+) -> ProviderErrorDetail {
+    let (suggestion, actions) = match code {
+        AgentErrorCode::InvalidApiKey => (
+            "API Key 无效或已过期，请在设置中检查 Provider 凭证",
+            vec![AgentErrorAction::OpenSettings],
+        ),
+        AgentErrorCode::InsufficientQuota => (
+            "账户额度不足，请充值或切换到其他 Provider / 模型",
+            vec![
+                AgentErrorAction::OpenSettings,
+                AgentErrorAction::SwitchModel,
+            ],
+        ),
+        AgentErrorCode::ModelNotFound => (
+            "模型不存在或当前凭证无权访问，请切换模型",
+            vec![AgentErrorAction::SwitchModel],
+        ),
+        AgentErrorCode::ContextLengthExceeded => (
+            "对话超出模型上下文长度，请压缩会话或切换到更长上下文的模型",
+            vec![
+                AgentErrorAction::CompactSession,
+                AgentErrorAction::SwitchModel,
+            ],
+        ),
+        AgentErrorCode::ContentFilter => (
+            "请求或回复触发了内容过滤，请调整提示词或切换模型",
+            vec![AgentErrorAction::SwitchModel],
+        ),
+        AgentErrorCode::Overloaded => (
+            "上游服务繁忙，请稍后重试或切换模型",
+            vec![AgentErrorAction::Retry, AgentErrorAction::SwitchModel],
+        ),
+        AgentErrorCode::RateLimit => ("请求过于频繁，请稍后重试", vec![AgentErrorAction::Retry]),
+        AgentErrorCode::Unknown => ("请求失败，请稍后重试", vec![AgentErrorAction::Retry]),
+    };
+
+    ProviderErrorDetail {
+        code,
+        status,
+        message,
+        suggestion: suggestion.to_string(),
+        actions,
+    }
+}
+
+/// 识别流式响应中途返回的错误（Anthropic 的 `error` 事件、OpenAI 兼容接口的 `{"error": {...}}`）
+///
+/// 回复被内容过滤终止（OpenAI 的 `finish_reason: "content_filter"`、
+/// Anthropic 的 `stop_reason: "refusal"`）同样视为错误
+pub fn classify_stream_error(data: &str) -> Option<ProviderErrorDetail> {
+    let value: serde_json::Value = serde_json::from_str(data).ok()?;
+    let is_error = value.get("type").and_then(|v| v.as_str()) == Some("error")
+        || value.get("error").is_some_and(|v| v.is_object());
+    if is_error {
+        return Some(classify_provider_error(None, data));
+    }
+
+    let content_filtered = value["choices"].as_array().is_some_and(|choices| {
+        choices
+            .iter()
+            .any(|c| c["finish_reason"] == "content_filter")
+    });
+    if content_filtered {
+        return Some(provider_error_detail(
+            AgentErrorCode::ContentFilter,
+            None,
+            "回复被上游内容过滤终止".to_string(),
+        ));
+    }
+    (value["delta"]["stop_reason"] == "refusal").then(|| {
+        provider_error_detail(
+            AgentErrorCode::ContentFilter,
+            None,
+            "模型拒绝回答该请求".to_string(),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openai_invalid_api_key() {
+        let body = r#"{"error":{"message":"Incorrect API key provided","type":"invalid_request_error","code":"invalid_api_key"}}"#;
+        let error = classify_provider_error(Some(401), body);
+        assert_eq!(error.code, AgentErrorCode::InvalidApiKey);
+        assert_eq!(error.message, "Incorrect API key provided");
+        assert_eq!(error.actions, vec![AgentErrorAction::OpenSettings]);
+    }
+
+    #[test]
+    fn test_anthropic_prompt_too_long() {
+        let body = r#"{"type":"error","error":{"type":"invalid_request_error","message":"prompt is too long: 210000 tokens > 200000 maximum"}}"#;
+        let error = classify_provider_error(Some(400), body);
+        assert_eq!(error.code, AgentErrorCode::ContextLengthExceeded);
+        assert!(error.actions.contains(&AgentErrorAction::CompactSession));
+    }
+
+    #[test]
+    fn test_quota_and_rate_limit() {
+        let quota = r#"{"error":{"message":"You exceeded your current quota","code":"insufficient_quota"}}"#;
+        assert_eq!(
+            classify_provider_error(Some(429), quota).code,
+            AgentErrorCode::InsufficientQuota
+        );
+        assert_eq!(
+            classify_provider_error(Some(429), "Too Many Requests").code,
+            AgentErrorCode::RateLimit
+        );
+    }
+
+    #[test]
+    fn test_content_filter_and_overloaded() {
+        let filtered = r#"{"error":{"message":"The response was filtered due to the prompt triggering Azure OpenAI's content management policy.","code":"content_filter"}}"#;
+        assert_eq!(
+            classify_provider_error(Some(400), filtered).code,
+            AgentErrorCode::ContentFilter
+        );
+        let overloaded =
+            r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        let error = classify_provider_error(Some(529), overloaded);
+        assert_eq!(error.code, AgentErrorCode::Overloaded);
+        assert!(error.actions.contains(&AgentErrorAction::Retry));
+    }
+
+    #[test]
+    fn test_classify_stream_error() {
+        let event =
+            r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        assert_eq!(
+            classify_stream_error(event).map(|e| e.code),
+            Some(AgentErrorCode::Overloaded)
+        );
+        let openai = r#"{"error":{"message":"You exceeded your current quota","code":"insufficient_quota"}}"#;
+        assert_eq!(
+            classify_stream_error(openai).map(|e| e.code),
+            Some(AgentErrorCode::InsufficientQuota)
+        );
+        assert!(classify_stream_error(r#"{"type":"content_block_delta"}"#).is_none());
+        assert!(classify_stream_error("[DONE]").is_none());
+    }
+
+    #[test]
+    fn test_classify_stream_content_filter() {
+        let openai = r#"{"choices":[{"index":0,"delta":{},"finish_reason":"content_filter"}]}"#;
+        let detail = classify_stream_error(openai).unwrap();
+        assert_eq!(detail.code, AgentErrorCode::ContentFilter);
+        assert_eq!(detail.actions, vec![AgentErrorAction::SwitchModel]);
+
+        let anthropic = r#"{"type":"message_delta","delta":{"stop_reason":"refusal"},"usage":{"output_tokens":0}}"#;
+        assert_eq!(
+            classify_stream_error(anthropic).map(|e| e.code),
+            Some(AgentErrorCode::ContentFilter)
+        );
+
+        let stopped = r#"{"choices":[{"index":0,"delta":{},"finish_reason":"stop"}]}"#;
+        assert!(classify_stream_error(stopped).is_none());
+        let ended = r#"{"type":"message_delta","delta":{"stop_reason":"end_turn"}}"#;
+        assert!(classify_stream_error(ended).is_none());
+
+        let error = AgentError::Stream(classify_stream_error(openai).unwrap());
+        let value = serde_json::to_value(&error).unwrap();
+        assert_eq!(value["code"], "upstream");
+        assert_eq!(value["detail"]["code"], "content_filter");
+    }
+
+    #[test]
+    fn test_model_not_found() {
+        let body =
+            r#"{"error":{"message":"The model `gpt-9` does not exist","code":"model_not_found"}}"#;
+        assert_eq!(
+            classify_provider_error(Some(404), body).code,
+            AgentErrorCode::ModelNotFound
+        );
+    }
+
+    #[test]
+    fn test_generic_wording_is_not_misclassified() {
+        let max_tokens = r#"{"error":{"message":"max_tokens: too many tokens requested for this model","type":"invalid_request_error"}}"#;
+        assert_eq!(
+            classify_provider_error(Some(400), max_tokens).code,
+            AgentErrorCode::Unknown
+        );
+        let quota_header = r#"{"error":{"message":"invalid value for header x-quota-project","type":"invalid_request_error"}}"#;
+        assert_eq!(
+            classify_provider_error(Some(400), quota_header).code,
+            AgentErrorCode::Unknown
+        );
+        let missing_file = r#"{"error":{"message":"File file-abc does not exist","type":"invalid_request_error"}}"#;
+        assert_eq!(
+            classify_provider_error(Some(400), missing_file).code,
+            AgentErrorCode::Unknown
+        );
+        let missing_model = r#"{"error":{"message":"The model `gpt-9` does not exist or you do not have access to it."}}"#;
+        assert_eq!(
+            classify_provider_error(Some(400), missing_model).code,
+            AgentErrorCode::ModelNotFound
+        );
+    }
+
+    #[test]
+    fn test_agent_error_serializes_code() {
+        let value = serde_json::to_value(AgentError::SessionNotFound("s1".to_string())).unwrap();
+        assert_eq!(value["code"], "session_not_found");
+        assert_eq!(value["message"], "会话不存在: s1");
+        assert_eq!(value["session_id"], "s1");
+
+        let upstream = AgentError::Upstream {
+            status: 401,
+            body: r#"{"error":{"message":"bad key","code":"invalid_api_key"}}"#.to_string(),
+        };
+        let value = serde_json::to_value(&upstream).unwrap();
+        assert_eq!(value["code"], "upstream");
+        assert_eq!(value["status"], 401);
+        assert_eq!(value["detail"]["code"], "invalid_api_key");
+
+        let other: AgentError = "boom".to_string().into();
+        assert_eq!(serde_json::to_value(other).unwrap()["code"], "other");
+    }
+
+    #[test]
+    fn test_unknown_error_keeps_raw_body() {
+        let error = classify_provider_error(Some(500), "upstream exploded");
+        assert_eq!(error.code, AgentErrorCode::Unknown);
+        assert_eq!(error.message, "upstream exploded");
+    }
+}
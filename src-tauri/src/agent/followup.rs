@@ -0,0 +1,446 @@
+//! Agent 计划的后续任务
+//!
+//! Agent 通过 `schedule_followup` 工具为当前会话创建一次性的后续任务
+//! （例如"20 分钟后再检查一次构建结果并汇报"）：
+//! - 新任务处于待批准状态，用户批准后才会执行，拒绝或取消后不再执行
+//! - 每个会话同时存在的待批准/待执行任务数有上限，延迟时间也有上限
+//! - 后台调度器到期后以任务内容作为用户消息在原会话中发起一轮带工具的对话
+//! - 任务放入 [`ApprovalQueue`]，状态变化时通过 [`FOLLOWUP_EVENT`] 事件推送到前端
+//!
+//! 系统休眠期间到期的已批准任务按 [`MissedTaskPolicy`] 处理（立即执行、顺延或跳过）。
+//!
+//! 任务只保存在内存中，应用重启后不会恢复。
+
+use crate::agent::approval_queue::{ApprovalItem, ApprovalQueue};
+use crate::agent::types::NativeChatRequest;
+use crate::agent::{NativeAgentState, ToolLoopEngine};
+use crate::config::MissedTaskPolicy;
+use crate::power::SleepDetector;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::mpsc;
+
+/// 推送到前端的事件名
+pub const FOLLOWUP_EVENT: &str = "agent-followup";
+
+/// 每个会话同时存在的待批准/待执行任务上限
+pub const MAX_ACTIVE_FOLLOWUPS_PER_SESSION: usize = 3;
+
+/// 最大延迟时间（分钟）
+pub const MAX_FOLLOWUP_DELAY_MINUTES: u32 = 24 * 60;
+
+/// 调度器检查间隔
+const FOLLOWUP_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// 后续任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FollowupStatus {
+    /// 等待用户批准
+    PendingApproval,
+    /// 已批准，等待到期执行
+    Scheduled,
+    /// 执行中
+    Running,
+    /// 已完成
+    Completed,
+    /// 执行失败
+    Failed,
+    /// 用户拒绝
+    Rejected,
+    /// 已取消
+    Cancelled,
+}
+
+impl FollowupStatus {
+    /// 是否仍会执行（计入会话上限）
+    pub fn is_active(self) -> bool {
+        matches!(
+            self,
+            FollowupStatus::PendingApproval | FollowupStatus::Scheduled | FollowupStatus::Running
+        )
+    }
+}
+
+/// 后续任务
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FollowupTask {
+    pub id: String,
+    /// 所属会话
+    pub session_id: String,
+    /// 到期后发送到会话的任务内容
+    pub prompt: String,
+    /// 创建时间
+    pub created_at: DateTime<Utc>,
+    /// 计划执行时间
+    pub due_at: DateTime<Utc>,
+    pub status: FollowupStatus,
+    /// 执行结果（Agent 的最终回复）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+    /// 失败原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ApprovalItem for FollowupTask {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn session_id(&self) -> Option<&str> {
+        Some(&self.session_id)
+    }
+
+    fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    fn is_pending(&self) -> bool {
+        self.status == FollowupStatus::PendingApproval
+    }
+
+    fn is_active(&self) -> bool {
+        self.status.is_active()
+    }
+
+    /// 批准时计划执行时间已过的任务会在下一次检查时立即执行
+    fn set_reviewed(&mut self, approved: bool) {
+        self.status = if approved {
+            FollowupStatus::Scheduled
+        } else {
+            FollowupStatus::Rejected
+        };
+    }
+
+    fn set_expired(&mut self) {
+        self.status = FollowupStatus::Cancelled;
+    }
+}
+
+/// 是否尚未开始执行（可以取消）
+fn is_cancellable(task: &FollowupTask) -> bool {
+    matches!(
+        task.status,
+        FollowupStatus::PendingApproval | FollowupStatus::Scheduled
+    )
+}
+
+/// 后续任务调度器（克隆后共享同一份任务表）
+#[derive(Clone)]
+pub struct FollowupScheduler {
+    tasks: ApprovalQueue<FollowupTask>,
+    /// 休眠期间错过的任务的处理方式
+    missed_policy: Arc<RwLock<MissedTaskPolicy>>,
+}
+
+impl Default for FollowupScheduler {
+    fn default() -> Self {
+        Self {
+            tasks: ApprovalQueue::new(FOLLOWUP_EVENT, "后续任务", MAX_ACTIVE_FOLLOWUPS_PER_SESSION),
+            missed_policy: Arc::default(),
+        }
+    }
+}
+
+impl FollowupScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置推送 [`FOLLOWUP_EVENT`] 事件的应用句柄
+    pub fn set_app_handle(&self, app_handle: AppHandle) {
+        self.tasks.set_app_handle(app_handle);
+    }
+
+    /// 创建待批准的后续任务
+    pub fn schedule(
+        &self,
+        session_id: &str,
+        prompt: &str,
+        delay_minutes: u32,
+    ) -> Result<FollowupTask, String> {
+        let prompt = prompt.trim();
+        if prompt.is_empty() {
+            return Err("后续任务内容不能为空".to_string());
+        }
+        if delay_minutes == 0 || delay_minutes > MAX_FOLLOWUP_DELAY_MINUTES {
+            return Err(format!(
+                "延迟时间必须在 1 到 {} 分钟之间",
+                MAX_FOLLOWUP_DELAY_MINUTES
+            ));
+        }
+
+        let now = Utc::now();
+        self.tasks.submit(FollowupTask {
+            id: uuid::Uuid::new_v4().to_string(),
+            session_id: session_id.to_string(),
+            prompt: prompt.to_string(),
+            created_at: now,
+            due_at: now + ChronoDuration::minutes(delay_minutes as i64),
+            status: FollowupStatus::PendingApproval,
+            result: None,
+            error: None,
+        })
+    }
+
+    /// 批准或拒绝待批准的任务
+    ///
+    /// 批准时计划执行时间已过的任务会在下一次检查时立即执行。
+    pub fn review(&self, id: &str, approved: bool) -> Result<FollowupTask, String> {
+        self.tasks.review(id, approved)
+    }
+
+    /// 取消尚未执行的任务
+    pub fn cancel(&self, id: &str) -> Result<FollowupTask, String> {
+        self.tasks.try_update(id, |task| {
+            if !is_cancellable(task) {
+                return Err(format!("后续任务无法取消: {:?}", task.status));
+            }
+            task.status = FollowupStatus::Cancelled;
+            Ok(())
+        })
+    }
+
+    /// 取消会话的所有未执行任务（删除会话时调用）
+    pub fn cancel_session(&self, session_id: &str) {
+        self.tasks.update_where(
+            |task| task.session_id == session_id && is_cancellable(task),
+            |task| task.status = FollowupStatus::Cancelled,
+        );
+    }
+
+    /// 列出任务（按计划执行时间排序），可按会话过滤
+    pub fn list(&self, session_id: Option<&str>) -> Vec<FollowupTask> {
+        let mut tasks = self.tasks.list(session_id);
+        tasks.sort_by(|a, b| a.due_at.cmp(&b.due_at));
+        tasks
+    }
+
+    /// 取出已到期的已批准任务并标记为执行中
+    pub fn take_due(&self, now: DateTime<Utc>) -> Vec<FollowupTask> {
+        self.tasks.update_where(
+            |task| task.status == FollowupStatus::Scheduled && task.due_at <= now,
+            |task| task.status = FollowupStatus::Running,
+        )
+    }
+
+    /// 设置休眠期间错过的任务的处理方式
+    pub fn set_missed_task_policy(&self, policy: MissedTaskPolicy) {
+        *self.missed_policy.write() = policy;
+    }
+
+    /// 处理 `slept_from` 到 `now` 休眠期间到期的已批准任务，返回状态或执行时间被修改的任务
+    ///
+    /// `RunOnce` 不修改任务，由下一次 [`FollowupScheduler::take_due`] 取出执行。
+    pub fn catch_up(&self, slept_from: DateTime<Utc>, now: DateTime<Utc>) -> Vec<FollowupTask> {
+        let policy = *self.missed_policy.read();
+        if policy == MissedTaskPolicy::RunOnce {
+            return Vec::new();
+        }
+
+        let slept = now - slept_from;
+        self.tasks.update_where(
+            |task| {
+                task.status == FollowupStatus::Scheduled
+                    && task.due_at > slept_from
+                    && task.due_at <= now
+            },
+            |task| match policy {
+                MissedTaskPolicy::Shift => task.due_at += slept,
+                MissedTaskPolicy::Skip => {
+                    task.status = FollowupStatus::Cancelled;
+                    task.error = Some("系统休眠期间错过执行时间，已跳过".to_string());
+                }
+                MissedTaskPolicy::RunOnce => {}
+            },
+        )
+    }
+
+    /// 记录执行结果
+    pub fn finish(&self, id: &str, outcome: Result<String, String>) -> Option<FollowupTask> {
+        self.tasks.update(id, |task| match outcome {
+            Ok(result) => {
+                task.status = FollowupStatus::Completed;
+                task.result = Some(result);
+            }
+            Err(error) => {
+                task.status = FollowupStatus::Failed;
+                task.error = Some(error);
+            }
+        })
+    }
+}
+
+/// 在原会话中执行到期的后续任务，返回 Agent 的最终回复
+async fn run_followup(
+    agent_state: &NativeAgentState,
+    task: &FollowupTask,
+) -> Result<String, String> {
+    if agent_state.get_session(&task.session_id)?.is_none() {
+        return Err("会话已不存在".to_string());
+    }
+
+    let request = NativeChatRequest {
+        session_id: Some(task.session_id.clone()),
+        message: format!("[计划的后续任务] {}", task.prompt),
+        model: None,
+        images: None,
+        audio: None,
+        attachments: None,
+        context: None,
+        stream: true,
+    };
+    // 执行时不再注册 schedule_followup，避免任务无限自我续期
+    let engine = ToolLoopEngine::new(agent_state.get_tool_registry(None)?);
+    let (tx, mut rx) = mpsc::channel(100);
+    let drain = tokio::spawn(async move { while rx.recv().await.is_some() {} });
+    let result = agent_state
+        .chat_stream_with_tools(request, tx, &engine)
+        .await;
+    let _ = drain.await;
+    result.map(|r| r.content).map_err(String::from)
+}
+
+/// 启动后续任务调度器
+///
+/// 每隔 [`FOLLOWUP_CHECK_INTERVAL`] 执行已到期的已批准任务（任务状态变化由任务队列推送）。
+/// 检测到系统休眠后先按策略处理休眠期间到期的任务。
+pub fn spawn_followup_scheduler(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(FOLLOWUP_CHECK_INTERVAL);
+        let mut detector = SleepDetector::new(FOLLOWUP_CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            let slept = detector.check();
+
+            let Some(agent_state) = app_handle.try_state::<NativeAgentState>() else {
+                continue;
+            };
+            let scheduler = agent_state.followups().clone();
+
+            if let Some(slept) = slept {
+                let now = Utc::now();
+                let slept_from = now
+                    - ChronoDuration::from_std(slept).unwrap_or_else(|_| ChronoDuration::zero());
+                let changed = scheduler.catch_up(slept_from, now);
+                if !changed.is_empty() {
+                    tracing::info!(
+                        "[Followup] 系统唤醒，处理 {} 个休眠期间到期的后续任务",
+                        changed.len()
+                    );
+                }
+            }
+
+            for task in scheduler.take_due(Utc::now()) {
+                tracing::info!(
+                    "[Followup] 执行后续任务: id={}, session={}",
+                    task.id,
+                    task.session_id
+                );
+                let app_handle = app_handle.clone();
+                let scheduler = scheduler.clone();
+                tauri::async_runtime::spawn(async move {
+                    let Some(agent_state) = app_handle.try_state::<NativeAgentState>() else {
+                        return;
+                    };
+                    let outcome = run_followup(&agent_state, &task).await;
+                    if let Err(e) = &outcome {
+                        tracing::warn!("[Followup] 后续任务 {} 执行失败: {}", task.id, e);
+                    }
+                    scheduler.finish(&task.id, outcome);
+                });
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule_requires_approval() {
+        let scheduler = FollowupScheduler::new();
+        let task = scheduler.schedule("s1", "check the build", 20).unwrap();
+        assert_eq!(task.status, FollowupStatus::PendingApproval);
+
+        // 未批准的任务到期也不会执行
+        let later = Utc::now() + ChronoDuration::minutes(30);
+        assert!(scheduler.take_due(later).is_empty());
+
+        scheduler.review(&task.id, true).unwrap();
+        assert!(scheduler.take_due(Utc::now()).is_empty());
+        let due = scheduler.take_due(later);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].status, FollowupStatus::Running);
+
+        let done = scheduler.finish(&task.id, Ok("done".to_string())).unwrap();
+        assert_eq!(done.status, FollowupStatus::Completed);
+        assert!(scheduler.review(&task.id, true).is_err());
+    }
+
+    #[test]
+    fn test_per_session_limit() {
+        let scheduler = FollowupScheduler::new();
+        for _ in 0..MAX_ACTIVE_FOLLOWUPS_PER_SESSION {
+            scheduler.schedule("s1", "ping", 5).unwrap();
+        }
+        assert!(scheduler.schedule("s1", "ping", 5).is_err());
+        assert!(scheduler.schedule("s2", "ping", 5).is_ok());
+
+        // 拒绝后释放名额
+        let id = scheduler.list(Some("s1"))[0].id.clone();
+        scheduler.review(&id, false).unwrap();
+        assert!(scheduler.schedule("s1", "ping", 5).is_ok());
+    }
+
+    #[test]
+    fn test_schedule_validates_input() {
+        let scheduler = FollowupScheduler::new();
+        assert!(scheduler.schedule("s1", "  ", 5).is_err());
+        assert!(scheduler.schedule("s1", "ping", 0).is_err());
+        assert!(scheduler
+            .schedule("s1", "ping", MAX_FOLLOWUP_DELAY_MINUTES + 1)
+            .is_err());
+    }
+
+    #[test]
+    fn test_catch_up_after_sleep() {
+        let scheduler = FollowupScheduler::new();
+        let task = scheduler.schedule("s1", "ping", 10).unwrap();
+        scheduler.review(&task.id, true).unwrap();
+        let slept_from = Utc::now();
+        let now = slept_from + ChronoDuration::hours(1);
+
+        // 默认立即执行，不修改任务
+        assert!(scheduler.catch_up(slept_from, now).is_empty());
+
+        scheduler.set_missed_task_policy(MissedTaskPolicy::Shift);
+        let changed = scheduler.catch_up(slept_from, now);
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].due_at, task.due_at + ChronoDuration::hours(1));
+        assert!(scheduler.take_due(now).is_empty());
+
+        scheduler.set_missed_task_policy(MissedTaskPolicy::Skip);
+        let changed = scheduler.catch_up(now, now + ChronoDuration::hours(2));
+        assert_eq!(changed[0].status, FollowupStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_cancel_session() {
+        let scheduler = FollowupScheduler::new();
+        let task = scheduler.schedule("s1", "ping", 5).unwrap();
+        scheduler.cancel_session("s1");
+        assert_eq!(
+            scheduler.list(Some("s1"))[0].status,
+            FollowupStatus::Cancelled
+        );
+        assert!(scheduler.cancel(&task.id).is_err());
+    }
+}
@@ -0,0 +1,267 @@
+//! 图片预处理
+//!
+//! 前端可以只传本地图片路径，由后端读取文件、校验格式和大小；
+//! 所有图片在构建消息前按 [`ImageProcessingConfig`] 缩放并重新编码，
+//! 避免超大截图撑爆 token 限制和拖慢上传。
+
+use crate::agent::types::ImageData;
+use crate::config::{ImageOutputFormat, ImageProcessingConfig};
+use base64::Engine;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat};
+use std::io::Cursor;
+
+/// 图片文件大小上限（20MB）
+const MAX_IMAGE_FILE_SIZE: u64 = 20 * 1024 * 1024;
+
+/// 超过该大小的图片即使尺寸未超限也会重新编码（多数 Provider 限制单张图片 5MB）
+const MAX_INLINE_IMAGE_SIZE: usize = 5 * 1024 * 1024;
+
+/// 解析图片列表：带 path 的图片读取文件，所有图片按配置缩放后编码为 base64
+pub fn resolve_images(
+    images: Option<&[ImageData]>,
+    options: &ImageProcessingConfig,
+) -> Result<Option<Vec<ImageData>>, String> {
+    match images {
+        Some(list) if !list.is_empty() => list
+            .iter()
+            .map(|image| resolve_image(image, options))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// 解析单张图片
+pub fn resolve_image(
+    image: &ImageData,
+    options: &ImageProcessingConfig,
+) -> Result<ImageData, String> {
+    let (bytes, name) = match image.path.as_deref() {
+        Some(path) => {
+            let size = std::fs::metadata(path)
+                .map_err(|e| format!("读取图片失败 {}: {}", path, e))?
+                .len();
+            if size > MAX_IMAGE_FILE_SIZE {
+                return Err(format!("图片过大: {} ({} MB)", path, size / 1024 / 1024));
+            }
+            let bytes = std::fs::read(path).map_err(|e| format!("读取图片失败 {}: {}", path, e))?;
+            (bytes, path)
+        }
+        None if image.data.is_empty() => return Err("图片缺少 path 或 data".to_string()),
+        None => {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(&image.data)
+                .map_err(|e| format!("图片 base64 解码失败: {}", e))?;
+            (bytes, "inline")
+        }
+    };
+
+    match preprocess(&bytes, name, options)? {
+        Some((bytes, media_type)) => Ok(ImageData {
+            data: base64::engine::general_purpose::STANDARD.encode(&bytes),
+            media_type,
+            path: None,
+            detail: image.detail,
+        }),
+        // 未重新编码：内联图片原样保留，路径图片直接编码
+        None if image.path.is_none() => Ok(image.clone()),
+        None => Ok(ImageData {
+            data: base64::engine::general_purpose::STANDARD.encode(&bytes),
+            media_type: sniff_format(&bytes, name)?.to_mime_type().to_string(),
+            path: None,
+            detail: image.detail,
+        }),
+    }
+}
+
+/// 按文件头识别图片格式（不信任扩展名）
+pub(crate) fn sniff_format(bytes: &[u8], name: &str) -> Result<ImageFormat, String> {
+    let format = image::guess_format(bytes).map_err(|_| format!("不支持的图片格式: {}", name))?;
+    match format {
+        ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Gif | ImageFormat::WebP => Ok(format),
+        other => Err(format!("不支持的图片格式: {} ({:?})", name, other)),
+    }
+}
+
+/// 尺寸或大小超限时缩放并重新编码，返回新的图片数据和 MIME 类型；无需处理时返回 None
+fn preprocess(
+    bytes: &[u8],
+    name: &str,
+    options: &ImageProcessingConfig,
+) -> Result<Option<(Vec<u8>, String)>, String> {
+    let format = sniff_format(bytes, name)?;
+    if !options.enabled {
+        return Ok(None);
+    }
+
+    let max_dimension = options.max_dimension.max(1);
+    let (width, height) = image::ImageReader::with_format(Cursor::new(bytes), format)
+        .into_dimensions()
+        .map_err(|e| format!("解析图片失败 {}: {}", name, e))?;
+    if bytes.len() <= MAX_INLINE_IMAGE_SIZE && width.max(height) <= max_dimension {
+        return Ok(None);
+    }
+
+    let mut img = image::load_from_memory_with_format(bytes, format)
+        .map_err(|e| format!("解析图片失败 {}: {}", name, e))?;
+    if width.max(height) > max_dimension {
+        img = img.resize(max_dimension, max_dimension, FilterType::Lanczos3);
+    }
+    let (output, media_type) =
+        encode(&img, options).map_err(|e| format!("编码图片失败 {}: {}", name, e))?;
+
+    tracing::debug!(
+        "[NativeAgent] 图片已缩放: {} {}x{} -> {}x{}, {} -> {} bytes",
+        name,
+        width,
+        height,
+        img.width(),
+        img.height(),
+        bytes.len(),
+        output.len()
+    );
+    Ok(Some((output, media_type)))
+}
+
+fn encode(
+    img: &DynamicImage,
+    options: &ImageProcessingConfig,
+) -> image::ImageResult<(Vec<u8>, String)> {
+    let mut output = Vec::new();
+    match options.format {
+        ImageOutputFormat::Jpeg => {
+            image::codecs::jpeg::JpegEncoder::new_with_quality(
+                &mut output,
+                options.quality.clamp(1, 100),
+            )
+            .encode_image(&img.to_rgb8())?;
+            Ok((output, "image/jpeg".to_string()))
+        }
+        ImageOutputFormat::Webp => {
+            image::codecs::webp::WebPEncoder::new_lossless(&mut output)
+                .encode_image(&img.to_rgba8())?;
+            Ok((output, "image/webp".to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb};
+    use std::io::Write;
+
+    fn png_bytes(width: u32, height: u32) -> Vec<u8> {
+        let img: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::new(width, height);
+        let mut bytes = Vec::new();
+        img.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    fn png_file(width: u32, height: u32) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&png_bytes(width, height)).unwrap();
+        file
+    }
+
+    fn path_image(path: &std::path::Path) -> ImageData {
+        ImageData {
+            data: String::new(),
+            media_type: String::new(),
+            path: Some(path.to_string_lossy().to_string()),
+            detail: None,
+        }
+    }
+
+    fn decode(image: &ImageData) -> DynamicImage {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(&image.data)
+            .unwrap();
+        image::load_from_memory(&bytes).unwrap()
+    }
+
+    #[test]
+    fn test_small_image_kept_as_is() {
+        let file = png_file(16, 16);
+        let image =
+            resolve_image(&path_image(file.path()), &ImageProcessingConfig::default()).unwrap();
+        assert_eq!(image.media_type, "image/png");
+        assert!(image.path.is_none());
+        assert!(!image.data.is_empty());
+    }
+
+    #[test]
+    fn test_large_image_downscaled() {
+        let file = png_file(4096, 64);
+        let image =
+            resolve_image(&path_image(file.path()), &ImageProcessingConfig::default()).unwrap();
+        assert_eq!(image.media_type, "image/jpeg");
+        assert_eq!(decode(&image).width(), 2048);
+    }
+
+    #[test]
+    fn test_inline_image_downscaled_to_webp() {
+        let inline = ImageData {
+            data: base64::engine::general_purpose::STANDARD.encode(png_bytes(800, 400)),
+            media_type: "image/png".to_string(),
+            path: None,
+            detail: None,
+        };
+        let options = ImageProcessingConfig {
+            max_dimension: 200,
+            format: ImageOutputFormat::Webp,
+            ..Default::default()
+        };
+        let image = resolve_image(&inline, &options).unwrap();
+        assert_eq!(image.media_type, "image/webp");
+        let decoded = decode(&image);
+        assert_eq!((decoded.width(), decoded.height()), (200, 100));
+    }
+
+    #[test]
+    fn test_disabled_keeps_inline_image() {
+        let inline = ImageData {
+            data: base64::engine::general_purpose::STANDARD.encode(png_bytes(4096, 64)),
+            media_type: "image/png".to_string(),
+            path: None,
+            detail: None,
+        };
+        let options = ImageProcessingConfig {
+            enabled: false,
+            ..Default::default()
+        };
+        let image = resolve_image(&inline, &options).unwrap();
+        assert_eq!(image.data, inline.data);
+    }
+
+    #[test]
+    fn test_detail_preserved() {
+        let file = png_file(4096, 64);
+        let mut image = path_image(file.path());
+        image.detail = Some(crate::agent::types::ImageDetail::Low);
+        let resolved = resolve_image(&image, &ImageProcessingConfig::default()).unwrap();
+        assert_eq!(resolved.detail, Some(crate::agent::types::ImageDetail::Low));
+    }
+
+    #[test]
+    fn test_rejects_non_image_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"not an image").unwrap();
+        assert!(
+            resolve_image(&path_image(file.path()), &ImageProcessingConfig::default()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_missing_path_and_data() {
+        let image = ImageData {
+            data: String::new(),
+            media_type: "image/png".to_string(),
+            path: None,
+            detail: None,
+        };
+        assert!(resolve_image(&image, &ImageProcessingConfig::default()).is_err());
+    }
+}
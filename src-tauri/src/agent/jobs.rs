@@ -0,0 +1,406 @@
+//! 后台 Agent 任务
+//!
+//! 耗时较长的 Agent 对话可以作为后台任务提交，不占用聊天界面：
+//! - 提交后立即返回任务 ID，对话在后台会话中执行（未指定会话时新建一个）
+//! - 流式输出和进度通过 `job://{id}` 事件推送（见 [`JobEvent`]），提交时传入的 Channel 同样收到全部事件
+//! - 运行中的任务可以取消，部分回复按 [`CancelMode`] 保留或丢弃
+//! - 同时运行的任务数有上限；已结束的任务只保留最近 [`MAX_FINISHED_JOBS`] 个
+//!
+//! 任务只保存在内存中，应用重启后不会恢复。
+
+use crate::agent::errors::AgentError;
+use crate::agent::types::{CancelMode, NativeChatRequest, StreamEvent, StreamInfo};
+use crate::agent::{NativeAgentState, ToolLoopEngine};
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::mpsc;
+
+/// 同时运行的任务上限
+pub const MAX_RUNNING_JOBS: usize = 4;
+
+/// 保留的已结束任务数
+pub const MAX_FINISHED_JOBS: usize = 50;
+
+/// 任务事件名
+pub fn job_event_name(id: &str) -> String {
+    format!("job://{}", id)
+}
+
+/// 后台任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// 后台任务
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentJob {
+    pub id: String,
+    /// 执行任务的会话
+    pub session_id: String,
+    /// 任务内容（用户消息）
+    pub message: String,
+    pub model: Option<String>,
+    pub status: JobStatus,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// 已生成的文本；运行中为部分输出，完成后为 Agent 的最终回复
+    pub output: String,
+    /// 已开始的工具调用数
+    pub tool_calls: u32,
+    /// 正在执行的工具
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_tool: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 推送到 `job://{id}` 的事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobEvent {
+    /// 流式输出（与聊天流式事件相同）
+    Stream { event: StreamEvent },
+    /// 任务状态或进度变化（开始、工具调用、结束）
+    Progress { job: AgentJob },
+}
+
+/// 后台任务管理器（克隆后共享同一份任务表）
+#[derive(Clone, Default)]
+pub struct JobManager {
+    jobs: Arc<RwLock<HashMap<String, AgentJob>>>,
+}
+
+impl JobManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 登记运行中的任务，达到同时运行上限时返回错误
+    pub fn create(
+        &self,
+        session_id: &str,
+        message: &str,
+        model: Option<String>,
+    ) -> Result<AgentJob, String> {
+        let mut jobs = self.jobs.write();
+        let running = jobs
+            .values()
+            .filter(|j| j.status == JobStatus::Running)
+            .count();
+        if running >= MAX_RUNNING_JOBS {
+            return Err(format!(
+                "已有 {} 个后台任务正在运行，请等待完成或取消后再提交",
+                running
+            ));
+        }
+        let job = AgentJob {
+            id: uuid::Uuid::new_v4().to_string(),
+            session_id: session_id.to_string(),
+            message: message.to_string(),
+            model,
+            status: JobStatus::Running,
+            created_at: Utc::now(),
+            finished_at: None,
+            output: String::new(),
+            tool_calls: 0,
+            current_tool: None,
+            error: None,
+        };
+        jobs.insert(job.id.clone(), job.clone());
+        Ok(job)
+    }
+
+    pub fn get(&self, id: &str) -> Option<AgentJob> {
+        self.jobs.read().get(id).cloned()
+    }
+
+    /// 列出任务（按创建时间倒序）
+    pub fn list(&self) -> Vec<AgentJob> {
+        let mut jobs: Vec<AgentJob> = self.jobs.read().values().cloned().collect();
+        jobs.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        jobs
+    }
+
+    /// 根据流式事件更新任务，进度发生变化（工具开始/结束）时返回更新后的任务
+    pub fn apply_event(&self, id: &str, event: &StreamEvent) -> Option<AgentJob> {
+        let mut jobs = self.jobs.write();
+        let job = jobs.get_mut(id)?;
+        match event {
+            StreamEvent::TextDelta { text } => {
+                job.output.push_str(text);
+                None
+            }
+            StreamEvent::ToolStart { tool_name, .. } => {
+                job.tool_calls += 1;
+                job.current_tool = Some(tool_name.clone());
+                Some(job.clone())
+            }
+            StreamEvent::ToolEnd { .. } => {
+                job.current_tool = None;
+                Some(job.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// 记录任务结束，并清理超出保留数量的已结束任务
+    ///
+    /// [`AgentError::Cancelled`] 记为已取消，其他错误记为失败。
+    pub fn finish(&self, id: &str, outcome: Result<String, AgentError>) -> Option<AgentJob> {
+        let mut jobs = self.jobs.write();
+        let job = jobs.get_mut(id)?;
+        job.finished_at = Some(Utc::now());
+        job.current_tool = None;
+        match outcome {
+            Ok(output) => {
+                job.status = JobStatus::Completed;
+                job.output = output;
+            }
+            Err(error) => {
+                job.status = match error {
+                    AgentError::Cancelled => JobStatus::Cancelled,
+                    _ => JobStatus::Failed,
+                };
+                job.error = Some(error.to_string());
+            }
+        }
+        let finished = job.clone();
+
+        let mut ended: Vec<(DateTime<Utc>, String)> = jobs
+            .values()
+            .filter_map(|j| j.finished_at.map(|at| (at, j.id.clone())))
+            .collect();
+        if ended.len() > MAX_FINISHED_JOBS {
+            ended.sort();
+            for (_, id) in ended.iter().take(ended.len() - MAX_FINISHED_JOBS) {
+                jobs.remove(id);
+            }
+        }
+        Some(finished)
+    }
+}
+
+/// 任务事件的推送目标：`job://{id}` 全局事件，以及提交时传入的 Channel
+struct JobEmitter {
+    app_handle: AppHandle,
+    event_name: String,
+    channel: Option<Channel<JobEvent>>,
+}
+
+impl JobEmitter {
+    fn emit(&self, event: JobEvent) {
+        if let Err(e) = self.app_handle.emit(&self.event_name, &event) {
+            tracing::warn!("[AgentJob] 推送任务事件失败: {}", e);
+        }
+        if let Some(channel) = &self.channel {
+            if let Err(e) = channel.send(event) {
+                tracing::debug!("[AgentJob] 推送任务事件到 Channel 失败: {}", e);
+            }
+        }
+    }
+}
+
+/// 在后台执行已登记的任务
+///
+/// 事件推送到 `job://{id}`；提交方在拿到任务 ID 之前无法订阅该事件，
+/// 需要完整事件时传入 `channel`（提交前创建，不会错过开始事件）。
+/// 取消信号通过 [`NativeAgentState::cancel_stream`] 以任务事件名发送。
+pub fn spawn_job(
+    app_handle: AppHandle,
+    agent_state: NativeAgentState,
+    job: AgentJob,
+    engine: ToolLoopEngine,
+    channel: Option<Channel<JobEvent>>,
+) {
+    let event_name = job_event_name(&job.id);
+    let mut cancel_rx = agent_state.register_stream(StreamInfo::new(
+        &event_name,
+        Some(job.session_id.clone()),
+        job.model.clone(),
+    ));
+    let turn_start = agent_state
+        .get_session(&job.session_id)
+        .ok()
+        .flatten()
+        .map(|s| s.messages.len());
+    let request = NativeChatRequest {
+        session_id: Some(job.session_id.clone()),
+        message: job.message.clone(),
+        model: job.model.clone(),
+        images: None,
+        audio: None,
+        attachments: None,
+        context: None,
+        stream: true,
+    };
+
+    let emitter = JobEmitter {
+        app_handle,
+        event_name: event_name.clone(),
+        channel,
+    };
+    tauri::async_runtime::spawn(async move {
+        let jobs = agent_state.jobs().clone();
+        emitter.emit(JobEvent::Progress { job: job.clone() });
+
+        let (tx, mut rx) = mpsc::channel::<StreamEvent>(100);
+        let stream_state = agent_state.clone();
+        let stream_task = tokio::spawn(async move {
+            stream_state
+                .chat_stream_with_tools(request, tx, &engine)
+                .await
+        });
+
+        // 当前这次 API 响应已生成的文本（工具开始执行时已写入历史，清空）
+        let mut partial = String::new();
+        let mut cancel_mode = None;
+        loop {
+            let event = tokio::select! {
+                event = rx.recv() => event,
+                Some(mode) = cancel_rx.recv() => {
+                    cancel_mode = Some(mode);
+                    break;
+                }
+            };
+            let Some(event) = event else {
+                break;
+            };
+            match &event {
+                StreamEvent::TextDelta { text } => partial.push_str(text),
+                StreamEvent::ToolStart { .. } => partial.clear(),
+                _ => {}
+            }
+            let progress = jobs.apply_event(&job.id, &event);
+            emitter.emit(JobEvent::Stream { event });
+            if let Some(job) = progress {
+                emitter.emit(JobEvent::Progress { job });
+            }
+        }
+        agent_state.unregister_stream(&event_name);
+
+        let finished = if let Some(mode) = cancel_mode {
+            tracing::info!("[AgentJob] 取消后台任务: id={}, mode={:?}", job.id, mode);
+            stream_task.abort();
+            let _ = stream_task.await;
+            while let Ok(event) = rx.try_recv() {
+                match event {
+                    StreamEvent::TextDelta { text } => partial.push_str(&text),
+                    StreamEvent::ToolStart { .. } => partial.clear(),
+                    _ => {}
+                }
+            }
+            if let Some(turn_start) = turn_start {
+                if let Err(e) = agent_state.settle_cancelled_turn(
+                    &job.session_id,
+                    turn_start,
+                    &job.message,
+                    &partial,
+                    mode,
+                    job.model.as_deref(),
+                ) {
+                    tracing::warn!("[AgentJob] 处理取消的对话失败: {}", e);
+                }
+            }
+            emitter.emit(JobEvent::Stream {
+                event: StreamEvent::Cancelled { mode },
+            });
+            jobs.finish(&job.id, Err(AgentError::Cancelled))
+        } else {
+            let outcome = match stream_task.await {
+                Ok(result) => result.map(|r| r.content),
+                Err(e) if e.is_cancelled() => Err(AgentError::Cancelled),
+                Err(e) => Err(AgentError::Other(format!("任务执行异常: {}", e))),
+            };
+            if let Err(e) = &outcome {
+                tracing::warn!("[AgentJob] 后台任务 {} 执行失败: {}", job.id, e);
+            }
+            jobs.finish(&job.id, outcome)
+        };
+        if let Some(job) = finished {
+            emitter.emit(JobEvent::Progress { job });
+        }
+    });
+}
+
+/// 取消运行中的任务，返回是否找到运行中的任务
+pub fn cancel_job(agent_state: &NativeAgentState, id: &str, mode: CancelMode) -> bool {
+    agent_state.cancel_stream(&job_event_name(id), mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_progress_and_finish() {
+        let manager = JobManager::new();
+        let job = manager.create("s1", "refactor the parser", None).unwrap();
+        assert_eq!(job.status, JobStatus::Running);
+
+        let delta = StreamEvent::TextDelta {
+            text: "Looking".to_string(),
+        };
+        assert!(manager.apply_event(&job.id, &delta).is_none());
+        let start = StreamEvent::ToolStart {
+            tool_name: "read_file".to_string(),
+            tool_id: "t1".to_string(),
+            arguments: None,
+        };
+        let progress = manager.apply_event(&job.id, &start).unwrap();
+        assert_eq!(progress.tool_calls, 1);
+        assert_eq!(progress.current_tool.as_deref(), Some("read_file"));
+        assert_eq!(progress.output, "Looking");
+
+        let done = manager.finish(&job.id, Ok("Done.".to_string())).unwrap();
+        assert_eq!(done.status, JobStatus::Completed);
+        assert_eq!(done.output, "Done.");
+        assert!(done.current_tool.is_none());
+        assert!(done.finished_at.is_some());
+
+        let other = manager.create("s2", "summarize", None).unwrap();
+        let cancelled = manager
+            .finish(&other.id, Err(AgentError::Cancelled))
+            .unwrap();
+        assert_eq!(cancelled.status, JobStatus::Cancelled);
+        assert_eq!(cancelled.error.as_deref(), Some("请求已取消"));
+        assert_eq!(manager.list().len(), 2);
+    }
+
+    #[test]
+    fn test_running_limit_and_pruning() {
+        let manager = JobManager::new();
+        let running: Vec<_> = (0..MAX_RUNNING_JOBS)
+            .map(|i| manager.create(&format!("s{}", i), "task", None).unwrap())
+            .collect();
+        assert!(manager.create("extra", "task", None).is_err());
+
+        let failed = manager
+            .finish(&running[0].id, Err(AgentError::Other("boom".to_string())))
+            .unwrap();
+        assert_eq!(failed.status, JobStatus::Failed);
+        let extra = manager.create("extra", "task", None).unwrap();
+        manager.finish(&extra.id, Ok(String::new()));
+
+        for _ in 0..MAX_FINISHED_JOBS + 5 {
+            let job = manager.create("s", "task", None).unwrap();
+            manager.finish(&job.id, Ok(String::new()));
+        }
+        let finished = manager
+            .list()
+            .into_iter()
+            .filter(|j| j.status != JobStatus::Running)
+            .count();
+        assert_eq!(finished, MAX_FINISHED_JOBS);
+        assert!(manager.get(&running[1].id).is_some());
+    }
+}
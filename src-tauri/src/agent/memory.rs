@@ -0,0 +1,241 @@
+//! Agent 长期记忆
+//!
+//! 跨会话保存重要事实（模型通过 `save_memory` 工具保存，或从用户消息中自动提取），
+//! 新会话首轮对话时检索相关记忆并注入系统提示词，模型也可以通过 `recall_memory` 工具主动检索。
+//! 模型保存的记忆标记为未审核，由用户确认保留或删除；未审核的记忆不会注入系统提示词，
+//! `recall_memory` 返回时标注为未审核。
+
+use crate::database::dao::agent_memory::{AgentMemory, AgentMemoryDao};
+use crate::database::DbConnection;
+use std::collections::HashSet;
+
+/// 每次注入系统提示词的最大记忆条数
+pub const MAX_INJECTED_MEMORIES: usize = 8;
+
+/// 单条记忆最大长度（字符）
+const MAX_MEMORY_CHARS: usize = 500;
+
+/// 自动提取的触发前缀
+const AUTO_EXTRACT_PREFIXES: &[&str] = &[
+    "remember that ",
+    "please remember that ",
+    "please remember ",
+    "remember: ",
+    "请记住：",
+    "请记住:",
+    "请记住",
+    "记住：",
+    "记住:",
+];
+
+/// 记忆存储
+#[derive(Clone)]
+pub struct MemoryStore {
+    db: DbConnection,
+}
+
+impl MemoryStore {
+    pub fn new(db: DbConnection) -> Self {
+        Self { db }
+    }
+
+    /// 保存一条记忆（内容重复时跳过，返回 None）
+    pub fn add(&self, content: &str, source: &str) -> Result<Option<AgentMemory>, String> {
+        self.add_from_session(content, source, None)
+    }
+
+    /// 保存一条记忆并记录来源会话（`tool` 来源的记忆标记为未审核）
+    pub fn add_from_session(
+        &self,
+        content: &str,
+        source: &str,
+        session_id: Option<&str>,
+    ) -> Result<Option<AgentMemory>, String> {
+        let content = normalize_content(content)?;
+        let conn = self.db.lock().map_err(|e| e.to_string())?;
+        if AgentMemoryDao::exists_content(&conn, &content).map_err(|e| e.to_string())? {
+            return Ok(None);
+        }
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let memory = AgentMemory {
+            id: uuid::Uuid::new_v4().to_string(),
+            content,
+            source: source.to_string(),
+            session_id: session_id.map(str::to_string),
+            reviewed: source != "tool",
+            created_at: now.clone(),
+            updated_at: now,
+        };
+        AgentMemoryDao::insert(&conn, &memory).map_err(|e| e.to_string())?;
+        tracing::info!("[Memory] 已保存记忆: id={}, source={}", memory.id, source);
+        Ok(Some(memory))
+    }
+
+    pub fn list(&self) -> Result<Vec<AgentMemory>, String> {
+        let conn = self.db.lock().map_err(|e| e.to_string())?;
+        AgentMemoryDao::list(&conn).map_err(|e| e.to_string())
+    }
+
+    pub fn update(&self, id: &str, content: &str) -> Result<bool, String> {
+        let content = normalize_content(content)?;
+        let conn = self.db.lock().map_err(|e| e.to_string())?;
+        AgentMemoryDao::update_content(&conn, id, &content, &chrono::Utc::now().to_rfc3339())
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn delete(&self, id: &str) -> Result<bool, String> {
+        let conn = self.db.lock().map_err(|e| e.to_string())?;
+        AgentMemoryDao::delete(&conn, id).map_err(|e| e.to_string())
+    }
+
+    /// 审核记忆：保留时标记为已审核，否则删除
+    pub fn review(&self, id: &str, keep: bool) -> Result<bool, String> {
+        if !keep {
+            return self.delete(id);
+        }
+        let conn = self.db.lock().map_err(|e| e.to_string())?;
+        AgentMemoryDao::mark_reviewed(&conn, id).map_err(|e| e.to_string())
+    }
+
+    /// 按查询检索记忆（包括未审核的记忆），查询为空时返回最近更新的记忆
+    pub fn recall(&self, query: &str, limit: usize) -> Result<Vec<AgentMemory>, String> {
+        let memories = self.list()?;
+        if query.trim().is_empty() {
+            return Ok(memories.into_iter().take(limit).collect());
+        }
+        Ok(rank_memories(memories, query, limit))
+    }
+
+    /// 检索与查询相关的已审核记忆（用于注入系统提示词）
+    pub fn relevant(&self, query: &str, limit: usize) -> Result<Vec<AgentMemory>, String> {
+        let reviewed = self.list()?.into_iter().filter(|m| m.reviewed).collect();
+        Ok(rank_memories(reviewed, query, limit))
+    }
+}
+
+fn normalize_content(content: &str) -> Result<String, String> {
+    let content = content.trim();
+    if content.is_empty() {
+        return Err("记忆内容不能为空".to_string());
+    }
+    Ok(content.chars().take(MAX_MEMORY_CHARS).collect())
+}
+
+/// 提取关键词（ASCII 单词 + CJK 单字）
+fn keywords(text: &str) -> HashSet<String> {
+    let mut words = HashSet::new();
+    let mut current = String::new();
+    for ch in text.to_lowercase().chars() {
+        if ch.is_ascii_alphanumeric() {
+            current.push(ch);
+            continue;
+        }
+        if current.len() >= 3 {
+            words.insert(std::mem::take(&mut current));
+        } else {
+            current.clear();
+        }
+        if ('\u{4e00}'..='\u{9fff}').contains(&ch) {
+            words.insert(ch.to_string());
+        }
+    }
+    if current.len() >= 3 {
+        words.insert(current);
+    }
+    words
+}
+
+/// 按关键词重合度排序记忆，仅保留有重合的记录（列表已按更新时间倒序，同分时新记忆优先）
+fn rank_memories(memories: Vec<AgentMemory>, query: &str, limit: usize) -> Vec<AgentMemory> {
+    let query_words = keywords(query);
+    if query_words.is_empty() {
+        return Vec::new();
+    }
+
+    let mut scored: Vec<(usize, AgentMemory)> = memories
+        .into_iter()
+        .map(|m| (keywords(&m.content).intersection(&query_words).count(), m))
+        .filter(|(score, _)| *score > 0)
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0));
+    scored.into_iter().take(limit).map(|(_, m)| m).collect()
+}
+
+/// 从用户消息中提取需要记住的内容（如 “请记住：我使用 Rust 开发”）
+pub fn extract_memory_candidates(message: &str) -> Vec<String> {
+    message
+        .lines()
+        .filter_map(|line| {
+            let trimmed = line.trim();
+            let lower = trimmed.to_lowercase();
+            AUTO_EXTRACT_PREFIXES
+                .iter()
+                .filter(|prefix| lower.starts_with(*prefix))
+                .find_map(|prefix| trimmed.get(prefix.len()..))
+                .map(|rest| rest.trim().to_string())
+        })
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+/// 将记忆格式化为系统提示词片段
+pub fn build_memory_prompt(memories: &[AgentMemory]) -> String {
+    if memories.is_empty() {
+        return String::new();
+    }
+    let mut prompt = String::from("以下是关于用户的长期记忆，回答时请参考：\n");
+    for memory in memories {
+        prompt.push_str(&format!("- {}\n", memory.content));
+    }
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory(id: &str, content: &str) -> AgentMemory {
+        AgentMemory {
+            id: id.to_string(),
+            content: content.to_string(),
+            source: "manual".to_string(),
+            session_id: None,
+            reviewed: true,
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_rank_memories_by_overlap() {
+        let memories = vec![
+            memory("a", "User prefers Python for scripting"),
+            memory("b", "User deploys services with Docker and Rust"),
+            memory("c", "User likes cats"),
+        ];
+        let ranked = rank_memories(memories, "how do I build a Rust Docker image?", 5);
+        let ids: Vec<_> = ranked.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["b"]);
+    }
+
+    #[test]
+    fn test_extract_memory_candidates() {
+        let message = "Remember that I use Rust nightly\n请记住：项目部署在 Fly.io\n普通内容";
+        assert_eq!(
+            extract_memory_candidates(message),
+            vec![
+                "I use Rust nightly".to_string(),
+                "项目部署在 Fly.io".to_string()
+            ]
+        );
+        assert!(extract_memory_candidates("记住").is_empty());
+    }
+
+    #[test]
+    fn test_build_memory_prompt() {
+        assert!(build_memory_prompt(&[]).is_empty());
+        let prompt = build_memory_prompt(&[memory("a", "用户使用 macOS")]);
+        assert!(prompt.contains("- 用户使用 macOS"));
+    }
+}
@@ -8,17 +8,98 @@
 //! - parsers/ - SSE 流解析器
 //! - native_agent - 核心 Agent 逻辑
 //! - tool_loop - 工具调用循环
+//! - tool_approval - 工具权限模式（auto / approve / read-only）与工具调用审核
+//! - approval_queue - 待审核请求队列（工具调用、脚本、补丁、后续任务共用，状态变化推送到前端）
+//! - batch - 批量提示词（数据集逐行渲染模板，有限并发执行，结果写入 CSV/JSONL）
+//! - background - 后台任务（摘要、标题、后续问题、记忆提取），使用独立的后台模型
+//! - attachments - 文件附件（文本提取、编码检测、token 预算截断）
+//! - audio - 音频输入（读取、格式识别、base64 编码，发送为 input_audio）
+//! - context_fallback - 上下文超限时为单次请求换用更大上下文的模型
+//! - cron - cron 表达式解析（定时任务使用）
+//! - errors - Agent 错误类型（带错误码）与 Provider 错误翻译（错误码与建议操作）
+//! - followup - Agent 计划的后续任务（需批准的一次性定时任务）
+//! - images - 图片预处理（按路径读取、格式校验、缩放、base64 编码）
+//! - jobs - 后台 Agent 任务（提交后在后台执行，通过 job://{id} 事件推送进度）
+//! - memory - 长期记忆（跨会话保存与检索）
+//! - model_pin - 会话模型快照固定与漂移检测
+//! - paste - 富文本粘贴（文本与图片混合内容组成消息草稿）
+//! - patch_review - Agent 提出的补丁审核（git_apply_patch 的补丁需用户批准后应用）
+//! - script_review - Skill 脚本运行审核（run_skill_script 运行前等待用户批准）
+//! - skill_draft - 从会话生成 Skill（规范化模型生成的 SKILL.md 并写入技能目录）
+//! - skill_usage - Skill 使用统计（识别模型对 Skill 的引用和调用并累计次数）
+//! - prompt_vars - 系统提示词变量（`{{date}}`、`{{os}}` 等，请求时展开）
+//! - session_bulk - 会话批量操作（删除、打标签、导出，整批校验后执行）
+//! - session_export - 会话导出为 Markdown / 单文件 HTML（用于分享）
+//! - session_lint - 会话分析（过期上下文检测与压缩建议）
+//! - session_meta - 会话整理（标签、文件夹、置顶、归档标记与筛选）
+//! - quick_ask - 快速提问（全局快捷键唤起，独立模型与提示词，可追加到草稿会话）
+//! - scheduled_tasks - 用户定义的定时任务（按 cron 在后台执行并保存结果）
+//! - session_quota - 会话配额（数量与消息总数上限、提醒与归档）
+//! - stream_coalesce - 流式文本合并（合并连续的 TextDelta 后再推送到前端）
+//! - transcript - 会话记录（可选，按会话追加 JSONL，用于审计和排查）
 //! - tools/ - 工具实现
 
+pub mod approval_queue;
+pub mod attachments;
+pub mod audio;
+pub mod background;
+pub mod batch;
+pub mod context_fallback;
+pub mod cron;
+pub mod errors;
+pub mod followup;
+pub mod images;
+pub mod jobs;
+pub mod memory;
+pub mod model_pin;
 pub mod native_agent;
 pub mod parsers;
+pub mod paste;
+pub mod patch_review;
+pub mod prompt_vars;
 pub mod protocols;
+pub mod quick_ask;
+pub mod scheduled_tasks;
+pub mod script_review;
+pub mod session_bulk;
+pub mod session_export;
+pub mod session_lint;
+pub mod session_meta;
+pub mod session_quota;
+pub mod skill_draft;
+pub mod skill_usage;
+pub mod stream_coalesce;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod tool_approval;
 pub mod tool_loop;
 pub mod tools;
+pub mod transcript;
 pub mod types;
 
+pub use background::BackgroundTask;
+pub use context_fallback::ContextFallback;
+pub use errors::{
+    classify_provider_error, classify_stream_error, AgentError, AgentErrorAction, AgentErrorCode,
+    ProviderErrorDetail,
+};
+pub use followup::{FollowupScheduler, FollowupStatus, FollowupTask};
+pub use jobs::{AgentJob, JobEvent, JobManager, JobStatus};
+pub use memory::MemoryStore;
+pub use model_pin::{ModelChange, ModelPin};
 pub use native_agent::{NativeAgent, NativeAgentState};
 pub use parsers::{AnthropicSSEParser, OpenAISSEParser};
+pub use paste::{DraftPart, MessageDraft, PasteItem, PastePayload};
 pub use protocols::{create_protocol, AnthropicProtocol, OpenAIProtocol, Protocol};
-pub use tool_loop::{ToolCallResult, ToolLoopConfig, ToolLoopEngine, ToolLoopError, ToolLoopState};
+pub use scheduled_tasks::{ScheduledTaskStatus, TaskScheduler, TaskTrigger};
+pub use session_bulk::{BulkExportResult, BulkOperation, BulkProgress};
+pub use session_lint::{lint_session, SessionLintKind, SessionLintSuggestion};
+pub use session_meta::{SessionFilter, SessionFolder, SessionMetaUpdate};
+pub use session_quota::{QuotaLevel, SessionQuotaStatus};
+pub use skill_usage::SkillUsageStore;
+pub use tool_approval::{ToolApprovalQueue, ToolApprovalRequest, ToolPermission};
+pub use tool_loop::{
+    ToolCallResult, ToolCancellations, ToolLoopConfig, ToolLoopEngine, ToolLoopError, ToolLoopState,
+};
+pub use transcript::{TranscriptEntry, TranscriptLogger};
 pub use types::*;
@@ -0,0 +1,171 @@
+//! 会话模型快照固定
+//!
+//! 会话记录首次使用的确切模型快照（如 `gpt-4o-2024-11-20`）：
+//! - 创建会话时若指定的模型本身就是带日期的快照，直接固定
+//! - 否则以第一轮响应中上游报告的模型为准
+//!
+//! 之后同一请求模型被路由到不同的快照时（上游更新别名、故障转移到备用端点等），
+//! 在会话元数据中记录变化，并通过流式事件提醒前端，方便需要长期保持行为一致的用户发现漂移。
+//! 用户主动切换请求模型时重新固定，不视为漂移。
+
+use crate::agent::types::{AgentSession, StreamEvent};
+use serde::{Deserialize, Serialize};
+
+/// 会话固定的模型快照
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelPin {
+    /// 请求时使用的模型名（可能是别名）
+    pub requested: String,
+    /// 固定的模型快照
+    pub snapshot: String,
+    /// 最近一次响应中上游报告的模型
+    pub last_resolved: String,
+    pub pinned_at: String,
+}
+
+/// 一次模型快照变化
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelChange {
+    pub requested: String,
+    /// 固定的模型快照
+    pub pinned: String,
+    /// 变化前的模型
+    pub from: String,
+    /// 变化后的模型
+    pub to: String,
+    pub changed_at: String,
+}
+
+impl ModelChange {
+    /// 转换为推送给前端的流式事件
+    pub fn into_event(self) -> StreamEvent {
+        StreamEvent::ModelDrift {
+            requested: self.requested,
+            pinned: self.pinned,
+            from: self.from,
+            to: self.to,
+        }
+    }
+}
+
+/// 模型名是否为带日期的快照（`-20241022` 或 `-2024-11-20` 结尾）
+pub fn is_model_snapshot(model: &str) -> bool {
+    let is_digits = |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_digit());
+    let parts: Vec<&str> = model.rsplitn(4, '-').collect();
+    match parts.as_slice() {
+        [day, month, year, _] if is_digits(year, 4) && is_digits(month, 2) && is_digits(day, 2) => {
+            true
+        }
+        [date, ..] if parts.len() > 1 => is_digits(date, 8),
+        _ => false,
+    }
+}
+
+/// 创建会话时的固定（只有带日期的快照才能在创建时确定）
+pub fn initial_pin(model: &str) -> Option<ModelPin> {
+    is_model_snapshot(model).then(|| ModelPin {
+        requested: model.to_string(),
+        snapshot: model.to_string(),
+        last_resolved: model.to_string(),
+        pinned_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+/// 记录一次响应中上游报告的模型，发生漂移时返回变化记录
+pub fn observe_resolved_model(
+    session: &mut AgentSession,
+    requested: &str,
+    resolved: &str,
+) -> Option<ModelChange> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let pin = match session.model_pin.as_mut() {
+        Some(pin) if pin.requested == requested => pin,
+        _ => {
+            session.model_pin = Some(ModelPin {
+                requested: requested.to_string(),
+                snapshot: resolved.to_string(),
+                last_resolved: resolved.to_string(),
+                pinned_at: now,
+            });
+            return None;
+        }
+    };
+
+    if pin.last_resolved == resolved {
+        return None;
+    }
+
+    let change = ModelChange {
+        requested: requested.to_string(),
+        pinned: pin.snapshot.clone(),
+        from: std::mem::replace(&mut pin.last_resolved, resolved.to_string()),
+        to: resolved.to_string(),
+        changed_at: now,
+    };
+    session.model_changes.push(change.clone());
+    Some(change)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::test_support;
+
+    fn session(model: &str) -> AgentSession {
+        AgentSession {
+            model: model.to_string(),
+            model_pin: initial_pin(model),
+            created_at: "2025-01-01T00:00:00Z".to_string(),
+            updated_at: "2025-01-01T00:00:00Z".to_string(),
+            ..test_support::session("s1", Vec::new())
+        }
+    }
+
+    #[test]
+    fn test_is_model_snapshot() {
+        assert!(is_model_snapshot("gpt-4o-2024-11-20"));
+        assert!(is_model_snapshot("claude-3-5-sonnet-20241022"));
+        assert!(!is_model_snapshot("gpt-4o"));
+        assert!(!is_model_snapshot("claude-sonnet-4-5"));
+        assert!(!is_model_snapshot("20241022"));
+    }
+
+    #[test]
+    fn test_observe_drift() {
+        let mut s = session("gpt-4o");
+        assert!(s.model_pin.is_none());
+
+        // 首轮响应固定快照
+        assert!(observe_resolved_model(&mut s, "gpt-4o", "gpt-4o-2024-08-06").is_none());
+        assert_eq!(s.model_pin.as_ref().unwrap().snapshot, "gpt-4o-2024-08-06");
+        assert!(observe_resolved_model(&mut s, "gpt-4o", "gpt-4o-2024-08-06").is_none());
+
+        // 同一请求模型解析到不同快照
+        let change = observe_resolved_model(&mut s, "gpt-4o", "gpt-4o-2024-11-20").unwrap();
+        assert_eq!(change.from, "gpt-4o-2024-08-06");
+        assert_eq!(change.to, "gpt-4o-2024-11-20");
+        assert_eq!(change.pinned, "gpt-4o-2024-08-06");
+        assert!(observe_resolved_model(&mut s, "gpt-4o", "gpt-4o-2024-11-20").is_none());
+        assert_eq!(s.model_changes.len(), 1);
+
+        // 主动切换模型时重新固定
+        assert!(observe_resolved_model(&mut s, "claude-sonnet-4-5", "claude-sonnet-4-5").is_none());
+        assert_eq!(
+            s.model_pin.as_ref().unwrap().snapshot,
+            "claude-sonnet-4-5".to_string()
+        );
+        assert_eq!(s.model_changes.len(), 1);
+    }
+
+    #[test]
+    fn test_snapshot_pinned_on_creation() {
+        let mut s = session("gpt-4o-2024-11-20");
+        assert_eq!(
+            s.model_pin.as_ref().unwrap().snapshot,
+            "gpt-4o-2024-11-20".to_string()
+        );
+        let change =
+            observe_resolved_model(&mut s, "gpt-4o-2024-11-20", "gpt-4o-2024-08-06").unwrap();
+        assert_eq!(change.pinned, "gpt-4o-2024-11-20");
+    }
+}
@@ -17,22 +17,213 @@
 
 #![allow(dead_code)]
 
+use crate::agent::attachments::{
+    prepare_message_with_attachments, split_native_documents, DEFAULT_ATTACHMENT_TOKEN_BUDGET,
+};
+use crate::agent::audio::resolve_audio;
+use crate::agent::context_fallback::{
+    compact_history, context_window, estimate_prompt_tokens, plan_fallback, usage_event,
+    ContextFallback,
+};
+use crate::agent::errors::{classify_provider_error, AgentError, AgentErrorCode};
+use crate::agent::followup::FollowupScheduler;
+use crate::agent::images::resolve_images;
+use crate::agent::jobs::JobManager;
+use crate::agent::memory::{
+    build_memory_prompt, extract_memory_candidates, MemoryStore, MAX_INJECTED_MEMORIES,
+};
+use crate::agent::model_pin::{self, ModelChange};
+use crate::agent::patch_review::PatchReviewQueue;
+use crate::agent::prompt_vars::expand_system_prompt;
 use crate::agent::protocols::{create_protocol, Protocol};
+use crate::agent::quick_ask;
+use crate::agent::scheduled_tasks::TaskScheduler;
+use crate::agent::script_review::{script_review_queue, ScriptReviewQueue};
+use crate::agent::session_bulk::{self, BulkProgress};
+use crate::agent::session_meta::{self, SessionMetaUpdate};
+use crate::agent::session_quota::{evaluate_quota, quota_level, QuotaLevel, SessionQuotaStatus};
+use crate::agent::skill_usage::{messages_since, SkillUsageStore};
+use crate::agent::tool_approval::{tool_approval_queue, ToolApprovalQueue, ToolPermission};
+use crate::agent::tool_loop::ToolCancellations;
 use crate::agent::tool_loop::{ToolCallResult, ToolLoopEngine, ToolLoopState};
-use crate::agent::tools::{create_default_registry, ToolRegistry};
+use crate::agent::tools::{
+    append_image_placeholders, create_default_registry, replace_history_images, BrowserHost,
+    CodeInterpreterTool, CommandTool, GitApplyPatchTool, GitDiffTool, GitLogTool, GitStatusTool,
+    HttpRequestTool, OcrTool, RecallMemoryTool, SaveMemoryTool, ScheduleFollowupTool,
+    SecurityManager, SkillScriptTool, SqlQueryTool, SqlSchemaTool, ToolRegistry, WasmPluginHost,
+};
+use crate::agent::transcript::TranscriptLogger;
 use crate::agent::types::*;
+use crate::config::{
+    AgentFallbackEndpoint, ContextFallbackConfig, ImageProcessingConfig, ProviderProfile,
+    ProviderProfilesConfig, SessionQuotaConfig, TranscriptConfig,
+};
+use crate::embeddings::{EmbeddingClient, VectorStore, DEFAULT_EMBEDDING_MODEL};
+use crate::knowledge::{retrieve_context, RETRIEVAL_TOP_K};
 use crate::models::openai::{
     ChatCompletionRequest, ChatCompletionResponse, ChatMessage, ContentPart as OpenAIContentPart,
     MessageContent as OpenAIMessageContent,
 };
+use crate::services::session_sync_service::is_newer;
+use crate::services::usage_recorder::{
+    UsageEvent, UsageRecorder, UsageSource, USAGE_SOURCE_HEADER,
+};
 use parking_lot::RwLock;
 use reqwest::Client;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
 
+/// 默认图片生成模型
+const DEFAULT_IMAGE_MODEL: &str = "dall-e-3";
+
+/// 获取图片保存目录（~/.proxycast/images）
+fn images_dir() -> Result<std::path::PathBuf, String> {
+    let home = dirs::home_dir().ok_or_else(|| "无法获取用户 home 目录".to_string())?;
+    let dir = home.join(".proxycast").join("images");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("创建图片目录失败: {}", e))?;
+    Ok(dir)
+}
+
+/// 主端点名称（记录在 served_by 中）
+const PRIMARY_ENDPOINT: &str = "primary";
+
+/// 请求端点（主端点或备用端点）
+struct Endpoint {
+    name: String,
+    base_url: String,
+    api_key: String,
+    protocol: Box<dyn Protocol>,
+    /// 覆盖模型名称
+    model: Option<String>,
+}
+
+/// 是否切换到下一个端点（限流或服务端错误）
+fn is_failover_status(status: u16) -> bool {
+    status == 429 || status >= 500
+}
+
+/// 流式请求失败后是否切换到下一个端点
+///
+/// 只有网络错误、超时和上游的限流/服务端错误才切换；
+/// 请求参数、附件处理、响应解析等错误换端点也无法恢复，直接返回
+fn is_failover_error(error: &AgentError) -> bool {
+    match error {
+        AgentError::Network(_) => true,
+        AgentError::Upstream { status, .. } => is_failover_status(*status),
+        AgentError::Stream(detail) => match detail.status {
+            Some(status) => is_failover_status(status),
+            None => matches!(
+                detail.code,
+                AgentErrorCode::Overloaded | AgentErrorCode::RateLimit
+            ),
+        },
+        _ => false,
+    }
+}
+
+/// 在系统提示词后追加长期记忆
+fn with_memory_prompt(base: Option<String>, memory_prompt: &str) -> String {
+    match base {
+        Some(prompt) => format!("{}\n\n{}", prompt, memory_prompt),
+        None => memory_prompt.to_string(),
+    }
+}
+
+/// 在系统提示词后追加本轮检索到的参考资料（只用于发送的请求，不写入会话）
+fn with_request_context(base: Option<String>, context: Option<&str>) -> Option<String> {
+    match context.filter(|c| !c.is_empty()) {
+        Some(context) => Some(with_memory_prompt(base, context)),
+        None => base,
+    }
+}
+
+/// 发送请求时使用的系统提示词
+///
+/// 只展开用户编写的系统提示词（会话提示词或默认提示词）中的变量，
+/// 之后追加的长期记忆和参考资料原样发送
+fn outgoing_system_prompt(
+    config: &AgentConfig,
+    session: Option<&AgentSession>,
+    context: Option<&str>,
+) -> Option<String> {
+    let cwd = session.and_then(|s| s.repo_path.as_deref());
+    let prompt = session
+        .and_then(|s| s.system_prompt.as_deref())
+        .or(config.system_prompt.as_deref())
+        .map(|prompt| expand_system_prompt(prompt, cwd));
+    let prompt = with_request_context(prompt, session.and_then(|s| s.memory_prompt.as_deref()));
+    with_request_context(prompt, context)
+}
+
+/// 附件处理结果（用户消息、附件元数据、原生文档）
+type ResolvedAttachments = (
+    String,
+    Option<Vec<AttachmentInfo>>,
+    Option<Vec<DocumentData>>,
+);
+
+/// 流式请求类型
+#[derive(Clone, Copy)]
+enum StreamCall<'a> {
+    /// 新一轮对话
+    Chat {
+        history: &'a [AgentMessage],
+        user_message: &'a str,
+        images: Option<&'a [ImageData]>,
+        audio: Option<&'a [AudioData]>,
+        documents: Option<&'a [DocumentData]>,
+    },
+    /// 工具调用后继续对话
+    Continue { messages: &'a [AgentMessage] },
+}
+
+/// 所有端点均失败的流式请求
+struct StreamFailure {
+    error: AgentError,
+    /// 尚未推送到前端的错误事件（输出任何内容之前失败时暂存）
+    event: Option<StreamEvent>,
+}
+
+impl StreamFailure {
+    /// 上游是否返回了上下文超长错误
+    fn is_context_overflow(&self) -> bool {
+        matches!(
+            &self.event,
+            Some(StreamEvent::Error { error: Some(detail), .. })
+                if detail.code == AgentErrorCode::ContextLengthExceeded
+        )
+    }
+
+    /// 推送暂存的错误事件，返回错误
+    async fn emit(self, tx: &mpsc::Sender<StreamEvent>) -> AgentError {
+        if let Some(event) = self.event {
+            let _ = tx.send(event).await;
+        }
+        self.error
+    }
+}
+
+/// 创建访问本地 API Server 的 HTTP 客户端
+///
+/// 请求带 `x-proxycast-source: native_agent`，用量由 Agent 自行上报，API Server 不重复记录
+fn build_http_client(base_url: &str) -> Result<Client, String> {
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        USAGE_SOURCE_HEADER,
+        reqwest::header::HeaderValue::from_static(UsageSource::NativeAgent.as_str()),
+    );
+    crate::server::tls::local_client_builder(base_url)
+        .default_headers(headers)
+        .timeout(Duration::from_secs(300))
+        .connect_timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))
+}
+
 /// 原生 Agent 实现
 pub struct NativeAgent {
     client: Client,
@@ -44,6 +235,18 @@ pub struct NativeAgent {
     provider_type: ProviderType,
     /// 协议处理器
     protocol: Box<dyn Protocol>,
+    /// 图片预处理配置
+    image_options: ImageProcessingConfig,
+    /// 备用端点（主端点失败时按顺序故障转移）
+    fallbacks: Vec<AgentFallbackEndpoint>,
+    /// 会话记录器
+    transcripts: TranscriptLogger,
+    /// 上下文超限回退配置
+    context_fallback: ContextFallbackConfig,
+    /// 统一用量记录（未设置时不上报）
+    usage_recorder: Option<Arc<UsageRecorder>>,
+    /// 提供 `ocr` 工具时是否仍发送图片（见 [`OcrConfig::send_images`](crate::config::OcrConfig)）
+    ocr_send_images: bool,
 }
 
 impl NativeAgent {
@@ -52,12 +255,7 @@ impl NativeAgent {
         api_key: String,
         provider_type: ProviderType,
     ) -> Result<Self, String> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(300))
-            .connect_timeout(Duration::from_secs(30))
-            .no_proxy()
-            .build()
-            .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+        let client = build_http_client(&base_url)?;
 
         let protocol = create_protocol(provider_type);
 
@@ -76,6 +274,12 @@ impl NativeAgent {
             config: AgentConfig::default(),
             provider_type,
             protocol,
+            image_options: ImageProcessingConfig::default(),
+            fallbacks: Vec::new(),
+            transcripts: TranscriptLogger::default(),
+            context_fallback: ContextFallbackConfig::default(),
+            usage_recorder: None,
+            ocr_send_images: false,
         })
     }
 
@@ -89,11 +293,47 @@ impl NativeAgent {
         self
     }
 
+    /// 改用 Provider 配置档案的端点和生成参数（档案未设置的参数沿用当前值）
+    fn apply_profile(&mut self, profile: &ProviderProfile) {
+        self.base_url = profile.base_url.trim_end_matches('/').to_string();
+        self.api_key = profile.api_key.clone();
+        self.provider_type = ProviderType::from_str(&profile.provider);
+        self.protocol = create_protocol(self.provider_type);
+        if let Some(model) = &profile.default_model {
+            self.config.model = model.clone();
+        }
+        if profile.temperature.is_some() {
+            self.config.temperature = profile.temperature;
+        }
+        if profile.max_tokens.is_some() {
+            self.config.max_tokens = profile.max_tokens;
+        }
+        if profile.top_p.is_some() {
+            self.config.top_p = profile.top_p;
+        }
+    }
+
     /// 发送聊天请求（非流式，用于简单场景）
-    pub async fn chat(&self, request: NativeChatRequest) -> Result<NativeChatResponse, String> {
+    #[tracing::instrument(
+        name = "agent.chat",
+        skip_all,
+        fields(
+            model = %request.model.as_deref().unwrap_or(&self.config.model),
+            provider = ?self.provider_type,
+            session_id = ?request.session_id,
+        )
+    )]
+    pub async fn chat(&self, request: NativeChatRequest) -> Result<NativeChatResponse, AgentError> {
+        let started = Instant::now();
         let model = request.model.unwrap_or_else(|| self.config.model.clone());
         let session_id = request.session_id.clone();
-        let has_images = request.images.as_ref().map(|i| i.len()).unwrap_or(0);
+        let images = resolve_images(request.images.as_deref(), &self.image_options)
+            .map_err(AgentError::InvalidRequest)?;
+        let has_images = images.as_ref().map(|i| i.len()).unwrap_or(0);
+        let audio = resolve_audio(request.audio.as_deref()).map_err(AgentError::InvalidRequest)?;
+        let (user_message, attachments, documents) = self
+            .resolve_attachments(&request.message, request.attachments.as_deref(), &model)
+            .map_err(AgentError::InvalidRequest)?;
 
         info!(
             "[NativeAgent] 发送聊天请求: model={}, session={:?}, images={}",
@@ -107,54 +347,100 @@ impl NativeAgent {
             None
         };
 
+        let system_prompt =
+            outgoing_system_prompt(&self.config, session.as_ref(), request.context.as_deref());
+        let fallback = self.plan_context_fallback(
+            &model,
+            system_prompt.as_deref(),
+            session
+                .as_ref()
+                .map(|s| s.messages.as_slice())
+                .unwrap_or(&[]),
+            &user_message,
+        );
+        let model = fallback.as_ref().map_or(model, |f| f.to.clone());
+
         // 构建消息
         let messages = self.build_openai_messages(
             session.as_ref(),
-            &request.message,
-            request.images.as_deref(),
+            system_prompt.as_deref(),
+            &user_message,
+            images.as_deref(),
+            audio.as_deref(),
+            documents.as_deref(),
         );
 
-        let chat_request = ChatCompletionRequest {
+        let mut chat_request = ChatCompletionRequest {
             model: model.clone(),
             messages,
             stream: false,
             temperature: self.config.temperature,
             max_tokens: self.config.max_tokens,
-            top_p: None,
+            top_p: self.config.top_p,
             tools: None,
             tool_choice: None,
             reasoning_effort: None,
+            stream_options: None,
         };
 
-        let url = format!("{}/v1/chat/completions", self.base_url);
-
-        let response = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&chat_request)
-            .send()
-            .await
-            .map_err(|e| format!("请求失败: {}", e))?;
+        let mut response = self.post_chat_completion(&chat_request).await;
 
-        let status = response.status();
-        if !status.is_success() {
-            let body = response.text().await.unwrap_or_default();
-            error!("[NativeAgent] 请求失败: {} - {}", status, body);
-            return Ok(NativeChatResponse {
-                content: String::new(),
-                model,
-                usage: None,
-                success: false,
-                error: Some(format!("API 错误 ({}): {}", status, body)),
-            });
+        // 上游返回上下文超长错误时丢弃最早的对话轮次并重试一次（只压缩本次发送的历史）
+        let overflow = matches!(
+            &response,
+            Err(AgentError::Upstream { status, body })
+                if classify_provider_error(Some(*status), body).code
+                    == AgentErrorCode::ContextLengthExceeded
+        );
+        let compacted = match &session {
+            Some(session) if overflow && self.context_fallback.compact_on_overflow => {
+                compact_history(&session.messages, true)
+                    .map(|(messages, removed)| (session, messages, removed))
+            }
+            _ => None,
+        };
+        if let Some((session, messages, removed)) = compacted {
+            let compacted_session = AgentSession {
+                messages,
+                ..session.clone()
+            };
+            warn!(
+                "[NativeAgent] 上游返回上下文超长错误，丢弃最早的 {} 条消息后重试: session={:?}, 剩余约 {} tokens",
+                removed,
+                session_id,
+                estimate_prompt_tokens(
+                    system_prompt.as_deref(),
+                    &compacted_session.messages,
+                    &user_message
+                )
+            );
+            chat_request.messages = self.build_openai_messages(
+                Some(&compacted_session),
+                system_prompt.as_deref(),
+                &user_message,
+                images.as_deref(),
+                audio.as_deref(),
+                documents.as_deref(),
+            );
+            response = self.post_chat_completion(&chat_request).await;
         }
 
-        let body: ChatCompletionResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("解析响应失败: {}", e))?;
+        let (body, served_by) = match response {
+            Ok(served) => served,
+            Err(AgentError::Upstream { status, body }) => {
+                return Ok(NativeChatResponse {
+                    content: String::new(),
+                    model,
+                    usage: None,
+                    success: false,
+                    error: Some(format!("API 错误 ({}): {}", status, body)),
+                    error_detail: Some(classify_provider_error(Some(status), &body)),
+                    served_by: None,
+                    context_fallback: fallback,
+                });
+            }
+            Err(e) => return Err(e),
+        };
 
         let content = body
             .choices
@@ -166,20 +452,29 @@ impl NativeAgent {
             input_tokens: body.usage.prompt_tokens,
             output_tokens: body.usage.completion_tokens,
         });
+        self.record_usage(session_id.as_deref(), &body.model, usage.as_ref(), started);
 
         // 更新会话历史
         if let Some(sid) = session_id {
+            if fallback.is_none() {
+                self.track_resolved_model(&sid, &model, Some(&body.model));
+            }
             self.add_message_to_session(
                 &sid,
                 "user",
-                MessageContent::Text(request.message.clone()),
-                request.images.as_deref(),
+                MessageContent::Text(user_message),
+                images.as_deref(),
+                audio.as_deref(),
+                documents.as_deref(),
+                attachments,
+                &model,
             );
-            self.add_message_to_session(
+            self.add_assistant_message_to_session(
                 &sid,
-                "assistant",
                 MessageContent::Text(content.clone()),
                 None,
+                &body.model,
+                usage.as_ref(),
             );
         }
 
@@ -191,23 +486,41 @@ impl NativeAgent {
             usage,
             success: true,
             error: None,
+            error_detail: None,
+            served_by: Some(served_by),
+            context_fallback: fallback,
         })
     }
 
     /// 流式聊天（使用协议策略模式）
     ///
     /// Requirements: 1.1, 1.3, 1.4
+    #[tracing::instrument(
+        name = "agent.chat_stream",
+        skip_all,
+        fields(
+            model = %request.model.as_deref().unwrap_or(&self.config.model),
+            provider = ?self.provider_type,
+            session_id = ?request.session_id,
+        )
+    )]
     pub async fn chat_stream(
         &self,
         request: NativeChatRequest,
         tools: Option<&[crate::models::openai::Tool]>,
         tx: mpsc::Sender<StreamEvent>,
-    ) -> Result<StreamResult, String> {
+    ) -> Result<StreamResult, AgentError> {
         let model = request
             .model
             .clone()
             .unwrap_or_else(|| self.config.model.clone());
         let session_id = request.session_id.clone();
+        let images = resolve_images(request.images.as_deref(), &self.image_options)
+            .map_err(AgentError::InvalidRequest)?;
+        let audio = resolve_audio(request.audio.as_deref()).map_err(AgentError::InvalidRequest)?;
+        let (user_message, attachments, documents) = self
+            .resolve_attachments(&request.message, request.attachments.as_deref(), &model)
+            .map_err(AgentError::InvalidRequest)?;
 
         info!(
             "[NativeAgent] 发送流式聊天请求: model={}, session={:?}, provider={:?}, tools_count={}",
@@ -230,45 +543,83 @@ impl NativeAgent {
             .map(|s| s.messages.clone())
             .unwrap_or_default();
 
-        let config = if let Some(ref sess) = session {
-            let mut cfg = self.config.clone();
-            if sess.system_prompt.is_some() {
-                cfg.system_prompt = sess.system_prompt.clone();
-            }
-            cfg
-        } else {
-            self.config.clone()
-        };
-
-        // 使用协议策略发送请求
-        let result = self
-            .protocol
-            .chat_stream(
-                &self.client,
-                &self.base_url,
-                &self.api_key,
-                &history,
-                &request.message,
-                request.images.as_deref(),
+        let mut config = self.config.clone();
+        config.system_prompt =
+            outgoing_system_prompt(&self.config, session.as_ref(), request.context.as_deref());
+
+        let fallback = self.plan_context_fallback(
+            &model,
+            config.system_prompt.as_deref(),
+            &history,
+            &user_message,
+        );
+        let model = fallback.as_ref().map_or(model, |f| f.to.clone());
+        let fell_back = fallback.is_some();
+        if let Some(fallback) = &fallback {
+            let _ = tx.send(fallback.clone().into_event()).await;
+        }
+        if let Some(usage) = self.context_usage(
+            &model,
+            config.system_prompt.as_deref(),
+            &history,
+            &user_message,
+        ) {
+            let _ = tx.send(usage).await;
+        }
+
+        // 使用协议策略发送请求（失败时故障转移到备用端点）
+        let call = StreamCall::Chat {
+            history: &history,
+            user_message: &user_message,
+            images: images.as_deref(),
+            audio: audio.as_deref(),
+            documents: documents.as_deref(),
+        };
+        let started = Instant::now();
+        let mut result = self
+            .stream_with_recovery(
+                call,
+                session_id.as_deref(),
                 &model,
                 &config,
                 tools,
-                tx,
+                tx.clone(),
             )
             .await?;
+        result.context_fallback = fallback;
+        self.record_usage(
+            session_id.as_deref(),
+            result.model.as_deref().unwrap_or(&model),
+            result.usage.as_ref(),
+            started,
+        );
 
         // 更新会话历史
         if let Some(sid) = &session_id {
+            let change = if fell_back {
+                None
+            } else {
+                self.track_resolved_model(sid, &model, result.model.as_deref())
+            };
+            if let Some(change) = change {
+                let _ = tx.send(change.into_event()).await;
+            }
             self.add_message_to_session(
                 sid,
                 "user",
-                MessageContent::Text(request.message.clone()),
-                request.images.as_deref(),
+                MessageContent::Text(user_message),
+                images.as_deref(),
+                audio.as_deref(),
+                documents.as_deref(),
+                attachments,
+                &model,
             );
             self.add_assistant_message_to_session(
                 sid,
                 MessageContent::Text(result.content.clone()),
                 result.tool_calls.clone(),
+                result.model.as_deref().unwrap_or(&model),
+                result.usage.as_ref(),
             );
         }
 
@@ -283,7 +634,41 @@ impl NativeAgent {
         request: NativeChatRequest,
         tx: mpsc::Sender<StreamEvent>,
         tool_loop_engine: &ToolLoopEngine,
-    ) -> Result<StreamResult, String> {
+    ) -> Result<StreamResult, AgentError> {
+        self.stream_with_tools(request, false, tx, tool_loop_engine)
+            .await
+    }
+
+    /// 以会话现有历史继续流式生成（用于重新生成），同样执行工具调用循环
+    pub async fn continue_stream_with_tools(
+        &self,
+        session_id: &str,
+        model: Option<String>,
+        tx: mpsc::Sender<StreamEvent>,
+        tool_loop_engine: &ToolLoopEngine,
+    ) -> Result<StreamResult, AgentError> {
+        let request = NativeChatRequest {
+            session_id: Some(session_id.to_string()),
+            message: String::new(),
+            model,
+            images: None,
+            audio: None,
+            attachments: None,
+            context: None,
+            stream: true,
+        };
+        self.stream_with_tools(request, true, tx, tool_loop_engine)
+            .await
+    }
+
+    /// 发送首次请求（`continue_history` 为 true 时不追加新的用户消息），然后执行工具调用循环
+    async fn stream_with_tools(
+        &self,
+        request: NativeChatRequest,
+        continue_history: bool,
+        tx: mpsc::Sender<StreamEvent>,
+        tool_loop_engine: &ToolLoopEngine,
+    ) -> Result<StreamResult, AgentError> {
         let session_id = request.session_id.clone();
         let mut state = ToolLoopState::new();
 
@@ -296,9 +681,13 @@ impl NativeAgent {
         };
 
         // 首次请求
-        let mut current_result = self
-            .chat_stream(request.clone(), tools_ref, tx.clone())
-            .await?;
+        let mut current_result = if continue_history {
+            self.chat_stream_continue(request.clone(), None, tools_ref, tx.clone())
+                .await?
+        } else {
+            self.chat_stream(request.clone(), tools_ref, tx.clone())
+                .await?
+        };
 
         // 工具调用循环
         // Requirements: 7.3 - THE Tool_Loop SHALL continue until the Agent produces a final response without tool_calls
@@ -326,17 +715,21 @@ impl NativeAgent {
                 }
             }
 
-            // 继续对话
+            // 继续对话（本轮已回退到更长上下文的模型时沿用该模型，不重复通知）
             let continue_request = NativeChatRequest {
                 session_id: session_id.clone(),
                 message: String::new(),
                 model: request.model.clone(),
                 images: None,
+                audio: None,
+                attachments: None,
+                context: request.context.clone(),
                 stream: true,
             };
 
+            let fallback = current_result.context_fallback.take();
             current_result = self
-                .chat_stream_continue(continue_request, tools_ref, tx.clone())
+                .chat_stream_continue(continue_request, fallback, tools_ref, tx.clone())
                 .await?;
         }
 
@@ -352,6 +745,7 @@ impl NativeAgent {
                         "达到最大工具调用迭代次数限制 ({})",
                         tool_loop_engine.max_iterations()
                     ),
+                    error: None,
                 })
                 .await;
         }
@@ -367,6 +761,7 @@ impl NativeAgent {
         let _ = tx
             .send(StreamEvent::FinalDone {
                 usage: current_result.usage.clone(),
+                served_by: current_result.served_by.clone(),
             })
             .await;
 
@@ -374,14 +769,29 @@ impl NativeAgent {
     }
 
     /// 继续流式对话（使用会话历史）
+    ///
+    /// `fallback` 为本轮之前的请求已经做出的上下文回退，传入时直接使用回退后的模型
+    #[tracing::instrument(
+        name = "agent.chat_stream_continue",
+        skip_all,
+        fields(
+            model = %request.model.as_deref().unwrap_or(&self.config.model),
+            provider = ?self.provider_type,
+            session_id = ?request.session_id,
+        )
+    )]
     async fn chat_stream_continue(
         &self,
         request: NativeChatRequest,
+        fallback: Option<ContextFallback>,
         tools: Option<&[crate::models::openai::Tool]>,
         tx: mpsc::Sender<StreamEvent>,
-    ) -> Result<StreamResult, String> {
+    ) -> Result<StreamResult, AgentError> {
         let model = request.model.unwrap_or_else(|| self.config.model.clone());
-        let session_id = request.session_id.as_ref().ok_or("需要 session_id")?;
+        let session_id = request
+            .session_id
+            .as_ref()
+            .ok_or_else(|| AgentError::InvalidRequest("需要 session_id".to_string()))?;
 
         debug!(
             "[NativeAgent] 继续流式对话: model={}, session={}, tools_count={}",
@@ -396,127 +806,829 @@ impl NativeAgent {
             .read()
             .get(session_id)
             .cloned()
-            .ok_or_else(|| format!("会话不存在: {}", session_id))?;
+            .ok_or_else(|| AgentError::SessionNotFound(session_id.clone()))?;
 
         // 获取配置
-        let config = {
-            let mut cfg = self.config.clone();
-            if session.system_prompt.is_some() {
-                cfg.system_prompt = session.system_prompt.clone();
-            }
-            cfg
-        };
-
-        // 使用协议策略继续对话
-        let result = self
-            .protocol
-            .chat_stream_continue(
-                &self.client,
-                &self.base_url,
-                &self.api_key,
-                &session.messages,
-                &model,
-                &config,
-                tools,
-                tx,
-            )
+        let mut config = self.config.clone();
+        config.system_prompt =
+            outgoing_system_prompt(&self.config, Some(&session), request.context.as_deref());
+
+        let fallback = match fallback {
+            Some(fallback) => Some(fallback),
+            None => {
+                let fallback = self.plan_context_fallback(
+                    &model,
+                    config.system_prompt.as_deref(),
+                    &session.messages,
+                    "",
+                );
+                if let Some(fallback) = &fallback {
+                    let _ = tx.send(fallback.clone().into_event()).await;
+                }
+                fallback
+            }
+        };
+        let model = fallback.as_ref().map_or(model, |f| f.to.clone());
+        let fell_back = fallback.is_some();
+        if let Some(usage) = self.context_usage(
+            &model,
+            config.system_prompt.as_deref(),
+            &session.messages,
+            "",
+        ) {
+            let _ = tx.send(usage).await;
+        }
+
+        // 使用协议策略继续对话（失败时故障转移到备用端点）
+        let call = StreamCall::Continue {
+            messages: &session.messages,
+        };
+        let started = Instant::now();
+        let mut result = self
+            .stream_with_recovery(call, Some(session_id), &model, &config, tools, tx.clone())
             .await?;
+        result.context_fallback = fallback;
+        self.record_usage(
+            Some(session_id),
+            result.model.as_deref().unwrap_or(&model),
+            result.usage.as_ref(),
+            started,
+        );
 
         // 更新会话历史
+        let change = if fell_back {
+            None
+        } else {
+            self.track_resolved_model(session_id, &model, result.model.as_deref())
+        };
+        if let Some(change) = change {
+            let _ = tx.send(change.into_event()).await;
+        }
         self.add_assistant_message_to_session(
             session_id,
             MessageContent::Text(result.content.clone()),
             result.tool_calls.clone(),
+            result.model.as_deref().unwrap_or(&model),
+            result.usage.as_ref(),
         );
 
         Ok(result)
     }
 
-    // ==================== 会话管理方法 ====================
+    /// 单次补全（不读写会话历史，用于摘要、标题等后台任务）
+    pub async fn complete(
+        &self,
+        system_prompt: &str,
+        prompt: &str,
+        model: Option<String>,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+    ) -> Result<String, String> {
+        let model = model.unwrap_or_else(|| self.config.model.clone());
+        let chat_request = ChatCompletionRequest {
+            model: model.clone(),
+            messages: vec![
+                ChatMessage {
+                    role: "system".to_string(),
+                    content: Some(OpenAIMessageContent::Text(system_prompt.to_string())),
+                    tool_calls: None,
+                    tool_call_id: None,
+                },
+                ChatMessage {
+                    role: "user".to_string(),
+                    content: Some(OpenAIMessageContent::Text(prompt.to_string())),
+                    tool_calls: None,
+                    tool_call_id: None,
+                },
+            ],
+            stream: false,
+            temperature,
+            max_tokens,
+            top_p: None,
+            tools: None,
+            tool_choice: None,
+            reasoning_effort: None,
+            stream_options: None,
+        };
 
-    /// 构建 OpenAI 格式消息（用于非流式请求）
-    fn build_openai_messages(
+        let started = Instant::now();
+        let (body, _) = self.post_chat_completion(&chat_request).await?;
+        self.record_usage(
+            None,
+            &body.model,
+            Some(&TokenUsage {
+                input_tokens: body.usage.prompt_tokens,
+                output_tokens: body.usage.completion_tokens,
+            }),
+            started,
+        );
+        debug!("[NativeAgent] 补全完成: model={}", model);
+        Ok(body
+            .choices
+            .first()
+            .and_then(|c| c.message.content.clone())
+            .unwrap_or_default())
+    }
+
+    /// 预览本轮请求：使用与 `chat_stream` 相同的协议构建请求体（系统提示词、历史、本次输入、
+    /// 图片和附件、工具定义），不发送也不修改会话
+    ///
+    /// `memory_prompt` 为会话首轮时将注入系统提示词的长期记忆
+    pub fn preview_request(
         &self,
-        session: Option<&AgentSession>,
-        user_message: &str,
-        images: Option<&[ImageData]>,
-    ) -> Vec<ChatMessage> {
-        let mut messages = Vec::new();
+        request: &NativeChatRequest,
+        tools: Option<&[crate::models::openai::Tool]>,
+        memory_prompt: Option<&str>,
+    ) -> Result<RequestPreview, AgentError> {
+        let model = request
+            .model
+            .clone()
+            .unwrap_or_else(|| self.config.model.clone());
+        let mut session = match &request.session_id {
+            Some(sid) => Some(
+                self.get_session(sid)
+                    .ok_or_else(|| AgentError::SessionNotFound(sid.clone()))?,
+            ),
+            None => None,
+        };
+        if let (Some(session), Some(memory_prompt)) = (session.as_mut(), memory_prompt) {
+            session.memory_prompt = Some(memory_prompt.to_string());
+        }
 
-        // 系统提示词
-        let system_prompt = session
-            .and_then(|s| s.system_prompt.as_ref())
-            .or(self.config.system_prompt.as_ref());
+        let images = resolve_images(request.images.as_deref(), &self.image_options)
+            .map_err(AgentError::InvalidRequest)?;
+        let audio = resolve_audio(request.audio.as_deref()).map_err(AgentError::InvalidRequest)?;
+        let (user_message, _, documents) = self
+            .resolve_attachments(&request.message, request.attachments.as_deref(), &model)
+            .map_err(AgentError::InvalidRequest)?;
+
+        let mut config = self.config.clone();
+        config.system_prompt =
+            outgoing_system_prompt(&self.config, session.as_ref(), request.context.as_deref());
+        let history = session
+            .as_ref()
+            .map(|s| s.messages.as_slice())
+            .unwrap_or(&[]);
+        let system_prompt = config.system_prompt.as_deref();
+        let estimated_tokens = estimate_prompt_tokens(system_prompt, history, &user_message);
+        let fallback = self.plan_context_fallback(&model, system_prompt, history, &user_message);
+        let window = context_window(&model, &self.context_fallback.context_windows);
+        let model = fallback.as_ref().map_or(model, |f| f.to.clone());
+
+        let tools = tools.filter(|t| !t.is_empty());
+        Ok(RequestPreview {
+            endpoint: self.protocol.endpoint().to_string(),
+            request: self.protocol.build_request(
+                history,
+                &user_message,
+                images.as_deref(),
+                audio.as_deref(),
+                documents.as_deref(),
+                &model,
+                &config,
+                tools,
+            ),
+            estimated_tokens,
+            context_window: window,
+            context_fallback: fallback,
+        })
+    }
+
+    /// 回放会话中的某一轮用户消息
+    ///
+    /// 使用该轮之前的历史在沙盒中重新请求（可覆盖模型、温度和系统提示词），
+    /// 不修改原会话历史，也不执行工具调用。
+    pub async fn replay_turn(
+        &self,
+        session_id: &str,
+        turn_id: usize,
+        overrides: ReplayOverrides,
+    ) -> Result<ReplayResult, AgentError> {
+        let session = self
+            .get_session(session_id)
+            .ok_or_else(|| AgentError::SessionNotFound(session_id.to_string()))?;
+        let (user_index, original) = locate_turn(&session.messages, turn_id)
+            .ok_or_else(|| AgentError::InvalidRequest(format!("对话轮次不存在: {}", turn_id)))?;
+
+        let model = overrides
+            .model
+            .clone()
+            .unwrap_or_else(|| session.model.clone());
+        let system_prompt = match overrides.system_prompt {
+            Some(prompt) => Some(expand_system_prompt(&prompt, session.repo_path.as_deref())),
+            None => outgoing_system_prompt(&self.config, Some(&session), None),
+        };
+
+        let mut messages = Vec::new();
         if let Some(prompt) = system_prompt {
             messages.push(ChatMessage {
                 role: "system".to_string(),
-                content: Some(OpenAIMessageContent::Text(prompt.clone())),
+                content: Some(OpenAIMessageContent::Text(prompt)),
                 tool_calls: None,
                 tool_call_id: None,
             });
         }
+        messages.extend(
+            session.messages[..=user_index]
+                .iter()
+                .map(|msg| self.convert_to_chat_message(msg)),
+        );
 
-        // 历史消息
-        if let Some(sess) = session {
-            for msg in &sess.messages {
-                messages.push(self.convert_to_chat_message(msg));
-            }
-        }
+        info!(
+            "[NativeAgent] 回放对话轮次: session={}, turn={}, model={}",
+            session_id, turn_id, model
+        );
 
-        // 用户消息
-        let user_msg = if let Some(imgs) = images {
-            let mut parts = vec![OpenAIContentPart::Text {
-                text: user_message.to_string(),
-            }];
-            for img in imgs {
-                parts.push(OpenAIContentPart::ImageUrl {
-                    image_url: crate::models::openai::ImageUrl {
-                        url: format!("data:{};base64,{}", img.media_type, img.data),
-                        detail: None,
-                    },
-                });
-            }
-            ChatMessage {
-                role: "user".to_string(),
-                content: Some(OpenAIMessageContent::Parts(parts)),
-                tool_calls: None,
-                tool_call_id: None,
-            }
-        } else {
-            ChatMessage {
-                role: "user".to_string(),
-                content: Some(OpenAIMessageContent::Text(user_message.to_string())),
-                tool_calls: None,
-                tool_call_id: None,
-            }
+        let chat_request = ChatCompletionRequest {
+            model: model.clone(),
+            messages,
+            stream: false,
+            temperature: overrides.temperature.or(self.config.temperature),
+            max_tokens: self.config.max_tokens,
+            top_p: self.config.top_p,
+            tools: None,
+            tool_choice: None,
+            reasoning_effort: None,
+            stream_options: None,
+        };
+        let started = Instant::now();
+        let (body, served_by) = self.post_chat_completion(&chat_request).await?;
+        let usage = TokenUsage {
+            input_tokens: body.usage.prompt_tokens,
+            output_tokens: body.usage.completion_tokens,
         };
+        self.record_usage(Some(session_id), &body.model, Some(&usage), started);
 
-        messages.push(user_msg);
-        messages
+        Ok(ReplayResult {
+            session_id: session_id.to_string(),
+            turn_id,
+            model,
+            original,
+            content: body
+                .choices
+                .first()
+                .and_then(|c| c.message.content.clone())
+                .unwrap_or_default(),
+            usage: Some(usage),
+            served_by: Some(served_by),
+        })
     }
 
-    /// 将 AgentMessage 转换为 OpenAI ChatMessage
-    fn convert_to_chat_message(&self, msg: &AgentMessage) -> ChatMessage {
-        let content = match &msg.content {
-            MessageContent::Text(text) => Some(OpenAIMessageContent::Text(text.clone())),
-            MessageContent::Parts(parts) => {
-                let openai_parts: Vec<OpenAIContentPart> = parts
-                    .iter()
-                    .map(|p| match p {
-                        ContentPart::Text { text } => {
-                            OpenAIContentPart::Text { text: text.clone() }
-                        }
-                        ContentPart::ImageUrl { image_url } => OpenAIContentPart::ImageUrl {
-                            image_url: crate::models::openai::ImageUrl {
-                                url: image_url.url.clone(),
-                                detail: image_url.detail.clone(),
-                            },
-                        },
-                    })
-                    .collect();
-                Some(OpenAIMessageContent::Parts(openai_parts))
-            }
-        };
+    /// 主端点和备用端点（按故障转移顺序）
+    fn endpoints(&self) -> Vec<Endpoint> {
+        let mut endpoints = vec![Endpoint {
+            name: PRIMARY_ENDPOINT.to_string(),
+            base_url: self.base_url.clone(),
+            api_key: self.api_key.clone(),
+            protocol: create_protocol(self.provider_type),
+            model: None,
+        }];
+        endpoints.extend(self.fallbacks.iter().map(|fallback| Endpoint {
+            name: fallback.name.clone(),
+            base_url: fallback.base_url.trim_end_matches('/').to_string(),
+            api_key: fallback.api_key.clone(),
+            protocol: create_protocol(ProviderType::from_str(&fallback.provider)),
+            model: fallback.model.clone(),
+        }));
+        endpoints
+    }
+
+    /// 发送流式请求；上游返回上下文超长错误时丢弃最早的对话轮次并重试一次
+    ///
+    /// 只压缩本次发送的历史，会话中保存的历史保持不变；推送 `context_compacted` 事件，
+    /// 重试仍失败时才推送错误
+    async fn stream_with_recovery(
+        &self,
+        call: StreamCall<'_>,
+        session_id: Option<&str>,
+        model: &str,
+        config: &AgentConfig,
+        tools: Option<&[crate::models::openai::Tool]>,
+        tx: mpsc::Sender<StreamEvent>,
+    ) -> Result<StreamResult, AgentError> {
+        // 提供 ocr 工具时图片以占位文本代替，由模型按需调用工具识别
+        let omit_images = !self.ocr_send_images
+            && tools.is_some_and(|tools| {
+                tools.iter().any(|tool| {
+                    matches!(tool, crate::models::openai::Tool::Function { function } if function.name == "ocr")
+                })
+            });
+        let without_images;
+        let placeholder_message;
+        let call = match call {
+            StreamCall::Chat {
+                history,
+                user_message,
+                images,
+                audio,
+                documents,
+            } if omit_images => {
+                let count = images.map_or(0, |images| images.len());
+                without_images = replace_history_images(history, count);
+                placeholder_message = append_image_placeholders(user_message, count);
+                StreamCall::Chat {
+                    history: &without_images,
+                    user_message: &placeholder_message,
+                    images: None,
+                    audio,
+                    documents,
+                }
+            }
+            StreamCall::Continue { messages } if omit_images => {
+                without_images = replace_history_images(messages, 0);
+                StreamCall::Continue {
+                    messages: &without_images,
+                }
+            }
+            call => call,
+        };
+
+        let failure = match self
+            .stream_with_failover(call, model, config, tools, tx.clone())
+            .await
+        {
+            Ok(result) => return Ok(result),
+            Err(failure) => failure,
+        };
+
+        let (history, pending) = match call {
+            StreamCall::Chat {
+                history,
+                user_message,
+                ..
+            } => (history, Some(user_message)),
+            StreamCall::Continue { messages } => (messages, None),
+        };
+        let compacted =
+            if failure.is_context_overflow() && self.context_fallback.compact_on_overflow {
+                compact_history(history, pending.is_some())
+            } else {
+                None
+            };
+        let Some((compacted, removed)) = compacted else {
+            return Err(failure.emit(&tx).await);
+        };
+        let estimated_tokens = estimate_prompt_tokens(
+            config.system_prompt.as_deref(),
+            &compacted,
+            pending.unwrap_or_default(),
+        );
+        warn!(
+            "[NativeAgent] 上游返回上下文超长错误，丢弃最早的 {} 条消息后重试: session={:?}, 剩余约 {} tokens",
+            removed, session_id, estimated_tokens
+        );
+        let _ = tx
+            .send(StreamEvent::ContextCompacted {
+                removed_messages: removed as u32,
+                estimated_tokens,
+            })
+            .await;
+
+        let call = match call {
+            StreamCall::Chat {
+                user_message,
+                images,
+                audio,
+                documents,
+                ..
+            } => StreamCall::Chat {
+                history: &compacted,
+                user_message,
+                images,
+                audio,
+                documents,
+            },
+            StreamCall::Continue { .. } => StreamCall::Continue {
+                messages: &compacted,
+            },
+        };
+        match self
+            .stream_with_failover(call, model, config, tools, tx.clone())
+            .await
+        {
+            Ok(result) => Ok(result),
+            Err(failure) => Err(failure.emit(&tx).await),
+        }
+    }
+
+    /// 按顺序向主端点和备用端点发送流式请求
+    ///
+    /// 端点返回 429/5xx、网络错误或超时（见 [`is_failover_error`]），
+    /// 且尚未向前端输出任何事件时，将同一请求转发到下一个端点；结果的 `served_by` 记录实际处理请求的端点。
+    /// 所有端点均失败时，尚未推送的错误事件随 [`StreamFailure`] 返回，由调用方推送
+    async fn stream_with_failover(
+        &self,
+        call: StreamCall<'_>,
+        model: &str,
+        config: &AgentConfig,
+        tools: Option<&[crate::models::openai::Tool]>,
+        tx: mpsc::Sender<StreamEvent>,
+    ) -> Result<StreamResult, StreamFailure> {
+        let endpoints = self.endpoints();
+        let last = endpoints.len() - 1;
+
+        for (index, endpoint) in endpoints.iter().enumerate() {
+            let model = endpoint.model.as_deref().unwrap_or(model);
+            let (attempt_tx, mut attempt_rx) = mpsc::channel::<StreamEvent>(100);
+
+            let request = async {
+                match call {
+                    StreamCall::Chat {
+                        history,
+                        user_message,
+                        images,
+                        audio,
+                        documents,
+                    } => {
+                        endpoint
+                            .protocol
+                            .chat_stream(
+                                &self.client,
+                                &endpoint.base_url,
+                                &endpoint.api_key,
+                                history,
+                                user_message,
+                                images,
+                                audio,
+                                documents,
+                                model,
+                                config,
+                                tools,
+                                attempt_tx,
+                            )
+                            .await
+                    }
+                    StreamCall::Continue { messages } => {
+                        endpoint
+                            .protocol
+                            .chat_stream_continue(
+                                &self.client,
+                                &endpoint.base_url,
+                                &endpoint.api_key,
+                                messages,
+                                model,
+                                config,
+                                tools,
+                                attempt_tx,
+                            )
+                            .await
+                    }
+                }
+            };
+
+            // 转发事件；首个事件之前的错误先暂存，用于判断是否切换端点
+            let forward = async {
+                let mut forwarded = false;
+                let mut held_error = None;
+                while let Some(event) = attempt_rx.recv().await {
+                    if !forwarded
+                        && held_error.is_none()
+                        && matches!(event, StreamEvent::Error { .. })
+                    {
+                        held_error = Some(event);
+                        continue;
+                    }
+                    forwarded = true;
+                    let _ = tx.send(event).await;
+                }
+                (forwarded, held_error)
+            };
+
+            let (result, (forwarded, held_error)) = tokio::join!(request, forward);
+
+            match result {
+                Ok(mut result) => {
+                    if let Some(event) = held_error {
+                        let _ = tx.send(event).await;
+                    }
+                    if index > 0 {
+                        info!("[NativeAgent] 请求由备用端点处理: {}", endpoint.name);
+                    }
+                    result.served_by = Some(endpoint.name.clone());
+                    return Ok(result);
+                }
+                Err(e) => {
+                    if index < last && !forwarded && is_failover_error(&e) {
+                        warn!(
+                            "[NativeAgent] 端点 {} 请求失败（{}），切换到下一个端点",
+                            endpoint.name, e
+                        );
+                        continue;
+                    }
+                    return Err(StreamFailure {
+                        error: e,
+                        event: held_error,
+                    });
+                }
+            }
+        }
+
+        Err(StreamFailure {
+            error: AgentError::Other("没有可用的端点".to_string()),
+            event: None,
+        })
+    }
+
+    /// 发送非流式 `/v1/chat/completions` 请求，返回响应和实际处理请求的端点名称
+    ///
+    /// 与流式请求相同，429/5xx、网络错误或超时时按顺序切换到备用端点
+    async fn post_chat_completion(
+        &self,
+        chat_request: &ChatCompletionRequest,
+    ) -> Result<(ChatCompletionResponse, String), AgentError> {
+        let endpoints = self.endpoints();
+        let last = endpoints.len() - 1;
+
+        for (index, endpoint) in endpoints.iter().enumerate() {
+            let mut request = chat_request.clone();
+            if let Some(model) = &endpoint.model {
+                request.model = model.clone();
+            }
+
+            let url = format!("{}/v1/chat/completions", endpoint.base_url);
+            let response = match self
+                .client
+                .post(&url)
+                .header("Authorization", format!("Bearer {}", endpoint.api_key))
+                .header("Content-Type", "application/json")
+                .json(&request)
+                .send()
+                .await
+            {
+                Ok(response) => response,
+                Err(e) if index < last => {
+                    warn!(
+                        "[NativeAgent] 端点 {} 请求失败（{}），切换到下一个端点",
+                        endpoint.name, e
+                    );
+                    continue;
+                }
+                Err(e) => return Err(AgentError::from_reqwest(e)),
+            };
+
+            let status = response.status();
+            if !status.is_success() {
+                let body = response.text().await.unwrap_or_default();
+                error!(
+                    "[NativeAgent] 补全请求失败: endpoint={}, {} - {}",
+                    endpoint.name, status, body
+                );
+                if index < last && is_failover_status(status.as_u16()) {
+                    warn!(
+                        "[NativeAgent] 端点 {} 返回 {}，切换到下一个端点",
+                        endpoint.name, status
+                    );
+                    continue;
+                }
+                return Err(AgentError::Upstream {
+                    status: status.as_u16(),
+                    body,
+                });
+            }
+
+            let body = response.json().await.map_err(AgentError::from_reqwest)?;
+            if index > 0 {
+                info!("[NativeAgent] 请求由备用端点处理: {}", endpoint.name);
+            }
+            return Ok((body, endpoint.name.clone()));
+        }
+
+        Err(AgentError::Other("没有可用的端点".to_string()))
+    }
+
+    /// 生成图片
+    ///
+    /// 通过 ProxyCast 服务器的 `/v1/images/generations` 端点生成图片，
+    /// 并将结果保存到 `~/.proxycast/images` 目录
+    pub async fn generate_image(
+        &self,
+        prompt: &str,
+        size: Option<String>,
+        model: Option<String>,
+    ) -> Result<ImageGenerationResult, String> {
+        self.generate_image_in(&images_dir()?, prompt, size, model)
+            .await
+    }
+
+    /// 生成图片并保存到指定目录，文件扩展名按图片实际格式确定
+    async fn generate_image_in(
+        &self,
+        images_dir: &std::path::Path,
+        prompt: &str,
+        size: Option<String>,
+        model: Option<String>,
+    ) -> Result<ImageGenerationResult, String> {
+        use base64::{engine::general_purpose::STANDARD as BASE64_STANDARD, Engine};
+
+        let model = model.unwrap_or_else(|| DEFAULT_IMAGE_MODEL.to_string());
+        let size = size.unwrap_or_else(|| "1024x1024".to_string());
+
+        info!(
+            "[NativeAgent] 生成图片: model={}, size={}, prompt_len={}",
+            model,
+            size,
+            prompt.len()
+        );
+
+        let url = format!("{}/v1/images/generations", self.base_url);
+        let body = serde_json::json!({
+            "model": model,
+            "prompt": prompt,
+            "size": size,
+            "n": 1,
+            "response_format": "b64_json",
+        });
+
+        let response = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| format!("请求失败: {}", e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            error!("[NativeAgent] 图片生成失败: {} - {}", status, body);
+            return Err(format!("API 错误 ({}): {}", status, body));
+        }
+
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+
+        let item = body["data"]
+            .as_array()
+            .and_then(|d| d.first())
+            .ok_or_else(|| "响应中没有图片数据".to_string())?;
+
+        let bytes = if let Some(b64) = item["b64_json"].as_str() {
+            BASE64_STANDARD
+                .decode(b64)
+                .map_err(|e| format!("解码图片数据失败: {}", e))?
+        } else if let Some(image_url) = item["url"].as_str() {
+            let response = self
+                .client
+                .get(image_url)
+                .send()
+                .await
+                .map_err(|e| format!("下载图片失败: {}", e))?;
+            let status = response.status();
+            if !status.is_success() {
+                return Err(format!("下载图片失败: HTTP {}", status));
+            }
+            response
+                .bytes()
+                .await
+                .map_err(|e| format!("下载图片失败: {}", e))?
+                .to_vec()
+        } else {
+            return Err("响应中没有 b64_json 或 url 字段".to_string());
+        };
+
+        let format = crate::agent::images::sniff_format(&bytes, "generated")?;
+        let extension = format.extensions_str().first().copied().unwrap_or("png");
+        let file_name = format!(
+            "{}-{}.{}",
+            chrono::Local::now().format("%Y%m%d-%H%M%S"),
+            &uuid::Uuid::new_v4().to_string()[..8],
+            extension
+        );
+        let path = images_dir.join(file_name);
+        tokio::fs::write(&path, &bytes)
+            .await
+            .map_err(|e| format!("保存图片失败: {}", e))?;
+
+        info!("[NativeAgent] 图片已保存: {:?}", path);
+
+        Ok(ImageGenerationResult {
+            path: path.to_string_lossy().to_string(),
+            revised_prompt: item["revised_prompt"].as_str().map(|s| s.to_string()),
+            model,
+        })
+    }
+
+    // ==================== 会话管理方法 ====================
+
+    /// 构建 OpenAI 格式消息（用于非流式请求）
+    fn build_openai_messages(
+        &self,
+        session: Option<&AgentSession>,
+        system_prompt: Option<&str>,
+        user_message: &str,
+        images: Option<&[ImageData]>,
+        audio: Option<&[AudioData]>,
+        documents: Option<&[DocumentData]>,
+    ) -> Vec<ChatMessage> {
+        let mut messages = Vec::new();
+
+        // 系统提示词
+        if let Some(prompt) = system_prompt {
+            messages.push(ChatMessage {
+                role: "system".to_string(),
+                content: Some(OpenAIMessageContent::Text(prompt.to_string())),
+                tool_calls: None,
+                tool_call_id: None,
+            });
+        }
+
+        // 历史消息
+        if let Some(sess) = session {
+            for msg in &sess.messages {
+                messages.push(self.convert_to_chat_message(msg));
+            }
+        }
+
+        // 用户消息
+        let user_msg = if images.is_some() || audio.is_some() || documents.is_some() {
+            let mut parts = vec![OpenAIContentPart::Text {
+                text: user_message.to_string(),
+            }];
+            for img in images.unwrap_or_default() {
+                parts.push(OpenAIContentPart::ImageUrl {
+                    image_url: crate::models::openai::ImageUrl {
+                        url: format!("data:{};base64,{}", img.media_type, img.data),
+                        detail: img.detail.map(|d| d.as_str().to_string()),
+                    },
+                });
+            }
+            for clip in audio.unwrap_or_default() {
+                parts.push(OpenAIContentPart::InputAudio {
+                    input_audio: crate::models::openai::InputAudio {
+                        data: clip.data.clone(),
+                        format: clip.format.clone(),
+                    },
+                });
+            }
+            for doc in documents.unwrap_or_default() {
+                parts.push(OpenAIContentPart::File {
+                    file: crate::models::openai::FileData {
+                        filename: Some(doc.name.clone()),
+                        file_data: format!("data:{};base64,{}", doc.media_type, doc.data),
+                    },
+                });
+            }
+            ChatMessage {
+                role: "user".to_string(),
+                content: Some(OpenAIMessageContent::Parts(parts)),
+                tool_calls: None,
+                tool_call_id: None,
+            }
+        } else {
+            ChatMessage {
+                role: "user".to_string(),
+                content: Some(OpenAIMessageContent::Text(user_message.to_string())),
+                tool_calls: None,
+                tool_call_id: None,
+            }
+        };
+
+        messages.push(user_msg);
+        messages
+    }
+
+    /// 将 AgentMessage 转换为 OpenAI ChatMessage
+    fn convert_to_chat_message(&self, msg: &AgentMessage) -> ChatMessage {
+        let content = match &msg.content {
+            MessageContent::Text(text) => Some(OpenAIMessageContent::Text(text.clone())),
+            MessageContent::Parts(parts) => {
+                let openai_parts: Vec<OpenAIContentPart> = parts
+                    .iter()
+                    .map(|p| match p {
+                        ContentPart::Text { text } => {
+                            OpenAIContentPart::Text { text: text.clone() }
+                        }
+                        ContentPart::ImageUrl { image_url } => OpenAIContentPart::ImageUrl {
+                            image_url: crate::models::openai::ImageUrl {
+                                url: image_url.url.clone(),
+                                detail: image_url.detail.clone(),
+                            },
+                        },
+                        ContentPart::InputAudio { data, format } => OpenAIContentPart::InputAudio {
+                            input_audio: crate::models::openai::InputAudio {
+                                data: data.clone(),
+                                format: format.clone(),
+                            },
+                        },
+                        ContentPart::Document {
+                            data,
+                            media_type,
+                            name,
+                        } => OpenAIContentPart::File {
+                            file: crate::models::openai::FileData {
+                                filename: name.clone(),
+                                file_data: format!("data:{};base64,{}", media_type, data),
+                            },
+                        },
+                    })
+                    .collect();
+                Some(OpenAIMessageContent::Parts(openai_parts))
+            }
+        };
 
         ChatMessage {
             role: msg.role.clone(),
@@ -538,6 +1650,40 @@ impl NativeAgent {
         }
     }
 
+    /// 处理附件：PDF 按模型支持情况作为原生文档，其余附件提取文本并拼接到用户消息前
+    fn resolve_attachments(
+        &self,
+        message: &str,
+        attachments: Option<&[AttachmentData]>,
+        model: &str,
+    ) -> Result<ResolvedAttachments, String> {
+        match attachments {
+            Some(list) if !list.is_empty() => {
+                let (documents, mut infos, rest) = split_native_documents(list, model)?;
+                let (message, text_infos) = prepare_message_with_attachments(
+                    message,
+                    &rest,
+                    model,
+                    DEFAULT_ATTACHMENT_TOKEN_BUDGET,
+                )?;
+                infos.extend(text_infos);
+                info!(
+                    "[NativeAgent] 已处理 {} 个附件（原生文档 {} 个）: {:?}",
+                    infos.len(),
+                    documents.len(),
+                    infos.iter().map(|a| a.name.as_str()).collect::<Vec<_>>()
+                );
+                let documents = if documents.is_empty() {
+                    None
+                } else {
+                    Some(documents)
+                };
+                Ok((message, Some(infos), documents))
+            }
+            _ => Ok((message.to_string(), None, None)),
+        }
+    }
+
     /// 添加消息到会话
     fn add_message_to_session(
         &self,
@@ -545,76 +1691,210 @@ impl NativeAgent {
         role: &str,
         content: MessageContent,
         images: Option<&[ImageData]>,
+        audio: Option<&[AudioData]>,
+        documents: Option<&[DocumentData]>,
+        attachments: Option<Vec<AttachmentInfo>>,
+        model: &str,
     ) {
-        let mut sessions = self.sessions.write();
-        if let Some(session) = sessions.get_mut(session_id) {
-            let final_content = if let Some(imgs) = images {
-                let mut parts = vec![ContentPart::Text {
-                    text: content.as_text(),
-                }];
-                for img in imgs {
-                    parts.push(ContentPart::ImageUrl {
-                        image_url: ImageUrl {
-                            url: format!("data:{};base64,{}", img.media_type, img.data),
-                            detail: None,
-                        },
-                    });
-                }
-                MessageContent::Parts(parts)
-            } else {
-                content
-            };
+        let final_content = if images.is_some() || audio.is_some() || documents.is_some() {
+            let mut parts = vec![ContentPart::Text {
+                text: content.as_text(),
+            }];
+            for img in images.unwrap_or_default() {
+                parts.push(ContentPart::ImageUrl {
+                    image_url: ImageUrl {
+                        url: format!("data:{};base64,{}", img.media_type, img.data),
+                        detail: img.detail.map(|d| d.as_str().to_string()),
+                    },
+                });
+            }
+            for clip in audio.unwrap_or_default() {
+                parts.push(ContentPart::InputAudio {
+                    data: clip.data.clone(),
+                    format: clip.format.clone(),
+                });
+            }
+            for doc in documents.unwrap_or_default() {
+                parts.push(ContentPart::Document {
+                    data: doc.data.clone(),
+                    media_type: doc.media_type.clone(),
+                    name: Some(doc.name.clone()),
+                });
+            }
+            MessageContent::Parts(parts)
+        } else {
+            content
+        };
 
-            session.messages.push(AgentMessage {
-                role: role.to_string(),
-                content: final_content,
-                timestamp: chrono::Utc::now().to_rfc3339(),
-                tool_calls: None,
-                tool_call_id: None,
-            });
-            session.updated_at = chrono::Utc::now().to_rfc3339();
-        }
+        let message = AgentMessage {
+            role: role.to_string(),
+            content: final_content,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            tool_calls: None,
+            tool_call_id: None,
+            attachments,
+            truncated: None,
+        };
+        self.push_session_message(session_id, message, Some(model), None);
     }
 
-    /// 添加 assistant 消息到会话（支持工具调用）
+    /// 添加 assistant 消息到会话（支持工具调用），`model` 为实际处理本轮请求的模型
     fn add_assistant_message_to_session(
         &self,
         session_id: &str,
         content: MessageContent,
         tool_calls: Option<Vec<ToolCall>>,
+        model: &str,
+        usage: Option<&TokenUsage>,
     ) {
-        let mut sessions = self.sessions.write();
-        if let Some(session) = sessions.get_mut(session_id) {
-            session.messages.push(AgentMessage {
-                role: "assistant".to_string(),
-                content,
-                timestamp: chrono::Utc::now().to_rfc3339(),
-                tool_calls,
-                tool_call_id: None,
-            });
-            session.updated_at = chrono::Utc::now().to_rfc3339();
-        }
+        let message = AgentMessage {
+            role: "assistant".to_string(),
+            content,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            tool_calls,
+            tool_call_id: None,
+            attachments: None,
+            truncated: None,
+        };
+        self.push_session_message(session_id, message, Some(model), usage);
     }
 
     /// 添加工具结果消息到会话
     fn add_tool_result_to_session(&self, session_id: &str, tool_result: &ToolCallResult) {
+        self.push_session_message(session_id, tool_result.to_agent_message(), None, None);
+    }
+
+    /// 追加消息到会话，启用会话记录时在释放锁后写入记录文件
+    ///
+    /// `model` 为处理本轮对话的模型（经过上下文回退后的模型或上游报告的快照），
+    /// 而不是会话的默认模型
+    fn push_session_message(
+        &self,
+        session_id: &str,
+        message: AgentMessage,
+        model: Option<&str>,
+        usage: Option<&TokenUsage>,
+    ) {
         let mut sessions = self.sessions.write();
-        if let Some(session) = sessions.get_mut(session_id) {
-            session.messages.push(tool_result.to_agent_message());
-            session.updated_at = chrono::Utc::now().to_rfc3339();
+        let Some(session) = sessions.get_mut(session_id) else {
+            return;
+        };
+        let recorded = self.transcripts.is_enabled().then(|| message.clone());
+        session.messages.push(message);
+        session.updated_at = chrono::Utc::now().to_rfc3339();
+        drop(sessions);
+
+        if let Some(message) = recorded {
+            self.transcripts.record(session_id, &message, model, usage);
         }
     }
 
-    // ==================== 公开会话管理 API ====================
-
-    pub fn create_session(&self, model: Option<String>, system_prompt: Option<String>) -> String {
-        let session_id = uuid::Uuid::new_v4().to_string();
+    /// 向统一用量记录上报一次模型请求的用量
+    fn record_usage(
+        &self,
+        session_id: Option<&str>,
+        model: &str,
+        usage: Option<&TokenUsage>,
+        started: Instant,
+    ) {
+        let Some(recorder) = &self.usage_recorder else {
+            return;
+        };
+        let Some(usage) = usage else {
+            warn!(
+                "[NativeAgent] 上游未返回用量，本次请求未计入统一用量: model={}",
+                model
+            );
+            return;
+        };
+        let provider = self.provider_type.as_str();
+        let mut event = UsageEvent::new(
+            UsageSource::NativeAgent,
+            model,
+            usage.input_tokens,
+            usage.output_tokens,
+        )
+        .with_latency_ms(started.elapsed().as_millis() as u64)
+        .with_session_id(session_id);
+        if let Ok(provider_type) = provider.parse::<crate::ProviderType>() {
+            event = event.with_provider_type(provider_type);
+        }
+        recorder.record(event.with_provider(provider));
+    }
+
+    /// 提示词超过模型上下文窗口时，选择本次请求使用的回退模型
+    fn plan_context_fallback(
+        &self,
+        model: &str,
+        system_prompt: Option<&str>,
+        history: &[AgentMessage],
+        pending: &str,
+    ) -> Option<ContextFallback> {
+        if !self.context_fallback.enabled {
+            return None;
+        }
+        let estimated = estimate_prompt_tokens(system_prompt, history, pending);
+        let fallback = plan_fallback(model, estimated, &self.context_fallback)?;
+        info!(
+            "[NativeAgent] 提示词约 {} tokens，超过 {} 的上下文窗口 {}，本次请求改用 {}",
+            fallback.estimated_tokens, fallback.from, fallback.context_window, fallback.to
+        );
+        Some(fallback)
+    }
+
+    /// 估算本次请求的上下文用量（模型的上下文窗口未知时返回 None）
+    fn context_usage(
+        &self,
+        model: &str,
+        system_prompt: Option<&str>,
+        history: &[AgentMessage],
+        pending: &str,
+    ) -> Option<StreamEvent> {
+        let used = estimate_prompt_tokens(system_prompt, history, pending);
+        usage_event(model, used, &self.context_fallback.context_windows)
+    }
+
+    /// 记录上游报告的模型，会话固定的模型快照发生变化时返回变化记录
+    fn track_resolved_model(
+        &self,
+        session_id: &str,
+        requested: &str,
+        resolved: Option<&str>,
+    ) -> Option<ModelChange> {
+        let resolved = resolved.filter(|m| !m.is_empty())?;
+        let mut sessions = self.sessions.write();
+        let session = sessions.get_mut(session_id)?;
+        let change = model_pin::observe_resolved_model(session, requested, resolved)?;
+        warn!(
+            "[NativeAgent] 会话 {} 的模型快照发生变化: {} -> {}（固定: {}）",
+            session_id, change.from, change.to, change.pinned
+        );
+        Some(change)
+    }
+
+    // ==================== 公开会话管理 API ====================
+
+    pub fn create_session(&self, model: Option<String>, system_prompt: Option<String>) -> String {
+        let session_id = uuid::Uuid::new_v4().to_string();
         let now = chrono::Utc::now().to_rfc3339();
+        let model = model.unwrap_or_else(|| self.config.model.clone());
         let session = AgentSession {
             id: session_id.clone(),
-            model: model.unwrap_or_else(|| self.config.model.clone()),
+            model: model.clone(),
             messages: Vec::new(),
             system_prompt,
+            memory_prompt: None,
+            knowledge_collection: None,
+            model_pin: model_pin::initial_pin(&model),
+            model_changes: Vec::new(),
+            tags: Vec::new(),
+            profile: None,
+            folder: None,
+            repo_path: None,
+            http_domains: Vec::new(),
+            permission_mode: None,
+            archived: false,
+            pinned: false,
             created_at: now.clone(),
             updated_at: now,
         };
@@ -637,6 +1917,139 @@ impl NativeAgent {
         self.sessions.read().values().cloned().collect()
     }
 
+    /// 写入同步拉取的会话，只替换 `updated_at` 更早的本地会话（同步期间本地的修改不会被覆盖），
+    /// 返回实际写入的会话数
+    pub fn upsert_newer_sessions(&self, sessions: Vec<AgentSession>) -> usize {
+        let mut map = self.sessions.write();
+        let mut count = 0;
+        for session in sessions {
+            let newer = map
+                .get(&session.id)
+                .is_none_or(|local| is_newer(&session.updated_at, &local.updated_at));
+            if newer {
+                map.insert(session.id.clone(), session);
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// 将快速提问追加到草稿会话（不存在时创建）
+    pub fn append_quick_ask(&self, prompt: &str, answer: &str, max_messages: usize) -> String {
+        let mut sessions = self.sessions.write();
+        let session = sessions
+            .entry(quick_ask::SCRATCH_SESSION_ID.to_string())
+            .or_insert_with(|| quick_ask::new_scratch_session(&self.config.model));
+        quick_ask::append_exchange(session, prompt, answer, max_messages);
+        session.id.clone()
+    }
+
+    /// 批量删除会话（整批校验后执行），返回被删除的会话
+    pub fn bulk_delete_sessions(
+        &self,
+        ids: &[String],
+        on_progress: &mut dyn FnMut(BulkProgress),
+    ) -> Result<Vec<AgentSession>, String> {
+        let removed = session_bulk::bulk_delete(&mut self.sessions.write(), ids, on_progress)?;
+        info!("[NativeAgent] 批量删除 {} 个会话", removed.len());
+        Ok(removed)
+    }
+
+    /// 批量添加/移除会话标签，返回修改的会话数
+    pub fn bulk_tag_sessions(
+        &self,
+        ids: &[String],
+        add: &[String],
+        remove: &[String],
+        on_progress: &mut dyn FnMut(BulkProgress),
+    ) -> Result<usize, String> {
+        session_bulk::bulk_tag(&mut self.sessions.write(), ids, add, remove, on_progress)
+    }
+
+    /// 批量读取会话（用于导出）
+    pub fn bulk_collect_sessions(
+        &self,
+        ids: &[String],
+        on_progress: &mut dyn FnMut(BulkProgress),
+    ) -> Result<Vec<AgentSession>, String> {
+        session_bulk::bulk_collect(&self.sessions.read(), ids, on_progress)
+    }
+
+    /// 设置会话关联的知识库集合（None 表示取消关联）
+    pub fn set_session_collection(&self, session_id: &str, collection: Option<String>) -> bool {
+        let mut sessions = self.sessions.write();
+        if let Some(session) = sessions.get_mut(session_id) {
+            session.knowledge_collection = collection;
+            session.updated_at = chrono::Utc::now().to_rfc3339();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 设置会话关联的 git 仓库（None 表示取消关联）
+    pub fn set_session_repo(&self, session_id: &str, repo_path: Option<String>) -> bool {
+        let mut sessions = self.sessions.write();
+        if let Some(session) = sessions.get_mut(session_id) {
+            session.repo_path = repo_path;
+            session.updated_at = chrono::Utc::now().to_rfc3339();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 设置会话 `http_request` 工具允许访问的域名
+    pub fn set_session_http_domains(&self, session_id: &str, domains: Vec<String>) -> bool {
+        let mut sessions = self.sessions.write();
+        if let Some(session) = sessions.get_mut(session_id) {
+            session.http_domains = domains;
+            session.updated_at = chrono::Utc::now().to_rfc3339();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 设置会话的工具权限模式（None 表示使用全局配置）
+    pub fn set_session_permission_mode(
+        &self,
+        session_id: &str,
+        mode: Option<crate::config::PermissionMode>,
+    ) -> bool {
+        let mut sessions = self.sessions.write();
+        if let Some(session) = sessions.get_mut(session_id) {
+            session.permission_mode = mode;
+            session.updated_at = chrono::Utc::now().to_rfc3339();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 修改会话标签、文件夹、置顶和归档状态
+    pub fn update_session_meta(&self, session_id: &str, update: &SessionMetaUpdate) -> bool {
+        let mut sessions = self.sessions.write();
+        if let Some(session) = sessions.get_mut(session_id) {
+            session_meta::apply_update(session, update);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 设置会话使用的 Provider 配置档案（None 表示使用激活的档案）
+    pub fn set_session_profile(&self, session_id: &str, profile: Option<String>) -> bool {
+        let mut sessions = self.sessions.write();
+        if let Some(session) = sessions.get_mut(session_id) {
+            session.profile = profile;
+            session.updated_at = chrono::Utc::now().to_rfc3339();
+            true
+        } else {
+            false
+        }
+    }
+
     pub fn clear_session_messages(&self, session_id: &str) -> bool {
         let mut sessions = self.sessions.write();
         if let Some(session) = sessions.get_mut(session_id) {
@@ -648,123 +2061,1175 @@ impl NativeAgent {
         }
     }
 
+    /// 修改会话的系统提示词（会话未设置时以默认系统提示词为基础），返回会话是否存在
+    pub fn update_session_system_prompt(
+        &self,
+        session_id: &str,
+        update: impl FnOnce(Option<String>) -> Option<String>,
+    ) -> bool {
+        let mut sessions = self.sessions.write();
+        let Some(session) = sessions.get_mut(session_id) else {
+            return false;
+        };
+        let base = session
+            .system_prompt
+            .clone()
+            .or_else(|| self.config.system_prompt.clone());
+        session.system_prompt = update(base);
+        session.updated_at = chrono::Utc::now().to_rfc3339();
+        true
+    }
+
     pub fn get_session_messages(&self, session_id: &str) -> Option<Vec<AgentMessage>> {
         self.sessions
             .read()
             .get(session_id)
             .map(|s| s.messages.clone())
     }
+
+    /// 编辑会话中指定消息的文本内容（保留图片）
+    pub fn edit_message(
+        &self,
+        session_id: &str,
+        index: usize,
+        content: String,
+    ) -> Result<(), AgentError> {
+        let mut sessions = self.sessions.write();
+        let session = sessions
+            .get_mut(session_id)
+            .ok_or_else(|| AgentError::SessionNotFound(session_id.to_string()))?;
+        let msg = session
+            .messages
+            .get_mut(index)
+            .ok_or_else(|| AgentError::InvalidRequest(format!("消息不存在: {}", index)))?;
+        replace_message_text(msg, content).map_err(AgentError::InvalidRequest)?;
+        session.updated_at = chrono::Utc::now().to_rfc3339();
+        Ok(())
+    }
+
+    /// 处理被中断的一轮对话，`turn_start` 为本轮开始前的消息数
+    ///
+    /// 保留部分回复时补写的消息同样写入会话记录，`model` 为本轮请求的模型（未指定时为默认模型）
+    pub fn settle_cancelled_turn(
+        &self,
+        session_id: &str,
+        turn_start: usize,
+        user_message: &str,
+        partial: &str,
+        mode: CancelMode,
+        model: Option<&str>,
+    ) -> Result<(), AgentError> {
+        let mut sessions = self.sessions.write();
+        let session = sessions
+            .get_mut(session_id)
+            .ok_or_else(|| AgentError::SessionNotFound(session_id.to_string()))?;
+        let existing = session.messages.len();
+        settle_cancelled_messages(
+            &mut session.messages,
+            turn_start,
+            user_message,
+            partial,
+            mode,
+        );
+        session.updated_at = chrono::Utc::now().to_rfc3339();
+        let added = if self.transcripts.is_enabled() {
+            session
+                .messages
+                .get(existing..)
+                .unwrap_or_default()
+                .to_vec()
+        } else {
+            Vec::new()
+        };
+        drop(sessions);
+
+        let model = model.unwrap_or(&self.config.model);
+        for message in &added {
+            let model = (message.role != "tool").then_some(model);
+            self.transcripts.record(session_id, message, model, None);
+        }
+        Ok(())
+    }
+
+    /// 删除会话中指定消息，返回实际删除的消息数
+    pub fn delete_message(&self, session_id: &str, index: usize) -> Result<usize, AgentError> {
+        let mut sessions = self.sessions.write();
+        let session = sessions
+            .get_mut(session_id)
+            .ok_or_else(|| AgentError::SessionNotFound(session_id.to_string()))?;
+        let removed =
+            remove_message(&mut session.messages, index).map_err(AgentError::InvalidRequest)?;
+        session.updated_at = chrono::Utc::now().to_rfc3339();
+        Ok(removed)
+    }
+
+    /// 移除最后一轮的回答（包括中间的工具调用），准备重新生成
+    ///
+    /// 被移除的消息随返回值暂存，重新生成失败或以 discard 方式中断时通过
+    /// [`Self::restore_answer`] 恢复
+    pub fn take_last_answer(&self, session_id: &str) -> Result<RegenerateTurn, AgentError> {
+        let mut sessions = self.sessions.write();
+        let session = sessions
+            .get_mut(session_id)
+            .ok_or_else(|| AgentError::SessionNotFound(session_id.to_string()))?;
+        let user_index = session
+            .messages
+            .iter()
+            .rposition(|m| m.role == "user")
+            .ok_or_else(|| AgentError::InvalidRequest("会话中没有可重新生成的消息".to_string()))?;
+        let user_message = session.messages[user_index].content.as_text();
+        let previous = session.messages.split_off(user_index + 1);
+        session.updated_at = chrono::Utc::now().to_rfc3339();
+        Ok(RegenerateTurn {
+            user_index,
+            user_message,
+            previous,
+        })
+    }
+
+    /// 丢弃重新生成期间写入的消息，恢复原回答
+    pub fn restore_answer(&self, session_id: &str, turn: RegenerateTurn) -> Result<(), AgentError> {
+        let mut sessions = self.sessions.write();
+        let session = sessions
+            .get_mut(session_id)
+            .ok_or_else(|| AgentError::SessionNotFound(session_id.to_string()))?;
+        let keep = turn.user_index + 1;
+        if session.messages.len() < keep {
+            return Err(AgentError::Other("会话在重新生成期间已被修改".to_string()));
+        }
+        session.messages.truncate(keep);
+        session.messages.extend(turn.previous);
+        session.updated_at = chrono::Utc::now().to_rfc3339();
+        Ok(())
+    }
+}
+
+/// 重新生成时暂存的原回答
+#[derive(Debug, Clone)]
+pub struct RegenerateTurn {
+    /// 最后一条用户消息的下标
+    pub user_index: usize,
+    /// 最后一条用户消息的文本
+    pub user_message: String,
+    /// 被移除的原回答（包括中间的工具调用）
+    previous: Vec<AgentMessage>,
+}
+
+/// 替换消息中的文本，图片部分保持不变
+fn replace_message_text(msg: &mut AgentMessage, content: String) -> Result<(), String> {
+    if msg.role != "user" && msg.role != "assistant" {
+        return Err(format!("不支持编辑 {} 消息", msg.role));
+    }
+    msg.content = match &msg.content {
+        MessageContent::Parts(parts) => {
+            let mut new_parts = vec![ContentPart::Text { text: content }];
+            new_parts.extend(
+                parts
+                    .iter()
+                    .filter(|p| !matches!(p, ContentPart::Text { .. }))
+                    .cloned(),
+            );
+            MessageContent::Parts(new_parts)
+        }
+        MessageContent::Text(_) => MessageContent::Text(content),
+    };
+    Ok(())
+}
+
+/// 删除消息；删除带工具调用的助手消息时一并删除对应的工具结果
+fn remove_message(messages: &mut Vec<AgentMessage>, index: usize) -> Result<usize, String> {
+    let msg = messages
+        .get(index)
+        .ok_or_else(|| format!("消息不存在: {}", index))?;
+    if msg.role == "tool" {
+        return Err("工具结果不能单独删除，请删除对应的助手消息".to_string());
+    }
+
+    let call_ids: HashSet<String> = msg
+        .tool_calls
+        .iter()
+        .flatten()
+        .map(|call| call.id.clone())
+        .collect();
+    let mut end = index + 1;
+    while end < messages.len()
+        && messages[end].role == "tool"
+        && messages[end]
+            .tool_call_id
+            .as_ref()
+            .is_some_and(|id| call_ids.contains(id))
+    {
+        end += 1;
+    }
+
+    messages.drain(index..end);
+    Ok(end - index)
+}
+
+/// 整理被中断的一轮对话
+///
+/// - `Discard`：回退到本轮开始前，用户消息、已完成的工具调用和部分回复全部丢弃
+/// - `Keep`：补上尚未写入的用户消息，为没有结果的工具调用补充"已取消"结果，
+///   再把部分回复作为 truncated 的 assistant 消息写入（已写入历史的相同回复不重复写入）
+fn settle_cancelled_messages(
+    messages: &mut Vec<AgentMessage>,
+    turn_start: usize,
+    user_message: &str,
+    partial: &str,
+    mode: CancelMode,
+) {
+    let now = chrono::Utc::now().to_rfc3339();
+    let message = |role: &str, text: &str| AgentMessage {
+        role: role.to_string(),
+        content: MessageContent::Text(text.to_string()),
+        timestamp: now.clone(),
+        tool_calls: None,
+        tool_call_id: None,
+        attachments: None,
+        truncated: None,
+    };
+
+    if mode == CancelMode::Discard {
+        messages.truncate(turn_start);
+        return;
+    }
+
+    if messages.len() <= turn_start {
+        messages.push(message("user", user_message));
+    }
+
+    // 中断在工具执行期间：为缺少结果的工具调用补充结果，保证历史可继续发送
+    let answered: HashSet<String> = messages[turn_start..]
+        .iter()
+        .filter_map(|m| m.tool_call_id.clone())
+        .collect();
+    let pending: Vec<String> = messages[turn_start..]
+        .iter()
+        .flat_map(|m| m.tool_calls.iter().flatten())
+        .map(|call| call.id.clone())
+        .filter(|id| !answered.contains(id))
+        .collect();
+    for id in pending {
+        let mut result = message("tool", "工具调用已取消");
+        result.tool_call_id = Some(id);
+        messages.push(result);
+    }
+
+    let already_saved = messages
+        .last()
+        .is_some_and(|m| m.role == "assistant" && m.content.as_text() == partial);
+    if !partial.is_empty() && !already_saved {
+        let mut reply = message("assistant", partial);
+        reply.truncated = Some(true);
+        messages.push(reply);
+    }
+}
+
+/// 定位第 `turn_id` 条用户消息，返回其下标和该轮的最终回答
+fn locate_turn(messages: &[AgentMessage], turn_id: usize) -> Option<(usize, Option<String>)> {
+    let user_index = messages
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| m.role == "user")
+        .nth(turn_id)
+        .map(|(i, _)| i)?;
+
+    let original = messages[user_index + 1..]
+        .iter()
+        .take_while(|m| m.role != "user")
+        .filter(|m| m.role == "assistant")
+        .map(|m| m.content.as_text())
+        .filter(|text| !text.trim().is_empty())
+        .last();
+
+    Some((user_index, original))
 }
 
 // ==================== Tauri 状态管理 ====================
 
+/// 进行中的流式对话
+struct ActiveStream {
+    info: StreamInfo,
+    cancel: mpsc::Sender<CancelMode>,
+}
+
 /// Tauri 状态：原生 Agent 管理器
 #[derive(Clone, Default)]
 pub struct NativeAgentState {
     agent: Arc<RwLock<Option<NativeAgent>>>,
+    /// 长期记忆存储（未设置时不注入记忆，也不注册记忆工具）
+    memory: Option<MemoryStore>,
+    /// 知识库向量存储（未设置时不检索会话关联的知识库）
+    knowledge: Option<VectorStore>,
+    /// 向量化模型（知识库集合为空时使用）
+    embedding_model: Arc<RwLock<String>>,
+    /// Skill 使用统计（未设置时不记录）
+    skill_usage: Option<SkillUsageStore>,
+    /// 工具严格模式（OpenAI strict function calling）
+    strict_tools: Arc<AtomicBool>,
+    /// 图片预处理配置
+    image_options: Arc<RwLock<ImageProcessingConfig>>,
+    /// 备用端点配置
+    fallbacks: Arc<RwLock<Vec<AgentFallbackEndpoint>>>,
+    /// Provider 配置档案
+    profiles: Arc<RwLock<ProviderProfilesConfig>>,
+    /// 会话配额
+    session_quota: Arc<RwLock<SessionQuotaConfig>>,
+    /// 会话记录器
+    transcripts: TranscriptLogger,
+    /// 上下文超限回退配置
+    context_fallback: Arc<RwLock<ContextFallbackConfig>>,
+    /// 进行中的流式对话（流 ID -> 对话信息和取消信号）
+    active_streams: Arc<RwLock<HashMap<String, ActiveStream>>>,
+    /// Agent 计划的后续任务
+    followups: FollowupScheduler,
+    /// 用户定义的定时任务
+    scheduled_tasks: TaskScheduler,
+    /// 后台任务
+    jobs: JobManager,
+    /// 运行中工具调用的取消信号
+    tool_cancellations: ToolCancellations,
+    /// 待审核的补丁
+    patches: PatchReviewQueue,
+    /// 待批准的 Skill 脚本运行请求
+    script_reviews: ScriptReviewQueue,
+    /// 全局工具权限模式（会话可单独覆盖）
+    permission_mode: Arc<RwLock<crate::config::PermissionMode>>,
+    /// `approve` 模式下待批准的工具调用
+    tool_approvals: ToolApprovalQueue,
+    /// 用户自定义的命令工具
+    custom_tools: Arc<RwLock<Vec<crate::config::CustomToolConfig>>>,
+    /// OCR 工具配置
+    ocr: Arc<RwLock<crate::config::OcrConfig>>,
+    /// 代码解释器工具配置
+    code_interpreter: Arc<RwLock<crate::config::CodeInterpreterConfig>>,
+    /// SQL 连接档案
+    sql_connections: Arc<RwLock<Vec<crate::config::SqlConnectionConfig>>>,
+    /// WASM 插件
+    wasm_plugins: WasmPluginHost,
+    /// 无头浏览器
+    browser: BrowserHost,
+    /// 统一用量记录（未设置时不上报）
+    usage_recorder: Option<Arc<UsageRecorder>>,
+    /// 初始化锁，保证并发的自动初始化只创建一个 Agent
+    init_lock: Arc<tokio::sync::Mutex<()>>,
 }
 
 impl NativeAgentState {
     pub fn new() -> Self {
         Self {
             agent: Arc::new(RwLock::new(None)),
+            memory: None,
+            knowledge: None,
+            embedding_model: Arc::new(RwLock::new(DEFAULT_EMBEDDING_MODEL.to_string())),
+            skill_usage: None,
+            strict_tools: Arc::new(AtomicBool::new(false)),
+            image_options: Arc::new(RwLock::new(ImageProcessingConfig::default())),
+            fallbacks: Arc::new(RwLock::new(Vec::new())),
+            profiles: Arc::new(RwLock::new(ProviderProfilesConfig::default())),
+            session_quota: Arc::new(RwLock::new(SessionQuotaConfig::default())),
+            transcripts: TranscriptLogger::default(),
+            context_fallback: Arc::new(RwLock::new(ContextFallbackConfig::default())),
+            active_streams: Arc::new(RwLock::new(HashMap::new())),
+            followups: FollowupScheduler::new(),
+            scheduled_tasks: TaskScheduler::new(),
+            jobs: JobManager::new(),
+            tool_cancellations: ToolCancellations::new(),
+            patches: PatchReviewQueue::new(),
+            script_reviews: script_review_queue(),
+            permission_mode: Arc::new(RwLock::new(crate::config::PermissionMode::default())),
+            tool_approvals: tool_approval_queue(),
+            custom_tools: Arc::new(RwLock::new(Vec::new())),
+            ocr: Arc::new(RwLock::new(crate::config::OcrConfig::default())),
+            code_interpreter: Arc::new(
+                RwLock::new(crate::config::CodeInterpreterConfig::default()),
+            ),
+            sql_connections: Arc::new(RwLock::new(Vec::new())),
+            wasm_plugins: WasmPluginHost::new(),
+            browser: BrowserHost::new(),
+            usage_recorder: None,
+            init_lock: Arc::new(tokio::sync::Mutex::new(())),
         }
     }
 
-    pub fn init(
-        &self,
-        base_url: String,
-        api_key: String,
-        provider_type: ProviderType,
-    ) -> Result<(), String> {
-        let agent = NativeAgent::new(base_url, api_key, provider_type)?;
-        *self.agent.write() = Some(agent);
+    /// 启用长期记忆
+    pub fn with_memory(mut self, store: MemoryStore) -> Self {
+        self.memory = Some(store);
+        self
+    }
+
+    pub fn memory(&self) -> Option<&MemoryStore> {
+        self.memory.as_ref()
+    }
+
+    /// 启用知识库检索
+    pub fn with_knowledge(mut self, store: VectorStore) -> Self {
+        self.knowledge = Some(store);
+        self
+    }
+
+    /// 更新向量化模型
+    pub fn set_embedding_model(&self, model: String) {
+        *self.embedding_model.write() = model;
+    }
+
+    /// 启用 Skill 使用统计
+    pub fn with_skill_usage(mut self, store: SkillUsageStore) -> Self {
+        self.skill_usage = Some(store);
+        self
+    }
+
+    /// 向统一用量记录上报对话用量
+    pub fn with_usage_recorder(mut self, recorder: Arc<UsageRecorder>) -> Self {
+        self.usage_recorder = Some(recorder);
+        self
+    }
+
+    /// 记录本轮对话（`since` 之后）新增消息中的 Skill 使用
+    fn record_skill_usage(&self, session_id: Option<&str>, since: chrono::DateTime<chrono::Utc>) {
+        let (Some(store), Some(sid)) = (&self.skill_usage, session_id) else {
+            return;
+        };
+        let Some(messages) = self.get_session_messages(sid) else {
+            return;
+        };
+        if let Err(e) = store.record_messages(messages_since(&messages, since)) {
+            warn!("[NativeAgent] 记录 Skill 使用失败: {}", e);
+        }
+    }
+
+    pub fn followups(&self) -> &FollowupScheduler {
+        &self.followups
+    }
+
+    pub fn patches(&self) -> &PatchReviewQueue {
+        &self.patches
+    }
+
+    pub fn script_reviews(&self) -> &ScriptReviewQueue {
+        &self.script_reviews
+    }
+
+    pub fn tool_approvals(&self) -> &ToolApprovalQueue {
+        &self.tool_approvals
+    }
+
+    /// 设置推送待审核请求状态变化的应用句柄（补丁、脚本、工具调用和后续任务）
+    pub fn set_app_handle(&self, app_handle: tauri::AppHandle) {
+        self.followups.set_app_handle(app_handle.clone());
+        self.patches.set_app_handle(app_handle.clone());
+        self.script_reviews.set_app_handle(app_handle.clone());
+        self.tool_approvals.set_app_handle(app_handle);
+    }
+
+    /// 设置全局工具权限模式（之后创建的工具注册表生效）
+    pub fn set_permission_mode(&self, mode: crate::config::PermissionMode) {
+        *self.permission_mode.write() = mode;
+    }
+
+    /// 会话生效的工具权限模式（会话未设置时使用全局模式）
+    pub fn permission_mode(&self, session_id: Option<&str>) -> crate::config::PermissionMode {
+        let session_mode = session_id.and_then(|sid| {
+            self.agent.read().as_ref().and_then(|agent| {
+                agent
+                    .sessions
+                    .read()
+                    .get(sid)
+                    .and_then(|s| s.permission_mode)
+            })
+        });
+        session_mode.unwrap_or(*self.permission_mode.read())
+    }
+
+    pub fn scheduled_tasks(&self) -> &TaskScheduler {
+        &self.scheduled_tasks
+    }
+
+    pub fn jobs(&self) -> &JobManager {
+        &self.jobs
+    }
+
+    pub fn tool_cancellations(&self) -> &ToolCancellations {
+        &self.tool_cancellations
+    }
+
+    pub fn wasm_plugins(&self) -> &WasmPluginHost {
+        &self.wasm_plugins
+    }
+
+    pub fn browser(&self) -> &BrowserHost {
+        &self.browser
+    }
+
+    /// 设置工具严格模式
+    pub fn set_strict_tools(&self, strict: bool) {
+        self.strict_tools.store(strict, Ordering::Relaxed);
+    }
+
+    pub fn strict_tools(&self) -> bool {
+        self.strict_tools.load(Ordering::Relaxed)
+    }
+
+    /// 更新图片预处理配置
+    pub fn set_image_options(&self, options: ImageProcessingConfig) {
+        *self.image_options.write() = options;
+    }
+
+    /// 更新备用端点配置
+    pub fn set_fallbacks(&self, fallbacks: Vec<AgentFallbackEndpoint>) {
+        *self.fallbacks.write() = fallbacks;
+    }
+
+    /// 更新 Provider 配置档案
+    pub fn set_provider_profiles(&self, profiles: ProviderProfilesConfig) {
+        *self.profiles.write() = profiles;
+    }
+
+    /// 更新会话配额
+    pub fn set_session_quota(&self, quota: SessionQuotaConfig) {
+        *self.session_quota.write() = quota;
+    }
+
+    /// 更新会话记录配置
+    pub fn set_transcripts(&self, config: &TranscriptConfig) {
+        self.transcripts.set_enabled(config.enabled);
+    }
+
+    pub fn transcripts(&self) -> &TranscriptLogger {
+        &self.transcripts
+    }
+
+    /// 更新上下文超限回退配置
+    pub fn set_context_fallback(&self, config: ContextFallbackConfig) {
+        *self.context_fallback.write() = config;
+    }
+
+    /// 更新定时任务
+    pub fn set_scheduled_tasks(&self, tasks: Vec<crate::config::ScheduledTaskConfig>) {
+        self.scheduled_tasks.set_tasks(tasks);
+    }
+
+    /// 更新自定义命令工具（之后创建的工具注册表生效）
+    pub fn set_custom_tools(&self, tools: Vec<crate::config::CustomToolConfig>) {
+        *self.custom_tools.write() = tools;
+    }
+
+    /// 更新 OCR 工具配置（之后创建的工具注册表生效）
+    pub fn set_ocr_config(&self, config: crate::config::OcrConfig) {
+        *self.ocr.write() = config;
+    }
+
+    /// 更新代码解释器工具配置（之后创建的工具注册表生效）
+    pub fn set_code_interpreter_config(&self, config: crate::config::CodeInterpreterConfig) {
+        *self.code_interpreter.write() = config;
+    }
+
+    /// 更新 SQL 连接档案（之后创建的工具注册表生效）
+    pub fn set_sql_connections(&self, connections: Vec<crate::config::SqlConnectionConfig>) {
+        *self.sql_connections.write() = connections;
+    }
+
+    /// 设置休眠期间错过的后续任务和定时任务的处理方式
+    pub fn set_missed_task_policy(&self, policy: crate::config::MissedTaskPolicy) {
+        self.followups.set_missed_task_policy(policy);
+        self.scheduled_tasks.set_missed_task_policy(policy);
+    }
+
+    /// 重建 Agent 的 HTTP 客户端（系统唤醒后调用，丢弃已失效的连接），会话保留
+    pub fn reset_connections(&self) -> Result<(), String> {
+        if let Some(agent) = self.agent.write().as_mut() {
+            agent.client = build_http_client(&agent.base_url)?;
+            info!("[NativeAgent] 已重建 HTTP 连接");
+        }
         Ok(())
     }
 
+    /// 获取会话配额使用情况和归档建议
+    pub fn session_quota_status(&self) -> SessionQuotaStatus {
+        evaluate_quota(&self.list_sessions(), &self.session_quota.read())
+    }
+
     pub fn is_initialized(&self) -> bool {
         self.agent.read().is_some()
     }
 
+    /// 确保 Agent 已初始化（init-once），返回当前 base_url
+    ///
+    /// - 已初始化且 `force` 为 false 时直接返回，不会调用 `connect`
+    /// - 并发调用时只有第一个调用者执行初始化，其余等待后复用同一个 Agent
+    /// - `force` 为 true 时使用 `connect` 返回的新参数重新初始化，已有会话保留；
+    ///   需要清空会话请使用 [`Self::reset`]
+    pub async fn ensure_initialized<F, Fut>(
+        &self,
+        force: bool,
+        connect: F,
+    ) -> Result<String, AgentError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<(String, String, ProviderType), AgentError>>,
+    {
+        if !force {
+            if let Some(agent) = self.agent.read().as_ref() {
+                return Ok(agent.base_url.clone());
+            }
+        }
+
+        let _guard = self.init_lock.lock().await;
+        // 等待锁期间可能已被其他调用者初始化
+        if !force {
+            if let Some(agent) = self.agent.read().as_ref() {
+                return Ok(agent.base_url.clone());
+            }
+        }
+
+        let (base_url, api_key, provider_type) = connect().await?;
+        let mut agent = NativeAgent::new(base_url.clone(), api_key, provider_type)?;
+        agent.transcripts = self.transcripts.clone();
+        agent.usage_recorder = self.usage_recorder.clone();
+
+        let mut slot = self.agent.write();
+        if let Some(previous) = slot.as_ref() {
+            agent.sessions = previous.sessions.clone();
+            info!("[NativeAgent] 重新初始化 Agent，保留已有会话");
+        }
+        *slot = Some(agent);
+        Ok(base_url)
+    }
+
     pub fn reset(&self) {
         *self.agent.write() = None;
     }
 
     /// 获取工具注册表
-    pub fn get_tool_registry(&self) -> Result<Arc<ToolRegistry>, String> {
+    ///
+    /// 指定会话时额外注册绑定到该会话的 `schedule_followup` 工具，`ocr` 工具可读取该会话附带的图片，
+    /// 浏览器工具使用该会话的标签页，`run_code` 生成的文件保存在该会话的目录下；
+    /// 会话关联了 git 仓库时注册 git 工具，配置了域名白名单时注册 `http_request` 工具，
+    /// 已安装的 Skill 带有脚本时注册 `run_skill_script` 工具
+    pub fn get_tool_registry(&self, session_id: Option<&str>) -> Result<Arc<ToolRegistry>, String> {
         let base_dir = dirs::home_dir().ok_or_else(|| "无法获取用户 home 目录".to_string())?;
-        let registry = create_default_registry(base_dir);
+        let registry = create_default_registry(&base_dir);
+        registry.set_strict(self.strict_tools());
+        registry.set_permission(
+            ToolPermission::new(self.permission_mode(session_id))
+                .with_approvals(self.tool_approvals.clone(), session_id),
+        );
+        if let Some(store) = &self.memory {
+            let mut save = SaveMemoryTool::new(store.clone());
+            if let Some(session_id) = session_id {
+                save = save.with_session(session_id);
+            }
+            let results = [
+                registry.register(save),
+                registry.register(RecallMemoryTool::new(store.clone())),
+            ];
+            for e in results.into_iter().filter_map(Result::err) {
+                error!("注册记忆工具失败: {}", e);
+            }
+        }
+        if let Some(session_id) = session_id {
+            let tool = ScheduleFollowupTool::new(self.followups.clone(), session_id);
+            if let Err(e) = registry.register(tool) {
+                error!("注册 ScheduleFollowupTool 失败: {}", e);
+            }
+            let (repo_path, http_domains) =
+                self.agent
+                    .read()
+                    .as_ref()
+                    .and_then(|agent| {
+                        agent.sessions.read().get(session_id).map(|session| {
+                            (session.repo_path.clone(), session.http_domains.clone())
+                        })
+                    })
+                    .unwrap_or_default();
+            if let Some(repo) = repo_path {
+                let results = [
+                    registry.register(GitStatusTool::new(&repo)),
+                    registry.register(GitDiffTool::new(&repo)),
+                    registry.register(GitLogTool::new(&repo)),
+                    registry.register(GitApplyPatchTool::new(
+                        &repo,
+                        self.patches.clone(),
+                        session_id,
+                    )),
+                ];
+                for e in results.into_iter().filter_map(Result::err) {
+                    error!("注册 git 工具失败: {}", e);
+                }
+            }
+            if let Some(dir) = crate::agent::tools::skill_script::skills_dir() {
+                let tool = SkillScriptTool::new(dir, self.script_reviews.clone(), session_id);
+                if tool.has_scripts() {
+                    if let Err(e) = registry.register(tool) {
+                        error!("注册 SkillScriptTool 失败: {}", e);
+                    }
+                }
+            }
+            if !http_domains.is_empty() {
+                let result = HttpRequestTool::new(http_domains)
+                    .and_then(|tool| registry.register(tool).map_err(|e| e.to_string()));
+                if let Err(e) = result {
+                    error!("注册 HttpRequestTool 失败: {}", e);
+                }
+            }
+        }
+        let ocr = self.ocr.read().clone();
+        if ocr.enabled {
+            let mut tool = OcrTool::new(ocr, Arc::new(SecurityManager::new(&base_dir)));
+            if let (Some(session_id), Some(agent)) = (session_id, self.agent.read().as_ref()) {
+                tool = tool.with_session(agent.sessions.clone(), session_id);
+            }
+            if let Err(e) = registry.register(tool) {
+                error!("注册 OcrTool 失败: {}", e);
+            }
+        }
+        let code_interpreter = self.code_interpreter.read().clone();
+        if code_interpreter.enabled {
+            if let Some(dir) = crate::agent::tools::code_interpreter::artifacts_dir() {
+                let mut tool = CodeInterpreterTool::new(code_interpreter, dir);
+                if let Some(session_id) = session_id {
+                    tool = tool.with_session(session_id);
+                }
+                if let Err(e) = registry.register(tool) {
+                    error!("注册 CodeInterpreterTool 失败: {}", e);
+                }
+            }
+        }
+        if self.browser.config().enabled {
+            for tool in self.browser.tools(session_id) {
+                if let Err(e) = registry.register(tool) {
+                    error!("注册浏览器工具失败: {}", e);
+                }
+            }
+        }
+        let sql_connections: Vec<_> = self
+            .sql_connections
+            .read()
+            .iter()
+            .filter(|c| c.enabled)
+            .cloned()
+            .collect();
+        if !sql_connections.is_empty() {
+            let connections = Arc::new(sql_connections);
+            let results = [
+                registry.register(SqlQueryTool::new(connections.clone())),
+                registry.register(SqlSchemaTool::new(connections)),
+            ];
+            for e in results.into_iter().filter_map(Result::err) {
+                error!("注册 SQL 工具失败: {}", e);
+            }
+        }
+        let custom_tools = self.custom_tools.read().clone();
+        if !custom_tools.is_empty() {
+            let security = Arc::new(SecurityManager::new(&base_dir));
+            for config in custom_tools.into_iter().filter(|t| t.enabled) {
+                let name = config.name.clone();
+                let result = CommandTool::new(config, security.clone())
+                    .and_then(|tool| registry.register(tool).map_err(|e| e.to_string()));
+                if let Err(e) = result {
+                    error!("注册自定义工具 {} 失败: {}", name, e);
+                }
+            }
+        }
+        for tool in self.wasm_plugins.tools() {
+            if let Err(e) = registry.register(tool) {
+                error!("注册 WASM 插件工具失败: {}", e);
+            }
+        }
         Ok(Arc::new(registry))
     }
 
-    /// 创建临时 Agent 用于异步操作
-    fn create_temp_agent(&self) -> Result<NativeAgent, String> {
+    /// 创建临时 Agent 用于异步操作
+    ///
+    /// 会话指定了配置档案时使用该档案，否则使用激活的档案；都未设置时连接初始化时的端点
+    fn create_temp_agent(&self, session_id: Option<&str>) -> Result<NativeAgent, AgentError> {
+        let guard = self.agent.read();
+        let agent = guard.as_ref().ok_or(AgentError::NotInitialized)?;
+
+        let client = build_http_client(&agent.base_url)?;
+
+        let protocol = create_protocol(agent.provider_type);
+
+        let mut temp_agent = NativeAgent {
+            client,
+            base_url: agent.base_url.clone(),
+            api_key: agent.api_key.clone(),
+            sessions: agent.sessions.clone(),
+            config: agent.config.clone(),
+            provider_type: agent.provider_type,
+            protocol,
+            image_options: self.image_options.read().clone(),
+            fallbacks: self.fallbacks.read().clone(),
+            transcripts: self.transcripts.clone(),
+            context_fallback: self.context_fallback.read().clone(),
+            usage_recorder: self.usage_recorder.clone(),
+            ocr_send_images: self.ocr.read().send_images,
+        };
+
+        let session_profile = session_id.and_then(|id| {
+            agent
+                .sessions
+                .read()
+                .get(id)
+                .and_then(|s| s.profile.clone())
+        });
+        let profiles = self.profiles.read();
+        if let Some(name) = session_profile.or_else(|| profiles.active.clone()) {
+            match profiles.get(&name) {
+                Some(profile) => {
+                    temp_agent.apply_profile(profile);
+                    // 档案可能指向其他端点，按新地址重建客户端
+                    temp_agent.client = build_http_client(&temp_agent.base_url)?;
+                }
+                None => warn!("[NativeAgent] 配置档案不存在，使用默认端点: {}", name),
+            }
+        }
+        Ok(temp_agent)
+    }
+
+    /// 检索知识库集合中与消息相关的内容作为本轮的参考资料（未指定集合时使用会话关联的集合）
+    ///
+    /// 通过 Agent 连接的 ProxyCast 服务器向量化查询文本；检索失败只记录警告，不影响对话
+    pub async fn retrieve_knowledge(
+        &self,
+        session_id: Option<&str>,
+        collection: Option<String>,
+        message: &str,
+    ) -> Option<String> {
+        let store = self.knowledge.as_ref()?;
+        if message.trim().is_empty() {
+            return None;
+        }
+        let collection = collection.or_else(|| {
+            session_id
+                .and_then(|sid| self.get_session(sid).ok().flatten())
+                .and_then(|s| s.knowledge_collection)
+        })?;
+        let client = {
+            let guard = self.agent.read();
+            let agent = guard.as_ref()?;
+            EmbeddingClient::new(agent.base_url.clone(), agent.api_key.clone())
+        };
+        let default_model = self.embedding_model.read().clone();
+        let result = match client {
+            Ok(client) => {
+                retrieve_context(
+                    &client,
+                    store,
+                    &collection,
+                    message,
+                    &default_model,
+                    RETRIEVAL_TOP_K,
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(context) if !context.is_empty() => Some(context),
+            Ok(_) => None,
+            Err(e) => {
+                warn!("[NativeAgent] 检索知识库失败，忽略: {}", e);
+                None
+            }
+        }
+    }
+
+    /// 请求未带参考资料时检索会话关联的知识库
+    async fn with_knowledge_context(&self, mut request: NativeChatRequest) -> NativeChatRequest {
+        if request.context.is_none() {
+            request.context = self
+                .retrieve_knowledge(request.session_id.as_deref(), None, &request.message)
+                .await;
+        }
+        request
+    }
+
+    /// 处理长期记忆：从用户消息中自动提取新记忆，并在会话首轮对话前注入相关记忆
+    fn apply_memories(&self, agent: &NativeAgent, request: &NativeChatRequest) {
+        let Some(store) = &self.memory else {
+            return;
+        };
+
+        for candidate in extract_memory_candidates(&request.message) {
+            if let Err(e) = store.add(&candidate, "auto") {
+                warn!("[NativeAgent] 自动保存记忆失败: {}", e);
+            }
+        }
+
+        let Some(session_id) = &request.session_id else {
+            return;
+        };
+        let Some((memory_prompt, count)) = self.first_turn_memory_prompt(agent, request) else {
+            return;
+        };
+        if let Some(session) = agent.sessions.write().get_mut(session_id) {
+            session.memory_prompt = Some(memory_prompt);
+            info!(
+                "[NativeAgent] 会话 {} 注入 {} 条长期记忆",
+                session_id, count
+            );
+        }
+    }
+
+    /// 会话首轮对话前需要注入的长期记忆提示词和记忆条数（非首轮或没有相关记忆时返回 None）
+    fn first_turn_memory_prompt(
+        &self,
+        agent: &NativeAgent,
+        request: &NativeChatRequest,
+    ) -> Option<(String, usize)> {
+        let store = self.memory.as_ref()?;
+        let session_id = request.session_id.as_ref()?;
+        let is_first_turn = agent
+            .sessions
+            .read()
+            .get(session_id)
+            .map(|s| s.messages.is_empty())
+            .unwrap_or(false);
+        if !is_first_turn {
+            return None;
+        }
+
+        match store.relevant(&request.message, MAX_INJECTED_MEMORIES) {
+            Ok(memories) if !memories.is_empty() => {
+                Some((build_memory_prompt(&memories), memories.len()))
+            }
+            Ok(_) => None,
+            Err(e) => {
+                warn!("[NativeAgent] 检索长期记忆失败: {}", e);
+                None
+            }
+        }
+    }
+
+    /// 预览本轮请求（含首轮注入的长期记忆和工具定义），不发送也不修改会话
+    pub fn preview_request(
+        &self,
+        request: &NativeChatRequest,
+        tools: Option<&[crate::models::openai::Tool]>,
+    ) -> Result<RequestPreview, AgentError> {
+        let temp_agent = self.create_temp_agent(request.session_id.as_deref())?;
+        let memory_prompt = self
+            .first_turn_memory_prompt(&temp_agent, request)
+            .map(|(prompt, _)| prompt);
+        temp_agent.preview_request(request, tools, memory_prompt.as_deref())
+    }
+
+    pub async fn chat(&self, request: NativeChatRequest) -> Result<NativeChatResponse, AgentError> {
+        let request = self.with_knowledge_context(request).await;
+        let temp_agent = self.create_temp_agent(request.session_id.as_deref())?;
+        self.apply_memories(&temp_agent, &request);
+        let session_id = request.session_id.clone();
+        let since = chrono::Utc::now();
+        let result = temp_agent.chat(request).await;
+        self.record_skill_usage(session_id.as_deref(), since);
+        result
+    }
+
+    pub async fn chat_stream(
+        &self,
+        request: NativeChatRequest,
+        tx: mpsc::Sender<StreamEvent>,
+    ) -> Result<StreamResult, AgentError> {
+        let request = self.with_knowledge_context(request).await;
+        let temp_agent = self.create_temp_agent(request.session_id.as_deref())?;
+        self.apply_memories(&temp_agent, &request);
+        let session_id = request.session_id.clone();
+        let since = chrono::Utc::now();
+        let result = temp_agent.chat_stream(request, None, tx).await;
+        self.record_skill_usage(session_id.as_deref(), since);
+        result
+    }
+
+    pub async fn chat_stream_with_tools(
+        &self,
+        request: NativeChatRequest,
+        tx: mpsc::Sender<StreamEvent>,
+        tool_loop_engine: &ToolLoopEngine,
+    ) -> Result<StreamResult, AgentError> {
+        let request = self.with_knowledge_context(request).await;
+        let temp_agent = self.create_temp_agent(request.session_id.as_deref())?;
+        self.apply_memories(&temp_agent, &request);
+        let session_id = request.session_id.clone();
+        let since = chrono::Utc::now();
+        let result = temp_agent
+            .chat_stream_with_tools(request, tx, tool_loop_engine)
+            .await;
+        self.record_skill_usage(session_id.as_deref(), since);
+        result
+    }
+
+    pub async fn generate_image(
+        &self,
+        prompt: &str,
+        size: Option<String>,
+        model: Option<String>,
+    ) -> Result<ImageGenerationResult, String> {
+        let temp_agent = self.create_temp_agent(None)?;
+        temp_agent.generate_image(prompt, size, model).await
+    }
+
+    pub async fn complete(
+        &self,
+        system_prompt: &str,
+        prompt: &str,
+        model: Option<String>,
+        max_tokens: Option<u32>,
+        temperature: Option<f32>,
+    ) -> Result<String, String> {
+        let temp_agent = self.create_temp_agent(None)?;
+        temp_agent
+            .complete(system_prompt, prompt, model, max_tokens, temperature)
+            .await
+    }
+
+    /// 登记流式对话，返回取消信号接收端
+    pub fn register_stream(&self, info: StreamInfo) -> mpsc::Receiver<CancelMode> {
+        let (tx, rx) = mpsc::channel(1);
+        self.active_streams
+            .write()
+            .insert(info.stream_id.clone(), ActiveStream { info, cancel: tx });
+        rx
+    }
+
+    /// 流式对话结束后注销
+    pub fn unregister_stream(&self, stream_id: &str) {
+        self.active_streams.write().remove(stream_id);
+    }
+
+    /// 中断流式对话，返回是否找到进行中的对话
+    pub fn cancel_stream(&self, stream_id: &str, mode: CancelMode) -> bool {
+        match self.active_streams.write().remove(stream_id) {
+            Some(stream) => stream.cancel.try_send(mode).is_ok(),
+            None => false,
+        }
+    }
+
+    /// 列出进行中的流式对话（按开始时间排序）
+    pub fn list_streams(&self) -> Vec<StreamInfo> {
+        let mut streams: Vec<StreamInfo> = self
+            .active_streams
+            .read()
+            .values()
+            .map(|s| s.info.clone())
+            .collect();
+        streams.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+        streams
+    }
+
+    /// 查询流式对话（已结束时返回 None）
+    pub fn get_stream(&self, stream_id: &str) -> Option<StreamInfo> {
+        self.active_streams
+            .read()
+            .get(stream_id)
+            .map(|s| s.info.clone())
+    }
+
+    pub fn settle_cancelled_turn(
+        &self,
+        session_id: &str,
+        turn_start: usize,
+        user_message: &str,
+        partial: &str,
+        mode: CancelMode,
+        model: Option<&str>,
+    ) -> Result<(), AgentError> {
+        let guard = self.agent.read();
+        let agent = guard.as_ref().ok_or(AgentError::NotInitialized)?;
+        agent.settle_cancelled_turn(session_id, turn_start, user_message, partial, mode, model)
+    }
+
+    pub fn edit_message(
+        &self,
+        session_id: &str,
+        index: usize,
+        content: String,
+    ) -> Result<(), AgentError> {
         let guard = self.agent.read();
-        let agent = guard.as_ref().ok_or_else(|| "Agent 未初始化".to_string())?;
-
-        let client = Client::builder()
-            .timeout(Duration::from_secs(300))
-            .connect_timeout(Duration::from_secs(30))
-            .no_proxy()
-            .build()
-            .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+        let agent = guard.as_ref().ok_or(AgentError::NotInitialized)?;
+        agent.edit_message(session_id, index, content)
+    }
 
-        let protocol = create_protocol(agent.provider_type);
+    pub fn delete_message(&self, session_id: &str, index: usize) -> Result<usize, AgentError> {
+        let guard = self.agent.read();
+        let agent = guard.as_ref().ok_or(AgentError::NotInitialized)?;
+        agent.delete_message(session_id, index)
+    }
 
-        Ok(NativeAgent {
-            client,
-            base_url: agent.base_url.clone(),
-            api_key: agent.api_key.clone(),
-            sessions: agent.sessions.clone(),
-            config: agent.config.clone(),
-            provider_type: agent.provider_type,
-            protocol,
-        })
+    pub fn take_last_answer(&self, session_id: &str) -> Result<RegenerateTurn, AgentError> {
+        let guard = self.agent.read();
+        let agent = guard.as_ref().ok_or(AgentError::NotInitialized)?;
+        agent.take_last_answer(session_id)
     }
 
-    pub async fn chat(&self, request: NativeChatRequest) -> Result<NativeChatResponse, String> {
-        let temp_agent = self.create_temp_agent()?;
-        temp_agent.chat(request).await
+    pub fn restore_answer(&self, session_id: &str, turn: RegenerateTurn) -> Result<(), AgentError> {
+        let guard = self.agent.read();
+        let agent = guard.as_ref().ok_or(AgentError::NotInitialized)?;
+        agent.restore_answer(session_id, turn)
     }
 
-    pub async fn chat_stream(
+    /// 以会话现有历史重新生成回答（流式，支持工具调用）
+    pub async fn continue_stream_with_tools(
         &self,
-        request: NativeChatRequest,
+        session_id: &str,
+        model: Option<String>,
         tx: mpsc::Sender<StreamEvent>,
-    ) -> Result<StreamResult, String> {
-        let temp_agent = self.create_temp_agent()?;
-        temp_agent.chat_stream(request, None, tx).await
+        tool_loop_engine: &ToolLoopEngine,
+    ) -> Result<StreamResult, AgentError> {
+        let temp_agent = self.create_temp_agent(Some(session_id))?;
+        let since = chrono::Utc::now();
+        let result = temp_agent
+            .continue_stream_with_tools(session_id, model, tx, tool_loop_engine)
+            .await;
+        self.record_skill_usage(Some(session_id), since);
+        result
     }
 
-    pub async fn chat_stream_with_tools(
+    pub async fn replay_turn(
         &self,
-        request: NativeChatRequest,
-        tx: mpsc::Sender<StreamEvent>,
-        tool_loop_engine: &ToolLoopEngine,
-    ) -> Result<StreamResult, String> {
-        let temp_agent = self.create_temp_agent()?;
-        temp_agent
-            .chat_stream_with_tools(request, tx, tool_loop_engine)
-            .await
+        session_id: &str,
+        turn_id: usize,
+        overrides: ReplayOverrides,
+    ) -> Result<ReplayResult, AgentError> {
+        let temp_agent = self.create_temp_agent(Some(session_id))?;
+        temp_agent.replay_turn(session_id, turn_id, overrides).await
     }
 
     pub fn create_session(
         &self,
         model: Option<String>,
         system_prompt: Option<String>,
-    ) -> Result<String, String> {
+    ) -> Result<String, AgentError> {
         let guard = self.agent.read();
-        let agent = guard.as_ref().ok_or_else(|| "Agent 未初始化".to_string())?;
+        let agent = guard.as_ref().ok_or(AgentError::NotInitialized)?;
+
+        let (sessions, messages) = {
+            let sessions = agent.sessions.read();
+            let messages: usize = sessions.values().map(|s| s.messages.len()).sum();
+            (sessions.len() as u32, messages as u32)
+        };
+        if quota_level(sessions, messages, &self.session_quota.read()) == QuotaLevel::Exceeded {
+            return Err(AgentError::Other(format!(
+                "会话存储已达到上限（{} 个会话、{} 条消息），请归档或删除旧会话后再创建",
+                sessions, messages
+            )));
+        }
+
         Ok(agent.create_session(model, system_prompt))
     }
 
-    pub fn get_session(&self, session_id: &str) -> Result<Option<AgentSession>, String> {
+    pub fn get_session(&self, session_id: &str) -> Result<Option<AgentSession>, AgentError> {
         let guard = self.agent.read();
-        let agent = guard.as_ref().ok_or_else(|| "Agent 未初始化".to_string())?;
+        let agent = guard.as_ref().ok_or(AgentError::NotInitialized)?;
         Ok(agent.get_session(session_id))
     }
 
     pub fn delete_session(&self, session_id: &str) -> bool {
+        self.followups.cancel_session(session_id);
+        self.patches.reject_session(session_id);
+        self.script_reviews.reject_session(session_id);
+        self.tool_approvals.reject_session(session_id);
+        self.close_browser_page(session_id);
         let guard = self.agent.read();
         if let Some(agent) = guard.as_ref() {
             agent.delete_session(session_id)
@@ -773,6 +3238,13 @@ impl NativeAgentState {
         }
     }
 
+    /// 在后台关闭会话的浏览器标签页
+    fn close_browser_page(&self, session_id: &str) {
+        let browser = self.browser.clone();
+        let session_id = session_id.to_string();
+        tauri::async_runtime::spawn(async move { browser.close_session(&session_id).await });
+    }
+
     pub fn list_sessions(&self) -> Vec<AgentSession> {
         let guard = self.agent.read();
         if let Some(agent) = guard.as_ref() {
@@ -782,6 +3254,163 @@ impl NativeAgentState {
         }
     }
 
+    pub fn upsert_newer_sessions(&self, sessions: Vec<AgentSession>) -> Result<usize, AgentError> {
+        let guard = self.agent.read();
+        let agent = guard.as_ref().ok_or(AgentError::NotInitialized)?;
+        Ok(agent.upsert_newer_sessions(sessions))
+    }
+
+    pub fn append_quick_ask(
+        &self,
+        prompt: &str,
+        answer: &str,
+        max_messages: usize,
+    ) -> Result<String, AgentError> {
+        let guard = self.agent.read();
+        let agent = guard.as_ref().ok_or(AgentError::NotInitialized)?;
+        Ok(agent.append_quick_ask(prompt, answer, max_messages))
+    }
+
+    pub fn bulk_delete_sessions(
+        &self,
+        ids: &[String],
+        on_progress: &mut dyn FnMut(BulkProgress),
+    ) -> Result<usize, String> {
+        let guard = self.agent.read();
+        let agent = guard.as_ref().ok_or(AgentError::NotInitialized)?;
+        let removed = agent.bulk_delete_sessions(ids, on_progress)?;
+        for session in &removed {
+            self.followups.cancel_session(&session.id);
+            self.patches.reject_session(&session.id);
+            self.script_reviews.reject_session(&session.id);
+            self.tool_approvals.reject_session(&session.id);
+            self.close_browser_page(&session.id);
+        }
+        Ok(removed.len())
+    }
+
+    pub fn bulk_tag_sessions(
+        &self,
+        ids: &[String],
+        add: &[String],
+        remove: &[String],
+        on_progress: &mut dyn FnMut(BulkProgress),
+    ) -> Result<usize, String> {
+        let guard = self.agent.read();
+        let agent = guard.as_ref().ok_or(AgentError::NotInitialized)?;
+        agent.bulk_tag_sessions(ids, add, remove, on_progress)
+    }
+
+    pub fn bulk_collect_sessions(
+        &self,
+        ids: &[String],
+        on_progress: &mut dyn FnMut(BulkProgress),
+    ) -> Result<Vec<AgentSession>, String> {
+        let guard = self.agent.read();
+        let agent = guard.as_ref().ok_or(AgentError::NotInitialized)?;
+        agent.bulk_collect_sessions(ids, on_progress)
+    }
+
+    pub fn set_session_collection(&self, session_id: &str, collection: Option<String>) -> bool {
+        let guard = self.agent.read();
+        if let Some(agent) = guard.as_ref() {
+            agent.set_session_collection(session_id, collection)
+        } else {
+            false
+        }
+    }
+
+    /// 设置会话关联的 git 仓库（校验路径是 git 仓库并规范化为仓库根目录），会话不存在时返回错误
+    pub async fn set_session_repo(
+        &self,
+        session_id: &str,
+        repo_path: Option<String>,
+    ) -> Result<Option<String>, AgentError> {
+        let repo_path = match repo_path {
+            Some(path) => Some(
+                crate::agent::tools::git::resolve_repo(&path)
+                    .await
+                    .map_err(AgentError::InvalidRequest)?
+                    .to_string_lossy()
+                    .into_owned(),
+            ),
+            None => None,
+        };
+        let guard = self.agent.read();
+        let agent = guard.as_ref().ok_or(AgentError::NotInitialized)?;
+        if agent.set_session_repo(session_id, repo_path.clone()) {
+            Ok(repo_path)
+        } else {
+            Err(AgentError::SessionNotFound(session_id.to_string()))
+        }
+    }
+
+    /// 设置会话 `http_request` 工具允许访问的域名（规范化后返回），会话不存在时返回错误
+    pub fn set_session_http_domains(
+        &self,
+        session_id: &str,
+        domains: &[String],
+    ) -> Result<Vec<String>, AgentError> {
+        let domains = crate::agent::tools::http_request::normalize_domains(domains)
+            .map_err(AgentError::InvalidRequest)?;
+        let guard = self.agent.read();
+        let agent = guard.as_ref().ok_or(AgentError::NotInitialized)?;
+        if agent.set_session_http_domains(session_id, domains.clone()) {
+            Ok(domains)
+        } else {
+            Err(AgentError::SessionNotFound(session_id.to_string()))
+        }
+    }
+
+    /// 设置会话的工具权限模式（None 表示使用全局配置），会话不存在时返回错误
+    pub fn set_session_permission_mode(
+        &self,
+        session_id: &str,
+        mode: Option<crate::config::PermissionMode>,
+    ) -> Result<(), AgentError> {
+        let guard = self.agent.read();
+        let agent = guard.as_ref().ok_or(AgentError::NotInitialized)?;
+        if agent.set_session_permission_mode(session_id, mode) {
+            Ok(())
+        } else {
+            Err(AgentError::SessionNotFound(session_id.to_string()))
+        }
+    }
+
+    /// 修改会话元数据，会话不存在时返回错误
+    pub fn update_session_meta(
+        &self,
+        session_id: &str,
+        update: &SessionMetaUpdate,
+    ) -> Result<(), AgentError> {
+        let guard = self.agent.read();
+        let agent = guard.as_ref().ok_or(AgentError::NotInitialized)?;
+        if agent.update_session_meta(session_id, update) {
+            Ok(())
+        } else {
+            Err(AgentError::SessionNotFound(session_id.to_string()))
+        }
+    }
+
+    /// 设置会话使用的 Provider 配置档案，档案不存在时返回错误
+    pub fn set_session_profile(
+        &self,
+        session_id: &str,
+        profile: Option<String>,
+    ) -> Result<bool, AgentError> {
+        if let Some(name) = &profile {
+            if self.profiles.read().get(name).is_none() {
+                return Err(AgentError::InvalidRequest(format!(
+                    "配置档案不存在: {}",
+                    name
+                )));
+            }
+        }
+        let guard = self.agent.read();
+        let agent = guard.as_ref().ok_or(AgentError::NotInitialized)?;
+        Ok(agent.set_session_profile(session_id, profile))
+    }
+
     pub fn clear_session_messages(&self, session_id: &str) -> bool {
         let guard = self.agent.read();
         if let Some(agent) = guard.as_ref() {
@@ -791,6 +3420,17 @@ impl NativeAgentState {
         }
     }
 
+    pub fn update_session_system_prompt(
+        &self,
+        session_id: &str,
+        update: impl FnOnce(Option<String>) -> Option<String>,
+    ) -> bool {
+        let guard = self.agent.read();
+        guard
+            .as_ref()
+            .is_some_and(|a| a.update_session_system_prompt(session_id, update))
+    }
+
     pub fn get_session_messages(&self, session_id: &str) -> Option<Vec<AgentMessage>> {
         let guard = self.agent.read();
         guard
@@ -803,6 +3443,7 @@ impl NativeAgentState {
 mod tests {
     use super::*;
     use crate::agent::parsers::OpenAISSEParser;
+    use crate::agent::test_support::{self, text_message as msg};
 
     #[test]
     fn test_sse_parser_text_delta() {
@@ -827,6 +3468,736 @@ mod tests {
         assert_eq!(parser.get_full_content(), "Hello World");
     }
 
+    #[test]
+    fn test_cancelled_turn_is_recorded_with_turn_model() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut agent = NativeAgent::new(
+            "http://127.0.0.1:8999".to_string(),
+            "key".to_string(),
+            ProviderType::OpenAI,
+        )
+        .unwrap();
+        agent.transcripts = TranscriptLogger::new(dir.path().to_path_buf());
+        agent.transcripts.set_enabled(true);
+        let session_id = agent.create_session(Some("gpt-4o".to_string()), None);
+
+        agent
+            .settle_cancelled_turn(
+                &session_id,
+                0,
+                "hello",
+                "partial",
+                CancelMode::Keep,
+                Some("gpt-4o-mini"),
+            )
+            .unwrap();
+
+        let entries = agent.transcripts.read(&session_id).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].role, "user");
+        assert_eq!(entries[1].content, "partial");
+        assert!(entries
+            .iter()
+            .all(|e| e.model.as_deref() == Some("gpt-4o-mini")));
+    }
+
+    #[test]
+    fn test_locate_turn() {
+        let messages = vec![
+            msg("user", "first"),
+            msg("assistant", "answer 1"),
+            msg("user", "second"),
+            msg("assistant", ""),
+            msg("tool", "ls output"),
+            msg("assistant", "answer 2"),
+        ];
+
+        assert_eq!(
+            locate_turn(&messages, 0),
+            Some((0, Some("answer 1".to_string())))
+        );
+        assert_eq!(
+            locate_turn(&messages, 1),
+            Some((2, Some("answer 2".to_string())))
+        );
+        assert_eq!(locate_turn(&messages, 2), None);
+    }
+
+    #[test]
+    fn test_replace_message_text_keeps_images() {
+        let mut message = msg("user", "");
+        message.content = MessageContent::Parts(vec![
+            ContentPart::Text {
+                text: "old".to_string(),
+            },
+            ContentPart::ImageUrl {
+                image_url: ImageUrl {
+                    url: "data:image/png;base64,AAAA".to_string(),
+                    detail: None,
+                },
+            },
+        ]);
+
+        replace_message_text(&mut message, "new".to_string()).unwrap();
+        match &message.content {
+            MessageContent::Parts(parts) => {
+                assert_eq!(parts.len(), 2);
+                assert!(matches!(&parts[0], ContentPart::Text { text } if text == "new"));
+            }
+            _ => panic!("应保留多部分内容"),
+        }
+        assert!(replace_message_text(&mut msg("tool", "x"), "y".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_remove_message_drops_tool_results() {
+        let mut assistant = msg("assistant", "");
+        assistant.tool_calls = Some(vec![ToolCall {
+            id: "call_1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "bash".to_string(),
+                arguments: "{}".to_string(),
+            },
+        }]);
+        let mut tool = msg("tool", "ok");
+        tool.tool_call_id = Some("call_1".to_string());
+
+        let mut messages = vec![
+            msg("user", "run"),
+            assistant,
+            tool,
+            msg("assistant", "done"),
+        ];
+
+        assert!(remove_message(&mut messages, 2).is_err());
+        assert_eq!(remove_message(&mut messages, 1).unwrap(), 2);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].content.as_text(), "done");
+        assert!(remove_message(&mut messages, 5).is_err());
+    }
+
+    fn tool_call(id: &str) -> ToolCall {
+        ToolCall {
+            id: id.to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "bash".to_string(),
+                arguments: "{}".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_settle_cancelled_discard() {
+        let mut assistant = msg("assistant", "");
+        assistant.tool_calls = Some(vec![tool_call("call_1")]);
+        let mut messages = vec![msg("user", "hi"), msg("assistant", "hello")];
+        messages.push(msg("user", "run"));
+        messages.push(assistant);
+
+        settle_cancelled_messages(&mut messages, 2, "run", "partial", CancelMode::Discard);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].content.as_text(), "hello");
+    }
+
+    #[test]
+    fn test_settle_cancelled_keep_before_commit() {
+        let mut messages = vec![msg("user", "hi"), msg("assistant", "hello")];
+
+        settle_cancelled_messages(&mut messages, 2, "explain", "Once upon", CancelMode::Keep);
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[2].role, "user");
+        assert_eq!(messages[2].content.as_text(), "explain");
+        assert_eq!(messages[3].content.as_text(), "Once upon");
+        assert_eq!(messages[3].truncated, Some(true));
+    }
+
+    #[test]
+    fn test_settle_cancelled_keep_during_tools() {
+        let mut assistant = msg("assistant", "checking");
+        assistant.tool_calls = Some(vec![tool_call("call_1"), tool_call("call_2")]);
+        let mut result = msg("tool", "ok");
+        result.tool_call_id = Some("call_1".to_string());
+        let mut messages = vec![msg("user", "run"), assistant, result];
+
+        // 部分回复已随工具调用写入历史，不重复写入
+        settle_cancelled_messages(&mut messages, 0, "run", "checking", CancelMode::Keep);
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[3].tool_call_id.as_deref(), Some("call_2"));
+        assert_eq!(messages[3].content.as_text(), "工具调用已取消");
+    }
+
+    #[test]
+    fn test_stream_registry() {
+        let state = NativeAgentState::new();
+        let mut rx_a = state.register_stream(StreamInfo::new("a", Some("s1".to_string()), None));
+        let _rx_b = state.register_stream(StreamInfo::new("b", Some("s2".to_string()), None));
+        assert_eq!(state.list_streams().len(), 2);
+        assert_eq!(
+            state.get_stream("b").and_then(|s| s.session_id).as_deref(),
+            Some("s2")
+        );
+
+        // 取消只影响对应的流
+        assert!(state.cancel_stream("a", CancelMode::Keep));
+        assert_eq!(rx_a.try_recv().ok(), Some(CancelMode::Keep));
+        assert!(!state.cancel_stream("a", CancelMode::Keep));
+        assert!(state.get_stream("a").is_none());
+        assert!(state.get_stream("b").is_some());
+
+        state.unregister_stream("b");
+        assert!(state.list_streams().is_empty());
+    }
+
+    #[test]
+    fn test_endpoints_follow_fallback_order() {
+        let mut agent = NativeAgent::new(
+            "http://127.0.0.1:8999".to_string(),
+            "key".to_string(),
+            ProviderType::OpenAI,
+        )
+        .unwrap();
+        agent.fallbacks = vec![AgentFallbackEndpoint {
+            name: "backup".to_string(),
+            base_url: "https://backup.example.com/".to_string(),
+            api_key: "backup-key".to_string(),
+            provider: "claude".to_string(),
+            model: Some("claude-sonnet-4".to_string()),
+        }];
+
+        let endpoints = agent.endpoints();
+        assert_eq!(endpoints.len(), 2);
+        assert_eq!(endpoints[0].name, PRIMARY_ENDPOINT);
+        assert_eq!(endpoints[1].base_url, "https://backup.example.com");
+        assert_eq!(endpoints[1].protocol.endpoint(), "/v1/messages");
+        assert_eq!(endpoints[1].model.as_deref(), Some("claude-sonnet-4"));
+    }
+
+    /// 模拟上游：首次请求返回上下文超长错误，之后返回回复（流式请求返回 SSE）；记录每次请求的消息数
+    async fn start_overflow_upstream() -> (String, Arc<parking_lot::Mutex<Vec<usize>>>) {
+        use axum::{extract::State, response::IntoResponse, routing::post, Json, Router};
+
+        type Requests = Arc<parking_lot::Mutex<Vec<usize>>>;
+        async fn handler(
+            State(requests): State<Requests>,
+            Json(body): Json<serde_json::Value>,
+        ) -> axum::response::Response {
+            let count = body["messages"].as_array().map_or(0, |m| m.len());
+            let first = {
+                let mut requests = requests.lock();
+                requests.push(count);
+                requests.len() == 1
+            };
+            if first {
+                let error = r#"{"error":{"message":"This model's maximum context length is 8192 tokens","code":"context_length_exceeded"}}"#;
+                return (axum::http::StatusCode::BAD_REQUEST, error).into_response();
+            }
+            if body["stream"] != true {
+                return Json(serde_json::json!({
+                    "id": "chatcmpl-1",
+                    "object": "chat.completion",
+                    "created": 0,
+                    "model": "gpt-4o",
+                    "choices": [{
+                        "index": 0,
+                        "message": {"role": "assistant", "content": "ok"},
+                        "finish_reason": "stop"
+                    }],
+                    "usage": {"prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2}
+                }))
+                .into_response();
+            }
+            let sse = concat!(
+                "data: {\"choices\":[{\"delta\":{\"content\":\"ok\"}}]}\n\n",
+                "data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n",
+                "data: [DONE]\n\n"
+            );
+            ([("content-type", "text/event-stream")], sse).into_response()
+        }
+
+        let requests = Requests::default();
+        let app = Router::new()
+            .route("/v1/chat/completions", post(handler))
+            .with_state(requests.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+        (format!("http://{}", addr), requests)
+    }
+
+    #[tokio::test]
+    async fn test_overflow_compaction_keeps_stored_history() {
+        let (base_url, requests) = start_overflow_upstream().await;
+        let agent = NativeAgent::new(base_url, "key".to_string(), ProviderType::OpenAI).unwrap();
+        let session_id = agent.create_session(Some("gpt-4o".to_string()), None);
+        let long = "x".repeat(400);
+        let history = vec![
+            msg("user", &long),
+            msg("assistant", &long),
+            msg("user", &long),
+            msg("assistant", "ok"),
+        ];
+        agent
+            .sessions
+            .write()
+            .get_mut(&session_id)
+            .unwrap()
+            .messages = history.clone();
+
+        let (tx, mut rx) = mpsc::channel(100);
+        let events = tokio::spawn(async move {
+            let mut compacted = false;
+            while let Some(event) = rx.recv().await {
+                compacted |= matches!(event, StreamEvent::ContextCompacted { .. });
+            }
+            compacted
+        });
+        let request = NativeChatRequest {
+            session_id: Some(session_id.clone()),
+            message: "latest".to_string(),
+            model: None,
+            images: None,
+            audio: None,
+            attachments: None,
+            context: None,
+            stream: true,
+        };
+        let result = agent.chat_stream(request, None, tx).await.unwrap();
+        assert_eq!(result.content, "ok");
+        assert!(events.await.unwrap());
+
+        // 重试请求使用压缩后的历史
+        let requests = requests.lock().clone();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1] < requests[0]);
+
+        // 会话中保存的历史不受压缩影响，只追加本轮的用户消息和回复
+        let stored = agent.get_session(&session_id).unwrap().messages;
+        assert_eq!(stored.len(), history.len() + 2);
+        for (stored, original) in stored.iter().zip(&history) {
+            assert_eq!(stored.role, original.role);
+            assert_eq!(stored.content.as_text(), original.content.as_text());
+        }
+        assert_eq!(stored[4].content.as_text(), "latest");
+        assert_eq!(stored[5].content.as_text(), "ok");
+    }
+
+    #[tokio::test]
+    async fn test_non_streaming_chat_compacts_on_overflow() {
+        let (base_url, requests) = start_overflow_upstream().await;
+        let agent = NativeAgent::new(base_url, "key".to_string(), ProviderType::OpenAI).unwrap();
+        let session_id = agent.create_session(Some("gpt-4o".to_string()), None);
+        let long = "x".repeat(400);
+        let history = vec![
+            msg("user", &long),
+            msg("assistant", &long),
+            msg("user", &long),
+            msg("assistant", "ok"),
+        ];
+        agent
+            .sessions
+            .write()
+            .get_mut(&session_id)
+            .unwrap()
+            .messages = history.clone();
+
+        let request = NativeChatRequest {
+            session_id: Some(session_id.clone()),
+            message: "latest".to_string(),
+            model: None,
+            images: None,
+            audio: None,
+            attachments: None,
+            context: None,
+            stream: false,
+        };
+        let response = agent.chat(request).await.unwrap();
+        assert!(response.success);
+        assert_eq!(response.content, "ok");
+
+        // 重试请求使用压缩后的历史
+        let requests = requests.lock().clone();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1] < requests[0]);
+
+        // 会话中保存的历史不受压缩影响
+        let stored = agent.get_session(&session_id).unwrap().messages;
+        assert_eq!(stored.len(), history.len() + 2);
+        assert_eq!(stored[0].content.as_text(), long);
+        assert_eq!(stored[4].content.as_text(), "latest");
+    }
+
+    #[tokio::test]
+    async fn test_context_fallback_is_announced_once_per_turn() {
+        use axum::{extract::State, response::IntoResponse, routing::post, Json, Router};
+
+        type Models = Arc<parking_lot::Mutex<Vec<String>>>;
+        async fn handler(
+            State(models): State<Models>,
+            Json(body): Json<serde_json::Value>,
+        ) -> axum::response::Response {
+            let first = {
+                let mut models = models.lock();
+                models.push(body["model"].as_str().unwrap_or_default().to_string());
+                models.len() == 1
+            };
+            let sse = if first {
+                concat!(
+                    "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"type\":\"function\",\"function\":{\"name\":\"missing_tool\",\"arguments\":\"{}\"}}]}}]}\n\n",
+                    "data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"tool_calls\"}]}\n\n",
+                    "data: [DONE]\n\n"
+                )
+            } else {
+                concat!(
+                    "data: {\"choices\":[{\"delta\":{\"content\":\"done\"}}]}\n\n",
+                    "data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n",
+                    "data: [DONE]\n\n"
+                )
+            };
+            ([("content-type", "text/event-stream")], sse).into_response()
+        }
+
+        let models = Models::default();
+        let app = Router::new()
+            .route("/v1/chat/completions", post(handler))
+            .with_state(models.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+
+        let mut agent = NativeAgent::new(
+            format!("http://{}", addr),
+            "key".to_string(),
+            ProviderType::OpenAI,
+        )
+        .unwrap();
+        agent.context_fallback = ContextFallbackConfig {
+            enabled: true,
+            models: vec!["big-model".to_string()],
+            reserve_output_tokens: 0,
+            context_windows: HashMap::from([
+                ("small-model".to_string(), 10),
+                ("big-model".to_string(), 100_000),
+            ]),
+            compact_on_overflow: false,
+        };
+        let session_id = agent.create_session(Some("small-model".to_string()), None);
+
+        let (tx, mut rx) = mpsc::channel(100);
+        let events = tokio::spawn(async move {
+            let mut fallbacks = 0;
+            while let Some(event) = rx.recv().await {
+                if matches!(event, StreamEvent::ContextFallback { .. }) {
+                    fallbacks += 1;
+                }
+            }
+            fallbacks
+        });
+        let request = NativeChatRequest {
+            session_id: Some(session_id.clone()),
+            message: "x".repeat(400),
+            model: Some("small-model".to_string()),
+            images: None,
+            audio: None,
+            attachments: None,
+            context: None,
+            stream: true,
+        };
+        let engine = ToolLoopEngine::new(Arc::new(ToolRegistry::new()));
+        let result = agent
+            .chat_stream_with_tools(request, tx, &engine)
+            .await
+            .unwrap();
+        assert_eq!(result.content, "done");
+        assert_eq!(events.await.unwrap(), 1);
+        assert_eq!(
+            *models.lock(),
+            vec!["big-model".to_string(), "big-model".to_string()]
+        );
+        // 回退只影响本轮请求，不改写会话的模型固定
+        let session = agent.get_session(&session_id).unwrap();
+        assert_eq!(session.model, "small-model");
+    }
+
+    #[tokio::test]
+    async fn test_regenerate_replaces_answer_and_restores_on_failure() {
+        let (base_url, requests) = start_overflow_upstream().await;
+        let agent = NativeAgent::new(base_url, "key".to_string(), ProviderType::OpenAI).unwrap();
+        let session_id = agent.create_session(Some("gpt-4o".to_string()), None);
+        let history = vec![
+            msg("user", "hello"),
+            msg("assistant", "hi"),
+            msg("user", "question"),
+            msg("assistant", "old answer"),
+        ];
+        agent
+            .sessions
+            .write()
+            .get_mut(&session_id)
+            .unwrap()
+            .messages = history.clone();
+
+        // 失败时恢复原回答
+        let turn = agent.take_last_answer(&session_id).unwrap();
+        assert_eq!(turn.user_index, 2);
+        assert_eq!(turn.user_message, "question");
+        assert_eq!(agent.get_session(&session_id).unwrap().messages.len(), 3);
+        agent.restore_answer(&session_id, turn).unwrap();
+        let restored = agent.get_session(&session_id).unwrap().messages;
+        assert_eq!(restored.len(), 4);
+        assert_eq!(restored[3].content.as_text(), "old answer");
+
+        // 重新生成走流式工具循环，新回答替换原回答
+        agent.take_last_answer(&session_id).unwrap();
+        let engine = ToolLoopEngine::new(Arc::new(ToolRegistry::new()));
+        let (tx, mut rx) = mpsc::channel(100);
+        let events = tokio::spawn(async move {
+            let mut final_done = false;
+            while let Some(event) = rx.recv().await {
+                final_done |= matches!(event, StreamEvent::FinalDone { .. });
+            }
+            final_done
+        });
+        let result = agent
+            .continue_stream_with_tools(&session_id, None, tx, &engine)
+            .await
+            .unwrap();
+        assert_eq!(result.content, "ok");
+        assert!(events.await.unwrap());
+        assert!(!requests.lock().is_empty());
+
+        let stored = agent.get_session(&session_id).unwrap().messages;
+        assert_eq!(stored.len(), 4);
+        assert_eq!(stored[2].content.as_text(), "question");
+        assert_eq!(stored[3].content.as_text(), "ok");
+    }
+
+    #[tokio::test]
+    async fn test_generate_image_keeps_format_and_checks_download() {
+        use axum::{routing::get, routing::post, Router};
+
+        let mut jpeg = Vec::new();
+        image::DynamicImage::new_rgb8(1, 1)
+            .write_to(
+                &mut std::io::Cursor::new(&mut jpeg),
+                image::ImageFormat::Jpeg,
+            )
+            .unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let image_url = format!("{}/files/image", base_url);
+        let app = Router::new()
+            .route(
+                "/v1/images/generations",
+                post(move |axum::Json(body): axum::Json<serde_json::Value>| {
+                    let image_url = image_url.clone();
+                    async move {
+                        let url = if body["prompt"] == "missing" {
+                            format!("{}-missing", image_url)
+                        } else {
+                            image_url
+                        };
+                        axum::Json(serde_json::json!({"data": [{"url": url}]}))
+                    }
+                }),
+            )
+            .route("/files/image", get(move || async move { jpeg }))
+            .route(
+                "/files/image-missing",
+                get(|| async { (axum::http::StatusCode::NOT_FOUND, "not found") }),
+            );
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+
+        let agent = NativeAgent::new(base_url, "key".to_string(), ProviderType::OpenAI).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let result = agent
+            .generate_image_in(dir.path(), "a cat", None, None)
+            .await
+            .unwrap();
+        assert!(result.path.ends_with(".jpg"), "{}", result.path);
+        assert!(std::path::Path::new(&result.path).exists());
+
+        // 下载失败时不保存错误页面
+        let err = agent
+            .generate_image_in(dir.path(), "missing", None, None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("404"), "{}", err);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_upsert_keeps_newer_local_sessions() {
+        let agent = NativeAgent::new(
+            "http://127.0.0.1:8999".to_string(),
+            "key".to_string(),
+            ProviderType::OpenAI,
+        )
+        .unwrap();
+        let at = |id: &str, updated_at: &str, text: &str| AgentSession {
+            updated_at: updated_at.to_string(),
+            ..test_support::session(id, vec![msg("user", text)])
+        };
+        assert_eq!(
+            agent.upsert_newer_sessions(vec![at("a", "2024-05-01T10:00:00Z", "local")]),
+            1
+        );
+
+        // 同步期间本地已更新：拉取的旧版本不覆盖
+        assert_eq!(
+            agent.upsert_newer_sessions(vec![at("a", "2024-05-01T09:00:00Z", "remote")]),
+            0
+        );
+        assert_eq!(
+            agent.get_session_messages("a").unwrap()[0]
+                .content
+                .as_text(),
+            "local"
+        );
+        assert_eq!(
+            agent.upsert_newer_sessions(vec![at("a", "2024-05-01T11:00:00Z", "remote")]),
+            1
+        );
+        assert_eq!(
+            agent.get_session_messages("a").unwrap()[0]
+                .content
+                .as_text(),
+            "remote"
+        );
+    }
+
+    #[test]
+    fn test_preview_uses_provider_protocol() {
+        let agent = NativeAgent::new(
+            "http://127.0.0.1:8999".to_string(),
+            "key".to_string(),
+            ProviderType::Claude,
+        )
+        .unwrap()
+        .with_system_prompt("be brief".to_string());
+        let request = NativeChatRequest {
+            session_id: None,
+            message: "hi".to_string(),
+            model: Some("claude-sonnet-4".to_string()),
+            images: None,
+            audio: None,
+            attachments: None,
+            context: None,
+            stream: true,
+        };
+
+        let preview = agent.preview_request(&request, None, None).unwrap();
+        assert_eq!(preview.endpoint, "/v1/messages");
+        assert_eq!(preview.request["system"], "be brief");
+        assert_eq!(preview.request["messages"][0]["role"], "user");
+        assert_eq!(preview.request["messages"][0]["content"], "hi");
+        assert!(preview.request.get("tools").is_none());
+    }
+
+    #[test]
+    fn test_request_context_only_in_outgoing_system_prompt() {
+        let agent = NativeAgent::new(
+            "http://127.0.0.1:8999".to_string(),
+            "key".to_string(),
+            ProviderType::Claude,
+        )
+        .unwrap()
+        .with_system_prompt("be brief".to_string());
+        let session_id = agent.create_session(Some("claude-sonnet-4".to_string()), None);
+        let request = NativeChatRequest {
+            session_id: Some(session_id.clone()),
+            message: "hi".to_string(),
+            model: None,
+            images: None,
+            audio: None,
+            attachments: None,
+            context: Some("[1] 参考资料".to_string()),
+            stream: true,
+        };
+
+        let preview = agent.preview_request(&request, None, None).unwrap();
+        assert_eq!(preview.request["system"], "be brief\n\n[1] 参考资料");
+        assert_eq!(preview.request["messages"][0]["content"], "hi");
+        assert!(agent
+            .get_session(&session_id)
+            .unwrap()
+            .system_prompt
+            .is_none());
+    }
+
+    #[test]
+    fn test_only_user_system_prompt_is_expanded() {
+        let agent = NativeAgent::new(
+            "http://127.0.0.1:8999".to_string(),
+            "key".to_string(),
+            ProviderType::Claude,
+        )
+        .unwrap()
+        .with_system_prompt("cwd: {{cwd}}".to_string());
+        let session_id = agent.create_session(Some("claude-sonnet-4".to_string()), None);
+        agent.set_session_repo(&session_id, Some("/work/repo".to_string()));
+        let request = NativeChatRequest {
+            session_id: Some(session_id.clone()),
+            message: "hi".to_string(),
+            model: None,
+            images: None,
+            audio: None,
+            attachments: None,
+            context: Some("资料 {{os}}".to_string()),
+            stream: true,
+        };
+
+        // 记忆和参考资料中的 {{...}} 原样发送
+        let preview = agent
+            .preview_request(&request, None, Some("记忆 {{date}}"))
+            .unwrap();
+        assert_eq!(
+            preview.request["system"],
+            "cwd: /work/repo\n\n记忆 {{date}}\n\n资料 {{os}}"
+        );
+    }
+
+    #[test]
+    fn test_failover_status() {
+        assert!(is_failover_status(429));
+        assert!(is_failover_status(503));
+        assert!(!is_failover_status(400));
+        assert!(!is_failover_status(401));
+    }
+
+    #[test]
+    fn test_failover_error() {
+        assert!(is_failover_error(&AgentError::Network(
+            "timeout".to_string()
+        )));
+        assert!(is_failover_error(&AgentError::Upstream {
+            status: 502,
+            body: String::new(),
+        }));
+        assert!(!is_failover_error(&AgentError::Upstream {
+            status: 400,
+            body: String::new(),
+        }));
+        let overloaded = crate::agent::errors::classify_stream_error(
+            r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#,
+        )
+        .unwrap();
+        assert!(is_failover_error(&AgentError::Stream(overloaded)));
+        assert!(!is_failover_error(&AgentError::Parse(
+            "bad json".to_string()
+        )));
+        assert!(!is_failover_error(&AgentError::InvalidRequest(
+            "图片读取失败".to_string()
+        )));
+    }
+
     #[test]
     fn test_sse_parser_tool_calls() {
         let mut parser = OpenAISSEParser::new();
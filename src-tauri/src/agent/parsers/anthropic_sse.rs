@@ -27,6 +27,8 @@ pub struct AnthropicSSEParser {
     current_tool: Option<AnthropicToolCallBuilder>,
     /// Usage 信息
     usage: Option<TokenUsage>,
+    /// 上游报告的模型
+    model: Option<String>,
 }
 
 /// Anthropic SSE 解析结果
@@ -71,7 +73,13 @@ impl AnthropicSSEParser {
 
         match event {
             AnthropicStreamEvent::MessageStart { message } => {
-                debug!("[AnthropicSSEParser] 消息开始: id={}", message.id);
+                debug!(
+                    "[AnthropicSSEParser] 消息开始: id={}, model={}",
+                    message.id, message.model
+                );
+                if !message.model.is_empty() {
+                    self.model = Some(message.model);
+                }
                 AnthropicParseResult {
                     text_delta: None,
                     is_done: false,
@@ -206,4 +214,9 @@ impl AnthropicSSEParser {
     pub fn get_usage(&self) -> Option<TokenUsage> {
         self.usage.clone()
     }
+
+    /// 获取上游报告的模型
+    pub fn get_model(&self) -> Option<String> {
+        self.model.clone()
+    }
 }
@@ -33,6 +33,8 @@ pub struct OpenAISSEParser {
     full_content: String,
     /// 当前正在构建的工具调用索引
     current_tool_indices: HashMap<usize, ToolCallDelta>,
+    /// 上游报告的模型
+    model: Option<String>,
 }
 
 impl OpenAISSEParser {
@@ -56,6 +58,14 @@ impl OpenAISSEParser {
             }
         };
 
+        if self.model.is_none() {
+            self.model = json
+                .get("model")
+                .and_then(|m| m.as_str())
+                .filter(|m| !m.is_empty())
+                .map(|m| m.to_string());
+        }
+
         // 提取 usage 信息（如果存在）
         let usage = json.get("usage").and_then(|u| {
             let input = u.get("prompt_tokens").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
@@ -185,6 +195,11 @@ impl OpenAISSEParser {
     pub fn has_tool_calls(&self) -> bool {
         !self.current_tool_indices.is_empty()
     }
+
+    /// 获取上游报告的模型
+    pub fn get_model(&self) -> Option<String> {
+        self.model.clone()
+    }
 }
 
 #[cfg(test)]
@@ -259,4 +274,15 @@ mod tests {
         let (_, done, _) = parser.parse_data("[DONE]");
         assert!(done);
     }
+
+    #[test]
+    fn test_model() {
+        let mut parser = OpenAISSEParser::new();
+        assert!(parser.get_model().is_none());
+
+        parser
+            .parse_data(r#"{"model":"gpt-4o-2024-11-20","choices":[{"delta":{"content":"Hi"}}]}"#);
+        parser.parse_data(r#"{"model":"","choices":[{"delta":{"content":"!"}}]}"#);
+        assert_eq!(parser.get_model().as_deref(), Some("gpt-4o-2024-11-20"));
+    }
 }
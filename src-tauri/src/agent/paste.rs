@@ -0,0 +1,219 @@
+//! 富文本粘贴
+//!
+//! 前端把剪贴板中的文本和图片按原始顺序传入，图片写入
+//! `~/.proxycast/attachments/paste/`，返回按顺序排列的内容片段和可直接随消息发送的
+//! 图片列表（只带 path，发送时由 [`crate::agent::images`] 读取并预处理）。
+
+use crate::agent::types::ImageData;
+use base64::Engine;
+use image::ImageFormat;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// 单张粘贴图片大小上限（20MB）
+const MAX_PASTE_IMAGE_SIZE: usize = 20 * 1024 * 1024;
+
+/// 剪贴板中的一项内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PasteItem {
+    /// 文本
+    Text { text: String },
+    /// 图片（base64 编码的原始字节）
+    Image {
+        data: String,
+        /// 文件名（截图等没有文件名时为空）
+        #[serde(default)]
+        name: Option<String>,
+    },
+}
+
+/// 粘贴内容（保持剪贴板中的顺序）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PastePayload {
+    pub items: Vec<PasteItem>,
+}
+
+/// 消息草稿中的内容片段
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DraftPart {
+    /// 文本
+    Text { text: String },
+    /// 已保存的图片
+    Image {
+        path: String,
+        media_type: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    },
+}
+
+/// 由粘贴内容组成的消息草稿
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageDraft {
+    /// 按顺序排列的内容片段（相邻文本已合并）
+    pub parts: Vec<DraftPart>,
+    /// 所有文本片段拼接后的消息文本
+    pub text: String,
+    /// 随消息发送的图片（只带 path）
+    pub images: Vec<ImageData>,
+}
+
+/// 粘贴图片保存目录（~/.proxycast/attachments/paste）
+fn paste_dir() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or_else(|| "无法获取用户 home 目录".to_string())?;
+    Ok(home.join(".proxycast").join("attachments").join("paste"))
+}
+
+/// 处理粘贴内容，图片保存到默认目录
+pub fn prepare_paste(payload: &PastePayload) -> Result<MessageDraft, String> {
+    prepare_paste_in(&paste_dir()?, payload)
+}
+
+/// 处理粘贴内容，图片保存到 `dir`
+fn prepare_paste_in(dir: &Path, payload: &PastePayload) -> Result<MessageDraft, String> {
+    let mut parts: Vec<DraftPart> = Vec::new();
+    let mut images = Vec::new();
+
+    for item in &payload.items {
+        match item {
+            PasteItem::Text { text } => {
+                let text = text.replace("\r\n", "\n");
+                if text.trim().is_empty() {
+                    continue;
+                }
+                match parts.last_mut() {
+                    Some(DraftPart::Text { text: previous }) => {
+                        previous.push('\n');
+                        previous.push_str(&text);
+                    }
+                    _ => parts.push(DraftPart::Text { text }),
+                }
+            }
+            PasteItem::Image { data, name } => {
+                let (path, media_type) = store_image(dir, data)?;
+                images.push(ImageData {
+                    data: String::new(),
+                    media_type: media_type.clone(),
+                    path: Some(path.clone()),
+                    detail: None,
+                });
+                parts.push(DraftPart::Image {
+                    path,
+                    media_type,
+                    name: name.clone(),
+                });
+            }
+        }
+    }
+
+    let text = parts
+        .iter()
+        .filter_map(|part| match part {
+            DraftPart::Text { text } => Some(text.as_str()),
+            DraftPart::Image { .. } => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    Ok(MessageDraft {
+        parts,
+        text,
+        images,
+    })
+}
+
+/// 校验并保存图片，返回文件路径和 MIME 类型
+fn store_image(dir: &Path, data: &str) -> Result<(String, String), String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .map_err(|e| format!("粘贴图片 base64 解码失败: {}", e))?;
+    if bytes.len() > MAX_PASTE_IMAGE_SIZE {
+        return Err(format!("粘贴图片过大: {} MB", bytes.len() / 1024 / 1024));
+    }
+
+    let format = image::guess_format(&bytes).map_err(|_| "不支持的粘贴图片格式".to_string())?;
+    let extension = match format {
+        ImageFormat::Png => "png",
+        ImageFormat::Jpeg => "jpg",
+        ImageFormat::Gif => "gif",
+        ImageFormat::WebP => "webp",
+        _ => return Err(format!("不支持的粘贴图片格式: {:?}", format)),
+    };
+
+    std::fs::create_dir_all(dir).map_err(|e| format!("创建粘贴图片目录失败: {}", e))?;
+    let path = dir.join(format!("{}.{}", uuid::Uuid::new_v4(), extension));
+    std::fs::write(&path, &bytes).map_err(|e| format!("保存粘贴图片失败: {}", e))?;
+
+    Ok((
+        path.to_string_lossy().to_string(),
+        format.to_mime_type().to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn png_base64() -> String {
+        let img = image::RgbImage::new(2, 2);
+        let mut bytes = Vec::new();
+        img.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    }
+
+    fn text(text: &str) -> PasteItem {
+        PasteItem::Text {
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_prepare_paste_keeps_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let payload = PastePayload {
+            items: vec![
+                text("before\r\nline"),
+                text("  "),
+                text("more"),
+                PasteItem::Image {
+                    data: png_base64(),
+                    name: Some("shot.png".to_string()),
+                },
+                text("after"),
+            ],
+        };
+
+        let draft = prepare_paste_in(dir.path(), &payload).unwrap();
+
+        assert_eq!(draft.parts.len(), 3);
+        assert_eq!(
+            draft.parts[0],
+            DraftPart::Text {
+                text: "before\nline\nmore".to_string()
+            }
+        );
+        assert!(matches!(
+            &draft.parts[1],
+            DraftPart::Image { media_type, .. } if media_type == "image/png"
+        ));
+        assert_eq!(draft.text, "before\nline\nmore\n\nafter");
+        assert_eq!(draft.images.len(), 1);
+        assert!(Path::new(draft.images[0].path.as_ref().unwrap()).exists());
+    }
+
+    #[test]
+    fn test_prepare_paste_rejects_non_image() {
+        let dir = tempfile::tempdir().unwrap();
+        let payload = PastePayload {
+            items: vec![PasteItem::Image {
+                data: base64::engine::general_purpose::STANDARD.encode(b"not an image"),
+                name: None,
+            }],
+        };
+        assert!(prepare_paste_in(dir.path(), &payload).is_err());
+    }
+}
@@ -0,0 +1,257 @@
+//! Agent 提出的补丁审核
+//!
+//! `git_apply_patch` 工具不直接修改仓库，而是把通过 `git apply --check` 的补丁放入待批准队列：
+//! - 用户批准后应用到会话配置的仓库，拒绝后不再处理
+//! - 每个会话同时存在的待批准补丁数和单个补丁大小有上限
+//! - 补丁放入 [`ApprovalQueue`]，状态变化时通过 [`PATCH_PROPOSAL_EVENT`] 事件推送到前端，
+//!   由用户在对话页审核
+
+use crate::agent::approval_queue::{ApprovalItem, ApprovalQueue};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+/// 补丁状态变化事件（载荷为 [`PatchProposal`]）
+pub const PATCH_PROPOSAL_EVENT: &str = "agent-patch-proposal";
+
+/// 每个会话同时存在的待批准补丁上限
+pub const MAX_PENDING_PATCHES_PER_SESSION: usize = 5;
+
+/// 单个补丁最大字节数
+pub const MAX_PATCH_BYTES: usize = 1024 * 1024;
+
+/// 补丁状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatchStatus {
+    /// 等待用户批准
+    PendingApproval,
+    /// 已批准，正在应用
+    Applying,
+    /// 已应用
+    Applied,
+    /// 应用失败
+    Failed,
+    /// 用户拒绝
+    Rejected,
+}
+
+/// 待审核的补丁
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatchProposal {
+    pub id: String,
+    /// 所属会话
+    pub session_id: String,
+    /// 目标仓库
+    pub repo_path: String,
+    /// unified diff
+    pub patch: String,
+    /// Agent 对修改的说明
+    pub description: String,
+    /// 补丁涉及的文件
+    pub files: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub status: PatchStatus,
+    /// 应用失败原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ApprovalItem for PatchProposal {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn session_id(&self) -> Option<&str> {
+        Some(&self.session_id)
+    }
+
+    fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    fn is_pending(&self) -> bool {
+        self.status == PatchStatus::PendingApproval
+    }
+
+    fn is_active(&self) -> bool {
+        matches!(
+            self.status,
+            PatchStatus::PendingApproval | PatchStatus::Applying
+        )
+    }
+
+    /// 批准后状态变为 `Applying`，由调用方应用补丁后调用 [`PatchReviewQueue::finish`]
+    fn set_reviewed(&mut self, approved: bool) {
+        self.status = if approved {
+            PatchStatus::Applying
+        } else {
+            PatchStatus::Rejected
+        };
+    }
+
+    /// 补丁不等待调用方，不会过期
+    fn set_expired(&mut self) {
+        self.status = PatchStatus::Rejected;
+    }
+}
+
+/// 从 unified diff 中提取涉及的文件（保持顺序并去重）
+pub fn patch_files(patch: &str) -> Vec<String> {
+    let mut files: Vec<String> = Vec::new();
+    for line in patch.lines() {
+        let path = match line
+            .strip_prefix("+++ ")
+            .or_else(|| line.strip_prefix("--- "))
+        {
+            Some(path) => path.split('\t').next().unwrap_or_default().trim(),
+            None => continue,
+        };
+        if path == "/dev/null" {
+            continue;
+        }
+        let path = path
+            .strip_prefix("a/")
+            .or_else(|| path.strip_prefix("b/"))
+            .unwrap_or(path);
+        if !files.iter().any(|f| f == path) {
+            files.push(path.to_string());
+        }
+    }
+    files
+}
+
+/// 补丁审核队列（克隆后共享同一份补丁表）
+#[derive(Clone)]
+pub struct PatchReviewQueue {
+    patches: ApprovalQueue<PatchProposal>,
+}
+
+impl Default for PatchReviewQueue {
+    fn default() -> Self {
+        Self {
+            patches: ApprovalQueue::new(
+                PATCH_PROPOSAL_EVENT,
+                "补丁",
+                MAX_PENDING_PATCHES_PER_SESSION,
+            ),
+        }
+    }
+}
+
+impl PatchReviewQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置推送 [`PATCH_PROPOSAL_EVENT`] 事件的应用句柄
+    pub fn set_app_handle(&self, app_handle: AppHandle) {
+        self.patches.set_app_handle(app_handle);
+    }
+
+    /// 创建待批准的补丁
+    pub fn propose(
+        &self,
+        session_id: &str,
+        repo_path: &str,
+        patch: &str,
+        description: &str,
+    ) -> Result<PatchProposal, String> {
+        if patch.trim().is_empty() {
+            return Err("补丁内容不能为空".to_string());
+        }
+        if patch.len() > MAX_PATCH_BYTES {
+            return Err(format!(
+                "补丁大小 {} 字节，超过上限 {} 字节",
+                patch.len(),
+                MAX_PATCH_BYTES
+            ));
+        }
+        let files = patch_files(patch);
+        if files.is_empty() {
+            return Err("补丁中没有找到文件修改（需要 unified diff 格式）".to_string());
+        }
+
+        self.patches.submit(PatchProposal {
+            id: uuid::Uuid::new_v4().to_string(),
+            session_id: session_id.to_string(),
+            repo_path: repo_path.to_string(),
+            patch: patch.to_string(),
+            description: description.trim().to_string(),
+            files,
+            created_at: Utc::now(),
+            status: PatchStatus::PendingApproval,
+            error: None,
+        })
+    }
+
+    /// 批准或拒绝待批准的补丁
+    ///
+    /// 批准后状态变为 `Applying`，由调用方应用补丁后调用 [`PatchReviewQueue::finish`]。
+    pub fn review(&self, id: &str, approved: bool) -> Result<PatchProposal, String> {
+        self.patches.review(id, approved)
+    }
+
+    /// 记录应用结果
+    pub fn finish(&self, id: &str, outcome: Result<(), String>) -> Option<PatchProposal> {
+        self.patches.update(id, |proposal| match outcome {
+            Ok(()) => proposal.status = PatchStatus::Applied,
+            Err(error) => {
+                proposal.status = PatchStatus::Failed;
+                proposal.error = Some(error);
+            }
+        })
+    }
+
+    /// 拒绝会话的所有待批准补丁（删除会话时调用）
+    pub fn reject_session(&self, session_id: &str) {
+        self.patches.reject_session(session_id);
+    }
+
+    /// 列出补丁（按创建时间排序），可按会话过滤
+    pub fn list(&self, session_id: Option<&str>) -> Vec<PatchProposal> {
+        self.patches.list(session_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATCH: &str = "diff --git a/src/main.rs b/src/main.rs\n\
+                         --- a/src/main.rs\n\
+                         +++ b/src/main.rs\n\
+                         @@ -1 +1 @@\n\
+                         -fn main() {}\n\
+                         +fn main() { println!(\"hi\"); }\n\
+                         diff --git a/NEW.md b/NEW.md\n\
+                         --- /dev/null\n\
+                         +++ b/NEW.md\n\
+                         @@ -0,0 +1 @@\n\
+                         +new\n";
+
+    #[test]
+    fn test_patch_review_lifecycle() {
+        let queue = PatchReviewQueue::new();
+        let proposal = queue.propose("s1", "/repo", PATCH, "say hi").unwrap();
+        assert_eq!(proposal.files, vec!["src/main.rs", "NEW.md"]);
+        assert_eq!(proposal.status, PatchStatus::PendingApproval);
+
+        let reviewed = queue.review(&proposal.id, true).unwrap();
+        assert_eq!(reviewed.status, PatchStatus::Applying);
+        assert!(queue.review(&proposal.id, true).is_err());
+        let finished = queue.finish(&proposal.id, Ok(())).unwrap();
+        assert_eq!(finished.status, PatchStatus::Applied);
+
+        assert!(queue.propose("s1", "/repo", "not a diff", "").is_err());
+        for _ in 0..MAX_PENDING_PATCHES_PER_SESSION {
+            queue.propose("s1", "/repo", PATCH, "").unwrap();
+        }
+        assert!(queue.propose("s1", "/repo", PATCH, "").is_err());
+        queue.reject_session("s1");
+        assert!(queue
+            .list(Some("s1"))
+            .iter()
+            .all(|p| p.status != PatchStatus::PendingApproval));
+    }
+}
@@ -0,0 +1,123 @@
+//! 系统提示词变量模块
+//!
+//! 系统提示词（预设和会话提示词）中可以使用运行时变量，在每次请求构建消息时展开：
+//! - `{{date}}`: 当前本地日期（如 `2026-10-16`）
+//! - `{{os}}`: 操作系统（macOS / Windows / Linux）
+//! - `{{locale}}`: 系统语言区域（如 `zh-CN`）
+//! - `{{cwd}}`: 会话关联的仓库目录（未关联时为用户 home 目录）
+//! - `{{user_name}}`: 当前系统用户名
+//!
+//! 未知的变量原样保留。只展开用户编写的系统提示词，注入的长期记忆和检索到的参考资料原样发送。
+
+use std::collections::HashMap;
+
+/// 支持的变量名
+pub const PROMPT_VARIABLES: &[&str] = &["date", "os", "locale", "cwd", "user_name"];
+
+/// 操作系统名称
+fn os_name() -> String {
+    match std::env::consts::OS {
+        "macos" => "macOS".to_string(),
+        "windows" => "Windows".to_string(),
+        "linux" => "Linux".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// 系统用户名
+fn user_name() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok()
+        .filter(|name| !name.trim().is_empty())
+        .or_else(|| {
+            dirs::home_dir()
+                .and_then(|home| home.file_name().map(|n| n.to_string_lossy().to_string()))
+        })
+        .unwrap_or_default()
+}
+
+/// 当前时刻的变量值，`cwd` 为会话的工作目录（为空时使用用户 home 目录）
+pub fn current_variables(cwd: Option<&str>) -> HashMap<&'static str, String> {
+    HashMap::from([
+        ("date", chrono::Local::now().format("%Y-%m-%d").to_string()),
+        ("os", os_name()),
+        (
+            "locale",
+            sys_locale::get_locale().unwrap_or_else(|| "en-US".to_string()),
+        ),
+        (
+            "cwd",
+            cwd.map(str::to_string)
+                .or_else(|| dirs::home_dir().map(|home| home.to_string_lossy().to_string()))
+                .unwrap_or_default(),
+        ),
+        ("user_name", user_name()),
+    ])
+}
+
+/// 用给定的变量值展开模板（`{{ name }}` 两侧空白可选）
+pub fn expand_with(template: &str, variables: &HashMap<&str, String>) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        match variables.get(after[..end].trim()) {
+            Some(value) => output.push_str(value),
+            None => output.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// 展开系统提示词中的变量（不含变量时直接返回）
+pub fn expand_system_prompt(prompt: &str, cwd: Option<&str>) -> String {
+    if !prompt.contains("{{") {
+        return prompt.to_string();
+    }
+    expand_with(prompt, &current_variables(cwd))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_with_known_and_unknown_variables() {
+        let variables = HashMap::from([
+            ("date", "2026-10-16".to_string()),
+            ("user_name", "alice".to_string()),
+        ]);
+        assert_eq!(
+            expand_with(
+                "Today is {{date}}. Hi {{ user_name }}! Keep {{unknown}} and {{date",
+                &variables
+            ),
+            "Today is 2026-10-16. Hi alice! Keep {{unknown}} and {{date"
+        );
+        assert_eq!(expand_with("no variables", &variables), "no variables");
+    }
+
+    #[test]
+    fn test_current_variables_cover_all_names() {
+        let variables = current_variables(None);
+        for name in PROMPT_VARIABLES {
+            assert!(variables.contains_key(name), "missing {}", name);
+        }
+        assert!(!expand_system_prompt("{{os}}", None).contains("{{"));
+    }
+
+    #[test]
+    fn test_cwd_uses_session_directory() {
+        assert_eq!(
+            expand_system_prompt("repo: {{cwd}}", Some("/work/repo")),
+            "repo: /work/repo"
+        );
+    }
+}
@@ -4,11 +4,13 @@
 //! 适用于 Claude、Claude OAuth 等 Anthropic 服务
 
 use super::Protocol;
+use crate::agent::errors::{classify_provider_error, classify_stream_error, AgentError};
 use crate::agent::parsers::AnthropicSSEParser;
 use crate::agent::types::{
-    AgentConfig, AgentMessage, ContentPart, ImageData, MessageContent, StreamEvent, StreamResult,
+    AgentConfig, AgentMessage, AudioData, ContentPart, DocumentData, ImageData, MessageContent,
+    StreamEvent, StreamResult,
 };
-use crate::models::anthropic::AnthropicMessage;
+use crate::models::anthropic::{document_block, AnthropicMessage};
 use crate::models::openai::Tool;
 use async_trait::async_trait;
 use futures::StreamExt;
@@ -29,6 +31,8 @@ struct AnthropicMessagesRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<AnthropicTool>>,
 }
 
@@ -152,6 +156,16 @@ impl AnthropicProtocol {
                                 }
                             })
                         }
+                        // Anthropic 不支持音频输入，保留占位说明
+                        ContentPart::InputAudio { format, .. } => serde_json::json!({
+                            "type": "text",
+                            "text": format!("[音频附件 ({})，当前模型不支持音频输入]", format)
+                        }),
+                        ContentPart::Document {
+                            data,
+                            media_type,
+                            name,
+                        } => document_block(media_type, data, name.as_deref()),
                     })
                     .collect();
                 serde_json::json!(blocks)
@@ -173,6 +187,8 @@ impl AnthropicProtocol {
         history: &[AgentMessage],
         user_message: &str,
         images: Option<&[ImageData]>,
+        audio: Option<&[AudioData]>,
+        documents: Option<&[DocumentData]>,
         config: &AgentConfig,
     ) -> (Vec<AnthropicMessage>, Option<serde_json::Value>) {
         let mut messages = Vec::new();
@@ -189,13 +205,13 @@ impl AnthropicProtocol {
         }
 
         // 添加当前用户消息
-        let user_content = if let Some(imgs) = images {
+        let user_content = if images.is_some() || audio.is_some() || documents.is_some() {
             let mut parts = vec![serde_json::json!({
                 "type": "text",
                 "text": user_message
             })];
 
-            for img in imgs {
+            for img in images.unwrap_or_default() {
                 parts.push(serde_json::json!({
                     "type": "image",
                     "source": {
@@ -205,6 +221,16 @@ impl AnthropicProtocol {
                     }
                 }));
             }
+            // Anthropic 不支持音频输入，保留占位说明
+            for clip in audio.unwrap_or_default() {
+                parts.push(serde_json::json!({
+                    "type": "text",
+                    "text": format!("[音频附件 ({})，当前模型不支持音频输入]", clip.format)
+                }));
+            }
+            for doc in documents.unwrap_or_default() {
+                parts.push(document_block(&doc.media_type, &doc.data, Some(&doc.name)));
+            }
             serde_json::json!(parts)
         } else {
             serde_json::json!(user_message)
@@ -218,6 +244,26 @@ impl AnthropicProtocol {
         (messages, system_prompt)
     }
 
+    /// 组装流式请求体
+    fn messages_request(
+        messages: Vec<AnthropicMessage>,
+        system: Option<serde_json::Value>,
+        model: &str,
+        config: &AgentConfig,
+        tools: Option<&[Tool]>,
+    ) -> AnthropicMessagesRequest {
+        AnthropicMessagesRequest {
+            model: model.to_string(),
+            messages,
+            max_tokens: config.max_tokens.unwrap_or(4096),
+            stream: true,
+            system,
+            temperature: config.temperature,
+            top_p: config.top_p,
+            tools: Self::convert_tools(tools),
+        }
+    }
+
     /// 从历史构建消息（不添加新用户消息）
     fn build_messages_from_history(
         history: &[AgentMessage],
@@ -244,7 +290,7 @@ impl AnthropicProtocol {
         response: reqwest::Response,
         tx: mpsc::Sender<StreamEvent>,
         send_done: bool,
-    ) -> Result<StreamResult, String> {
+    ) -> Result<StreamResult, AgentError> {
         let mut stream = response.bytes_stream();
         let mut buffer = String::new();
         let mut parser = AnthropicSSEParser::new();
@@ -280,6 +326,20 @@ impl AnthropicProtocol {
                             "[AnthropicProtocol] SSE event={}, data={}",
                             event_type, data
                         );
+
+                        // 上游在流中途返回错误（如 overloaded_error）
+                        if let Some(detail) = classify_stream_error(&data) {
+                            error!("[AnthropicProtocol] 流式响应返回错误: {}", detail.message);
+                            let message = format!("API 错误: {}", detail.message);
+                            let _ = tx
+                                .send(StreamEvent::Error {
+                                    message: message.clone(),
+                                    error: Some(detail.clone()),
+                                })
+                                .await;
+                            return Err(AgentError::Stream(detail));
+                        }
+
                         let result = parser.parse_data(&data);
 
                         // 发送工具开始事件
@@ -320,6 +380,9 @@ impl AnthropicProtocol {
                                 content: full_content,
                                 tool_calls,
                                 usage,
+                                served_by: None,
+                                model: parser.get_model(),
+                                context_fallback: None,
                             });
                         }
                     }
@@ -329,9 +392,10 @@ impl AnthropicProtocol {
                     let _ = tx
                         .send(StreamEvent::Error {
                             message: format!("流读取错误: {}", e),
+                            error: None,
                         })
                         .await;
-                    return Err(format!("流读取错误: {}", e));
+                    return Err(AgentError::from_reqwest(e));
                 }
             }
         }
@@ -357,6 +421,9 @@ impl AnthropicProtocol {
             content: full_content,
             tool_calls,
             usage,
+            served_by: None,
+            model: parser.get_model(),
+            context_fallback: None,
         })
     }
 }
@@ -371,11 +438,13 @@ impl Protocol for AnthropicProtocol {
         messages: &[AgentMessage],
         user_message: &str,
         images: Option<&[ImageData]>,
+        audio: Option<&[AudioData]>,
+        documents: Option<&[DocumentData]>,
         model: &str,
         config: &AgentConfig,
         tools: Option<&[Tool]>,
         tx: mpsc::Sender<StreamEvent>,
-    ) -> Result<StreamResult, String> {
+    ) -> Result<StreamResult, AgentError> {
         info!(
             "[AnthropicProtocol] 发送流式请求: model={}, history_len={}, tools_count={}",
             model,
@@ -384,19 +453,9 @@ impl Protocol for AnthropicProtocol {
         );
 
         let (anthropic_messages, system) =
-            Self::build_messages(messages, user_message, images, config);
-
-        let anthropic_tools = Self::convert_tools(tools);
+            Self::build_messages(messages, user_message, images, audio, documents, config);
 
-        let request = AnthropicMessagesRequest {
-            model: model.to_string(),
-            messages: anthropic_messages,
-            max_tokens: config.max_tokens.unwrap_or(4096),
-            stream: true,
-            system,
-            temperature: config.temperature,
-            tools: anthropic_tools,
-        };
+        let request = Self::messages_request(anthropic_messages, system, model, config, tools);
 
         let url = format!("{}{}", base_url, self.endpoint());
 
@@ -408,7 +467,7 @@ impl Protocol for AnthropicProtocol {
             .json(&request)
             .send()
             .await
-            .map_err(|e| format!("请求失败: {}", e))?;
+            .map_err(AgentError::from_reqwest)?;
 
         let status = response.status();
         if !status.is_success() {
@@ -417,9 +476,13 @@ impl Protocol for AnthropicProtocol {
             let _ = tx
                 .send(StreamEvent::Error {
                     message: format!("API 错误 ({}): {}", status, body),
+                    error: Some(classify_provider_error(Some(status.as_u16()), &body)),
                 })
                 .await;
-            return Err(format!("API 错误: {}", status));
+            return Err(AgentError::Upstream {
+                status: status.as_u16(),
+                body,
+            });
         }
 
         Self::process_stream(response, tx, true).await
@@ -435,7 +498,7 @@ impl Protocol for AnthropicProtocol {
         config: &AgentConfig,
         tools: Option<&[Tool]>,
         tx: mpsc::Sender<StreamEvent>,
-    ) -> Result<StreamResult, String> {
+    ) -> Result<StreamResult, AgentError> {
         debug!(
             "[AnthropicProtocol] 继续流式对话: model={}, history_len={}, tools_count={}",
             model,
@@ -445,17 +508,7 @@ impl Protocol for AnthropicProtocol {
 
         let (anthropic_messages, system) = Self::build_messages_from_history(messages, config);
 
-        let anthropic_tools = Self::convert_tools(tools);
-
-        let request = AnthropicMessagesRequest {
-            model: model.to_string(),
-            messages: anthropic_messages,
-            max_tokens: config.max_tokens.unwrap_or(4096),
-            stream: true,
-            system,
-            temperature: config.temperature,
-            tools: anthropic_tools,
-        };
+        let request = Self::messages_request(anthropic_messages, system, model, config, tools);
 
         let url = format!("{}{}", base_url, self.endpoint());
 
@@ -467,7 +520,7 @@ impl Protocol for AnthropicProtocol {
             .json(&request)
             .send()
             .await
-            .map_err(|e| format!("请求失败: {}", e))?;
+            .map_err(AgentError::from_reqwest)?;
 
         let status = response.status();
         if !status.is_success() {
@@ -476,15 +529,42 @@ impl Protocol for AnthropicProtocol {
             let _ = tx
                 .send(StreamEvent::Error {
                     message: format!("API 错误 ({}): {}", status, body),
+                    error: Some(classify_provider_error(Some(status.as_u16()), &body)),
                 })
                 .await;
-            return Err(format!("API 错误: {}", status));
+            return Err(AgentError::Upstream {
+                status: status.as_u16(),
+                body,
+            });
         }
 
         // 继续对话时不发送 Done 事件
         Self::process_stream(response, tx, false).await
     }
 
+    fn build_request(
+        &self,
+        messages: &[AgentMessage],
+        user_message: &str,
+        images: Option<&[ImageData]>,
+        audio: Option<&[AudioData]>,
+        documents: Option<&[DocumentData]>,
+        model: &str,
+        config: &AgentConfig,
+        tools: Option<&[Tool]>,
+    ) -> serde_json::Value {
+        let (anthropic_messages, system) =
+            Self::build_messages(messages, user_message, images, audio, documents, config);
+        serde_json::to_value(Self::messages_request(
+            anthropic_messages,
+            system,
+            model,
+            config,
+            tools,
+        ))
+        .unwrap_or_default()
+    }
+
     fn endpoint(&self) -> &'static str {
         "/v1/messages"
     }
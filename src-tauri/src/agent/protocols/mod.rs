@@ -8,8 +8,10 @@ mod openai;
 pub use anthropic::AnthropicProtocol;
 pub use openai::OpenAIProtocol;
 
+use crate::agent::errors::AgentError;
 use crate::agent::types::{
-    AgentConfig, AgentMessage, ImageData, ProviderType, StreamEvent, StreamResult,
+    AgentConfig, AgentMessage, AudioData, DocumentData, ImageData, ProviderType, StreamEvent,
+    StreamResult,
 };
 use crate::models::openai::Tool;
 use async_trait::async_trait;
@@ -32,11 +34,13 @@ pub trait Protocol: Send + Sync {
         messages: &[AgentMessage],
         user_message: &str,
         images: Option<&[ImageData]>,
+        audio: Option<&[AudioData]>,
+        documents: Option<&[DocumentData]>,
         model: &str,
         config: &AgentConfig,
         tools: Option<&[Tool]>,
         tx: mpsc::Sender<StreamEvent>,
-    ) -> Result<StreamResult, String>;
+    ) -> Result<StreamResult, AgentError>;
 
     /// 继续流式对话（工具调用后）
     ///
@@ -51,7 +55,20 @@ pub trait Protocol: Send + Sync {
         config: &AgentConfig,
         tools: Option<&[Tool]>,
         tx: mpsc::Sender<StreamEvent>,
-    ) -> Result<StreamResult, String>;
+    ) -> Result<StreamResult, AgentError>;
+
+    /// 组装 `chat_stream` 将发送的请求体（用于请求预览，不发送）
+    fn build_request(
+        &self,
+        messages: &[AgentMessage],
+        user_message: &str,
+        images: Option<&[ImageData]>,
+        audio: Option<&[AudioData]>,
+        documents: Option<&[DocumentData]>,
+        model: &str,
+        config: &AgentConfig,
+        tools: Option<&[Tool]>,
+    ) -> serde_json::Value;
 
     /// 获取 API 端点
     fn endpoint(&self) -> &'static str;
@@ -4,13 +4,15 @@
 //! 适用于 OpenAI、Qwen、Codex、Antigravity、IFlow、Kiro 等兼容服务
 
 use super::Protocol;
+use crate::agent::errors::{classify_provider_error, classify_stream_error, AgentError};
 use crate::agent::parsers::OpenAISSEParser;
 use crate::agent::types::{
-    AgentConfig, AgentMessage, ContentPart, ImageData, MessageContent, StreamEvent, StreamResult,
+    AgentConfig, AgentMessage, AudioData, ContentPart, DocumentData, ImageData, MessageContent,
+    StreamEvent, StreamResult,
 };
 use crate::models::openai::{
     ChatCompletionRequest, ChatMessage, ContentPart as OpenAIContentPart,
-    MessageContent as OpenAIMessageContent, Tool,
+    MessageContent as OpenAIMessageContent, StreamOptions, Tool,
 };
 use async_trait::async_trait;
 use futures::StreamExt;
@@ -39,6 +41,22 @@ impl OpenAIProtocol {
                                 detail: image_url.detail.clone(),
                             },
                         },
+                        ContentPart::InputAudio { data, format } => OpenAIContentPart::InputAudio {
+                            input_audio: crate::models::openai::InputAudio {
+                                data: data.clone(),
+                                format: format.clone(),
+                            },
+                        },
+                        ContentPart::Document {
+                            data,
+                            media_type,
+                            name,
+                        } => OpenAIContentPart::File {
+                            file: crate::models::openai::FileData {
+                                filename: name.clone(),
+                                file_data: format!("data:{};base64,{}", media_type, data),
+                            },
+                        },
                     })
                     .collect();
                 Some(OpenAIMessageContent::Parts(openai_parts))
@@ -70,6 +88,8 @@ impl OpenAIProtocol {
         history: &[AgentMessage],
         user_message: &str,
         images: Option<&[ImageData]>,
+        audio: Option<&[AudioData]>,
+        documents: Option<&[DocumentData]>,
         config: &AgentConfig,
     ) -> Vec<ChatMessage> {
         let mut messages = Vec::new();
@@ -90,16 +110,32 @@ impl OpenAIProtocol {
         }
 
         // 添加当前用户消息
-        let user_msg = if let Some(imgs) = images {
+        let user_msg = if images.is_some() || audio.is_some() || documents.is_some() {
             let mut parts = vec![OpenAIContentPart::Text {
                 text: user_message.to_string(),
             }];
 
-            for img in imgs {
+            for img in images.unwrap_or_default() {
                 parts.push(OpenAIContentPart::ImageUrl {
                     image_url: crate::models::openai::ImageUrl {
                         url: format!("data:{};base64,{}", img.media_type, img.data),
-                        detail: None,
+                        detail: img.detail.map(|d| d.as_str().to_string()),
+                    },
+                });
+            }
+            for clip in audio.unwrap_or_default() {
+                parts.push(OpenAIContentPart::InputAudio {
+                    input_audio: crate::models::openai::InputAudio {
+                        data: clip.data.clone(),
+                        format: clip.format.clone(),
+                    },
+                });
+            }
+            for doc in documents.unwrap_or_default() {
+                parts.push(OpenAIContentPart::File {
+                    file: crate::models::openai::FileData {
+                        filename: Some(doc.name.clone()),
+                        file_data: format!("data:{};base64,{}", doc.media_type, doc.data),
                     },
                 });
             }
@@ -123,6 +159,34 @@ impl OpenAIProtocol {
         messages
     }
 
+    /// 组装流式请求体
+    fn chat_request(
+        messages: Vec<ChatMessage>,
+        model: &str,
+        config: &AgentConfig,
+        tools: Option<&[Tool]>,
+    ) -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: model.to_string(),
+            messages,
+            stream: true,
+            temperature: config.temperature,
+            max_tokens: config.max_tokens,
+            top_p: config.top_p,
+            tools: tools.map(|t| t.to_vec()),
+            tool_choice: if tools.is_some() {
+                Some(serde_json::json!("auto"))
+            } else {
+                None
+            },
+            reasoning_effort: None,
+            // 要求上游在最后一个数据块返回用量，用于统一用量记录
+            stream_options: Some(StreamOptions {
+                include_usage: true,
+            }),
+        }
+    }
+
     /// 从历史构建消息（不添加新用户消息）
     fn build_messages_from_history(
         history: &[AgentMessage],
@@ -153,7 +217,7 @@ impl OpenAIProtocol {
         response: reqwest::Response,
         tx: mpsc::Sender<StreamEvent>,
         send_done: bool,
-    ) -> Result<StreamResult, String> {
+    ) -> Result<StreamResult, AgentError> {
         let mut stream = response.bytes_stream();
         let mut buffer = String::new();
         let mut parser = OpenAISSEParser::new();
@@ -173,6 +237,20 @@ impl OpenAIProtocol {
                         for line in event.lines() {
                             if let Some(data) = line.strip_prefix("data: ") {
                                 debug!("[OpenAIProtocol] SSE data: {}", data);
+
+                                // 上游在流中途返回错误
+                                if let Some(detail) = classify_stream_error(data) {
+                                    error!("[OpenAIProtocol] 流式响应返回错误: {}", detail.message);
+                                    let message = format!("API 错误: {}", detail.message);
+                                    let _ = tx
+                                        .send(StreamEvent::Error {
+                                            message: message.clone(),
+                                            error: Some(detail.clone()),
+                                        })
+                                        .await;
+                                    return Err(AgentError::Stream(detail));
+                                }
+
                                 let (text_delta, is_done, usage) = parser.parse_data(data);
 
                                 if usage.is_some() {
@@ -203,6 +281,9 @@ impl OpenAIProtocol {
                                         content: full_content,
                                         tool_calls,
                                         usage: final_usage,
+                                        served_by: None,
+                                        model: parser.get_model(),
+                                        context_fallback: None,
                                     });
                                 }
                             }
@@ -214,9 +295,10 @@ impl OpenAIProtocol {
                     let _ = tx
                         .send(StreamEvent::Error {
                             message: format!("流读取错误: {}", e),
+                            error: None,
                         })
                         .await;
-                    return Err(format!("流读取错误: {}", e));
+                    return Err(AgentError::from_reqwest(e));
                 }
             }
         }
@@ -241,6 +323,9 @@ impl OpenAIProtocol {
             content: full_content,
             tool_calls,
             usage: final_usage,
+            served_by: None,
+            model: parser.get_model(),
+            context_fallback: None,
         })
     }
 }
@@ -255,11 +340,13 @@ impl Protocol for OpenAIProtocol {
         messages: &[AgentMessage],
         user_message: &str,
         images: Option<&[ImageData]>,
+        audio: Option<&[AudioData]>,
+        documents: Option<&[DocumentData]>,
         model: &str,
         config: &AgentConfig,
         tools: Option<&[Tool]>,
         tx: mpsc::Sender<StreamEvent>,
-    ) -> Result<StreamResult, String> {
+    ) -> Result<StreamResult, AgentError> {
         info!(
             "[OpenAIProtocol] 发送流式请求: model={}, history_len={}, tools_count={}",
             model,
@@ -267,23 +354,10 @@ impl Protocol for OpenAIProtocol {
             tools.map(|t| t.len()).unwrap_or(0)
         );
 
-        let chat_messages = Self::build_messages(messages, user_message, images, config);
+        let chat_messages =
+            Self::build_messages(messages, user_message, images, audio, documents, config);
 
-        let request = ChatCompletionRequest {
-            model: model.to_string(),
-            messages: chat_messages,
-            stream: true,
-            temperature: config.temperature,
-            max_tokens: config.max_tokens,
-            top_p: None,
-            tools: tools.map(|t| t.to_vec()),
-            tool_choice: if tools.is_some() {
-                Some(serde_json::json!("auto"))
-            } else {
-                None
-            },
-            reasoning_effort: None,
-        };
+        let request = Self::chat_request(chat_messages, model, config, tools);
 
         let url = format!("{}{}", base_url, self.endpoint());
 
@@ -294,7 +368,7 @@ impl Protocol for OpenAIProtocol {
             .json(&request)
             .send()
             .await
-            .map_err(|e| format!("请求失败: {}", e))?;
+            .map_err(AgentError::from_reqwest)?;
 
         let status = response.status();
         if !status.is_success() {
@@ -303,9 +377,13 @@ impl Protocol for OpenAIProtocol {
             let _ = tx
                 .send(StreamEvent::Error {
                     message: format!("API 错误 ({}): {}", status, body),
+                    error: Some(classify_provider_error(Some(status.as_u16()), &body)),
                 })
                 .await;
-            return Err(format!("API 错误: {}", status));
+            return Err(AgentError::Upstream {
+                status: status.as_u16(),
+                body,
+            });
         }
 
         Self::process_stream(response, tx, true).await
@@ -321,7 +399,7 @@ impl Protocol for OpenAIProtocol {
         config: &AgentConfig,
         tools: Option<&[Tool]>,
         tx: mpsc::Sender<StreamEvent>,
-    ) -> Result<StreamResult, String> {
+    ) -> Result<StreamResult, AgentError> {
         debug!(
             "[OpenAIProtocol] 继续流式对话: model={}, history_len={}, tools_count={}",
             model,
@@ -331,21 +409,7 @@ impl Protocol for OpenAIProtocol {
 
         let chat_messages = Self::build_messages_from_history(messages, config);
 
-        let request = ChatCompletionRequest {
-            model: model.to_string(),
-            messages: chat_messages,
-            stream: true,
-            temperature: config.temperature,
-            max_tokens: config.max_tokens,
-            top_p: None,
-            tools: tools.map(|t| t.to_vec()),
-            tool_choice: if tools.is_some() {
-                Some(serde_json::json!("auto"))
-            } else {
-                None
-            },
-            reasoning_effort: None,
-        };
+        let request = Self::chat_request(chat_messages, model, config, tools);
 
         let url = format!("{}{}", base_url, self.endpoint());
 
@@ -356,7 +420,7 @@ impl Protocol for OpenAIProtocol {
             .json(&request)
             .send()
             .await
-            .map_err(|e| format!("请求失败: {}", e))?;
+            .map_err(AgentError::from_reqwest)?;
 
         let status = response.status();
         if !status.is_success() {
@@ -365,15 +429,36 @@ impl Protocol for OpenAIProtocol {
             let _ = tx
                 .send(StreamEvent::Error {
                     message: format!("API 错误 ({}): {}", status, body),
+                    error: Some(classify_provider_error(Some(status.as_u16()), &body)),
                 })
                 .await;
-            return Err(format!("API 错误: {}", status));
+            return Err(AgentError::Upstream {
+                status: status.as_u16(),
+                body,
+            });
         }
 
         // 继续对话时不发送 Done 事件（工具循环可能还会继续）
         Self::process_stream(response, tx, false).await
     }
 
+    fn build_request(
+        &self,
+        messages: &[AgentMessage],
+        user_message: &str,
+        images: Option<&[ImageData]>,
+        audio: Option<&[AudioData]>,
+        documents: Option<&[DocumentData]>,
+        model: &str,
+        config: &AgentConfig,
+        tools: Option<&[Tool]>,
+    ) -> serde_json::Value {
+        let chat_messages =
+            Self::build_messages(messages, user_message, images, audio, documents, config);
+        serde_json::to_value(Self::chat_request(chat_messages, model, config, tools))
+            .unwrap_or_default()
+    }
+
     fn endpoint(&self) -> &'static str {
         "/v1/chat/completions"
     }
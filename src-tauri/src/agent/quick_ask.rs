@@ -0,0 +1,125 @@
+//! 快速提问
+//!
+//! 全局快捷键唤起的轻量问答：使用独立的模型和系统提示词，不带历史、不调用工具，尽快返回回答。
+//! 可选把每次问答追加到固定 ID 的"草稿"会话中（只保留最近的若干条消息），之后可以在会话列表中继续。
+//!
+//! 快捷键在启动时注册（修改后需重启），按下时显示主窗口并推送 [`QUICK_ASK_EVENT`]，由前端弹出输入框。
+
+use crate::agent::model_pin;
+use crate::agent::types::{AgentMessage, AgentSession, MessageContent};
+use crate::config::QuickAskConfig;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+
+/// 快捷键按下时推送的事件名
+pub const QUICK_ASK_EVENT: &str = "quick-ask-open";
+
+/// 草稿会话 ID
+pub const SCRATCH_SESSION_ID: &str = "quick-ask-scratch";
+
+/// 快速提问结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuickAskResponse {
+    pub content: String,
+    pub duration_ms: u64,
+    /// 问答已追加到的草稿会话
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scratch_session_id: Option<String>,
+}
+
+fn text_message(role: &str, text: &str, timestamp: &str) -> AgentMessage {
+    AgentMessage {
+        role: role.to_string(),
+        content: MessageContent::Text(text.to_string()),
+        timestamp: timestamp.to_string(),
+        tool_calls: None,
+        tool_call_id: None,
+        attachments: None,
+        truncated: None,
+    }
+}
+
+/// 新建草稿会话
+pub fn new_scratch_session(model: &str) -> AgentSession {
+    let now = chrono::Utc::now().to_rfc3339();
+    AgentSession {
+        id: SCRATCH_SESSION_ID.to_string(),
+        model: model.to_string(),
+        messages: Vec::new(),
+        system_prompt: None,
+        memory_prompt: None,
+        knowledge_collection: None,
+        model_pin: model_pin::initial_pin(model),
+        model_changes: Vec::new(),
+        tags: vec!["quick-ask".to_string()],
+        profile: None,
+        folder: None,
+        repo_path: None,
+        http_domains: Vec::new(),
+        permission_mode: None,
+        archived: false,
+        pinned: false,
+        created_at: now.clone(),
+        updated_at: now,
+    }
+}
+
+/// 追加一次问答，超过 `max_messages` 时按问答对丢弃最早的消息
+pub fn append_exchange(
+    session: &mut AgentSession,
+    prompt: &str,
+    answer: &str,
+    max_messages: usize,
+) {
+    let now = chrono::Utc::now().to_rfc3339();
+    session.messages.push(text_message("user", prompt, &now));
+    session
+        .messages
+        .push(text_message("assistant", answer, &now));
+    let limit = max_messages.max(2) / 2 * 2;
+    if session.messages.len() > limit {
+        let excess = session.messages.len() - limit;
+        session.messages.drain(..excess);
+    }
+    session.updated_at = now;
+}
+
+/// 注册快速提问快捷键（未启用时跳过）
+pub fn register_shortcut(app: &AppHandle, config: &QuickAskConfig) -> Result<(), String> {
+    if !config.enabled || config.shortcut.trim().is_empty() {
+        return Ok(());
+    }
+    app.global_shortcut()
+        .on_shortcut(config.shortcut.trim(), |app, _shortcut, event| {
+            if !matches!(event.state, ShortcutState::Pressed) {
+                return;
+            }
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+            let _ = app.emit(QUICK_ASK_EVENT, ());
+        })
+        .map_err(|e| format!("注册快捷键 {} 失败: {}", config.shortcut, e))?;
+    tracing::info!("[QuickAsk] 已注册快捷键: {}", config.shortcut);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_exchange_keeps_recent_pairs() {
+        let mut session = new_scratch_session("gpt-4o-mini");
+        for i in 0..4 {
+            append_exchange(&mut session, &format!("q{}", i), &format!("a{}", i), 5);
+        }
+        // 上限向下取整到完整的问答对
+        assert_eq!(session.messages.len(), 4);
+        assert_eq!(session.messages[0].role, "user");
+        assert_eq!(session.messages[0].content.as_text(), "q2");
+        assert_eq!(session.messages[3].content.as_text(), "a3");
+    }
+}
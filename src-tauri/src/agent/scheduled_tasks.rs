@@ -0,0 +1,499 @@
+//! Agent 定时任务
+//!
+//! 用户在配置中定义定时任务（提示词 + 配置档案 + cron 表达式）：
+//! - 后台调度器按 cron 表达式（本地时间）在临时会话中发起一轮带工具的对话，
+//!   执行完成后删除临时会话
+//! - 每次执行的结果保存在 `scheduled_task_runs` 表中，每个任务保留最近
+//!   [`MAX_RUNS_PER_TASK`] 条记录
+//! - 执行开始和结束时通过 [`SCHEDULED_TASK_EVENT`] 事件推送执行记录，
+//!   任务开启 `notify` 时结束后发送系统通知
+//! - 同一任务上一次执行尚未结束时跳过本次执行
+//!
+//! 系统休眠期间错过的执行按 [`MissedTaskPolicy`] 处理：`RunOnce` 和 `Shift`
+//! 在唤醒后补执行一次，`Skip` 直接跳过。
+
+use crate::agent::cron::CronSchedule;
+use crate::agent::types::NativeChatRequest;
+use crate::agent::{NativeAgentState, ToolLoopEngine};
+use crate::config::{MissedTaskPolicy, ScheduledTaskConfig};
+use crate::database::dao::scheduled_task::{ScheduledTaskDao, ScheduledTaskRun};
+use crate::database::DbConnection;
+use crate::power::SleepDetector;
+use crate::AppState;
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveDateTime, TimeZone, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::mpsc;
+
+/// 推送到前端的事件名
+pub const SCHEDULED_TASK_EVENT: &str = "agent-scheduled-task";
+
+/// 每个任务保留的执行记录数
+pub const MAX_RUNS_PER_TASK: u32 = 50;
+
+/// 调度器检查间隔
+const SCHEDULED_TASK_CHECK_INTERVAL: Duration = Duration::from_secs(20);
+
+/// 系统通知正文的最大字符数
+const NOTIFICATION_BODY_CHARS: usize = 200;
+
+/// 执行触发方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskTrigger {
+    /// 按计划执行
+    Schedule,
+    /// 系统唤醒后补执行休眠期间错过的计划
+    Missed,
+    /// 用户手动执行
+    Manual,
+}
+
+impl TaskTrigger {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TaskTrigger::Schedule => "schedule",
+            TaskTrigger::Missed => "missed",
+            TaskTrigger::Manual => "manual",
+        }
+    }
+}
+
+/// 定时任务及其调度状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledTaskStatus {
+    #[serde(flatten)]
+    pub task: ScheduledTaskConfig,
+    /// 下一次计划执行时间（任务已禁用或表达式无效时为空）
+    pub next_run: Option<DateTime<Local>>,
+    /// 是否正在执行
+    pub running: bool,
+    /// cron 表达式错误
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 校验定时任务配置：名称唯一、提示词非空、cron 表达式有效
+pub fn validate_tasks(tasks: &[ScheduledTaskConfig]) -> Result<(), String> {
+    let mut names = HashSet::new();
+    for task in tasks {
+        if task.name.trim().is_empty() {
+            return Err("定时任务名称不能为空".to_string());
+        }
+        if !names.insert(task.name.as_str()) {
+            return Err(format!("定时任务名称重复: {}", task.name));
+        }
+        if task.prompt.trim().is_empty() {
+            return Err(format!("定时任务 {} 的提示词不能为空", task.name));
+        }
+        CronSchedule::parse(&task.cron).map_err(|e| format!("定时任务 {}: {}", task.name, e))?;
+    }
+    Ok(())
+}
+
+/// 定时任务调度器（克隆后共享同一份任务表）
+#[derive(Clone, Default)]
+pub struct TaskScheduler {
+    tasks: Arc<RwLock<Vec<ScheduledTaskConfig>>>,
+    /// 正在执行的任务名称
+    running: Arc<RwLock<HashSet<String>>>,
+    /// 休眠期间错过的执行的处理方式
+    missed_policy: Arc<RwLock<MissedTaskPolicy>>,
+}
+
+impl TaskScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 更新任务列表
+    pub fn set_tasks(&self, tasks: Vec<ScheduledTaskConfig>) {
+        *self.tasks.write() = tasks;
+    }
+
+    /// 设置休眠期间错过的执行的处理方式
+    pub fn set_missed_task_policy(&self, policy: MissedTaskPolicy) {
+        *self.missed_policy.write() = policy;
+    }
+
+    pub fn missed_task_policy(&self) -> MissedTaskPolicy {
+        *self.missed_policy.read()
+    }
+
+    pub fn get(&self, name: &str) -> Option<ScheduledTaskConfig> {
+        self.tasks.read().iter().find(|t| t.name == name).cloned()
+    }
+
+    /// 列出任务及下一次执行时间
+    pub fn list(&self, now: DateTime<Local>) -> Vec<ScheduledTaskStatus> {
+        let running = self.running.read();
+        self.tasks
+            .read()
+            .iter()
+            .map(|task| {
+                let (next_run, error) = match CronSchedule::parse(&task.cron) {
+                    Ok(schedule) if task.enabled => (
+                        schedule
+                            .next_after(now.naive_local())
+                            .and_then(|next| Local.from_local_datetime(&next).earliest()),
+                        None,
+                    ),
+                    Ok(_) => (None, None),
+                    Err(e) => (None, Some(e)),
+                };
+                ScheduledTaskStatus {
+                    task: task.clone(),
+                    next_run,
+                    running: running.contains(&task.name),
+                    error,
+                }
+            })
+            .collect()
+    }
+
+    /// 计划执行时间落在 `(from, to]` 内的已启用任务（每个任务最多返回一次）
+    pub fn due(&self, from: NaiveDateTime, to: NaiveDateTime) -> Vec<ScheduledTaskConfig> {
+        self.tasks
+            .read()
+            .iter()
+            .filter(|task| task.enabled)
+            .filter(|task| match CronSchedule::parse(&task.cron) {
+                Ok(schedule) => schedule.next_after(from).is_some_and(|next| next <= to),
+                Err(e) => {
+                    tracing::warn!(
+                        "[ScheduledTask] 任务 {} 的 cron 表达式无效: {}",
+                        task.name,
+                        e
+                    );
+                    false
+                }
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// 标记任务开始执行，任务已在执行时返回 None
+    ///
+    /// 返回的标记释放时清除执行状态，任务 panic 时同样会清除
+    fn try_start(&self, name: &str) -> Option<RunningTask> {
+        self.running
+            .write()
+            .insert(name.to_string())
+            .then(|| RunningTask {
+                running: self.running.clone(),
+                name: name.to_string(),
+            })
+    }
+}
+
+/// 任务执行中标记
+struct RunningTask {
+    running: Arc<RwLock<HashSet<String>>>,
+    name: String,
+}
+
+impl Drop for RunningTask {
+    fn drop(&mut self) {
+        self.running.write().remove(&self.name);
+    }
+}
+
+/// 在临时会话中执行任务，返回 Agent 的最终回复
+async fn execute_task(
+    app_handle: &AppHandle,
+    task: &ScheduledTaskConfig,
+) -> Result<String, String> {
+    let agent_state = app_handle
+        .try_state::<NativeAgentState>()
+        .ok_or_else(|| "Agent 状态不可用".to_string())?;
+    let app_state = app_handle
+        .try_state::<AppState>()
+        .ok_or_else(|| "应用状态不可用".to_string())?;
+    crate::commands::native_agent_cmd::ensure_agent_initialized(&agent_state, &app_state, false)
+        .await?;
+
+    let session_id = agent_state.create_session(task.model.clone(), None)?;
+    let result = async {
+        agent_state.set_session_profile(&session_id, task.profile.clone())?;
+        let request = NativeChatRequest {
+            session_id: Some(session_id.clone()),
+            message: task.prompt.clone(),
+            model: task.model.clone(),
+            images: None,
+            audio: None,
+            attachments: None,
+            context: None,
+            stream: true,
+        };
+        let engine = ToolLoopEngine::new(agent_state.get_tool_registry(None)?);
+        let (tx, mut rx) = mpsc::channel(100);
+        let drain = tokio::spawn(async move { while rx.recv().await.is_some() {} });
+        let result = agent_state
+            .chat_stream_with_tools(request, tx, &engine)
+            .await;
+        let _ = drain.await;
+        result.map(|r| r.content).map_err(String::from)
+    }
+    .await;
+    agent_state.delete_session(&session_id);
+    result
+}
+
+fn emit_run(app_handle: &AppHandle, run: &ScheduledTaskRun) {
+    if let Err(e) = app_handle.emit(SCHEDULED_TASK_EVENT, run) {
+        tracing::warn!("[ScheduledTask] 推送执行记录失败: {}", e);
+    }
+}
+
+/// 保存执行记录，数据库不可用时只记录日志
+fn save_run(app_handle: &AppHandle, run: &ScheduledTaskRun) {
+    let Some(db) = app_handle.try_state::<DbConnection>() else {
+        return;
+    };
+    let result = db.lock().map_err(|e| e.to_string()).and_then(|conn| {
+        let saved = if run.finished_at.is_some() {
+            ScheduledTaskDao::finish(
+                &conn,
+                &run.id,
+                &run.status,
+                run.finished_at.as_deref().unwrap_or_default(),
+                run.output.as_deref(),
+                run.error.as_deref(),
+            )
+            .map(|_| ())
+        } else {
+            ScheduledTaskDao::insert(&conn, run)
+                .and_then(|_| ScheduledTaskDao::prune(&conn, &run.task_name, MAX_RUNS_PER_TASK))
+                .map(|_| ())
+        };
+        saved.map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        tracing::warn!("[ScheduledTask] 保存执行记录失败: {}", e);
+    }
+}
+
+fn notify(app_handle: &AppHandle, run: &ScheduledTaskRun) {
+    let (title, body) = match &run.error {
+        Some(error) => (
+            format!("定时任务 {} 执行失败", run.task_name),
+            error.clone(),
+        ),
+        None => (
+            format!("定时任务 {} 已完成", run.task_name),
+            run.output.clone().unwrap_or_default(),
+        ),
+    };
+    let body: String = body.chars().take(NOTIFICATION_BODY_CHARS).collect();
+    if let Err(e) = app_handle
+        .notification()
+        .builder()
+        .title(title)
+        .body(body)
+        .show()
+    {
+        tracing::warn!("[ScheduledTask] 发送系统通知失败: {}", e);
+    }
+}
+
+/// 开始执行任务，返回执行中的记录；执行在后台进行，结束后推送最终记录
+pub fn start_task(
+    app_handle: &AppHandle,
+    task: ScheduledTaskConfig,
+    trigger: TaskTrigger,
+) -> Result<ScheduledTaskRun, String> {
+    let scheduler = app_handle
+        .try_state::<NativeAgentState>()
+        .ok_or_else(|| "Agent 状态不可用".to_string())?
+        .scheduled_tasks()
+        .clone();
+    let Some(running) = scheduler.try_start(&task.name) else {
+        return Err(format!("定时任务 {} 正在执行", task.name));
+    };
+
+    tracing::info!(
+        "[ScheduledTask] 执行定时任务: name={}, trigger={}",
+        task.name,
+        trigger.as_str()
+    );
+    let run = ScheduledTaskRun {
+        id: uuid::Uuid::new_v4().to_string(),
+        task_name: task.name.clone(),
+        trigger: trigger.as_str().to_string(),
+        status: "running".to_string(),
+        started_at: Utc::now().to_rfc3339(),
+        finished_at: None,
+        output: None,
+        error: None,
+    };
+    save_run(app_handle, &run);
+    emit_run(app_handle, &run);
+
+    let app_handle = app_handle.clone();
+    let mut finished = run.clone();
+    tauri::async_runtime::spawn(async move {
+        // 在独立任务中执行，执行过程 panic 时同样记录为失败
+        let execution = {
+            let app_handle = app_handle.clone();
+            let task = task.clone();
+            tokio::spawn(async move { execute_task(&app_handle, &task).await })
+        };
+        let outcome = match execution.await {
+            Ok(outcome) => outcome,
+            Err(e) => Err(format!("任务执行异常: {}", e)),
+        };
+        drop(running);
+
+        finished.finished_at = Some(Utc::now().to_rfc3339());
+        match outcome {
+            Ok(output) => {
+                finished.status = "completed".to_string();
+                finished.output = Some(output);
+            }
+            Err(e) => {
+                tracing::warn!("[ScheduledTask] 定时任务 {} 执行失败: {}", task.name, e);
+                finished.status = "failed".to_string();
+                finished.error = Some(e);
+            }
+        }
+        save_run(&app_handle, &finished);
+        emit_run(&app_handle, &finished);
+        if task.notify {
+            notify(&app_handle, &finished);
+        }
+    });
+    Ok(run)
+}
+
+/// 启动定时任务调度器
+///
+/// 每隔 [`SCHEDULED_TASK_CHECK_INTERVAL`] 执行计划时间落在上次检查之后的任务。
+/// 启动时将上次退出前未执行完的记录标记为失败。
+pub fn spawn_task_scheduler(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        if let Some(db) = app_handle.try_state::<DbConnection>() {
+            if let Ok(conn) = db.lock() {
+                match ScheduledTaskDao::fail_unfinished(&conn, &Utc::now().to_rfc3339()) {
+                    Ok(0) => {}
+                    Ok(n) => tracing::info!("[ScheduledTask] {} 条未完成的执行记录已标记为失败", n),
+                    Err(e) => tracing::warn!("[ScheduledTask] 更新未完成的执行记录失败: {}", e),
+                }
+            }
+        }
+
+        let mut last_check = Local::now().naive_local();
+        let mut interval = tokio::time::interval(SCHEDULED_TASK_CHECK_INTERVAL);
+        let mut detector = SleepDetector::new(SCHEDULED_TASK_CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            let slept = detector.check();
+            let now = Local::now().naive_local();
+            // 时钟回拨（如夏令时结束）时等待追上上次检查时间，避免重复执行
+            if now <= last_check {
+                continue;
+            }
+
+            let Some(agent_state) = app_handle.try_state::<NativeAgentState>() else {
+                continue;
+            };
+            let scheduler = agent_state.scheduled_tasks().clone();
+
+            let mut from = last_check;
+            let mut trigger = TaskTrigger::Schedule;
+            if slept.is_some() {
+                if scheduler.missed_task_policy() == MissedTaskPolicy::Skip {
+                    let recent = now
+                        - ChronoDuration::from_std(SCHEDULED_TASK_CHECK_INTERVAL)
+                            .unwrap_or_else(|_| ChronoDuration::zero());
+                    from = from.max(recent);
+                } else {
+                    trigger = TaskTrigger::Missed;
+                }
+            }
+            last_check = now;
+
+            for task in scheduler.due(from, now) {
+                if let Err(e) = start_task(&app_handle, task, trigger) {
+                    tracing::warn!("[ScheduledTask] {}", e);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(name: &str, cron: &str) -> ScheduledTaskConfig {
+        ScheduledTaskConfig {
+            name: name.to_string(),
+            prompt: "summarize overnight alerts".to_string(),
+            cron: cron.to_string(),
+            profile: None,
+            model: None,
+            enabled: true,
+            notify: false,
+        }
+    }
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_validate_tasks() {
+        assert!(validate_tasks(&[task("a", "0 9 * * *"), task("b", "*/5 * * * *")]).is_ok());
+        assert!(validate_tasks(&[task("a", "0 9 * * *"), task("a", "0 10 * * *")]).is_err());
+        assert!(validate_tasks(&[task("a", "0 9 * *")]).is_err());
+
+        let mut empty = task("a", "0 9 * * *");
+        empty.prompt = "  ".to_string();
+        assert!(validate_tasks(&[empty]).is_err());
+    }
+
+    #[test]
+    fn test_due_window() {
+        let scheduler = TaskScheduler::new();
+        let mut disabled = task("disabled", "0 9 * * *");
+        disabled.enabled = false;
+        scheduler.set_tasks(vec![
+            task("morning", "0 9 * * *"),
+            task("hourly", "0 * * * *"),
+            disabled,
+        ]);
+
+        let names = |tasks: Vec<ScheduledTaskConfig>| -> Vec<String> {
+            tasks.into_iter().map(|t| t.name).collect()
+        };
+        assert_eq!(
+            names(scheduler.due(at("2026-03-02 08:59:50"), at("2026-03-02 09:00:10"))),
+            vec!["morning", "hourly"]
+        );
+        // 已经检查过 9:00 之后不会重复执行
+        assert!(scheduler
+            .due(at("2026-03-02 09:00:10"), at("2026-03-02 09:00:30"))
+            .is_empty());
+        // 休眠跨越多个计划时间时每个任务只执行一次
+        assert_eq!(
+            names(scheduler.due(at("2026-03-01 20:00:00"), at("2026-03-02 12:30:00"))),
+            vec!["morning", "hourly"]
+        );
+
+        // 同一任务不会并发执行，执行标记释放后（包括 panic）可再次执行
+        let running = scheduler.try_start("morning").unwrap();
+        assert!(scheduler.try_start("morning").is_none());
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
+            let _running = running;
+            panic!("task panicked");
+        }));
+        assert!(panicked.is_err());
+        assert!(scheduler.try_start("morning").is_some());
+    }
+}
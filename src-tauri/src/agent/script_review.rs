@@ -0,0 +1,159 @@
+//! Skill 脚本运行审核
+//!
+//! `run_skill_script` 工具运行脚本前先创建待批准的运行请求并等待：
+//! - 用户批准后工具运行脚本，把输出返回给模型
+//! - 用户拒绝、等待超时或会话被删除时，工具返回失败，脚本不会运行
+//!
+//! 请求放入 [`ApprovalQueue`]，状态变化时通过 [`SCRIPT_REQUEST_EVENT`] 事件推送到前端。
+
+use crate::agent::approval_queue::{ApprovalItem, ApprovalQueue};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 运行请求状态变化事件（载荷为 [`ScriptRunRequest`]）
+pub const SCRIPT_REQUEST_EVENT: &str = "agent-script-request";
+
+/// 每个会话同时等待批准的运行请求上限
+pub const MAX_PENDING_RUNS_PER_SESSION: usize = 3;
+
+/// 运行请求状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptRunStatus {
+    /// 等待用户批准
+    PendingApproval,
+    /// 已批准
+    Approved,
+    /// 用户拒绝
+    Rejected,
+    /// 等待超时或工具调用已结束
+    Expired,
+}
+
+/// 脚本运行请求
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScriptRunRequest {
+    pub id: String,
+    /// 所属会话
+    pub session_id: String,
+    /// Skill 目录名
+    pub skill: String,
+    /// 脚本路径（相对 Skill 目录）
+    pub script: String,
+    /// 命令行参数
+    pub args: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub status: ScriptRunStatus,
+}
+
+impl ApprovalItem for ScriptRunRequest {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn session_id(&self) -> Option<&str> {
+        Some(&self.session_id)
+    }
+
+    fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    fn is_pending(&self) -> bool {
+        self.status == ScriptRunStatus::PendingApproval
+    }
+
+    fn set_reviewed(&mut self, approved: bool) {
+        self.status = if approved {
+            ScriptRunStatus::Approved
+        } else {
+            ScriptRunStatus::Rejected
+        };
+    }
+
+    fn set_expired(&mut self) {
+        self.status = ScriptRunStatus::Expired;
+    }
+}
+
+impl ScriptRunRequest {
+    /// 新的待批准运行请求
+    pub fn new(session_id: &str, skill: &str, script: &str, args: Vec<String>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            session_id: session_id.to_string(),
+            skill: skill.to_string(),
+            script: script.to_string(),
+            args,
+            created_at: Utc::now(),
+            status: ScriptRunStatus::PendingApproval,
+        }
+    }
+}
+
+/// 脚本运行审核队列
+pub type ScriptReviewQueue = ApprovalQueue<ScriptRunRequest>;
+
+/// 创建脚本运行审核队列
+pub fn script_review_queue() -> ScriptReviewQueue {
+    ApprovalQueue::new(
+        SCRIPT_REQUEST_EVENT,
+        "脚本运行请求",
+        MAX_PENDING_RUNS_PER_SESSION,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_script_review_lifecycle() {
+        let queue = script_review_queue();
+        let (request, pending) = queue
+            .request(ScriptRunRequest::new(
+                "s1",
+                "pdf",
+                "scripts/fill.py",
+                vec!["a.pdf".to_string()],
+            ))
+            .unwrap();
+        assert_eq!(
+            queue.review(&request.id, true).unwrap().status,
+            ScriptRunStatus::Approved
+        );
+        assert_eq!(pending.wait(Duration::from_secs(1)).await, Some(true));
+        assert!(queue.review(&request.id, false).is_err());
+
+        // 工具调用已结束（等待句柄被丢弃）时审核失败并标记过期
+        let (dropped, pending) = queue
+            .request(ScriptRunRequest::new(
+                "s1",
+                "pdf",
+                "scripts/fill.py",
+                vec![],
+            ))
+            .unwrap();
+        drop(pending);
+        assert!(queue.review(&dropped.id, true).is_err());
+        let expired = queue
+            .list(Some("s1"))
+            .into_iter()
+            .find(|r| r.id == dropped.id);
+        assert_eq!(expired.unwrap().status, ScriptRunStatus::Expired);
+
+        let mut waiting = Vec::new();
+        for _ in 0..MAX_PENDING_RUNS_PER_SESSION {
+            let run = ScriptRunRequest::new("s1", "pdf", "run.sh", vec![]);
+            waiting.push(queue.request(run).unwrap().1);
+        }
+        assert!(queue
+            .request(ScriptRunRequest::new("s1", "pdf", "run.sh", vec![]))
+            .is_err());
+        queue.reject_session("s1");
+        for pending in waiting {
+            assert_eq!(pending.wait(Duration::from_secs(1)).await, Some(false));
+        }
+    }
+}
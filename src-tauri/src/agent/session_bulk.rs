@@ -0,0 +1,296 @@
+//! 会话批量操作
+//!
+//! 批量删除、打标签和导出会话，避免前端逐个调用：
+//! - 先校验全部会话 ID，任一不存在时整批失败，不做任何修改
+//! - 校验通过后在同一把写锁内完成全部修改，其他请求看不到中间状态
+//! - 每处理一个会话回调一次进度，由命令层通过 [`SESSION_BULK_PROGRESS_EVENT`] 推送给前端
+//!
+//! 导出先写入临时文件再重命名，失败时不会留下不完整的导出文件。
+
+use crate::agent::types::AgentSession;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// 推送到前端的进度事件名
+pub const SESSION_BULK_PROGRESS_EVENT: &str = "agent-session-bulk-progress";
+
+/// 批量操作类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkOperation {
+    Delete,
+    Tag,
+    Export,
+}
+
+/// 批量操作进度
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BulkProgress {
+    pub operation: BulkOperation,
+    /// 刚处理完的会话
+    pub session_id: String,
+    /// 已处理数量
+    pub processed: usize,
+    pub total: usize,
+}
+
+/// 批量导出结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkExportResult {
+    /// 导出文件路径
+    pub path: String,
+    /// 导出的会话数
+    pub sessions: usize,
+}
+
+/// 导出文件内容
+#[derive(Debug, Serialize, Deserialize)]
+struct SessionExport {
+    exported_at: String,
+    sessions: Vec<AgentSession>,
+}
+
+/// 去重并校验会话 ID，任一不存在时返回错误
+fn validate_ids(
+    sessions: &HashMap<String, AgentSession>,
+    ids: &[String],
+) -> Result<Vec<String>, String> {
+    let mut seen = HashSet::new();
+    let ids: Vec<String> = ids
+        .iter()
+        .filter(|id| seen.insert(id.as_str()))
+        .cloned()
+        .collect();
+    if ids.is_empty() {
+        return Err("未指定会话".to_string());
+    }
+
+    let missing: Vec<&str> = ids
+        .iter()
+        .filter(|id| !sessions.contains_key(id.as_str()))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "会话不存在: {}（未做任何修改）",
+            missing.join(", ")
+        ));
+    }
+    Ok(ids)
+}
+
+/// 批量删除会话，返回被删除的会话
+pub fn bulk_delete(
+    sessions: &mut HashMap<String, AgentSession>,
+    ids: &[String],
+    on_progress: &mut dyn FnMut(BulkProgress),
+) -> Result<Vec<AgentSession>, String> {
+    let ids = validate_ids(sessions, ids)?;
+    let total = ids.len();
+    let mut removed = Vec::with_capacity(total);
+    for (i, id) in ids.into_iter().enumerate() {
+        removed.extend(sessions.remove(&id));
+        on_progress(BulkProgress {
+            operation: BulkOperation::Delete,
+            session_id: id,
+            processed: i + 1,
+            total,
+        });
+    }
+    Ok(removed)
+}
+
+/// 批量添加/移除标签，返回修改的会话数
+///
+/// 标签去除首尾空白后比较，忽略空标签；同一标签同时出现在添加和移除中时以移除为准。
+pub fn bulk_tag(
+    sessions: &mut HashMap<String, AgentSession>,
+    ids: &[String],
+    add: &[String],
+    remove: &[String],
+    on_progress: &mut dyn FnMut(BulkProgress),
+) -> Result<usize, String> {
+    let normalize = |tags: &[String]| -> Vec<String> {
+        tags.iter()
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect()
+    };
+    let add = normalize(add);
+    let remove = normalize(remove);
+    if add.is_empty() && remove.is_empty() {
+        return Err("未指定要添加或移除的标签".to_string());
+    }
+
+    let ids = validate_ids(sessions, ids)?;
+    let total = ids.len();
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut changed = 0;
+    for (i, id) in ids.into_iter().enumerate() {
+        if let Some(session) = sessions.get_mut(&id) {
+            let before = session.tags.clone();
+            for tag in &add {
+                if !session.tags.contains(tag) {
+                    session.tags.push(tag.clone());
+                }
+            }
+            session.tags.retain(|t| !remove.contains(t));
+            if session.tags != before {
+                session.updated_at = now.clone();
+                changed += 1;
+            }
+        }
+        on_progress(BulkProgress {
+            operation: BulkOperation::Tag,
+            session_id: id,
+            processed: i + 1,
+            total,
+        });
+    }
+    Ok(changed)
+}
+
+/// 批量读取要导出的会话（按传入顺序）
+pub fn bulk_collect(
+    sessions: &HashMap<String, AgentSession>,
+    ids: &[String],
+    on_progress: &mut dyn FnMut(BulkProgress),
+) -> Result<Vec<AgentSession>, String> {
+    let ids = validate_ids(sessions, ids)?;
+    let total = ids.len();
+    let mut collected = Vec::with_capacity(total);
+    for (i, id) in ids.into_iter().enumerate() {
+        collected.extend(sessions.get(&id).cloned());
+        on_progress(BulkProgress {
+            operation: BulkOperation::Export,
+            session_id: id,
+            processed: i + 1,
+            total,
+        });
+    }
+    Ok(collected)
+}
+
+/// 导出目录（~/.proxycast/sessions/exports）
+pub fn exports_dir() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or_else(|| "无法获取用户 home 目录".to_string())?;
+    Ok(home.join(".proxycast").join("sessions").join("exports"))
+}
+
+/// 默认导出路径（~/.proxycast/sessions/exports/sessions-<时间>.json）
+pub fn default_export_path() -> Result<PathBuf, String> {
+    Ok(exports_dir()?.join(format!(
+        "sessions-{}.json",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    )))
+}
+
+/// 写入导出文件（先写同目录下的 `.tmp` 临时文件再重命名，不会留下写了一半的文件）
+pub fn write_export_file(path: &Path, content: &str) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("创建导出目录失败: {}", e))?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    std::fs::write(&tmp, content).map_err(|e| format!("写入导出文件失败: {}", e))?;
+    if let Err(e) = std::fs::rename(&tmp, path) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(format!("写入导出文件失败: {}", e));
+    }
+    Ok(())
+}
+
+/// 将会话写入 JSON 导出文件
+pub fn write_export(path: &Path, sessions: Vec<AgentSession>) -> Result<BulkExportResult, String> {
+    let count = sessions.len();
+    let export = SessionExport {
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        sessions,
+    };
+    let json =
+        serde_json::to_string_pretty(&export).map_err(|e| format!("序列化会话失败: {}", e))?;
+    write_export_file(path, &json)?;
+
+    Ok(BulkExportResult {
+        path: path.to_string_lossy().to_string(),
+        sessions: count,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::test_support::session;
+
+    fn sessions(ids: &[&str]) -> HashMap<String, AgentSession> {
+        ids.iter()
+            .map(|id| (id.to_string(), session(id, Vec::new())))
+            .collect()
+    }
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_bulk_delete_is_all_or_nothing() {
+        let mut map = sessions(&["a", "b", "c"]);
+        let mut progress = Vec::new();
+
+        let err = bulk_delete(&mut map, &ids(&["a", "x"]), &mut |p| progress.push(p));
+        assert!(err.unwrap_err().contains("x"));
+        assert_eq!(map.len(), 3);
+        assert!(progress.is_empty());
+
+        let removed =
+            bulk_delete(&mut map, &ids(&["a", "b", "a"]), &mut |p| progress.push(p)).unwrap();
+        assert_eq!(removed.len(), 2);
+        assert_eq!(map.len(), 1);
+        assert_eq!(progress.len(), 2);
+        assert_eq!(progress[1].processed, 2);
+        assert_eq!(progress[1].total, 2);
+    }
+
+    #[test]
+    fn test_bulk_tag() {
+        let mut map = sessions(&["a", "b"]);
+        map.get_mut("b").unwrap().tags = vec!["work".to_string()];
+
+        let changed = bulk_tag(
+            &mut map,
+            &ids(&["a", "b"]),
+            &ids(&[" work ", "urgent"]),
+            &[],
+            &mut |_| {},
+        )
+        .unwrap();
+        assert_eq!(changed, 2);
+        assert_eq!(map["a"].tags, ids(&["work", "urgent"]));
+        assert_eq!(map["b"].tags, ids(&["work", "urgent"]));
+
+        let changed =
+            bulk_tag(&mut map, &ids(&["a"]), &[], &ids(&["urgent"]), &mut |_| {}).unwrap();
+        assert_eq!(changed, 1);
+        assert_eq!(map["a"].tags, ids(&["work"]));
+
+        assert!(bulk_tag(&mut map, &ids(&["a"]), &ids(&[" "]), &[], &mut |_| {}).is_err());
+    }
+
+    #[test]
+    fn test_write_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out").join("sessions.json");
+        let map = sessions(&["a", "b"]);
+
+        let collected = bulk_collect(&map, &ids(&["b", "a"]), &mut |_| {}).unwrap();
+        let result = write_export(&path, collected).unwrap();
+
+        assert_eq!(result.sessions, 2);
+        let export: SessionExport =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(export.sessions[0].id, "b");
+        assert!(!path.with_extension("json.tmp").exists());
+    }
+}
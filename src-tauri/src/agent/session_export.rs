@@ -0,0 +1,402 @@
+//! 会话导出为 Markdown / HTML
+//!
+//! 用于分享对话：按顺序输出消息（角色、时间、文本、工具调用），图片以 data URL 内联，
+//! HTML 为不依赖外部资源的单文件。可选附带统计（消息数、估算 token 数，
+//! 启用会话记录时附带实际 token 用量）。
+
+use crate::agent::session_lint::{estimate_tokens, message_tokens};
+use crate::agent::types::{AgentMessage, AgentSession, ContentPart, MessageContent};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionExportFormat {
+    Markdown,
+    Html,
+}
+
+impl SessionExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+        }
+    }
+}
+
+/// 导出时附带的统计
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionExportStats {
+    pub messages: usize,
+    /// 估算的 token 数（约 4 字符 ≈ 1 token）
+    pub estimated_tokens: u32,
+    /// 实际输入 token（来自会话记录）
+    pub input_tokens: Option<u32>,
+    /// 实际输出 token（来自会话记录）
+    pub output_tokens: Option<u32>,
+}
+
+impl SessionExportStats {
+    pub fn from_session(session: &AgentSession) -> Self {
+        Self {
+            messages: session.messages.len(),
+            estimated_tokens: session
+                .system_prompt
+                .as_deref()
+                .map(estimate_tokens)
+                .unwrap_or(0)
+                + session.messages.iter().map(message_tokens).sum::<u32>(),
+            input_tokens: None,
+            output_tokens: None,
+        }
+    }
+}
+
+/// 渲染后的一段消息内容
+enum Block<'a> {
+    Text(&'a str),
+    Image(&'a str),
+    Note(String),
+}
+
+fn blocks(content: &MessageContent) -> Vec<Block<'_>> {
+    match content {
+        MessageContent::Text(text) => vec![Block::Text(text)],
+        MessageContent::Parts(parts) => parts
+            .iter()
+            .filter_map(|part| match part {
+                ContentPart::Text { text } if text.is_empty() => None,
+                ContentPart::Text { text } => Some(Block::Text(text)),
+                ContentPart::ImageUrl { image_url } => Some(Block::Image(&image_url.url)),
+                ContentPart::InputAudio { format, .. } => {
+                    Some(Block::Note(format!("[音频: {}]", format)))
+                }
+                ContentPart::Document { name, .. } => Some(Block::Note(format!(
+                    "[文档: {}]",
+                    name.as_deref().unwrap_or("未命名")
+                ))),
+            })
+            .collect(),
+    }
+}
+
+fn role_label(role: &str) -> &str {
+    match role {
+        "user" => "用户",
+        "assistant" => "助手",
+        "system" => "系统",
+        "tool" => "工具",
+        other => other,
+    }
+}
+
+/// 只内联 data URL 和 http(s) 图片
+fn is_safe_image_url(url: &str) -> bool {
+    url.starts_with("data:image/") || url.starts_with("https://") || url.starts_with("http://")
+}
+
+/// 选择比内容中最长的连续反引号更长的代码围栏
+fn code_fence(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn push_stats_markdown(out: &mut String, stats: &SessionExportStats) {
+    let _ = writeln!(out, "| 统计 | 数值 |\n|------|------|");
+    let _ = writeln!(out, "| 消息数 | {} |", stats.messages);
+    let _ = writeln!(out, "| 估算 tokens | {} |", stats.estimated_tokens);
+    if let (Some(input), Some(output)) = (stats.input_tokens, stats.output_tokens) {
+        let _ = writeln!(out, "| 输入 tokens | {} |", input);
+        let _ = writeln!(out, "| 输出 tokens | {} |", output);
+    }
+    out.push('\n');
+}
+
+/// 渲染为 Markdown
+pub fn render_markdown(session: &AgentSession, stats: Option<&SessionExportStats>) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# 会话 {}\n", session.id);
+    let _ = writeln!(out, "- 模型: `{}`", session.model);
+    let _ = writeln!(out, "- 创建时间: {}", session.created_at);
+    let _ = writeln!(out, "- 最后活动: {}", session.updated_at);
+    if !session.tags.is_empty() {
+        let _ = writeln!(out, "- 标签: {}", session.tags.join(", "));
+    }
+    out.push('\n');
+    if let Some(stats) = stats {
+        push_stats_markdown(&mut out, stats);
+    }
+    if let Some(prompt) = session.system_prompt.as_deref().filter(|p| !p.is_empty()) {
+        let fence = code_fence(prompt);
+        let _ = writeln!(out, "## 系统提示词\n\n{}\n{}\n{}\n", fence, prompt, fence);
+    }
+
+    for message in &session.messages {
+        push_message_markdown(&mut out, message);
+    }
+    out
+}
+
+fn push_message_markdown(out: &mut String, message: &AgentMessage) {
+    let _ = writeln!(
+        out,
+        "---\n\n### {} · {}\n",
+        role_label(&message.role),
+        message.timestamp
+    );
+    for block in blocks(&message.content) {
+        match block {
+            // 工具结果通常是 JSON 或命令输出，放在代码块中
+            Block::Text(text) if message.role == "tool" => {
+                let fence = code_fence(text);
+                let _ = writeln!(out, "{}\n{}\n{}\n", fence, text, fence);
+            }
+            Block::Text(text) => {
+                let _ = writeln!(out, "{}\n", text);
+            }
+            Block::Image(url) if is_safe_image_url(url) => {
+                let _ = writeln!(out, "![image]({})\n", url);
+            }
+            Block::Image(_) => {}
+            Block::Note(note) => {
+                let _ = writeln!(out, "_{}_\n", note);
+            }
+        }
+    }
+    for call in message.tool_calls.iter().flatten() {
+        let fence = code_fence(&call.function.arguments);
+        let _ = writeln!(
+            out,
+            "调用工具 `{}`:\n\n{}json\n{}\n{}\n",
+            call.function.name, fence, call.function.arguments, fence
+        );
+    }
+    if message.truncated == Some(true) {
+        let _ = writeln!(out, "_（已中断）_\n");
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const HTML_STYLE: &str = "body{font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',sans-serif;\
+max-width:860px;margin:2rem auto;padding:0 1rem;color:#1f2328;line-height:1.6}\
+.meta{color:#59636e;font-size:.9rem}\
+.msg{border:1px solid #d1d9e0;border-radius:8px;padding:.75rem 1rem;margin:1rem 0}\
+.msg.user{background:#f6f8fa}.msg.tool{background:#fffbea}\
+.head{font-weight:600;margin-bottom:.5rem}.head span{font-weight:400;color:#59636e;font-size:.85rem}\
+.text{white-space:pre-wrap;word-wrap:break-word}\
+pre{background:#f6f8fa;padding:.5rem;border-radius:6px;overflow-x:auto}\
+img{max-width:100%;border-radius:6px}table{border-collapse:collapse}\
+td,th{border:1px solid #d1d9e0;padding:.25rem .75rem}";
+
+/// 渲染为单文件 HTML
+pub fn render_html(session: &AgentSession, stats: Option<&SessionExportStats>) -> String {
+    let mut out = String::new();
+    let title = escape_html(&format!("会话 {}", session.id));
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n\
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+<title>{title}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n"
+    );
+    let _ = writeln!(
+        out,
+        "<p class=\"meta\">模型: <code>{}</code> · 创建时间: {} · 最后活动: {}</p>",
+        escape_html(&session.model),
+        escape_html(&session.created_at),
+        escape_html(&session.updated_at)
+    );
+    if !session.tags.is_empty() {
+        let _ = writeln!(
+            out,
+            "<p class=\"meta\">标签: {}</p>",
+            escape_html(&session.tags.join(", "))
+        );
+    }
+    if let Some(stats) = stats {
+        let _ = write!(
+            out,
+            "<table>\n<tr><th>消息数</th><td>{}</td></tr>\n<tr><th>估算 tokens</th><td>{}</td></tr>\n",
+            stats.messages, stats.estimated_tokens
+        );
+        if let (Some(input), Some(output)) = (stats.input_tokens, stats.output_tokens) {
+            let _ = write!(
+                out,
+                "<tr><th>输入 tokens</th><td>{}</td></tr>\n<tr><th>输出 tokens</th><td>{}</td></tr>\n",
+                input, output
+            );
+        }
+        out.push_str("</table>\n");
+    }
+    if let Some(prompt) = session.system_prompt.as_deref().filter(|p| !p.is_empty()) {
+        let _ = writeln!(
+            out,
+            "<h2>系统提示词</h2>\n<pre>{}</pre>",
+            escape_html(prompt)
+        );
+    }
+
+    for message in &session.messages {
+        push_message_html(&mut out, message);
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn push_message_html(out: &mut String, message: &AgentMessage) {
+    let _ = writeln!(
+        out,
+        "<div class=\"msg {}\">\n<div class=\"head\">{} <span>{}</span></div>",
+        escape_html(&message.role),
+        escape_html(role_label(&message.role)),
+        escape_html(&message.timestamp)
+    );
+    for block in blocks(&message.content) {
+        match block {
+            Block::Text(text) if message.role == "tool" => {
+                let _ = writeln!(out, "<pre>{}</pre>", escape_html(text));
+            }
+            Block::Text(text) => {
+                let _ = writeln!(out, "<div class=\"text\">{}</div>", escape_html(text));
+            }
+            Block::Image(url) if is_safe_image_url(url) => {
+                let _ = writeln!(out, "<img src=\"{}\" alt=\"image\">", escape_html(url));
+            }
+            Block::Image(_) => {}
+            Block::Note(note) => {
+                let _ = writeln!(out, "<p class=\"meta\">{}</p>", escape_html(&note));
+            }
+        }
+    }
+    for call in message.tool_calls.iter().flatten() {
+        let _ = writeln!(
+            out,
+            "<p class=\"meta\">调用工具 <code>{}</code></p>\n<pre>{}</pre>",
+            escape_html(&call.function.name),
+            escape_html(&call.function.arguments)
+        );
+    }
+    if message.truncated == Some(true) {
+        out.push_str("<p class=\"meta\">（已中断）</p>\n");
+    }
+    out.push_str("</div>\n");
+}
+
+/// 按格式渲染
+pub fn render(
+    session: &AgentSession,
+    format: SessionExportFormat,
+    stats: Option<&SessionExportStats>,
+) -> String {
+    match format {
+        SessionExportFormat::Markdown => render_markdown(session, stats),
+        SessionExportFormat::Html => render_html(session, stats),
+    }
+}
+
+/// 默认导出路径（~/.proxycast/sessions/exports/<会话 ID>-<时间>.md|html）
+pub fn default_export_path(
+    session_id: &str,
+    format: SessionExportFormat,
+) -> Result<PathBuf, String> {
+    Ok(crate::agent::session_bulk::exports_dir()?.join(format!(
+        "{}-{}.{}",
+        session_id,
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        format.extension()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::test_support;
+    use crate::agent::types::{FunctionCall, ImageUrl, ToolCall};
+
+    fn message(role: &str, content: MessageContent) -> AgentMessage {
+        AgentMessage {
+            timestamp: "2024-05-01T10:00:00Z".to_string(),
+            ..test_support::message(role, content)
+        }
+    }
+
+    fn session() -> AgentSession {
+        let mut assistant = message("assistant", MessageContent::Text("<b>hi</b>".to_string()));
+        assistant.tool_calls = Some(vec![ToolCall {
+            id: "call_1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "bash".to_string(),
+                arguments: "{\"command\":\"ls\"}".to_string(),
+            },
+        }]);
+        AgentSession {
+            model: "gpt-4o".to_string(),
+            system_prompt: Some("be brief".to_string()),
+            tags: vec!["work".to_string()],
+            created_at: "2024-05-01T09:00:00Z".to_string(),
+            updated_at: "2024-05-01T10:00:00Z".to_string(),
+            ..test_support::session(
+                "s1",
+                vec![
+                    message(
+                        "user",
+                        MessageContent::Parts(vec![
+                            ContentPart::Text {
+                                text: "看看这张图".to_string(),
+                            },
+                            ContentPart::ImageUrl {
+                                image_url: ImageUrl {
+                                    url: "data:image/png;base64,AAAA".to_string(),
+                                    detail: None,
+                                },
+                            },
+                        ]),
+                    ),
+                    assistant,
+                    message("tool", MessageContent::Text("a.txt\n```".to_string())),
+                ],
+            )
+        }
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let session = session();
+        let stats = SessionExportStats::from_session(&session);
+        let md = render_markdown(&session, Some(&stats));
+        assert!(md.starts_with("# 会话 s1"));
+        assert!(md.contains("| 消息数 | 3 |"));
+        assert!(md.contains("![image](data:image/png;base64,AAAA)"));
+        assert!(md.contains("调用工具 `bash`"));
+        // 工具结果中含有 ``` 时使用更长的围栏
+        assert!(md.contains("````\na.txt\n```\n````"));
+        assert!(!render_markdown(&session, None).contains("消息数"));
+    }
+
+    #[test]
+    fn test_render_html_escapes_content() {
+        let html = render_html(&session(), None);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("&lt;b&gt;hi&lt;/b&gt;"));
+        assert!(!html.contains("<b>hi</b>"));
+        assert!(html.contains("<img src=\"data:image/png;base64,AAAA\""));
+        assert!(html.contains("&quot;command&quot;"));
+    }
+}
@@ -0,0 +1,306 @@
+//! 会话分析（Conversation Lint）
+//!
+//! 后台定期分析 Agent 会话，检测以下问题并向前端推送压缩建议：
+//! - 话题转移：早期上下文与最近对话几乎无关，可以压缩
+//! - Token 膨胀：每轮对话的 token 消耗快速增长
+//!
+//! Token 数按约 4 字符 ≈ 1 token 粗略估算，仅用于给出建议。
+
+use crate::agent::types::{AgentMessage, AgentSession};
+use crate::agent::NativeAgentState;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// 推送到前端的事件名
+pub const SESSION_LINT_EVENT: &str = "agent-session-lint";
+
+/// 后台分析间隔
+const LINT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 参与分析的最少消息数
+const MIN_MESSAGES: usize = 8;
+
+/// 视为“最近对话”的消息数
+const RECENT_WINDOW: usize = 4;
+
+/// 早期与最近对话的词汇相似度低于该值时视为话题转移
+const TOPIC_SHIFT_THRESHOLD: f64 = 0.08;
+
+/// 最近几轮平均 token 数相对早期增长超过该倍数时提示
+const GROWTH_RATIO_THRESHOLD: f64 = 2.5;
+
+/// 会话总 token 少于该值时不提示 token 膨胀
+const MIN_TOKENS_FOR_GROWTH: u32 = 4000;
+
+/// 分析建议类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionLintKind {
+    /// 早期上下文可能已无关
+    StaleContext,
+    /// 每轮 token 消耗快速增长
+    TokenGrowth,
+}
+
+/// 会话分析建议
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionLintSuggestion {
+    /// 会话 ID
+    pub session_id: String,
+    /// 建议类型
+    pub kind: SessionLintKind,
+    /// 面向用户的提示文本
+    pub message: String,
+    /// 压缩后预计节省的 token 比例（0-100）
+    pub estimated_savings_pct: u8,
+    /// 建议压缩的早期消息数
+    pub compactable_messages: usize,
+}
+
+/// 粗略估算文本 token 数
+pub(crate) fn estimate_tokens(text: &str) -> u32 {
+    (text.chars().count() as u32).div_ceil(4)
+}
+
+pub(crate) fn message_tokens(msg: &AgentMessage) -> u32 {
+    let tool_args: usize = msg
+        .tool_calls
+        .as_ref()
+        .map(|calls| calls.iter().map(|c| c.function.arguments.len()).sum())
+        .unwrap_or(0);
+    estimate_tokens(&msg.content.as_text()) + (tool_args as u32).div_ceil(4)
+}
+
+/// 提取词汇集合（ASCII 单词 + CJK 单字）
+fn vocabulary(messages: &[AgentMessage]) -> HashSet<String> {
+    let mut words = HashSet::new();
+    for msg in messages {
+        let text = msg.content.as_text().to_lowercase();
+        let mut current = String::new();
+        for ch in text.chars() {
+            if ch.is_ascii_alphanumeric() || ch == '_' {
+                current.push(ch);
+                continue;
+            }
+            if current.len() >= 3 {
+                words.insert(std::mem::take(&mut current));
+            } else {
+                current.clear();
+            }
+            if ('\u{4e00}'..='\u{9fff}').contains(&ch) {
+                words.insert(ch.to_string());
+            }
+        }
+        if current.len() >= 3 {
+            words.insert(current);
+        }
+    }
+    words
+}
+
+/// Jaccard 相似度
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let intersection = a.intersection(b).count() as f64;
+    let union = a.union(b).count() as f64;
+    intersection / union
+}
+
+/// 按用户消息切分对话轮次，返回每轮 token 数
+fn turn_tokens(messages: &[AgentMessage]) -> Vec<u32> {
+    let mut turns = Vec::new();
+    let mut current = 0u32;
+    for msg in messages {
+        if msg.role == "user" && current > 0 {
+            turns.push(current);
+            current = 0;
+        }
+        current += message_tokens(msg);
+    }
+    if current > 0 {
+        turns.push(current);
+    }
+    turns
+}
+
+/// 分析单个会话
+pub fn lint_session(session: &AgentSession) -> Vec<SessionLintSuggestion> {
+    let messages = &session.messages;
+    let mut suggestions = Vec::new();
+    if messages.len() < MIN_MESSAGES {
+        return suggestions;
+    }
+
+    let total_tokens: u32 = messages.iter().map(message_tokens).sum();
+    if total_tokens == 0 {
+        return suggestions;
+    }
+
+    // 话题转移检测：早期消息与最近消息词汇重合度极低
+    let split = messages.len() - RECENT_WINDOW;
+    let (early, recent) = messages.split_at(split);
+    let similarity = jaccard(&vocabulary(early), &vocabulary(recent));
+    if similarity < TOPIC_SHIFT_THRESHOLD {
+        let early_tokens: u32 = early.iter().map(message_tokens).sum();
+        let pct = (early_tokens as u64 * 100 / total_tokens as u64) as u8;
+        suggestions.push(SessionLintSuggestion {
+            session_id: session.id.clone(),
+            kind: SessionLintKind::StaleContext,
+            message: format!(
+                "话题已明显转移，建议压缩此会话的早期 {} 条消息，可节省约 {}% 的 token",
+                early.len(),
+                pct
+            ),
+            estimated_savings_pct: pct,
+            compactable_messages: early.len(),
+        });
+    }
+
+    // Token 膨胀检测：最近几轮的平均消耗远高于最初几轮
+    let turns = turn_tokens(messages);
+    if turns.len() >= 6 && total_tokens >= MIN_TOKENS_FOR_GROWTH {
+        let first: f64 = turns[..3].iter().map(|t| *t as f64).sum::<f64>() / 3.0;
+        let last: f64 = turns[turns.len() - 3..]
+            .iter()
+            .map(|t| *t as f64)
+            .sum::<f64>()
+            / 3.0;
+        if first > 0.0 && last / first >= GROWTH_RATIO_THRESHOLD {
+            let compactable = split.max(1);
+            let compactable_tokens: u32 = messages[..compactable].iter().map(message_tokens).sum();
+            let pct = (compactable_tokens as u64 * 100 / total_tokens as u64) as u8;
+            suggestions.push(SessionLintSuggestion {
+                session_id: session.id.clone(),
+                kind: SessionLintKind::TokenGrowth,
+                message: format!(
+                    "每轮 token 消耗已增长到最初的 {:.1} 倍，建议压缩此会话，可节省约 {}% 的 token",
+                    last / first,
+                    pct
+                ),
+                estimated_savings_pct: pct,
+                compactable_messages: compactable,
+            });
+        }
+    }
+
+    suggestions
+}
+
+/// 启动后台会话分析任务
+///
+/// 每隔 [`LINT_INTERVAL`] 分析一次所有会话，仅在会话更新后重新分析，
+/// 有建议时通过 [`SESSION_LINT_EVENT`] 事件推送到前端。
+pub fn spawn_session_linter(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        // session_id -> 上次分析时的 updated_at
+        let mut analyzed: HashMap<String, String> = HashMap::new();
+        let mut interval = tokio::time::interval(LINT_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let Some(agent_state) = app_handle.try_state::<NativeAgentState>() else {
+                continue;
+            };
+            let sessions = agent_state.list_sessions();
+            analyzed.retain(|id, _| sessions.iter().any(|s| &s.id == id));
+
+            for session in sessions {
+                if analyzed.get(&session.id) == Some(&session.updated_at) {
+                    continue;
+                }
+                analyzed.insert(session.id.clone(), session.updated_at.clone());
+
+                let suggestions = lint_session(&session);
+                if suggestions.is_empty() {
+                    continue;
+                }
+                tracing::info!(
+                    "[SessionLint] 会话 {} 产生 {} 条建议",
+                    session.id,
+                    suggestions.len()
+                );
+                if let Err(e) = app_handle.emit(SESSION_LINT_EVENT, &suggestions) {
+                    tracing::warn!("[SessionLint] 推送建议失败: {}", e);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::test_support::{self, text_message as msg};
+
+    fn session(messages: Vec<AgentMessage>) -> AgentSession {
+        test_support::session("s1", messages)
+    }
+
+    #[test]
+    fn test_short_session_has_no_suggestions() {
+        let s = session(vec![msg("user", "hello"), msg("assistant", "hi")]);
+        assert!(lint_session(&s).is_empty());
+    }
+
+    #[test]
+    fn test_topic_shift_detected() {
+        let mut messages = Vec::new();
+        for _ in 0..3 {
+            messages.push(msg(
+                "user",
+                "configure postgres database replication settings",
+            ));
+            messages.push(msg(
+                "assistant",
+                "postgres replication uses wal streaming slots",
+            ));
+        }
+        messages.push(msg("user", "write haiku about autumn leaves"));
+        messages.push(msg("assistant", "crimson leaves drifting softly"));
+        messages.push(msg("user", "another haiku about winter snow"));
+        messages.push(msg("assistant", "silent snow blankets sleeping hills"));
+
+        let suggestions = lint_session(&session(messages));
+        let stale = suggestions
+            .iter()
+            .find(|s| s.kind == SessionLintKind::StaleContext)
+            .expect("应检测到话题转移");
+        assert_eq!(stale.compactable_messages, 6);
+        assert!(stale.estimated_savings_pct > 0);
+    }
+
+    #[test]
+    fn test_same_topic_not_flagged_as_stale() {
+        let mut messages = Vec::new();
+        for _ in 0..5 {
+            messages.push(msg("user", "refactor the rust parser module"));
+            messages.push(msg(
+                "assistant",
+                "the rust parser module now uses combinators",
+            ));
+        }
+        let suggestions = lint_session(&session(messages));
+        assert!(suggestions
+            .iter()
+            .all(|s| s.kind != SessionLintKind::StaleContext));
+    }
+
+    #[test]
+    fn test_token_growth_detected() {
+        let mut messages = Vec::new();
+        for i in 0..6 {
+            let size = if i < 3 { 100 } else { 6000 };
+            messages.push(msg("user", "analyze the build log output"));
+            messages.push(msg("assistant", &"build log output ".repeat(size / 17)));
+        }
+        let suggestions = lint_session(&session(messages));
+        assert!(suggestions
+            .iter()
+            .any(|s| s.kind == SessionLintKind::TokenGrowth));
+    }
+}
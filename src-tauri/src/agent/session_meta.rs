@@ -0,0 +1,241 @@
+//! 会话整理：标签、文件夹、置顶与归档
+//!
+//! 会话数量较多时按标签和文件夹分组，常用会话置顶，不再活跃的会话归档后默认不在列表中显示。
+//! 这里的归档只是标记，会话仍保留在内存中；需要释放配额时使用 [`crate::agent::session_quota`]
+//! 的归档（写入文件后移除）。
+
+use crate::agent::types::AgentSession;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 会话元数据修改（未设置的字段保持不变）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionMetaUpdate {
+    /// 替换全部标签
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    /// 移动到文件夹（空字符串表示移回根目录）
+    #[serde(default)]
+    pub folder: Option<String>,
+    #[serde(default)]
+    pub archived: Option<bool>,
+    #[serde(default)]
+    pub pinned: Option<bool>,
+}
+
+/// 会话列表筛选条件（除归档状态外，未设置的条件不参与筛选）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionFilter {
+    /// 包含全部指定标签
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 所在文件夹（空字符串表示根目录）
+    #[serde(default)]
+    pub folder: Option<String>,
+    /// 是否已归档（未设置时只包含未归档的会话）
+    #[serde(default)]
+    pub archived: Option<bool>,
+    #[serde(default)]
+    pub pinned: Option<bool>,
+}
+
+/// 文件夹及其中的会话数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionFolder {
+    pub name: String,
+    pub sessions: usize,
+}
+
+/// 去除首尾空白，忽略空标签并去重（保留原顺序）
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim();
+        if !tag.is_empty() && !result.iter().any(|t| t == tag) {
+            result.push(tag.to_string());
+        }
+    }
+    result
+}
+
+fn normalize_folder(folder: &str) -> Option<String> {
+    let folder = folder.trim().trim_matches('/');
+    (!folder.is_empty()).then(|| folder.to_string())
+}
+
+/// 应用元数据修改，返回会话是否发生变化
+///
+/// 标签和文件夹变化时更新 `updated_at`；置顶和归档只影响列表显示，不改变最后活动时间，
+/// 避免会话因此在列表中的位置跳动。
+pub fn apply_update(session: &mut AgentSession, update: &SessionMetaUpdate) -> bool {
+    let mut organized = false;
+    if let Some(tags) = &update.tags {
+        let tags = normalize_tags(tags);
+        organized |= session.tags != tags;
+        session.tags = tags;
+    }
+    if let Some(folder) = &update.folder {
+        let folder = normalize_folder(folder);
+        organized |= session.folder != folder;
+        session.folder = folder;
+    }
+    let mut changed = organized;
+    if let Some(archived) = update.archived {
+        changed |= session.archived != archived;
+        session.archived = archived;
+    }
+    if let Some(pinned) = update.pinned {
+        changed |= session.pinned != pinned;
+        session.pinned = pinned;
+    }
+    if organized {
+        session.updated_at = chrono::Utc::now().to_rfc3339();
+    }
+    changed
+}
+
+impl SessionFilter {
+    pub fn matches(&self, session: &AgentSession) -> bool {
+        let folder_matches = match &self.folder {
+            Some(folder) => session.folder == normalize_folder(folder),
+            None => true,
+        };
+        folder_matches
+            && normalize_tags(&self.tags)
+                .iter()
+                .all(|tag| session.tags.contains(tag))
+            && session.archived == self.archived.unwrap_or(false)
+            && self.pinned.is_none_or(|p| session.pinned == p)
+    }
+}
+
+/// 筛选会话，置顶的会话在前，其余按最后活动时间倒序
+pub fn filter_sessions(sessions: Vec<AgentSession>, filter: &SessionFilter) -> Vec<AgentSession> {
+    let mut sessions: Vec<AgentSession> =
+        sessions.into_iter().filter(|s| filter.matches(s)).collect();
+    sessions.sort_by(|a, b| {
+        b.pinned
+            .cmp(&a.pinned)
+            .then_with(|| b.updated_at.cmp(&a.updated_at))
+    });
+    sessions
+}
+
+/// 列出所有文件夹（按名称排序）
+pub fn list_folders(sessions: &[AgentSession]) -> Vec<SessionFolder> {
+    let mut folders: BTreeMap<&str, usize> = BTreeMap::new();
+    for session in sessions {
+        if let Some(folder) = &session.folder {
+            *folders.entry(folder).or_default() += 1;
+        }
+    }
+    folders
+        .into_iter()
+        .map(|(name, sessions)| SessionFolder {
+            name: name.to_string(),
+            sessions,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::test_support;
+
+    fn session(id: &str, updated_at: &str) -> AgentSession {
+        AgentSession {
+            updated_at: updated_at.to_string(),
+            ..test_support::session(id, Vec::new())
+        }
+    }
+
+    #[test]
+    fn test_apply_update() {
+        let mut s = session("a", "");
+        let update = SessionMetaUpdate {
+            tags: Some(vec![
+                " work ".to_string(),
+                "work".to_string(),
+                "".to_string(),
+            ]),
+            folder: Some("/projects/".to_string()),
+            pinned: Some(true),
+            ..Default::default()
+        };
+        assert!(apply_update(&mut s, &update));
+        assert_eq!(s.tags, vec!["work".to_string()]);
+        assert_eq!(s.folder.as_deref(), Some("projects"));
+        assert!(s.pinned && !s.archived);
+        assert!(!apply_update(&mut s, &update));
+
+        let update = SessionMetaUpdate {
+            folder: Some(String::new()),
+            ..Default::default()
+        };
+        assert!(apply_update(&mut s, &update));
+        assert_eq!(s.folder, None);
+
+        // 置顶和归档不改变最后活动时间
+        s.updated_at = "2024-01-01T00:00:00Z".to_string();
+        let update = SessionMetaUpdate {
+            archived: Some(true),
+            pinned: Some(false),
+            ..Default::default()
+        };
+        assert!(apply_update(&mut s, &update));
+        assert!(s.archived && !s.pinned);
+        assert_eq!(s.updated_at, "2024-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn test_filter_sessions() {
+        let mut a = session("a", "2024-01-01T00:00:00Z");
+        a.tags = vec!["work".to_string(), "urgent".to_string()];
+        a.folder = Some("projects".to_string());
+        let mut b = session("b", "2024-01-03T00:00:00Z");
+        b.tags = vec!["work".to_string()];
+        let mut c = session("c", "2024-01-02T00:00:00Z");
+        c.archived = true;
+        c.folder = Some("projects".to_string());
+        let mut d = session("d", "2023-12-01T00:00:00Z");
+        d.pinned = true;
+        let all = vec![a, b, c, d];
+
+        let ids = |filter: &SessionFilter| -> Vec<String> {
+            filter_sessions(all.clone(), filter)
+                .into_iter()
+                .map(|s| s.id)
+                .collect()
+        };
+        let active = SessionFilter {
+            archived: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(ids(&active), vec!["d", "b", "a"]);
+        assert_eq!(ids(&SessionFilter::default()), vec!["d", "b", "a"]);
+        let archived = SessionFilter {
+            archived: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(ids(&archived), vec!["c"]);
+        let work = SessionFilter {
+            tags: vec!["work".to_string(), "urgent".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(ids(&work), vec!["a"]);
+        let root = SessionFilter {
+            folder: Some(String::new()),
+            ..Default::default()
+        };
+        assert_eq!(ids(&root), vec!["d", "b"]);
+
+        assert_eq!(
+            list_folders(&all),
+            vec![SessionFolder {
+                name: "projects".to_string(),
+                sessions: 2,
+            }]
+        );
+    }
+}
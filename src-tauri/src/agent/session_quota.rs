@@ -0,0 +1,270 @@
+//! 会话配额
+//!
+//! 统计会话数量和所有会话保存的消息总数：
+//! - 使用量达到提醒阈值（默认 80%）时向前端推送提醒，并按最后活动时间给出建议归档的会话
+//! - 达到上限后禁止创建新会话
+//!
+//! 归档会将会话写入 `~/.proxycast/sessions/archive/<id>.json` 后从内存中移除。
+
+use crate::agent::types::AgentSession;
+use crate::agent::NativeAgentState;
+use crate::config::SessionQuotaConfig;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// 推送到前端的事件名
+pub const SESSION_QUOTA_EVENT: &str = "agent-session-quota";
+
+/// 后台检查间隔
+const QUOTA_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 配额使用等级
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaLevel {
+    /// 正常
+    Normal,
+    /// 达到提醒阈值
+    Warning,
+    /// 达到上限，禁止创建新会话
+    Exceeded,
+}
+
+/// 会话配额状态
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionQuotaStatus {
+    /// 使用等级
+    pub level: QuotaLevel,
+    /// 当前会话数
+    pub sessions: u32,
+    /// 最大会话数（0 表示不限制）
+    pub max_sessions: u32,
+    /// 当前消息总数
+    pub messages: u32,
+    /// 最大消息总数（0 表示不限制）
+    pub max_messages: u32,
+    /// 建议归档的会话 ID（按最后活动时间从旧到新）
+    pub archive_suggestions: Vec<String>,
+    /// 面向用户的提示文本
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// 使用百分比（上限为 0 时视为不限制）
+fn usage_percent(used: u32, limit: u32) -> u64 {
+    if limit == 0 {
+        0
+    } else {
+        used as u64 * 100 / limit as u64
+    }
+}
+
+/// 根据会话数和消息总数计算使用等级
+pub fn quota_level(sessions: u32, messages: u32, config: &SessionQuotaConfig) -> QuotaLevel {
+    let peak = usage_percent(sessions, config.max_sessions)
+        .max(usage_percent(messages, config.max_messages));
+    if peak >= 100 {
+        QuotaLevel::Exceeded
+    } else if peak >= config.warning_percent as u64 {
+        QuotaLevel::Warning
+    } else {
+        QuotaLevel::Normal
+    }
+}
+
+/// 评估会话配额，超过提醒阈值时给出归档建议
+pub fn evaluate_quota(
+    sessions: &[AgentSession],
+    config: &SessionQuotaConfig,
+) -> SessionQuotaStatus {
+    let session_count = sessions.len() as u32;
+    let message_count: u32 = sessions.iter().map(|s| s.messages.len() as u32).sum();
+    let level = quota_level(session_count, message_count, config);
+
+    let archive_suggestions = if level == QuotaLevel::Normal {
+        Vec::new()
+    } else {
+        suggest_archive(sessions, session_count, message_count, config)
+    };
+
+    let message = match level {
+        QuotaLevel::Normal => None,
+        QuotaLevel::Warning => Some(format!(
+            "会话存储即将达到上限（{} 个会话、{} 条消息），建议归档 {} 个最久未活动的会话",
+            session_count,
+            message_count,
+            archive_suggestions.len()
+        )),
+        QuotaLevel::Exceeded => Some(format!(
+            "会话存储已达到上限（{} 个会话、{} 条消息），无法创建新会话，请归档或删除旧会话",
+            session_count, message_count
+        )),
+    };
+
+    SessionQuotaStatus {
+        level,
+        sessions: session_count,
+        max_sessions: config.max_sessions,
+        messages: message_count,
+        max_messages: config.max_messages,
+        archive_suggestions,
+        message,
+    }
+}
+
+/// 从最久未活动的会话开始，选出归档后使用量回到提醒阈值以下所需的会话
+fn suggest_archive(
+    sessions: &[AgentSession],
+    mut session_count: u32,
+    mut message_count: u32,
+    config: &SessionQuotaConfig,
+) -> Vec<String> {
+    let mut ordered: Vec<&AgentSession> = sessions.iter().collect();
+    ordered.sort_by(|a, b| a.updated_at.cmp(&b.updated_at));
+
+    let mut suggestions = Vec::new();
+    for session in ordered {
+        if quota_level(session_count, message_count, config) == QuotaLevel::Normal {
+            break;
+        }
+        suggestions.push(session.id.clone());
+        session_count -= 1;
+        message_count -= session.messages.len() as u32;
+    }
+    suggestions
+}
+
+/// 会话归档目录（~/.proxycast/sessions/archive）
+fn archive_dir() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or_else(|| "无法获取用户 home 目录".to_string())?;
+    Ok(home.join(".proxycast").join("sessions").join("archive"))
+}
+
+/// 将会话写入归档目录，返回归档文件路径
+fn write_archive(dir: &Path, session: &AgentSession) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("创建归档目录失败: {}", e))?;
+    let path = dir.join(format!("{}.json", session.id));
+    let json =
+        serde_json::to_string_pretty(session).map_err(|e| format!("序列化会话失败: {}", e))?;
+    std::fs::write(&path, json).map_err(|e| format!("写入归档文件失败: {}", e))?;
+    Ok(path)
+}
+
+/// 归档会话：写入归档文件后从内存中移除，返回归档文件路径
+pub fn archive_sessions(
+    agent_state: &NativeAgentState,
+    session_ids: &[String],
+) -> Result<Vec<String>, String> {
+    let dir = archive_dir()?;
+    let mut archived = Vec::with_capacity(session_ids.len());
+    for session_id in session_ids {
+        let session = agent_state
+            .get_session(session_id)?
+            .ok_or_else(|| format!("会话不存在: {}", session_id))?;
+        let path = write_archive(&dir, &session)?;
+        agent_state.delete_session(session_id);
+        archived.push(path.to_string_lossy().to_string());
+    }
+    tracing::info!("[SessionQuota] 已归档 {} 个会话", archived.len());
+    Ok(archived)
+}
+
+/// 启动后台配额检查任务
+///
+/// 每隔 [`QUOTA_CHECK_INTERVAL`] 检查一次，使用等级升高到提醒或上限时
+/// 通过 [`SESSION_QUOTA_EVENT`] 事件推送配额状态和归档建议。
+pub fn spawn_session_quota_monitor(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut last_level = QuotaLevel::Normal;
+        let mut interval = tokio::time::interval(QUOTA_CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let Some(agent_state) = app_handle.try_state::<NativeAgentState>() else {
+                continue;
+            };
+            let status = agent_state.session_quota_status();
+            if status.level != last_level && status.level != QuotaLevel::Normal {
+                tracing::warn!(
+                    "[SessionQuota] 会话配额 {:?}: {} 个会话, {} 条消息",
+                    status.level,
+                    status.sessions,
+                    status.messages
+                );
+                if let Err(e) = app_handle.emit(SESSION_QUOTA_EVENT, &status) {
+                    tracing::warn!("[SessionQuota] 推送配额提醒失败: {}", e);
+                }
+            }
+            last_level = status.level;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::test_support::{self, text_message};
+
+    fn session(id: &str, updated_at: &str, messages: usize) -> AgentSession {
+        let messages = (0..messages).map(|_| text_message("user", "hi")).collect();
+        AgentSession {
+            updated_at: updated_at.to_string(),
+            ..test_support::session(id, messages)
+        }
+    }
+
+    fn config(max_sessions: u32, max_messages: u32) -> SessionQuotaConfig {
+        SessionQuotaConfig {
+            max_sessions,
+            max_messages,
+            warning_percent: 80,
+        }
+    }
+
+    #[test]
+    fn test_quota_level_thresholds() {
+        let config = config(10, 0);
+        assert_eq!(quota_level(7, 1_000_000, &config), QuotaLevel::Normal);
+        assert_eq!(quota_level(8, 0, &config), QuotaLevel::Warning);
+        assert_eq!(quota_level(10, 0, &config), QuotaLevel::Exceeded);
+    }
+
+    #[test]
+    fn test_warning_suggests_oldest_sessions() {
+        let sessions = vec![
+            session("new", "2026-01-03T00:00:00+00:00", 1),
+            session("old", "2026-01-01T00:00:00+00:00", 1),
+            session("mid", "2026-01-02T00:00:00+00:00", 1),
+            session("newest", "2026-01-04T00:00:00+00:00", 1),
+        ];
+        let status = evaluate_quota(&sessions, &config(5, 0));
+
+        assert_eq!(status.level, QuotaLevel::Warning);
+        assert_eq!(status.archive_suggestions, vec!["old".to_string()]);
+        assert!(status.message.is_some());
+    }
+
+    #[test]
+    fn test_message_volume_triggers_warning() {
+        let sessions = vec![
+            session("a", "2026-01-01T00:00:00+00:00", 6),
+            session("b", "2026-01-02T00:00:00+00:00", 3),
+        ];
+        let status = evaluate_quota(&sessions, &config(0, 10));
+
+        assert_eq!(status.level, QuotaLevel::Warning);
+        assert_eq!(status.archive_suggestions, vec!["a".to_string()]);
+    }
+
+    #[test]
+    fn test_write_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_archive(dir.path(), &session("s1", "", 2)).unwrap();
+        let restored: AgentSession =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(restored.messages.len(), 2);
+    }
+}
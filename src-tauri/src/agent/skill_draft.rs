@@ -0,0 +1,145 @@
+//! 从会话生成 Skill
+//!
+//! 后台模型把会话提炼为 SKILL.md（frontmatter + 使用说明 + 示例），这里负责：
+//! - 校验 Skill 名称（小写字母、数字和连字符）
+//! - 规范化模型输出：去掉代码块包裹，frontmatter 的 `name` 以用户指定的名称为准，缺少版本号时补 `1.0.0`
+//! - 写入 `~/.proxycast/skills/<name>/SKILL.md`，目录已存在时拒绝覆盖
+
+use std::path::{Path, PathBuf};
+
+/// Skill 名称最大长度
+const MAX_SKILL_NAME_LEN: usize = 64;
+
+/// 新生成 Skill 的默认版本号
+const DEFAULT_SKILL_VERSION: &str = "1.0.0";
+
+/// 生成的 Skill
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkillDraft {
+    pub name: String,
+    pub description: String,
+    /// SKILL.md 完整内容
+    pub content: String,
+}
+
+/// 校验 Skill 名称
+pub fn validate_skill_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_SKILL_NAME_LEN
+        && !name.starts_with('-')
+        && !name.ends_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "无效的 Skill 名称: {}（只能包含小写字母、数字和连字符，最长 {} 个字符）",
+            name, MAX_SKILL_NAME_LEN
+        ))
+    }
+}
+
+/// 规范化模型生成的 SKILL.md
+pub fn normalize_skill_markdown(output: &str, name: &str) -> Result<SkillDraft, String> {
+    let mut text = output.trim();
+    // 去掉 ```markdown ... ``` 包裹
+    if let Some(rest) = text.strip_prefix("```") {
+        let rest = rest.split_once('\n').map_or("", |(_, body)| body);
+        text = rest.trim_end().strip_suffix("```").unwrap_or(rest).trim();
+    }
+
+    let (front, body) =
+        split_frontmatter(text).ok_or_else(|| "生成的 SKILL.md 缺少 frontmatter".to_string())?;
+    let mut front: serde_yaml::Mapping = serde_yaml::from_str(front)
+        .map_err(|e| format!("生成的 SKILL.md frontmatter 无法解析: {}", e))?;
+    let description = front
+        .get("description")
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| "生成的 SKILL.md 缺少 description".to_string())?;
+    let body = body.trim();
+    if body.is_empty() {
+        return Err("生成的 SKILL.md 缺少使用说明".to_string());
+    }
+
+    front.insert("name".into(), name.into());
+    if !front.contains_key("version") {
+        front.insert("version".into(), DEFAULT_SKILL_VERSION.into());
+    }
+    let front = serde_yaml::to_string(&front).map_err(|e| e.to_string())?;
+
+    Ok(SkillDraft {
+        name: name.to_string(),
+        description,
+        content: format!("---\n{}---\n\n{}\n", front, body),
+    })
+}
+
+/// 拆分 frontmatter 和正文：第一行和结束行都必须是单独的 `---`
+///
+/// 按行匹配分隔符，frontmatter 或正文中出现的 `---`（如描述里的破折号、正文的分隔线）不会被误当作分隔符。
+fn split_frontmatter(text: &str) -> Option<(&str, &str)> {
+    let is_delimiter = |line: &str| line.trim_end() == "---";
+    let (first, rest) = text.split_once('\n')?;
+    if !is_delimiter(first) {
+        return None;
+    }
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if is_delimiter(line) {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// 写入 Skill 目录，返回 SKILL.md 路径
+pub fn write_skill(skills_dir: &Path, draft: &SkillDraft) -> Result<PathBuf, String> {
+    validate_skill_name(&draft.name)?;
+    let dir = skills_dir.join(&draft.name);
+    if dir.exists() {
+        return Err(format!("Skill 已存在: {}", draft.name));
+    }
+    std::fs::create_dir_all(&dir).map_err(|e| format!("创建 Skill 目录失败: {}", e))?;
+    let path = dir.join("SKILL.md");
+    std::fs::write(&path, &draft.content).map_err(|e| format!("写入 SKILL.md 失败: {}", e))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_and_write_skill() {
+        assert!(validate_skill_name("release-notes").is_ok());
+        assert!(validate_skill_name("Release Notes").is_err());
+        assert!(validate_skill_name("../x").is_err());
+
+        let output = "```markdown\n---\nname: something-else\ndescription: Write release notes from git history\n---\n\n# Release notes\n\n## Instructions\n1. Run git log\n\n## Example\n...\n```";
+        let draft = normalize_skill_markdown(output, "release-notes").unwrap();
+        assert_eq!(draft.description, "Write release notes from git history");
+        assert!(draft.content.starts_with("---\nname: release-notes\n"));
+        assert!(draft
+            .content
+            .contains("version: 1.0.0\n---\n\n# Release notes"));
+        assert!(normalize_skill_markdown("# No frontmatter", "x").is_err());
+        assert!(normalize_skill_markdown("---\nname: x\n---\nbody", "x").is_err());
+
+        // 描述和正文中的 `---` 不是分隔符
+        let output = "---\nname: x\ndescription: Notes --- with dashes\n---\n# Title\n\nA---B\n\n---\n\nMore\n";
+        let draft = normalize_skill_markdown(output, "x").unwrap();
+        assert_eq!(draft.description, "Notes --- with dashes");
+        assert!(draft.content.ends_with("# Title\n\nA---B\n\n---\n\nMore\n"));
+        assert!(normalize_skill_markdown("---x\ndescription: d\n---\nbody", "x").is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_skill(dir.path(), &draft).unwrap();
+        assert_eq!(std::fs::read_to_string(path).unwrap(), draft.content);
+        assert!(write_skill(dir.path(), &draft).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+//! Skill 使用统计
+//!
+//! 每轮对话结束后扫描新增的 assistant 消息，识别模型对 Skill 的使用：
+//! - 调用：`run_skill_script` 工具调用的 `skill` 参数
+//! - 引用：工具参数或回复文本中出现 `<skill>/SKILL.md` 路径（如 `read_file` 读取技能说明）
+//!
+//! 同一轮对话中每个 Skill 的引用和调用各最多计一次，统计结果保存在数据库，
+//! 用户可通过 `skill_stats` 找出长期未使用的 Skill。
+
+use crate::agent::types::AgentMessage;
+use crate::database::dao::skills::SkillDao;
+use crate::database::DbConnection;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeSet;
+
+/// 一次 Skill 使用
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SkillUse {
+    /// Skill 目录名
+    pub skill: String,
+    /// 是否为调用（否则为引用）
+    pub invoked: bool,
+}
+
+/// 从消息中识别 Skill 使用（去重后按名称排序）
+pub fn detect_skill_usage(messages: &[AgentMessage]) -> Vec<SkillUse> {
+    let mut uses = BTreeSet::new();
+    for message in messages.iter().filter(|m| m.role == "assistant") {
+        for skill in referenced_skills(&message.content.as_text()) {
+            uses.insert(SkillUse {
+                skill,
+                invoked: false,
+            });
+        }
+        for call in message.tool_calls.iter().flatten() {
+            if call.function.name == "run_skill_script" {
+                let skill = serde_json::from_str::<serde_json::Value>(&call.function.arguments)
+                    .ok()
+                    .and_then(|args| args.get("skill")?.as_str().map(str::to_string));
+                if let Some(skill) = skill.filter(|s| is_skill_name(s)) {
+                    uses.insert(SkillUse {
+                        skill,
+                        invoked: true,
+                    });
+                }
+            } else {
+                for skill in referenced_skills(&call.function.arguments) {
+                    uses.insert(SkillUse {
+                        skill,
+                        invoked: false,
+                    });
+                }
+            }
+        }
+    }
+    uses.into_iter().collect()
+}
+
+/// 本轮对话新增的消息（时间戳不早于本轮开始时间）
+///
+/// 按时间戳而不是下标筛选，本轮期间会话历史被裁剪或删除消息时也不会漏记或重复记录。
+pub fn messages_since(messages: &[AgentMessage], since: DateTime<Utc>) -> &[AgentMessage] {
+    let start = messages
+        .iter()
+        .rposition(|m| DateTime::parse_from_rfc3339(&m.timestamp).map_or(true, |t| t < since))
+        .map_or(0, |i| i + 1);
+    &messages[start..]
+}
+
+/// 文本中 `.../<skill>/SKILL.md` 路径引用的 Skill 目录名
+fn referenced_skills(text: &str) -> Vec<String> {
+    text.match_indices("SKILL.md")
+        .filter_map(|(index, _)| {
+            let before = &text[..index];
+            if !before.ends_with(['/', '\\']) {
+                return None;
+            }
+            // JSON 参数中的 Windows 路径分隔符会被转义为 `\\`
+            let name = before
+                .trim_end_matches(['/', '\\'])
+                .rsplit(['/', '\\'])
+                .next()?;
+            is_skill_name(name).then(|| name.to_string())
+        })
+        .collect()
+}
+
+fn is_skill_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Skill 使用统计存储
+#[derive(Clone)]
+pub struct SkillUsageStore {
+    db: DbConnection,
+}
+
+impl SkillUsageStore {
+    pub fn new(db: DbConnection) -> Self {
+        Self { db }
+    }
+
+    /// 识别并记录消息中的 Skill 使用
+    pub fn record_messages(&self, messages: &[AgentMessage]) -> Result<usize, String> {
+        let uses = detect_skill_usage(messages);
+        if uses.is_empty() {
+            return Ok(0);
+        }
+        let now = chrono::Utc::now().timestamp();
+        let conn = self.db.lock().map_err(|e| e.to_string())?;
+        for usage in &uses {
+            SkillDao::record_skill_use(&conn, &usage.skill, usage.invoked, now)
+                .map_err(|e| e.to_string())?;
+        }
+        tracing::debug!("[SkillUsage] 记录 Skill 使用: {:?}", uses);
+        Ok(uses.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::test_support::text_message;
+    use crate::agent::types::{FunctionCall, ToolCall};
+    use std::sync::{Arc, Mutex};
+
+    fn assistant(text: &str, calls: Vec<(&str, &str)>) -> AgentMessage {
+        let calls = calls
+            .into_iter()
+            .map(|(name, arguments)| ToolCall {
+                id: "call".to_string(),
+                call_type: "function".to_string(),
+                function: FunctionCall {
+                    name: name.to_string(),
+                    arguments: arguments.to_string(),
+                },
+            })
+            .collect();
+        AgentMessage {
+            tool_calls: Some(calls),
+            ..text_message("assistant", text)
+        }
+    }
+
+    #[test]
+    fn test_detect_and_record_skill_usage() {
+        let messages = vec![
+            assistant(
+                "",
+                vec![
+                    (
+                        "read_file",
+                        r#"{"path":"/home/u/.proxycast/skills/pdf/SKILL.md"}"#,
+                    ),
+                    (
+                        "read_file",
+                        r#"{"path":"C:\\Users\\u\\.proxycast\\skills\\docx\\SKILL.md"}"#,
+                    ),
+                    (
+                        "run_skill_script",
+                        r#"{"skill":"pdf","script":"scripts/fill.py"}"#,
+                    ),
+                ],
+            ),
+            assistant(
+                "按照 skills/pdf/SKILL.md 的说明完成了。SKILL.md 很有用",
+                vec![],
+            ),
+        ];
+        let uses = detect_skill_usage(&messages);
+        assert_eq!(
+            uses,
+            vec![
+                SkillUse {
+                    skill: "docx".to_string(),
+                    invoked: false
+                },
+                SkillUse {
+                    skill: "pdf".to_string(),
+                    invoked: false
+                },
+                SkillUse {
+                    skill: "pdf".to_string(),
+                    invoked: true
+                },
+            ]
+        );
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::database::schema::create_tables(&conn).unwrap();
+        let db: DbConnection = Arc::new(Mutex::new(conn));
+        let store = SkillUsageStore::new(db.clone());
+        assert_eq!(store.record_messages(&messages).unwrap(), 3);
+        store.record_messages(&messages[1..]).unwrap();
+
+        let stats = SkillDao::get_skill_usage(&db.lock().unwrap()).unwrap();
+        let pdf = stats.iter().find(|s| s.directory == "pdf").unwrap();
+        assert_eq!((pdf.reference_count, pdf.invoke_count), (2, 1));
+        assert!(pdf.last_used_at.is_some());
+    }
+
+    #[test]
+    fn test_messages_since() {
+        let since = Utc::now();
+        let at = |text: &str, offset: i64| AgentMessage {
+            timestamp: (since + chrono::Duration::seconds(offset)).to_rfc3339(),
+            ..text_message("assistant", text)
+        };
+        let messages = vec![
+            at("old", -60),
+            at("older turn", -1),
+            at("new", 0),
+            at("tool", 2),
+        ];
+        let new: Vec<String> = messages_since(&messages, since)
+            .iter()
+            .map(|m| m.content.as_text())
+            .collect();
+        assert_eq!(new, vec!["new", "tool"]);
+
+        // 本轮期间历史被裁剪：只剩本轮消息
+        assert_eq!(messages_since(&messages[2..], since).len(), 2);
+        assert!(messages_since(&messages[..2], since).is_empty());
+    }
+}
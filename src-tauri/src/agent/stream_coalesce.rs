@@ -0,0 +1,157 @@
+//! 流式文本合并
+//!
+//! 速度很快的 Provider 每个 token 都会产生一个 `TextDelta`，逐个通过 Tauri 事件推送会让前端频繁重绘。
+//! 转发前把连续的 `TextDelta` 合并：每次回复的首个片段立即推送以保证首字延迟，之后缓冲到
+//! `min_chunk_chars` 个字符或距上次推送超过 `flush_interval_ms` 时再推送；其他事件到达前先推送缓冲文本，
+//! 保持事件顺序不变。
+
+use crate::agent::types::StreamEvent;
+use crate::config::StreamCoalesceConfig;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// 流式文本合并器
+pub struct StreamCoalescer {
+    enabled: bool,
+    flush_interval: Duration,
+    min_chunk_chars: usize,
+    buffer: String,
+    /// 缓冲区开始积累文本的时间
+    buffered_since: Option<Instant>,
+    /// 当前回复是否已推送过文本（工具调用后的新回复重新计算首字）
+    first_sent: bool,
+}
+
+impl StreamCoalescer {
+    pub fn new(config: &StreamCoalesceConfig) -> Self {
+        Self {
+            enabled: config.enabled,
+            flush_interval: Duration::from_millis(config.flush_interval_ms),
+            min_chunk_chars: config.min_chunk_chars,
+            buffer: String::new(),
+            buffered_since: None,
+            first_sent: false,
+        }
+    }
+
+    /// 接收一个事件，返回需要立即推送的事件（按顺序）
+    pub fn push(&mut self, event: StreamEvent) -> Vec<StreamEvent> {
+        self.push_at(event, Instant::now())
+    }
+
+    fn push_at(&mut self, event: StreamEvent, now: Instant) -> Vec<StreamEvent> {
+        if !self.enabled {
+            return vec![event];
+        }
+        match event {
+            StreamEvent::TextDelta { text } => {
+                if !self.first_sent {
+                    self.first_sent = true;
+                    return vec![StreamEvent::TextDelta { text }];
+                }
+                self.buffer.push_str(&text);
+                let since = *self.buffered_since.get_or_insert(now);
+                if self.buffer.chars().count() >= self.min_chunk_chars
+                    || now.duration_since(since) >= self.flush_interval
+                {
+                    self.flush().into_iter().collect()
+                } else {
+                    Vec::new()
+                }
+            }
+            other => {
+                if matches!(
+                    other,
+                    StreamEvent::ToolStart { .. } | StreamEvent::Done { .. }
+                ) {
+                    self.first_sent = false;
+                }
+                let mut events: Vec<StreamEvent> = self.flush().into_iter().collect();
+                events.push(other);
+                events
+            }
+        }
+    }
+
+    /// 缓冲文本的推送期限（没有缓冲文本时返回 None）
+    pub fn deadline(&self) -> Option<Instant> {
+        self.buffered_since.map(|since| since + self.flush_interval)
+    }
+
+    /// 取出缓冲的文本
+    pub fn flush(&mut self) -> Option<StreamEvent> {
+        self.buffered_since = None;
+        if self.buffer.is_empty() {
+            return None;
+        }
+        Some(StreamEvent::TextDelta {
+            text: std::mem::take(&mut self.buffer),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delta(text: &str) -> StreamEvent {
+        StreamEvent::TextDelta {
+            text: text.to_string(),
+        }
+    }
+
+    fn texts(events: &[StreamEvent]) -> Vec<String> {
+        events
+            .iter()
+            .map(|e| match e {
+                StreamEvent::TextDelta { text } => text.clone(),
+                other => format!("<{:?}>", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_coalesce_text_deltas() {
+        let mut c = StreamCoalescer::new(&StreamCoalesceConfig {
+            enabled: true,
+            flush_interval_ms: 50,
+            min_chunk_chars: 5,
+        });
+        let t0 = Instant::now();
+        // 首字立即推送
+        assert_eq!(texts(&c.push_at(delta("H"), t0)), vec!["H"]);
+        assert!(c.push_at(delta("el"), t0).is_empty());
+        assert_eq!(c.deadline(), Some(t0 + Duration::from_millis(50)));
+        // 达到最小字符数时推送
+        assert_eq!(texts(&c.push_at(delta("lo!"), t0)), vec!["ello!"]);
+        assert_eq!(c.deadline(), None);
+        // 超过间隔时推送
+        assert!(c.push_at(delta("a"), t0).is_empty());
+        assert_eq!(
+            texts(&c.push_at(delta("b"), t0 + Duration::from_millis(60))),
+            vec!["ab"]
+        );
+        // 其他事件前先推送缓冲文本
+        assert!(c.push_at(delta("c"), t0).is_empty());
+        let events = c.push_at(
+            StreamEvent::Error {
+                message: "x".to_string(),
+                error: None,
+            },
+            t0,
+        );
+        assert_eq!(events.len(), 2);
+        assert_eq!(texts(&events[..1]), vec!["c"]);
+    }
+
+    #[test]
+    fn test_disabled_passthrough() {
+        let mut c = StreamCoalescer::new(&StreamCoalesceConfig {
+            enabled: false,
+            ..Default::default()
+        });
+        assert_eq!(texts(&c.push(delta("a"))), vec!["a"]);
+        assert_eq!(texts(&c.push(delta("b"))), vec!["b"]);
+        assert!(c.flush().is_none());
+    }
+}
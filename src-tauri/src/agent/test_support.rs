@@ -0,0 +1,45 @@
+//! 测试用的消息和会话构造函数
+
+use crate::agent::types::{AgentMessage, AgentSession, MessageContent};
+
+/// 指定内容的消息（时间戳为空）
+pub fn message(role: &str, content: MessageContent) -> AgentMessage {
+    AgentMessage {
+        role: role.to_string(),
+        content,
+        timestamp: String::new(),
+        tool_calls: None,
+        tool_call_id: None,
+        attachments: None,
+        truncated: None,
+    }
+}
+
+/// 文本消息（时间戳为空）
+pub fn text_message(role: &str, text: &str) -> AgentMessage {
+    message(role, MessageContent::Text(text.to_string()))
+}
+
+/// 包含给定消息的会话（模型为 `test`，其余字段为空）
+pub fn session(id: &str, messages: Vec<AgentMessage>) -> AgentSession {
+    AgentSession {
+        id: id.to_string(),
+        model: "test".to_string(),
+        messages,
+        system_prompt: None,
+        memory_prompt: None,
+        knowledge_collection: None,
+        model_pin: None,
+        model_changes: Vec::new(),
+        tags: Vec::new(),
+        profile: None,
+        folder: None,
+        repo_path: None,
+        http_domains: Vec::new(),
+        permission_mode: None,
+        archived: false,
+        pinned: false,
+        created_at: String::new(),
+        updated_at: String::new(),
+    }
+}
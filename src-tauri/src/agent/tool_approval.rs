@@ -0,0 +1,260 @@
+//! 工具权限与调用审核
+//!
+//! 工具注册表按权限模式决定工具调用能否执行（见 [`ToolRegistry::authorize`]）：
+//! - `auto`：所有工具直接执行（不受隔离地运行代码的工具除外，仍需用户批准）
+//! - `approve`：会修改状态的工具先创建待批准的调用请求并等待，用户批准后执行，拒绝或超时则不执行
+//! - `read-only`：只允许只读工具，其余工具直接返回错误
+//!
+//! 自带审核流程的工具（`git_apply_patch`、`run_skill_script` 等）在 `approve` 模式下不重复请求批准。
+//! 调用请求放入 [`ApprovalQueue`]，状态变化时通过 [`TOOL_APPROVAL_EVENT`] 事件推送到前端，
+//! 对话被取消时请求随之过期。
+//!
+//! [`ToolRegistry::authorize`]: crate::agent::tools::ToolRegistry::authorize
+
+use crate::agent::approval_queue::{ApprovalItem, ApprovalQueue};
+use crate::agent::tools::{ToolAccess, ToolError};
+use crate::config::PermissionMode;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::info;
+
+/// 等待用户批准的时间
+pub const APPROVAL_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// 调用请求状态变化事件（载荷为 [`ToolApprovalRequest`]）
+pub const TOOL_APPROVAL_EVENT: &str = "agent-tool-approval";
+
+/// 每个会话同时等待批准的调用请求上限（工具调用按顺序执行，正常情况下只有一个）
+pub const MAX_PENDING_APPROVALS_PER_SESSION: usize = 8;
+
+/// 调用请求状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolApprovalStatus {
+    /// 等待用户批准
+    PendingApproval,
+    /// 已批准
+    Approved,
+    /// 用户拒绝
+    Rejected,
+    /// 等待超时或工具调用已结束
+    Expired,
+}
+
+/// 工具调用请求
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolApprovalRequest {
+    pub id: String,
+    /// 所属会话（定时任务等无会话的调用为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    pub tool_name: String,
+    /// 调用参数
+    pub arguments: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub status: ToolApprovalStatus,
+}
+
+impl ApprovalItem for ToolApprovalRequest {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
+
+    fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    fn is_pending(&self) -> bool {
+        self.status == ToolApprovalStatus::PendingApproval
+    }
+
+    fn set_reviewed(&mut self, approved: bool) {
+        self.status = if approved {
+            ToolApprovalStatus::Approved
+        } else {
+            ToolApprovalStatus::Rejected
+        };
+    }
+
+    fn set_expired(&mut self) {
+        self.status = ToolApprovalStatus::Expired;
+    }
+}
+
+/// 工具调用审核队列
+pub type ToolApprovalQueue = ApprovalQueue<ToolApprovalRequest>;
+
+/// 创建工具调用审核队列
+pub fn tool_approval_queue() -> ToolApprovalQueue {
+    ApprovalQueue::new(
+        TOOL_APPROVAL_EVENT,
+        "工具调用请求",
+        MAX_PENDING_APPROVALS_PER_SESSION,
+    )
+}
+
+/// 工具注册表的权限设置
+#[derive(Clone, Default)]
+pub struct ToolPermission {
+    pub mode: PermissionMode,
+    /// `approve` 模式下提交调用请求的队列（为空时需要批准的调用直接拒绝）
+    pub approvals: Option<ToolApprovalQueue>,
+    pub session_id: Option<String>,
+}
+
+impl ToolPermission {
+    pub fn new(mode: PermissionMode) -> Self {
+        Self {
+            mode,
+            ..Self::default()
+        }
+    }
+
+    /// 使用审核队列，调用请求记在指定会话下
+    pub fn with_approvals(mut self, queue: ToolApprovalQueue, session_id: Option<&str>) -> Self {
+        self.approvals = Some(queue);
+        self.session_id = session_id.map(str::to_string);
+        self
+    }
+
+    /// 检查工具调用是否允许执行，需要批准时等待用户审核
+    pub async fn check(
+        &self,
+        tool_name: &str,
+        access: ToolAccess,
+        arguments: &serde_json::Value,
+    ) -> Result<(), ToolError> {
+        match (self.mode, access) {
+            (_, ToolAccess::ReadOnly) => Ok(()),
+            (PermissionMode::ReadOnly, _) => Err(ToolError::Security(format!(
+                "当前为只读模式，不允许调用会修改状态的工具: {}",
+                tool_name
+            ))),
+            (_, ToolAccess::Unconfined) => self.wait_for_approval(tool_name, arguments).await,
+            (PermissionMode::Auto, _) => Ok(()),
+            (PermissionMode::Approve, ToolAccess::Reviewed) => Ok(()),
+            (PermissionMode::Approve, ToolAccess::Mutating) => {
+                self.wait_for_approval(tool_name, arguments).await
+            }
+        }
+    }
+
+    async fn wait_for_approval(
+        &self,
+        tool_name: &str,
+        arguments: &serde_json::Value,
+    ) -> Result<(), ToolError> {
+        let queue = self.approvals.as_ref().ok_or_else(|| {
+            ToolError::Security(format!(
+                "当前需要批准工具调用，但无法请求批准: {}",
+                tool_name
+            ))
+        })?;
+        let (request, pending) = queue
+            .request(ToolApprovalRequest {
+                id: uuid::Uuid::new_v4().to_string(),
+                session_id: self.session_id.clone(),
+                tool_name: tool_name.to_string(),
+                arguments: arguments.clone(),
+                created_at: Utc::now(),
+                status: ToolApprovalStatus::PendingApproval,
+            })
+            .map_err(ToolError::Security)?;
+        info!(
+            "[ToolPermission] 等待批准工具调用: id={}, session={:?}, tool={}",
+            request.id, self.session_id, tool_name
+        );
+        match pending.wait(APPROVAL_TIMEOUT).await {
+            Some(true) => Ok(()),
+            Some(false) => Err(ToolError::Security("用户拒绝执行该工具调用".to_string())),
+            None => Err(ToolError::Security(format!(
+                "用户在 {} 秒内没有批准，工具未执行",
+                APPROVAL_TIMEOUT.as_secs()
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_permission_modes() {
+        let args = serde_json::json!({ "command": "ls" });
+        let auto = ToolPermission::new(PermissionMode::Auto);
+        assert!(auto
+            .check("bash", ToolAccess::Mutating, &args)
+            .await
+            .is_ok());
+        // 不受隔离地运行代码时 auto 模式也需要批准，没有审核队列时直接拒绝
+        assert!(auto
+            .check("run_code", ToolAccess::Unconfined, &args)
+            .await
+            .is_err());
+
+        let read_only = ToolPermission::new(PermissionMode::ReadOnly);
+        assert!(read_only
+            .check("read_file", ToolAccess::ReadOnly, &args)
+            .await
+            .is_ok());
+        assert!(read_only
+            .check("bash", ToolAccess::Mutating, &args)
+            .await
+            .is_err());
+        assert!(read_only
+            .check("git_apply_patch", ToolAccess::Reviewed, &args)
+            .await
+            .is_err());
+
+        // 没有审核队列时需要批准的调用直接拒绝
+        let approve = ToolPermission::new(PermissionMode::Approve);
+        assert!(approve
+            .check("git_apply_patch", ToolAccess::Reviewed, &args)
+            .await
+            .is_ok());
+        assert!(approve
+            .check("bash", ToolAccess::Mutating, &args)
+            .await
+            .is_err());
+
+        let queue = tool_approval_queue();
+        let approve = approve.with_approvals(queue.clone(), Some("s1"));
+        let reviewer = tokio::spawn(async move {
+            loop {
+                if let Some(request) = queue.list(Some("s1")).pop() {
+                    return queue.review(&request.id, true).unwrap();
+                }
+                tokio::task::yield_now().await;
+            }
+        });
+        assert!(approve
+            .check("bash", ToolAccess::Mutating, &args)
+            .await
+            .is_ok());
+        let request = reviewer.await.unwrap();
+        assert_eq!(request.tool_name, "bash");
+        assert_eq!(request.status, ToolApprovalStatus::Approved);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_call_expires_request() {
+        let queue = tool_approval_queue();
+        let permission =
+            ToolPermission::new(PermissionMode::Approve).with_approvals(queue.clone(), Some("s1"));
+        let args = serde_json::json!({});
+        // 对话被取消时工具调用的 future 被丢弃，请求不再停留在待批准状态
+        let check = permission.check("write_file", ToolAccess::Mutating, &args);
+        assert!(tokio::time::timeout(Duration::from_millis(10), check)
+            .await
+            .is_err());
+        let request = queue.list(Some("s1")).pop().unwrap();
+        assert_eq!(request.status, ToolApprovalStatus::Expired);
+        assert!(queue.review(&request.id, true).is_err());
+    }
+}
@@ -8,14 +8,22 @@
 //! - 执行工具并收集结果
 //! - 将工具结果发送回 Agent 继续对话
 //! - 最大迭代限制防止无限循环
+//! - 单个工具超时或被用户取消时返回"已超时/已取消"结果，对话继续
 
-use crate::agent::tools::{ToolError, ToolRegistry, ToolResult as ToolsResult};
+use crate::agent::tools::{
+    format_violations, ToolError, ToolRegistry, ToolResult as ToolsResult,
+    DEFAULT_TOOL_TIMEOUT_SECS,
+};
 use crate::agent::types::{
     AgentMessage, MessageContent, StreamEvent, StreamResult, ToolCall, ToolExecutionResult,
+    ToolInterruption,
 };
+use parking_lot::Mutex;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
 use tracing::{debug, warn};
 
 /// 工具循环错误类型
@@ -53,6 +61,8 @@ pub struct ToolCallResult {
     pub tool_name: String,
     /// 执行结果
     pub result: ToolsResult,
+    /// 超时或被取消时的中断原因
+    pub interrupted: Option<ToolInterruption>,
 }
 
 impl ToolCallResult {
@@ -62,6 +72,22 @@ impl ToolCallResult {
             tool_call_id,
             tool_name,
             result,
+            interrupted: None,
+        }
+    }
+
+    /// 创建被中断（超时或取消）的工具调用结果
+    pub fn interrupted(
+        tool_call_id: String,
+        tool_name: String,
+        reason: ToolInterruption,
+        message: String,
+    ) -> Self {
+        Self {
+            tool_call_id,
+            tool_name,
+            result: ToolsResult::failure(message),
+            interrupted: Some(reason),
         }
     }
 
@@ -84,6 +110,8 @@ impl ToolCallResult {
             timestamp: chrono::Utc::now().to_rfc3339(),
             tool_calls: None,
             tool_call_id: Some(self.tool_call_id.clone()),
+            attachments: (!self.result.artifacts.is_empty()).then(|| self.result.artifacts.clone()),
+            truncated: None,
         }
     }
 
@@ -93,6 +121,8 @@ impl ToolCallResult {
             success: self.result.success,
             output: self.result.output.clone(),
             error: self.result.error.clone(),
+            interrupted: self.interrupted,
+            artifacts: self.result.artifacts.clone(),
         }
     }
 }
@@ -120,6 +150,55 @@ impl ToolLoopConfig {
     }
 }
 
+/// 取消信号的登记键：（会话 ID，工具调用 ID），无会话的对话会话 ID 为空
+///
+/// 工具调用 ID 由上游生成，不同会话之间可能重复（如 `call_0`），因此按会话区分
+type ToolCallKey = (String, String);
+
+/// 运行中工具调用的取消信号（按会话和工具调用 ID 登记，克隆后共享）
+#[derive(Clone, Default)]
+pub struct ToolCancellations {
+    pending: Arc<Mutex<HashMap<ToolCallKey, oneshot::Sender<()>>>>,
+}
+
+impl ToolCancellations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn key(session_id: Option<&str>, tool_id: &str) -> ToolCallKey {
+        (
+            session_id.unwrap_or_default().to_string(),
+            tool_id.to_string(),
+        )
+    }
+
+    fn register(&self, session_id: Option<&str>, tool_id: &str) -> oneshot::Receiver<()> {
+        let (tx, rx) = oneshot::channel();
+        self.pending
+            .lock()
+            .insert(Self::key(session_id, tool_id), tx);
+        rx
+    }
+
+    fn unregister(&self, session_id: Option<&str>, tool_id: &str) {
+        self.pending.lock().remove(&Self::key(session_id, tool_id));
+    }
+
+    /// 取消会话中运行的工具调用，返回是否找到该调用
+    pub fn cancel(&self, session_id: Option<&str>, tool_id: &str) -> bool {
+        match self.pending.lock().remove(&Self::key(session_id, tool_id)) {
+            Some(tx) => tx.send(()).is_ok(),
+            None => false,
+        }
+    }
+
+    /// 运行中的工具调用（会话 ID，工具调用 ID）
+    pub fn running(&self) -> Vec<(String, String)> {
+        self.pending.lock().keys().cloned().collect()
+    }
+}
+
 /// 工具循环引擎
 ///
 /// 负责执行工具调用循环，直到 Agent 产生最终响应
@@ -129,6 +208,10 @@ pub struct ToolLoopEngine {
     registry: Arc<ToolRegistry>,
     /// 配置
     config: ToolLoopConfig,
+    /// 工具调用取消信号
+    cancellations: ToolCancellations,
+    /// 工具调用所属的会话（用于登记取消信号）
+    session_id: Option<String>,
 }
 
 impl ToolLoopEngine {
@@ -137,12 +220,30 @@ impl ToolLoopEngine {
         Self {
             registry,
             config: ToolLoopConfig::default(),
+            cancellations: ToolCancellations::new(),
+            session_id: None,
         }
     }
 
     /// 使用自定义配置创建
     pub fn with_config(registry: Arc<ToolRegistry>, config: ToolLoopConfig) -> Self {
-        Self { registry, config }
+        Self {
+            registry,
+            config,
+            cancellations: ToolCancellations::new(),
+            session_id: None,
+        }
+    }
+
+    /// 使用共享的取消信号表（前端可按会话和工具调用 ID 取消单个工具）
+    pub fn with_cancellations(
+        mut self,
+        cancellations: ToolCancellations,
+        session_id: Option<&str>,
+    ) -> Self {
+        self.cancellations = cancellations;
+        self.session_id = session_id.map(str::to_string);
+        self
     }
 
     /// 获取最大迭代次数
@@ -164,8 +265,14 @@ impl ToolLoopEngine {
 
     /// 执行单个工具调用
     ///
+    /// 超过工具定义的超时时间或被取消时中止执行，返回中断结果
     /// Requirements: 7.1 - THE Tool_Loop SHALL execute each tool and collect results
     /// Requirements: 7.4 - IF a tool execution fails, THEN THE Tool_Loop SHALL include the error
+    #[tracing::instrument(
+        name = "agent.tool_call",
+        skip_all,
+        fields(tool = %tool_call.function.name, tool_call_id = %tool_call.id)
+    )]
     pub async fn execute_tool_call(&self, tool_call: &ToolCall) -> ToolCallResult {
         let tool_name = &tool_call.function.name;
         let tool_id = &tool_call.id;
@@ -185,8 +292,55 @@ impl ToolLoopEngine {
             }
         };
 
-        // 执行工具
-        match self.registry.execute(tool_name, args).await {
+        // 按权限模式检查（等待批准的时间不计入工具超时）
+        if let Err(e) = self.registry.authorize(tool_name, &args).await {
+            warn!("[ToolLoopEngine] 工具调用未被允许: {} - {}", tool_name, e);
+            return ToolCallResult::new(
+                tool_id.clone(),
+                tool_name.clone(),
+                ToolsResult::failure(e.to_string()),
+            );
+        }
+
+        // 执行工具（带超时，可取消）
+        let timeout = self
+            .registry
+            .get(tool_name)
+            .map(|tool| tool.definition().timeout())
+            .unwrap_or(Duration::from_secs(DEFAULT_TOOL_TIMEOUT_SECS));
+        let session_id = self.session_id.as_deref();
+        let mut cancel_rx = self.cancellations.register(session_id, tool_id);
+        let outcome = tokio::select! {
+            result = tokio::time::timeout(timeout, self.registry.execute(tool_name, args)) => {
+                result.map_err(|_| ToolInterruption::Timeout)
+            }
+            _ = &mut cancel_rx => Err(ToolInterruption::Cancelled),
+        };
+        self.cancellations.unregister(session_id, tool_id);
+
+        let outcome = match outcome {
+            Ok(outcome) => outcome,
+            Err(reason) => {
+                let message = match reason {
+                    ToolInterruption::Timeout => {
+                        format!("工具执行超时（{} 秒），已终止", timeout.as_secs())
+                    }
+                    ToolInterruption::Cancelled => "用户取消了该工具调用".to_string(),
+                };
+                warn!(
+                    "[ToolLoopEngine] {}: {} (id={})",
+                    message, tool_name, tool_id
+                );
+                return ToolCallResult::interrupted(
+                    tool_id.clone(),
+                    tool_name.clone(),
+                    reason,
+                    message,
+                );
+            }
+        };
+
+        match outcome {
             Ok(result) => {
                 debug!(
                     "[ToolLoopEngine] 工具执行成功: {} success={}",
@@ -199,17 +353,25 @@ impl ToolLoopEngine {
                 let error_msg = match &e {
                     ToolError::NotFound(name) => format!("工具不存在: {}", name),
                     ToolError::InvalidArguments(msg) => format!("参数无效: {}", msg),
+                    ToolError::SchemaValidation(violations) => format!(
+                        "参数不符合 Schema，请修正后重新调用:\n{}",
+                        format_violations(violations)
+                    ),
                     ToolError::ExecutionFailed(msg) => format!("执行失败: {}", msg),
                     ToolError::Security(msg) => format!("安全错误: {}", msg),
                     ToolError::Timeout => "执行超时".to_string(),
                     ToolError::Io(e) => format!("IO 错误: {}", e),
                     ToolError::Json(e) => format!("JSON 错误: {}", e),
                 };
-                ToolCallResult::new(
+                let mut result = ToolCallResult::new(
                     tool_id.clone(),
                     tool_name.clone(),
                     ToolsResult::failure(error_msg),
-                )
+                );
+                if matches!(e, ToolError::Timeout) {
+                    result.interrupted = Some(ToolInterruption::Timeout);
+                }
+                result
             }
         }
     }
@@ -301,6 +463,8 @@ impl ToolLoopEngine {
                     .collect()
             }),
             tool_call_id: None,
+            attachments: None,
+            truncated: None,
         }
     }
 }
@@ -458,6 +622,9 @@ mod tests {
             content: "".to_string(),
             tool_calls: Some(vec![]),
             usage: None,
+            served_by: None,
+            model: None,
+            context_fallback: None,
         };
         assert!(!ToolLoopEngine::has_tool_calls(&result_empty_tools));
     }
@@ -519,6 +686,54 @@ mod tests {
             .contains("参数解析失败"));
     }
 
+    /// 测试用的慢工具（超时 1 秒）
+    struct SlowTool;
+
+    #[async_trait]
+    impl Tool for SlowTool {
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition::new("slow", "A tool that never finishes in time").with_timeout(1)
+        }
+
+        async fn execute(&self, _args: serde_json::Value) -> Result<ToolsResult, ToolError> {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            Ok(ToolsResult::success("done"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_tool_call_timeout_and_cancel() {
+        let registry = ToolRegistry::new();
+        registry.register(SlowTool).unwrap();
+        let cancellations = ToolCancellations::new();
+        let engine = ToolLoopEngine::new(Arc::new(registry))
+            .with_cancellations(cancellations.clone(), Some("s1"));
+
+        let result = engine
+            .execute_tool_call(&create_tool_call("call_slow", "slow", "{}"))
+            .await;
+        assert!(!result.result.success);
+        assert_eq!(result.interrupted, Some(ToolInterruption::Timeout));
+        assert_eq!(
+            result.to_execution_result().interrupted,
+            Some(ToolInterruption::Timeout)
+        );
+
+        let call = create_tool_call("call_cancel", "slow", "{}");
+        let cancel = async {
+            // 其他会话中同 ID 的调用不受影响
+            assert!(!cancellations.cancel(Some("s2"), "call_cancel"));
+            while !cancellations.cancel(Some("s1"), "call_cancel") {
+                tokio::task::yield_now().await;
+            }
+        };
+        let (result, _) = tokio::join!(engine.execute_tool_call(&call), cancel);
+        assert_eq!(result.interrupted, Some(ToolInterruption::Cancelled));
+        assert!(result.to_agent_message().content.as_text().contains("取消"));
+        assert!(cancellations.running().is_empty());
+        assert!(!cancellations.cancel(Some("s1"), "call_cancel"));
+    }
+
     #[tokio::test]
     async fn test_execute_all_tool_calls() {
         let registry = create_test_registry();
@@ -1050,6 +1265,9 @@ mod proptests {
                 content: content.clone(),
                 tool_calls: Some(vec![]),
                 usage: None,
+                served_by: None,
+                model: None,
+                context_fallback: None,
             };
 
             // 验证：should_continue 返回 false
@@ -27,7 +27,13 @@ use tokio::time::timeout;
 use tracing::{debug, info, warn};
 
 /// 默认超时时间（秒）
-const DEFAULT_TIMEOUT_SECS: u64 = 120;
+pub(super) const DEFAULT_TIMEOUT_SECS: u64 = 120;
+
+/// 模型可指定的最大超时时间（秒）
+const MAX_TIMEOUT_SECS: u64 = 600;
+
+/// 工具级超时比命令超时多留出的时间（秒），保证命令超时先触发并返回已捕获的输出
+pub(super) const TOOL_TIMEOUT_GRACE_SECS: u64 = 10;
 
 /// 最大输出大小（字节）
 const MAX_OUTPUT_SIZE: usize = 1024 * 1024; // 1MB
@@ -300,6 +306,8 @@ impl BashTool {
         cmd.stdin(Stdio::null());
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
+        // 工具调用被取消或超时中止时终止子进程
+        cmd.kill_on_drop(true);
 
         // 启动进程
         let mut child = cmd
@@ -410,6 +418,11 @@ impl BashTool {
     pub fn timeout_secs(&self) -> u64 {
         self.timeout_secs
     }
+
+    /// 单条命令允许的最大超时时间
+    fn max_timeout_secs(&self) -> u64 {
+        self.timeout_secs.max(MAX_TIMEOUT_SECS)
+    }
 }
 
 #[async_trait]
@@ -433,12 +446,13 @@ impl Tool for BashTool {
                 .add_property(
                     "timeout",
                     PropertySchema::integer(
-                        "Optional timeout in seconds. Defaults to 120 seconds.",
+                        "Optional timeout in seconds (at most 600). Defaults to 120 seconds.",
                     )
                     .with_default(serde_json::json!(120)),
                     false,
                 ),
         )
+        .with_timeout(self.max_timeout_secs() + TOOL_TIMEOUT_GRACE_SECS)
     }
 
     async fn execute(&self, args: serde_json::Value) -> Result<ToolResult, ToolError> {
@@ -451,7 +465,8 @@ impl Tool for BashTool {
         let timeout_secs = args
             .get("timeout")
             .and_then(|v| v.as_u64())
-            .unwrap_or(self.timeout_secs);
+            .unwrap_or(self.timeout_secs)
+            .min(self.max_timeout_secs());
 
         // 执行命令
         let result = self
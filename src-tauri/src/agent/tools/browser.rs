@@ -0,0 +1,668 @@
+//! 无头浏览器工具模块
+//!
+//! 通过 chromiumoxide 驱动本地 Chrome / Chromium，让模型读取需要执行 JavaScript 的页面：
+//! - `browser_navigate`: 打开网页，返回标题和最终地址
+//! - `browser_extract_text`: 提取整页或指定元素的可见文本
+//! - `browser_click`: 按 CSS 选择器点击元素
+//! - `browser_screenshot`: 截图保存到 `~/.proxycast/screenshots`，可再交给 `ocr` 工具识别
+//!
+//! 浏览器在首次调用时启动，所有会话共用一个浏览器进程，每个会话一个标签页，
+//! 同一会话内的工具调用共享页面状态。只允许访问 http / https 地址；默认通过 CDP 拦截
+//! 页面发出的所有请求（包括跳转和子资源），拒绝访问本机和内网地址。
+//! 截图超过 [`SCREENSHOT_RETENTION`] 或删除会话后清理。
+
+use super::bash::TOOL_TIMEOUT_GRACE_SECS;
+use super::http_request::is_public_ip;
+use super::registry::Tool;
+use super::types::{JsonSchema, PropertySchema, ToolAccess, ToolDefinition, ToolError, ToolResult};
+use crate::config::BrowserToolConfig;
+use async_trait::async_trait;
+use chromiumoxide::cdp::browser_protocol::fetch::{
+    ContinueRequestParams, EnableParams, EventRequestPaused, FailRequestParams,
+};
+use chromiumoxide::cdp::browser_protocol::network::ErrorReason;
+use chromiumoxide::page::ScreenshotParams;
+use chromiumoxide::{Browser, BrowserConfig, Page};
+use futures::StreamExt;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tracing::{debug, info, warn};
+
+/// 提取文本的最大字符数（超出部分截断）
+const MAX_TEXT_CHARS: usize = 50_000;
+
+/// 点击后等待可能发生的页面跳转的时间
+const CLICK_NAVIGATION_WAIT: Duration = Duration::from_secs(5);
+
+/// 未指定会话时使用的标签页
+const DEFAULT_PAGE_KEY: &str = "default";
+
+/// 截图保留时间
+pub const SCREENSHOT_RETENTION: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// 截图保存目录 `~/.proxycast/screenshots`
+pub fn screenshots_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".proxycast").join("screenshots"))
+}
+
+/// 删除过期的截图，指定会话时同时删除该会话的全部截图，返回删除的文件数
+fn prune_screenshots(dir: &Path, session_key: Option<&str>, now: SystemTime) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    let session_prefix = session_key.map(|key| format!("{}-", key));
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !name.ends_with(".png") {
+            continue;
+        }
+        let expired = entry
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .is_some_and(|age| age > SCREENSHOT_RETENTION);
+        let of_session = session_prefix
+            .as_deref()
+            .is_some_and(|prefix| name.starts_with(prefix));
+        if (expired || of_session) && std::fs::remove_file(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+/// 地址是否只指向公网（非 http(s) / ws(s) 的地址如 data:、blob: 不产生网络请求，视为允许）
+async fn url_is_public(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    if !matches!(url.scheme(), "http" | "https" | "ws" | "wss") {
+        return true;
+    }
+    let port = url.port_or_known_default().unwrap_or(80);
+    match url.host() {
+        Some(url::Host::Ipv4(ip)) => is_public_ip(IpAddr::V4(ip)),
+        Some(url::Host::Ipv6(ip)) => is_public_ip(IpAddr::V6(ip)),
+        Some(url::Host::Domain(domain)) => match tokio::net::lookup_host((domain, port)).await {
+            Ok(addrs) => {
+                let addrs: Vec<_> = addrs.collect();
+                !addrs.is_empty() && addrs.iter().all(|addr| is_public_ip(addr.ip()))
+            }
+            Err(_) => false,
+        },
+        None => false,
+    }
+}
+
+/// 拦截页面发出的请求，拒绝访问本机和内网地址
+async fn block_private_network(page: &Page) -> Result<(), String> {
+    let mut paused = page
+        .event_listener::<EventRequestPaused>()
+        .await
+        .map_err(|e| format!("监听页面请求失败: {}", e))?;
+    page.execute(EnableParams::default())
+        .await
+        .map_err(|e| format!("启用请求拦截失败: {}", e))?;
+    let page = page.clone();
+    tokio::spawn(async move {
+        while let Some(event) = paused.next().await {
+            let page = page.clone();
+            tokio::spawn(async move {
+                let result = if url_is_public(&event.request.url).await {
+                    page.execute(ContinueRequestParams::new(event.request_id.clone()))
+                        .await
+                        .map(|_| ())
+                } else {
+                    info!("[BrowserTool] 拒绝访问内网地址: {}", event.request.url);
+                    page.execute(FailRequestParams::new(
+                        event.request_id.clone(),
+                        ErrorReason::BlockedByClient,
+                    ))
+                    .await
+                    .map(|_| ())
+                };
+                if let Err(e) = result {
+                    debug!("[BrowserTool] 处理拦截的请求失败: {}", e);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+/// 校验导航地址，只允许 http / https
+fn check_url(url: &str) -> Result<reqwest::Url, String> {
+    let parsed =
+        reqwest::Url::parse(url.trim()).map_err(|e| format!("无效的 URL {}: {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("只支持 http / https: {}", url));
+    }
+    Ok(parsed)
+}
+
+/// 读取元素 `innerText` 的脚本，元素不存在时返回 null
+fn text_expression(selector: Option<&str>) -> String {
+    match selector {
+        Some(selector) => format!(
+            "(() => {{ const el = document.querySelector({}); return el ? el.innerText : null; }})()",
+            serde_json::Value::from(selector)
+        ),
+        None => "document.body ? document.body.innerText : ''".to_string(),
+    }
+}
+
+fn truncate_text(mut text: String) -> String {
+    if let Some((index, _)) = text.char_indices().nth(MAX_TEXT_CHARS) {
+        text.truncate(index);
+        text.push_str("\n... [文本过长，已截断]");
+    }
+    text
+}
+
+/// 修改后需要重新启动浏览器的配置（超时等只影响单次调用的配置不需要）
+fn requires_relaunch(running: &BrowserToolConfig, config: &BrowserToolConfig) -> bool {
+    running.executable != config.executable
+        || running.headless != config.headless
+        || running.allow_private_network != config.allow_private_network
+}
+
+struct RunningBrowser {
+    browser: Browser,
+    handler: tokio::task::JoinHandle<()>,
+    /// 启动时使用的配置，浏览器相关配置变化后重新启动
+    config: BrowserToolConfig,
+    /// 会话 ID -> 标签页
+    pages: HashMap<String, Page>,
+}
+
+/// 浏览器宿主（克隆后共享同一个浏览器进程）
+#[derive(Clone, Default)]
+pub struct BrowserHost {
+    config: Arc<RwLock<BrowserToolConfig>>,
+    running: Arc<tokio::sync::Mutex<Option<RunningBrowser>>>,
+}
+
+impl BrowserHost {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn config(&self) -> BrowserToolConfig {
+        self.config.read().clone()
+    }
+
+    /// 更新配置（浏览器相关配置变化后，下次调用时重新启动浏览器）
+    pub fn set_config(&self, config: BrowserToolConfig) {
+        *self.config.write() = config;
+    }
+
+    async fn launch(config: &BrowserToolConfig) -> Result<RunningBrowser, String> {
+        let mut builder = BrowserConfig::builder()
+            .request_timeout(Duration::from_secs(config.timeout_secs.max(1)));
+        if let Some(path) = config
+            .executable
+            .as_deref()
+            .filter(|p| !p.trim().is_empty())
+        {
+            builder = builder.chrome_executable(crate::config::expand_tilde(path));
+        }
+        if !config.headless {
+            builder = builder.with_head();
+        }
+        let browser_config = builder.build()?;
+        let (browser, mut handler) = Browser::launch(browser_config)
+            .await
+            .map_err(|e| format!("启动浏览器失败（需要安装 Chrome 或 Chromium）: {}", e))?;
+        // 单条 CDP 消息出错（如无法解析的事件）不影响后续消息，浏览器退出后流才会结束
+        let handler = tokio::spawn(async move {
+            while let Some(event) = handler.next().await {
+                if let Err(e) = event {
+                    debug!("[BrowserHost] CDP 消息处理失败: {}", e);
+                }
+            }
+            info!("[BrowserHost] 浏览器已退出");
+        });
+        info!("[BrowserHost] 浏览器已启动");
+        Ok(RunningBrowser {
+            browser,
+            handler,
+            config: config.clone(),
+            pages: HashMap::new(),
+        })
+    }
+
+    /// 获取会话的标签页，浏览器未启动、已退出或配置变化时（重新）启动
+    async fn page(&self, session_key: &str) -> Result<Page, String> {
+        let config = self.config();
+        let mut running = self.running.lock().await;
+        let stale = running
+            .as_ref()
+            .is_some_and(|r| r.handler.is_finished() || requires_relaunch(&r.config, &config));
+        if stale {
+            if let Some(mut old) = running.take() {
+                let _ = old.browser.close().await;
+                old.handler.abort();
+            }
+        }
+        if running.is_none() {
+            *running = Some(Self::launch(&config).await?);
+        }
+        let running = running.as_mut().ok_or("浏览器未启动")?;
+        if let Some(page) = running.pages.get(session_key) {
+            return Ok(page.clone());
+        }
+        let page = running
+            .browser
+            .new_page("about:blank")
+            .await
+            .map_err(|e| format!("打开标签页失败: {}", e))?;
+        if !running.config.allow_private_network {
+            if let Err(e) = block_private_network(&page).await {
+                let _ = page.close().await;
+                return Err(e);
+            }
+        }
+        running.pages.insert(session_key.to_string(), page.clone());
+        Ok(page)
+    }
+
+    /// 关闭会话的标签页并删除其截图（删除会话时调用）
+    pub async fn close_session(&self, session_id: &str) {
+        let page = match self.running.lock().await.as_mut() {
+            Some(running) => running.pages.remove(session_id),
+            None => None,
+        };
+        if let Some(page) = page {
+            if let Err(e) = page.close().await {
+                warn!("[BrowserHost] 关闭标签页失败: {}", e);
+            }
+        }
+        if let Some(dir) = screenshots_dir() {
+            let session_id = session_id.to_string();
+            let _ = tokio::task::spawn_blocking(move || {
+                prune_screenshots(&dir, Some(&session_id), SystemTime::now())
+            })
+            .await;
+        }
+    }
+
+    /// 创建绑定到会话标签页的全部浏览器工具
+    pub fn tools(&self, session_id: Option<&str>) -> Vec<BrowserTool> {
+        let session_key = session_id.unwrap_or(DEFAULT_PAGE_KEY);
+        [
+            BrowserAction::Navigate,
+            BrowserAction::ExtractText,
+            BrowserAction::Click,
+            BrowserAction::Screenshot,
+        ]
+        .into_iter()
+        .map(|action| BrowserTool {
+            host: self.clone(),
+            session_key: session_key.to_string(),
+            action,
+        })
+        .collect()
+    }
+}
+
+/// 浏览器工具的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrowserAction {
+    Navigate,
+    ExtractText,
+    Click,
+    Screenshot,
+}
+
+/// 浏览器工具（每种操作一个工具，共享会话的标签页）
+pub struct BrowserTool {
+    host: BrowserHost,
+    session_key: String,
+    action: BrowserAction,
+}
+
+impl BrowserTool {
+    async fn navigate(&self, page: &Page, args: &serde_json::Value) -> Result<String, String> {
+        let url = args
+            .get("url")
+            .and_then(|v| v.as_str())
+            .ok_or("缺少 url 参数")?;
+        let url = check_url(url)?;
+        if !self.host.config().allow_private_network && !url_is_public(url.as_str()).await {
+            return Err(format!("不允许访问本机或内网地址: {}", url));
+        }
+        info!("[BrowserTool] 打开 {}", url);
+        page.goto(url.as_str())
+            .await
+            .map_err(|e| format!("打开页面失败: {}", e))?;
+        page_summary(page).await
+    }
+
+    async fn extract_text(&self, page: &Page, args: &serde_json::Value) -> Result<String, String> {
+        let selector = args
+            .get("selector")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.trim().is_empty());
+        let text: Option<String> = page
+            .evaluate(text_expression(selector))
+            .await
+            .map_err(|e| format!("读取页面文本失败: {}", e))?
+            .into_value()
+            .map_err(|e| format!("读取页面文本失败: {}", e))?;
+        match text {
+            Some(text) if !text.trim().is_empty() => Ok(truncate_text(text)),
+            Some(_) => Ok("页面没有可见文本".to_string()),
+            None => Err(format!("没有找到元素: {}", selector.unwrap_or_default())),
+        }
+    }
+
+    async fn click(&self, page: &Page, args: &serde_json::Value) -> Result<String, String> {
+        let selector = args
+            .get("selector")
+            .and_then(|v| v.as_str())
+            .ok_or("缺少 selector 参数")?;
+        page.find_element(selector)
+            .await
+            .map_err(|e| format!("没有找到元素 {}: {}", selector, e))?
+            .click()
+            .await
+            .map_err(|e| format!("点击 {} 失败: {}", selector, e))?;
+        // 点击可能触发跳转，等待片刻后再返回当前页面
+        let _ = tokio::time::timeout(CLICK_NAVIGATION_WAIT, page.wait_for_navigation()).await;
+        page_summary(page).await
+    }
+
+    async fn screenshot(&self, page: &Page, args: &serde_json::Value) -> Result<String, String> {
+        let full_page = args
+            .get("full_page")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let png = page
+            .screenshot(ScreenshotParams::builder().full_page(full_page).build())
+            .await
+            .map_err(|e| format!("截图失败: {}", e))?;
+        let dir = screenshots_dir().ok_or("无法获取用户 home 目录")?;
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| format!("创建截图目录失败: {}", e))?;
+        let path = dir.join(format!(
+            "{}-{}.png",
+            self.session_key,
+            chrono::Utc::now().format("%Y%m%d-%H%M%S%3f")
+        ));
+        tokio::fs::write(&path, &png)
+            .await
+            .map_err(|e| format!("保存截图失败: {}", e))?;
+        tokio::task::spawn_blocking(move || prune_screenshots(&dir, None, SystemTime::now()));
+        Ok(format!(
+            "截图已保存: {}（{} 字节）",
+            path.display(),
+            png.len()
+        ))
+    }
+}
+
+async fn page_summary(page: &Page) -> Result<String, String> {
+    let title = page
+        .get_title()
+        .await
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+    let url = page
+        .url()
+        .await
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+    Ok(format!("标题: {}\n地址: {}", title, url))
+}
+
+#[async_trait]
+impl Tool for BrowserTool {
+    fn definition(&self) -> ToolDefinition {
+        let (name, description, parameters) = match self.action {
+            BrowserAction::Navigate => (
+                "browser_navigate",
+                "Open a web page in a headless browser (JavaScript is executed) and return its \
+                 title and final URL. The page stays open for the other browser_* tools.",
+                JsonSchema::new().add_property(
+                    "url",
+                    PropertySchema::string("Full http(s) URL."),
+                    true,
+                ),
+            ),
+            BrowserAction::ExtractText => (
+                "browser_extract_text",
+                "Return the visible text of the page currently open in the browser, or of the \
+                 first element matching a CSS selector.",
+                JsonSchema::new().add_property(
+                    "selector",
+                    PropertySchema::string("CSS selector (omit for the whole page)."),
+                    false,
+                ),
+            ),
+            BrowserAction::Click => (
+                "browser_click",
+                "Click the first element matching a CSS selector on the current page, wait for \
+                 any resulting navigation, and return the page title and URL.",
+                JsonSchema::new().add_property(
+                    "selector",
+                    PropertySchema::string("CSS selector of the element to click."),
+                    true,
+                ),
+            ),
+            BrowserAction::Screenshot => (
+                "browser_screenshot",
+                "Take a PNG screenshot of the current page and save it locally. Returns the file \
+                 path, which can be passed to the `ocr` tool.",
+                JsonSchema::new().add_property(
+                    "full_page",
+                    PropertySchema::boolean(
+                        "Capture the full scrollable page instead of the viewport.",
+                    ),
+                    false,
+                ),
+            ),
+        };
+        ToolDefinition::new(name, description)
+            .with_parameters(parameters)
+            .with_timeout(self.host.config().timeout_secs + TOOL_TIMEOUT_GRACE_SECS)
+    }
+
+    /// 点击可能提交表单等，其余操作只读取页面
+    fn access(&self) -> ToolAccess {
+        match self.action {
+            BrowserAction::Click => ToolAccess::Mutating,
+            _ => ToolAccess::ReadOnly,
+        }
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> Result<ToolResult, ToolError> {
+        let timeout = Duration::from_secs(self.host.config().timeout_secs.max(1));
+        let task = async {
+            let page = self.host.page(&self.session_key).await?;
+            match self.action {
+                BrowserAction::Navigate => self.navigate(&page, &args).await,
+                BrowserAction::ExtractText => self.extract_text(&page, &args).await,
+                BrowserAction::Click => self.click(&page, &args).await,
+                BrowserAction::Screenshot => self.screenshot(&page, &args).await,
+            }
+        };
+        let output = tokio::time::timeout(timeout, task)
+            .await
+            .map_err(|_| ToolError::Timeout)?
+            .map_err(ToolError::ExecutionFailed)?;
+        Ok(ToolResult::success(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_and_text_helpers() {
+        assert!(check_url("https://example.com/app#/home").is_ok());
+        assert!(check_url("file:///etc/passwd").is_err());
+        assert!(check_url("javascript:alert(1)").is_err());
+
+        assert_eq!(
+            text_expression(Some("a[href=\"x\"]")),
+            "(() => { const el = document.querySelector(\"a[href=\\\"x\\\"]\"); \
+             return el ? el.innerText : null; })()"
+        );
+
+        let long = "字".repeat(MAX_TEXT_CHARS + 1);
+        assert!(truncate_text(long).ends_with("[文本过长，已截断]"));
+    }
+
+    #[test]
+    fn test_tools_share_session_page() {
+        let host = BrowserHost::new();
+        let names: Vec<String> = host
+            .tools(Some("s1"))
+            .iter()
+            .map(|t| t.definition().name)
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "browser_navigate",
+                "browser_extract_text",
+                "browser_click",
+                "browser_screenshot"
+            ]
+        );
+        assert!(host.tools(None).iter().all(|t| t.session_key == "default"));
+    }
+
+    #[test]
+    fn test_relaunch_and_screenshot_pruning() {
+        let config = BrowserToolConfig::default();
+        let slower = BrowserToolConfig {
+            timeout_secs: config.timeout_secs + 10,
+            ..config.clone()
+        };
+        assert!(!requires_relaunch(&config, &slower));
+        let private = BrowserToolConfig {
+            allow_private_network: true,
+            ..config.clone()
+        };
+        assert!(requires_relaunch(&config, &private));
+
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["s1-a.png", "s1-b.png", "s2-a.png", "notes.txt"] {
+            std::fs::write(dir.path().join(name), b"x").unwrap();
+        }
+        assert_eq!(
+            prune_screenshots(dir.path(), Some("s1"), SystemTime::now()),
+            2
+        );
+        assert!(dir.path().join("s2-a.png").exists());
+        let later = SystemTime::now() + SCREENSHOT_RETENTION + Duration::from_secs(60);
+        assert_eq!(prune_screenshots(dir.path(), None, later), 1);
+        assert!(dir.path().join("notes.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_private_urls_are_not_public() {
+        assert!(!url_is_public("http://127.0.0.1:8080/").await);
+        assert!(!url_is_public("http://localhost/").await);
+        assert!(!url_is_public("http://[::1]/").await);
+        assert!(!url_is_public("http://192.168.1.1/admin").await);
+        assert!(url_is_public("http://93.184.216.34/").await);
+        assert!(url_is_public("data:text/html,hi").await);
+    }
+
+    /// 启动本地页面并创建浏览器工具（需要本机安装 Chrome）
+    async fn launch_with_page(allow_private_network: bool) -> (BrowserHost, String) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let app = axum::Router::new()
+            .route(
+                "/",
+                axum::routing::get(|| async {
+                    axum::response::Html(
+                        "<html><head><title>Home</title></head><body>\
+                         <p id=\"intro\">Hello browser</p>\
+                         <a id=\"next\" href=\"/next\">next</a></body></html>",
+                    )
+                }),
+            )
+            .route(
+                "/next",
+                axum::routing::get(|| async {
+                    axum::response::Html(
+                        "<html><head><title>Next</title></head><body>Second page</body></html>",
+                    )
+                }),
+            );
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+
+        let host = BrowserHost::new();
+        host.set_config(BrowserToolConfig {
+            allow_private_network,
+            ..BrowserToolConfig::default()
+        });
+        host.page("test").await.expect("启动浏览器失败");
+        (host, format!("http://127.0.0.1:{}/", port))
+    }
+
+    fn tool(host: &BrowserHost, action: BrowserAction) -> BrowserTool {
+        host.tools(Some("test"))
+            .into_iter()
+            .find(|t| t.action == action)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "需要本机安装 Chrome，使用 cargo test -- --ignored 运行"]
+    async fn test_navigate_extract_and_click() {
+        let (host, url) = launch_with_page(true).await;
+        let result = tool(&host, BrowserAction::Navigate)
+            .execute(serde_json::json!({ "url": url }))
+            .await
+            .unwrap();
+        assert!(result.output.contains("Home"), "{}", result.output);
+
+        let result = tool(&host, BrowserAction::ExtractText)
+            .execute(serde_json::json!({ "selector": "#intro" }))
+            .await
+            .unwrap();
+        assert!(result.output.contains("Hello browser"), "{}", result.output);
+
+        let result = tool(&host, BrowserAction::Click)
+            .execute(serde_json::json!({ "selector": "#next" }))
+            .await
+            .unwrap();
+        assert!(result.output.contains("Next"), "{}", result.output);
+
+        host.close_session("test").await;
+    }
+
+    #[tokio::test]
+    #[ignore = "需要本机安装 Chrome，使用 cargo test -- --ignored 运行"]
+    async fn test_private_network_is_blocked_by_default() {
+        let (host, url) = launch_with_page(false).await;
+        let err = tool(&host, BrowserAction::Navigate)
+            .execute(serde_json::json!({ "url": url }))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("内网"), "{}", err);
+
+        // 绕过导航前的检查，页面内发起的请求也会被拦截
+        let page = host.page("test").await.unwrap();
+        let _ = page.goto(url.as_str()).await;
+        let title = page.get_title().await.ok().flatten().unwrap_or_default();
+        assert_ne!(title, "Home");
+
+        host.close_session("test").await;
+    }
+}
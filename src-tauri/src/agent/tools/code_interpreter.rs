@@ -0,0 +1,591 @@
+//! 代码解释器工具模块
+//!
+//! `run_code(language, code)` 在子进程中运行模型生成的 Python / Node 代码片段：
+//! - 通过 bubblewrap / sandbox-exec 隔离（见 [`super::sandbox`]）；本机没有可用的隔离工具时每次运行都需要用户批准
+//! - 每次运行使用新的临时目录（工作目录为其中的 `work/`，HOME 和 TMPDIR 为临时目录本身），不继承 ProxyCast 的环境变量，
+//!   运行结束或被取消后删除
+//! - 默认禁止网络（隔离时断开网络；另外 Python 屏蔽 socket，Node 屏蔽 net / http / dns 等模块和 fetch）
+//! - 超时后终止整个进程组；Unix 上另用 `ulimit` 限制 CPU 时间和内存，Windows 上用 Job Object 限制，
+//!   Node 用 `--max-old-space-size` 限制堆大小
+//! - 工作目录中生成的文件保存到 `~/.proxycast/artifacts/<会话>/<运行 ID>/`，作为附件附加到 tool 消息
+
+use super::bash::TOOL_TIMEOUT_GRACE_SECS;
+use super::registry::Tool;
+use super::sandbox::{Isolation, ProcessGuard};
+use super::types::{JsonSchema, PropertySchema, ToolAccess, ToolDefinition, ToolError, ToolResult};
+use crate::agent::attachments::guess_media_type;
+use crate::agent::types::AttachmentInfo;
+use crate::config::CodeInterpreterConfig;
+use async_trait::async_trait;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+/// 输出最大字符数（超出部分截断）
+const MAX_OUTPUT_CHARS: usize = 50_000;
+
+/// 单次运行最多保留的生成文件数
+const MAX_ARTIFACTS: usize = 20;
+
+/// 单个生成文件大小上限（20MB），超出的文件不保留
+const MAX_ARTIFACT_SIZE: u64 = 20 * 1024 * 1024;
+
+/// 禁止网络时 Python 通过 `sitecustomize` 预先执行的代码
+const PYTHON_NO_NETWORK: &str = r#"import socket as _socket
+
+
+def _blocked(*args, **kwargs):
+    raise OSError("network access is disabled in this sandbox")
+
+
+_socket.socket = _blocked
+_socket.create_connection = _blocked
+_socket.getaddrinfo = _blocked
+del _socket, _blocked
+"#;
+
+/// 禁止网络时 Node 通过 `--require` 预先执行的代码
+const NODE_NO_NETWORK: &str = r#"const blocked = () => {
+  throw new Error("network access is disabled in this sandbox");
+};
+for (const name of ["net", "tls", "http", "https", "http2", "dgram", "dns"]) {
+  const mod = require(name);
+  for (const key of Object.keys(mod)) {
+    if (typeof mod[key] === "function") mod[key] = blocked;
+  }
+}
+globalThis.fetch = blocked;
+"#;
+
+/// Unix 上设置资源限制后再执行解释器：`$1` 为 CPU 秒数，`$2` 为虚拟内存 KB（0 表示不限制）
+const ULIMIT_SCRIPT: &str =
+    r#"ulimit -t "$1" || exit 125; [ "$2" = 0 ] || ulimit -v "$2" 2>/dev/null; shift 2; exec "$@""#;
+
+/// 生成文件保存目录 `~/.proxycast/artifacts`
+pub fn artifacts_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".proxycast").join("artifacts"))
+}
+
+/// 支持的语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeLanguage {
+    Python,
+    Node,
+}
+
+impl CodeLanguage {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "python" | "py" | "python3" => Some(Self::Python),
+            "node" | "javascript" | "js" | "nodejs" => Some(Self::Node),
+            _ => None,
+        }
+    }
+
+    fn file_name(self) -> &'static str {
+        match self {
+            Self::Python => "main.py",
+            Self::Node => "main.js",
+        }
+    }
+}
+
+fn truncate_output(mut output: String) -> String {
+    if let Some((index, _)) = output.char_indices().nth(MAX_OUTPUT_CHARS) {
+        output.truncate(index);
+        output.push_str("\n... [输出过长，已截断]");
+    }
+    output
+}
+
+/// 收集工作目录中的文件（相对路径），跳过代码文件本身
+fn collect_files(work_dir: &Path, skip: &Path) -> Vec<(String, PathBuf, u64)> {
+    let mut files = Vec::new();
+    let mut pending = vec![work_dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if meta.is_dir() {
+                pending.push(path);
+            } else if meta.is_file() && path != skip {
+                let name = path
+                    .strip_prefix(work_dir)
+                    .unwrap_or(&path)
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                files.push((name, path, meta.len()));
+            }
+        }
+    }
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    files
+}
+
+/// 将生成的文件复制到 `dest`，返回附件元数据和被跳过的文件说明
+fn save_artifacts(
+    work_dir: &Path,
+    code_file: &Path,
+    dest: &Path,
+) -> (Vec<AttachmentInfo>, Vec<String>) {
+    let mut artifacts = Vec::new();
+    let mut skipped = Vec::new();
+    for (name, path, size) in collect_files(work_dir, code_file) {
+        if artifacts.len() >= MAX_ARTIFACTS {
+            skipped.push(format!("{}（超过 {} 个文件）", name, MAX_ARTIFACTS));
+            continue;
+        }
+        if size > MAX_ARTIFACT_SIZE {
+            skipped.push(format!(
+                "{}（超过 {} MB）",
+                name,
+                MAX_ARTIFACT_SIZE / 1024 / 1024
+            ));
+            continue;
+        }
+        let target = dest.join(&name);
+        let saved = target
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::copy(&path, &target));
+        match saved {
+            Ok(_) => artifacts.push(AttachmentInfo {
+                media_type: guess_media_type(&name).to_string(),
+                name,
+                size,
+                tokens: 0,
+                truncated: false,
+                path: Some(target.to_string_lossy().into_owned()),
+            }),
+            Err(e) => skipped.push(format!("{}（保存失败: {}）", name, e)),
+        }
+    }
+    (artifacts, skipped)
+}
+
+/// 运行代码的临时目录，释放时删除（运行被取消或超时后同样会清理）
+struct SandboxDir(PathBuf);
+
+impl Drop for SandboxDir {
+    fn drop(&mut self) {
+        let path = std::mem::take(&mut self.0);
+        let remove = move || {
+            if let Err(e) = std::fs::remove_dir_all(&path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!("[CodeInterpreterTool] 清理临时目录失败: {}", e);
+                }
+            }
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(remove);
+            }
+            Err(_) => remove(),
+        }
+    }
+}
+
+/// 创建临时目录并写入代码文件，返回（规范化的临时目录, 代码文件）
+fn prepare_sandbox(
+    sandbox_dir: &Path,
+    language: CodeLanguage,
+    code: &str,
+    allow_network: bool,
+) -> std::io::Result<(PathBuf, PathBuf)> {
+    std::fs::create_dir_all(sandbox_dir.join("work"))?;
+    // 隔离工具按真实路径授权（如 macOS 的 /var 实际为 /private/var）
+    let sandbox_dir = std::fs::canonicalize(sandbox_dir)?;
+    if !allow_network {
+        std::fs::write(sandbox_dir.join("sitecustomize.py"), PYTHON_NO_NETWORK)?;
+        std::fs::write(sandbox_dir.join("no_network.js"), NODE_NO_NETWORK)?;
+    }
+    let code_file = sandbox_dir.join("work").join(language.file_name());
+    std::fs::write(&code_file, code)?;
+    Ok((sandbox_dir, code_file))
+}
+
+/// `run_code` 工具
+pub struct CodeInterpreterTool {
+    config: CodeInterpreterConfig,
+    artifacts_dir: PathBuf,
+    session_id: Option<String>,
+    isolation: Isolation,
+}
+
+impl CodeInterpreterTool {
+    pub fn new(config: CodeInterpreterConfig, artifacts_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            config,
+            artifacts_dir: artifacts_dir.into(),
+            session_id: None,
+            isolation: Isolation::detect().clone(),
+        }
+    }
+
+    /// 绑定会话，生成的文件保存在该会话的目录下
+    pub fn with_session(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    /// 构造运行命令：解释器参数、环境变量和资源限制
+    fn command(&self, language: CodeLanguage, code_file: &Path, sandbox_dir: &Path) -> Command {
+        let mut argv = Vec::new();
+        let program = match language {
+            CodeLanguage::Python => {
+                argv.push("-B".to_string());
+                self.config.python_path.clone()
+            }
+            CodeLanguage::Node => {
+                argv.push(format!(
+                    "--max-old-space-size={}",
+                    self.config.memory_limit_mb
+                ));
+                if !self.config.allow_network {
+                    argv.push("--require".to_string());
+                    argv.push(
+                        sandbox_dir
+                            .join("no_network.js")
+                            .to_string_lossy()
+                            .into_owned(),
+                    );
+                }
+                self.config.node_path.clone()
+            }
+        };
+        argv.push(code_file.to_string_lossy().into_owned());
+
+        let mut command_line: Vec<OsString> = Vec::new();
+        if cfg!(unix) {
+            // V8 预留大量虚拟内存，Node 只用堆大小限制，不设置 ulimit -v
+            let memory_kb = match language {
+                CodeLanguage::Python => self.config.memory_limit_mb * 1024,
+                CodeLanguage::Node => 0,
+            };
+            command_line.extend([
+                "sh".into(),
+                "-c".into(),
+                ULIMIT_SCRIPT.into(),
+                "sandbox".into(),
+                self.config.timeout_secs.to_string().into(),
+                memory_kb.to_string().into(),
+            ]);
+        }
+        command_line.push(program.into());
+        command_line.extend(argv.into_iter().map(OsString::from));
+
+        let work_dir = code_file.parent().unwrap_or(sandbox_dir);
+        let command_line = self.isolation.wrap(
+            command_line,
+            sandbox_dir,
+            work_dir,
+            self.config.allow_network,
+        );
+        let mut command = Command::new(&command_line[0]);
+        command.args(&command_line[1..]);
+        // 超时或取消时结束整个进程组
+        #[cfg(unix)]
+        command.process_group(0);
+        command.env_clear();
+        for key in ["PATH", "SYSTEMROOT", "WINDIR", "COMSPEC"] {
+            if let Some(value) = std::env::var_os(key) {
+                command.env(key, value);
+            }
+        }
+        command
+            .env("HOME", sandbox_dir)
+            .env("USERPROFILE", sandbox_dir)
+            .env("TMPDIR", sandbox_dir)
+            .env("TEMP", sandbox_dir)
+            .env("TMP", sandbox_dir)
+            .env("LANG", "C.UTF-8")
+            .env("PYTHONIOENCODING", "utf-8")
+            .env("PYTHONNOUSERSITE", "1");
+        if language == CodeLanguage::Python && !self.config.allow_network {
+            command.env("PYTHONPATH", sandbox_dir);
+        }
+        command
+            .current_dir(work_dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        command
+    }
+
+    /// 在临时目录中运行代码，返回（是否成功, 输出, 生成的文件）
+    async fn run(
+        &self,
+        language: CodeLanguage,
+        code: &str,
+        sandbox_dir: &Path,
+    ) -> Result<(bool, String, Vec<AttachmentInfo>), ToolError> {
+        let prepared = {
+            let (sandbox_dir, code) = (sandbox_dir.to_path_buf(), code.to_string());
+            let allow_network = self.config.allow_network;
+            tokio::task::spawn_blocking(move || {
+                prepare_sandbox(&sandbox_dir, language, &code, allow_network)
+            })
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("准备临时目录失败: {}", e)))?
+        };
+        let (sandbox_dir, code_file) =
+            prepared.map_err(|e| ToolError::ExecutionFailed(format!("准备临时目录失败: {}", e)))?;
+        let work_dir = sandbox_dir.join("work");
+
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        let child = self
+            .command(language, &code_file, &sandbox_dir)
+            .spawn()
+            .map_err(|e| ToolError::ExecutionFailed(format!("无法启动解释器: {}", e)))?;
+        let _guard = ProcessGuard::attach(
+            &child,
+            self.config.timeout_secs,
+            self.config.memory_limit_mb,
+        );
+        let output = tokio::time::timeout(timeout, child.wait_with_output())
+            .await
+            .map_err(|_| {
+                ToolError::ExecutionFailed(format!("运行超时（{} 秒）", timeout.as_secs()))
+            })?
+            .map_err(|e| ToolError::ExecutionFailed(format!("运行解释器失败: {}", e)))?;
+
+        let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !stderr.trim().is_empty() {
+            text.push_str("\n[stderr]\n");
+            text.push_str(&stderr);
+        }
+        if !output.status.success() {
+            text.push_str(&format!(
+                "\n[退出码: {}]",
+                output.status.code().unwrap_or(-1)
+            ));
+        }
+        let mut text = truncate_output(text);
+
+        let run_id = uuid::Uuid::new_v4().to_string();
+        let dest = self
+            .artifacts_dir
+            .join(self.session_id.as_deref().unwrap_or("default"))
+            .join(&run_id);
+        let (artifacts, skipped) =
+            tokio::task::spawn_blocking(move || save_artifacts(&work_dir, &code_file, &dest))
+                .await
+                .map_err(|e| ToolError::ExecutionFailed(format!("保存生成的文件失败: {}", e)))?;
+        if !artifacts.is_empty() {
+            text.push_str("\n[生成的文件]\n");
+            for artifact in &artifacts {
+                text.push_str(&format!("- {} ({} 字节)\n", artifact.name, artifact.size));
+            }
+        }
+        if !skipped.is_empty() {
+            text.push_str(&format!("\n[未保存的文件] {}\n", skipped.join(", ")));
+        }
+        Ok((output.status.success(), text, artifacts))
+    }
+}
+
+#[async_trait]
+impl Tool for CodeInterpreterTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "run_code",
+            format!(
+                "Run a Python or Node.js snippet {} and return stdout, stderr and the exit code. \
+                 Each run starts in a fresh empty working directory; files written there are \
+                 returned to the user as attachments. Network access is {}. Runs are limited to \
+                 {} seconds and {} MB of memory. Use print / console.log to show results.",
+                if self.isolation.is_isolated() {
+                    "in an isolated sandbox"
+                } else {
+                    "on the user's machine (each run must be approved by the user)"
+                },
+                if self.config.allow_network {
+                    "allowed"
+                } else {
+                    "disabled"
+                },
+                self.config.timeout_secs,
+                self.config.memory_limit_mb
+            ),
+        )
+        .with_parameters(
+            JsonSchema::new()
+                .add_property(
+                    "language",
+                    PropertySchema::string("Language of the snippet.")
+                        .with_enum(vec!["python".into(), "node".into()]),
+                    true,
+                )
+                .add_property(
+                    "code",
+                    PropertySchema::string("Complete source code to run."),
+                    true,
+                ),
+        )
+        .with_timeout(self.config.timeout_secs + TOOL_TIMEOUT_GRACE_SECS)
+    }
+
+    /// 没有可用的隔离工具时代码直接在本机运行，任何权限模式下都需要用户批准
+    fn access(&self) -> ToolAccess {
+        if self.isolation.is_isolated() {
+            ToolAccess::Mutating
+        } else {
+            ToolAccess::Unconfined
+        }
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> Result<ToolResult, ToolError> {
+        let language = args
+            .get("language")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidArguments("缺少 language 参数".to_string()))?;
+        let language = CodeLanguage::parse(language).ok_or_else(|| {
+            ToolError::InvalidArguments(format!("不支持的语言: {}（python / node）", language))
+        })?;
+        let code = args
+            .get("code")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidArguments("缺少 code 参数".to_string()))?;
+
+        let sandbox_dir = SandboxDir(
+            std::env::temp_dir().join(format!("proxycast-code-{}", uuid::Uuid::new_v4())),
+        );
+        info!(
+            "[CodeInterpreterTool] 运行 {:?} 代码: {} 字节, session={:?}, isolation={:?}",
+            language,
+            code.len(),
+            self.session_id,
+            self.isolation
+        );
+        let (success, output, artifacts) = self.run(language, code, &sandbox_dir.0).await?;
+        Ok(if success {
+            ToolResult::success(output)
+        } else {
+            ToolResult::failure(output)
+        }
+        .with_artifacts(artifacts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_artifacts_skips_code_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let work = dir.path().join("work");
+        std::fs::create_dir_all(work.join("out")).unwrap();
+        std::fs::write(work.join("main.py"), "print(1)\n").unwrap();
+        std::fs::write(work.join("chart.png"), [0u8; 4]).unwrap();
+        std::fs::write(work.join("out").join("data.csv"), "a,b\n1,2\n").unwrap();
+
+        let dest = dir.path().join("artifacts");
+        let (artifacts, skipped) = save_artifacts(&work, &work.join("main.py"), &dest);
+        assert!(skipped.is_empty());
+        let names: Vec<_> = artifacts.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["chart.png", "out/data.csv"]);
+        assert_eq!(artifacts[0].media_type, "image/png");
+        assert_eq!(artifacts[1].size, 8);
+        assert!(dest.join("out").join("data.csv").is_file());
+        assert_eq!(CodeLanguage::parse("JavaScript"), Some(CodeLanguage::Node));
+        assert_eq!(CodeLanguage::parse("ruby"), None);
+    }
+
+    /// 本机 Python 解释器的真实路径（pyenv 等 shim 依赖 HOME，不能在清空环境变量后运行）
+    fn python() -> String {
+        let output = std::process::Command::new(default_python())
+            .args(["-c", "import sys; print(sys.executable)"])
+            .output()
+            .expect("未找到 Python");
+        assert!(output.status.success(), "未找到 Python");
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    }
+
+    fn default_python() -> &'static str {
+        if cfg!(windows) {
+            "python"
+        } else {
+            "python3"
+        }
+    }
+
+    /// 使用本机 Python 创建代码解释器工具（需要本机安装 Python 3）
+    fn python_tool(artifacts: &Path, timeout_secs: u64) -> CodeInterpreterTool {
+        let config = CodeInterpreterConfig {
+            enabled: true,
+            python_path: python(),
+            timeout_secs,
+            ..CodeInterpreterConfig::default()
+        };
+        CodeInterpreterTool::new(config, artifacts).with_session("s1")
+    }
+
+    #[tokio::test]
+    #[ignore = "需要本机安装 Python 3，使用 cargo test -- --ignored 运行"]
+    async fn test_run_python_snippet() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = python_tool(dir.path(), 30);
+        let code = "open('result.txt', 'w').write('42')\nprint(6 * 7)\n";
+        let result = tool
+            .execute(serde_json::json!({ "language": "python", "code": code }))
+            .await
+            .unwrap();
+        assert!(result.success, "{}", result.output);
+        assert!(result.output.starts_with("42"));
+        let artifacts = result.artifacts;
+        assert_eq!(artifacts.len(), 1);
+        assert_eq!(artifacts[0].name, "result.txt");
+        let saved = artifacts[0].path.as_deref().unwrap();
+        assert!(saved.starts_with(dir.path().join("s1").to_string_lossy().as_ref()));
+        assert_eq!(std::fs::read_to_string(saved).unwrap(), "42");
+    }
+
+    #[tokio::test]
+    #[ignore = "需要本机安装 Python 3，使用 cargo test -- --ignored 运行"]
+    async fn test_run_python_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = python_tool(dir.path(), 1);
+        let started = std::time::Instant::now();
+        let error = tool
+            .execute(serde_json::json!({
+                "language": "python",
+                "code": "import time\ntime.sleep(30)\n",
+            }))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("超时"), "{}", error);
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    #[ignore = "需要本机安装 Python 3，使用 cargo test -- --ignored 运行"]
+    async fn test_network_is_blocked() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = python_tool(dir.path(), 30);
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let code = format!(
+            "import socket\nsocket.create_connection(('127.0.0.1', {}), timeout=5)\nprint('connected')\n",
+            port
+        );
+        let result = tool
+            .execute(serde_json::json!({ "language": "python", "code": code }))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        let error = result.error.unwrap_or_default();
+        assert!(!error.contains("connected"), "{}", error);
+        assert!(error.contains("Error"), "{}", error);
+    }
+}
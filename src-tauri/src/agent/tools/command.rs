@@ -0,0 +1,347 @@
+//! 自定义命令工具模块
+//!
+//! 用户在配置中声明的命令工具（`custom_tools`）：
+//! - 模型调用时将参数代入命令模板中的 `{{参数名}}` 占位符
+//! - 参数值按当前 shell 的规则转义为单个参数，不会被解释为 shell 语法
+//! - 通过 [`BashTool`] 执行，返回 stdout/stderr 和退出码
+
+use super::bash::{BashTool, ShellType, DEFAULT_TIMEOUT_SECS, TOOL_TIMEOUT_GRACE_SECS};
+use super::registry::Tool;
+use super::security::SecurityManager;
+use super::types::{JsonSchema, ToolDefinition, ToolError, ToolResult};
+use crate::config::{expand_tilde, CustomToolConfig};
+use async_trait::async_trait;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::info;
+
+/// 内置工具名称，自定义工具不能与之重名
+pub(crate) const RESERVED_NAMES: &[&str] = &[
+    "bash",
+    "read_file",
+    "write_file",
+    "edit_file",
+    "save_memory",
+    "recall_memory",
+    "schedule_followup",
+    "ocr",
+    "git_status",
+    "git_diff",
+    "git_log",
+    "git_apply_patch",
+    "run_code",
+    "sql_query",
+    "sql_schema",
+    "http_request",
+    "browser_navigate",
+    "browser_extract_text",
+    "browser_click",
+    "browser_screenshot",
+    "run_skill_script",
+];
+
+/// 工具名称是否合法（非空，只包含字母、数字、`_` 和 `-`）
+pub(crate) fn is_valid_tool_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// 提取命令模板中的占位符名称
+fn placeholders(template: &str) -> Result<Vec<&str>, String> {
+    let mut names = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| format!("命令模板中的占位符未闭合: {}", template))?;
+        let name = after[..end].trim();
+        if name.is_empty() {
+            return Err(format!("命令模板中包含空占位符: {}", template));
+        }
+        names.push(name);
+        rest = &after[end + 2..];
+    }
+    Ok(names)
+}
+
+/// PowerShell 中与 `'` 等价的单引号字符（包括弯引号）
+const POWERSHELL_SINGLE_QUOTES: &[char] = &['\'', '\u{2018}', '\u{2019}', '\u{201A}', '\u{201B}'];
+
+/// 将参数值转义为单个 shell 参数
+///
+/// - PowerShell：单引号字符串中只有单引号（包括 `‘ ’ ‚ ‛` 弯引号）需要重复一次转义，
+///   `“ ”` 等双引号和 `$` 在单引号内都是普通字符
+/// - cmd：双引号内 `%VAR%` 仍会展开，`%` 改为在引号外用 `^%` 转义
+fn quote_arg(value: &str, shell: ShellType) -> String {
+    match shell {
+        ShellType::Bash | ShellType::Zsh | ShellType::Sh => {
+            format!("'{}'", value.replace('\'', "'\\''"))
+        }
+        ShellType::PowerShell => {
+            let mut quoted = String::with_capacity(value.len() + 2);
+            quoted.push('\'');
+            for c in value.chars() {
+                if POWERSHELL_SINGLE_QUOTES.contains(&c) {
+                    quoted.push(c);
+                }
+                quoted.push(c);
+            }
+            quoted.push('\'');
+            quoted
+        }
+        ShellType::Cmd => format!("\"{}\"", value.replace('"', "\"\"").replace('%', "\"^%\"")),
+    }
+}
+
+/// 参数值转为字符串：字符串原样使用，缺省或 null 为空字符串，其他类型使用 JSON 表示
+fn arg_to_string(value: Option<&serde_json::Value>) -> String {
+    match value {
+        None | Some(serde_json::Value::Null) => String::new(),
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// 将参数代入命令模板
+fn render_command(
+    template: &str,
+    args: &serde_json::Value,
+    shell: ShellType,
+) -> Result<String, String> {
+    let mut command = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| format!("命令模板中的占位符未闭合: {}", template))?;
+        command.push_str(&rest[..start]);
+        let value = arg_to_string(args.get(after[..end].trim()));
+        command.push_str(&quote_arg(&value, shell));
+        rest = &after[end + 2..];
+    }
+    command.push_str(rest);
+    Ok(command)
+}
+
+/// 解析参数 schema，未配置时为无参数的 object
+fn parse_schema(tool: &CustomToolConfig) -> Result<JsonSchema, String> {
+    let schema = match &tool.parameters {
+        Some(value) => serde_json::from_value::<JsonSchema>(value.clone())
+            .map_err(|e| format!("自定义工具 {} 的参数 schema 无效: {}", tool.name, e))?,
+        None => JsonSchema::new(),
+    };
+    if schema.schema_type != "object" {
+        return Err(format!(
+            "自定义工具 {} 的参数 schema 类型必须为 object",
+            tool.name
+        ));
+    }
+    schema
+        .validate()
+        .map_err(|e| format!("自定义工具 {} 的参数 schema 无效: {}", tool.name, e))?;
+    Ok(schema)
+}
+
+/// 校验自定义工具配置：名称合法且唯一、命令非空、schema 有效、占位符都已定义
+pub fn validate_custom_tools(tools: &[CustomToolConfig]) -> Result<(), String> {
+    let mut names = HashSet::new();
+    for tool in tools {
+        if !is_valid_tool_name(&tool.name) {
+            return Err(format!(
+                "自定义工具名称只能包含字母、数字、_ 和 -: {:?}",
+                tool.name
+            ));
+        }
+        if RESERVED_NAMES.contains(&tool.name.as_str()) {
+            return Err(format!("自定义工具名称与内置工具重名: {}", tool.name));
+        }
+        if !names.insert(tool.name.as_str()) {
+            return Err(format!("自定义工具名称重复: {}", tool.name));
+        }
+        if tool.description.trim().is_empty() {
+            return Err(format!("自定义工具 {} 的描述不能为空", tool.name));
+        }
+        if tool.command.trim().is_empty() {
+            return Err(format!("自定义工具 {} 的命令不能为空", tool.name));
+        }
+        let schema = parse_schema(tool)?;
+        for name in
+            placeholders(&tool.command).map_err(|e| format!("自定义工具 {}: {}", tool.name, e))?
+        {
+            if !schema.properties.contains_key(name) {
+                return Err(format!(
+                    "自定义工具 {} 的命令模板引用了未定义的参数: {}",
+                    tool.name, name
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 自定义命令工具
+pub struct CommandTool {
+    config: CustomToolConfig,
+    schema: JsonSchema,
+    bash: BashTool,
+}
+
+impl CommandTool {
+    pub fn new(config: CustomToolConfig, security: Arc<SecurityManager>) -> Result<Self, String> {
+        let schema = parse_schema(&config)?;
+        let mut bash = BashTool::new(security)
+            .with_timeout(config.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+        if let Some(dir) = &config.working_dir {
+            bash = bash.with_working_dir(expand_tilde(dir));
+        }
+        Ok(Self {
+            config,
+            schema,
+            bash,
+        })
+    }
+}
+
+#[async_trait]
+impl Tool for CommandTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(&self.config.name, &self.config.description)
+            .with_parameters(self.schema.clone())
+            .with_timeout(self.bash.timeout_secs() + TOOL_TIMEOUT_GRACE_SECS)
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> Result<ToolResult, ToolError> {
+        let command = render_command(&self.config.command, &args, self.bash.shell_type())
+            .map_err(ToolError::ExecutionFailed)?;
+        info!(
+            "[CommandTool] 执行自定义工具 {}: {}",
+            self.config.name, command
+        );
+
+        let result = self.bash.execute_command(&command, None, None).await?;
+        if result.timed_out {
+            return Err(ToolError::Timeout);
+        }
+        if result.is_success() {
+            Ok(ToolResult::success(result.combined_output()))
+        } else {
+            let exit_code = result.exit_code.unwrap_or(-1);
+            Ok(ToolResult::failure_with_output(
+                format!(
+                    "命令执行失败 (退出码: {})\n\n{}",
+                    exit_code,
+                    result.combined_output()
+                ),
+                format!("命令退出码: {}", exit_code),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool(command: &str, parameters: Option<serde_json::Value>) -> CustomToolConfig {
+        CustomToolConfig {
+            name: "search_notes".to_string(),
+            description: "Search notes".to_string(),
+            parameters,
+            command: command.to_string(),
+            working_dir: None,
+            timeout_secs: None,
+            enabled: true,
+        }
+    }
+
+    fn pattern_schema() -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "pattern": {"type": "string", "description": "Search pattern"},
+                "limit": {"type": "integer", "description": "Max results"}
+            },
+            "required": ["pattern"]
+        })
+    }
+
+    #[test]
+    fn test_render_command_quotes_arguments() {
+        let args = json!({"pattern": "it's $(rm -rf ~)", "limit": 5});
+        assert_eq!(
+            render_command(
+                "grep -m {{limit}} {{ pattern }} notes",
+                &args,
+                ShellType::Bash
+            )
+            .unwrap(),
+            "grep -m '5' 'it'\\''s $(rm -rf ~)' notes"
+        );
+        assert_eq!(
+            render_command("Select-String {{pattern}}", &args, ShellType::PowerShell).unwrap(),
+            "Select-String 'it''s $(rm -rf ~)'"
+        );
+        assert_eq!(
+            quote_arg(
+                "\u{2018}; rm x; \u{2019} \u{201C}$env:PATH\u{201D}",
+                ShellType::PowerShell
+            ),
+            "'\u{2018}\u{2018}; rm x; \u{2019}\u{2019} \u{201C}$env:PATH\u{201D}'"
+        );
+        assert_eq!(
+            quote_arg("50%PATH% \"x\"", ShellType::Cmd),
+            "\"50\"^%\"PATH\"^%\" \"\"x\"\"\""
+        );
+        // 缺省的可选参数替换为空字符串
+        assert_eq!(
+            render_command("echo {{limit}}", &json!({}), ShellType::Sh).unwrap(),
+            "echo ''"
+        );
+        assert!(render_command("echo {{limit", &args, ShellType::Sh).is_err());
+    }
+
+    #[test]
+    fn test_validate_custom_tools() {
+        let valid = tool("rg {{pattern}}", Some(pattern_schema()));
+        assert!(validate_custom_tools(&[valid.clone(), tool("date", None)]).is_err());
+        let mut other = tool("date", None);
+        other.name = "current_date".to_string();
+        assert!(validate_custom_tools(&[valid.clone(), other]).is_ok());
+
+        // 占位符必须在 schema 中定义
+        assert!(validate_custom_tools(&[tool("rg {{query}}", Some(pattern_schema()))]).is_err());
+        let mut reserved = valid.clone();
+        reserved.name = "bash".to_string();
+        assert!(validate_custom_tools(&[reserved]).is_err());
+        let mut invalid_name = valid;
+        invalid_name.name = "search notes".to_string();
+        assert!(validate_custom_tools(&[invalid_name]).is_err());
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_execute_captures_output() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let security = Arc::new(SecurityManager::new(temp_dir.path()));
+        let tool = CommandTool::new(
+            tool(
+                "echo {{pattern}}; echo oops >&2; exit 3",
+                Some(pattern_schema()),
+            ),
+            security,
+        )
+        .unwrap();
+        assert_eq!(tool.definition().name, "search_notes");
+
+        let result = tool.execute(json!({"pattern": "a;b"})).await.unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("a;b"));
+        assert!(result.output.contains("oops"));
+        assert!(result.output.contains("退出码: 3"));
+    }
+}
@@ -0,0 +1,500 @@
+//! Git 工具模块
+//!
+//! 在会话配置的仓库路径（`repo_path`）上提供只读的 `git_status`、`git_diff`、`git_log`，
+//! 以及 `git_apply_patch`：补丁先用 `git apply --check` 校验，再放入待批准队列，
+//! 用户批准后才会应用到工作区（见 [`crate::agent::patch_review`]）。
+
+use super::registry::Tool;
+use super::types::{JsonSchema, PropertySchema, ToolAccess, ToolDefinition, ToolError, ToolResult};
+use crate::agent::patch_review::PatchReviewQueue;
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::info;
+
+/// git 命令超时
+const GIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 输出最大字符数（超出部分截断）
+const MAX_OUTPUT_CHARS: usize = 50_000;
+
+/// `git_log` 默认和最大条数
+const DEFAULT_LOG_COUNT: u64 = 20;
+const MAX_LOG_COUNT: u64 = 200;
+
+/// 运行 git 命令，`stdin` 不为空时写入标准输入
+async fn run_git(repo: &Path, args: &[&str], stdin: Option<&str>) -> Result<String, String> {
+    let mut command = Command::new("git");
+    command
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("无法运行 git: {}", e))?;
+    // 写入标准输入与读取输出同时进行并一起计入超时，避免 git 不读取输入时一直阻塞
+    let pipe = child.stdin.take();
+    let write = async move {
+        if let (Some(input), Some(mut pipe)) = (stdin, pipe) {
+            pipe.write_all(input.as_bytes()).await?;
+        }
+        Ok::<(), std::io::Error>(())
+    };
+    let (written, output) = tokio::time::timeout(GIT_TIMEOUT, async {
+        tokio::join!(write, child.wait_with_output())
+    })
+    .await
+    .map_err(|_| format!("git 命令超时（{} 秒）", GIT_TIMEOUT.as_secs()))?;
+    let output = output.map_err(|e| format!("git 执行失败: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} 失败: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    written.map_err(|e| format!("写入 git 标准输入失败: {}", e))?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn truncate_output(mut output: String) -> String {
+    if let Some((index, _)) = output.char_indices().nth(MAX_OUTPUT_CHARS) {
+        output.truncate(index);
+        output.push_str("\n... [输出过长，已截断]");
+    }
+    output
+}
+
+/// 校验修订或路径参数，拒绝以 `-` 开头的值（避免被当作 git 选项）
+fn check_arg<'a>(name: &str, value: &'a str) -> Result<&'a str, ToolError> {
+    let value = value.trim();
+    if value.starts_with('-') {
+        return Err(ToolError::InvalidArguments(format!(
+            "{} 不能以 - 开头: {}",
+            name, value
+        )));
+    }
+    Ok(value)
+}
+
+fn optional_str<'a>(args: &'a serde_json::Value, name: &str) -> Option<&'a str> {
+    args.get(name)
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.trim().is_empty())
+}
+
+/// 校验仓库路径，返回规范化后的路径
+pub async fn resolve_repo(path: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(path.trim());
+    let path = path
+        .canonicalize()
+        .map_err(|e| format!("仓库路径无效 {}: {}", path.display(), e))?;
+    let top = run_git(&path, &["rev-parse", "--show-toplevel"], None)
+        .await
+        .map_err(|_| format!("不是 git 仓库: {}", path.display()))?;
+    Ok(PathBuf::from(top.trim()))
+}
+
+/// 检查补丁能否干净地应用
+pub async fn check_patch(repo: &Path, patch: &str) -> Result<(), String> {
+    run_git(repo, &["apply", "--check", "-"], Some(patch))
+        .await
+        .map(|_| ())
+}
+
+/// 应用补丁到工作区
+pub async fn apply_patch(repo: &Path, patch: &str) -> Result<(), String> {
+    run_git(repo, &["apply", "-"], Some(patch))
+        .await
+        .map(|_| ())
+}
+
+/// `git_status` 工具
+pub struct GitStatusTool {
+    repo: PathBuf,
+}
+
+impl GitStatusTool {
+    pub fn new(repo: impl Into<PathBuf>) -> Self {
+        Self { repo: repo.into() }
+    }
+}
+
+#[async_trait]
+impl Tool for GitStatusTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "git_status",
+            format!(
+                "Show the branch and working tree status (short format) of the repository \
+                 configured for this session ({}).",
+                self.repo.display()
+            ),
+        )
+        .with_parameters(JsonSchema::new())
+        .with_timeout(GIT_TIMEOUT.as_secs())
+    }
+
+    fn access(&self) -> ToolAccess {
+        ToolAccess::ReadOnly
+    }
+
+    async fn execute(&self, _args: serde_json::Value) -> Result<ToolResult, ToolError> {
+        let output = run_git(&self.repo, &["status", "--short", "--branch"], None)
+            .await
+            .map_err(ToolError::ExecutionFailed)?;
+        Ok(ToolResult::success(truncate_output(output)))
+    }
+}
+
+/// `git_diff` 工具
+pub struct GitDiffTool {
+    repo: PathBuf,
+}
+
+impl GitDiffTool {
+    pub fn new(repo: impl Into<PathBuf>) -> Self {
+        Self { repo: repo.into() }
+    }
+}
+
+#[async_trait]
+impl Tool for GitDiffTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "git_diff",
+            "Show a unified diff of the session repository. By default shows unstaged changes; \
+             set `staged` for staged changes or `revision` (e.g. `HEAD~3`, `main..feature`) to \
+             compare commits.",
+        )
+        .with_parameters(
+            JsonSchema::new()
+                .add_property(
+                    "staged",
+                    PropertySchema::boolean("Show staged changes instead of unstaged ones."),
+                    false,
+                )
+                .add_property(
+                    "revision",
+                    PropertySchema::string("Commit or range to diff against."),
+                    false,
+                )
+                .add_property(
+                    "path",
+                    PropertySchema::string("Limit the diff to this path (relative to the repo)."),
+                    false,
+                ),
+        )
+        .with_timeout(GIT_TIMEOUT.as_secs())
+    }
+
+    fn access(&self) -> ToolAccess {
+        ToolAccess::ReadOnly
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> Result<ToolResult, ToolError> {
+        let mut git_args = vec!["diff"];
+        if args
+            .get("staged")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+        {
+            git_args.push("--cached");
+        }
+        if let Some(revision) = optional_str(&args, "revision") {
+            git_args.push(check_arg("revision", revision)?);
+        }
+        git_args.push("--");
+        if let Some(path) = optional_str(&args, "path") {
+            git_args.push(check_arg("path", path)?);
+        }
+        let output = run_git(&self.repo, &git_args, None)
+            .await
+            .map_err(ToolError::ExecutionFailed)?;
+        if output.trim().is_empty() {
+            return Ok(ToolResult::success("没有差异"));
+        }
+        Ok(ToolResult::success(truncate_output(output)))
+    }
+}
+
+/// `git_log` 工具
+pub struct GitLogTool {
+    repo: PathBuf,
+}
+
+impl GitLogTool {
+    pub fn new(repo: impl Into<PathBuf>) -> Self {
+        Self { repo: repo.into() }
+    }
+}
+
+#[async_trait]
+impl Tool for GitLogTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "git_log",
+            "List recent commits of the session repository (hash, date, author, subject).",
+        )
+        .with_parameters(
+            JsonSchema::new()
+                .add_property(
+                    "max_count",
+                    PropertySchema::integer(format!(
+                        "Number of commits to show (default {}, max {}).",
+                        DEFAULT_LOG_COUNT, MAX_LOG_COUNT
+                    )),
+                    false,
+                )
+                .add_property(
+                    "revision",
+                    PropertySchema::string("Branch, commit or range to list (default HEAD)."),
+                    false,
+                )
+                .add_property(
+                    "path",
+                    PropertySchema::string("Only commits touching this path."),
+                    false,
+                ),
+        )
+        .with_timeout(GIT_TIMEOUT.as_secs())
+    }
+
+    fn access(&self) -> ToolAccess {
+        ToolAccess::ReadOnly
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> Result<ToolResult, ToolError> {
+        let count = args
+            .get("max_count")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_LOG_COUNT)
+            .clamp(1, MAX_LOG_COUNT);
+        let count_arg = format!("--max-count={}", count);
+        let mut git_args = vec![
+            "log",
+            count_arg.as_str(),
+            "--date=short",
+            "--pretty=format:%h %ad %an: %s",
+        ];
+        if let Some(revision) = optional_str(&args, "revision") {
+            git_args.push(check_arg("revision", revision)?);
+        }
+        git_args.push("--");
+        if let Some(path) = optional_str(&args, "path") {
+            git_args.push(check_arg("path", path)?);
+        }
+        let output = run_git(&self.repo, &git_args, None)
+            .await
+            .map_err(ToolError::ExecutionFailed)?;
+        Ok(ToolResult::success(truncate_output(output)))
+    }
+}
+
+/// `git_apply_patch` 工具（补丁需用户批准后才会应用）
+pub struct GitApplyPatchTool {
+    repo: PathBuf,
+    queue: PatchReviewQueue,
+    session_id: String,
+}
+
+impl GitApplyPatchTool {
+    pub fn new(
+        repo: impl Into<PathBuf>,
+        queue: PatchReviewQueue,
+        session_id: impl Into<String>,
+    ) -> Self {
+        Self {
+            repo: repo.into(),
+            queue,
+            session_id: session_id.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for GitApplyPatchTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "git_apply_patch",
+            "Propose a change to the session repository as a unified diff (as produced by \
+             `git diff`, paths relative to the repo root). The patch is checked with \
+             `git apply --check` and then waits for the user to approve it; it is not applied \
+             until they do.",
+        )
+        .with_parameters(
+            JsonSchema::new()
+                .add_property(
+                    "patch",
+                    PropertySchema::string("Unified diff to apply."),
+                    true,
+                )
+                .add_property(
+                    "description",
+                    PropertySchema::string("Short explanation of the change for the reviewer."),
+                    true,
+                ),
+        )
+        .with_timeout(GIT_TIMEOUT.as_secs())
+    }
+
+    fn access(&self) -> ToolAccess {
+        ToolAccess::Reviewed
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> Result<ToolResult, ToolError> {
+        let patch = args
+            .get("patch")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidArguments("缺少 patch 参数".to_string()))?;
+        let description = args
+            .get("description")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+
+        check_patch(&self.repo, patch)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("补丁无法应用: {}", e)))?;
+        let proposal = self
+            .queue
+            .propose(
+                &self.session_id,
+                &self.repo.to_string_lossy(),
+                patch,
+                description,
+            )
+            .map_err(ToolError::ExecutionFailed)?;
+
+        info!(
+            "[GitApplyPatchTool] 创建待批准补丁: id={}, session={}, repo={}",
+            proposal.id,
+            self.session_id,
+            self.repo.display()
+        );
+        Ok(ToolResult::success(format!(
+            "补丁已通过校验并提交审核（{}），涉及文件: {}。需等待用户批准后才会应用",
+            proposal.id,
+            proposal.files.join(", ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_arg_rejects_options() {
+        assert_eq!(check_arg("revision", " HEAD~2 ").unwrap(), "HEAD~2");
+        assert!(check_arg("revision", "--output=/tmp/x").is_err());
+        assert!(check_arg("path", "-p").is_err());
+
+        let long = "a".repeat(MAX_OUTPUT_CHARS + 10);
+        assert!(truncate_output(long).ends_with("[输出过长，已截断]"));
+    }
+
+    /// 创建包含一次提交的临时仓库（需要本机安装 git）
+    async fn init_repo() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("README.md"), "hello\n").unwrap();
+        let steps: [&[&str]; 5] = [
+            &["init", "-q"],
+            &["config", "user.email", "test@example.com"],
+            &["config", "user.name", "test"],
+            &["add", "README.md"],
+            &["commit", "-q", "-m", "init"],
+        ];
+        for args in steps {
+            run_git(dir.path(), args, None)
+                .await
+                .expect("初始化测试仓库失败");
+        }
+        dir
+    }
+
+    const README_PATCH: &str = "diff --git a/README.md b/README.md\n\
+                                --- a/README.md\n\
+                                +++ b/README.md\n\
+                                @@ -1 +1 @@\n\
+                                -hello\n\
+                                +hello world\n";
+
+    #[tokio::test]
+    #[ignore = "需要本机安装 git，使用 cargo test -- --ignored 运行"]
+    async fn test_patch_is_applied_only_after_approval() {
+        let dir = init_repo().await;
+        let repo = resolve_repo(&dir.path().to_string_lossy()).await.unwrap();
+        let queue = PatchReviewQueue::new();
+        let tool = GitApplyPatchTool::new(&repo, queue.clone(), "s1");
+
+        let result = tool
+            .execute(serde_json::json!({ "patch": README_PATCH, "description": "greet" }))
+            .await
+            .unwrap();
+        assert!(result.success);
+        let proposal = queue.list(Some("s1")).pop().unwrap();
+        assert_eq!(proposal.files, vec!["README.md"]);
+        assert_eq!(
+            std::fs::read_to_string(repo.join("README.md")).unwrap(),
+            "hello\n"
+        );
+
+        queue.review(&proposal.id, true).unwrap();
+        apply_patch(&repo, &proposal.patch).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(repo.join("README.md")).unwrap(),
+            "hello world\n"
+        );
+        let status = GitStatusTool::new(&repo)
+            .execute(serde_json::json!({}))
+            .await
+            .unwrap();
+        assert!(status.output.contains(" M README.md"), "{}", status.output);
+        let diff = GitDiffTool::new(&repo)
+            .execute(serde_json::json!({}))
+            .await
+            .unwrap();
+        assert!(diff.output.contains("+hello world"), "{}", diff.output);
+
+        // 已应用的补丁再次校验会失败，不会进入审核队列
+        let error = tool
+            .execute(serde_json::json!({ "patch": README_PATCH, "description": "again" }))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("补丁无法应用"), "{}", error);
+        assert_eq!(queue.list(Some("s1")).len(), 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "需要本机安装 git，使用 cargo test -- --ignored 运行"]
+    async fn test_git_log_and_resolve_repo() {
+        let dir = init_repo().await;
+        let nested = dir.path().join("sub");
+        std::fs::create_dir_all(&nested).unwrap();
+        let repo = resolve_repo(&nested.to_string_lossy()).await.unwrap();
+        assert_eq!(repo, dir.path().canonicalize().unwrap());
+
+        let log = GitLogTool::new(&repo)
+            .execute(serde_json::json!({ "count": 5 }))
+            .await
+            .unwrap();
+        assert!(log.output.contains("test: init"), "{}", log.output);
+
+        let outside = tempfile::tempdir().unwrap();
+        assert!(resolve_repo(&outside.path().to_string_lossy())
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,397 @@
+//! HTTP 请求工具模块
+//!
+//! 让模型调用用户自己的 API，但只能访问当前会话配置的域名白名单（`http_domains`）：
+//! - 白名单项为主机名，`*.example.com` 匹配其所有子域名（不含 example.com 本身）
+//! - 只支持 http / https，重定向目标同样需要在白名单内
+//! - 只连接公网地址：域名解析到回环、内网、链路本地等地址时拒绝（解析结果即实际连接的地址），不使用系统代理
+//! - 不允许设置 `Host` 和逐跳请求头（`Connection`、`Transfer-Encoding` 等）
+//! - 响应体超过 [`MAX_RESPONSE_BYTES`] 时截断
+
+use super::registry::Tool;
+use super::types::{JsonSchema, PropertySchema, ToolDefinition, ToolError, ToolResult};
+use async_trait::async_trait;
+use futures::StreamExt;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::{Client, Method};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+
+/// 请求超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 最多跟随的重定向次数
+const MAX_REDIRECTS: usize = 5;
+
+/// 返回给模型的最大响应体字节数
+pub const MAX_RESPONSE_BYTES: usize = 64 * 1024;
+
+/// 不允许模型设置的请求头（由 HTTP 客户端管理）
+const FORBIDDEN_HEADERS: &[&str] = &[
+    "host",
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "content-length",
+];
+
+/// 是否为公网地址（回环、内网、链路本地、组播等地址均不是）
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                || v4.is_documentation()
+                || a == 0
+                // 100.64.0.0/10 运营商级 NAT
+                || (a == 100 && (64..128).contains(&b))
+                // 198.18.0.0/15 基准测试网段
+                || (a == 198 && (18..20).contains(&b))
+                || a >= 240)
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(v4));
+            }
+            let first = v6.segments()[0];
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                // fc00::/7 唯一本地地址
+                || (first & 0xfe00) == 0xfc00
+                // fe80::/10 链路本地地址
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// 只返回公网地址的 DNS 解析器（防止白名单域名指向本机或内网）
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| is_public_ip(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} 没有解析到公网地址，不允许访问本机或内网", host).into());
+            }
+            Ok::<Addrs, Box<dyn std::error::Error + Send + Sync>>(Box::new(addrs.into_iter()))
+        })
+    }
+}
+
+/// 规范化域名白名单：去除空白、转为小写、去重，拒绝带协议、路径或端口的条目
+pub fn normalize_domains(domains: &[String]) -> Result<Vec<String>, String> {
+    let mut result: Vec<String> = Vec::new();
+    for domain in domains {
+        let domain = domain.trim().trim_end_matches('.').to_ascii_lowercase();
+        if domain.is_empty() {
+            continue;
+        }
+        let host = domain.strip_prefix("*.").unwrap_or(&domain);
+        let valid = !host.is_empty()
+            && host
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+            && !host.starts_with('.')
+            && !host.contains("..");
+        if !valid {
+            return Err(format!(
+                "无效的域名: {}（只填写主机名，如 api.example.com 或 *.example.com）",
+                domain
+            ));
+        }
+        if !result.contains(&domain) {
+            result.push(domain);
+        }
+    }
+    Ok(result)
+}
+
+/// 主机名是否在白名单内
+pub fn domain_allowed(allowlist: &[String], host: &str) -> bool {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    allowlist
+        .iter()
+        .any(|entry| match entry.strip_prefix("*.") {
+            Some(suffix) => host
+                .strip_suffix(suffix)
+                .is_some_and(|prefix| prefix.ends_with('.')),
+            None => host == *entry,
+        })
+}
+
+fn check_url(allowlist: &[String], url: &reqwest::Url) -> Result<(), String> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("只支持 http / https: {}", url));
+    }
+    let host = url.host_str().unwrap_or_default();
+    if !domain_allowed(allowlist, host) {
+        return Err(format!("域名不在当前会话的白名单内: {}", host));
+    }
+    // IP 地址不经过 DNS 解析，在这里检查
+    let ip = match url.host() {
+        Some(url::Host::Ipv4(ip)) => Some(IpAddr::V4(ip)),
+        Some(url::Host::Ipv6(ip)) => Some(IpAddr::V6(ip)),
+        _ => None,
+    };
+    if ip.is_some_and(|ip| !is_public_ip(ip)) {
+        return Err(format!("不允许访问本机或内网地址: {}", host));
+    }
+    Ok(())
+}
+
+fn check_header(name: &str) -> Result<(), String> {
+    if FORBIDDEN_HEADERS.contains(&name.trim().to_ascii_lowercase().as_str()) {
+        return Err(format!("不允许设置请求头: {}", name));
+    }
+    Ok(())
+}
+
+/// HTTP 请求工具（绑定当前会话的域名白名单）
+pub struct HttpRequestTool {
+    allowlist: Arc<Vec<String>>,
+    client: Client,
+}
+
+impl HttpRequestTool {
+    pub fn new(allowlist: Vec<String>) -> Result<Self, String> {
+        let allowlist = Arc::new(allowlist);
+        let redirect_allowlist = allowlist.clone();
+        let client = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .no_proxy()
+            .dns_resolver(Arc::new(PublicResolver))
+            .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+                if attempt.previous().len() >= MAX_REDIRECTS {
+                    attempt.error("重定向次数过多")
+                } else if let Err(e) = check_url(&redirect_allowlist, attempt.url()) {
+                    attempt.error(e)
+                } else {
+                    attempt.follow()
+                }
+            }))
+            .build()
+            .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+        Ok(Self { allowlist, client })
+    }
+}
+
+#[async_trait]
+impl Tool for HttpRequestTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "http_request",
+            format!(
+                "Send an HTTP request and return the status, content type and response body \
+                 (truncated to {} KB). Only these domains are allowed in this session: {}.",
+                MAX_RESPONSE_BYTES / 1024,
+                self.allowlist.join(", ")
+            ),
+        )
+        .with_parameters(
+            JsonSchema::new()
+                .add_property(
+                    "method",
+                    PropertySchema::string("HTTP method (default GET).").with_enum(
+                        ["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD"]
+                            .iter()
+                            .map(|m| serde_json::Value::from(*m))
+                            .collect(),
+                    ),
+                    false,
+                )
+                .add_property("url", PropertySchema::string("Full http(s) URL."), true)
+                .add_property(
+                    "headers",
+                    PropertySchema::object("Request headers as a string-to-string map."),
+                    false,
+                )
+                .add_property("body", PropertySchema::string("Request body."), false),
+        )
+        .with_timeout(REQUEST_TIMEOUT.as_secs() + super::bash::TOOL_TIMEOUT_GRACE_SECS)
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> Result<ToolResult, ToolError> {
+        let url = args
+            .get("url")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidArguments("缺少 url 参数".to_string()))?;
+        let url = reqwest::Url::parse(url)
+            .map_err(|e| ToolError::InvalidArguments(format!("无效的 URL {}: {}", url, e)))?;
+        check_url(&self.allowlist, &url).map_err(ToolError::Security)?;
+        let method = args
+            .get("method")
+            .and_then(|v| v.as_str())
+            .unwrap_or("GET")
+            .to_ascii_uppercase();
+        let method = Method::from_bytes(method.as_bytes())
+            .map_err(|_| ToolError::InvalidArguments(format!("无效的请求方法: {}", method)))?;
+
+        let mut request = self.client.request(method.clone(), url.clone());
+        if let Some(headers) = args.get("headers").and_then(|v| v.as_object()) {
+            for (name, value) in headers {
+                check_header(name).map_err(ToolError::InvalidArguments)?;
+                let value = match value {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                request = request.header(name.as_str(), value);
+            }
+        }
+        if let Some(body) = args.get("body").and_then(|v| v.as_str()) {
+            request = request.body(body.to_string());
+        }
+
+        info!("[HttpRequestTool] {} {}", method, url);
+        let response = request.send().await.map_err(|e| {
+            if e.is_timeout() {
+                ToolError::Timeout
+            } else {
+                ToolError::ExecutionFailed(format!("请求失败: {}", e))
+            }
+        })?;
+        let status = response.status();
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+
+        let mut body = Vec::new();
+        let mut truncated = false;
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk =
+                chunk.map_err(|e| ToolError::ExecutionFailed(format!("读取响应失败: {}", e)))?;
+            let remaining = MAX_RESPONSE_BYTES - body.len();
+            if chunk.len() > remaining {
+                body.extend_from_slice(&chunk[..remaining]);
+                truncated = true;
+                break;
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        let mut output = format!("HTTP {}\nContent-Type: {}\n\n", status, content_type);
+        output.push_str(&String::from_utf8_lossy(&body));
+        if truncated {
+            output.push_str(&format!(
+                "\n... [响应超过 {} KB，已截断]",
+                MAX_RESPONSE_BYTES / 1024
+            ));
+        }
+        Ok(if status.is_success() || status.is_redirection() {
+            ToolResult::success(output)
+        } else {
+            ToolResult::failure(output)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain_allowlist() {
+        let allowlist = normalize_domains(&[
+            " API.example.com ".to_string(),
+            "*.internal.dev".to_string(),
+            "api.example.com".to_string(),
+            String::new(),
+        ])
+        .unwrap();
+        assert_eq!(allowlist, vec!["api.example.com", "*.internal.dev"]);
+
+        assert!(domain_allowed(&allowlist, "api.example.com"));
+        assert!(domain_allowed(&allowlist, "API.EXAMPLE.COM."));
+        assert!(!domain_allowed(&allowlist, "example.com"));
+        assert!(!domain_allowed(&allowlist, "evil-api.example.com"));
+        assert!(domain_allowed(&allowlist, "svc.internal.dev"));
+        assert!(domain_allowed(&allowlist, "a.b.internal.dev"));
+        assert!(!domain_allowed(&allowlist, "internal.dev"));
+        assert!(!domain_allowed(&allowlist, "notinternal.dev"));
+
+        assert!(normalize_domains(&["https://api.example.com".to_string()]).is_err());
+        assert!(normalize_domains(&["api.example.com/v1".to_string()]).is_err());
+
+        let url = reqwest::Url::parse("ftp://api.example.com/file").unwrap();
+        assert!(check_url(&allowlist, &url).is_err());
+    }
+
+    #[test]
+    fn test_private_addresses_and_headers() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+        assert!(is_public_ip("93.184.216.34".parse().unwrap()));
+        assert!(is_public_ip("2606:2800:220:1::".parse().unwrap()));
+
+        let allowlist = normalize_domains(&["127.0.0.1".to_string()]).unwrap();
+        let url = reqwest::Url::parse("http://127.0.0.1:8080/admin").unwrap();
+        assert!(check_url(&allowlist, &url).is_err());
+
+        assert!(check_header("Host").is_err());
+        assert!(check_header("transfer-encoding").is_err());
+        assert!(check_header("Connection").is_err());
+        assert!(check_header("Authorization").is_ok());
+        assert!(check_header("X-Api-Key").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_allowlisted_host_resolving_to_loopback_is_blocked() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let app = axum::Router::new().route("/", axum::routing::get(|| async { "secret" }));
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+
+        let tool = HttpRequestTool::new(vec!["localhost".to_string()]).unwrap();
+        let error = tool
+            .execute(serde_json::json!({ "url": format!("http://localhost:{}/", port) }))
+            .await
+            .unwrap_err();
+        assert!(!error.to_string().contains("secret"));
+        assert!(error.to_string().contains("请求失败"), "{}", error);
+
+        let error = tool
+            .execute(serde_json::json!({
+                "url": format!("http://localhost:{}/", port),
+                "headers": { "Host": "internal.example.com" },
+            }))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ToolError::InvalidArguments(_)));
+    }
+}
@@ -8,27 +8,62 @@
 //! - `registry`: 工具注册表和 Tool trait
 //! - `security`: 安全管理器（路径验证、符号链接检查等）
 //! - `bash`: Bash 命令执行工具
+//! - `browser`: 无头浏览器工具（打开网页、提取文本、点击、截图）
+//! - `code_interpreter`: 代码解释器工具（在隔离子进程中运行 Python / Node 代码片段）
+//! - `sandbox`: 子进程隔离（bubblewrap / sandbox-exec）与资源限制
+//! - `command`: 用户自定义的命令工具（配置中的 `custom_tools`）
+//! - `http_request`: HTTP 请求工具（只能访问会话配置的域名白名单）
 //! - `read_file`: 文件读取工具
 //! - `write_file`: 文件写入工具
 //! - `edit_file`: 文件编辑工具
+//! - `git`: Git 工具（会话关联仓库的 status/diff/log，补丁需用户批准后应用）
+//! - `memory`: 长期记忆工具（save_memory / recall_memory）
+//! - `ocr`: 图片文字识别工具（本地 tesseract）
+//! - `skill_script`: Skill 脚本工具（运行 Skill 目录内的脚本，需用户批准）
+//! - `sql`: SQL 查询工具（配置的连接档案，只读查询与表结构查看）
+//! - `schedule_followup`: 后续任务工具（需用户批准）
+//! - `wasm_plugin`: WASM 插件工具（WASI 沙箱）
 //! - `prompt`: 工具 Prompt 生成器（System Prompt 工具注入）
 
 pub mod bash;
+pub mod browser;
+pub mod code_interpreter;
+pub mod command;
 pub mod edit_file;
+pub mod git;
+pub mod http_request;
+pub mod memory;
+pub mod ocr;
 pub mod prompt;
 pub mod read_file;
 pub mod registry;
+pub mod sandbox;
+pub mod schedule_followup;
 pub mod security;
+pub mod skill_script;
+pub mod sql;
 pub mod types;
+pub mod wasm_plugin;
 pub mod write_file;
 
 pub use bash::{BashExecutionResult, BashTool, ShellType};
+pub use browser::{BrowserHost, BrowserTool};
+pub use code_interpreter::CodeInterpreterTool;
+pub use command::{validate_custom_tools, CommandTool};
 pub use edit_file::{EditFileResult, EditFileTool, UndoResult};
+pub use git::{GitApplyPatchTool, GitDiffTool, GitLogTool, GitStatusTool};
+pub use http_request::HttpRequestTool;
+pub use memory::{RecallMemoryTool, SaveMemoryTool};
+pub use ocr::{append_image_placeholders, replace_history_images, OcrBlock, OcrTool};
 pub use prompt::{generate_tools_prompt, PromptFormat, ToolPromptGenerator};
 pub use read_file::{ReadFileResult, ReadFileTool};
 pub use registry::{Tool, ToolRegistry};
+pub use schedule_followup::ScheduleFollowupTool;
 pub use security::{SecurityError, SecurityManager};
+pub use skill_script::SkillScriptTool;
+pub use sql::{validate_sql_connections, SqlQueryTool, SqlSchemaTool};
 pub use types::*;
+pub use wasm_plugin::{WasmPluginHost, WasmPluginInfo, WasmPluginManifest, WasmTool};
 pub use write_file::{WriteFileResult, WriteFileTool};
 
 use std::path::Path;
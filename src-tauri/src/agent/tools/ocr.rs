@@ -0,0 +1,401 @@
+//! OCR 工具模块
+//!
+//! 用本地 tesseract 识别图片中的文字，让模型读取截图内容而不消耗视觉 token：
+//! - 图片来源为当前会话中附带的图片（按从新到旧的序号）或基础目录内的本地文件
+//! - 调用 `tesseract <图片> stdout tsv`，按行合并单词，返回带边界框和置信度的文本块
+//! - 提供 `ocr` 工具时图片默认不发送给模型，以占位文本代替（见 [`replace_history_images`]）
+
+use super::bash::TOOL_TIMEOUT_GRACE_SECS;
+use super::registry::Tool;
+use super::security::SecurityManager;
+use super::types::{JsonSchema, PropertySchema, ToolAccess, ToolDefinition, ToolError, ToolResult};
+use crate::agent::types::{AgentMessage, AgentSession, ContentPart, MessageContent};
+use crate::config::OcrConfig;
+use async_trait::async_trait;
+use base64::Engine;
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::info;
+
+/// 边界框（像素，左上角为原点）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BoundingBox {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl BoundingBox {
+    fn union(self, other: BoundingBox) -> BoundingBox {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = (self.x + self.width).max(other.x + other.width);
+        let bottom = (self.y + self.height).max(other.y + other.height);
+        BoundingBox {
+            x,
+            y,
+            width: right - x,
+            height: bottom - y,
+        }
+    }
+}
+
+/// 识别出的一行文字
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OcrBlock {
+    pub text: String,
+    pub bbox: BoundingBox,
+    /// 平均置信度（0-100）
+    pub confidence: f32,
+    /// tesseract 的段落块编号，同一编号的行属于同一段
+    pub block: u32,
+}
+
+/// 解析 tesseract 的 TSV 输出，按行合并单词
+pub fn parse_tesseract_tsv(tsv: &str) -> Vec<OcrBlock> {
+    // (block_num, par_num, line_num) -> (单词, 边界框, 置信度)
+    let mut lines: BTreeMap<(u32, u32, u32), (Vec<String>, BoundingBox, Vec<f32>)> =
+        BTreeMap::new();
+    for row in tsv.lines().skip(1) {
+        let cols: Vec<&str> = row.split('\t').collect();
+        if cols.len() < 12 || cols[0] != "5" {
+            continue;
+        }
+        let text = cols[11].trim();
+        if text.is_empty() {
+            continue;
+        }
+        let num = |i: usize| cols[i].parse::<u32>().unwrap_or(0);
+        let bbox = BoundingBox {
+            x: num(6),
+            y: num(7),
+            width: num(8),
+            height: num(9),
+        };
+        let confidence = cols[10].parse::<f32>().unwrap_or(0.0);
+        let entry = lines
+            .entry((num(2), num(3), num(4)))
+            .or_insert_with(|| (Vec::new(), bbox, Vec::new()));
+        entry.0.push(text.to_string());
+        entry.1 = entry.1.union(bbox);
+        entry.2.push(confidence);
+    }
+    lines
+        .into_iter()
+        .map(|((block, _, _), (words, bbox, confidences))| OcrBlock {
+            text: words.join(" "),
+            bbox,
+            confidence: confidences.iter().sum::<f32>() / confidences.len() as f32,
+            block,
+        })
+        .collect()
+}
+
+/// 会话中附带的图片（从新到旧）
+fn session_images(session: &AgentSession) -> Vec<String> {
+    session
+        .messages
+        .iter()
+        .rev()
+        .filter(|m| m.role == "user")
+        .flat_map(|m| match &m.content {
+            MessageContent::Parts(parts) => parts
+                .iter()
+                .rev()
+                .filter_map(|p| match p {
+                    ContentPart::ImageUrl { image_url } => Some(image_url.url.clone()),
+                    _ => None,
+                })
+                .collect(),
+            MessageContent::Text(_) => Vec::new(),
+        })
+        .collect()
+}
+
+/// 未发送给模型的图片的占位文本（序号与 `image_index` 一致）
+fn image_placeholder(index: usize) -> String {
+    format!(
+        "[Image #{} omitted; call the ocr tool with image_index={} to read its text]",
+        index, index
+    )
+}
+
+/// 将历史中用户消息附带的图片替换为占位文本
+///
+/// 图片按从新到旧编号，`newer` 为比这些历史更新的图片数（本轮消息附带的图片）
+pub fn replace_history_images(messages: &[AgentMessage], newer: usize) -> Vec<AgentMessage> {
+    let mut messages = messages.to_vec();
+    let mut index = newer;
+    for message in messages.iter_mut().rev().filter(|m| m.role == "user") {
+        let MessageContent::Parts(parts) = &mut message.content else {
+            continue;
+        };
+        for part in parts.iter_mut().rev() {
+            if matches!(part, ContentPart::ImageUrl { .. }) {
+                index += 1;
+                *part = ContentPart::Text {
+                    text: image_placeholder(index),
+                };
+            }
+        }
+    }
+    messages
+}
+
+/// 为本轮消息附带的 `count` 张图片追加占位文本（最后一张为 #1）
+pub fn append_image_placeholders(text: &str, count: usize) -> String {
+    let mut text = text.to_string();
+    for index in (1..=count).rev() {
+        text.push_str("\n");
+        text.push_str(&image_placeholder(index));
+    }
+    text
+}
+
+/// 临时图片文件，释放时删除（工具被取消或超时后同样会清理）
+struct TempImage(PathBuf);
+
+impl Drop for TempImage {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn decode_data_url(url: &str) -> Result<Vec<u8>, String> {
+    let data = url
+        .split_once(";base64,")
+        .map(|(_, data)| data)
+        .ok_or_else(|| "只支持 base64 data URL 形式的图片".to_string())?;
+    base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .map_err(|e| format!("图片 base64 解码失败: {}", e))
+}
+
+/// OCR 工具（可绑定当前会话以读取附带的图片）
+pub struct OcrTool {
+    config: OcrConfig,
+    security: Arc<SecurityManager>,
+    sessions: Option<(Arc<RwLock<HashMap<String, AgentSession>>>, String)>,
+}
+
+impl OcrTool {
+    pub fn new(config: OcrConfig, security: Arc<SecurityManager>) -> Self {
+        Self {
+            config,
+            security,
+            sessions: None,
+        }
+    }
+
+    /// 绑定会话，`image_index` 从该会话附带的图片中选择
+    pub fn with_session(
+        mut self,
+        sessions: Arc<RwLock<HashMap<String, AgentSession>>>,
+        session_id: impl Into<String>,
+    ) -> Self {
+        self.sessions = Some((sessions, session_id.into()));
+        self
+    }
+
+    fn load_image(&self, args: &serde_json::Value) -> Result<Vec<u8>, ToolError> {
+        if let Some(path) = args.get("path").and_then(|v| v.as_str()) {
+            let path = self
+                .security
+                .validate_path(Path::new(path))
+                .map_err(|e| ToolError::Security(e.to_string()))?;
+            return std::fs::read(&path)
+                .map_err(|e| ToolError::ExecutionFailed(format!("读取图片失败: {}", e)));
+        }
+        let index = args
+            .get("image_index")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(1)
+            .max(1) as usize;
+        let (sessions, session_id) = self.sessions.as_ref().ok_or_else(|| {
+            ToolError::InvalidArguments("当前没有会话，请通过 path 指定图片".to_string())
+        })?;
+        let images = sessions
+            .read()
+            .get(session_id)
+            .map(session_images)
+            .unwrap_or_default();
+        let url = images.get(index - 1).ok_or_else(|| {
+            ToolError::InvalidArguments(format!(
+                "会话中只有 {} 张图片，无法读取第 {} 张",
+                images.len(),
+                index
+            ))
+        })?;
+        decode_data_url(url).map_err(ToolError::ExecutionFailed)
+    }
+
+    async fn run_tesseract(&self, image: Vec<u8>) -> Result<String, ToolError> {
+        let file =
+            TempImage(std::env::temp_dir().join(format!("proxycast-ocr-{}", uuid::Uuid::new_v4())));
+        tokio::fs::write(&file.0, &image)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("写入临时文件失败: {}", e)))?;
+        self.run_tesseract_on(&file.0).await
+    }
+
+    async fn run_tesseract_on(&self, path: &Path) -> Result<String, ToolError> {
+        let mut command = Command::new(&self.config.tesseract_path);
+        command
+            .arg(path)
+            .arg("stdout")
+            .arg("-l")
+            .arg(&self.config.languages)
+            .arg("tsv")
+            .kill_on_drop(true);
+        let output = tokio::time::timeout(
+            Duration::from_secs(self.config.timeout_secs),
+            command.output(),
+        )
+        .await
+        .map_err(|_| ToolError::Timeout)?
+        .map_err(|e| {
+            ToolError::ExecutionFailed(format!(
+                "无法运行 tesseract（{}）: {}",
+                self.config.tesseract_path, e
+            ))
+        })?;
+        if !output.status.success() {
+            return Err(ToolError::ExecutionFailed(format!(
+                "tesseract 执行失败: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+#[async_trait]
+impl Tool for OcrTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "ocr",
+            "Extract text from an image with local OCR instead of viewing it. Use this for \
+             screenshots, scanned documents and other text-heavy images. Returns one block per \
+             text line with its bounding box (pixels) and confidence (0-100).",
+        )
+        .with_parameters(
+            JsonSchema::new()
+                .add_property(
+                    "image_index",
+                    PropertySchema::integer(
+                        "Which image attached in this conversation to read: 1 is the most \
+                         recent, 2 the one before it. Defaults to 1.",
+                    ),
+                    false,
+                )
+                .add_property(
+                    "path",
+                    PropertySchema::string("Local image file path (instead of image_index)."),
+                    false,
+                ),
+        )
+        .with_timeout(self.config.timeout_secs + TOOL_TIMEOUT_GRACE_SECS)
+    }
+
+    fn access(&self) -> ToolAccess {
+        ToolAccess::ReadOnly
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> Result<ToolResult, ToolError> {
+        let image = self.load_image(&args)?;
+        info!("[OcrTool] 识别图片: {} 字节", image.len());
+        let tsv = self.run_tesseract(image).await?;
+        let blocks = parse_tesseract_tsv(&tsv);
+        if blocks.is_empty() {
+            return Ok(ToolResult::success("图片中没有识别到文字"));
+        }
+        let text = blocks
+            .iter()
+            .map(|b| b.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let output = serde_json::json!({ "text": text, "blocks": blocks });
+        Ok(ToolResult::success(
+            serde_json::to_string_pretty(&output).unwrap_or(text),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::test_support::{message, session};
+
+    #[test]
+    fn test_parse_tesseract_tsv() {
+        let tsv = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext\n\
+                   1\t1\t0\t0\t0\t0\t0\t0\t800\t600\t-1\t\n\
+                   4\t1\t1\t1\t1\t0\t10\t20\t200\t30\t-1\t\n\
+                   5\t1\t1\t1\t1\t1\t10\t20\t80\t30\t96.5\tHello\n\
+                   5\t1\t1\t1\t1\t2\t100\t22\t110\t28\t91.5\tworld\n\
+                   5\t1\t1\t1\t2\t1\t10\t60\t50\t20\t80\t \n\
+                   5\t1\t2\t1\t1\t1\t10\t300\t60\t25\t88\tSave\n";
+        let blocks = parse_tesseract_tsv(tsv);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].text, "Hello world");
+        assert_eq!(
+            blocks[0].bbox,
+            BoundingBox {
+                x: 10,
+                y: 20,
+                width: 200,
+                height: 30,
+            }
+        );
+        assert_eq!(blocks[0].confidence, 94.0);
+        assert_eq!(blocks[1].text, "Save");
+        assert_eq!(blocks[1].block, 2);
+    }
+
+    #[test]
+    fn test_replace_history_images() {
+        let image = |url: &str| ContentPart::ImageUrl {
+            image_url: crate::agent::types::ImageUrl {
+                url: url.to_string(),
+                detail: None,
+            },
+        };
+        let user = |parts: Vec<ContentPart>| message("user", MessageContent::Parts(parts));
+        let history = vec![
+            user(vec![
+                ContentPart::Text {
+                    text: "two screenshots".to_string(),
+                },
+                image("data:image/png;base64,AAA"),
+                image("data:image/png;base64,BBB"),
+            ]),
+            user(vec![image("data:image/png;base64,CCC")]),
+        ];
+
+        let replaced = replace_history_images(&history, 1);
+        let texts: Vec<String> = replaced.iter().map(|m| m.content.as_text()).collect();
+        assert!(texts[1].contains("image_index=2"));
+        assert!(texts[0].contains("image_index=4"));
+        assert!(texts[0].contains("image_index=3"));
+        assert!(replaced
+            .iter()
+            .flat_map(|m| match &m.content {
+                MessageContent::Parts(parts) => parts.clone(),
+                MessageContent::Text(_) => Vec::new(),
+            })
+            .all(|p| !matches!(p, ContentPart::ImageUrl { .. })));
+        // 编号与 ocr 工具读取会话图片的顺序一致
+        let session = session("s", history);
+        assert_eq!(session_images(&session)[2], "data:image/png;base64,AAA");
+
+        let text = append_image_placeholders("what does it say?", 2);
+        assert!(text.starts_with("what does it say?"));
+        assert!(text.find("image_index=2").unwrap() < text.find("image_index=1").unwrap());
+    }
+}
@@ -513,6 +513,7 @@ mod proptests {
                 name,
                 description,
                 parameters,
+                timeout_secs: None,
             })
     }
 
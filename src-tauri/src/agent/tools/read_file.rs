@@ -12,7 +12,7 @@
 
 use super::registry::Tool;
 use super::security::SecurityManager;
-use super::types::{JsonSchema, PropertySchema, ToolDefinition, ToolError, ToolResult};
+use super::types::{JsonSchema, PropertySchema, ToolAccess, ToolDefinition, ToolError, ToolResult};
 use async_trait::async_trait;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -383,6 +383,10 @@ impl Tool for ReadFileTool {
         )
     }
 
+    fn access(&self) -> ToolAccess {
+        ToolAccess::ReadOnly
+    }
+
     async fn execute(&self, args: serde_json::Value) -> Result<ToolResult, ToolError> {
         // 解析参数
         let path_str = args
@@ -3,10 +3,12 @@
 //! 提供工具的注册、查找和验证功能
 //! 符合 Requirements 2.2, 2.4
 
-use super::types::{ToolDefinition, ToolError, ToolResult, ToolValidationError};
+use super::types::{ToolAccess, ToolDefinition, ToolError, ToolResult, ToolValidationError};
+use crate::agent::tool_approval::ToolPermission;
 use async_trait::async_trait;
 use parking_lot::RwLock;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tracing::{debug, info, warn};
 
@@ -29,6 +31,11 @@ pub trait Tool: Send + Sync {
     /// * `Err(ToolError)` - 执行错误
     async fn execute(&self, args: serde_json::Value) -> Result<ToolResult, ToolError>;
 
+    /// 工具对外部状态的影响（默认视为会修改状态）
+    fn access(&self) -> ToolAccess {
+        ToolAccess::Mutating
+    }
+
     /// 获取工具名称（便捷方法）
     fn name(&self) -> String {
         self.definition().name
@@ -63,6 +70,10 @@ pub trait Tool: Send + Sync {
 /// Requirements: 2.4 - WHEN a new tool is added, THE Tool_Registry SHALL make it available to the Agent without restart
 pub struct ToolRegistry {
     tools: RwLock<HashMap<String, Arc<dyn Tool>>>,
+    /// 严格模式：生成 `strict: true` 的函数定义，并拒绝未定义的参数
+    strict: AtomicBool,
+    /// 权限模式
+    permission: RwLock<ToolPermission>,
 }
 
 impl Default for ToolRegistry {
@@ -76,9 +87,37 @@ impl ToolRegistry {
     pub fn new() -> Self {
         Self {
             tools: RwLock::new(HashMap::new()),
+            strict: AtomicBool::new(false),
+            permission: RwLock::new(ToolPermission::default()),
         }
     }
 
+    /// 设置严格模式（OpenAI strict function calling）
+    pub fn set_strict(&self, strict: bool) {
+        self.strict.store(strict, Ordering::Relaxed);
+    }
+
+    /// 是否启用严格模式
+    pub fn is_strict(&self) -> bool {
+        self.strict.load(Ordering::Relaxed)
+    }
+
+    /// 设置权限模式
+    pub fn set_permission(&self, permission: ToolPermission) {
+        *self.permission.write() = permission;
+    }
+
+    /// 按权限模式检查工具调用能否执行，`approve` 模式下等待用户批准
+    ///
+    /// 工具不存在时不做检查，由 [`Self::execute`] 返回错误
+    pub async fn authorize(&self, name: &str, args: &serde_json::Value) -> Result<(), ToolError> {
+        let Some(tool) = self.get(name) else {
+            return Ok(());
+        };
+        let permission = self.permission.read().clone();
+        permission.check(name, tool.access(), args).await
+    }
+
     /// 注册工具
     ///
     /// Requirements: 2.2 - WHEN tools are registered, THE Tool_Registry SHALL validate the tool definitions
@@ -162,10 +201,11 @@ impl ToolRegistry {
 
     /// 获取所有工具定义（OpenAI API 格式）
     pub fn list_definitions_api(&self) -> Vec<crate::models::openai::Tool> {
+        let strict = self.is_strict();
         self.tools
             .read()
             .values()
-            .map(|t| t.definition().to_api_format())
+            .map(|t| t.definition().to_api_format_with(strict))
             .collect()
     }
 
@@ -198,8 +238,14 @@ impl ToolRegistry {
 
         debug!("[ToolRegistry] 执行工具: {} args={:?}", name, args);
 
-        // 验证参数
+        // 验证参数（严格模式下按完整 schema 校验，拒绝未定义的参数）
         tool.validate_args(&args)?;
+        if self.is_strict() {
+            tool.definition()
+                .parameters
+                .validate_args(&args, true)
+                .map_err(ToolError::SchemaValidation)?;
+        }
 
         // 执行工具
         let result = tool.execute(args).await?;
@@ -386,6 +432,29 @@ mod tests {
         assert!(matches!(result, Err(ToolError::InvalidArguments(_))));
     }
 
+    #[tokio::test]
+    async fn test_registry_strict_mode_rejects_unknown_args() {
+        let registry = ToolRegistry::new();
+        registry.register(EchoTool).unwrap();
+        let args = serde_json::json!({"message": "hi", "extra": true});
+
+        assert!(registry.execute("echo", args.clone()).await.is_ok());
+
+        registry.set_strict(true);
+        let result = registry.execute("echo", args).await;
+        let Err(ToolError::SchemaValidation(violations)) = result else {
+            panic!("严格模式应拒绝未定义的参数");
+        };
+        assert_eq!(violations[0].path, "extra");
+
+        let crate::models::openai::Tool::Function { function } =
+            &registry.list_definitions_api()[0]
+        else {
+            panic!("应为 function 工具");
+        };
+        assert_eq!(function.strict, Some(true));
+    }
+
     #[test]
     fn test_registry_clear() {
         let registry = ToolRegistry::new();
@@ -0,0 +1,327 @@
+//! 子进程隔离模块
+//!
+//! 为运行模型生成代码的工具（`run_code`）提供进程隔离和资源限制：
+//! - Linux：使用 bubblewrap（`bwrap`），系统目录只读、隐藏 home 目录和 `/tmp`，只有临时目录可写，可断开网络
+//! - macOS：使用 `sandbox-exec`，只允许写入临时目录，禁止读取 home 目录，可禁止网络
+//! - 未安装隔离工具或其他平台上不隔离，调用方需按 [`ToolAccess::Unconfined`] 请求用户批准
+//! - Unix 上子进程在独立的进程组中运行，[`ProcessGuard`] 释放时结束整个进程组；
+//!   Windows 上通过 Job Object 限制 CPU 时间和内存，释放时终止 Job 中的所有进程
+//!
+//! [`ToolAccess::Unconfined`]: super::ToolAccess::Unconfined
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+/// 子进程隔离方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Isolation {
+    /// bubblewrap（Linux）
+    Bubblewrap(PathBuf),
+    /// sandbox-exec（macOS）
+    SandboxExec(PathBuf),
+    /// 不隔离
+    None,
+}
+
+/// 在 PATH 中查找可执行文件
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    std::env::var_os("PATH").and_then(|paths| {
+        std::env::split_paths(&paths)
+            .map(|dir| dir.join(name))
+            .find(|path| path.is_file())
+    })
+}
+
+/// 转义 sandbox-exec 配置中的字符串
+fn sbpl_string(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    format!("\"{}\"", path.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl Isolation {
+    /// 检测本机可用的隔离方式（结果缓存）
+    ///
+    /// bwrap 在禁止非特权用户命名空间的环境（如部分容器）中无法运行，检测时先试运行一次
+    pub fn detect() -> &'static Isolation {
+        static DETECTED: OnceLock<Isolation> = OnceLock::new();
+        DETECTED.get_or_init(|| {
+            let isolation = if cfg!(target_os = "linux") {
+                find_in_path("bwrap")
+                    .filter(|bwrap| {
+                        std::process::Command::new(bwrap)
+                            .args(["--ro-bind", "/", "/", "--unshare-all", "true"])
+                            .stdin(std::process::Stdio::null())
+                            .stdout(std::process::Stdio::null())
+                            .stderr(std::process::Stdio::null())
+                            .status()
+                            .is_ok_and(|status| status.success())
+                    })
+                    .map_or(Isolation::None, Isolation::Bubblewrap)
+            } else if cfg!(target_os = "macos") {
+                let path = PathBuf::from("/usr/bin/sandbox-exec");
+                if path.is_file() {
+                    Isolation::SandboxExec(path)
+                } else {
+                    Isolation::None
+                }
+            } else {
+                Isolation::None
+            };
+            match &isolation {
+                Isolation::None => warn!("[Sandbox] 未找到可用的隔离工具，代码将直接在本机运行"),
+                isolation => info!("[Sandbox] 使用隔离方式: {:?}", isolation),
+            }
+            isolation
+        })
+    }
+
+    pub fn is_isolated(&self) -> bool {
+        !matches!(self, Isolation::None)
+    }
+
+    /// 用隔离工具包装命令行，`sandbox_dir` 为唯一可写的目录（需为规范路径）
+    pub fn wrap(
+        &self,
+        argv: Vec<OsString>,
+        sandbox_dir: &Path,
+        work_dir: &Path,
+        allow_network: bool,
+    ) -> Vec<OsString> {
+        match self {
+            Isolation::Bubblewrap(bwrap) => {
+                let mut wrapped: Vec<OsString> = vec![bwrap.into()];
+                wrapped.extend(
+                    [
+                        "--ro-bind",
+                        "/",
+                        "/",
+                        "--dev",
+                        "/dev",
+                        "--proc",
+                        "/proc",
+                        "--tmpfs",
+                        "/tmp",
+                    ]
+                    .map(OsString::from),
+                );
+                if let Some(home) = dirs::home_dir().filter(|home| home.parent().is_some()) {
+                    wrapped.extend(["--tmpfs".into(), home.into()]);
+                }
+                wrapped.extend([
+                    "--bind".into(),
+                    sandbox_dir.into(),
+                    sandbox_dir.into(),
+                    "--unshare-all".into(),
+                ]);
+                if allow_network {
+                    wrapped.push("--share-net".into());
+                }
+                wrapped.extend([
+                    "--die-with-parent".into(),
+                    "--new-session".into(),
+                    "--chdir".into(),
+                    work_dir.into(),
+                    "--".into(),
+                ]);
+                wrapped.extend(argv);
+                wrapped
+            }
+            Isolation::SandboxExec(sandbox_exec) => {
+                let mut profile = format!(
+                    "(version 1)\n(allow default)\n(deny file-write*)\n\
+                     (allow file-write* (subpath {}) (literal \"/dev/null\"))\n",
+                    sbpl_string(sandbox_dir)
+                );
+                if let Some(home) = dirs::home_dir() {
+                    profile.push_str(&format!(
+                        "(deny file-read* (subpath {}))\n",
+                        sbpl_string(&home)
+                    ));
+                }
+                if !allow_network {
+                    profile.push_str("(deny network*)\n");
+                }
+                let mut wrapped: Vec<OsString> =
+                    vec![sandbox_exec.into(), "-p".into(), profile.into()];
+                wrapped.extend(argv);
+                wrapped
+            }
+            Isolation::None => argv,
+        }
+    }
+}
+
+/// 子进程资源限制和清理
+///
+/// Unix 上子进程需以 `process_group(0)` 启动；释放时结束整个进程组（超时、取消或正常结束后残留的后台进程）
+pub struct ProcessGuard {
+    #[cfg(unix)]
+    pgid: Option<i32>,
+    #[cfg(windows)]
+    job: Option<windows::Win32::Foundation::HANDLE>,
+}
+
+impl ProcessGuard {
+    /// 接管已启动的子进程；Windows 上同时设置 CPU 时间（秒）和内存（MB）上限
+    #[allow(unused_variables)]
+    pub fn attach(child: &tokio::process::Child, cpu_secs: u64, memory_mb: u64) -> Self {
+        #[cfg(unix)]
+        {
+            Self {
+                pgid: child.id().and_then(|pid| i32::try_from(pid).ok()),
+            }
+        }
+        #[cfg(windows)]
+        {
+            let job = child.raw_handle().and_then(|process| {
+                match windows_job::create(process, cpu_secs, memory_mb) {
+                    Ok(job) => Some(job),
+                    Err(e) => {
+                        warn!("[Sandbox] 设置 Job Object 资源限制失败: {}", e);
+                        None
+                    }
+                }
+            });
+            Self { job }
+        }
+        #[cfg(not(any(unix, windows)))]
+        {
+            Self {}
+        }
+    }
+}
+
+impl Drop for ProcessGuard {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(pgid) = self.pgid.take() {
+            // SAFETY: 向子进程所在的进程组发送 SIGKILL，进程组不存在时调用返回错误
+            unsafe {
+                libc::kill(-pgid, libc::SIGKILL);
+            }
+        }
+        #[cfg(windows)]
+        if let Some(job) = self.job.take() {
+            // 设置了 KILL_ON_JOB_CLOSE，关闭句柄即终止 Job 中的所有进程
+            // SAFETY: 句柄由 CreateJobObjectW 创建且只关闭一次
+            unsafe {
+                let _ = windows::Win32::Foundation::CloseHandle(job);
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows_job {
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+        SetInformationJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_JOB_MEMORY,
+        JOB_OBJECT_LIMIT_JOB_TIME, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+
+    /// 创建限制 CPU 时间和内存的 Job Object 并将进程加入其中
+    pub fn create(
+        process: std::os::windows::io::RawHandle,
+        cpu_secs: u64,
+        memory_mb: u64,
+    ) -> windows::core::Result<HANDLE> {
+        // SAFETY: 传入的结构体在调用期间有效，失败时关闭已创建的句柄
+        unsafe {
+            let job = CreateJobObjectW(None, PCWSTR::null())?;
+            let mut info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+            info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_JOB_TIME
+                | JOB_OBJECT_LIMIT_JOB_MEMORY
+                | JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+            // 单位为 100 纳秒
+            info.BasicLimitInformation.PerJobUserTimeLimit =
+                i64::try_from(cpu_secs.saturating_mul(10_000_000)).unwrap_or(i64::MAX);
+            info.JobMemoryLimit =
+                usize::try_from(memory_mb.saturating_mul(1024 * 1024)).unwrap_or(usize::MAX);
+            let result = SetInformationJobObject(
+                job,
+                JobObjectExtendedLimitInformation,
+                &info as *const _ as *const core::ffi::c_void,
+                std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            )
+            .and_then(|_| AssignProcessToJobObject(job, HANDLE(process as isize)));
+            match result {
+                Ok(()) => Ok(job),
+                Err(e) => {
+                    let _ = CloseHandle(job);
+                    Err(e)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_commands() {
+        let argv = vec![OsString::from("python3"), OsString::from("main.py")];
+        let dir = Path::new("/tmp/proxycast-code-1");
+        let work = dir.join("work");
+        assert_eq!(Isolation::None.wrap(argv.clone(), dir, &work, false), argv);
+
+        let bwrap = Isolation::Bubblewrap(PathBuf::from("/usr/bin/bwrap"));
+        let wrapped = bwrap.wrap(argv.clone(), dir, &work, false);
+        let wrapped: Vec<_> = wrapped.iter().map(|a| a.to_string_lossy()).collect();
+        assert_eq!(wrapped[0], "/usr/bin/bwrap");
+        assert!(wrapped.contains(&"--unshare-all".into()));
+        assert!(!wrapped.contains(&"--share-net".into()));
+        assert_eq!(wrapped[wrapped.len() - 3], "--");
+        assert_eq!(wrapped[wrapped.len() - 2], "python3");
+        let shared = bwrap.wrap(argv.clone(), dir, &work, true);
+        assert!(shared.contains(&OsString::from("--share-net")));
+
+        let sandbox_exec = Isolation::SandboxExec(PathBuf::from("/usr/bin/sandbox-exec"));
+        let wrapped = sandbox_exec.wrap(argv, Path::new("/tmp/a\"b"), &work, false);
+        let profile = wrapped[2].to_string_lossy();
+        assert!(profile.contains("(allow file-write* (subpath \"/tmp/a\\\"b\")"));
+        assert!(profile.contains("(deny network*)"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_guard_kills_process_group() {
+        use tokio::io::AsyncBufReadExt;
+
+        let mut command = tokio::process::Command::new("sh");
+        command
+            .args(["-c", "sleep 30 & echo $!; wait"])
+            .stdout(std::process::Stdio::piped())
+            .process_group(0)
+            .kill_on_drop(true);
+        let mut child = command.spawn().unwrap();
+        let guard = ProcessGuard::attach(&child, 30, 64);
+        let mut lines = tokio::io::BufReader::new(child.stdout.take().unwrap()).lines();
+        let background: i32 = lines.next_line().await.unwrap().unwrap().parse().unwrap();
+
+        drop(guard);
+        let _ = child.wait().await;
+        // 进程可能在被回收前短暂处于僵尸状态
+        let running = |pid: i32| {
+            // SAFETY: 信号 0 只检查进程是否存在
+            let exists = unsafe { libc::kill(pid, 0) } == 0;
+            exists
+                && std::fs::read_to_string(format!("/proc/{}/stat", pid))
+                    .map_or(true, |stat| !stat.contains(") Z "))
+        };
+        let mut alive = true;
+        for _ in 0..50 {
+            alive = running(background);
+            if !alive {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert!(!alive, "后台进程应随进程组一起结束");
+    }
+}
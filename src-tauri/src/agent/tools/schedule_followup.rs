@@ -0,0 +1,115 @@
+//! 后续任务工具模块
+//!
+//! 让模型为当前会话计划一次性的后续任务，任务需用户批准后才会执行
+
+use super::registry::Tool;
+use super::types::{JsonSchema, PropertySchema, ToolAccess, ToolDefinition, ToolError, ToolResult};
+use crate::agent::followup::{FollowupScheduler, MAX_FOLLOWUP_DELAY_MINUTES};
+use async_trait::async_trait;
+use tracing::info;
+
+/// 后续任务工具（绑定到当前会话）
+pub struct ScheduleFollowupTool {
+    scheduler: FollowupScheduler,
+    session_id: String,
+}
+
+impl ScheduleFollowupTool {
+    pub fn new(scheduler: FollowupScheduler, session_id: impl Into<String>) -> Self {
+        Self {
+            scheduler,
+            session_id: session_id.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ScheduleFollowupTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "schedule_followup",
+            "Schedule a one-shot follow-up in this conversation, e.g. \"check the build again in \
+             20 minutes and report back\". When it is due, the task is sent to you as a new \
+             message in this session and you can use tools to complete it. The user must approve \
+             the follow-up before it runs.",
+        )
+        .with_parameters(
+            JsonSchema::new()
+                .add_property(
+                    "task",
+                    PropertySchema::string(
+                        "What to do when the follow-up runs, written as an instruction to yourself.",
+                    ),
+                    true,
+                )
+                .add_property(
+                    "delay_minutes",
+                    PropertySchema::integer(format!(
+                        "Minutes from now until the follow-up runs (1-{}).",
+                        MAX_FOLLOWUP_DELAY_MINUTES
+                    )),
+                    true,
+                ),
+        )
+    }
+
+    fn access(&self) -> ToolAccess {
+        ToolAccess::Reviewed
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> Result<ToolResult, ToolError> {
+        let task = args
+            .get("task")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidArguments("缺少 task 参数".to_string()))?;
+        let delay_minutes = args
+            .get("delay_minutes")
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| ToolError::InvalidArguments("缺少 delay_minutes 参数".to_string()))?;
+        let delay_minutes = u32::try_from(delay_minutes)
+            .map_err(|_| ToolError::InvalidArguments("delay_minutes 超出范围".to_string()))?;
+
+        let followup = self
+            .scheduler
+            .schedule(&self.session_id, task, delay_minutes)
+            .map_err(ToolError::ExecutionFailed)?;
+
+        info!(
+            "[ScheduleFollowupTool] 创建后续任务: id={}, session={}, due_at={}",
+            followup.id, self.session_id, followup.due_at
+        );
+
+        Ok(ToolResult::success(format!(
+            "已创建后续任务 {}，计划于 {} 执行，需等待用户批准后才会运行",
+            followup.id,
+            followup.due_at.to_rfc3339()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::followup::FollowupStatus;
+
+    #[tokio::test]
+    async fn test_schedule_followup_tool() {
+        let scheduler = FollowupScheduler::new();
+        let tool = ScheduleFollowupTool::new(scheduler.clone(), "s1");
+
+        let result = tool
+            .execute(serde_json::json!({"task": "check the build", "delay_minutes": 20}))
+            .await
+            .unwrap();
+        assert!(result.success);
+
+        let tasks = scheduler.list(Some("s1"));
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].status, FollowupStatus::PendingApproval);
+
+        let err = tool
+            .execute(serde_json::json!({"task": "x", "delay_minutes": 0}))
+            .await;
+        assert!(err.is_err());
+    }
+}
@@ -0,0 +1,329 @@
+//! Skill 脚本工具模块
+//!
+//! 支持包含脚本的 Skill 目录（`~/.proxycast/skills/<skill>/scripts/...`）：
+//! - `run_skill_script(skill, script, args)` 运行 Skill 目录内的脚本，路径解析后必须仍在该目录内
+//! - 运行前需用户批准（见 [`crate::agent::script_review`]），拒绝或超时则不运行
+//! - 按扩展名选择解释器，参数直接作为命令行参数传递（不经过 shell），工作目录为 Skill 目录
+
+use super::bash::TOOL_TIMEOUT_GRACE_SECS;
+use super::registry::Tool;
+use super::types::{JsonSchema, PropertySchema, ToolAccess, ToolDefinition, ToolError, ToolResult};
+use crate::agent::script_review::{ScriptReviewQueue, ScriptRunRequest};
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::info;
+
+/// 等待用户批准的时间
+const APPROVAL_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// 脚本运行超时
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 输出最大字符数（超出部分截断）
+const MAX_OUTPUT_CHARS: usize = 50_000;
+
+/// 工具描述中列出的脚本数量上限
+const MAX_LISTED_SCRIPTS: usize = 50;
+
+/// Skill 安装目录 `~/.proxycast/skills`
+pub fn skills_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".proxycast").join("skills"))
+}
+
+/// 解析脚本路径：Skill 需已安装（包含 SKILL.md），脚本解析后必须位于 Skill 目录内
+pub fn resolve_script(skills_dir: &Path, skill: &str, script: &str) -> Result<PathBuf, String> {
+    if skill.is_empty() || skill.starts_with('.') || skill.contains(['/', '\\']) {
+        return Err(format!("无效的 Skill 名称: {}", skill));
+    }
+    let skill_dir = skills_dir.join(skill);
+    if !skill_dir.join("SKILL.md").is_file() {
+        return Err(format!("Skill 不存在: {}", skill));
+    }
+    let skill_dir = skill_dir
+        .canonicalize()
+        .map_err(|e| format!("读取 Skill 目录失败: {}", e))?;
+    let path = skill_dir
+        .join(script.trim())
+        .canonicalize()
+        .map_err(|_| format!("脚本不存在: {}/{}", skill, script))?;
+    if !path.starts_with(&skill_dir) {
+        return Err(format!("脚本必须位于 Skill 目录内: {}", script));
+    }
+    if !path.is_file() {
+        return Err(format!("不是文件: {}/{}", skill, script));
+    }
+    Ok(path)
+}
+
+/// 按扩展名选择解释器，返回（程序, 前置参数）
+fn interpreter(path: &Path) -> (String, Vec<String>) {
+    let path_arg = path.to_string_lossy().into_owned();
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let python = if cfg!(windows) { "python" } else { "python3" };
+    match ext.as_str() {
+        "py" => (python.to_string(), vec![path_arg]),
+        "sh" => ("bash".to_string(), vec![path_arg]),
+        "js" | "mjs" | "cjs" => ("node".to_string(), vec![path_arg]),
+        "rb" => ("ruby".to_string(), vec![path_arg]),
+        "ps1" => (
+            "powershell".to_string(),
+            vec![
+                "-NoProfile".to_string(),
+                "-ExecutionPolicy".to_string(),
+                "Bypass".to_string(),
+                "-File".to_string(),
+                path_arg,
+            ],
+        ),
+        _ => (path_arg, Vec::new()),
+    }
+}
+
+/// 列出已安装 Skill 的 `scripts/` 目录中的脚本（`skill/scripts/name` 形式）
+pub fn list_skill_scripts(skills_dir: &Path) -> Vec<String> {
+    let mut scripts = Vec::new();
+    let Ok(skills) = std::fs::read_dir(skills_dir) else {
+        return scripts;
+    };
+    for skill in skills.flatten() {
+        let skill_dir = skill.path();
+        if !skill_dir.join("SKILL.md").is_file() {
+            continue;
+        }
+        let Ok(entries) = std::fs::read_dir(skill_dir.join("scripts")) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if entry.path().is_file() {
+                scripts.push(format!(
+                    "{}/scripts/{}",
+                    skill.file_name().to_string_lossy(),
+                    entry.file_name().to_string_lossy()
+                ));
+            }
+        }
+    }
+    scripts.sort();
+    scripts
+}
+
+fn truncate_output(mut output: String) -> String {
+    if let Some((index, _)) = output.char_indices().nth(MAX_OUTPUT_CHARS) {
+        output.truncate(index);
+        output.push_str("\n... [输出过长，已截断]");
+    }
+    output
+}
+
+/// 在 Skill 目录中运行脚本，返回（是否成功, 输出）
+async fn run_script(
+    skill_dir: &Path,
+    path: &Path,
+    args: &[String],
+) -> Result<(bool, String), String> {
+    let (program, mut argv) = interpreter(path);
+    argv.extend(args.iter().cloned());
+    let mut command = Command::new(&program);
+    command
+        .args(&argv)
+        .current_dir(skill_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let output = tokio::time::timeout(SCRIPT_TIMEOUT, command.output())
+        .await
+        .map_err(|_| format!("脚本运行超时（{} 秒）", SCRIPT_TIMEOUT.as_secs()))?
+        .map_err(|e| format!("无法运行 {}: {}", program, e))?;
+
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stderr.trim().is_empty() {
+        text.push_str("\n[stderr]\n");
+        text.push_str(&stderr);
+    }
+    if !output.status.success() {
+        text.push_str(&format!(
+            "\n[退出码: {}]",
+            output.status.code().unwrap_or(-1)
+        ));
+    }
+    Ok((output.status.success(), truncate_output(text)))
+}
+
+/// `run_skill_script` 工具（运行前需用户批准）
+pub struct SkillScriptTool {
+    skills_dir: PathBuf,
+    /// 创建时扫描到的脚本，列在工具描述中
+    scripts: Vec<String>,
+    queue: ScriptReviewQueue,
+    session_id: String,
+}
+
+impl SkillScriptTool {
+    pub fn new(
+        skills_dir: impl Into<PathBuf>,
+        queue: ScriptReviewQueue,
+        session_id: impl Into<String>,
+    ) -> Self {
+        let skills_dir = skills_dir.into();
+        Self {
+            scripts: list_skill_scripts(&skills_dir),
+            skills_dir,
+            queue,
+            session_id: session_id.into(),
+        }
+    }
+
+    /// 是否有可运行的脚本（没有时不需要注册工具）
+    pub fn has_scripts(&self) -> bool {
+        !self.scripts.is_empty()
+    }
+}
+
+#[async_trait]
+impl Tool for SkillScriptTool {
+    fn definition(&self) -> ToolDefinition {
+        let mut listed = self
+            .scripts
+            .iter()
+            .take(MAX_LISTED_SCRIPTS)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", ");
+        if self.scripts.len() > MAX_LISTED_SCRIPTS {
+            listed.push_str(", ...");
+        }
+        ToolDefinition::new(
+            "run_skill_script",
+            format!(
+                "Run a script bundled with an installed skill, as instructed by that skill's \
+                 SKILL.md, and return its output. The user must approve each run first. The \
+                 script runs in the skill directory with the given arguments (no shell). \
+                 Available scripts: {}",
+                if listed.is_empty() { "none" } else { &listed }
+            ),
+        )
+        .with_parameters(
+            JsonSchema::new()
+                .add_property(
+                    "skill",
+                    PropertySchema::string("Skill directory name."),
+                    true,
+                )
+                .add_property(
+                    "script",
+                    PropertySchema::string(
+                        "Script path relative to the skill directory, e.g. `scripts/convert.py`.",
+                    ),
+                    true,
+                )
+                .add_property(
+                    "args",
+                    PropertySchema::array("Command-line arguments (strings).")
+                        .with_items(PropertySchema::string("Argument")),
+                    false,
+                ),
+        )
+        .with_timeout(
+            APPROVAL_TIMEOUT.as_secs() + SCRIPT_TIMEOUT.as_secs() + TOOL_TIMEOUT_GRACE_SECS,
+        )
+    }
+
+    fn access(&self) -> ToolAccess {
+        ToolAccess::Reviewed
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> Result<ToolResult, ToolError> {
+        let skill = args
+            .get("skill")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidArguments("缺少 skill 参数".to_string()))?;
+        let script = args
+            .get("script")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidArguments("缺少 script 参数".to_string()))?;
+        let script_args: Vec<String> = args
+            .get("args")
+            .and_then(|v| v.as_array())
+            .map(|values| {
+                values
+                    .iter()
+                    .map(|v| match v {
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let path = resolve_script(&self.skills_dir, skill, script).map_err(ToolError::Security)?;
+        let (request, pending) = self
+            .queue
+            .request(ScriptRunRequest::new(
+                &self.session_id,
+                skill,
+                script,
+                script_args.clone(),
+            ))
+            .map_err(ToolError::ExecutionFailed)?;
+        info!(
+            "[SkillScriptTool] 等待批准运行脚本: id={}, session={}, script={}/{}",
+            request.id, self.session_id, skill, script
+        );
+
+        match pending.wait(APPROVAL_TIMEOUT).await {
+            Some(true) => {}
+            Some(false) => return Ok(ToolResult::failure("用户拒绝运行该脚本")),
+            None => {
+                return Ok(ToolResult::failure(format!(
+                    "用户在 {} 秒内没有批准，脚本未运行",
+                    APPROVAL_TIMEOUT.as_secs()
+                )))
+            }
+        }
+
+        let skill_dir = self.skills_dir.join(skill);
+        let (success, output) = run_script(&skill_dir, &path, &script_args)
+            .await
+            .map_err(ToolError::ExecutionFailed)?;
+        Ok(if success {
+            ToolResult::success(output)
+        } else {
+            ToolResult::failure(output)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_script_stays_in_skill_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let skill = dir.path().join("pdf");
+        std::fs::create_dir_all(skill.join("scripts")).unwrap();
+        std::fs::write(skill.join("SKILL.md"), "---\nname: pdf\n---\n").unwrap();
+        std::fs::write(skill.join("scripts").join("fill.py"), "print(1)\n").unwrap();
+        std::fs::write(dir.path().join("secret.txt"), "x").unwrap();
+
+        assert!(resolve_script(dir.path(), "pdf", "scripts/fill.py").is_ok());
+        assert!(resolve_script(dir.path(), "pdf", "../secret.txt").is_err());
+        assert!(resolve_script(dir.path(), "pdf", "scripts").is_err());
+        assert!(resolve_script(dir.path(), "../pdf", "scripts/fill.py").is_err());
+        assert!(resolve_script(dir.path(), "missing", "scripts/fill.py").is_err());
+        assert_eq!(list_skill_scripts(dir.path()), vec!["pdf/scripts/fill.py"]);
+
+        let (program, argv) = interpreter(&skill.join("scripts").join("fill.py"));
+        assert!(program.starts_with("python"));
+        assert_eq!(argv.len(), 1);
+    }
+}
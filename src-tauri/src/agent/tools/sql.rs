@@ -0,0 +1,576 @@
+//! SQL 查询工具模块
+//!
+//! 基于配置中的连接档案（`sql_connections`）提供只读的 `sql_query` 和 `sql_schema` 工具：
+//! - SQLite 使用 rusqlite 以只读模式打开数据库文件
+//! - Postgres / MySQL 使用 sqlx 连接，在只读事务中执行后回滚
+//! - 执行前检查只能是单条 SELECT / WITH / EXPLAIN / SHOW / DESCRIBE / VALUES 语句
+//!   （关键字检查只是第一道防线，写入由只读连接和只读事务拒绝）
+//! - 每个连接单独配置超时和最大返回行数，超出的行不返回
+
+use super::registry::Tool;
+use super::types::{JsonSchema, PropertySchema, ToolAccess, ToolDefinition, ToolError, ToolResult};
+use crate::config::{expand_tilde, SqlConnectionConfig, SqlDriver};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{Column, Connection, Row, TypeInfo};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+
+/// 允许的语句首个关键字
+const READ_ONLY_KEYWORDS: &[&str] = &[
+    "select", "with", "explain", "show", "describe", "desc", "values",
+];
+
+/// 查询结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueryOutput {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+    /// 结果超过最大行数，只返回了前 `max_rows` 行
+    pub truncated: bool,
+}
+
+/// 检查是否为单条只读语句，返回去掉注释和末尾分号后的语句
+pub fn check_read_only(sql: &str) -> Result<String, String> {
+    let mut statement = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    let mut quote: Option<char> = None;
+    let mut ended = false;
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            statement.push(c);
+            if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '-' if chars.peek() == Some(&'-') => {
+                while chars.next_if(|&n| n != '\n').is_some() {}
+                statement.push(' ');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for n in chars.by_ref() {
+                    if prev == '*' && n == '/' {
+                        break;
+                    }
+                    prev = n;
+                }
+                statement.push(' ');
+            }
+            ';' => ended = true,
+            c if ended && !c.is_whitespace() => {
+                return Err("只能执行单条语句".to_string());
+            }
+            c => {
+                if matches!(c, '\'' | '"' | '`') {
+                    quote = Some(c);
+                }
+                statement.push(c);
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err("语句中的引号未闭合".to_string());
+    }
+
+    let statement = statement.trim().to_string();
+    let keyword: String = statement
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if keyword.is_empty() {
+        return Err("查询语句不能为空".to_string());
+    }
+    if !READ_ONLY_KEYWORDS.contains(&keyword.as_str()) {
+        return Err(format!(
+            "只允许只读查询，不支持 {} 语句",
+            keyword.to_uppercase()
+        ));
+    }
+    Ok(statement)
+}
+
+/// 校验连接档案：名称合法且唯一、地址非空
+pub fn validate_sql_connections(connections: &[SqlConnectionConfig]) -> Result<(), String> {
+    let mut names = HashSet::new();
+    for connection in connections {
+        if !super::command::is_valid_tool_name(&connection.name) {
+            return Err(format!(
+                "SQL 连接名称只能包含字母、数字、_ 和 -: {:?}",
+                connection.name
+            ));
+        }
+        if !names.insert(connection.name.as_str()) {
+            return Err(format!("SQL 连接名称重复: {}", connection.name));
+        }
+        if connection.url.trim().is_empty() {
+            return Err(format!("SQL 连接 {} 的地址不能为空", connection.name));
+        }
+        if connection.max_rows == 0 {
+            return Err(format!(
+                "SQL 连接 {} 的 max_rows 必须大于 0",
+                connection.name
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn sqlite_value(value: rusqlite::types::ValueRef<'_>) -> Value {
+    use rusqlite::types::ValueRef;
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => Value::from(i),
+        ValueRef::Real(f) => Value::from(f),
+        ValueRef::Text(t) => Value::String(String::from_utf8_lossy(t).into_owned()),
+        ValueRef::Blob(b) => bytes_value(b.to_vec()),
+    }
+}
+
+fn bytes_value(bytes: Vec<u8>) -> Value {
+    match String::from_utf8(bytes) {
+        Ok(text) => Value::String(text),
+        Err(e) => Value::String(format!("<{} 字节二进制数据>", e.as_bytes().len())),
+    }
+}
+
+/// 以只读模式在 SQLite 上执行查询（阻塞，在 spawn_blocking 中调用）
+fn query_sqlite(
+    path: &str,
+    sql: &str,
+    params: &[String],
+    max_rows: usize,
+    timeout: Duration,
+) -> Result<QueryOutput, String> {
+    use rusqlite::OpenFlags;
+
+    let path = expand_tilde(path);
+    let conn = rusqlite::Connection::open_with_flags(
+        &path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(|e| format!("打开数据库 {} 失败: {}", path.display(), e))?;
+    let deadline = std::time::Instant::now() + timeout;
+    conn.progress_handler(1000, Some(move || std::time::Instant::now() > deadline));
+
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+    let mut rows = stmt
+        .query(rusqlite::params_from_iter(params))
+        .map_err(|e| e.to_string())?;
+    let mut output = QueryOutput {
+        columns,
+        rows: Vec::new(),
+        truncated: false,
+    };
+    while let Some(row) = rows.next().map_err(|e| {
+        if matches!(
+            e.sqlite_error_code(),
+            Some(rusqlite::ErrorCode::OperationInterrupted)
+        ) {
+            format!("查询超时（{} 秒）", timeout.as_secs())
+        } else {
+            e.to_string()
+        }
+    })? {
+        if output.rows.len() == max_rows {
+            output.truncated = true;
+            break;
+        }
+        let values = (0..output.columns.len())
+            .map(|i| row.get_ref(i).map(sqlite_value).unwrap_or(Value::Null))
+            .collect();
+        output.rows.push(values);
+    }
+    Ok(output)
+}
+
+/// 为 sqlx 的行类型生成查询函数：依次尝试常见类型解码单元格，都不支持时返回类型名
+macro_rules! sqlx_query_fn {
+    ($name:ident, $conn:ty, $row:ty, [$($ty:ty => $conv:expr),+ $(,)?]) => {
+        async fn $name(
+            conn: &mut $conn,
+            sql: &str,
+            params: &[String],
+            max_rows: usize,
+        ) -> Result<QueryOutput, String> {
+            fn cell(row: &$row, i: usize) -> Value {
+                $(
+                    if let Ok(value) = row.try_get::<Option<$ty>, _>(i) {
+                        return value.map($conv).unwrap_or(Value::Null);
+                    }
+                )+
+                Value::String(format!("<{}>", row.column(i).type_info().name()))
+            }
+
+            let mut query = sqlx::query(sql);
+            for param in params {
+                query = query.bind(param.as_str());
+            }
+            let mut stream = query.fetch(&mut *conn);
+            let mut output = QueryOutput {
+                columns: Vec::new(),
+                rows: Vec::new(),
+                truncated: false,
+            };
+            while let Some(row) = stream.try_next().await.map_err(|e| e.to_string())? {
+                if output.columns.is_empty() {
+                    output.columns = row.columns().iter().map(|c| c.name().to_string()).collect();
+                }
+                if output.rows.len() == max_rows {
+                    output.truncated = true;
+                    break;
+                }
+                output.rows.push((0..row.len()).map(|i| cell(&row, i)).collect());
+            }
+            Ok(output)
+        }
+    };
+}
+
+sqlx_query_fn!(fetch_postgres, sqlx::PgConnection, sqlx::postgres::PgRow, [
+    String => Value::String,
+    i64 => Value::from,
+    i32 => Value::from,
+    i16 => Value::from,
+    f64 => Value::from,
+    f32 => Value::from,
+    bool => Value::Bool,
+    sqlx::types::Decimal => |v| Value::String(v.to_string()),
+    chrono::DateTime<chrono::Utc> => |v| Value::String(v.to_rfc3339()),
+    chrono::NaiveDateTime => |v| Value::String(v.to_string()),
+    chrono::NaiveDate => |v| Value::String(v.to_string()),
+    chrono::NaiveTime => |v| Value::String(v.to_string()),
+    serde_json::Value => |v| v,
+    Vec<u8> => bytes_value,
+]);
+
+sqlx_query_fn!(fetch_mysql, sqlx::MySqlConnection, sqlx::mysql::MySqlRow, [
+    String => Value::String,
+    i64 => Value::from,
+    u64 => Value::from,
+    i32 => Value::from,
+    u32 => Value::from,
+    i16 => Value::from,
+    i8 => Value::from,
+    f64 => Value::from,
+    f32 => Value::from,
+    bool => Value::Bool,
+    sqlx::types::Decimal => |v| Value::String(v.to_string()),
+    chrono::DateTime<chrono::Utc> => |v| Value::String(v.to_rfc3339()),
+    chrono::NaiveDateTime => |v| Value::String(v.to_string()),
+    chrono::NaiveDate => |v| Value::String(v.to_string()),
+    chrono::NaiveTime => |v| Value::String(v.to_string()),
+    serde_json::Value => |v| v,
+    Vec<u8> => bytes_value,
+]);
+
+/// 在连接上执行只读查询（`sql` 需已通过 [`check_read_only`]）
+pub async fn run_query(
+    connection: &SqlConnectionConfig,
+    sql: &str,
+    params: &[String],
+) -> Result<QueryOutput, String> {
+    let timeout = Duration::from_secs(connection.timeout_secs.max(1));
+    let max_rows = connection.max_rows;
+    let task = async {
+        match connection.driver {
+            SqlDriver::Sqlite => {
+                let (path, sql, params) =
+                    (connection.url.clone(), sql.to_string(), params.to_vec());
+                tokio::task::spawn_blocking(move || {
+                    query_sqlite(&path, &sql, &params, max_rows, timeout)
+                })
+                .await
+                .map_err(|e| e.to_string())?
+            }
+            SqlDriver::Postgres => {
+                let mut conn = sqlx::PgConnection::connect(&connection.url)
+                    .await
+                    .map_err(|e| format!("连接数据库失败: {}", e))?;
+                sqlx::raw_sql("BEGIN READ ONLY")
+                    .execute(&mut conn)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                let result = fetch_postgres(&mut conn, sql, params, max_rows).await;
+                let _ = sqlx::raw_sql("ROLLBACK").execute(&mut conn).await;
+                let _ = conn.close().await;
+                result
+            }
+            SqlDriver::Mysql => {
+                let mut conn = sqlx::MySqlConnection::connect(&connection.url)
+                    .await
+                    .map_err(|e| format!("连接数据库失败: {}", e))?;
+                sqlx::raw_sql("START TRANSACTION READ ONLY")
+                    .execute(&mut conn)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                let result = fetch_mysql(&mut conn, sql, params, max_rows).await;
+                let _ = sqlx::raw_sql("ROLLBACK").execute(&mut conn).await;
+                let _ = conn.close().await;
+                result
+            }
+        }
+    };
+    tokio::time::timeout(timeout, task)
+        .await
+        .map_err(|_| format!("查询超时（{} 秒）", timeout.as_secs()))?
+}
+
+/// 列出表（`table` 为空时）或表的列的查询语句和参数
+fn schema_query(driver: SqlDriver, table: Option<&str>) -> (&'static str, Vec<String>) {
+    let params = table.map(|t| vec![t.to_string()]).unwrap_or_default();
+    let sql = match (driver, table.is_some()) {
+        (SqlDriver::Sqlite, false) => {
+            "SELECT name, type FROM sqlite_master \
+             WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite_%' ORDER BY name"
+        }
+        (SqlDriver::Sqlite, true) => {
+            "SELECT name, type, \"notnull\" AS not_null, pk FROM pragma_table_info(?1)"
+        }
+        (SqlDriver::Postgres, false) => {
+            "SELECT table_schema::text, table_name::text, table_type::text \
+             FROM information_schema.tables \
+             WHERE table_schema NOT IN ('pg_catalog', 'information_schema') ORDER BY 1, 2"
+        }
+        (SqlDriver::Postgres, true) => {
+            "SELECT column_name::text, data_type::text, is_nullable::text, column_default::text \
+             FROM information_schema.columns WHERE table_name = $1 ORDER BY ordinal_position"
+        }
+        (SqlDriver::Mysql, false) => {
+            "SELECT table_name, table_type FROM information_schema.tables \
+             WHERE table_schema = DATABASE() ORDER BY table_name"
+        }
+        (SqlDriver::Mysql, true) => {
+            "SELECT column_name, column_type, is_nullable, column_key, column_default \
+             FROM information_schema.columns \
+             WHERE table_schema = DATABASE() AND table_name = ? ORDER BY ordinal_position"
+        }
+    };
+    (sql, params)
+}
+
+fn find_connection<'a>(
+    connections: &'a [SqlConnectionConfig],
+    args: &serde_json::Value,
+) -> Result<&'a SqlConnectionConfig, ToolError> {
+    let name = args
+        .get("connection")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidArguments("缺少 connection 参数".to_string()))?;
+    connections
+        .iter()
+        .find(|c| c.name == name)
+        .ok_or_else(|| ToolError::InvalidArguments(format!("SQL 连接不存在: {}", name)))
+}
+
+fn connection_property(connections: &[SqlConnectionConfig]) -> PropertySchema {
+    let list = connections
+        .iter()
+        .map(|c| match &c.description {
+            Some(description) => format!("{} ({:?}: {})", c.name, c.driver, description),
+            None => format!("{} ({:?})", c.name, c.driver),
+        })
+        .collect::<Vec<_>>()
+        .join("; ");
+    PropertySchema::string(format!("Connection profile to use. Available: {}", list)).with_enum(
+        connections
+            .iter()
+            .map(|c| Value::from(c.name.clone()))
+            .collect(),
+    )
+}
+
+fn max_timeout(connections: &[SqlConnectionConfig]) -> u64 {
+    connections
+        .iter()
+        .map(|c| c.timeout_secs)
+        .max()
+        .unwrap_or_default()
+        + super::bash::TOOL_TIMEOUT_GRACE_SECS
+}
+
+fn format_output(output: &QueryOutput) -> String {
+    serde_json::to_string_pretty(output).unwrap_or_default()
+}
+
+/// `sql_query` 工具
+pub struct SqlQueryTool {
+    connections: Arc<Vec<SqlConnectionConfig>>,
+}
+
+impl SqlQueryTool {
+    pub fn new(connections: Arc<Vec<SqlConnectionConfig>>) -> Self {
+        Self { connections }
+    }
+}
+
+#[async_trait]
+impl Tool for SqlQueryTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "sql_query",
+            "Run a single read-only SQL statement (SELECT, WITH, EXPLAIN, SHOW, DESCRIBE) against \
+             a configured database connection. Results are returned as JSON with `columns`, \
+             `rows` and `truncated` (true when the row limit was hit). Use `sql_schema` first to \
+             discover tables and columns.",
+        )
+        .with_parameters(
+            JsonSchema::new()
+                .add_property("connection", connection_property(&self.connections), true)
+                .add_property(
+                    "query",
+                    PropertySchema::string("The SQL statement, in the connection's dialect."),
+                    true,
+                ),
+        )
+        .with_timeout(max_timeout(&self.connections))
+    }
+
+    fn access(&self) -> ToolAccess {
+        ToolAccess::ReadOnly
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> Result<ToolResult, ToolError> {
+        let connection = find_connection(&self.connections, &args)?;
+        let query = args
+            .get("query")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidArguments("缺少 query 参数".to_string()))?;
+        let statement = check_read_only(query).map_err(ToolError::InvalidArguments)?;
+
+        info!("[SqlQueryTool] 在 {} 上执行查询", connection.name);
+        let output = run_query(connection, &statement, &[])
+            .await
+            .map_err(ToolError::ExecutionFailed)?;
+        Ok(ToolResult::success(format_output(&output)))
+    }
+}
+
+/// `sql_schema` 工具
+pub struct SqlSchemaTool {
+    connections: Arc<Vec<SqlConnectionConfig>>,
+}
+
+impl SqlSchemaTool {
+    pub fn new(connections: Arc<Vec<SqlConnectionConfig>>) -> Self {
+        Self { connections }
+    }
+}
+
+#[async_trait]
+impl Tool for SqlSchemaTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition::new(
+            "sql_schema",
+            "Inspect the schema of a configured database connection: without `table`, list its \
+             tables and views; with `table`, list that table's columns and types.",
+        )
+        .with_parameters(
+            JsonSchema::new()
+                .add_property("connection", connection_property(&self.connections), true)
+                .add_property(
+                    "table",
+                    PropertySchema::string("Table to describe (omit to list tables)."),
+                    false,
+                ),
+        )
+        .with_timeout(max_timeout(&self.connections))
+    }
+
+    fn access(&self) -> ToolAccess {
+        ToolAccess::ReadOnly
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> Result<ToolResult, ToolError> {
+        let connection = find_connection(&self.connections, &args)?;
+        let table = args
+            .get("table")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|t| !t.is_empty());
+        let (sql, params) = schema_query(connection.driver, table);
+        let output = run_query(connection, sql, &params)
+            .await
+            .map_err(ToolError::ExecutionFailed)?;
+        if table.is_some() && output.rows.is_empty() {
+            return Ok(ToolResult::failure(format!(
+                "表不存在或没有列: {}",
+                table.unwrap_or_default()
+            )));
+        }
+        Ok(ToolResult::success(format_output(&output)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_read_only() {
+        assert_eq!(
+            check_read_only("-- count users\nSELECT count(*) FROM users;  ").unwrap(),
+            "SELECT count(*) FROM users"
+        );
+        assert!(check_read_only("select ';' as semi /* ; */").is_ok());
+        assert!(check_read_only("WITH t AS (SELECT 1) SELECT * FROM t").is_ok());
+        assert!(check_read_only("DELETE FROM users").is_err());
+        assert!(check_read_only("SELECT 1; DROP TABLE users").is_err());
+        assert!(check_read_only("/* x */ update users set a = 1").is_err());
+        assert!(check_read_only("SELECT 'open").is_err());
+        assert!(check_read_only("  -- only a comment").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_read_only_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.db");
+        {
+            let conn = rusqlite::Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, score REAL);
+                 INSERT INTO users (name, score) VALUES ('alice', 1.5), ('bob', NULL), ('carol', 3);",
+            )
+            .unwrap();
+        }
+        let connection = SqlConnectionConfig {
+            name: "local".to_string(),
+            driver: SqlDriver::Sqlite,
+            url: path.to_string_lossy().into_owned(),
+            description: None,
+            max_rows: 2,
+            timeout_secs: 5,
+            enabled: true,
+        };
+
+        let output = run_query(
+            &connection,
+            "SELECT name, score FROM users ORDER BY id",
+            &[],
+        )
+        .await
+        .unwrap();
+        assert_eq!(output.columns, vec!["name", "score"]);
+        assert_eq!(output.rows.len(), 2);
+        assert_eq!(output.rows[1], vec![Value::from("bob"), Value::Null]);
+        assert!(output.truncated);
+
+        let (sql, params) = schema_query(SqlDriver::Sqlite, Some("users"));
+        let columns = run_query(&connection, sql, &params).await.unwrap();
+        assert_eq!(columns.rows[0][0], Value::from("id"));
+
+        // 只读连接拒绝写入（即使绕过了关键字检查）
+        assert!(run_query(&connection, "DELETE FROM users", &[])
+            .await
+            .is_err());
+    }
+}
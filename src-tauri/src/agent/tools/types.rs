@@ -3,10 +3,28 @@
 //! 定义工具系统的核心类型，包括工具定义、调用、结果和错误
 //! 符合 Requirements 2.1, 2.5
 
+use crate::agent::types::AttachmentInfo;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use thiserror::Error;
 
+/// 未设置超时时间的工具的默认超时（秒）
+pub const DEFAULT_TOOL_TIMEOUT_SECS: u64 = 300;
+
+/// 工具对外部状态的影响，决定权限模式下能否直接执行
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolAccess {
+    /// 只读取数据
+    ReadOnly,
+    /// 会写文件、执行命令或产生其他副作用
+    Mutating,
+    /// 会修改状态，但工具自身在生效前请求用户审核
+    Reviewed,
+    /// 在本机不受隔离地运行任意代码，`auto` 模式下同样需要用户批准
+    Unconfined,
+}
+
 /// 工具定义结构
 ///
 /// 包含工具的名称、描述和参数 JSON Schema
@@ -19,6 +37,9 @@ pub struct ToolDefinition {
     pub description: String,
     /// 参数 JSON Schema
     pub parameters: JsonSchema,
+    /// 执行超时时间（秒），未设置时使用 [`DEFAULT_TOOL_TIMEOUT_SECS`]；不发送给模型
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
 }
 
 impl ToolDefinition {
@@ -28,6 +49,7 @@ impl ToolDefinition {
             name: name.into(),
             description: description.into(),
             parameters: JsonSchema::default(),
+            timeout_secs: None,
         }
     }
 
@@ -37,6 +59,17 @@ impl ToolDefinition {
         self
     }
 
+    /// 设置执行超时时间（秒）
+    pub fn with_timeout(mut self, timeout_secs: u64) -> Self {
+        self.timeout_secs = Some(timeout_secs);
+        self
+    }
+
+    /// 执行超时时间
+    pub fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.timeout_secs.unwrap_or(DEFAULT_TOOL_TIMEOUT_SECS))
+    }
+
     /// 验证工具定义是否有效
     pub fn validate(&self) -> Result<(), ToolValidationError> {
         if self.name.is_empty() {
@@ -51,16 +84,111 @@ impl ToolDefinition {
 
     /// 转换为 OpenAI API 格式的工具定义
     pub fn to_api_format(&self) -> crate::models::openai::Tool {
+        self.to_api_format_with(false)
+    }
+
+    /// 转换为 OpenAI API 格式的工具定义，strict 为 true 时使用严格模式
+    ///
+    /// 严格模式下输出 `strict: true`，参数 schema 按 OpenAI 要求规范化
+    /// （见 [`JsonSchema::to_strict_value`]）；schema 无法用严格模式表达时按非严格模式输出
+    pub fn to_api_format_with(&self, strict: bool) -> crate::models::openai::Tool {
+        let strict_parameters = if strict {
+            let value = self.parameters.to_strict_value();
+            if value.is_none() {
+                tracing::debug!(
+                    "工具 {} 的参数 schema 不支持严格模式，按非严格模式发送",
+                    self.name
+                );
+            }
+            value
+        } else {
+            None
+        };
+        let strict = strict_parameters.is_some();
+        let parameters = strict_parameters
+            .unwrap_or_else(|| serde_json::to_value(&self.parameters).unwrap_or_default());
         crate::models::openai::Tool::Function {
             function: crate::models::openai::FunctionDef {
                 name: self.name.clone(),
                 description: Some(self.description.clone()),
-                parameters: Some(serde_json::to_value(&self.parameters).unwrap_or_default()),
+                parameters: Some(parameters),
+                strict: strict.then_some(true),
             },
         }
     }
 }
 
+/// 递归规范化 schema 节点，无法用严格模式表达时返回 false
+fn make_strict(schema: &mut serde_json::Value) -> bool {
+    let Some(obj) = schema.as_object_mut() else {
+        return false;
+    };
+    obj.remove("default");
+    let has_type =
+        |obj: &serde_json::Map<String, serde_json::Value>, name: &str| match obj.get("type") {
+            Some(serde_json::Value::String(t)) => t == name,
+            Some(serde_json::Value::Array(types)) => types.iter().any(|t| t == name),
+            _ => false,
+        };
+
+    if has_type(obj, "array") && !obj.get_mut("items").is_some_and(make_strict) {
+        return false;
+    }
+
+    if has_type(obj, "object") {
+        let required: Vec<String> = obj
+            .get("required")
+            .and_then(|r| r.as_array())
+            .map(|r| {
+                r.iter()
+                    .filter_map(|v| v.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let Some(properties) = obj.get_mut("properties").and_then(|p| p.as_object_mut()) else {
+            return false;
+        };
+        let mut names = Vec::new();
+        for (name, prop) in properties.iter_mut() {
+            if !make_strict(prop) {
+                return false;
+            }
+            if !required.contains(name) {
+                make_nullable(prop);
+            }
+            names.push(name.clone());
+        }
+        names.sort();
+        obj.insert("required".to_string(), serde_json::json!(names));
+        obj.insert("additionalProperties".to_string(), serde_json::json!(false));
+    }
+    true
+}
+
+/// 允许属性取 null（严格模式下可选属性的表示方式）
+fn make_nullable(prop: &mut serde_json::Value) {
+    let Some(obj) = prop.as_object_mut() else {
+        return;
+    };
+    let nullable_type = match obj.get("type") {
+        Some(serde_json::Value::String(t)) => Some(serde_json::json!([t, "null"])),
+        Some(serde_json::Value::Array(types)) if !types.iter().any(|t| t == "null") => {
+            let mut types = types.clone();
+            types.push(serde_json::json!("null"));
+            Some(serde_json::Value::Array(types))
+        }
+        _ => None,
+    };
+    if let Some(nullable_type) = nullable_type {
+        obj.insert("type".to_string(), nullable_type);
+    }
+    if let Some(serde_json::Value::Array(values)) = obj.get_mut("enum") {
+        if !values.contains(&serde_json::Value::Null) {
+            values.push(serde_json::Value::Null);
+        }
+    }
+}
+
 /// JSON Schema 参数定义
 ///
 /// 定义工具参数的类型和结构
@@ -119,6 +247,127 @@ impl JsonSchema {
         }
         Ok(())
     }
+
+    /// 生成 OpenAI 严格模式的 schema
+    ///
+    /// - 每一层对象（包括嵌套对象和数组元素）都设置 `additionalProperties: false`
+    /// - 所有属性都列入 required，原本可选的属性类型改为可为 null（有枚举时枚举中加入 null）
+    /// - 移除严格模式不支持的 `default`
+    ///
+    /// 没有定义属性的对象（任意键值的 map）和没有 `items` 的数组无法用严格模式表达，返回 None。
+    pub fn to_strict_value(&self) -> Option<serde_json::Value> {
+        let mut value = serde_json::to_value(self).ok()?;
+        make_strict(&mut value).then_some(value)
+    }
+
+    /// 按 schema 校验工具调用参数
+    ///
+    /// 检查必需参数、参数类型和枚举取值；strict 为 true 时额外拒绝未定义的参数。
+    /// 可选参数允许为 null（严格模式下模型会为未使用的可选参数传 null）。
+    pub fn validate_args(
+        &self,
+        args: &serde_json::Value,
+        strict: bool,
+    ) -> Result<(), Vec<SchemaViolation>> {
+        let Some(obj) = args.as_object() else {
+            return Err(vec![SchemaViolation::new("$", "参数必须是 JSON 对象")]);
+        };
+
+        let mut violations = Vec::new();
+
+        for required in &self.required {
+            match obj.get(required) {
+                None => violations.push(SchemaViolation::new(required, "缺少必需参数")),
+                Some(serde_json::Value::Null) => {
+                    violations.push(SchemaViolation::new(required, "必需参数不能为 null"))
+                }
+                Some(_) => {}
+            }
+        }
+
+        let mut keys: Vec<&String> = obj.keys().collect();
+        keys.sort();
+        for key in keys {
+            let value = &obj[key];
+            let Some(prop) = self.properties.get(key) else {
+                if strict {
+                    violations.push(SchemaViolation::new(key, "未定义的参数"));
+                }
+                continue;
+            };
+            if value.is_null() {
+                continue;
+            }
+            if !value_matches_type(value, &prop.prop_type) {
+                violations.push(SchemaViolation::new(
+                    key,
+                    format!("类型应为 {}", prop.prop_type),
+                ));
+                continue;
+            }
+            if let Some(allowed) = &prop.enum_values {
+                if !allowed.contains(value) {
+                    let allowed: Vec<String> = allowed.iter().map(|v| v.to_string()).collect();
+                    violations.push(SchemaViolation::new(
+                        key,
+                        format!("取值必须是以下之一: {}", allowed.join(", ")),
+                    ));
+                }
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+/// 检查 JSON 值是否符合 schema 类型（未知类型视为通过）
+fn value_matches_type(value: &serde_json::Value, prop_type: &str) -> bool {
+    match prop_type {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => true,
+    }
+}
+
+/// 参数 schema 校验失败项
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaViolation {
+    /// 参数路径（顶层参数名，`$` 表示整个参数对象）
+    pub path: String,
+    /// 失败原因
+    pub message: String,
+}
+
+impl SchemaViolation {
+    pub fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// 将校验失败项格式化为多行文本（反馈给模型）
+pub fn format_violations(violations: &[SchemaViolation]) -> String {
+    violations
+        .iter()
+        .map(|v| format!("- {}", v))
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 /// 属性 Schema
@@ -137,57 +386,58 @@ pub struct PropertySchema {
     /// 枚举值（可选，用于限制取值范围）
     #[serde(skip_serializing_if = "Option::is_none", rename = "enum")]
     pub enum_values: Option<Vec<serde_json::Value>>,
+    /// 数组元素的 schema（array 类型）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub items: Option<Box<PropertySchema>>,
+    /// 嵌套对象的属性（object 类型）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub properties: Option<HashMap<String, PropertySchema>>,
+    /// 嵌套对象的必需属性
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required: Option<Vec<String>>,
 }
 
 impl PropertySchema {
-    /// 创建字符串类型属性
-    pub fn string(description: impl Into<String>) -> Self {
+    fn of_type(prop_type: &str, description: impl Into<String>) -> Self {
         Self {
-            prop_type: "string".to_string(),
+            prop_type: prop_type.to_string(),
             description: description.into(),
             default: None,
             enum_values: None,
+            items: None,
+            properties: None,
+            required: None,
         }
     }
 
+    /// 创建字符串类型属性
+    pub fn string(description: impl Into<String>) -> Self {
+        Self::of_type("string", description)
+    }
+
     /// 创建数字类型属性
     pub fn number(description: impl Into<String>) -> Self {
-        Self {
-            prop_type: "number".to_string(),
-            description: description.into(),
-            default: None,
-            enum_values: None,
-        }
+        Self::of_type("number", description)
     }
 
     /// 创建整数类型属性
     pub fn integer(description: impl Into<String>) -> Self {
-        Self {
-            prop_type: "integer".to_string(),
-            description: description.into(),
-            default: None,
-            enum_values: None,
-        }
+        Self::of_type("integer", description)
     }
 
     /// 创建布尔类型属性
     pub fn boolean(description: impl Into<String>) -> Self {
-        Self {
-            prop_type: "boolean".to_string(),
-            description: description.into(),
-            default: None,
-            enum_values: None,
-        }
+        Self::of_type("boolean", description)
     }
 
     /// 创建数组类型属性
     pub fn array(description: impl Into<String>) -> Self {
-        Self {
-            prop_type: "array".to_string(),
-            description: description.into(),
-            default: None,
-            enum_values: None,
-        }
+        Self::of_type("array", description)
+    }
+
+    /// 创建对象类型属性
+    pub fn object(description: impl Into<String>) -> Self {
+        Self::of_type("object", description)
     }
 
     /// 设置默认值
@@ -201,6 +451,19 @@ impl PropertySchema {
         self.enum_values = Some(values);
         self
     }
+
+    /// 设置数组元素的 schema
+    pub fn with_items(mut self, items: PropertySchema) -> Self {
+        self.items = Some(Box::new(items));
+        self
+    }
+
+    /// 设置嵌套对象的属性（按 [`JsonSchema`] 的属性和必需列表）
+    pub fn with_properties(mut self, schema: JsonSchema) -> Self {
+        self.properties = Some(schema.properties);
+        self.required = Some(schema.required);
+        self
+    }
 }
 
 /// 工具调用请求
@@ -257,6 +520,9 @@ pub struct ToolResult {
     /// 错误信息（如果失败）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// 工具生成的文件（附加到 tool 消息上）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<AttachmentInfo>,
 }
 
 impl ToolResult {
@@ -266,6 +532,7 @@ impl ToolResult {
             success: true,
             output: output.into(),
             error: None,
+            artifacts: Vec::new(),
         }
     }
 
@@ -276,6 +543,7 @@ impl ToolResult {
             success: false,
             output: String::new(),
             error: Some(error_msg),
+            artifacts: Vec::new(),
         }
     }
 
@@ -285,8 +553,15 @@ impl ToolResult {
             success: false,
             output: output.into(),
             error: Some(error.into()),
+            artifacts: Vec::new(),
         }
     }
+
+    /// 附加工具生成的文件
+    pub fn with_artifacts(mut self, artifacts: Vec<AttachmentInfo>) -> Self {
+        self.artifacts = artifacts;
+        self
+    }
 }
 
 /// 工具错误类型
@@ -300,6 +575,10 @@ pub enum ToolError {
     #[error("参数验证失败: {0}")]
     InvalidArguments(String),
 
+    /// 参数不符合 schema
+    #[error("参数不符合 Schema:\n{}", format_violations(.0))]
+    SchemaValidation(Vec<SchemaViolation>),
+
     /// 执行失败
     #[error("执行失败: {0}")]
     ExecutionFailed(String),
@@ -388,6 +667,153 @@ mod tests {
         ));
     }
 
+    fn create_test_schema() -> JsonSchema {
+        JsonSchema::new()
+            .add_property("path", PropertySchema::string("File path"), true)
+            .add_property(
+                "limit",
+                PropertySchema::integer("Max lines").with_default(serde_json::json!(100)),
+                false,
+            )
+            .add_property(
+                "mode",
+                PropertySchema::string("Mode")
+                    .with_enum(vec![serde_json::json!("fast"), serde_json::json!("full")]),
+                false,
+            )
+    }
+
+    #[test]
+    fn test_to_strict_value() {
+        let strict = create_test_schema().to_strict_value().unwrap();
+        assert_eq!(strict["additionalProperties"], serde_json::json!(false));
+        assert_eq!(
+            strict["required"],
+            serde_json::json!(["limit", "mode", "path"])
+        );
+        assert_eq!(strict["properties"]["path"]["type"], "string");
+        assert_eq!(
+            strict["properties"]["limit"]["type"],
+            serde_json::json!(["integer", "null"])
+        );
+        assert!(strict["properties"]["limit"].get("default").is_none());
+        assert_eq!(
+            strict["properties"]["mode"]["enum"],
+            serde_json::json!(["fast", "full", null])
+        );
+    }
+
+    #[test]
+    fn test_to_strict_value_nested() {
+        let filter = JsonSchema::new()
+            .add_property("field", PropertySchema::string("Field"), true)
+            .add_property(
+                "limit",
+                PropertySchema::integer("Limit").with_default(serde_json::json!(10)),
+                false,
+            );
+        let schema = JsonSchema::new()
+            .add_property(
+                "filters",
+                PropertySchema::array("Filters")
+                    .with_items(PropertySchema::object("Filter").with_properties(filter)),
+                true,
+            )
+            .add_property(
+                "tags",
+                PropertySchema::array("Tags").with_items(PropertySchema::string("Tag")),
+                false,
+            );
+        let strict = schema.to_strict_value().unwrap();
+        let item = &strict["properties"]["filters"]["items"];
+        assert_eq!(item["additionalProperties"], serde_json::json!(false));
+        assert_eq!(item["required"], serde_json::json!(["field", "limit"]));
+        assert_eq!(
+            item["properties"]["limit"]["type"],
+            serde_json::json!(["integer", "null"])
+        );
+        assert!(item["properties"]["limit"].get("default").is_none());
+        assert_eq!(
+            strict["properties"]["tags"]["type"],
+            serde_json::json!(["array", "null"])
+        );
+
+        // 自定义工具的嵌套 schema 反序列化后保留
+        let parsed: JsonSchema = serde_json::from_value(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "point": {
+                    "type": "object",
+                    "description": "Point",
+                    "properties": {"x": {"type": "number", "description": "X"}},
+                    "required": ["x"]
+                }
+            },
+            "required": ["point"]
+        }))
+        .unwrap();
+        let strict = parsed.to_strict_value().unwrap();
+        assert_eq!(
+            strict["properties"]["point"]["properties"]["x"]["type"],
+            "number"
+        );
+        assert_eq!(
+            strict["properties"]["point"]["additionalProperties"],
+            serde_json::json!(false)
+        );
+
+        // 任意键值的对象和没有 items 的数组无法使用严格模式
+        let map =
+            JsonSchema::new().add_property("headers", PropertySchema::object("Headers"), false);
+        assert!(map.to_strict_value().is_none());
+        let list = JsonSchema::new().add_property("args", PropertySchema::array("Args"), false);
+        assert!(list.to_strict_value().is_none());
+        let def = ToolDefinition::new("http", "HTTP").with_parameters(map);
+        let crate::models::openai::Tool::Function { function } = def.to_api_format_with(true)
+        else {
+            panic!("应为 function 工具");
+        };
+        assert_eq!(function.strict, None);
+    }
+
+    #[test]
+    fn test_to_api_format_strict_flag() {
+        let def = ToolDefinition::new("read", "Read a file").with_parameters(create_test_schema());
+        let crate::models::openai::Tool::Function { function } = def.to_api_format_with(true)
+        else {
+            panic!("应为 function 工具");
+        };
+        assert_eq!(function.strict, Some(true));
+
+        let crate::models::openai::Tool::Function { function } = def.to_api_format() else {
+            panic!("应为 function 工具");
+        };
+        assert_eq!(function.strict, None);
+    }
+
+    #[test]
+    fn test_validate_args() {
+        let schema = create_test_schema();
+        assert!(schema
+            .validate_args(&serde_json::json!({"path": "a.txt", "limit": null}), true)
+            .is_ok());
+
+        let violations = schema
+            .validate_args(
+                &serde_json::json!({"limit": "10", "mode": "slow", "extra": 1}),
+                true,
+            )
+            .unwrap_err();
+        let paths: Vec<_> = violations.iter().map(|v| v.path.as_str()).collect();
+        assert_eq!(paths, vec!["path", "extra", "limit", "mode"]);
+
+        // 非严格模式允许额外参数
+        assert!(schema
+            .validate_args(&serde_json::json!({"path": "a.txt", "extra": 1}), false)
+            .is_ok());
+        assert!(schema.validate_args(&serde_json::json!([]), false).is_err());
+    }
+
     #[test]
     fn test_tool_result_creation() {
         let success = ToolResult::success("Hello, World!");
@@ -481,6 +907,7 @@ mod proptests {
                 name,
                 description,
                 parameters,
+                timeout_secs: None,
             })
     }
 
@@ -537,6 +964,7 @@ mod proptests {
                 name,
                 description,
                 parameters: schema,
+                timeout_secs: None,
             };
             prop_assert!(
                 matches!(def.validate(), Err(ToolValidationError::RequiredPropertyNotDefined(_))),
@@ -558,6 +986,7 @@ mod proptests {
                 name,
                 description,
                 parameters: schema,
+                timeout_secs: None,
             };
             prop_assert!(def.validate().is_ok(), "已定义的 required 属性应该通过验证");
         }
@@ -0,0 +1,542 @@
+//! WASM 插件工具模块
+//!
+//! 从插件目录（默认 `~/.proxycast/plugins`）加载 `.wasm` 工具插件，
+//! 每个插件提供一个工具。插件需导出：
+//! - `memory`: 线性内存
+//! - `alloc(len: i32) -> i32`: 分配 `len` 字节，返回指针（宿主写入调用参数）
+//! - `manifest() -> i64`: 返回清单 JSON `{"name", "description", "parameters"}`
+//! - `invoke(ptr: i32, len: i32) -> i64`: 参数为 JSON 参数所在位置，
+//!   返回结果 JSON `{"success", "output", "error"}`
+//!
+//! `i64` 返回值的高 32 位为指针、低 32 位为长度。
+//!
+//! 插件运行在 WASI preview1 沙箱中：默认不能访问文件系统和环境变量，
+//! 目录、环境变量、超时和内存上限在配置中按插件授予。每次调用使用新的实例，
+//! 调用之间不保留状态。
+
+use super::command::{is_valid_tool_name, RESERVED_NAMES};
+use super::registry::Tool;
+use super::types::{JsonSchema, ToolDefinition, ToolError, ToolResult};
+use crate::config::{expand_tilde, WasmPluginPermissions, WasmPluginsConfig};
+use async_trait::async_trait;
+use once_cell::sync::OnceCell;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+use wasmtime::{
+    Config as EngineConfig, Engine, Instance, Linker, Module, Store, StoreLimits,
+    StoreLimitsBuilder, Trap,
+};
+use wasmtime_wasi::pipe::MemoryOutputPipe;
+use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+use wasmtime_wasi::{DirPerms, FilePerms, WasiCtxBuilder};
+
+/// 单次调用的默认超时（秒）
+pub const DEFAULT_PLUGIN_TIMEOUT_SECS: u64 = 30;
+
+/// 默认内存上限（MB）
+pub const DEFAULT_PLUGIN_MAX_MEMORY_MB: u64 = 64;
+
+/// epoch 推进间隔，超时按此粒度检测
+const EPOCH_TICK: Duration = Duration::from_millis(100);
+
+/// 工具级超时比插件超时多留出的时间（秒），保证插件超时先触发
+const PLUGIN_TIMEOUT_GRACE_SECS: u64 = 5;
+
+/// 读取清单的超时
+const MANIFEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 保留的插件 stderr 输出上限
+const MAX_STDERR_BYTES: usize = 64 * 1024;
+
+/// 默认插件目录 `~/.proxycast/plugins`
+pub fn default_plugins_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".proxycast").join("plugins"))
+}
+
+/// 插件清单（`manifest` 导出函数返回）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WasmPluginManifest {
+    /// 工具名称
+    pub name: String,
+    /// 工具描述
+    pub description: String,
+    /// 参数 JSON Schema
+    #[serde(default)]
+    pub parameters: JsonSchema,
+}
+
+/// 插件加载状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WasmPluginInfo {
+    /// 插件文件名（不含 `.wasm`），配置权限时使用
+    pub id: String,
+    pub path: String,
+    pub enabled: bool,
+    /// 加载成功时的清单
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<WasmPluginManifest>,
+    /// 加载失败原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 插件调用结果（`invoke` 导出函数返回）
+#[derive(Debug, Deserialize)]
+struct PluginOutput {
+    #[serde(default = "default_plugin_success")]
+    success: bool,
+    #[serde(default)]
+    output: String,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+fn default_plugin_success() -> bool {
+    true
+}
+
+#[derive(Debug)]
+enum InvokeError {
+    Timeout,
+    Failed(String),
+}
+
+struct PluginCtx {
+    wasi: WasiP1Ctx,
+    limits: StoreLimits,
+}
+
+struct Runtime {
+    engine: Engine,
+    linker: Linker<PluginCtx>,
+}
+
+/// 全局 WASM 运行时（首次使用时创建，并启动推进 epoch 的后台线程）
+fn runtime() -> Result<&'static Runtime, String> {
+    static RUNTIME: OnceCell<Runtime> = OnceCell::new();
+    RUNTIME.get_or_try_init(|| {
+        let mut config = EngineConfig::new();
+        config.epoch_interruption(true);
+        let engine = Engine::new(&config).map_err(|e| format!("创建 WASM 引擎失败: {}", e))?;
+        let mut linker = Linker::new(&engine);
+        preview1::add_to_linker_sync(&mut linker, |ctx: &mut PluginCtx| &mut ctx.wasi)
+            .map_err(|e| format!("注册 WASI 接口失败: {}", e))?;
+
+        let ticker = engine.clone();
+        std::thread::Builder::new()
+            .name("wasm-plugin-epoch".to_string())
+            .spawn(move || loop {
+                std::thread::sleep(EPOCH_TICK);
+                ticker.increment_epoch();
+            })
+            .map_err(|e| format!("启动 WASM 超时检测线程失败: {}", e))?;
+        Ok(Runtime { engine, linker })
+    })
+}
+
+/// 拆分 `i64` 返回值为 (指针, 长度)
+fn unpack(packed: i64) -> (usize, usize) {
+    let packed = packed as u64;
+    ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize)
+}
+
+/// 按权限创建沙箱并实例化插件
+fn instantiate(
+    module: &Module,
+    permissions: &WasmPluginPermissions,
+    timeout: Duration,
+) -> Result<(Store<PluginCtx>, Instance, MemoryOutputPipe), String> {
+    let runtime = runtime()?;
+    let stderr = MemoryOutputPipe::new(MAX_STDERR_BYTES);
+    let mut builder = WasiCtxBuilder::new();
+    builder.stderr(stderr.clone());
+    for (key, value) in &permissions.env {
+        builder.env(key, value);
+    }
+    for dir in &permissions.dirs {
+        let host = expand_tilde(&dir.host);
+        let guest = dir
+            .guest
+            .clone()
+            .unwrap_or_else(|| host.to_string_lossy().to_string());
+        let (dir_perms, file_perms) = if dir.writable {
+            (DirPerms::all(), FilePerms::all())
+        } else {
+            (DirPerms::READ, FilePerms::READ)
+        };
+        builder
+            .preopened_dir(&host, &guest, dir_perms, file_perms)
+            .map_err(|e| format!("无法映射目录 {}: {}", host.display(), e))?;
+    }
+
+    let max_memory = permissions
+        .max_memory_mb
+        .unwrap_or(DEFAULT_PLUGIN_MAX_MEMORY_MB) as usize
+        * 1024
+        * 1024;
+    let ctx = PluginCtx {
+        wasi: builder.build_p1(),
+        limits: StoreLimitsBuilder::new().memory_size(max_memory).build(),
+    };
+    let mut store = Store::new(&runtime.engine, ctx);
+    store.limiter(|ctx| &mut ctx.limits);
+    store.set_epoch_deadline((timeout.as_millis() / EPOCH_TICK.as_millis()).max(1) as u64);
+
+    let instance = runtime
+        .linker
+        .instantiate(&mut store, module)
+        .map_err(|e| format!("实例化插件失败: {}", e))?;
+    Ok((store, instance, stderr))
+}
+
+/// 读取插件内存中 `packed` 指向的数据
+fn read_packed(
+    store: &mut Store<PluginCtx>,
+    instance: &Instance,
+    packed: i64,
+) -> Result<Vec<u8>, String> {
+    let memory = instance
+        .get_memory(&mut *store, "memory")
+        .ok_or_else(|| "插件未导出 memory".to_string())?;
+    let (ptr, len) = unpack(packed);
+    if ptr.saturating_add(len) > memory.data_size(&*store) {
+        return Err("插件返回值超出内存范围".to_string());
+    }
+    let mut buffer = vec![0u8; len];
+    memory
+        .read(&*store, ptr, &mut buffer)
+        .map_err(|e| format!("读取插件返回值失败: {}", e))?;
+    Ok(buffer)
+}
+
+/// 转换调用错误，超时中断单独返回
+fn call_error(e: wasmtime::Error, stderr: &MemoryOutputPipe) -> InvokeError {
+    if matches!(e.downcast_ref::<Trap>(), Some(Trap::Interrupt)) {
+        return InvokeError::Timeout;
+    }
+    let stderr = String::from_utf8_lossy(&stderr.contents())
+        .trim()
+        .to_string();
+    if stderr.is_empty() {
+        InvokeError::Failed(format!("插件执行出错: {}", e))
+    } else {
+        InvokeError::Failed(format!("插件执行出错: {}\n{}", e, stderr))
+    }
+}
+
+/// 读取并校验插件清单（不授予任何权限）
+fn read_manifest(module: &Module) -> Result<WasmPluginManifest, String> {
+    let (mut store, instance, _stderr) =
+        instantiate(module, &WasmPluginPermissions::default(), MANIFEST_TIMEOUT)?;
+    let func = instance
+        .get_typed_func::<(), i64>(&mut store, "manifest")
+        .map_err(|e| format!("插件未导出 manifest 函数: {}", e))?;
+    let packed = func
+        .call(&mut store, ())
+        .map_err(|e| format!("调用 manifest 失败: {}", e))?;
+    let bytes = read_packed(&mut store, &instance, packed)?;
+    let manifest: WasmPluginManifest =
+        serde_json::from_slice(&bytes).map_err(|e| format!("插件清单格式无效: {}", e))?;
+
+    if !is_valid_tool_name(&manifest.name) {
+        return Err(format!(
+            "插件工具名称只能包含字母、数字、_ 和 -: {:?}",
+            manifest.name
+        ));
+    }
+    if RESERVED_NAMES.contains(&manifest.name.as_str()) {
+        return Err(format!("插件工具名称与内置工具重名: {}", manifest.name));
+    }
+    if manifest.description.trim().is_empty() {
+        return Err("插件工具描述不能为空".to_string());
+    }
+    manifest
+        .parameters
+        .validate()
+        .map_err(|e| format!("插件参数 schema 无效: {}", e))?;
+    Ok(manifest)
+}
+
+/// 已加载的插件
+pub struct WasmPlugin {
+    id: String,
+    manifest: WasmPluginManifest,
+    module: Module,
+    permissions: WasmPluginPermissions,
+}
+
+impl WasmPlugin {
+    /// 编译插件并读取清单（耗时操作）
+    fn load(id: &str, path: &Path, permissions: WasmPluginPermissions) -> Result<Self, String> {
+        let module = Module::from_file(&runtime()?.engine, path)
+            .map_err(|e| format!("编译插件失败: {}", e))?;
+        let manifest = read_manifest(&module)?;
+        Ok(Self {
+            id: id.to_string(),
+            manifest,
+            module,
+            permissions,
+        })
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.permissions
+                .timeout_secs
+                .unwrap_or(DEFAULT_PLUGIN_TIMEOUT_SECS),
+        )
+    }
+
+    /// 调用插件（阻塞执行）
+    fn invoke(&self, args: &serde_json::Value) -> Result<PluginOutput, InvokeError> {
+        let (mut store, instance, stderr) =
+            instantiate(&self.module, &self.permissions, self.timeout())
+                .map_err(InvokeError::Failed)?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&mut store, "alloc")
+            .map_err(|e| InvokeError::Failed(format!("插件未导出 alloc 函数: {}", e)))?;
+        let invoke = instance
+            .get_typed_func::<(i32, i32), i64>(&mut store, "invoke")
+            .map_err(|e| InvokeError::Failed(format!("插件未导出 invoke 函数: {}", e)))?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| InvokeError::Failed("插件未导出 memory".to_string()))?;
+
+        let input = serde_json::to_vec(args).map_err(|e| InvokeError::Failed(e.to_string()))?;
+        let len =
+            i32::try_from(input.len()).map_err(|_| InvokeError::Failed("参数过大".to_string()))?;
+        let ptr = alloc
+            .call(&mut store, len)
+            .map_err(|e| call_error(e, &stderr))?;
+        memory
+            .write(&mut store, ptr as u32 as usize, &input)
+            .map_err(|e| InvokeError::Failed(format!("写入插件参数失败: {}", e)))?;
+
+        let packed = invoke
+            .call(&mut store, (ptr, len))
+            .map_err(|e| call_error(e, &stderr))?;
+        let bytes = read_packed(&mut store, &instance, packed).map_err(InvokeError::Failed)?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| InvokeError::Failed(format!("插件返回值格式无效: {}", e)))
+    }
+}
+
+/// 列出目录中的 `.wasm` 文件（按文件名排序）
+fn scan_plugins(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "wasm"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// WASM 插件管理（克隆后共享同一份插件列表）
+#[derive(Clone, Default)]
+pub struct WasmPluginHost {
+    config: Arc<RwLock<WasmPluginsConfig>>,
+    plugins: Arc<RwLock<Vec<Arc<WasmPlugin>>>>,
+    infos: Arc<RwLock<Vec<WasmPluginInfo>>>,
+}
+
+impl WasmPluginHost {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 更新配置，返回配置是否变化（变化后需调用 [`Self::reload`]）
+    pub fn set_config(&self, config: WasmPluginsConfig) -> bool {
+        let mut current = self.config.write();
+        if *current == config {
+            return false;
+        }
+        *current = config;
+        true
+    }
+
+    /// 插件目录
+    pub fn plugins_dir(&self) -> Option<PathBuf> {
+        match &self.config.read().dir {
+            Some(dir) => Some(expand_tilde(dir)),
+            None => default_plugins_dir(),
+        }
+    }
+
+    /// 重新扫描插件目录并加载插件
+    ///
+    /// 编译 WASM 较耗时，需在阻塞线程中调用
+    pub fn reload(&self) -> Vec<WasmPluginInfo> {
+        let config = self.config.read().clone();
+        let mut plugins = Vec::new();
+        let mut infos = Vec::new();
+        let mut names = HashSet::new();
+
+        let paths = match self.plugins_dir() {
+            Some(dir) if config.enabled => scan_plugins(&dir),
+            _ => Vec::new(),
+        };
+        for path in paths {
+            let id = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let permissions = config.plugins.get(&id).cloned().unwrap_or_default();
+            let mut info = WasmPluginInfo {
+                id: id.clone(),
+                path: path.to_string_lossy().to_string(),
+                enabled: permissions.enabled,
+                manifest: None,
+                error: None,
+            };
+            if permissions.enabled {
+                match WasmPlugin::load(&id, &path, permissions) {
+                    Ok(plugin) if !names.insert(plugin.manifest.name.clone()) => {
+                        info.error = Some(format!("工具名称重复: {}", plugin.manifest.name));
+                    }
+                    Ok(plugin) => {
+                        info.manifest = Some(plugin.manifest.clone());
+                        plugins.push(Arc::new(plugin));
+                    }
+                    Err(e) => info.error = Some(e),
+                }
+            }
+            if let Some(error) = &info.error {
+                warn!("[WasmPlugin] 加载插件 {} 失败: {}", id, error);
+            }
+            infos.push(info);
+        }
+
+        info!("[WasmPlugin] 已加载 {} 个插件", plugins.len());
+        *self.plugins.write() = plugins;
+        *self.infos.write() = infos.clone();
+        infos
+    }
+
+    /// 最近一次加载的插件状态
+    pub fn list(&self) -> Vec<WasmPluginInfo> {
+        self.infos.read().clone()
+    }
+
+    /// 已加载插件对应的工具
+    pub fn tools(&self) -> Vec<WasmTool> {
+        self.plugins
+            .read()
+            .iter()
+            .map(|plugin| WasmTool {
+                plugin: plugin.clone(),
+            })
+            .collect()
+    }
+}
+
+/// WASM 插件提供的工具
+pub struct WasmTool {
+    plugin: Arc<WasmPlugin>,
+}
+
+#[async_trait]
+impl Tool for WasmTool {
+    fn definition(&self) -> ToolDefinition {
+        let manifest = &self.plugin.manifest;
+        ToolDefinition::new(&manifest.name, &manifest.description)
+            .with_parameters(manifest.parameters.clone())
+            .with_timeout(self.plugin.timeout().as_secs() + PLUGIN_TIMEOUT_GRACE_SECS)
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> Result<ToolResult, ToolError> {
+        info!(
+            "[WasmPlugin] 调用插件 {} ({})",
+            self.plugin.id, self.plugin.manifest.name
+        );
+        let plugin = self.plugin.clone();
+        let result = tokio::task::spawn_blocking(move || plugin.invoke(&args))
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("插件执行线程异常: {}", e)))?;
+
+        match result {
+            Ok(output) if output.success => Ok(ToolResult::success(output.output)),
+            Ok(output) => Ok(ToolResult::failure_with_output(
+                output.output,
+                output.error.unwrap_or_else(|| "插件返回失败".to_string()),
+            )),
+            Err(InvokeError::Timeout) => Err(ToolError::Timeout),
+            Err(InvokeError::Failed(e)) => Err(ToolError::ExecutionFailed(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 返回固定结果的最小插件
+    const PING_PLUGIN: &str = r#"(module
+        (memory (export "memory") 1)
+        (global $heap (mut i32) (i32.const 1024))
+        (data (i32.const 0) "{\"name\":\"ping\",\"description\":\"Reply with pong\",\"parameters\":{\"type\":\"object\",\"properties\":{}}}")
+        (data (i32.const 512) "{\"success\":true,\"output\":\"pong\"}")
+        (func (export "alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $heap))
+            (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+            (local.get $ptr))
+        (func (export "manifest") (result i64)
+            (i64.const 94))
+        (func (export "invoke") (param i32 i32) (result i64)
+            (i64.or (i64.shl (i64.const 512) (i64.const 32)) (i64.const 32))))"#;
+
+    #[test]
+    fn test_unpack() {
+        assert_eq!(unpack((512_i64 << 32) | 32), (512, 32));
+        assert_eq!(unpack(-1), (0xffff_ffff, 0xffff_ffff));
+    }
+
+    #[test]
+    fn test_load_and_invoke_plugin() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("ping.wasm"), PING_PLUGIN).unwrap();
+        std::fs::write(dir.path().join("broken.wasm"), "not a module").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let host = WasmPluginHost::new();
+        let mut config = WasmPluginsConfig {
+            dir: Some(dir.path().to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        assert!(host.set_config(config.clone()));
+        assert!(!host.set_config(config.clone()));
+
+        let infos = host.reload();
+        assert_eq!(infos.len(), 2);
+        assert_eq!(infos[0].id, "broken");
+        assert!(infos[0].error.is_some());
+        assert_eq!(infos[1].manifest.as_ref().unwrap().name, "ping");
+
+        let tools = host.tools();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].definition().name, "ping");
+        let output = tools[0].plugin.invoke(&serde_json::json!({})).unwrap();
+        assert!(output.success);
+        assert_eq!(output.output, "pong");
+
+        // 禁用的插件不加载
+        config.plugins.insert(
+            "ping".to_string(),
+            WasmPluginPermissions {
+                enabled: false,
+                ..Default::default()
+            },
+        );
+        assert!(host.set_config(config));
+        assert!(!host.reload()[1].enabled);
+        assert!(host.tools().is_empty());
+    }
+}
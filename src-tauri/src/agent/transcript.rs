@@ -0,0 +1,212 @@
+//! 会话记录（Transcript）
+//!
+//! 启用后，每条写入会话的用户、助手和工具消息都会追加到
+//! `~/.proxycast/transcripts/<session_id>.jsonl`（每行一条记录，含时间戳、模型和 token 用量），
+//! 便于审计和排查问题。会话删除后记录文件保留。
+
+use crate::agent::types::{AgentMessage, AttachmentInfo, TokenUsage, ToolCall};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tracing::warn;
+
+/// 会话记录中的一条消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptEntry {
+    /// 写入时间（RFC 3339）
+    pub timestamp: String,
+    /// 角色: user, assistant, tool
+    pub role: String,
+    /// 消息文本（图片、音频等二进制内容不记录）
+    pub content: String,
+    /// 处理本轮对话的模型（上游报告的快照或上下文回退后的模型；工具消息为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Token 使用量（assistant 消息）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attachments: Option<Vec<AttachmentInfo>>,
+}
+
+/// 会话记录器（默认关闭，克隆后共享开关）
+#[derive(Clone)]
+pub struct TranscriptLogger {
+    enabled: Arc<AtomicBool>,
+    dir: PathBuf,
+}
+
+impl Default for TranscriptLogger {
+    fn default() -> Self {
+        let dir = dirs::home_dir()
+            .unwrap_or_default()
+            .join(".proxycast")
+            .join("transcripts");
+        Self::new(dir)
+    }
+}
+
+impl TranscriptLogger {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            enabled: Arc::new(AtomicBool::new(false)),
+            dir,
+        }
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// 会话记录文件路径（会话 ID 只允许字母、数字、`-` 和 `_`）
+    pub fn path_for(&self, session_id: &str) -> Result<PathBuf, String> {
+        let valid = !session_id.is_empty()
+            && session_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(format!("无效的会话 ID: {}", session_id));
+        }
+        Ok(self.dir.join(format!("{}.jsonl", session_id)))
+    }
+
+    /// 追加一条消息（未启用时忽略，写入失败只记录警告）
+    pub fn record(
+        &self,
+        session_id: &str,
+        message: &AgentMessage,
+        model: Option<&str>,
+        usage: Option<&TokenUsage>,
+    ) {
+        if !self.is_enabled() {
+            return;
+        }
+        let entry = TranscriptEntry {
+            timestamp: message.timestamp.clone(),
+            role: message.role.clone(),
+            content: message.content.as_text(),
+            model: model.map(str::to_string),
+            usage: usage.cloned(),
+            tool_calls: message.tool_calls.clone(),
+            tool_call_id: message.tool_call_id.clone(),
+            attachments: message.attachments.clone(),
+        };
+        if let Err(e) = self.append(session_id, &entry) {
+            warn!(
+                "[Transcript] 写入会话记录失败: session={}, {}",
+                session_id, e
+            );
+        }
+    }
+
+    fn append(&self, session_id: &str, entry: &TranscriptEntry) -> Result<(), String> {
+        let path = self.path_for(session_id)?;
+        std::fs::create_dir_all(&self.dir).map_err(|e| format!("创建记录目录失败: {}", e))?;
+        let line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("打开记录文件失败: {}", e))?;
+        writeln!(file, "{}", line).map_err(|e| format!("写入记录文件失败: {}", e))
+    }
+
+    /// 读取会话记录（跳过无法解析的行）
+    pub fn read(&self, session_id: &str) -> Result<Vec<TranscriptEntry>, String> {
+        let path = self.path_for(session_id)?;
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let content =
+            std::fs::read_to_string(&path).map_err(|e| format!("读取记录文件失败: {}", e))?;
+        Ok(content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// 导出会话记录到指定路径，返回导出的记录数
+    pub fn export(&self, session_id: &str, dest: &Path) -> Result<usize, String> {
+        let path = self.path_for(session_id)?;
+        if !path.exists() {
+            return Err(format!("会话没有记录: {}", session_id));
+        }
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("创建导出目录失败: {}", e))?;
+        }
+        std::fs::copy(&path, dest).map_err(|e| format!("导出会话记录失败: {}", e))?;
+        Ok(self.read(session_id)?.len())
+    }
+
+    /// 默认导出路径：`~/.proxycast/transcripts/exports/<session_id>-<时间>.jsonl`
+    pub fn default_export_path(&self, session_id: &str) -> PathBuf {
+        self.dir.join("exports").join(format!(
+            "{}-{}.jsonl",
+            session_id,
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::test_support::text_message;
+
+    fn message(role: &str, text: &str) -> AgentMessage {
+        AgentMessage {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            ..text_message(role, text)
+        }
+    }
+
+    #[test]
+    fn test_record_only_when_enabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let logger = TranscriptLogger::new(dir.path().to_path_buf());
+
+        logger.record("s1", &message("user", "ignored"), Some("gpt-4"), None);
+        assert!(logger.read("s1").unwrap().is_empty());
+
+        logger.set_enabled(true);
+        let usage = TokenUsage {
+            input_tokens: 10,
+            output_tokens: 5,
+        };
+        logger.record("s1", &message("user", "hello"), Some("gpt-4"), None);
+        logger.record(
+            "s1",
+            &message("assistant", "hi"),
+            Some("gpt-4"),
+            Some(&usage),
+        );
+
+        let entries = logger.read("s1").unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].content, "hello");
+        assert_eq!(entries[1].usage, Some(usage));
+
+        let dest = dir.path().join("out").join("s1.jsonl");
+        assert_eq!(logger.export("s1", &dest).unwrap(), 2);
+        assert!(dest.exists());
+    }
+
+    #[test]
+    fn test_rejects_path_traversal() {
+        let logger = TranscriptLogger::new(PathBuf::from("/tmp/transcripts"));
+        assert!(logger.path_for("../etc/passwd").is_err());
+        assert!(logger.path_for("").is_err());
+        assert!(logger.path_for("0b6f-4c1a_x").is_ok());
+    }
+}
@@ -3,6 +3,8 @@
 //! 定义 Agent 模块使用的核心类型
 //! 参考 goose 项目的 Conversation 设计，支持连续对话和工具调用
 
+use crate::agent::errors::ProviderErrorDetail;
+use crate::agent::model_pin::{ModelChange, ModelPin};
 use serde::{Deserialize, Serialize};
 
 /// Provider 类型枚举
@@ -49,6 +51,21 @@ impl ProviderType {
         }
     }
 
+    /// 名称（与 [`ProviderType::from_str`] 对应）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Claude => "claude",
+            Self::ClaudeOauth => "claude_oauth",
+            Self::Kiro => "kiro",
+            Self::Gemini => "gemini",
+            Self::OpenAI => "openai",
+            Self::Qwen => "qwen",
+            Self::Codex => "codex",
+            Self::Antigravity => "antigravity",
+            Self::IFlow => "iflow",
+        }
+    }
+
     /// 获取 API 端点路径
     pub fn endpoint(&self) -> &'static str {
         match self {
@@ -84,6 +101,42 @@ pub struct AgentSession {
     pub messages: Vec<AgentMessage>,
     /// 系统提示词
     pub system_prompt: Option<String>,
+    /// 会话首轮注入的长期记忆（发送请求时追加在系统提示词之后，不展开变量）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_prompt: Option<String>,
+    /// 关联的知识库集合（每轮对话前检索并注入相关内容）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub knowledge_collection: Option<String>,
+    /// 固定的模型快照（见 [`crate::agent::model_pin`]）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_pin: Option<ModelPin>,
+    /// 模型快照变化记录
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub model_changes: Vec<ModelChange>,
+    /// 会话标签（用于分组和批量操作）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// 使用的 Provider 配置档案（为空时使用当前激活的档案）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// 所在文件夹（为空时位于根目录）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+    /// 关联的本地 git 仓库（设置后注册 git 工具）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo_path: Option<String>,
+    /// `http_request` 工具允许访问的域名（为空时不注册该工具）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub http_domains: Vec<String>,
+    /// 工具权限模式（为空时使用全局配置的 `permission_mode`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permission_mode: Option<crate::config::PermissionMode>,
+    /// 是否已归档（归档的会话保留在内存中，默认不在会话列表中显示）
+    #[serde(default)]
+    pub archived: bool,
+    /// 是否置顶
+    #[serde(default)]
+    pub pinned: bool,
     /// 创建时间
     pub created_at: String,
     /// 最后活动时间
@@ -105,6 +158,12 @@ pub struct AgentMessage {
     /// 工具调用 ID（tool 角色消息需要）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_call_id: Option<String>,
+    /// 附件元数据（user 消息的附件文本已拼接在 content 中；tool 消息为工具生成的文件）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attachments: Option<Vec<AttachmentInfo>>,
+    /// 是否为被用户中断的不完整回复（assistant 消息）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub truncated: Option<bool>,
 }
 
 /// 消息内容类型
@@ -142,6 +201,15 @@ pub enum ContentPart {
     Text { text: String },
     /// 图片 URL
     ImageUrl { image_url: ImageUrl },
+    /// 音频输入（base64 编码，format 为 wav 或 mp3）
+    InputAudio { data: String, format: String },
+    /// 文档（base64 编码的 PDF，以原生文档块发送）
+    Document {
+        data: String,
+        media_type: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    },
 }
 
 /// 图片 URL
@@ -205,6 +273,9 @@ pub struct AgentConfig {
     pub temperature: Option<f32>,
     /// 最大 token 数
     pub max_tokens: Option<u32>,
+    /// Top-p 采样参数
+    #[serde(default)]
+    pub top_p: Option<f32>,
     /// 可用工具
     pub tools: Vec<ToolDefinition>,
 }
@@ -216,6 +287,7 @@ impl Default for AgentConfig {
             system_prompt: None,
             temperature: Some(0.7),
             max_tokens: Some(4096),
+            top_p: None,
             tools: Vec::new(),
         }
     }
@@ -232,14 +304,115 @@ pub struct NativeChatRequest {
     pub model: Option<String>,
     /// 图片列表（可选）
     pub images: Option<Vec<ImageData>>,
+    /// 音频列表（可选，需要支持音频输入的模型）
+    #[serde(default)]
+    pub audio: Option<Vec<AudioData>>,
+    /// 文件附件列表（可选，PDF、文本、CSV 等）
+    #[serde(default)]
+    pub attachments: Option<Vec<AttachmentData>>,
+    /// 本轮检索到的参考资料（只附加到发送的请求中，不写入会话历史）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
     /// 是否流式响应
     pub stream: bool,
 }
 
-/// 图片数据
+/// 文件附件（path 与 data 二选一）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentData {
+    /// 文件名
+    pub name: String,
+    /// 本地文件路径
+    #[serde(default)]
+    pub path: Option<String>,
+    /// base64 编码的文件内容
+    #[serde(default)]
+    pub data: Option<String>,
+    /// MIME 类型（缺省时按扩展名推断）
+    #[serde(default)]
+    pub media_type: Option<String>,
+    /// 是否以原生文档块发送（仅 PDF；缺省时按模型是否支持自动选择，false 强制提取文本）
+    #[serde(default)]
+    pub native: Option<bool>,
+}
+
+/// 附件元数据（保存在消息上）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttachmentInfo {
+    /// 文件名
+    pub name: String,
+    /// MIME 类型
+    pub media_type: String,
+    /// 原始文件大小（字节）
+    pub size: u64,
+    /// 注入上下文的 token 数
+    pub tokens: u32,
+    /// 是否因超出 token 预算被截断
+    pub truncated: bool,
+    /// 本地文件路径（工具生成的文件）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// 图片数据（data 与 path 二选一，传 path 时由后端读取并编码）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImageData {
     /// base64 编码的图片数据
+    #[serde(default)]
+    pub data: String,
+    /// MIME 类型
+    #[serde(default)]
+    pub media_type: String,
+    /// 本地图片路径
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// 识别精度（未设置时由 Provider 决定）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<ImageDetail>,
+}
+
+/// 图片识别精度（OpenAI vision 的 `detail` 参数）
+///
+/// `low` 固定按低分辨率处理，token 消耗最少；`high` 按图片分块计费；
+/// Anthropic 等不支持该参数的协议会忽略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageDetail {
+    Low,
+    High,
+    Auto,
+}
+
+impl ImageDetail {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::High => "high",
+            Self::Auto => "auto",
+        }
+    }
+}
+
+/// 音频数据（data 与 path 二选一，传 path 时由后端读取并编码）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioData {
+    /// base64 编码的音频数据
+    #[serde(default)]
+    pub data: String,
+    /// 音频格式（wav 或 mp3，按文件头识别）
+    #[serde(default)]
+    pub format: String,
+    /// 本地音频路径
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// 文档数据（以原生文档块发送的附件）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentData {
+    /// 文件名
+    pub name: String,
+    /// base64 编码的文件内容
     pub data: String,
     /// MIME 类型
     pub media_type: String,
@@ -258,6 +431,78 @@ pub struct NativeChatResponse {
     pub success: bool,
     /// 错误信息
     pub error: Option<String>,
+    /// 翻译后的 Provider 错误（错误码与建议操作）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_detail: Option<ProviderErrorDetail>,
+    /// 实际处理请求的端点名称（发生故障转移时为备用端点）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub served_by: Option<String>,
+    /// 提示词超过上下文窗口时改用的模型
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_fallback: Option<crate::agent::context_fallback::ContextFallback>,
+}
+
+/// 回放对话轮次时的参数覆盖（未设置的字段沿用原会话配置）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplayOverrides {
+    /// 模型
+    #[serde(default)]
+    pub model: Option<String>,
+    /// 温度
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// 系统提示词
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+}
+
+/// 对话轮次回放结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayResult {
+    /// 会话 ID
+    pub session_id: String,
+    /// 回放的轮次（第几条用户消息，从 0 开始）
+    pub turn_id: usize,
+    /// 使用的模型
+    pub model: String,
+    /// 原始回答
+    pub original: Option<String>,
+    /// 回放得到的回答
+    pub content: String,
+    /// Token 使用量
+    pub usage: Option<TokenUsage>,
+    /// 实际处理请求的端点名称
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub served_by: Option<String>,
+}
+
+/// 请求预览（按实际发送流程组装但不发送）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestPreview {
+    /// 请求发往的接口路径（`/v1/chat/completions` 或 `/v1/messages`）
+    pub endpoint: String,
+    /// 将发送给模型的请求体（按 Provider 协议组装，含系统提示词、历史消息、本次输入和工具定义）
+    pub request: serde_json::Value,
+    /// 估算的提示词 token 数（系统提示词、历史消息和本次输入）
+    pub estimated_tokens: u32,
+    /// 模型的上下文窗口（未知模型为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<u32>,
+    /// 上下文超限时本次请求将改用的模型
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_fallback: Option<crate::agent::context_fallback::ContextFallback>,
+}
+
+/// 图片生成结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageGenerationResult {
+    /// 保存到本地的图片路径
+    pub path: String,
+    /// 模型改写后的提示词（部分模型返回）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revised_prompt: Option<String>,
+    /// 使用的模型
+    pub model: String,
 }
 
 /// Token 使用量
@@ -330,12 +575,126 @@ pub enum StreamEvent {
     /// 最终完成（整个对话完成，包括所有工具调用循环）
     /// 前端收到此事件后才能取消监听
     #[serde(rename = "final_done")]
-    FinalDone { usage: Option<TokenUsage> },
+    FinalDone {
+        usage: Option<TokenUsage>,
+        /// 实际处理请求的端点名称（发生故障转移时为备用端点）
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        served_by: Option<String>,
+    },
 
     /// 错误
     /// Requirements: 1.4 - IF a streaming error occurs, THEN THE Streaming_Handler SHALL emit an error event
     #[serde(rename = "error")]
-    Error { message: String },
+    Error {
+        message: String,
+        /// 翻译后的 Provider 错误（错误码与建议操作）
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<ProviderErrorDetail>,
+    },
+
+    /// 会话固定的模型快照发生变化（路由解析到了不同的快照）
+    #[serde(rename = "model_drift")]
+    ModelDrift {
+        /// 请求的模型
+        requested: String,
+        /// 会话固定的快照
+        pinned: String,
+        /// 变化前的模型
+        from: String,
+        /// 变化后的模型
+        to: String,
+    },
+
+    /// 提示词超过会话模型的上下文窗口，本次请求改用更大上下文的模型
+    #[serde(rename = "context_fallback")]
+    ContextFallback {
+        /// 会话模型
+        from: String,
+        /// 本次请求实际使用的模型
+        to: String,
+        /// 估算的提示词 token 数
+        estimated_tokens: u32,
+        /// 会话模型的上下文窗口
+        context_window: u32,
+    },
+
+    /// 上游返回上下文超长错误，已丢弃最早的对话轮次并自动重试
+    #[serde(rename = "context_compacted")]
+    ContextCompacted {
+        /// 本次请求中省略的最早消息数（会话中保存的历史不变）
+        removed_messages: u32,
+        /// 压缩后估算的提示词 token 数
+        estimated_tokens: u32,
+    },
+
+    /// 本次请求的上下文用量（每次请求模型前推送，用于显示上下文占用）
+    #[serde(rename = "context_usage")]
+    ContextUsage {
+        /// 估算的提示词 token 数（系统提示词、历史消息和本次输入）
+        used_tokens: u32,
+        /// 本次请求所用模型的上下文窗口
+        max_tokens: u32,
+    },
+
+    /// 已取消（用户中断生成，之后不会再有事件）
+    #[serde(rename = "cancelled")]
+    Cancelled {
+        /// 部分回复的处理方式
+        mode: CancelMode,
+    },
+}
+
+/// 中断生成时部分回复的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CancelMode {
+    /// 保留已生成的内容并写入历史（标记为 truncated）
+    Keep,
+    /// 丢弃本轮对话（用户消息和部分回复都不写入历史）
+    Discard,
+}
+
+/// 带流 ID 的流式事件
+///
+/// 推送给前端的每个事件都带有所属流式对话的 ID，同时进行的多个对话不会互相混淆
+#[derive(Debug, Clone, Serialize)]
+pub struct TaggedStreamEvent {
+    pub stream_id: String,
+    #[serde(flatten)]
+    pub event: StreamEvent,
+}
+
+/// 进行中的流式对话
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamInfo {
+    pub stream_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// 开始时间
+    pub started_at: String,
+}
+
+impl StreamInfo {
+    pub fn new(stream_id: &str, session_id: Option<String>, model: Option<String>) -> Self {
+        Self {
+            stream_id: stream_id.to_string(),
+            session_id,
+            model,
+            started_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// 工具调用被中断的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolInterruption {
+    /// 超过工具的执行超时时间
+    Timeout,
+    /// 用户取消
+    Cancelled,
 }
 
 /// 工具执行结果（用于 StreamEvent）
@@ -350,6 +709,12 @@ pub struct ToolExecutionResult {
     /// 错误信息（如果失败）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// 超时或被取消时的中断原因
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interrupted: Option<ToolInterruption>,
+    /// 工具生成的文件
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<AttachmentInfo>,
 }
 
 impl ToolExecutionResult {
@@ -359,6 +724,8 @@ impl ToolExecutionResult {
             success: true,
             output: output.into(),
             error: None,
+            interrupted: None,
+            artifacts: Vec::new(),
         }
     }
 
@@ -369,6 +736,8 @@ impl ToolExecutionResult {
             success: false,
             output: String::new(),
             error: Some(error_msg),
+            interrupted: None,
+            artifacts: Vec::new(),
         }
     }
 
@@ -378,6 +747,8 @@ impl ToolExecutionResult {
             success: false,
             output: output.into(),
             error: Some(error.into()),
+            interrupted: None,
+            artifacts: Vec::new(),
         }
     }
 }
@@ -396,6 +767,15 @@ pub struct StreamResult {
     /// Token 使用量
     #[serde(skip_serializing_if = "Option::is_none")]
     pub usage: Option<TokenUsage>,
+    /// 实际处理请求的端点名称（发生故障转移时为备用端点）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub served_by: Option<String>,
+    /// 上游响应中报告的模型（通常是确切的快照版本）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// 提示词超过上下文窗口时改用的模型
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_fallback: Option<crate::agent::context_fallback::ContextFallback>,
 }
 
 impl StreamResult {
@@ -405,6 +785,9 @@ impl StreamResult {
             content,
             tool_calls: None,
             usage: None,
+            served_by: None,
+            model: None,
+            context_fallback: None,
         }
     }
 
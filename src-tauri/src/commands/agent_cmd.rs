@@ -2,10 +2,23 @@
 //!
 //! 提供原生 Agent 的 Tauri 命令（兼容旧 API）
 
-use crate::agent::{ImageData, NativeAgentState, NativeChatRequest, ProviderType};
+use crate::agent::background::{run_background_task, BackgroundTask};
+use crate::agent::batch::{
+    self, BatchOutputFormat, BatchProgress, BatchRunResult, BATCH_PROGRESS_EVENT,
+};
+use crate::agent::jobs;
+use crate::agent::quick_ask::QuickAskResponse;
+use crate::agent::session_meta;
+use crate::agent::skill_draft;
+use crate::agent::{
+    AgentJob, CancelMode, ImageData, ImageDetail, JobEvent, ModelChange, ModelPin,
+    NativeAgentState, NativeChatRequest, SessionFilter, ToolLoopEngine,
+};
+use crate::commands::native_agent_cmd::ensure_agent_initialized;
 use crate::AppState;
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use tauri::ipc::Channel;
+use tauri::{Emitter, State};
 
 /// Agent 进程状态响应
 #[derive(Debug, Serialize)]
@@ -34,25 +47,8 @@ pub async fn agent_start_process(
 ) -> Result<AgentProcessStatus, String> {
     tracing::info!("[Agent] 初始化原生 Agent");
 
-    let (port, api_key, running, default_provider) = {
-        let state = app_state.read().await;
-        (
-            state.config.server.port,
-            state.running_api_key.clone(),
-            state.running,
-            state.config.routing.default_provider.clone(),
-        )
-    };
-
-    if !running {
-        return Err("ProxyCast API Server 未运行，请先启动服务器".to_string());
-    }
-
-    let api_key = api_key.ok_or_else(|| "ProxyCast API Server 未配置 API Key".to_string())?;
-    let base_url = format!("http://127.0.0.1:{}", port);
-    let provider_type = ProviderType::from_str(&default_provider);
-
-    agent_state.init(base_url.clone(), api_key, provider_type)?;
+    let base_url = ensure_agent_initialized(agent_state.inner(), app_state.inner(), false).await?;
+    let port = app_state.read().await.config.server.port;
 
     Ok(AgentProcessStatus {
         running: true,
@@ -81,7 +77,7 @@ pub async fn agent_get_process_status(
         let state = app_state.read().await;
         Ok(AgentProcessStatus {
             running: true,
-            base_url: Some(format!("http://127.0.0.1:{}", state.config.server.port)),
+            base_url: Some(state.local_base_url()),
             port: Some(state.config.server.port),
         })
     } else {
@@ -94,7 +90,7 @@ pub async fn agent_get_process_status(
 }
 
 /// Skill 信息
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SkillInfo {
     pub name: String,
     pub description: Option<String>,
@@ -118,27 +114,7 @@ pub async fn agent_create_session(
         skills.as_ref().map(|s| s.len())
     );
 
-    // 如果未初始化，自动初始化
-    if !agent_state.is_initialized() {
-        let (port, api_key, running, default_provider) = {
-            let state = app_state.read().await;
-            (
-                state.config.server.port,
-                state.running_api_key.clone(),
-                state.running,
-                state.config.routing.default_provider.clone(),
-            )
-        };
-
-        if !running {
-            return Err("ProxyCast API Server 未运行".to_string());
-        }
-
-        let api_key = api_key.ok_or_else(|| "未配置 API Key".to_string())?;
-        let base_url = format!("http://127.0.0.1:{}", port);
-        let provider_type = ProviderType::from_str(&default_provider);
-        agent_state.init(base_url, api_key, provider_type)?;
-    }
+    ensure_agent_initialized(agent_state.inner(), app_state.inner(), false).await?;
 
     // 构建包含 Skills 的 System Prompt
     let final_system_prompt = build_system_prompt_with_skills(system_prompt, skills.as_ref());
@@ -163,15 +139,7 @@ fn build_system_prompt_with_skills(
         Some(skills) if !skills.is_empty() => {
             let mut xml = String::from("<available_skills>\n");
             for skill in skills {
-                xml.push_str("  <skill>\n");
-                xml.push_str(&format!("    <name>{}</name>\n", skill.name));
-                if let Some(desc) = &skill.description {
-                    xml.push_str(&format!("    <description>{}</description>\n", desc));
-                }
-                if let Some(path) = &skill.path {
-                    xml.push_str(&format!("    <location>{}</location>\n", path));
-                }
-                xml.push_str("  </skill>\n");
+                xml.push_str(&skill_xml_entry(skill));
             }
             xml.push_str("</available_skills>\n\n");
             xml.push_str("当用户的请求匹配某个 Skill 的描述时，请使用该 Skill 来完成任务。\n");
@@ -189,11 +157,101 @@ fn build_system_prompt_with_skills(
     }
 }
 
+/// 单个 Skill 的 `<skill>` 条目
+fn skill_xml_entry(skill: &SkillInfo) -> String {
+    let mut xml = String::from("  <skill>\n");
+    xml.push_str(&format!("    <name>{}</name>\n", skill.name));
+    if let Some(desc) = &skill.description {
+        xml.push_str(&format!("    <description>{}</description>\n", desc));
+    }
+    if let Some(path) = &skill.path {
+        xml.push_str(&format!("    <location>{}</location>\n", path));
+    }
+    xml.push_str("  </skill>\n");
+    xml
+}
+
+/// 将新 Skill 加入系统提示词的 `<available_skills>` 列表（没有列表时新建）
+fn add_skill_to_prompt(prompt: Option<String>, skill: &SkillInfo) -> Option<String> {
+    match prompt {
+        Some(prompt) if prompt.contains("</available_skills>") => Some(prompt.replacen(
+            "</available_skills>",
+            &format!("{}</available_skills>", skill_xml_entry(skill)),
+            1,
+        )),
+        prompt => build_system_prompt_with_skills(prompt, Some(&vec![skill.clone()])),
+    }
+}
+
+/// 从会话生成 Skill
+///
+/// 使用后台模型把会话提炼为 SKILL.md，写入 `~/.proxycast/skills/<name>/`，
+/// 并把新 Skill 加入该会话的 Skills 提示词（新会话会在创建时自动包含）。
+#[tauri::command]
+pub async fn skill_create_from_session(
+    agent_state: State<'_, NativeAgentState>,
+    app_state: State<'_, AppState>,
+    session_id: String,
+    name: String,
+) -> Result<SkillInfo, String> {
+    let name = name.trim().to_string();
+    skill_draft::validate_skill_name(&name)?;
+    let skills_dir = crate::agent::tools::skill_script::skills_dir()
+        .ok_or_else(|| "无法获取用户目录".to_string())?;
+    if skills_dir.join(&name).exists() {
+        return Err(format!("Skill 已存在: {}", name));
+    }
+
+    let messages = agent_state
+        .get_session_messages(&session_id)
+        .ok_or_else(|| format!("会话不存在: {}", session_id))?;
+    let config = app_state.read().await.config.background_model.clone();
+    let output = run_background_task(
+        agent_state.inner(),
+        &config,
+        BackgroundTask::CreateSkill,
+        &messages,
+    )
+    .await?;
+
+    let draft = skill_draft::normalize_skill_markdown(&output, &name)?;
+    let path = skill_draft::write_skill(&skills_dir, &draft)?;
+    tracing::info!(
+        "[Agent] 从会话 {} 生成 Skill: {}",
+        session_id,
+        path.display()
+    );
+
+    let skill = SkillInfo {
+        name: draft.name,
+        description: Some(draft.description),
+        path: Some(path.to_string_lossy().into_owned()),
+    };
+    let updated = agent_state
+        .update_session_system_prompt(&session_id, |prompt| add_skill_to_prompt(prompt, &skill));
+    if !updated {
+        tracing::warn!(
+            "[Agent] 会话 {} 已不存在，Skill {} 未加入会话系统提示词",
+            session_id,
+            skill.name
+        );
+    }
+    Ok(skill)
+}
+
 /// 图片输入参数
 #[derive(Debug, Deserialize)]
 pub struct ImageInputParam {
+    #[serde(default)]
     pub data: String,
+    #[serde(default)]
     pub media_type: String,
+    /// 本地图片路径（传入时由后端读取并编码）
+    #[serde(default)]
+    pub path: Option<String>,
+    /// 识别精度: low, high, auto
+    #[serde(default)]
+    pub detail: Option<ImageDetail>,
 }
 
 /// 发送消息到 Agent
@@ -224,27 +282,7 @@ pub async fn agent_send_message(
         thinking
     );
 
-    // 如果未初始化，自动初始化
-    if !agent_state.is_initialized() {
-        let (port, api_key, running, default_provider) = {
-            let state = app_state.read().await;
-            (
-                state.config.server.port,
-                state.running_api_key.clone(),
-                state.running,
-                state.config.routing.default_provider.clone(),
-            )
-        };
-
-        if !running {
-            return Err("ProxyCast API Server 未运行".to_string());
-        }
-
-        let api_key = api_key.ok_or_else(|| "未配置 API Key".to_string())?;
-        let base_url = format!("http://127.0.0.1:{}", port);
-        let provider_type = ProviderType::from_str(&default_provider);
-        agent_state.init(base_url, api_key, provider_type)?;
-    }
+    ensure_agent_initialized(agent_state.inner(), app_state.inner(), false).await?;
 
     // 根据启用的模式构建最终消息
     let web_search_enabled = web_search.unwrap_or(false);
@@ -275,9 +313,14 @@ pub async fn agent_send_message(
                 .map(|img| ImageData {
                     data: img.data,
                     media_type: img.media_type,
+                    path: img.path,
+                    detail: img.detail,
                 })
                 .collect()
         }),
+        audio: None,
+        attachments: None,
+        context: None,
         stream: false,
     };
 
@@ -299,14 +342,44 @@ pub struct SessionInfo {
     pub created_at: String,
     pub last_activity: String,
     pub messages_count: usize,
+    /// 固定的模型快照
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_pin: Option<ModelPin>,
+    /// 模型快照变化记录
+    #[serde(default)]
+    pub model_changes: Vec<ModelChange>,
+    /// 会话标签
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 使用的 Provider 配置档案
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// 所在文件夹
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+    /// 关联的 git 仓库
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo_path: Option<String>,
+    /// `http_request` 工具允许访问的域名
+    #[serde(default)]
+    pub http_domains: Vec<String>,
+    /// 工具权限模式（为空时使用全局配置）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permission_mode: Option<crate::config::PermissionMode>,
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub pinned: bool,
 }
 
-/// 获取会话列表
+/// 获取会话列表（按标签、文件夹、归档和置顶状态筛选，默认不含已归档的会话，置顶的会话在前）
 #[tauri::command]
 pub async fn agent_list_sessions(
     agent_state: State<'_, NativeAgentState>,
+    filter: Option<SessionFilter>,
 ) -> Result<Vec<SessionInfo>, String> {
     let sessions = agent_state.list_sessions();
+    let sessions = session_meta::filter_sessions(sessions, &filter.unwrap_or_default());
 
     Ok(sessions
         .into_iter()
@@ -317,6 +390,16 @@ pub async fn agent_list_sessions(
             created_at: s.created_at.clone(),
             last_activity: s.created_at,
             messages_count: s.messages.len(),
+            model_pin: s.model_pin,
+            model_changes: s.model_changes,
+            tags: s.tags,
+            profile: s.profile,
+            folder: s.folder,
+            repo_path: s.repo_path,
+            http_domains: s.http_domains,
+            permission_mode: s.permission_mode,
+            archived: s.archived,
+            pinned: s.pinned,
         })
         .collect())
 }
@@ -338,6 +421,16 @@ pub async fn agent_get_session(
         created_at: session.created_at.clone(),
         last_activity: session.created_at,
         messages_count: session.messages.len(),
+        model_pin: session.model_pin,
+        model_changes: session.model_changes,
+        tags: session.tags,
+        profile: session.profile,
+        folder: session.folder,
+        repo_path: session.repo_path,
+        http_domains: session.http_domains,
+        permission_mode: session.permission_mode,
+        archived: session.archived,
+        pinned: session.pinned,
     })
 }
 
@@ -353,3 +446,198 @@ pub async fn agent_delete_session(
         Err("会话不存在".to_string())
     }
 }
+
+/// 提交后台任务，立即返回任务（进度通过 `job://{id}` 事件推送）
+///
+/// 未指定会话时新建一个会话执行任务，完成后可在会话列表中查看。
+/// 传入 `on_event` 时同样的事件也推送到该 Channel，提交方不会错过任务开始后的事件
+#[tauri::command]
+pub async fn agent_submit_task(
+    app_handle: tauri::AppHandle,
+    agent_state: State<'_, NativeAgentState>,
+    app_state: State<'_, AppState>,
+    message: String,
+    session_id: Option<String>,
+    model: Option<String>,
+    on_event: Option<Channel<JobEvent>>,
+) -> Result<AgentJob, String> {
+    if message.trim().is_empty() {
+        return Err("任务内容不能为空".to_string());
+    }
+    ensure_agent_initialized(agent_state.inner(), app_state.inner(), false).await?;
+
+    let session_id = match session_id {
+        Some(id) => {
+            agent_state
+                .get_session(&id)?
+                .ok_or_else(|| "会话不存在".to_string())?;
+            id
+        }
+        None => agent_state.create_session(model.clone(), None)?,
+    };
+    let engine = ToolLoopEngine::new(agent_state.get_tool_registry(Some(&session_id))?)
+        .with_cancellations(agent_state.tool_cancellations().clone(), Some(&session_id));
+    let job = agent_state.jobs().create(&session_id, &message, model)?;
+    tracing::info!(
+        "[Agent] 提交后台任务: id={}, session={}",
+        job.id,
+        session_id
+    );
+    jobs::spawn_job(
+        app_handle,
+        agent_state.inner().clone(),
+        job.clone(),
+        engine,
+        on_event,
+    );
+    Ok(job)
+}
+
+/// 列出后台任务（按创建时间倒序）
+#[tauri::command]
+pub fn agent_list_tasks(agent_state: State<'_, NativeAgentState>) -> Vec<AgentJob> {
+    agent_state.jobs().list()
+}
+
+/// 获取后台任务详情（运行中的任务包含部分输出）
+#[tauri::command]
+pub fn agent_get_task(
+    agent_state: State<'_, NativeAgentState>,
+    id: String,
+) -> Result<AgentJob, String> {
+    agent_state
+        .jobs()
+        .get(&id)
+        .ok_or_else(|| format!("后台任务不存在: {}", id))
+}
+
+/// 取消运行中的后台任务，返回是否找到运行中的任务
+///
+/// `mode` 默认为 `keep`：部分回复写入会话历史并标记为 truncated
+#[tauri::command]
+pub fn agent_cancel_task(
+    agent_state: State<'_, NativeAgentState>,
+    id: String,
+    mode: Option<CancelMode>,
+) -> bool {
+    jobs::cancel_job(&agent_state, &id, mode.unwrap_or(CancelMode::Keep))
+}
+
+/// 批量提示词：对每行输入渲染模板并以有限并发执行，结果写入 CSV 或 JSONL 文件
+///
+/// 输入通过 `inputs`（对象或字符串数组）或 `input_path`（CSV/JSON/JSONL 文件）提供；
+/// `output_path` 以 `.csv` 结尾时写 CSV，否则写 JSONL，未指定时写入 `~/.proxycast/batch`。
+/// 每完成一项推送一次 `agent-batch-progress` 事件。
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn agent_batch_run(
+    app_handle: tauri::AppHandle,
+    agent_state: State<'_, NativeAgentState>,
+    app_state: State<'_, AppState>,
+    template: String,
+    inputs: Option<Vec<serde_json::Value>>,
+    input_path: Option<String>,
+    model: Option<String>,
+    concurrency: Option<usize>,
+    output_path: Option<String>,
+) -> Result<BatchRunResult, String> {
+    let rows = match (inputs, input_path) {
+        (Some(inputs), _) => inputs.iter().map(batch::row_from_value).collect(),
+        (None, Some(path)) => batch::load_input_file(std::path::Path::new(&path))?,
+        (None, None) => return Err("需要提供 inputs 或 input_path".to_string()),
+    };
+    batch::validate_batch(&rows, &template)?;
+    ensure_agent_initialized(agent_state.inner(), app_state.inner(), false).await?;
+
+    let batch_id = format!("batch-{}", chrono::Local::now().format("%Y%m%d-%H%M%S-%3f"));
+    let output_path = match output_path {
+        Some(path) => std::path::PathBuf::from(path),
+        None => batch::default_output_path(&batch_id)?,
+    };
+    let total = rows.len();
+    tracing::info!(
+        "[Agent] 开始批量执行: id={}, items={}, concurrency={:?}",
+        batch_id,
+        total,
+        concurrency
+    );
+
+    let results = batch::run_batch(
+        agent_state.inner(),
+        &template,
+        rows,
+        model,
+        concurrency.unwrap_or(4),
+        |item, completed| {
+            let progress = BatchProgress {
+                batch_id: batch_id.clone(),
+                index: item.index,
+                completed,
+                total,
+                success: item.error.is_none(),
+                error: item.error.clone(),
+            };
+            let _ = app_handle.emit(BATCH_PROGRESS_EVENT, &progress);
+        },
+    )
+    .await;
+
+    batch::write_results(
+        &output_path,
+        &results,
+        BatchOutputFormat::from_path(&output_path),
+    )?;
+    let failed = results.iter().filter(|r| r.error.is_some()).count();
+    tracing::info!(
+        "[Agent] 批量执行完成: id={}, succeeded={}, failed={}, output={}",
+        batch_id,
+        total - failed,
+        failed,
+        output_path.display()
+    );
+    Ok(BatchRunResult {
+        batch_id,
+        total,
+        succeeded: total - failed,
+        failed,
+        output_path: output_path.to_string_lossy().to_string(),
+    })
+}
+
+/// 快速提问：使用 `quick_ask` 配置的模型和系统提示词单轮作答（不带会话历史和工具）
+///
+/// `scratch_session` 开启时问答追加到草稿会话，返回结果中带有草稿会话 ID
+#[tauri::command]
+pub async fn quick_ask(
+    agent_state: State<'_, NativeAgentState>,
+    app_state: State<'_, AppState>,
+    prompt: String,
+    model: Option<String>,
+) -> Result<QuickAskResponse, String> {
+    if prompt.trim().is_empty() {
+        return Err("问题不能为空".to_string());
+    }
+    ensure_agent_initialized(agent_state.inner(), app_state.inner(), false).await?;
+    let config = app_state.read().await.config.quick_ask.clone();
+
+    let started = std::time::Instant::now();
+    let content = agent_state
+        .complete(
+            &config.system_prompt,
+            &prompt,
+            model.or(config.model),
+            Some(config.max_tokens),
+            None,
+        )
+        .await?;
+    let scratch_session_id = if config.scratch_session {
+        Some(agent_state.append_quick_ask(&prompt, &content, config.scratch_max_messages)?)
+    } else {
+        None
+    };
+    Ok(QuickAskResponse {
+        content,
+        duration_ms: started.elapsed().as_millis() as u64,
+        scratch_session_id,
+    })
+}
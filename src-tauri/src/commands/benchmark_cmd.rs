@@ -0,0 +1,58 @@
+//! Provider 基准测试命令
+//!
+//! 通过本地 API Server 对已配置的 Provider 发送计时请求，对比延迟、输出速度和错误率
+
+use crate::database::DbConnection;
+use crate::services::provider_benchmark_service::{
+    self, BenchmarkReport, DEFAULT_BENCHMARK_PROMPT,
+};
+use crate::AppState;
+use tauri::State;
+
+/// 运行 Provider 延迟基准测试
+///
+/// 对每个已启用的凭证（或 `credential_uuids` 指定的凭证）和每个模型发送 `iterations` 次流式请求。
+/// 请求经过本地 API Server 转发，会消耗真实 Provider 的额度。
+#[tauri::command]
+pub async fn provider_benchmark(
+    state: State<'_, AppState>,
+    db: State<'_, DbConnection>,
+    models: Vec<String>,
+    prompt: Option<String>,
+    iterations: u32,
+    credential_uuids: Option<Vec<String>>,
+) -> Result<BenchmarkReport, String> {
+    let (base_url, api_key) = {
+        let s = state.read().await;
+        if !s.running {
+            return Err("ProxyCast API Server 未运行，请先启动服务器".to_string());
+        }
+        let api_key = s
+            .running_api_key
+            .clone()
+            .ok_or_else(|| "ProxyCast API Server 未配置 API Key".to_string())?;
+        (s.local_base_url(), api_key)
+    };
+
+    let targets = provider_benchmark_service::load_targets(&db, credential_uuids.as_deref())?;
+    let prompt = prompt
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_BENCHMARK_PROMPT.to_string());
+
+    tracing::info!(
+        "[BENCHMARK] 开始基准测试: credentials={}, models={:?}, iterations={}",
+        targets.len(),
+        models,
+        iterations
+    );
+    let report = provider_benchmark_service::run_benchmark(
+        &base_url, &api_key, &targets, &models, &prompt, iterations,
+    )
+    .await?;
+    tracing::info!(
+        "[BENCHMARK] 基准测试完成: entries={}, duration={}ms",
+        report.entries.len(),
+        report.duration_ms
+    );
+    Ok(report)
+}
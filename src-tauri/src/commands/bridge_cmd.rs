@@ -0,0 +1,55 @@
+//! 聊天桥接命令模块
+//!
+//! 提供将 Agent 会话桥接到 Telegram / Matrix 机器人的 Tauri 命令
+
+use crate::agent::NativeAgentState;
+use crate::services::chat_bridge_service::{BridgeInfo, BridgeTarget, ChatBridgeService};
+use tauri::State;
+
+/// ChatBridgeService 状态封装
+#[derive(Clone, Default)]
+pub struct ChatBridgeState(pub ChatBridgeService);
+
+/// 将会话桥接到机器人
+#[tauri::command]
+pub async fn bridge_attach_session(
+    bridge_state: State<'_, ChatBridgeState>,
+    agent_state: State<'_, NativeAgentState>,
+    session_id: String,
+    target: BridgeTarget,
+    accept_replies: Option<bool>,
+) -> Result<BridgeInfo, String> {
+    if !agent_state.is_initialized() {
+        return Err("Agent 未初始化".to_string());
+    }
+    if agent_state.get_session(&session_id)?.is_none() {
+        return Err(format!("会话不存在: {}", session_id));
+    }
+
+    bridge_state
+        .0
+        .attach(
+            session_id,
+            target,
+            accept_replies.unwrap_or(true),
+            agent_state.inner().clone(),
+        )
+        .await
+}
+
+/// 解除会话桥接
+#[tauri::command]
+pub async fn bridge_detach_session(
+    bridge_state: State<'_, ChatBridgeState>,
+    session_id: String,
+) -> Result<bool, String> {
+    Ok(bridge_state.0.detach(&session_id))
+}
+
+/// 列出所有会话桥接
+#[tauri::command]
+pub async fn bridge_list_sessions(
+    bridge_state: State<'_, ChatBridgeState>,
+) -> Result<Vec<BridgeInfo>, String> {
+    Ok(bridge_state.0.list())
+}
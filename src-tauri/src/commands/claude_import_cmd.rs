@@ -0,0 +1,26 @@
+//! Claude Code 配置导入命令
+
+use crate::database::DbConnection;
+use crate::services::claude_import_service::{
+    ClaudeImportOptions, ClaudeImportPlan, ClaudeImportReport, ClaudeImportService,
+};
+use tauri::State;
+
+fn home_dir() -> Result<std::path::PathBuf, String> {
+    dirs::home_dir().ok_or_else(|| "无法获取用户 home 目录".to_string())
+}
+
+/// 扫描可导入的 Claude Code 配置（Skills、MCP 服务器、CLAUDE.md）
+#[tauri::command]
+pub fn scan_claude_config() -> Result<ClaudeImportPlan, String> {
+    Ok(ClaudeImportService::scan(&home_dir()?))
+}
+
+/// 导入 Claude Code 配置，已存在的条目会被跳过
+#[tauri::command]
+pub fn import_claude_config(
+    db: State<'_, DbConnection>,
+    options: Option<ClaudeImportOptions>,
+) -> Result<ClaudeImportReport, String> {
+    ClaudeImportService::import(db.inner(), &home_dir()?, &options.unwrap_or_default())
+}
@@ -0,0 +1,130 @@
+//! 客户端 API Key 管理命令
+//!
+//! 创建、列出和吊销本地 API Server 的客户端 Key，修改后写入配置并立即生效
+
+use crate::config::{self, ClientApiKey};
+use crate::middleware::ClientKeyUsage;
+use crate::AppState;
+use serde::Serialize;
+use tauri::State;
+
+/// 客户端 Key 信息（Key 内容已掩码）
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientKeyInfo {
+    pub id: String,
+    pub name: String,
+    /// 掩码后的 Key
+    pub key_preview: String,
+    pub allowed_models: Vec<String>,
+    pub allowed_routes: Vec<String>,
+    pub created_at: String,
+    pub revoked: bool,
+    /// 本次运行期间的使用统计
+    pub usage: ClientKeyUsage,
+}
+
+/// 列出所有客户端 Key
+#[tauri::command]
+pub async fn list_client_keys(state: State<'_, AppState>) -> Result<Vec<ClientKeyInfo>, String> {
+    let s = state.read().await;
+    Ok(s.config
+        .server
+        .client_keys
+        .iter()
+        .map(|key| ClientKeyInfo {
+            id: key.id.clone(),
+            name: key.name.clone(),
+            key_preview: mask_key(&key.key),
+            allowed_models: key.allowed_models.clone(),
+            allowed_routes: key.allowed_routes.clone(),
+            created_at: key.created_at.clone(),
+            revoked: key.revoked,
+            usage: s.client_keys.usage(&key.id),
+        })
+        .collect())
+}
+
+/// 创建客户端 Key，返回完整 Key（只在创建时返回一次明文）
+#[tauri::command]
+pub async fn create_client_key(
+    state: State<'_, AppState>,
+    name: String,
+    allowed_models: Option<Vec<String>>,
+    allowed_routes: Option<Vec<String>>,
+) -> Result<ClientApiKey, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Key 名称不能为空".to_string());
+    }
+
+    let mut s = state.write().await;
+    if s.config
+        .server
+        .client_keys
+        .iter()
+        .any(|k| !k.revoked && k.name == name)
+    {
+        return Err(format!("已存在名为 {} 的 Key", name));
+    }
+
+    let key = ClientApiKey {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        key: config::generate_secure_api_key(),
+        allowed_models: normalize_scopes(allowed_models),
+        allowed_routes: normalize_scopes(allowed_routes),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        revoked: false,
+    };
+    s.config.server.client_keys.push(key.clone());
+    persist(&s)?;
+
+    tracing::info!("[CLIENT_KEY] 创建客户端 Key: {} ({})", key.name, key.id);
+    Ok(key)
+}
+
+/// 吊销客户端 Key（保留记录，之后使用该 Key 的请求返回 401）
+#[tauri::command]
+pub async fn revoke_client_key(state: State<'_, AppState>, id: String) -> Result<bool, String> {
+    let mut s = state.write().await;
+    let Some(key) = s.config.server.client_keys.iter_mut().find(|k| k.id == id) else {
+        return Ok(false);
+    };
+    if key.revoked {
+        return Ok(false);
+    }
+    key.revoked = true;
+    tracing::info!("[CLIENT_KEY] 吊销客户端 Key: {} ({})", key.name, key.id);
+    persist(&s)?;
+    Ok(true)
+}
+
+/// 保存配置并同步到运行中的服务器
+fn persist(s: &crate::server::ServerState) -> Result<(), String> {
+    s.client_keys.set_keys(s.config.server.client_keys.clone());
+    config::save_config(&s.config).map_err(|e| e.to_string())
+}
+
+/// 去除空白项和重复项
+fn normalize_scopes(scopes: Option<Vec<String>>) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    for scope in scopes.unwrap_or_default() {
+        let scope = scope.trim().to_string();
+        if !scope.is_empty() && !result.contains(&scope) {
+            result.push(scope);
+        }
+    }
+    result
+}
+
+/// 将 Key 转换为掩码显示
+fn mask_key(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() <= 12 {
+        "****".to_string()
+    } else {
+        let prefix: String = chars[..6].iter().collect();
+        let suffix: String = chars[chars.len() - 4..].iter().collect();
+        format!("{}****{}", prefix, suffix)
+    }
+}
@@ -1,6 +1,7 @@
 use crate::config::{
-    Config, ConfigManager, ExportBundle, ExportOptions as ExportServiceOptions, ExportService,
-    ImportOptions as ImportServiceOptions, ImportService, ValidationResult,
+    merge_profiles, Config, ConfigManager, ExportBundle, ExportOptions as ExportServiceOptions,
+    ExportService, ImportOptions as ImportServiceOptions, ImportService, ProfileBundle,
+    ProfileMergeSummary, ValidationResult,
 };
 use crate::models::AppType;
 use serde::{Deserialize, Serialize};
@@ -336,6 +337,86 @@ pub fn import_config(
     })
 }
 
+// ============ Provider Profile Commands ============
+
+/// 导出 Provider 配置档案为 JSON
+///
+/// # Arguments
+/// * `config` - 当前配置
+/// * `names` - 要导出的档案名称（为空时导出全部）
+/// * `include_keys` - 是否包含 API Key
+#[tauri::command]
+pub fn export_provider_profiles(
+    config: Config,
+    names: Option<Vec<String>>,
+    include_keys: bool,
+) -> Result<ExportResult, String> {
+    let bundle = ProfileBundle::new(&config.provider_profiles, names.as_deref(), include_keys);
+    if bundle.profiles.is_empty() {
+        return Err("没有可导出的配置档案".to_string());
+    }
+    let content = bundle.to_json()?;
+
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+    let suffix = if include_keys { "" } else { "_nokeys" };
+    let suggested_filename = format!("proxycast_profiles_{}{}.json", timestamp, suffix);
+
+    Ok(ExportResult {
+        content,
+        suggested_filename,
+    })
+}
+
+/// Provider 配置档案导入结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileImportResult {
+    /// 导入后的配置（需调用 save_config 保存）
+    pub config: Config,
+    /// 新增、覆盖和跳过的档案
+    pub summary: ProfileMergeSummary,
+    /// 警告信息（如果有）
+    pub warnings: Vec<String>,
+}
+
+/// 导入 Provider 配置档案
+///
+/// # Arguments
+/// * `current_config` - 当前配置
+/// * `json_content` - 档案 JSON 内容
+/// * `overwrite` - 是否覆盖同名档案
+#[tauri::command]
+pub fn import_provider_profiles(
+    current_config: Config,
+    json_content: String,
+    overwrite: bool,
+) -> Result<ProfileImportResult, String> {
+    let bundle = ProfileBundle::from_json(&json_content)?;
+    let mut config = current_config;
+    let summary = merge_profiles(&mut config.provider_profiles, bundle.profiles, overwrite);
+
+    let mut warnings = Vec::new();
+    let missing_keys: Vec<&str> = config
+        .provider_profiles
+        .profiles
+        .iter()
+        .filter(|p| p.api_key.is_empty())
+        .filter(|p| summary.added.contains(&p.name) || summary.replaced.contains(&p.name))
+        .map(|p| p.name.as_str())
+        .collect();
+    if !missing_keys.is_empty() {
+        warnings.push(format!(
+            "以下档案未包含 API Key，请手动填写: {}",
+            missing_keys.join(", ")
+        ));
+    }
+
+    Ok(ProfileImportResult {
+        config,
+        summary,
+        warnings,
+    })
+}
+
 /// 获取配置文件路径信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConfigPathInfo {
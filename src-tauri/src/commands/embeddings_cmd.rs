@@ -0,0 +1,122 @@
+//! 向量化命令模块
+//!
+//! 提供文本向量化、最近邻检索和集合管理的 Tauri 命令
+
+use crate::database::DbConnection;
+use crate::embeddings::{EmbeddingClient, ScoredEmbedding, VectorStore};
+use crate::AppState;
+use serde::Serialize;
+use tauri::State;
+
+/// 默认检索条数
+const DEFAULT_TOP_K: usize = 5;
+
+/// 集合信息
+#[derive(Debug, Serialize)]
+pub struct EmbeddingCollectionInfo {
+    pub name: String,
+    pub count: i64,
+}
+
+/// 基于运行中的 ProxyCast 服务器创建向量化客户端
+pub async fn create_embedding_client(app_state: &AppState) -> Result<EmbeddingClient, String> {
+    let (base_url, api_key, running) = {
+        let state = app_state.read().await;
+        (
+            state.local_base_url(),
+            state.running_api_key.clone(),
+            state.running,
+        )
+    };
+
+    if !running {
+        return Err("ProxyCast API Server 未运行".to_string());
+    }
+
+    let api_key = api_key.ok_or_else(|| "未配置 API Key".to_string())?;
+    EmbeddingClient::new(base_url, api_key)
+}
+
+/// 集合使用的向量化模型
+///
+/// 已有记录的集合使用建立索引时的模型（指定了其他模型时返回错误），
+/// 空集合使用指定的模型，未指定时使用配置中的 `embeddings.model`
+pub async fn collection_model(
+    app_state: &AppState,
+    store: &VectorStore,
+    collection: &str,
+    model: Option<String>,
+) -> Result<String, String> {
+    let default = app_state.read().await.config.embeddings.model.clone();
+    let requested = model.filter(|m| !m.trim().is_empty());
+    store.model_for(collection, requested.as_deref(), &default)
+}
+
+/// 向量化文本并保存到集合，返回记录 ID
+#[tauri::command]
+pub async fn embeddings_embed_text(
+    app_state: State<'_, AppState>,
+    db: State<'_, DbConnection>,
+    collection: String,
+    text: String,
+    metadata: Option<serde_json::Value>,
+    model: Option<String>,
+) -> Result<String, String> {
+    let store = VectorStore::new(db.inner().clone());
+    let model = collection_model(app_state.inner(), &store, &collection, model).await?;
+    let client = create_embedding_client(app_state.inner()).await?;
+    let vector = client.embed_one(&text, &model).await?;
+
+    let id = store.add(&collection, &text, metadata, &model, vector)?;
+    tracing::info!(
+        "[Embeddings] 已保存向量: collection={}, id={}",
+        collection,
+        id
+    );
+    Ok(id)
+}
+
+/// 检索集合中与查询文本最相似的记录
+#[tauri::command]
+pub async fn embeddings_query(
+    app_state: State<'_, AppState>,
+    db: State<'_, DbConnection>,
+    collection: String,
+    query: String,
+    top_k: Option<usize>,
+    model: Option<String>,
+) -> Result<Vec<ScoredEmbedding>, String> {
+    let store = VectorStore::new(db.inner().clone());
+    let model = collection_model(app_state.inner(), &store, &collection, model).await?;
+    let client = create_embedding_client(app_state.inner()).await?;
+    let vector = client.embed_one(&query, &model).await?;
+
+    store.query(&collection, &vector, top_k.unwrap_or(DEFAULT_TOP_K))
+}
+
+/// 列出所有向量集合
+#[tauri::command]
+pub fn embeddings_list_collections(
+    db: State<'_, DbConnection>,
+) -> Result<Vec<EmbeddingCollectionInfo>, String> {
+    Ok(VectorStore::new(db.inner().clone())
+        .list_collections()?
+        .into_iter()
+        .map(|(name, count)| EmbeddingCollectionInfo { name, count })
+        .collect())
+}
+
+/// 删除单条向量记录
+#[tauri::command]
+pub fn embeddings_delete(db: State<'_, DbConnection>, id: String) -> Result<bool, String> {
+    VectorStore::new(db.inner().clone()).delete(&id)
+}
+
+/// 删除整个向量集合
+#[tauri::command]
+pub fn embeddings_delete_collection(
+    db: State<'_, DbConnection>,
+    collection: String,
+) -> Result<usize, String> {
+    VectorStore::new(db.inner().clone()).delete_collection(&collection)
+}
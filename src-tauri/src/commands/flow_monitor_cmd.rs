@@ -104,6 +104,9 @@ pub struct ExportFlowsRequest {
     /// 是否脱敏敏感数据
     #[serde(default)]
     pub redact_sensitive: bool,
+    /// 是否嵌入来源信息（模型、提供商、时间戳、ProxyCast 版本）
+    #[serde(default)]
+    pub include_provenance: bool,
     /// Flow ID 列表（如果指定，则只导出这些 Flow）
     #[serde(default)]
     pub flow_ids: Option<Vec<String>>,
@@ -324,6 +327,7 @@ pub async fn export_flows(
         redact_sensitive: request.redact_sensitive,
         redaction_rules: Vec::new(),
         compress: false,
+        include_provenance: request.include_provenance,
     };
     let exporter = FlowExporter::new(options);
 
@@ -893,6 +897,7 @@ mod tests {
             include_raw: true,
             include_stream_chunks: false,
             redact_sensitive: false,
+            include_provenance: false,
             flow_ids: None,
         };
 
@@ -1663,6 +1668,9 @@ pub struct ExportSessionRequest {
     /// 导出格式
     #[serde(default)]
     pub format: ExportFormat,
+    /// 是否嵌入来源信息
+    #[serde(default)]
+    pub include_provenance: bool,
 }
 
 /// 创建新会话
@@ -1904,7 +1912,12 @@ pub async fn export_session(
     // 导出会话
     session_manager
         .0
-        .export_session(&request.session_id, &flows, request.format)
+        .export_session(
+            &request.session_id,
+            &flows,
+            request.format,
+            request.include_provenance,
+        )
         .map_err(|e| format!("导出会话失败: {}", e))
 }
 
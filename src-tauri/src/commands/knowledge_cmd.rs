@@ -0,0 +1,87 @@
+//! 知识库命令模块
+//!
+//! 提供文档导入和会话关联知识库集合的 Tauri 命令。
+//! 集合的查询与删除复用 `embeddings_cmd` 中的命令。
+
+use crate::agent::NativeAgentState;
+use crate::commands::embeddings_cmd::{collection_model, create_embedding_client};
+use crate::database::DbConnection;
+use crate::embeddings::VectorStore;
+use crate::knowledge::{ingest_file, ChunkOptions, IngestResult};
+use crate::AppState;
+use std::path::PathBuf;
+use tauri::State;
+
+/// 导入文件到知识库集合（支持 PDF、Markdown、纯文本）
+///
+/// 单个文件失败不会中断其余文件的导入，失败原因记录在对应结果中。
+#[tauri::command]
+pub async fn knowledge_ingest_files(
+    app_state: State<'_, AppState>,
+    db: State<'_, DbConnection>,
+    collection: String,
+    paths: Vec<String>,
+    model: Option<String>,
+    chunk_size: Option<usize>,
+    chunk_overlap: Option<usize>,
+) -> Result<Vec<IngestResult>, String> {
+    if collection.trim().is_empty() {
+        return Err("集合名称不能为空".to_string());
+    }
+
+    let store = VectorStore::new(db.inner().clone());
+    let model = collection_model(app_state.inner(), &store, &collection, model).await?;
+    let defaults = ChunkOptions::default();
+    let options = ChunkOptions {
+        chunk_size: chunk_size.unwrap_or(defaults.chunk_size),
+        overlap: chunk_overlap.unwrap_or(defaults.overlap),
+    };
+
+    let client = create_embedding_client(app_state.inner()).await?;
+
+    let mut results = Vec::with_capacity(paths.len());
+    for path in paths {
+        let result = ingest_file(
+            &client,
+            &store,
+            &collection,
+            &PathBuf::from(&path),
+            &model,
+            options,
+        )
+        .await;
+        results.push(match result {
+            Ok(chunks) => IngestResult {
+                path,
+                chunks,
+                error: None,
+            },
+            Err(e) => {
+                tracing::warn!("[Knowledge] 导入文件失败: path={}, error={}", path, e);
+                IngestResult {
+                    path,
+                    chunks: 0,
+                    error: Some(e),
+                }
+            }
+        });
+    }
+
+    Ok(results)
+}
+
+/// 将知识库集合关联到会话（collection 为空时取消关联）
+#[tauri::command]
+pub async fn knowledge_attach_collection(
+    agent_state: State<'_, NativeAgentState>,
+    session_id: String,
+    collection: Option<String>,
+) -> Result<bool, String> {
+    let collection = collection.filter(|c| !c.trim().is_empty());
+    tracing::info!(
+        "[Knowledge] 会话 {} 关联知识库集合: {:?}",
+        session_id,
+        collection
+    );
+    Ok(agent_state.set_session_collection(&session_id, collection))
+}
@@ -0,0 +1,97 @@
+//! 代理压测命令
+//!
+//! 通过本地 API Server 向模拟上游发送合成流量，评估吞吐量和延迟
+
+use crate::commands::provider_pool_cmd::ProviderPoolServiceState;
+use crate::database::DbConnection;
+use crate::models::provider_pool_model::CredentialData;
+use crate::server::loadtest::{self, LoadTestReport, MockUpstream, PayloadProfile};
+use crate::AppState;
+use tauri::State;
+
+/// 运行代理压测
+///
+/// 临时创建一个指向模拟上游的 OpenAI 凭证（标记为禁用，不参与正常路由），
+/// 按凭证 UUID 直接路由压测请求，结束后删除凭证并关闭模拟上游。
+#[tauri::command]
+pub async fn proxy_loadtest(
+    state: State<'_, AppState>,
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    requests: u32,
+    concurrency: u32,
+    payload_profile: Option<PayloadProfile>,
+) -> Result<LoadTestReport, String> {
+    loadtest::validate_params(requests, concurrency)?;
+    let profile = payload_profile.unwrap_or_default();
+
+    let (port, api_key) = {
+        let s = state.read().await;
+        if !s.running {
+            return Err("ProxyCast API Server 未运行，请先启动服务器".to_string());
+        }
+        let api_key = s
+            .running_api_key
+            .clone()
+            .ok_or_else(|| "ProxyCast API Server 未配置 API Key".to_string())?;
+        (s.config.server.port, api_key)
+    };
+
+    let mock = MockUpstream::start().await?;
+    let credential = pool_service.0.add_credential(
+        &db,
+        "openai",
+        CredentialData::OpenAIKey {
+            api_key: "proxycast-loadtest".to_string(),
+            base_url: Some(mock.base_url().to_string()),
+        },
+        Some(format!(
+            "proxycast-loadtest-{}",
+            &uuid::Uuid::new_v4().simple().to_string()[..8]
+        )),
+        Some(false),
+        None,
+    )?;
+
+    tracing::info!(
+        "[LOADTEST] 开始压测: requests={}, concurrency={}, profile={:?}, upstream={}",
+        requests,
+        concurrency,
+        profile,
+        mock.base_url()
+    );
+
+    let endpoint = format!(
+        "http://127.0.0.1:{}/{}/v1/chat/completions",
+        port, credential.uuid
+    );
+    let result = match pool_service.0.update_credential(
+        &db,
+        &credential.uuid,
+        None,
+        Some(true),
+        None,
+        None,
+        None,
+        None,
+    ) {
+        Ok(_) => loadtest::run_load_test(&endpoint, &api_key, requests, concurrency, profile).await,
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = pool_service.0.delete_credential(&db, &credential.uuid) {
+        tracing::warn!("[LOADTEST] 删除压测凭证失败: {}", e);
+    }
+    drop(mock);
+
+    let report = result?;
+    tracing::info!(
+        "[LOADTEST] 压测完成: succeeded={}, failed={}, rps={:.1}, p50={:.1}ms, p99={:.1}ms",
+        report.succeeded,
+        report.failed,
+        report.requests_per_second,
+        report.latency.p50_ms,
+        report.latency.p99_ms
+    );
+    Ok(report)
+}
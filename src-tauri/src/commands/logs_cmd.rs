@@ -0,0 +1,108 @@
+//! 实时日志命令
+//!
+//! 查询内存中的后端日志（[`LiveLogBuffer`]），将新日志通过 `log_line` 事件推送到前端，
+//! 并支持将日志文件打包导出
+
+use crate::commands::telemetry_cmd::TimeRangeParam;
+use crate::telemetry::{default_log_dir, export_logs, LiveLogBuffer, LogFilter, LogLine};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_dialog::DialogExt;
+use tokio::sync::broadcast::error::RecvError;
+
+/// 新日志推送事件
+pub const LOG_LINE_EVENT: &str = "log_line";
+
+/// `logs_tail` 未指定条数时返回的日志数
+const DEFAULT_TAIL_LIMIT: usize = 200;
+
+/// 实时日志状态
+pub struct LiveLogState(pub Arc<LiveLogBuffer>);
+
+/// 获取最近的后端日志（按时间升序），可按级别和模块过滤
+#[tauri::command]
+pub fn logs_tail(
+    state: tauri::State<'_, LiveLogState>,
+    filter: Option<LogFilter>,
+    limit: Option<usize>,
+) -> Vec<LogLine> {
+    state.0.tail(
+        &filter.unwrap_or_default(),
+        limit.unwrap_or(DEFAULT_TAIL_LIMIT),
+    )
+}
+
+/// 日志导出结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogExportResult {
+    /// 导出的 zip 文件路径
+    pub path: String,
+    /// 打包的日志文件数
+    pub files: usize,
+}
+
+/// 默认导出目录（~/.proxycast/exports）
+fn default_export_dir() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or_else(|| "无法获取用户 home 目录".to_string())?;
+    Ok(home.join(".proxycast").join("exports"))
+}
+
+/// 将时间范围内的日志文件打包为 zip（用于提交问题报告），未指定范围时打包全部日志
+///
+/// 导出路径只能由用户在保存对话框中选择，取消时返回 None；不覆盖已有文件
+#[tauri::command]
+pub async fn logs_export(
+    app: AppHandle,
+    range: Option<TimeRangeParam>,
+) -> Result<Option<LogExportResult>, String> {
+    let range = range.map(|r| r.to_time_range()).transpose()?.flatten();
+    let dir = default_export_dir()?;
+    let _ = std::fs::create_dir_all(&dir);
+    let Some(selected) = app
+        .dialog()
+        .file()
+        .set_title("导出日志")
+        .set_directory(&dir)
+        .set_file_name(format!(
+            "proxycast-logs-{}.zip",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ))
+        .add_filter("Zip", &["zip"])
+        .blocking_save_file()
+    else {
+        return Ok(None);
+    };
+    let dest = selected
+        .into_path()
+        .map_err(|e| format!("无效的导出路径: {}", e))?;
+    let files = tokio::task::spawn_blocking({
+        let dest = dest.clone();
+        move || export_logs(&default_log_dir(), range.as_ref(), &dest)
+    })
+    .await
+    .map_err(|e| format!("导出日志失败: {}", e))??;
+    tracing::info!("[Logs] 已导出 {} 个日志文件: {}", files, dest.display());
+    Ok(Some(LogExportResult {
+        path: dest.to_string_lossy().to_string(),
+        files,
+    }))
+}
+
+/// 启动日志推送任务，每条新日志通过 [`LOG_LINE_EVENT`] 事件推送
+pub fn spawn_log_line_forwarder(app_handle: AppHandle, buffer: Arc<LiveLogBuffer>) {
+    let mut receiver = buffer.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                // 推送失败时不能再写 tracing 日志，否则会产生新的推送
+                Ok(line) => {
+                    let _ = app_handle.emit(LOG_LINE_EVENT, &line);
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
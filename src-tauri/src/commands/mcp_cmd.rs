@@ -1,8 +1,20 @@
 use crate::database::DbConnection;
 use crate::models::McpServer;
 use crate::services::mcp_service::McpService;
+use crate::services::mcp_supervisor::{McpServerStatus, McpSupervisor};
 use tauri::State;
 
+/// McpSupervisor 状态封装
+#[derive(Clone)]
+pub struct McpSupervisorState(pub McpSupervisor);
+
+fn find_server(db: &DbConnection, id: &str) -> Result<McpServer, String> {
+    McpService::get_all(db)?
+        .into_iter()
+        .find(|server| server.id == id)
+        .ok_or_else(|| format!("MCP 服务器不存在: {}", id))
+}
+
 #[tauri::command]
 pub fn get_mcp_servers(db: State<'_, DbConnection>) -> Result<Vec<McpServer>, String> {
     McpService::get_all(&db)
@@ -14,12 +26,22 @@ pub fn add_mcp_server(db: State<'_, DbConnection>, server: McpServer) -> Result<
 }
 
 #[tauri::command]
-pub fn update_mcp_server(db: State<'_, DbConnection>, server: McpServer) -> Result<(), String> {
+pub fn update_mcp_server(
+    db: State<'_, DbConnection>,
+    supervisor: State<'_, McpSupervisorState>,
+    server: McpServer,
+) -> Result<(), String> {
+    supervisor.0.refresh(&server);
     McpService::update(&db, server)
 }
 
 #[tauri::command]
-pub fn delete_mcp_server(db: State<'_, DbConnection>, id: String) -> Result<(), String> {
+pub fn delete_mcp_server(
+    db: State<'_, DbConnection>,
+    supervisor: State<'_, McpSupervisorState>,
+    id: String,
+) -> Result<(), String> {
+    supervisor.0.stop(&id);
     McpService::delete(&db, &id)
 }
 
@@ -42,3 +64,70 @@ pub fn import_mcp_from_app(db: State<'_, DbConnection>, app_type: String) -> Res
 pub fn sync_all_mcp_to_live(db: State<'_, DbConnection>) -> Result<(), String> {
     McpService::sync_all_to_live(&db)
 }
+
+/// ProxyCast 自身作为 MCP 服务端的运行状态
+#[tauri::command]
+pub async fn mcp_host_status(
+    mcp_host: State<'_, crate::mcp_host::McpHostState>,
+) -> Result<crate::mcp_host::McpHostStatus, String> {
+    Ok(mcp_host.status().await)
+}
+
+/// 启动 stdio MCP 服务器进程（已在运行时直接返回状态）
+#[tauri::command]
+pub async fn mcp_server_start(
+    db: State<'_, DbConnection>,
+    supervisor: State<'_, McpSupervisorState>,
+    id: String,
+) -> Result<McpServerStatus, String> {
+    let server = find_server(&db, &id)?;
+    supervisor.0.ensure_started(&server).await
+}
+
+/// 停止 MCP 服务器进程，返回进程是否存在
+#[tauri::command]
+pub fn mcp_server_stop(supervisor: State<'_, McpSupervisorState>, id: String) -> bool {
+    supervisor.0.stop(&id)
+}
+
+/// 重启 MCP 服务器进程
+#[tauri::command]
+pub async fn mcp_server_restart(
+    db: State<'_, DbConnection>,
+    supervisor: State<'_, McpSupervisorState>,
+    id: String,
+) -> Result<McpServerStatus, String> {
+    let server = find_server(&db, &id)?;
+    supervisor.0.stop(&id);
+    supervisor.0.ensure_started(&server).await
+}
+
+/// 列出 MCP 服务器提供的工具（未运行时自动启动）
+#[tauri::command]
+pub async fn mcp_server_list_tools(
+    db: State<'_, DbConnection>,
+    supervisor: State<'_, McpSupervisorState>,
+    id: String,
+) -> Result<serde_json::Value, String> {
+    let server = find_server(&db, &id)?;
+    let result = supervisor
+        .0
+        .request(&server, "tools/list", serde_json::json!({}))
+        .await?;
+    Ok(result
+        .get("tools")
+        .cloned()
+        .unwrap_or_else(|| serde_json::json!([])))
+}
+
+/// MCP 服务器进程状态（不传 id 时返回所有已启动的进程）
+#[tauri::command]
+pub fn mcp_server_status(
+    supervisor: State<'_, McpSupervisorState>,
+    id: Option<String>,
+) -> Vec<McpServerStatus> {
+    match id {
+        Some(id) => supervisor.0.status(&id).into_iter().collect(),
+        None => supervisor.0.list_status(),
+    }
+}
@@ -0,0 +1,52 @@
+//! 长期记忆命令模块
+//!
+//! 提供 Agent 长期记忆的查看、添加、编辑、删除和审核命令
+
+use crate::agent::MemoryStore;
+use crate::database::dao::agent_memory::AgentMemory;
+use crate::database::DbConnection;
+use tauri::State;
+
+/// 获取长期记忆，`unreviewed_only` 为 true 时只返回模型保存且未审核的记忆
+#[tauri::command]
+pub fn memory_list(
+    db: State<'_, DbConnection>,
+    unreviewed_only: Option<bool>,
+) -> Result<Vec<AgentMemory>, String> {
+    let memories = MemoryStore::new(db.inner().clone()).list()?;
+    if unreviewed_only.unwrap_or(false) {
+        return Ok(memories.into_iter().filter(|m| !m.reviewed).collect());
+    }
+    Ok(memories)
+}
+
+/// 手动添加记忆（内容重复时返回 None）
+#[tauri::command]
+pub fn memory_add(
+    db: State<'_, DbConnection>,
+    content: String,
+) -> Result<Option<AgentMemory>, String> {
+    MemoryStore::new(db.inner().clone()).add(&content, "manual")
+}
+
+/// 编辑记忆内容
+#[tauri::command]
+pub fn memory_update(
+    db: State<'_, DbConnection>,
+    id: String,
+    content: String,
+) -> Result<bool, String> {
+    MemoryStore::new(db.inner().clone()).update(&id, &content)
+}
+
+/// 删除记忆
+#[tauri::command]
+pub fn memory_delete(db: State<'_, DbConnection>, id: String) -> Result<bool, String> {
+    MemoryStore::new(db.inner().clone()).delete(&id)
+}
+
+/// 审核模型保存的记忆：`keep` 为 true 时保留，否则删除
+#[tauri::command]
+pub fn memory_review(db: State<'_, DbConnection>, id: String, keep: bool) -> Result<bool, String> {
+    MemoryStore::new(db.inner().clone()).review(&id, keep)
+}
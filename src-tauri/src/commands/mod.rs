@@ -1,13 +1,22 @@
 pub mod agent_cmd;
 pub mod api_key_provider_cmd;
 pub mod auto_fix_cmd;
+pub mod benchmark_cmd;
+pub mod bridge_cmd;
 pub mod browser_interceptor_cmd;
+pub mod claude_import_cmd;
+pub mod client_key_cmd;
 pub mod config_cmd;
+pub mod embeddings_cmd;
 pub mod flow_monitor_cmd;
 pub mod injection_cmd;
 pub mod kiro_local;
+pub mod knowledge_cmd;
+pub mod loadtest_cmd;
+pub mod logs_cmd;
 pub mod machine_id_cmd;
 pub mod mcp_cmd;
+pub mod memory_cmd;
 pub mod native_agent_cmd;
 pub mod network_cmd;
 pub mod oauth_cmd;
@@ -16,12 +25,17 @@ pub mod plugin_install_cmd;
 pub mod prompt_cmd;
 pub mod provider_pool_cmd;
 pub mod resilience_cmd;
+pub mod response_cache_cmd;
 pub mod route_cmd;
 pub mod router_cmd;
+pub mod session_sync_cmd;
 pub mod skill_cmd;
 pub mod switch_cmd;
 pub mod telemetry_cmd;
+pub mod token_budget_cmd;
 pub mod tray_cmd;
+pub mod upstream_health_cmd;
 pub mod usage_cmd;
+pub mod usage_recorder_cmd;
 pub mod websocket_cmd;
 pub mod window_cmd;
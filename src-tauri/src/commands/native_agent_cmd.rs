@@ -2,13 +2,39 @@
 //!
 //! 提供原生 Rust Agent 的 Tauri 命令，替代 aster sidecar 方案
 
+use crate::agent::background::{parse_lines, run_background_task, BackgroundTask};
+use crate::agent::paste::prepare_paste;
+use crate::agent::patch_review::PatchProposal;
+use crate::agent::scheduled_tasks;
+use crate::agent::script_review::ScriptRunRequest;
+use crate::agent::session_bulk::{
+    default_export_path, write_export, write_export_file, SESSION_BULK_PROGRESS_EVENT,
+};
+use crate::agent::session_export::{self, SessionExportFormat, SessionExportStats};
+use crate::agent::session_meta;
+use crate::agent::session_quota::archive_sessions;
+use crate::agent::stream_coalesce::StreamCoalescer;
+use crate::agent::tool_approval::ToolApprovalRequest;
+use crate::agent::tools::WasmPluginInfo;
+use crate::agent::MemoryStore;
 use crate::agent::{
-    AgentSession, ImageData, NativeAgentState, NativeChatRequest, NativeChatResponse, ProviderType,
-    StreamEvent, ToolLoopEngine,
+    lint_session, AgentError, AgentSession, AttachmentData, AudioData, BulkExportResult,
+    BulkProgress, CancelMode, FollowupTask, ImageData, ImageDetail, ImageGenerationResult,
+    MessageDraft, NativeAgentState, NativeChatRequest, NativeChatResponse, PastePayload,
+    ProviderType, ReplayOverrides, ReplayResult, RequestPreview, ScheduledTaskStatus,
+    SessionFilter, SessionFolder, SessionLintSuggestion, SessionMetaUpdate, SessionQuotaStatus,
+    StreamEvent, StreamInfo, StreamResult, TaggedStreamEvent, TaskTrigger, ToolLoopEngine,
+    TranscriptEntry,
 };
+use crate::commands::bridge_cmd::ChatBridgeState;
+use crate::config::{PermissionMode, StreamCoalesceConfig};
+use crate::database::dao::agent_memory::AgentMemory;
+use crate::database::dao::scheduled_task::{ScheduledTaskDao, ScheduledTaskRun};
+use crate::database::DbConnection;
 use crate::AppState;
 use serde::{Deserialize, Serialize};
-use tauri::{Emitter, State};
+use tauri::ipc::Channel;
+use tauri::{Emitter, Manager, State};
 use tokio::sync::mpsc;
 
 #[derive(Debug, Serialize)]
@@ -17,41 +43,50 @@ pub struct NativeAgentStatus {
     pub base_url: Option<String>,
 }
 
+/// 确保 Agent 已初始化（所有命令共用，并发安全）
+///
+/// 连接参数取自当前运行中的 API Server；`force` 为 true 时按最新参数重新初始化。
+pub async fn ensure_agent_initialized(
+    agent_state: &NativeAgentState,
+    app_state: &AppState,
+    force: bool,
+) -> Result<String, AgentError> {
+    agent_state
+        .ensure_initialized(force, || async {
+            let state = app_state.read().await;
+            if !state.running {
+                return Err(AgentError::ServerNotRunning);
+            }
+            let api_key = state
+                .running_api_key
+                .clone()
+                .ok_or(AgentError::MissingApiKey)?;
+            let base_url = state.local_base_url();
+            let provider_type = ProviderType::from_str(&state.config.routing.default_provider);
+
+            tracing::info!(
+                "[NativeAgent] 初始化 Agent: base_url={}, provider={:?}",
+                base_url,
+                provider_type
+            );
+            Ok((base_url, api_key, provider_type))
+        })
+        .await
+}
+
+/// 初始化 Agent（已初始化时直接返回，`force` 为 true 时重新初始化）
 #[tauri::command]
 pub async fn native_agent_init(
     agent_state: State<'_, NativeAgentState>,
     app_state: State<'_, AppState>,
-) -> Result<NativeAgentStatus, String> {
-    tracing::info!("[NativeAgent] 初始化 Agent");
-
-    let (port, api_key, running, default_provider) = {
-        let state = app_state.read().await;
-        (
-            state.config.server.port,
-            state.running_api_key.clone(),
-            state.running,
-            state.config.routing.default_provider.clone(),
-        )
-    };
-
-    if !running {
-        return Err("ProxyCast API Server 未运行，请先启动服务器".to_string());
-    }
-
-    let api_key = api_key.ok_or_else(|| "ProxyCast API Server 未配置 API Key".to_string())?;
-
-    let base_url = format!("http://127.0.0.1:{}", port);
-    let provider_type = ProviderType::from_str(&default_provider);
-
-    tracing::info!(
-        "[NativeAgent] 初始化 Agent: base_url={}, provider={:?}",
-        base_url,
-        provider_type
-    );
-
-    agent_state.init(base_url.clone(), api_key, provider_type)?;
-
-    tracing::info!("[NativeAgent] Agent 初始化成功: {}", base_url);
+    force: Option<bool>,
+) -> Result<NativeAgentStatus, AgentError> {
+    let base_url = ensure_agent_initialized(
+        agent_state.inner(),
+        app_state.inner(),
+        force.unwrap_or(false),
+    )
+    .await?;
 
     Ok(NativeAgentStatus {
         initialized: true,
@@ -78,8 +113,27 @@ pub async fn native_agent_reset(agent_state: State<'_, NativeAgentState>) -> Res
 
 #[derive(Debug, Deserialize)]
 pub struct ImageInputParam {
+    #[serde(default)]
     pub data: String,
+    #[serde(default)]
     pub media_type: String,
+    /// 本地图片路径（传入时由后端读取并编码）
+    #[serde(default)]
+    pub path: Option<String>,
+    /// 识别精度: low, high, auto
+    #[serde(default)]
+    pub detail: Option<ImageDetail>,
+}
+
+impl From<ImageInputParam> for ImageData {
+    fn from(img: ImageInputParam) -> Self {
+        ImageData {
+            data: img.data,
+            media_type: img.media_type,
+            path: img.path,
+            detail: img.detail,
+        }
+    }
 }
 
 #[tauri::command]
@@ -89,47 +143,25 @@ pub async fn native_agent_chat(
     message: String,
     model: Option<String>,
     images: Option<Vec<ImageInputParam>>,
-) -> Result<NativeChatResponse, String> {
+    audio: Option<Vec<AudioData>>,
+    attachments: Option<Vec<AttachmentData>>,
+) -> Result<NativeChatResponse, AgentError> {
     tracing::info!(
         "[NativeAgent] 发送消息: message_len={}, model={:?}",
         message.len(),
         model
     );
 
-    // 如果 Agent 未初始化，自动初始化
-    if !agent_state.is_initialized() {
-        let (port, api_key, running, default_provider) = {
-            let state = app_state.read().await;
-            (
-                state.config.server.port,
-                state.running_api_key.clone(),
-                state.running,
-                state.config.routing.default_provider.clone(),
-            )
-        };
-
-        if !running {
-            return Err("ProxyCast API Server 未运行".to_string());
-        }
-
-        let api_key = api_key.ok_or_else(|| "未配置 API Key".to_string())?;
-        let base_url = format!("http://127.0.0.1:{}", port);
-        let provider_type = ProviderType::from_str(&default_provider);
-        agent_state.init(base_url, api_key, provider_type)?;
-    }
+    ensure_agent_initialized(agent_state.inner(), app_state.inner(), false).await?;
 
     let request = NativeChatRequest {
         session_id: None,
         message,
         model,
-        images: images.map(|imgs| {
-            imgs.into_iter()
-                .map(|img| ImageData {
-                    data: img.data,
-                    media_type: img.media_type,
-                })
-                .collect()
-        }),
+        images: images.map(|imgs| imgs.into_iter().map(ImageData::from).collect()),
+        audio,
+        attachments,
+        context: None,
         stream: false,
     };
 
@@ -137,82 +169,104 @@ pub async fn native_agent_chat(
     agent_state.chat(request).await
 }
 
+/// 流式对话，返回流 ID
+///
+/// 流式事件通过调用方传入的 `on_event` 通道推送，只有发起请求的窗口会收到，
+/// 每个事件都带有 `stream_id`，并发对话之间不会串流；流 ID 用于中断和查询对话状态。
 #[tauri::command]
 pub async fn native_agent_chat_stream(
     app_handle: tauri::AppHandle,
     agent_state: State<'_, NativeAgentState>,
     app_state: State<'_, AppState>,
     message: String,
-    event_name: String,
+    on_event: Channel<TaggedStreamEvent>,
     session_id: Option<String>,
     model: Option<String>,
     images: Option<Vec<ImageInputParam>>,
-) -> Result<(), String> {
+    audio: Option<Vec<AudioData>>,
+    attachments: Option<Vec<AttachmentData>>,
+    retrieval_collection: Option<String>,
+) -> Result<String, AgentError> {
+    let stream_id = uuid::Uuid::new_v4().to_string();
     tracing::info!(
-        "[NativeAgent] 发送流式消息: message_len={}, model={:?}, event={}, session={:?}",
+        "[NativeAgent] 发送流式消息: message_len={}, model={:?}, stream={}, session={:?}",
         message.len(),
         model,
-        event_name,
+        stream_id,
         session_id
     );
 
-    // 如果 Agent 未初始化，自动初始化
-    if !agent_state.is_initialized() {
-        let (port, api_key, running, default_provider) = {
-            let state = app_state.read().await;
-            (
-                state.config.server.port,
-                state.running_api_key.clone(),
-                state.running,
-                state.config.routing.default_provider.clone(),
-            )
-        };
-
-        if !running {
-            return Err("ProxyCast API Server 未运行".to_string());
-        }
-
-        let api_key = api_key.ok_or_else(|| "未配置 API Key".to_string())?;
-        let base_url = format!("http://127.0.0.1:{}", port);
-        let provider_type = ProviderType::from_str(&default_provider);
-        agent_state.init(base_url, api_key, provider_type)?;
-    }
+    ensure_agent_initialized(agent_state.inner(), app_state.inner(), false).await?;
 
     // 获取工具注册表（用于创建 ToolLoopEngine）
-    let tool_registry = agent_state.get_tool_registry()?;
+    let tool_registry = agent_state.get_tool_registry(session_id.as_deref())?;
+
+    // 指定了集合时检索该集合作为本轮的参考资料，否则由 Agent 检索会话关联的知识库
+    let context = match retrieval_collection {
+        Some(collection) => {
+            agent_state
+                .retrieve_knowledge(session_id.as_deref(), Some(collection), &message)
+                .await
+        }
+        None => None,
+    };
 
     let request = NativeChatRequest {
         session_id, // 使用前端传递的 session_id 以保持上下文
         message,
         model,
-        images: images.map(|imgs| {
-            imgs.into_iter()
-                .map(|img| ImageData {
-                    data: img.data,
-                    media_type: img.media_type,
-                })
-                .collect()
-        }),
+        images: images.map(|imgs| imgs.into_iter().map(ImageData::from).collect()),
+        audio,
+        attachments,
+        context,
         stream: true,
     };
 
+    // 记录本轮开始前的消息数，中断时据此保留或回退本轮对话
+    let turn_start = request
+        .session_id
+        .as_ref()
+        .and_then(|sid| agent_state.get_session(sid).ok().flatten())
+        .map(|s| s.messages.len());
+    let cancel_rx = agent_state.register_stream(StreamInfo::new(
+        &stream_id,
+        request.session_id.clone(),
+        request.model.clone(),
+    ));
+
     // 克隆 agent_state 用于后台任务（共享 sessions）
     let agent_state_clone = agent_state.inner().clone();
+    let tool_cancellations = agent_state.tool_cancellations().clone();
+    let tool_session_id = request.session_id.clone();
+    let agent_state_for_cancel = agent_state.inner().clone();
+    let cancel_context = request
+        .session_id
+        .clone()
+        .zip(turn_start)
+        .map(|(sid, start)| (sid, start, request.message.clone(), request.model.clone()));
+
+    // 如果会话已桥接到聊天机器人，完成后转发本轮对话
+    let bridge = request.session_id.as_ref().and_then(|sid| {
+        app_handle
+            .try_state::<ChatBridgeState>()
+            .filter(|b| b.0.is_bridged(sid))
+            .map(|b| (b.0.clone(), sid.clone(), request.message.clone()))
+    });
+
+    let coalesce_config = app_state.read().await.config.stream_coalesce.clone();
 
     // 在后台任务中处理流式响应
-    let event_name_clone = event_name.clone();
+    let stream_id_clone = stream_id.clone();
     eprintln!(
-        "[native_agent_chat_stream] 启动后台任务, event_name={}",
-        event_name_clone
+        "[native_agent_chat_stream] 启动后台任务, stream_id={}",
+        stream_id_clone
     );
     tauri::async_runtime::spawn(async move {
-        eprintln!("[native_agent_chat_stream] 后台任务开始执行");
-
         // 创建工具循环引擎（使用共享的 tool_registry）
-        let tool_loop_engine = ToolLoopEngine::new(tool_registry);
-        eprintln!("[native_agent_chat_stream] 工具循环引擎创建成功");
+        let tool_loop_engine = ToolLoopEngine::new(tool_registry)
+            .with_cancellations(tool_cancellations, tool_session_id.as_deref());
 
-        let (tx, mut rx) = mpsc::channel::<StreamEvent>(100);
+        let (tx, rx) = mpsc::channel::<StreamEvent>(100);
 
         // 使用 agent_state 的方法（共享 sessions）
         eprintln!(
@@ -225,45 +279,240 @@ pub async fn native_agent_chat_stream(
                 .await
         });
 
-        eprintln!("[native_agent_chat_stream] 开始接收流式事件...");
-        // 注意：不要在收到 Done 事件后立即 break，因为工具循环可能还在执行
-        // 继续接收直到 channel 关闭（stream_task 完成）
-        while let Some(event) = rx.recv().await {
-            eprintln!("[native_agent_chat_stream] 收到事件: {:?}", event);
-            tracing::debug!(
-                "[NativeAgent] 收到流式事件: {:?}, 发送到: {}",
-                event,
-                event_name_clone
-            );
-            if let Err(e) = app_handle.emit(&event_name_clone, &event) {
-                tracing::error!("[NativeAgent] 发送事件失败: {}", e);
-                eprintln!("[native_agent_chat_stream] 发送事件失败: {}", e);
-                break;
+        let outcome = relay_stream(
+            &stream_id_clone,
+            &on_event,
+            rx,
+            cancel_rx,
+            stream_task,
+            &coalesce_config,
+        )
+        .await;
+        agent_state_for_cancel.unregister_stream(&stream_id_clone);
+
+        match outcome {
+            RelayOutcome::Cancelled { mode, partial } => {
+                tracing::info!("[NativeAgent] 用户中断生成: mode={:?}", mode);
+                if let Some((sid, turn_start, user_message, model)) = &cancel_context {
+                    if let Err(e) = agent_state_for_cancel.settle_cancelled_turn(
+                        sid,
+                        *turn_start,
+                        user_message,
+                        &partial,
+                        mode,
+                        model.as_deref(),
+                    ) {
+                        tracing::warn!("[NativeAgent] 处理中断的对话失败: {}", e);
+                    }
+                }
+                let _ = on_event.send(TaggedStreamEvent {
+                    stream_id: stream_id_clone,
+                    event: StreamEvent::Cancelled { mode },
+                });
+            }
+            RelayOutcome::Finished(result) => {
+                eprintln!("[native_agent_chat_stream] stream_task 完成: {:?}", result);
+                if let (Some((bridge, sid, user_message)), Ok(stream_result)) = (bridge, &result) {
+                    bridge.forward(&sid, &format!("👤 {}", user_message)).await;
+                    bridge.forward(&sid, &stream_result.content).await;
+                }
             }
-            tracing::debug!("[NativeAgent] 事件发送成功");
+        }
+        eprintln!("[native_agent_chat_stream] 后台任务结束");
+    });
+
+    Ok(stream_id)
+}
 
-            // 只在 Error 时 break，Done 不 break 因为工具循环可能还会发送更多事件
-            if matches!(event, StreamEvent::Error { .. }) {
-                tracing::info!("[NativeAgent] 流式响应错误，停止接收");
-                eprintln!("[native_agent_chat_stream] 流式响应错误");
+/// 流式对话的结束方式
+enum RelayOutcome {
+    /// 用户中断，附带当前这次 API 响应已生成的部分回复
+    Cancelled { mode: CancelMode, partial: String },
+    /// 对话结束（成功或失败）
+    Finished(Result<StreamResult, AgentError>),
+}
+
+/// 将后台对话任务的事件转发到前端 Channel，直到对话结束或收到取消信号
+///
+/// 中断时中止对话任务并返回部分回复；`Cancelled` 事件由调用方在处理完历史后推送。
+async fn relay_stream(
+    stream_id: &str,
+    on_event: &Channel<TaggedStreamEvent>,
+    mut rx: mpsc::Receiver<StreamEvent>,
+    mut cancel_rx: mpsc::Receiver<CancelMode>,
+    stream_task: tokio::task::JoinHandle<Result<StreamResult, AgentError>>,
+    coalesce_config: &StreamCoalesceConfig,
+) -> RelayOutcome {
+    // 为事件附加流 ID 后推送
+    let send = |event: StreamEvent| {
+        on_event.send(TaggedStreamEvent {
+            stream_id: stream_id.to_string(),
+            event,
+        })
+    };
+
+    // 当前这次 API 响应已生成的文本（工具开始执行时已写入历史，清空）
+    let mut partial = String::new();
+    let mut cancel_mode = None;
+    // 合并连续的文本片段，减少推送到前端的事件数
+    let mut coalescer = StreamCoalescer::new(coalesce_config);
+    // 注意：不要在收到 Done 事件后立即 break，因为工具循环可能还在执行
+    // 继续接收直到 channel 关闭（stream_task 完成）或收到取消信号
+    'recv: loop {
+        let deadline = coalescer.deadline();
+        let flush_at = deadline.unwrap_or_else(tokio::time::Instant::now);
+        let event = tokio::select! {
+            event = rx.recv() => event,
+            Some(mode) = cancel_rx.recv() => {
+                cancel_mode = Some(mode);
                 break;
             }
+            _ = tokio::time::sleep_until(flush_at), if deadline.is_some() => {
+                if let Some(event) = coalescer.flush() {
+                    let _ = send(event);
+                }
+                continue;
+            }
+        };
+        let Some(event) = event else {
+            break;
+        };
+        match &event {
+            StreamEvent::TextDelta { text } => partial.push_str(text),
+            StreamEvent::ToolStart { .. } => partial.clear(),
+            _ => {}
         }
-        eprintln!("[native_agent_chat_stream] channel 关闭，事件接收完成");
-
-        eprintln!("[native_agent_chat_stream] 等待 stream_task 完成...");
-        match stream_task.await {
-            Ok(result) => {
-                eprintln!("[native_agent_chat_stream] stream_task 完成: {:?}", result);
+        tracing::debug!(
+            "[NativeAgent] 收到流式事件: {:?}, stream={}",
+            event,
+            stream_id
+        );
+        let is_error = matches!(event, StreamEvent::Error { .. });
+        for event in coalescer.push(event) {
+            if let Err(e) = send(event) {
+                tracing::error!("[NativeAgent] 发送事件失败: {}", e);
+                break 'recv;
             }
-            Err(e) => {
-                eprintln!("[native_agent_chat_stream] stream_task 错误: {}", e);
+        }
+
+        // 只在 Error 时 break，Done 不 break 因为工具循环可能还会发送更多事件
+        if is_error {
+            tracing::info!("[NativeAgent] 流式响应错误，停止接收");
+            break;
+        }
+    }
+    // 推送尚未发出的缓冲文本（中断时也先于 Cancelled 事件）
+    if let Some(event) = coalescer.flush() {
+        let _ = send(event);
+    }
+
+    if let Some(mode) = cancel_mode {
+        stream_task.abort();
+        let _ = stream_task.await;
+        // 任务中止前已发出但尚未转发的文本也属于部分回复
+        while let Ok(event) = rx.try_recv() {
+            match event {
+                StreamEvent::TextDelta { text } => partial.push_str(&text),
+                StreamEvent::ToolStart { .. } => partial.clear(),
+                _ => {}
             }
         }
-        eprintln!("[native_agent_chat_stream] 后台任务结束");
-    });
+        return RelayOutcome::Cancelled { mode, partial };
+    }
 
-    Ok(())
+    RelayOutcome::Finished(match stream_task.await {
+        Ok(result) => result,
+        Err(e) if e.is_cancelled() => Err(AgentError::Cancelled),
+        Err(e) => Err(AgentError::Other(format!("对话任务异常: {}", e))),
+    })
+}
+
+/// 中断流式对话
+///
+/// `mode` 为 `keep` 时部分回复写入历史并标记为 truncated，为 `discard` 时本轮对话整体丢弃。
+/// 返回是否找到进行中的对话。
+#[tauri::command]
+pub fn native_agent_cancel_stream(
+    agent_state: State<'_, NativeAgentState>,
+    stream_id: String,
+    mode: CancelMode,
+) -> bool {
+    agent_state.cancel_stream(&stream_id, mode)
+}
+
+/// 列出进行中的流式对话（含后台任务）
+#[tauri::command]
+pub fn native_agent_list_streams(agent_state: State<'_, NativeAgentState>) -> Vec<StreamInfo> {
+    agent_state.list_streams()
+}
+
+/// 查询流式对话，已结束时返回 None
+#[tauri::command]
+pub fn native_agent_get_stream(
+    agent_state: State<'_, NativeAgentState>,
+    stream_id: String,
+) -> Option<StreamInfo> {
+    agent_state.get_stream(&stream_id)
+}
+
+/// 取消运行中的单个工具调用，对话继续进行
+///
+/// Agent 收到"用户取消了该工具调用"的工具结果。工具调用 ID 只在会话内唯一，
+/// 需同时传入会话 ID（无会话的对话不传）。返回是否找到运行中的工具调用。
+#[tauri::command]
+pub fn native_agent_cancel_tool(
+    agent_state: State<'_, NativeAgentState>,
+    session_id: Option<String>,
+    tool_id: String,
+) -> bool {
+    agent_state
+        .tool_cancellations()
+        .cancel(session_id.as_deref(), &tool_id)
+}
+
+/// 获取 WASM 插件的加载状态
+#[tauri::command]
+pub fn native_agent_list_wasm_plugins(
+    agent_state: State<'_, NativeAgentState>,
+) -> Vec<WasmPluginInfo> {
+    agent_state.wasm_plugins().list()
+}
+
+/// 重新扫描插件目录并加载 WASM 插件（新增或更新插件文件后调用）
+#[tauri::command]
+pub async fn native_agent_reload_wasm_plugins(
+    agent_state: State<'_, NativeAgentState>,
+) -> Result<Vec<WasmPluginInfo>, String> {
+    let host = agent_state.wasm_plugins().clone();
+    tokio::task::spawn_blocking(move || host.reload())
+        .await
+        .map_err(|e| format!("加载 WASM 插件失败: {}", e))
+}
+
+/// 处理剪贴板粘贴内容（文本 + 图片），返回按顺序排列的消息草稿
+#[tauri::command]
+pub fn native_agent_prepare_paste(payload: PastePayload) -> Result<MessageDraft, String> {
+    prepare_paste(&payload)
+}
+
+#[tauri::command]
+pub async fn native_agent_generate_image(
+    agent_state: State<'_, NativeAgentState>,
+    app_state: State<'_, AppState>,
+    prompt: String,
+    size: Option<String>,
+    model: Option<String>,
+) -> Result<ImageGenerationResult, AgentError> {
+    tracing::info!(
+        "[NativeAgent] 生成图片: prompt_len={}, size={:?}, model={:?}",
+        prompt.len(),
+        size,
+        model
+    );
+
+    ensure_agent_initialized(agent_state.inner(), app_state.inner(), false).await?;
+
+    Ok(agent_state.generate_image(&prompt, size, model).await?)
 }
 
 #[tauri::command]
@@ -271,7 +520,7 @@ pub async fn native_agent_create_session(
     agent_state: State<'_, NativeAgentState>,
     model: Option<String>,
     system_prompt: Option<String>,
-) -> Result<String, String> {
+) -> Result<String, AgentError> {
     agent_state.create_session(model, system_prompt)
 }
 
@@ -279,7 +528,7 @@ pub async fn native_agent_create_session(
 pub async fn native_agent_get_session(
     agent_state: State<'_, NativeAgentState>,
     session_id: String,
-) -> Result<Option<AgentSession>, String> {
+) -> Result<Option<AgentSession>, AgentError> {
     agent_state.get_session(&session_id)
 }
 
@@ -291,9 +540,651 @@ pub async fn native_agent_delete_session(
     Ok(agent_state.delete_session(&session_id))
 }
 
+/// 列出会话（按标签、文件夹、归档和置顶状态筛选，默认不含已归档的会话，置顶的会话在前）
 #[tauri::command]
 pub async fn native_agent_list_sessions(
     agent_state: State<'_, NativeAgentState>,
+    filter: Option<SessionFilter>,
 ) -> Result<Vec<AgentSession>, String> {
-    Ok(agent_state.list_sessions())
+    let sessions = agent_state.list_sessions();
+    Ok(session_meta::filter_sessions(
+        sessions,
+        &filter.unwrap_or_default(),
+    ))
+}
+
+/// 修改会话标签、文件夹、置顶和归档状态（未设置的字段保持不变）
+#[tauri::command]
+pub fn native_agent_update_session_meta(
+    agent_state: State<'_, NativeAgentState>,
+    session_id: String,
+    update: SessionMetaUpdate,
+) -> Result<(), AgentError> {
+    agent_state.update_session_meta(&session_id, &update)
+}
+
+/// 列出会话文件夹及其中的会话数
+#[tauri::command]
+pub fn native_agent_list_session_folders(
+    agent_state: State<'_, NativeAgentState>,
+) -> Vec<SessionFolder> {
+    session_meta::list_folders(&agent_state.list_sessions())
+}
+
+/// 设置工具严格模式（strict function calling + 参数 schema 校验）
+#[tauri::command]
+pub fn native_agent_set_strict_tools(agent_state: State<'_, NativeAgentState>, enabled: bool) {
+    tracing::info!("[NativeAgent] 工具严格模式: {}", enabled);
+    agent_state.set_strict_tools(enabled);
+}
+
+/// 设置会话使用的 Provider 配置档案（profile 为空时使用激活的档案）
+#[tauri::command]
+pub fn native_agent_set_session_profile(
+    agent_state: State<'_, NativeAgentState>,
+    session_id: String,
+    profile: Option<String>,
+) -> Result<bool, AgentError> {
+    let profile = profile.filter(|p| !p.trim().is_empty());
+    tracing::info!(
+        "[NativeAgent] 会话 {} 使用配置档案: {:?}",
+        session_id,
+        profile
+    );
+    agent_state.set_session_profile(&session_id, profile)
+}
+
+/// 设置会话关联的 git 仓库（repo_path 为空时取消关联），返回规范化后的仓库根目录
+#[tauri::command]
+pub async fn native_agent_set_session_repo(
+    agent_state: State<'_, NativeAgentState>,
+    session_id: String,
+    repo_path: Option<String>,
+) -> Result<Option<String>, AgentError> {
+    let repo_path = repo_path.filter(|p| !p.trim().is_empty());
+    let repo_path = agent_state.set_session_repo(&session_id, repo_path).await?;
+    tracing::info!(
+        "[NativeAgent] 会话 {} 关联仓库: {:?}",
+        session_id,
+        repo_path
+    );
+    Ok(repo_path)
+}
+
+/// 设置会话 `http_request` 工具允许访问的域名（为空时不注册该工具），返回规范化后的白名单
+#[tauri::command]
+pub fn native_agent_set_session_http_domains(
+    agent_state: State<'_, NativeAgentState>,
+    session_id: String,
+    domains: Vec<String>,
+) -> Result<Vec<String>, AgentError> {
+    let domains = agent_state.set_session_http_domains(&session_id, &domains)?;
+    tracing::info!(
+        "[NativeAgent] 会话 {} 允许访问的域名: {:?}",
+        session_id,
+        domains
+    );
+    Ok(domains)
+}
+
+/// 设置会话的工具权限模式（auto / approve / read-only，为空时使用全局配置）
+#[tauri::command]
+pub fn native_agent_set_session_permission_mode(
+    agent_state: State<'_, NativeAgentState>,
+    session_id: String,
+    mode: Option<PermissionMode>,
+) -> Result<(), AgentError> {
+    agent_state.set_session_permission_mode(&session_id, mode)?;
+    tracing::info!("[NativeAgent] 会话 {} 工具权限模式: {:?}", session_id, mode);
+    Ok(())
+}
+
+/// 分析会话并返回压缩建议（话题转移、token 膨胀）
+#[tauri::command]
+pub async fn native_agent_lint_session(
+    agent_state: State<'_, NativeAgentState>,
+    session_id: String,
+) -> Result<Vec<SessionLintSuggestion>, AgentError> {
+    let session = agent_state
+        .get_session(&session_id)?
+        .ok_or(AgentError::SessionNotFound(session_id))?;
+    Ok(lint_session(&session))
+}
+
+/// 获取会话配额使用情况和归档建议
+#[tauri::command]
+pub fn native_agent_get_session_quota(
+    agent_state: State<'_, NativeAgentState>,
+) -> SessionQuotaStatus {
+    agent_state.session_quota_status()
+}
+
+/// 归档会话（写入 ~/.proxycast/sessions/archive 后从内存移除），返回归档文件路径
+#[tauri::command]
+pub fn native_agent_archive_sessions(
+    agent_state: State<'_, NativeAgentState>,
+    session_ids: Vec<String>,
+) -> Result<Vec<String>, String> {
+    archive_sessions(agent_state.inner(), &session_ids)
+}
+
+/// 读取会话记录（未启用会话记录或会话没有记录时返回空列表）
+#[tauri::command]
+pub fn native_agent_get_transcript(
+    agent_state: State<'_, NativeAgentState>,
+    session_id: String,
+) -> Result<Vec<TranscriptEntry>, String> {
+    agent_state.transcripts().read(&session_id)
+}
+
+/// 导出会话记录（未指定路径时写入 ~/.proxycast/transcripts/exports），返回导出文件路径
+#[tauri::command]
+pub fn native_agent_export_transcript(
+    agent_state: State<'_, NativeAgentState>,
+    session_id: String,
+    path: Option<String>,
+) -> Result<String, String> {
+    let transcripts = agent_state.transcripts();
+    let path = match path {
+        Some(path) => std::path::PathBuf::from(path),
+        None => transcripts.default_export_path(&session_id),
+    };
+    let count = transcripts.export(&session_id, &path)?;
+    tracing::info!(
+        "[NativeAgent] 导出会话记录: session={}, entries={}, path={}",
+        session_id,
+        count,
+        path.display()
+    );
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// 导出会话为 Markdown 或单文件 HTML（未指定路径时写入 ~/.proxycast/sessions/exports），返回导出文件路径
+///
+/// `include_stats` 为 true 时附带消息数和估算 token 数，启用了会话记录时还附带实际 token 用量
+#[tauri::command]
+pub fn native_agent_export_session(
+    agent_state: State<'_, NativeAgentState>,
+    session_id: String,
+    format: SessionExportFormat,
+    path: Option<String>,
+    include_stats: Option<bool>,
+) -> Result<String, AgentError> {
+    let session = agent_state
+        .get_session(&session_id)?
+        .ok_or_else(|| AgentError::SessionNotFound(session_id.clone()))?;
+
+    let stats = include_stats.unwrap_or(false).then(|| {
+        let mut stats = SessionExportStats::from_session(&session);
+        let usages: Vec<_> = agent_state
+            .transcripts()
+            .read(&session_id)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|entry| entry.usage)
+            .collect();
+        if !usages.is_empty() {
+            stats.input_tokens = Some(usages.iter().map(|u| u.input_tokens).sum());
+            stats.output_tokens = Some(usages.iter().map(|u| u.output_tokens).sum());
+        }
+        stats
+    });
+
+    let path = match path {
+        Some(path) => std::path::PathBuf::from(path),
+        None => session_export::default_export_path(&session_id, format)?,
+    };
+    let content = session_export::render(&session, format, stats.as_ref());
+    write_export_file(&path, &content)?;
+    tracing::info!(
+        "[NativeAgent] 导出会话: session={}, format={:?}, path={}",
+        session_id,
+        format,
+        path.display()
+    );
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// 推送批量操作进度
+fn emit_bulk_progress(app_handle: &tauri::AppHandle) -> impl FnMut(BulkProgress) + '_ {
+    move |progress| {
+        let _ = app_handle.emit(SESSION_BULK_PROGRESS_EVENT, &progress);
+    }
+}
+
+/// 批量删除会话（任一会话不存在时整批失败），返回删除的会话数
+#[tauri::command]
+pub fn sessions_bulk_delete(
+    app_handle: tauri::AppHandle,
+    agent_state: State<'_, NativeAgentState>,
+    ids: Vec<String>,
+) -> Result<usize, String> {
+    agent_state.bulk_delete_sessions(&ids, &mut emit_bulk_progress(&app_handle))
+}
+
+/// 批量添加/移除会话标签，返回标签有变化的会话数
+#[tauri::command]
+pub fn sessions_bulk_tag(
+    app_handle: tauri::AppHandle,
+    agent_state: State<'_, NativeAgentState>,
+    ids: Vec<String>,
+    add: Option<Vec<String>>,
+    remove: Option<Vec<String>>,
+) -> Result<usize, String> {
+    agent_state.bulk_tag_sessions(
+        &ids,
+        &add.unwrap_or_default(),
+        &remove.unwrap_or_default(),
+        &mut emit_bulk_progress(&app_handle),
+    )
+}
+
+/// 批量导出会话到 JSON 文件（未指定路径时写入 ~/.proxycast/sessions/exports）
+#[tauri::command]
+pub fn sessions_bulk_export(
+    app_handle: tauri::AppHandle,
+    agent_state: State<'_, NativeAgentState>,
+    ids: Vec<String>,
+    path: Option<String>,
+) -> Result<BulkExportResult, String> {
+    let sessions = agent_state.bulk_collect_sessions(&ids, &mut emit_bulk_progress(&app_handle))?;
+    let path = match path {
+        Some(path) => std::path::PathBuf::from(path),
+        None => default_export_path()?,
+    };
+    write_export(&path, sessions)
+}
+
+/// 列出 Agent 计划的后续任务（可按会话过滤）
+#[tauri::command]
+pub fn native_agent_list_scheduled_followups(
+    agent_state: State<'_, NativeAgentState>,
+    session_id: Option<String>,
+) -> Vec<FollowupTask> {
+    agent_state.followups().list(session_id.as_deref())
+}
+
+/// 批准或拒绝 Agent 计划的后续任务
+#[tauri::command]
+pub fn native_agent_review_scheduled_followup(
+    agent_state: State<'_, NativeAgentState>,
+    id: String,
+    approved: bool,
+) -> Result<FollowupTask, String> {
+    agent_state.followups().review(&id, approved)
+}
+
+/// 取消尚未执行的后续任务
+#[tauri::command]
+pub fn native_agent_cancel_scheduled_followup(
+    agent_state: State<'_, NativeAgentState>,
+    id: String,
+) -> Result<FollowupTask, String> {
+    agent_state.followups().cancel(&id)
+}
+
+/// 列出 Agent 提出的补丁（可按会话过滤）
+#[tauri::command]
+pub fn native_agent_list_patch_proposals(
+    agent_state: State<'_, NativeAgentState>,
+    session_id: Option<String>,
+) -> Vec<PatchProposal> {
+    agent_state.patches().list(session_id.as_deref())
+}
+
+/// 批准（应用到仓库）或拒绝 Agent 提出的补丁
+#[tauri::command]
+pub async fn native_agent_review_patch_proposal(
+    agent_state: State<'_, NativeAgentState>,
+    id: String,
+    approved: bool,
+) -> Result<PatchProposal, String> {
+    let proposal = agent_state.patches().review(&id, approved)?;
+    if !approved {
+        return Ok(proposal);
+    }
+    let outcome = crate::agent::tools::git::apply_patch(
+        std::path::Path::new(&proposal.repo_path),
+        &proposal.patch,
+    )
+    .await;
+    if let Err(e) = &outcome {
+        tracing::warn!("[NativeAgent] 应用补丁 {} 失败: {}", id, e);
+    }
+    agent_state
+        .patches()
+        .finish(&id, outcome)
+        .ok_or_else(|| format!("补丁不存在: {}", id))
+}
+
+/// 列出 Skill 脚本运行请求（可按会话过滤）
+#[tauri::command]
+pub fn native_agent_list_script_requests(
+    agent_state: State<'_, NativeAgentState>,
+    session_id: Option<String>,
+) -> Vec<ScriptRunRequest> {
+    agent_state.script_reviews().list(session_id.as_deref())
+}
+
+/// 批准或拒绝 Skill 脚本运行（批准后由等待中的工具调用运行脚本）
+#[tauri::command]
+pub fn native_agent_review_script_request(
+    agent_state: State<'_, NativeAgentState>,
+    id: String,
+    approved: bool,
+) -> Result<ScriptRunRequest, String> {
+    agent_state.script_reviews().review(&id, approved)
+}
+
+/// 列出 approve 模式下的工具调用请求（可按会话过滤）
+#[tauri::command]
+pub fn native_agent_list_tool_approvals(
+    agent_state: State<'_, NativeAgentState>,
+    session_id: Option<String>,
+) -> Vec<ToolApprovalRequest> {
+    agent_state.tool_approvals().list(session_id.as_deref())
+}
+
+/// 批准或拒绝工具调用（批准后由等待中的工具调用继续执行）
+#[tauri::command]
+pub fn native_agent_review_tool_approval(
+    agent_state: State<'_, NativeAgentState>,
+    id: String,
+    approved: bool,
+) -> Result<ToolApprovalRequest, String> {
+    agent_state.tool_approvals().review(&id, approved)
+}
+
+/// 列出定时任务及下一次执行时间
+#[tauri::command]
+pub fn native_agent_list_scheduled_tasks(
+    agent_state: State<'_, NativeAgentState>,
+) -> Vec<ScheduledTaskStatus> {
+    agent_state.scheduled_tasks().list(chrono::Local::now())
+}
+
+/// 立即执行定时任务，返回执行中的记录（结果通过事件推送）
+#[tauri::command]
+pub fn native_agent_run_scheduled_task(
+    app_handle: tauri::AppHandle,
+    agent_state: State<'_, NativeAgentState>,
+    name: String,
+) -> Result<ScheduledTaskRun, String> {
+    let task = agent_state
+        .scheduled_tasks()
+        .get(&name)
+        .ok_or_else(|| format!("定时任务不存在: {}", name))?;
+    scheduled_tasks::start_task(&app_handle, task, TaskTrigger::Manual)
+}
+
+/// 获取定时任务的执行记录（按开始时间倒序，可按任务过滤）
+#[tauri::command]
+pub fn native_agent_list_scheduled_task_runs(
+    db: State<'_, DbConnection>,
+    task_name: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<ScheduledTaskRun>, String> {
+    let conn = db.lock().map_err(|e| e.to_string())?;
+    ScheduledTaskDao::list(
+        &conn,
+        task_name.as_deref(),
+        limit.unwrap_or(scheduled_tasks::MAX_RUNS_PER_TASK),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// 编辑会话中指定消息的文本内容
+#[tauri::command]
+pub fn native_agent_edit_message(
+    agent_state: State<'_, NativeAgentState>,
+    session_id: String,
+    index: usize,
+    new_content: String,
+) -> Result<(), AgentError> {
+    agent_state.edit_message(&session_id, index, new_content)
+}
+
+/// 删除会话中指定消息，返回实际删除的消息数（含关联的工具结果）
+#[tauri::command]
+pub fn native_agent_delete_message(
+    agent_state: State<'_, NativeAgentState>,
+    session_id: String,
+    index: usize,
+) -> Result<usize, AgentError> {
+    agent_state.delete_message(&session_id, index)
+}
+
+/// 丢弃最后一轮回答并重新生成
+///
+/// 与 `native_agent_chat_stream` 相同，回答通过 `on_event` 流式推送并执行工具调用，返回流 ID。
+/// 请求失败或以 discard 方式中断时恢复原回答。
+#[tauri::command]
+pub async fn native_agent_regenerate(
+    agent_state: State<'_, NativeAgentState>,
+    app_state: State<'_, AppState>,
+    session_id: String,
+    on_event: Channel<TaggedStreamEvent>,
+    model: Option<String>,
+) -> Result<String, AgentError> {
+    let stream_id = uuid::Uuid::new_v4().to_string();
+    tracing::info!(
+        "[NativeAgent] 重新生成: session={}, stream={}",
+        session_id,
+        stream_id
+    );
+
+    ensure_agent_initialized(agent_state.inner(), app_state.inner(), false).await?;
+    let tool_registry = agent_state.get_tool_registry(Some(&session_id))?;
+    let coalesce_config = app_state.read().await.config.stream_coalesce.clone();
+
+    let turn = agent_state.take_last_answer(&session_id)?;
+    let cancel_rx = agent_state.register_stream(StreamInfo::new(
+        &stream_id,
+        Some(session_id.clone()),
+        model.clone(),
+    ));
+
+    let agent_state = agent_state.inner().clone();
+    let tool_loop_engine = ToolLoopEngine::new(tool_registry)
+        .with_cancellations(agent_state.tool_cancellations().clone(), Some(&session_id));
+    let stream_id_clone = stream_id.clone();
+    tauri::async_runtime::spawn(async move {
+        let (tx, rx) = mpsc::channel::<StreamEvent>(100);
+        let stream_state = agent_state.clone();
+        let sid = session_id.clone();
+        let requested_model = model.clone();
+        let stream_task = tokio::spawn(async move {
+            stream_state
+                .continue_stream_with_tools(&sid, requested_model, tx, &tool_loop_engine)
+                .await
+        });
+
+        let outcome = relay_stream(
+            &stream_id_clone,
+            &on_event,
+            rx,
+            cancel_rx,
+            stream_task,
+            &coalesce_config,
+        )
+        .await;
+        agent_state.unregister_stream(&stream_id_clone);
+
+        let cancelled = match &outcome {
+            RelayOutcome::Cancelled { mode, .. } => Some(*mode),
+            RelayOutcome::Finished(_) => None,
+        };
+        let settled = match outcome {
+            RelayOutcome::Cancelled {
+                mode: CancelMode::Keep,
+                partial,
+            } => agent_state.settle_cancelled_turn(
+                &session_id,
+                turn.user_index,
+                &turn.user_message,
+                &partial,
+                CancelMode::Keep,
+                model.as_deref(),
+            ),
+            RelayOutcome::Cancelled { .. } | RelayOutcome::Finished(Err(_)) => {
+                agent_state.restore_answer(&session_id, turn)
+            }
+            RelayOutcome::Finished(Ok(_)) => Ok(()),
+        };
+        if let Err(e) = settled {
+            tracing::warn!("[NativeAgent] 处理重新生成结果失败: {}", e);
+        }
+        if let Some(mode) = cancelled {
+            let _ = on_event.send(TaggedStreamEvent {
+                stream_id: stream_id_clone,
+                event: StreamEvent::Cancelled { mode },
+            });
+        }
+    });
+
+    Ok(stream_id)
+}
+
+/// 预览发送一条消息时模型实际收到的请求（含检索上下文、长期记忆、图片、附件和工具定义），
+/// 不发送也不修改会话
+#[tauri::command]
+pub async fn native_agent_preview_request(
+    agent_state: State<'_, NativeAgentState>,
+    app_state: State<'_, AppState>,
+    session_id: String,
+    message: String,
+    model: Option<String>,
+    retrieval_collection: Option<String>,
+    images: Option<Vec<ImageInputParam>>,
+    audio: Option<Vec<AudioData>>,
+    attachments: Option<Vec<AttachmentData>>,
+) -> Result<RequestPreview, AgentError> {
+    ensure_agent_initialized(agent_state.inner(), app_state.inner(), false).await?;
+
+    let tools = agent_state
+        .get_tool_registry(Some(&session_id))?
+        .list_definitions_api();
+    let context = agent_state
+        .retrieve_knowledge(Some(&session_id), retrieval_collection, &message)
+        .await;
+    let request = NativeChatRequest {
+        session_id: Some(session_id),
+        message,
+        model,
+        images: images.map(|imgs| imgs.into_iter().map(ImageData::from).collect()),
+        audio,
+        attachments,
+        context,
+        stream: true,
+    };
+    agent_state.preview_request(&request, Some(&tools))
+}
+
+/// 使用不同参数回放会话中的某一轮（沙盒执行，不修改原会话历史）
+#[tauri::command]
+pub async fn native_agent_replay_turn(
+    agent_state: State<'_, NativeAgentState>,
+    session_id: String,
+    turn_id: usize,
+    overrides: Option<ReplayOverrides>,
+) -> Result<ReplayResult, AgentError> {
+    agent_state
+        .replay_turn(&session_id, turn_id, overrides.unwrap_or_default())
+        .await
+}
+
+async fn run_session_background_task(
+    agent_state: &NativeAgentState,
+    app_state: &AppState,
+    session_id: &str,
+    task: BackgroundTask,
+) -> Result<String, AgentError> {
+    let session = agent_state
+        .get_session(session_id)?
+        .ok_or_else(|| AgentError::SessionNotFound(session_id.to_string()))?;
+    let config = app_state.read().await.config.background_model.clone();
+    Ok(run_background_task(agent_state, &config, task, &session.messages).await?)
+}
+
+/// 使用后台模型生成会话摘要
+#[tauri::command]
+pub async fn native_agent_summarize_session(
+    agent_state: State<'_, NativeAgentState>,
+    app_state: State<'_, AppState>,
+    session_id: String,
+) -> Result<String, AgentError> {
+    run_session_background_task(
+        agent_state.inner(),
+        app_state.inner(),
+        &session_id,
+        BackgroundTask::Summarize,
+    )
+    .await
+}
+
+/// 使用后台模型生成会话标题
+#[tauri::command]
+pub async fn native_agent_generate_title(
+    agent_state: State<'_, NativeAgentState>,
+    app_state: State<'_, AppState>,
+    session_id: String,
+) -> Result<String, AgentError> {
+    let title = run_session_background_task(
+        agent_state.inner(),
+        app_state.inner(),
+        &session_id,
+        BackgroundTask::Title,
+    )
+    .await?;
+    Ok(title
+        .trim_matches(|c| c == '"' || c == '“' || c == '”')
+        .to_string())
+}
+
+/// 使用后台模型生成后续问题建议
+#[tauri::command]
+pub async fn native_agent_suggest_followups(
+    agent_state: State<'_, NativeAgentState>,
+    app_state: State<'_, AppState>,
+    session_id: String,
+) -> Result<Vec<String>, AgentError> {
+    let output = run_session_background_task(
+        agent_state.inner(),
+        app_state.inner(),
+        &session_id,
+        BackgroundTask::FollowUps,
+    )
+    .await?;
+    Ok(parse_lines(&output))
+}
+
+/// 使用后台模型从会话中提取长期记忆并保存，返回新增的记忆
+#[tauri::command]
+pub async fn native_agent_extract_memories(
+    agent_state: State<'_, NativeAgentState>,
+    app_state: State<'_, AppState>,
+    db: State<'_, DbConnection>,
+    session_id: String,
+) -> Result<Vec<AgentMemory>, AgentError> {
+    let output = run_session_background_task(
+        agent_state.inner(),
+        app_state.inner(),
+        &session_id,
+        BackgroundTask::ExtractMemories,
+    )
+    .await?;
+
+    let store = MemoryStore::new(db.inner().clone());
+    let mut added = Vec::new();
+    for content in parse_lines(&output) {
+        if let Some(memory) = store.add(&content, "auto")? {
+            added.push(memory);
+        }
+    }
+    tracing::info!(
+        "[Background] 会话 {} 提取到 {} 条新记忆",
+        session_id,
+        added.len()
+    );
+    Ok(added)
 }
@@ -6,8 +6,9 @@ use crate::credential::CredentialSyncService;
 use crate::database::dao::provider_pool::ProviderPoolDao;
 use crate::database::DbConnection;
 use crate::models::provider_pool_model::{
-    AddCredentialRequest, CredentialData, CredentialDisplay, HealthCheckResult, OAuthStatus,
-    PoolProviderType, ProviderCredential, ProviderPoolOverview, UpdateCredentialRequest,
+    AddCredentialRequest, CredentialData, CredentialDisplay, CredentialHealth, HealthCheckResult,
+    OAuthStatus, PoolProviderType, ProviderCredential, ProviderPoolOverview,
+    UpdateCredentialRequest,
 };
 use crate::services::provider_pool_service::ProviderPoolService;
 use chrono::Utc;
@@ -576,6 +577,25 @@ pub fn reset_provider_pool_health(
     pool_service.0.reset_health_by_type(&db, &provider_type)
 }
 
+/// 获取指定类型所有凭证的 Key 健康状态（含冷却信息）
+#[tauri::command]
+pub fn get_provider_pool_key_health(
+    db: State<'_, DbConnection>,
+    pool_service: State<'_, ProviderPoolServiceState>,
+    provider_type: String,
+) -> Result<Vec<CredentialHealth>, String> {
+    pool_service.0.get_key_health(&db, &provider_type)
+}
+
+/// 手动解除凭证冷却
+#[tauri::command]
+pub fn clear_provider_pool_cooldown(
+    pool_service: State<'_, ProviderPoolServiceState>,
+    uuid: String,
+) -> bool {
+    pool_service.0.clear_cooldown(&uuid)
+}
+
 /// 执行单个凭证的健康检查
 #[tauri::command]
 pub async fn check_provider_pool_credential_health(
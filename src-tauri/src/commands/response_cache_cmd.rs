@@ -0,0 +1,25 @@
+//! 响应缓存命令
+//!
+//! 查看和清空本地 API Server 的响应缓存
+
+use crate::middleware::ResponseCacheStats;
+use crate::AppState;
+use tauri::State;
+
+/// 获取响应缓存统计
+#[tauri::command]
+pub async fn get_response_cache_stats(
+    state: State<'_, AppState>,
+) -> Result<ResponseCacheStats, String> {
+    let s = state.read().await;
+    Ok(s.response_cache.stats())
+}
+
+/// 清空响应缓存，返回清除的条目数
+#[tauri::command]
+pub async fn clear_response_cache(state: State<'_, AppState>) -> Result<usize, String> {
+    let s = state.read().await;
+    let cleared = s.response_cache.clear();
+    tracing::info!("[RESPONSE_CACHE] 已清空 {} 条缓存", cleared);
+    Ok(cleared)
+}
@@ -13,7 +13,12 @@ pub async fn get_available_routes(
 ) -> Result<RouteListResponse, String> {
     // 获取配置中的服务器地址和默认 Provider
     let config = config::load_config().unwrap_or_default();
-    let base_url = format!("http://{}:{}", config.server.host, config.server.port);
+    let base_url = format!(
+        "{}://{}:{}",
+        config.server.scheme(),
+        config.server.host,
+        config.server.port
+    );
     let default_provider = config.default_provider.clone();
 
     let routes = pool_service
@@ -58,7 +63,12 @@ pub async fn get_route_curl_examples(
     pool_service: tauri::State<'_, ProviderPoolServiceState>,
 ) -> Result<Vec<crate::models::route_model::CurlExample>, String> {
     let config = config::load_config().unwrap_or_default();
-    let base_url = format!("http://{}:{}", config.server.host, config.server.port);
+    let base_url = format!(
+        "{}://{}:{}",
+        config.server.scheme(),
+        config.server.host,
+        config.server.port
+    );
     let default_provider = config.default_provider.clone();
 
     let routes = pool_service
@@ -0,0 +1,21 @@
+//! Agent 会话同步命令
+
+use crate::agent::NativeAgentState;
+use crate::commands::native_agent_cmd::ensure_agent_initialized;
+use crate::services::session_sync_service::{self, SessionSyncReport};
+use crate::AppState;
+use tauri::State;
+
+/// 立即与配置的 WebDAV / S3 存储同步会话
+#[tauri::command]
+pub async fn session_sync_now(
+    app_state: State<'_, AppState>,
+    agent_state: State<'_, NativeAgentState>,
+) -> Result<SessionSyncReport, String> {
+    let config = app_state.read().await.config.session_sync.clone();
+    if !config.enabled {
+        return Err("会话同步未启用".to_string());
+    }
+    ensure_agent_initialized(agent_state.inner(), app_state.inner(), false).await?;
+    session_sync_service::sync_now(agent_state.inner(), &config).await
+}
@@ -1,6 +1,6 @@
 use crate::database::dao::skills::SkillDao;
 use crate::database::DbConnection;
-use crate::models::{AppType, Skill, SkillRepo, SkillState};
+use crate::models::{AppType, Skill, SkillRepo, SkillState, SkillUpdateInfo, SkillUsageStats};
 use crate::services::skill_service::SkillService;
 use chrono::Utc;
 use std::path::Path;
@@ -55,6 +55,49 @@ pub async fn get_installed_proxycast_skills() -> Result<Vec<String>, String> {
     Ok(scan_installed_skills(&skills_dir))
 }
 
+/// 获取 ProxyCast Skills 的使用统计
+///
+/// 包含所有已安装的 Skill（未使用过的次数为 0）和已卸载但有使用记录的 Skill，
+/// 按最后使用时间升序排列（从未使用的在前），便于清理不常用的 Skill。
+#[tauri::command]
+pub fn skill_stats(db: State<'_, DbConnection>) -> Result<Vec<SkillUsageStats>, String> {
+    let home = dirs::home_dir().ok_or_else(|| "Failed to get home directory".to_string())?;
+    let installed = scan_installed_skills(&home.join(".proxycast").join("skills"));
+    let usage = {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        SkillDao::get_skill_usage(&conn).map_err(|e| e.to_string())?
+    };
+    Ok(merge_skill_usage(installed, usage))
+}
+
+/// 合并已安装的 Skill 列表和使用记录
+fn merge_skill_usage(installed: Vec<String>, usage: Vec<SkillUsageStats>) -> Vec<SkillUsageStats> {
+    let mut stats: Vec<SkillUsageStats> = usage
+        .into_iter()
+        .map(|mut s| {
+            s.installed = installed.contains(&s.directory);
+            s
+        })
+        .collect();
+    for directory in installed {
+        if !stats.iter().any(|s| s.directory == directory) {
+            stats.push(SkillUsageStats {
+                directory,
+                installed: true,
+                reference_count: 0,
+                invoke_count: 0,
+                last_used_at: None,
+            });
+        }
+    }
+    stats.sort_by(|a, b| {
+        a.last_used_at
+            .cmp(&b.last_used_at)
+            .then_with(|| a.directory.cmp(&b.directory))
+    });
+    stats
+}
+
 pub struct SkillServiceState(pub Arc<SkillService>);
 
 fn get_skill_key(app_type: &AppType, directory: &str) -> String {
@@ -218,6 +261,59 @@ pub fn uninstall_skill_for_app(
     Ok(true)
 }
 
+/// 检查已安装技能的更新（比较 SKILL.md 版本号或 git 上游）
+#[tauri::command]
+pub async fn skill_check_updates(
+    db: State<'_, DbConnection>,
+    skill_service: State<'_, SkillServiceState>,
+    app: String,
+) -> Result<Vec<SkillUpdateInfo>, String> {
+    let app_type: AppType = app.parse().map_err(|e: String| e)?;
+    let repos = {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        SkillDao::get_skill_repos(&conn).map_err(|e| e.to_string())?
+    };
+
+    skill_service
+        .0
+        .check_updates(&app_type, &repos)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 更新技能到最新版本，返回更新信息（含更新说明）
+#[tauri::command]
+pub async fn skill_update(
+    db: State<'_, DbConnection>,
+    skill_service: State<'_, SkillServiceState>,
+    app: String,
+    name: String,
+) -> Result<SkillUpdateInfo, String> {
+    let app_type: AppType = app.parse().map_err(|e: String| e)?;
+    let repos = {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        SkillDao::get_skill_repos(&conn).map_err(|e| e.to_string())?
+    };
+
+    let info = skill_service
+        .0
+        .update_skill(&app_type, &repos, &name)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let key = get_skill_key(&app_type, &name);
+    let state = SkillState {
+        installed: true,
+        installed_at: Utc::now(),
+    };
+    {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        SkillDao::update_skill_state(&conn, &key, &state).map_err(|e| e.to_string())?;
+    }
+
+    Ok(info)
+}
+
 #[tauri::command]
 pub fn get_skill_repos(db: State<'_, DbConnection>) -> Result<Vec<SkillRepo>, String> {
     let conn = db.lock().map_err(|e| e.to_string())?;
@@ -249,6 +345,32 @@ mod tests {
     use std::collections::HashSet;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_merge_skill_usage_lists_unused_first() {
+        let used = SkillUsageStats {
+            directory: "pdf".to_string(),
+            installed: false,
+            reference_count: 3,
+            invoke_count: 1,
+            last_used_at: chrono::DateTime::from_timestamp(1_700_000_000, 0),
+        };
+        let removed = SkillUsageStats {
+            directory: "old".to_string(),
+            last_used_at: chrono::DateTime::from_timestamp(1_600_000_000, 0),
+            ..used.clone()
+        };
+        let stats = merge_skill_usage(
+            vec!["pdf".to_string(), "docx".to_string()],
+            vec![used, removed],
+        );
+        let order: Vec<_> = stats
+            .iter()
+            .map(|s| (s.directory.as_str(), s.installed))
+            .collect();
+        assert_eq!(order, vec![("docx", true), ("old", false), ("pdf", true)]);
+        assert_eq!(stats[0].reference_count, 0);
+    }
+
     /// 生成有效的 Skill 目录名（字母数字和连字符）
     fn skill_name_strategy() -> impl Strategy<Value = String> {
         "[a-z][a-z0-9-]{0,20}".prop_filter("non-empty", |s| !s.is_empty())
@@ -3,8 +3,9 @@
 //! 提供请求日志、统计数据和 Token 追踪的 Tauri 命令
 
 use crate::telemetry::{
-    ModelStats, ModelTokenStats, ProviderStats, ProviderTokenStats, RequestLog, RequestLogger,
-    RequestStatus, StatsAggregator, StatsSummary, TimeRange, TokenStatsSummary, TokenTracker,
+    ModelStats, ModelTokenStats, ProviderStats, ProviderTokenStats, RequestLog, RequestLogQuery,
+    RequestLogger, RequestStatus, StatsAggregator, StatsSummary, TimeRange, TokenStatsSummary,
+    TokenTracker,
 };
 use crate::ProviderType;
 use chrono::{DateTime, Utc};
@@ -118,6 +119,17 @@ pub async fn get_request_logs(
     Ok(logs)
 }
 
+/// 按条件查询请求日志（流量查看器）
+///
+/// 支持按 Provider、模型、状态、方法、路径、时间范围和最小耗时过滤，结果按时间倒序分页返回
+#[tauri::command]
+pub async fn query_request_logs(
+    state: tauri::State<'_, TelemetryState>,
+    query: RequestLogQuery,
+) -> Result<Vec<RequestLog>, String> {
+    Ok(state.logger.query(&query))
+}
+
 /// 获取单个请求日志详情
 #[tauri::command]
 pub async fn get_request_log_detail(
@@ -148,7 +160,7 @@ pub struct TimeRangeParam {
 }
 
 impl TimeRangeParam {
-    fn to_time_range(&self) -> Result<Option<TimeRange>, String> {
+    pub(crate) fn to_time_range(&self) -> Result<Option<TimeRange>, String> {
         if let Some(preset) = &self.preset {
             let range = match preset.as_str() {
                 "1h" => TimeRange::last_hours(1),
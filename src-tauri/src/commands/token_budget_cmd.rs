@@ -0,0 +1,19 @@
+//! Token 预算命令
+//!
+//! 查看各预算在当前周期的用量和剩余额度
+
+use crate::database::DbConnection;
+use crate::services::token_budget_service::TokenBudgetStatus;
+use crate::AppState;
+use tauri::State;
+
+/// 获取所有 Token 预算的当前状态
+#[tauri::command]
+pub async fn get_token_budget_status(
+    state: State<'_, AppState>,
+    db: State<'_, DbConnection>,
+) -> Result<Vec<TokenBudgetStatus>, String> {
+    let budget = state.read().await.token_budget.clone();
+    let conn = db.lock().map_err(|e| e.to_string())?;
+    budget.status(&conn)
+}
@@ -0,0 +1,15 @@
+//! 上游健康命令
+//!
+//! 为状态页探测各上游 Provider 的连通性，返回与 `/healthz?probe=true` 相同的数据
+
+use crate::database::DbConnection;
+use crate::services::upstream_probe_service::{self, UpstreamHealthReport};
+use tauri::State;
+
+/// 探测所有上游 Provider 的状态和延迟
+#[tauri::command]
+pub async fn probe_upstream_health(
+    db: State<'_, DbConnection>,
+) -> Result<UpstreamHealthReport, String> {
+    upstream_probe_service::probe_upstreams(&db).await
+}
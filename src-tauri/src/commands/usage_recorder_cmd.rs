@@ -0,0 +1,27 @@
+//! 统一用量命令
+//!
+//! 查询 NativeAgent、Goose Agent 和 API Server 上报到统一用量记录的用量明细和汇总
+
+use crate::services::usage_recorder::{UsageEvent, UsageQuery, UsageSummary};
+use crate::AppState;
+use tauri::State;
+
+/// 查询用量明细（按时间倒序）
+#[tauri::command]
+pub async fn list_usage_events(
+    state: State<'_, AppState>,
+    query: Option<UsageQuery>,
+) -> Result<Vec<UsageEvent>, String> {
+    let recorder = state.read().await.usage_recorder.clone();
+    Ok(recorder.list(&query.unwrap_or_default()))
+}
+
+/// 按来源和模型汇总用量
+#[tauri::command]
+pub async fn get_usage_summary(
+    state: State<'_, AppState>,
+    query: Option<UsageQuery>,
+) -> Result<UsageSummary, String> {
+    let recorder = state.read().await.usage_recorder.clone();
+    Ok(recorder.summary(&query.unwrap_or_default()))
+}
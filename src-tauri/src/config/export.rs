@@ -289,6 +289,9 @@ impl ExportService {
 
         // 脱敏服务器 API 密钥
         redacted.server.api_key = REDACTED_PLACEHOLDER.to_string();
+        if !redacted.server.request_signing.secret.is_empty() {
+            redacted.server.request_signing.secret = REDACTED_PLACEHOLDER.to_string();
+        }
 
         // 脱敏 Provider API 密钥
         if redacted.providers.openai.api_key.is_some() {
@@ -301,6 +304,13 @@ impl ExportService {
         // 脱敏凭证池中的 API Key
         redacted.credential_pool = Self::redact_credential_pool(&config.credential_pool);
 
+        // 脱敏 Provider 配置档案中的 API Key
+        for profile in &mut redacted.provider_profiles.profiles {
+            if !profile.api_key.is_empty() {
+                profile.api_key = REDACTED_PLACEHOLDER.to_string();
+            }
+        }
+
         redacted
     }
 
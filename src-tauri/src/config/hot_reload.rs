@@ -369,12 +369,13 @@ impl HotReloadManager {
         let content = std::fs::read_to_string(&self.config_path)
             .map_err(|e| HotReloadError::LoadError(e.to_string()))?;
 
-        ConfigManager::parse_yaml(&content).map_err(|e| HotReloadError::LoadError(e.to_string()))
+        ConfigManager::parse_config_file(&content)
+            .map_err(|e| HotReloadError::LoadError(e.to_string()))
     }
 
     /// 验证配置
     fn validate_config(&self, config: &Config) -> Result<(), HotReloadError> {
-        let is_localhost = is_localhost_host(&config.server.host);
+        let is_localhost = config.server.is_loopback();
 
         // 验证端口范围
         if config.server.port == 0 {
@@ -383,11 +384,10 @@ impl HotReloadManager {
             ));
         }
 
-        if !is_localhost {
-            return Err(HotReloadError::ValidationError(
-                "当前版本仅支持本地监听，请使用 127.0.0.1/localhost/::1".to_string(),
-            ));
-        }
+        config
+            .server
+            .validate_network()
+            .map_err(HotReloadError::ValidationError)?;
 
         // 验证重试配置
         if config.retry.max_retries > 100 {
@@ -423,12 +423,6 @@ impl HotReloadManager {
             ));
         }
 
-        if config.server.tls.enable {
-            return Err(HotReloadError::ValidationError(
-                "当前版本暂不支持 TLS，请关闭 TLS 配置".to_string(),
-            ));
-        }
-
         if config.remote_management.allow_remote {
             return Err(HotReloadError::ValidationError(
                 "当前版本未启用 TLS，禁止开启远程管理".to_string(),
@@ -475,15 +469,6 @@ impl HotReloadManager {
     }
 }
 
-fn is_localhost_host(host: &str) -> bool {
-    if host == "localhost" {
-        return true;
-    }
-    host.parse::<std::net::IpAddr>()
-        .map(|addr| addr.is_loopback())
-        .unwrap_or(false)
-}
-
 /// 热重载状态
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct HotReloadStatus {
@@ -9,6 +9,8 @@ mod export;
 mod hot_reload;
 mod import;
 mod path_utils;
+mod profiles;
+mod secrets;
 mod types;
 mod yaml;
 
@@ -18,12 +20,27 @@ pub use hot_reload::{
 };
 pub use import::{ImportOptions, ImportService, ValidationResult};
 pub use path_utils::{collapse_tilde, contains_tilde, expand_tilde};
+pub use profiles::{merge_profiles, ProfileBundle, ProfileMergeSummary, PROFILE_BUNDLE_VERSION};
+pub use secrets::{
+    config_for_file, has_plaintext_secrets, resolve_references, KEYCHAIN_REF_PREFIX,
+};
 pub use types::{
-    generate_secure_api_key, AmpConfig, AmpModelMapping, ApiKeyEntry, Config, CredentialEntry,
-    CredentialPoolConfig, CustomProviderConfig, EndpointProvidersConfig, GeminiApiKeyEntry,
-    IFlowCredentialEntry, InjectionRuleConfig, InjectionSettings, LoggingConfig, ProviderConfig,
-    ProvidersConfig, QuotaExceededConfig, RemoteManagementConfig, RetrySettings, RoutingConfig,
-    ServerConfig, TlsConfig, VertexApiKeyEntry, VertexModelAlias, DEFAULT_API_KEY,
+    generate_secure_api_key, AgentFallbackEndpoint, AmpConfig, AmpModelMapping, ApiKeyEntry,
+    BackgroundModelConfig, BrowserToolConfig, BudgetPeriod, ClientApiKey, CodeInterpreterConfig,
+    Config, ContextFallbackConfig, CorsConfig, CredentialEntry, CredentialPoolConfig,
+    CustomProviderConfig, CustomToolConfig, EmbeddingsConfig, EndpointProvidersConfig,
+    GeminiApiKeyEntry, IFlowCredentialEntry, ImageOutputFormat, ImageProcessingConfig,
+    InjectionRuleConfig, InjectionSettings, KeyRotationConfig, KeychainConfig, LoggingConfig,
+    McpHostConfig, MissedTaskPolicy, ModelPrice, ModelRouteConditions, ModelRouteConfig,
+    ModelRouteStrategy, ModelRouteTarget, OcrConfig, OtlpConfig, PermissionMode, PromptPosition,
+    ProviderConcurrencyConfig, ProviderConfig, ProviderProfile, ProviderProfilesConfig,
+    ProvidersConfig, QuickAskConfig, QuotaExceededConfig, RateLimitConfig, RemoteManagementConfig,
+    RequestMiddlewareAction, RequestMiddlewareConfig, RequestSigningConfig, ResponseCacheConfig,
+    RetrySettings, RotationStrategy, RoutingConfig, ScheduledTaskConfig, ServerConfig,
+    SessionQuotaConfig, SessionSyncBackend, SessionSyncConfig, SleepResumeConfig,
+    SqlConnectionConfig, SqlDriver, StreamCoalesceConfig, TlsConfig, TokenBudgetConfig,
+    TokenBudgetLimit, TranscriptConfig, VertexApiKeyEntry, VertexModelAlias, WasmPluginDir,
+    WasmPluginPermissions, WasmPluginsConfig, DEFAULT_API_KEY,
 };
 pub use yaml::{load_config, save_config, ConfigError, ConfigManager, YamlService};
 
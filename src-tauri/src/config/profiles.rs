@@ -0,0 +1,198 @@
+//! Provider 配置档案导入导出
+//!
+//! 档案以 JSON 文件在机器之间共享：
+//! - 导出时可选择排除 API Key，排除后导入方需自行填写
+//! - 导入按名称合并：同名档案默认跳过，选择覆盖时替换；
+//!   导入文件中的 Key 为空时保留本机已有的 Key
+
+use super::types::{ProviderProfile, ProviderProfilesConfig};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// 档案文件格式版本
+pub const PROFILE_BUNDLE_VERSION: u32 = 1;
+
+/// 档案导出文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileBundle {
+    /// 文件格式版本
+    pub version: u32,
+    /// 导出时间
+    pub exported_at: DateTime<Utc>,
+    /// 是否包含 API Key
+    pub include_keys: bool,
+    /// 档案列表
+    pub profiles: Vec<ProviderProfile>,
+}
+
+impl ProfileBundle {
+    /// 从配置创建导出文件
+    ///
+    /// `names` 为空时导出全部档案；不包含 Key 时 `api_key` 置空
+    pub fn new(
+        config: &ProviderProfilesConfig,
+        names: Option<&[String]>,
+        include_keys: bool,
+    ) -> Self {
+        let profiles = config
+            .profiles
+            .iter()
+            .filter(|p| names.map_or(true, |names| names.contains(&p.name)))
+            .cloned()
+            .map(|mut p| {
+                if !include_keys {
+                    p.api_key.clear();
+                }
+                p
+            })
+            .collect();
+        Self {
+            version: PROFILE_BUNDLE_VERSION,
+            exported_at: Utc::now(),
+            include_keys,
+            profiles,
+        }
+    }
+
+    /// 序列化为 JSON
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| format!("序列化档案失败: {}", e))
+    }
+
+    /// 解析并校验 JSON 档案文件
+    pub fn from_json(content: &str) -> Result<Self, String> {
+        let bundle: Self =
+            serde_json::from_str(content).map_err(|e| format!("档案文件格式错误: {}", e))?;
+        if bundle.version > PROFILE_BUNDLE_VERSION {
+            return Err(format!(
+                "不支持的档案文件版本: {}（当前支持 {}）",
+                bundle.version, PROFILE_BUNDLE_VERSION
+            ));
+        }
+        let mut names = HashSet::new();
+        for profile in &bundle.profiles {
+            if profile.name.trim().is_empty() {
+                return Err("档案名称不能为空".to_string());
+            }
+            if profile.base_url.trim().is_empty() {
+                return Err(format!("档案 {} 缺少 base_url", profile.name));
+            }
+            if !names.insert(profile.name.as_str()) {
+                return Err(format!("档案名称重复: {}", profile.name));
+            }
+        }
+        Ok(bundle)
+    }
+}
+
+/// 档案导入结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ProfileMergeSummary {
+    /// 新增的档案
+    pub added: Vec<String>,
+    /// 覆盖的同名档案
+    pub replaced: Vec<String>,
+    /// 因同名而跳过的档案
+    pub skipped: Vec<String>,
+}
+
+/// 将导入的档案按名称合并到配置
+pub fn merge_profiles(
+    config: &mut ProviderProfilesConfig,
+    imported: Vec<ProviderProfile>,
+    overwrite: bool,
+) -> ProfileMergeSummary {
+    let mut summary = ProfileMergeSummary::default();
+    for mut profile in imported {
+        match config.profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) if overwrite => {
+                if profile.api_key.is_empty() {
+                    profile.api_key = std::mem::take(&mut existing.api_key);
+                }
+                summary.replaced.push(profile.name.clone());
+                *existing = profile;
+            }
+            Some(_) => summary.skipped.push(profile.name),
+            None => {
+                summary.added.push(profile.name.clone());
+                config.profiles.push(profile);
+            }
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str, api_key: &str, model: &str) -> ProviderProfile {
+        ProviderProfile {
+            name: name.to_string(),
+            provider: "openai".to_string(),
+            base_url: "https://api.deepseek.com".to_string(),
+            api_key: api_key.to_string(),
+            default_model: Some(model.to_string()),
+            temperature: Some(0.3),
+            max_tokens: None,
+            top_p: None,
+        }
+    }
+
+    #[test]
+    fn test_export_without_keys_roundtrip() {
+        let config = ProviderProfilesConfig {
+            active: Some("work".to_string()),
+            profiles: vec![
+                profile("work", "sk-work", "deepseek-chat"),
+                profile("home", "sk-home", "deepseek-reasoner"),
+            ],
+        };
+        let names = vec!["work".to_string()];
+        let json = ProfileBundle::new(&config, Some(&names), false)
+            .to_json()
+            .unwrap();
+        assert!(!json.contains("sk-work"));
+
+        let bundle = ProfileBundle::from_json(&json).unwrap();
+        assert!(!bundle.include_keys);
+        assert_eq!(bundle.profiles.len(), 1);
+        assert_eq!(bundle.profiles[0].api_key, "");
+        assert_eq!(
+            bundle.profiles[0].default_model.as_deref(),
+            Some("deepseek-chat")
+        );
+
+        let duplicate = format!(
+            r#"{{"version":1,"exported_at":"2026-01-01T00:00:00Z","include_keys":false,"profiles":[{},{}]}}"#,
+            serde_json::to_string(&bundle.profiles[0]).unwrap(),
+            serde_json::to_string(&bundle.profiles[0]).unwrap()
+        );
+        assert!(ProfileBundle::from_json(&duplicate).is_err());
+    }
+
+    #[test]
+    fn test_merge_profiles() {
+        let mut config = ProviderProfilesConfig {
+            active: None,
+            profiles: vec![profile("work", "sk-local", "deepseek-chat")],
+        };
+        let imported = vec![
+            profile("work", "", "deepseek-reasoner"),
+            profile("home", "sk-home", "gpt-4o"),
+        ];
+
+        let skipped = merge_profiles(&mut config.clone(), imported.clone(), false);
+        assert_eq!(skipped.skipped, vec!["work".to_string()]);
+        assert_eq!(skipped.added, vec!["home".to_string()]);
+
+        let summary = merge_profiles(&mut config, imported, true);
+        assert_eq!(summary.replaced, vec!["work".to_string()]);
+        assert_eq!(config.profiles.len(), 2);
+        // 导入文件未包含 Key 时保留本机 Key
+        let work = config.get("work").unwrap();
+        assert_eq!(work.api_key, "sk-local");
+        assert_eq!(work.default_model.as_deref(), Some("deepseek-reasoner"));
+    }
+}
@@ -0,0 +1,527 @@
+//! 配置中的密钥与引用
+//!
+//! API Key 和 Base URL 支持引用外部值，加载配置时展开，内存中的配置始终是实际值：
+//! - `${ENV_VAR}`：读取环境变量，可嵌在字符串中，如 `https://${API_HOST}/v1`
+//! - `file:/path/to/key`：读取文件内容（去掉首尾空白，支持 `~`）
+//! - `keychain:<名称>`：读取系统钥匙串（仅 API Key）
+//!
+//! 保存配置时，值未改变的字段写回文件中原有的引用，展开后的值不会落盘。
+//!
+//! 启用 `keychain.enabled` 后：
+//! - 保存配置时明文 API Key 写入系统钥匙串，配置文件中只保留 `keychain:` 引用
+//! - 只写入新增或改变的 Key，值未改变的字段沿用原有的 `keychain:` 引用
+//! - 写入钥匙串失败的 Key 仍以明文保存，避免丢失
+//! - 关闭后再次保存，引用的 Key 会写回配置文件
+//!
+//! 配置文件中已有的明文 Key 在启动时自动迁移到钥匙串。
+
+use super::path_utils::expand_tilde;
+use super::types::{Config, SqlDriver};
+use super::yaml::ConfigManager;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// 钥匙串服务名
+pub const KEYCHAIN_SERVICE: &str = "proxycast";
+
+/// 钥匙串引用前缀
+pub const KEYCHAIN_REF_PREFIX: &str = "keychain:";
+
+/// 文件引用前缀
+pub const FILE_REF_PREFIX: &str = "file:";
+
+/// 密钥存储后端
+pub trait SecretStore {
+    /// 读取密钥
+    fn get(&self, account: &str) -> Result<String, String>;
+    /// 写入密钥（已存在时覆盖）
+    fn set(&self, account: &str, secret: &str) -> Result<(), String>;
+}
+
+/// 系统钥匙串（macOS Keychain / Windows 凭据管理器 / Linux Secret Service）
+pub struct SystemKeychain;
+
+impl SecretStore for SystemKeychain {
+    fn get(&self, account: &str) -> Result<String, String> {
+        keyring::Entry::new(KEYCHAIN_SERVICE, account)
+            .and_then(|entry| entry.get_password())
+            .map_err(|e| e.to_string())
+    }
+
+    fn set(&self, account: &str, secret: &str) -> Result<(), String> {
+        keyring::Entry::new(KEYCHAIN_SERVICE, account)
+            .and_then(|entry| entry.set_password(secret))
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// 支持引用的配置字段
+struct ConfigField<'a> {
+    /// 字段名（API Key 的字段名同时作为钥匙串账户名）
+    name: String,
+    value: &'a mut String,
+    /// 是否为 API Key
+    secret: bool,
+}
+
+/// 配置中所有支持引用的字段（API Key 和 Base URL）
+fn config_fields(config: &mut Config) -> Vec<ConfigField<'_>> {
+    fn key(name: String, value: &mut String) -> ConfigField<'_> {
+        ConfigField {
+            name,
+            value,
+            secret: true,
+        }
+    }
+    fn url(name: String, value: &mut String) -> ConfigField<'_> {
+        ConfigField {
+            name,
+            value,
+            secret: false,
+        }
+    }
+
+    let mut fields = vec![key(
+        "server.api_key".to_string(),
+        &mut config.server.api_key,
+    )];
+    for client_key in &mut config.server.client_keys {
+        let name = format!("server.client_keys.{}", client_key.id);
+        fields.push(key(name, &mut client_key.key));
+    }
+
+    let providers = &mut config.providers;
+    let optional = [
+        (
+            "providers.openai.api_key",
+            &mut providers.openai.api_key,
+            true,
+        ),
+        (
+            "providers.openai.base_url",
+            &mut providers.openai.base_url,
+            false,
+        ),
+        (
+            "providers.claude.api_key",
+            &mut providers.claude.api_key,
+            true,
+        ),
+        (
+            "providers.claude.base_url",
+            &mut providers.claude.base_url,
+            false,
+        ),
+        (
+            "remote_management.secret_key",
+            &mut config.remote_management.secret_key,
+            true,
+        ),
+    ];
+    for (name, value, secret) in optional {
+        if let Some(value) = value.as_mut() {
+            let name = name.to_string();
+            fields.push(if secret {
+                key(name, value)
+            } else {
+                url(name, value)
+            });
+        }
+    }
+
+    let pool = &mut config.credential_pool;
+    let api_key_entries = pool
+        .openai
+        .iter_mut()
+        .map(|e| ("openai", &e.id, &mut e.api_key, &mut e.base_url))
+        .chain(
+            pool.claude
+                .iter_mut()
+                .map(|e| ("claude", &e.id, &mut e.api_key, &mut e.base_url)),
+        )
+        .chain(
+            pool.gemini_api_keys
+                .iter_mut()
+                .map(|e| ("gemini_api_keys", &e.id, &mut e.api_key, &mut e.base_url)),
+        )
+        .chain(
+            pool.vertex_api_keys
+                .iter_mut()
+                .map(|e| ("vertex_api_keys", &e.id, &mut e.api_key, &mut e.base_url)),
+        );
+    for (kind, id, api_key, base_url) in api_key_entries {
+        let name = format!("credential_pool.{}.{}", kind, id);
+        if let Some(base_url) = base_url.as_mut() {
+            fields.push(url(format!("{}.base_url", name), base_url));
+        }
+        fields.push(key(name, api_key));
+    }
+
+    for fallback in &mut config.agent_fallbacks {
+        let name = format!("agent_fallbacks.{}", fallback.name);
+        fields.push(url(format!("{}.base_url", name), &mut fallback.base_url));
+        fields.push(key(name, &mut fallback.api_key));
+    }
+    for profile in &mut config.provider_profiles.profiles {
+        let name = format!("provider_profiles.{}", profile.name);
+        fields.push(url(format!("{}.base_url", name), &mut profile.base_url));
+        fields.push(key(name, &mut profile.api_key));
+    }
+    for connection in &mut config.sql_connections {
+        let name = format!("sql_connections.{}", connection.name);
+        // SQLite 的地址是文件路径，不写入钥匙串
+        fields.push(if connection.driver == SqlDriver::Sqlite {
+            url(name, &mut connection.url)
+        } else {
+            key(name, &mut connection.url)
+        });
+    }
+    fields.push(key(
+        "session_sync.password".to_string(),
+        &mut config.session_sync.password,
+    ));
+    fields.push(key(
+        "server.request_signing.secret".to_string(),
+        &mut config.server.request_signing.secret,
+    ));
+    fields
+}
+
+/// 是否为环境变量或文件引用
+fn is_external_reference(value: &str) -> bool {
+    value.starts_with(FILE_REF_PREFIX) || value.contains("${")
+}
+
+fn is_plaintext(value: &str) -> bool {
+    !value.is_empty() && !value.starts_with(KEYCHAIN_REF_PREFIX) && !is_external_reference(value)
+}
+
+/// 展开 `${ENV_VAR}` 和 `file:` 引用，`env` 用于读取环境变量
+fn expand_reference(value: &str, env: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+    if let Some(path) = value.strip_prefix(FILE_REF_PREFIX) {
+        let path = expand_tilde(path.trim());
+        return std::fs::read_to_string(&path)
+            .map(|content| content.trim().to_string())
+            .map_err(|e| format!("读取文件 {} 失败: {}", path.display(), e));
+    }
+
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("环境变量引用缺少右括号: {}", value))?;
+        let name = &rest[start + 2..start + end];
+        let var = env(name).ok_or_else(|| format!("环境变量未设置: {}", name))?;
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&var);
+        rest = &rest[start + end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// 展开配置中的所有引用
+fn resolve_with(
+    config: &mut Config,
+    store: &dyn SecretStore,
+    env: &dyn Fn(&str) -> Option<String>,
+) -> Result<(), String> {
+    for field in config_fields(config) {
+        let resolved = if let Some(account) = field.value.strip_prefix(KEYCHAIN_REF_PREFIX) {
+            if !field.secret {
+                continue;
+            }
+            store
+                .get(account)
+                .map_err(|e| format!("无法从系统钥匙串读取 {}: {}", account, e))?
+        } else if is_external_reference(field.value) {
+            expand_reference(field.value, env).map_err(|e| format!("{}: {}", field.name, e))?
+        } else {
+            continue;
+        };
+        *field.value = resolved;
+    }
+    Ok(())
+}
+
+/// 将明文 Key 写入钥匙串并替换为引用，返回写入的数量
+///
+/// 写入失败的 Key 保持明文，只记录警告
+pub fn store_secrets(config: &mut Config, store: &dyn SecretStore) -> usize {
+    let mut stored = 0;
+    for field in config_fields(config) {
+        if !field.secret || !is_plaintext(field.value) {
+            continue;
+        }
+        match store.set(&field.name, field.value) {
+            Ok(()) => {
+                *field.value = format!("{}{}", KEYCHAIN_REF_PREFIX, field.name);
+                stored += 1;
+            }
+            Err(e) => tracing::warn!("[KEYCHAIN] 写入 {} 失败，保留明文: {}", field.name, e),
+        }
+    }
+    stored
+}
+
+/// 恢复文件中原有的引用（展开值与当前值一致的字段）
+///
+/// 传入 `store` 时同时恢复 API Key 的 `keychain:` 引用，避免保存时重复写入钥匙串
+fn restore_references(
+    config: &mut Config,
+    previous: &mut Config,
+    store: Option<&dyn SecretStore>,
+    env: &dyn Fn(&str) -> Option<String>,
+) {
+    let references: HashMap<String, (String, bool)> = config_fields(previous)
+        .into_iter()
+        .filter(|field| {
+            is_external_reference(field.value.as_str())
+                || (field.secret && field.value.starts_with(KEYCHAIN_REF_PREFIX))
+        })
+        .map(|field| (field.name, (field.value.clone(), field.secret)))
+        .collect();
+    if references.is_empty() {
+        return;
+    }
+    for field in config_fields(config) {
+        let Some((reference, secret)) = references.get(&field.name) else {
+            continue;
+        };
+        if !is_plaintext(field.value) || field.secret != *secret {
+            continue;
+        }
+        let expanded = if let Some(account) = reference.strip_prefix(KEYCHAIN_REF_PREFIX) {
+            match store {
+                Some(store) => store.get(account).ok(),
+                None => continue,
+            }
+        } else {
+            expand_reference(reference, env).ok()
+        };
+        if expanded.as_deref() == Some(field.value.as_str()) {
+            *field.value = reference.clone();
+        }
+    }
+}
+
+fn system_env(name: &str) -> Option<String> {
+    std::env::var(name).ok()
+}
+
+/// 配置中是否有尚未迁移到钥匙串的明文 Key
+pub fn has_plaintext_secrets(config: &Config) -> bool {
+    let mut config = config.clone();
+    let has_plaintext = config_fields(&mut config)
+        .iter()
+        .any(|field| field.secret && is_plaintext(field.value.as_str()));
+    has_plaintext
+}
+
+/// 加载配置后调用：展开环境变量、文件和钥匙串引用
+pub fn resolve_references(config: &mut Config) -> Result<(), String> {
+    resolve_with(config, &SystemKeychain, &system_env)
+}
+
+/// 保存配置前调用：返回写入 `path` 的配置
+///
+/// 值未改变的字段保留文件中原有的引用；启用钥匙串时明文 Key 写入系统钥匙串，
+/// 返回的配置中只包含引用
+pub fn config_for_file(config: &Config, path: &Path) -> Config {
+    let mut file_config = config.clone();
+    let store: Option<&dyn SecretStore> = config
+        .keychain
+        .enabled
+        .then_some(&SystemKeychain as &dyn SecretStore);
+    let previous = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| ConfigManager::parse_yaml(&content).ok());
+    if let Some(mut previous) = previous {
+        restore_references(&mut file_config, &mut previous, store, &system_env);
+    }
+    if let Some(store) = store {
+        let stored = store_secrets(&mut file_config, store);
+        if stored > 0 {
+            tracing::debug!("[KEYCHAIN] 已将 {} 个 Key 写入系统钥匙串", stored);
+        }
+    }
+    file_config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ApiKeyEntry, ClientApiKey};
+    use parking_lot::Mutex;
+    use std::collections::HashMap;
+
+    /// 内存中的密钥存储，`fail` 中的账户写入失败
+    #[derive(Default)]
+    struct MemoryStore {
+        secrets: Mutex<HashMap<String, String>>,
+        fail: Vec<String>,
+        /// 写入次数
+        writes: Mutex<usize>,
+    }
+
+    impl SecretStore for MemoryStore {
+        fn get(&self, account: &str) -> Result<String, String> {
+            self.secrets
+                .lock()
+                .get(account)
+                .cloned()
+                .ok_or_else(|| "not found".to_string())
+        }
+
+        fn set(&self, account: &str, secret: &str) -> Result<(), String> {
+            if self.fail.iter().any(|a| a == account) {
+                return Err("locked".to_string());
+            }
+            self.secrets
+                .lock()
+                .insert(account.to_string(), secret.to_string());
+            *self.writes.lock() += 1;
+            Ok(())
+        }
+    }
+
+    fn config() -> Config {
+        let mut config = Config::default();
+        config.server.api_key = "pc_master".to_string();
+        config.server.client_keys.push(ClientApiKey {
+            id: "team-a".to_string(),
+            name: "Team A".to_string(),
+            key: "pc_team".to_string(),
+            allowed_models: Vec::new(),
+            allowed_routes: Vec::new(),
+            created_at: String::new(),
+            revoked: false,
+        });
+        config.credential_pool.openai.push(ApiKeyEntry {
+            id: "openai-1".to_string(),
+            api_key: "sk-openai".to_string(),
+            base_url: None,
+            disabled: false,
+            proxy_url: None,
+        });
+        config
+    }
+
+    fn no_env(_: &str) -> Option<String> {
+        None
+    }
+
+    #[test]
+    fn test_store_and_resolve_roundtrip() {
+        let store = MemoryStore::default();
+        let original = config();
+        assert!(has_plaintext_secrets(&original));
+
+        let mut file_config = original.clone();
+        assert_eq!(store_secrets(&mut file_config, &store), 3);
+        assert!(!has_plaintext_secrets(&file_config));
+        assert_eq!(file_config.server.api_key, "keychain:server.api_key");
+        assert_eq!(
+            file_config.credential_pool.openai[0].api_key,
+            "keychain:credential_pool.openai.openai-1"
+        );
+        // 已是引用的 Key 不重复写入
+        assert_eq!(store_secrets(&mut file_config, &store), 0);
+
+        resolve_with(&mut file_config, &store, &no_env).unwrap();
+        assert_eq!(file_config, original);
+
+        let mut missing = original.clone();
+        missing.server.api_key = "keychain:unknown".to_string();
+        assert!(resolve_with(&mut missing, &store, &no_env).is_err());
+    }
+
+    #[test]
+    fn test_unchanged_keys_not_rewritten() {
+        let store = MemoryStore::default();
+        let mut previous = config();
+        assert_eq!(store_secrets(&mut previous, &store), 3);
+        assert_eq!(*store.writes.lock(), 3);
+
+        // 只修改一个 Key：其余字段沿用原引用，只写入改变的 Key
+        let mut changed = config();
+        changed.server.api_key = "pc_rotated".to_string();
+        let mut file_config = changed.clone();
+        restore_references(
+            &mut file_config,
+            &mut previous.clone(),
+            Some(&store),
+            &no_env,
+        );
+        assert_eq!(store_secrets(&mut file_config, &store), 1);
+        assert_eq!(*store.writes.lock(), 4);
+        assert_eq!(
+            file_config.server.client_keys[0].key,
+            "keychain:server.client_keys.team-a"
+        );
+        resolve_with(&mut file_config, &store, &no_env).unwrap();
+        assert_eq!(file_config, changed);
+
+        // 未启用钥匙串时引用的 Key 写回明文
+        let mut file_config = config();
+        restore_references(&mut file_config, &mut previous, None, &no_env);
+        assert_eq!(file_config, config());
+    }
+
+    #[test]
+    fn test_store_failure_keeps_plaintext() {
+        let store = MemoryStore {
+            fail: vec!["server.client_keys.team-a".to_string()],
+            ..Default::default()
+        };
+        let mut file_config = config();
+        assert_eq!(store_secrets(&mut file_config, &store), 2);
+        assert_eq!(file_config.server.client_keys[0].key, "pc_team");
+        assert!(has_plaintext_secrets(&file_config));
+    }
+
+    #[test]
+    fn test_env_and_file_references() {
+        let env = |name: &str| match name {
+            "OPENAI_KEY" => Some("sk-env".to_string()),
+            "API_HOST" => Some("api.example.com".to_string()),
+            _ => None,
+        };
+        let key_file = std::env::temp_dir().join(format!("proxycast-key-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&key_file, "pc_from_file\n").unwrap();
+
+        let mut raw = config();
+        raw.server.api_key = format!("file:{}", key_file.display());
+        raw.credential_pool.openai[0].api_key = "${OPENAI_KEY}".to_string();
+        raw.credential_pool.openai[0].base_url = Some("https://${API_HOST}/v1".to_string());
+
+        let mut config = raw.clone();
+        resolve_with(&mut config, &MemoryStore::default(), &env).unwrap();
+        let _ = std::fs::remove_file(&key_file);
+        assert_eq!(config.server.api_key, "pc_from_file");
+        assert_eq!(config.credential_pool.openai[0].api_key, "sk-env");
+        assert_eq!(
+            config.credential_pool.openai[0].base_url.as_deref(),
+            Some("https://api.example.com/v1")
+        );
+
+        // 保存时未改变的字段写回引用，改变的字段使用新值
+        config.credential_pool.openai[0].base_url = Some("https://other.example.com".to_string());
+        let mut file_config = config.clone();
+        restore_references(&mut file_config, &mut raw.clone(), None, &env);
+        assert_eq!(
+            file_config.credential_pool.openai[0].api_key,
+            "${OPENAI_KEY}"
+        );
+        assert_eq!(
+            file_config.credential_pool.openai[0].base_url.as_deref(),
+            Some("https://other.example.com")
+        );
+
+        let mut missing = raw;
+        missing.server.api_key = "${MISSING_KEY}".to_string();
+        let err = resolve_with(&mut missing, &MemoryStore::default(), &env).unwrap_err();
+        assert!(err.contains("MISSING_KEY"));
+    }
+}
@@ -38,6 +38,15 @@ fn arb_server_config() -> impl Strategy<Value = ServerConfig> {
         port,
         api_key,
         tls: crate::config::TlsConfig::default(),
+        rate_limit: crate::config::RateLimitConfig::default(),
+        client_keys: Vec::new(),
+        response_cache: crate::config::ResponseCacheConfig::default(),
+        request_middlewares: Vec::new(),
+        allowed_ips: Vec::new(),
+        token_budget: crate::config::TokenBudgetConfig::default(),
+        provider_concurrency: crate::config::ProviderConcurrencyConfig::default(),
+        request_signing: crate::config::RequestSigningConfig::default(),
+        cors: crate::config::CorsConfig::default(),
     })
 }
 
@@ -149,6 +158,7 @@ fn arb_routing_config() -> impl Strategy<Value = RoutingConfig> {
                     .collect(),
                 model_aliases,
                 exclusions,
+                model_routes: Vec::new(),
             },
         )
 }
@@ -190,6 +200,8 @@ fn arb_logging_config() -> impl Strategy<Value = LoggingConfig> {
                 level,
                 retention_days,
                 include_request_body,
+                max_file_size_mb: 10,
+                max_total_size_mb: 200,
             },
         )
 }
@@ -219,6 +231,30 @@ fn arb_config() -> impl Strategy<Value = Config> {
             ampcode: crate::config::AmpConfig::default(),
             endpoint_providers: crate::config::EndpointProvidersConfig::default(),
             minimize_to_tray: true,
+            background_model: crate::config::BackgroundModelConfig::default(),
+            image_processing: crate::config::ImageProcessingConfig::default(),
+            agent_fallbacks: Vec::new(),
+            key_rotation: crate::config::KeyRotationConfig::default(),
+            session_quota: crate::config::SessionQuotaConfig::default(),
+            transcripts: crate::config::TranscriptConfig::default(),
+            context_fallback: crate::config::ContextFallbackConfig::default(),
+            session_sync: crate::config::SessionSyncConfig::default(),
+            stream_coalesce: crate::config::StreamCoalesceConfig::default(),
+            quick_ask: crate::config::QuickAskConfig::default(),
+            ocr: crate::config::OcrConfig::default(),
+            code_interpreter: crate::config::CodeInterpreterConfig::default(),
+            sql_connections: Vec::new(),
+            browser: crate::config::BrowserToolConfig::default(),
+            permission_mode: crate::config::PermissionMode::default(),
+            otlp: crate::config::OtlpConfig::default(),
+            sleep_resume: crate::config::SleepResumeConfig::default(),
+            provider_profiles: crate::config::ProviderProfilesConfig::default(),
+            keychain: crate::config::KeychainConfig::default(),
+            scheduled_tasks: Vec::new(),
+            custom_tools: Vec::new(),
+            wasm_plugins: crate::config::WasmPluginsConfig::default(),
+            mcp_host: crate::config::McpHostConfig::default(),
+            embeddings: crate::config::EmbeddingsConfig::default(),
         })
 }
 
@@ -423,6 +459,15 @@ fn arb_valid_server_config() -> impl Strategy<Value = ServerConfig> {
         port,
         api_key,
         tls: crate::config::TlsConfig::default(),
+        rate_limit: crate::config::RateLimitConfig::default(),
+        client_keys: Vec::new(),
+        response_cache: crate::config::ResponseCacheConfig::default(),
+        request_middlewares: Vec::new(),
+        allowed_ips: Vec::new(),
+        token_budget: crate::config::TokenBudgetConfig::default(),
+        provider_concurrency: crate::config::ProviderConcurrencyConfig::default(),
+        request_signing: crate::config::RequestSigningConfig::default(),
+        cors: crate::config::CorsConfig::default(),
     })
 }
 
@@ -463,6 +508,8 @@ fn arb_valid_logging_config() -> impl Strategy<Value = LoggingConfig> {
                 level,
                 retention_days,
                 include_request_body,
+                max_file_size_mb: 10,
+                max_total_size_mb: 200,
             },
         )
 }
@@ -492,6 +539,30 @@ fn arb_valid_config() -> impl Strategy<Value = Config> {
             ampcode: crate::config::AmpConfig::default(),
             endpoint_providers: crate::config::EndpointProvidersConfig::default(),
             minimize_to_tray: true,
+            background_model: crate::config::BackgroundModelConfig::default(),
+            image_processing: crate::config::ImageProcessingConfig::default(),
+            agent_fallbacks: Vec::new(),
+            key_rotation: crate::config::KeyRotationConfig::default(),
+            session_quota: crate::config::SessionQuotaConfig::default(),
+            transcripts: crate::config::TranscriptConfig::default(),
+            context_fallback: crate::config::ContextFallbackConfig::default(),
+            session_sync: crate::config::SessionSyncConfig::default(),
+            stream_coalesce: crate::config::StreamCoalesceConfig::default(),
+            quick_ask: crate::config::QuickAskConfig::default(),
+            ocr: crate::config::OcrConfig::default(),
+            code_interpreter: crate::config::CodeInterpreterConfig::default(),
+            sql_connections: Vec::new(),
+            browser: crate::config::BrowserToolConfig::default(),
+            permission_mode: crate::config::PermissionMode::default(),
+            otlp: crate::config::OtlpConfig::default(),
+            sleep_resume: crate::config::SleepResumeConfig::default(),
+            provider_profiles: crate::config::ProviderProfilesConfig::default(),
+            keychain: crate::config::KeychainConfig::default(),
+            scheduled_tasks: Vec::new(),
+            custom_tools: Vec::new(),
+            wasm_plugins: crate::config::WasmPluginsConfig::default(),
+            mcp_host: crate::config::McpHostConfig::default(),
+            embeddings: crate::config::EmbeddingsConfig::default(),
         })
 }
 
@@ -537,6 +608,30 @@ fn arb_invalid_config() -> impl Strategy<Value = Config> {
                     ampcode: crate::config::AmpConfig::default(),
                     endpoint_providers: crate::config::EndpointProvidersConfig::default(),
                     minimize_to_tray: true,
+                    background_model: crate::config::BackgroundModelConfig::default(),
+                    image_processing: crate::config::ImageProcessingConfig::default(),
+                    agent_fallbacks: Vec::new(),
+                    key_rotation: crate::config::KeyRotationConfig::default(),
+                    session_quota: crate::config::SessionQuotaConfig::default(),
+                    transcripts: crate::config::TranscriptConfig::default(),
+                    context_fallback: crate::config::ContextFallbackConfig::default(),
+                    session_sync: crate::config::SessionSyncConfig::default(),
+                    stream_coalesce: crate::config::StreamCoalesceConfig::default(),
+                    quick_ask: crate::config::QuickAskConfig::default(),
+                    ocr: crate::config::OcrConfig::default(),
+                    code_interpreter: crate::config::CodeInterpreterConfig::default(),
+                    sql_connections: Vec::new(),
+                    browser: crate::config::BrowserToolConfig::default(),
+                    permission_mode: crate::config::PermissionMode::default(),
+                    otlp: crate::config::OtlpConfig::default(),
+                    sleep_resume: crate::config::SleepResumeConfig::default(),
+                    provider_profiles: crate::config::ProviderProfilesConfig::default(),
+                    keychain: crate::config::KeychainConfig::default(),
+                    scheduled_tasks: Vec::new(),
+                    custom_tools: Vec::new(),
+                    wasm_plugins: crate::config::WasmPluginsConfig::default(),
+                    mcp_host: crate::config::McpHostConfig::default(),
+                    embeddings: crate::config::EmbeddingsConfig::default(),
                 };
                 // 根据类型使配置无效
                 match invalid_type {
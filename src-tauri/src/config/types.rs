@@ -283,6 +283,9 @@ pub struct Config {
     /// 日志配置
     #[serde(default)]
     pub logging: LoggingConfig,
+    /// OpenTelemetry 链路追踪导出
+    #[serde(default)]
+    pub otlp: OtlpConfig,
     /// 参数注入配置
     #[serde(default)]
     pub injection: InjectionSettings,
@@ -311,16 +314,975 @@ pub struct Config {
     /// 关闭时最小化到托盘（而不是退出应用）
     #[serde(default = "default_minimize_to_tray")]
     pub minimize_to_tray: bool,
+    /// 后台任务模型配置（摘要、标题、建议、记忆提取）
+    #[serde(default)]
+    pub background_model: BackgroundModelConfig,
+    /// Agent 图片预处理配置（缩放、重新编码）
+    #[serde(default)]
+    pub image_processing: ImageProcessingConfig,
+    /// Agent 备用端点（按顺序故障转移）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub agent_fallbacks: Vec<AgentFallbackEndpoint>,
+    /// 凭证池轮换与冷却配置
+    #[serde(default)]
+    pub key_rotation: KeyRotationConfig,
+    /// Agent 会话配额（会话数量、消息总数上限）
+    #[serde(default)]
+    pub session_quota: SessionQuotaConfig,
+    /// Agent 会话记录（按会话写入 JSONL，默认关闭）
+    #[serde(default)]
+    pub transcripts: TranscriptConfig,
+    /// Agent 上下文超限时的模型回退（默认关闭）
+    #[serde(default)]
+    pub context_fallback: ContextFallbackConfig,
+    /// Agent 会话同步（WebDAV / S3，默认关闭）
+    #[serde(default)]
+    pub session_sync: SessionSyncConfig,
+    /// 流式文本合并（减少前端事件数量）
+    #[serde(default)]
+    pub stream_coalesce: StreamCoalesceConfig,
+    /// 快速提问（全局快捷键唤起，修改快捷键后需重启）
+    #[serde(default)]
+    pub quick_ask: QuickAskConfig,
+    /// OCR 工具（本地 tesseract，默认关闭）
+    #[serde(default)]
+    pub ocr: OcrConfig,
+    /// 代码解释器工具（默认关闭）
+    #[serde(default)]
+    pub code_interpreter: CodeInterpreterConfig,
+    /// SQL 连接档案（Agent 的只读 `sql_query` / `sql_schema` 工具使用）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sql_connections: Vec<SqlConnectionConfig>,
+    /// 无头浏览器工具（默认关闭）
+    #[serde(default)]
+    pub browser: BrowserToolConfig,
+    /// Agent 工具权限模式（会话可单独覆盖）
+    #[serde(default)]
+    pub permission_mode: PermissionMode,
+    /// 系统休眠/唤醒处理
+    #[serde(default)]
+    pub sleep_resume: SleepResumeConfig,
+    /// Provider 配置档案（Agent 可按会话切换）
+    #[serde(default)]
+    pub provider_profiles: ProviderProfilesConfig,
+    /// 系统钥匙串配置
+    #[serde(default)]
+    pub keychain: KeychainConfig,
+    /// Agent 定时任务（按 cron 表达式在后台执行）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scheduled_tasks: Vec<ScheduledTaskConfig>,
+    /// 用户自定义的命令工具（Agent 自动注册）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub custom_tools: Vec<CustomToolConfig>,
+    /// WASM 工具插件
+    #[serde(default)]
+    pub wasm_plugins: WasmPluginsConfig,
+    /// 将 ProxyCast 作为 MCP 服务端暴露给其他 MCP 客户端
+    #[serde(default)]
+    pub mcp_host: McpHostConfig,
+    /// 向量化与知识库检索
+    #[serde(default)]
+    pub embeddings: EmbeddingsConfig,
+}
+
+fn default_minimize_to_tray() -> bool {
+    true
+}
+
+/// 后台任务模型配置
+///
+/// 摘要、标题生成、后续建议、记忆提取等后台操作统一使用此模型，
+/// 可配置为比对话模型更便宜的模型以控制成本
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BackgroundModelConfig {
+    /// 模型名称（为空时使用对话模型）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// 最大输出 token 数
+    #[serde(default = "default_background_max_tokens")]
+    pub max_tokens: u32,
+    /// 温度参数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+}
+
+fn default_background_max_tokens() -> u32 {
+    1024
+}
+
+impl Default for BackgroundModelConfig {
+    fn default() -> Self {
+        Self {
+            model: None,
+            max_tokens: default_background_max_tokens(),
+            temperature: None,
+        }
+    }
+}
+
+/// 图片重新编码格式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageOutputFormat {
+    /// JPEG（有损，按 quality 压缩）
+    #[default]
+    Jpeg,
+    /// WebP（无损，适合截图）
+    Webp,
+}
+
+/// Agent 图片预处理配置
+///
+/// 发送给模型前，尺寸超过 `max_dimension` 的图片会被等比缩放并重新编码，
+/// 以降低 token 消耗和上传时间
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ImageProcessingConfig {
+    /// 是否启用缩放
+    #[serde(default = "default_image_processing_enabled")]
+    pub enabled: bool,
+    /// 最长边上限（像素）
+    #[serde(default = "default_image_max_dimension")]
+    pub max_dimension: u32,
+    /// 重新编码格式
+    #[serde(default)]
+    pub format: ImageOutputFormat,
+    /// JPEG 质量（1-100）
+    #[serde(default = "default_image_quality")]
+    pub quality: u8,
+}
+
+fn default_image_processing_enabled() -> bool {
+    true
+}
+
+fn default_image_max_dimension() -> u32 {
+    2048
+}
+
+fn default_image_quality() -> u8 {
+    85
+}
+
+impl Default for ImageProcessingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_image_processing_enabled(),
+            max_dimension: default_image_max_dimension(),
+            format: ImageOutputFormat::default(),
+            quality: default_image_quality(),
+        }
+    }
+}
+
+/// Agent 会话配额
+///
+/// 限制会话数量和所有会话保存的消息总数，防止长期运行时会话存储无限增长。
+/// 使用量达到 `warning_percent` 时推送提醒并建议归档，达到上限后禁止创建新会话；
+/// 上限为 0 表示不限制
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SessionQuotaConfig {
+    /// 最大会话数
+    #[serde(default = "default_max_sessions")]
+    pub max_sessions: u32,
+    /// 所有会话保存的最大消息总数
+    #[serde(default = "default_max_stored_messages")]
+    pub max_messages: u32,
+    /// 提醒阈值（百分比）
+    #[serde(default = "default_quota_warning_percent")]
+    pub warning_percent: u8,
+}
+
+fn default_max_sessions() -> u32 {
+    200
+}
+
+fn default_max_stored_messages() -> u32 {
+    20_000
+}
+
+fn default_quota_warning_percent() -> u8 {
+    80
+}
+
+impl Default for SessionQuotaConfig {
+    fn default() -> Self {
+        Self {
+            max_sessions: default_max_sessions(),
+            max_messages: default_max_stored_messages(),
+            warning_percent: default_quota_warning_percent(),
+        }
+    }
+}
+
+/// Agent 会话记录
+///
+/// 启用后每条会话消息追加写入 `~/.proxycast/transcripts/<session_id>.jsonl`，
+/// 记录时间戳、模型和 token 用量，用于审计和问题排查
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TranscriptConfig {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// Agent 上下文超限回退
+///
+/// 发送前估算的提示词 token 数加上 `reserve_output_tokens` 超过会话模型的上下文窗口时，
+/// 本次请求按 `models` 顺序换用第一个窗口足够的模型，会话模型不变。
+/// 内置常见模型的窗口大小，未知模型可通过 `context_windows` 指定
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ContextFallbackConfig {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    /// 候选模型（按优先级排列）
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// 为模型输出预留的 token 数
+    #[serde(default = "default_reserve_output_tokens")]
+    pub reserve_output_tokens: u32,
+    /// 自定义模型上下文窗口（模型名 -> token 数，优先于内置表）
+    #[serde(default)]
+    pub context_windows: HashMap<String, u32>,
+    /// 上游返回上下文超长错误时自动丢弃最早的对话轮次并重试一次
+    #[serde(default = "default_compact_on_overflow")]
+    pub compact_on_overflow: bool,
+}
+
+fn default_reserve_output_tokens() -> u32 {
+    4096
+}
+
+fn default_compact_on_overflow() -> bool {
+    true
+}
+
+impl Default for ContextFallbackConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            models: Vec::new(),
+            reserve_output_tokens: default_reserve_output_tokens(),
+            context_windows: HashMap::new(),
+            compact_on_overflow: default_compact_on_overflow(),
+        }
+    }
+}
+
+/// 流式文本合并配置
+///
+/// 流式对话转发到前端前合并连续的文本片段：每次回复的首个片段立即推送，
+/// 之后累计到 `min_chunk_chars` 个字符或等待 `flush_interval_ms` 后推送一次
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StreamCoalesceConfig {
+    /// 是否启用
+    #[serde(default = "default_stream_coalesce_enabled")]
+    pub enabled: bool,
+    /// 最长缓冲时间（毫秒）
+    #[serde(default = "default_stream_flush_interval_ms")]
+    pub flush_interval_ms: u64,
+    /// 缓冲达到该字符数时立即推送
+    #[serde(default = "default_stream_min_chunk_chars")]
+    pub min_chunk_chars: usize,
+}
+
+fn default_stream_coalesce_enabled() -> bool {
+    true
+}
+
+fn default_stream_flush_interval_ms() -> u64 {
+    30
+}
+
+fn default_stream_min_chunk_chars() -> usize {
+    64
+}
+
+impl Default for StreamCoalesceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_stream_coalesce_enabled(),
+            flush_interval_ms: default_stream_flush_interval_ms(),
+            min_chunk_chars: default_stream_min_chunk_chars(),
+        }
+    }
+}
+
+/// 快速提问配置
+///
+/// 全局快捷键唤起的单轮问答，不带会话历史和工具；`scratch_session` 开启时问答追加到草稿会话
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QuickAskConfig {
+    /// 是否启用（关闭时不注册快捷键，`quick_ask` 命令仍可调用）
+    #[serde(default)]
+    pub enabled: bool,
+    /// 全局快捷键
+    #[serde(default = "default_quick_ask_shortcut")]
+    pub shortcut: String,
+    /// 使用的模型（未设置时使用 Agent 默认模型）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// 系统提示词
+    #[serde(default = "default_quick_ask_system_prompt")]
+    pub system_prompt: String,
+    /// 最大输出 token 数
+    #[serde(default = "default_quick_ask_max_tokens")]
+    pub max_tokens: u32,
+    /// 是否把问答追加到草稿会话
+    #[serde(default = "default_quick_ask_scratch_session")]
+    pub scratch_session: bool,
+    /// 草稿会话保留的最大消息数
+    #[serde(default = "default_quick_ask_scratch_max_messages")]
+    pub scratch_max_messages: usize,
+}
+
+fn default_quick_ask_shortcut() -> String {
+    "CommandOrControl+Shift+Space".to_string()
+}
+
+fn default_quick_ask_system_prompt() -> String {
+    "你是一个快速问答助手，请直接、简洁地回答问题。".to_string()
+}
+
+fn default_quick_ask_max_tokens() -> u32 {
+    1024
+}
+
+fn default_quick_ask_scratch_session() -> bool {
+    true
+}
+
+fn default_quick_ask_scratch_max_messages() -> usize {
+    100
+}
+
+impl Default for QuickAskConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            shortcut: default_quick_ask_shortcut(),
+            model: None,
+            system_prompt: default_quick_ask_system_prompt(),
+            max_tokens: default_quick_ask_max_tokens(),
+            scratch_session: default_quick_ask_scratch_session(),
+            scratch_max_messages: default_quick_ask_scratch_max_messages(),
+        }
+    }
+}
+
+/// OCR 工具配置
+///
+/// 启用后 Agent 可以调用 `ocr` 工具，用本地 tesseract 识别会话中附带的图片或本地图片文件
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OcrConfig {
+    /// 是否注册 `ocr` 工具
+    #[serde(default)]
+    pub enabled: bool,
+    /// tesseract 可执行文件路径（默认从 PATH 查找）
+    #[serde(default = "default_ocr_tesseract_path")]
+    pub tesseract_path: String,
+    /// 识别语言（tesseract 的 `-l` 参数，多个语言用 `+` 连接，如 `chi_sim+eng`）
+    #[serde(default = "default_ocr_languages")]
+    pub languages: String,
+    /// 单次识别超时（秒）
+    #[serde(default = "default_ocr_timeout_secs")]
+    pub timeout_secs: u64,
+    /// 提供 `ocr` 工具时是否仍将图片发送给模型（默认不发送，以占位文本代替以节省 token）
+    #[serde(default)]
+    pub send_images: bool,
+}
+
+fn default_ocr_tesseract_path() -> String {
+    "tesseract".to_string()
+}
+
+fn default_ocr_languages() -> String {
+    "eng".to_string()
+}
+
+fn default_ocr_timeout_secs() -> u64 {
+    30
+}
+
+impl Default for OcrConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tesseract_path: default_ocr_tesseract_path(),
+            languages: default_ocr_languages(),
+            timeout_secs: default_ocr_timeout_secs(),
+            send_images: false,
+        }
+    }
+}
+
+/// 代码解释器工具配置
+///
+/// 启用后 Agent 可以调用 `run_code` 工具，在临时目录的子进程中运行 Python / Node 代码片段
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CodeInterpreterConfig {
+    /// 是否注册 `run_code` 工具
+    #[serde(default)]
+    pub enabled: bool,
+    /// Python 解释器路径（默认从 PATH 查找 python3）
+    #[serde(default = "default_code_interpreter_python")]
+    pub python_path: String,
+    /// Node 可执行文件路径（默认从 PATH 查找）
+    #[serde(default = "default_code_interpreter_node")]
+    pub node_path: String,
+    /// 单次运行超时（秒），同时作为 CPU 时间上限
+    #[serde(default = "default_code_interpreter_timeout_secs")]
+    pub timeout_secs: u64,
+    /// 内存上限（MB）
+    #[serde(default = "default_code_interpreter_memory_mb")]
+    pub memory_limit_mb: u64,
+    /// 是否允许访问网络（默认禁止）
+    #[serde(default)]
+    pub allow_network: bool,
+}
+
+fn default_code_interpreter_python() -> String {
+    let python = if cfg!(windows) { "python" } else { "python3" };
+    python.to_string()
+}
+
+fn default_code_interpreter_node() -> String {
+    "node".to_string()
+}
+
+fn default_code_interpreter_timeout_secs() -> u64 {
+    30
+}
+
+fn default_code_interpreter_memory_mb() -> u64 {
+    512
+}
+
+impl Default for CodeInterpreterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            python_path: default_code_interpreter_python(),
+            node_path: default_code_interpreter_node(),
+            timeout_secs: default_code_interpreter_timeout_secs(),
+            memory_limit_mb: default_code_interpreter_memory_mb(),
+            allow_network: false,
+        }
+    }
+}
+
+/// SQL 数据库类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SqlDriver {
+    Sqlite,
+    Postgres,
+    Mysql,
+}
+
+/// SQL 连接档案
+///
+/// Agent 通过 `sql_query` / `sql_schema` 工具只读访问这些数据库
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SqlConnectionConfig {
+    /// 连接名称（唯一，模型调用工具时使用）
+    pub name: String,
+    pub driver: SqlDriver,
+    /// SQLite 为数据库文件路径，Postgres / MySQL 为连接 URL（可使用环境变量、文件或钥匙串引用）
+    pub url: String,
+    /// 数据库用途说明（供模型选择连接）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// 单次查询最多返回的行数
+    #[serde(default = "default_sql_max_rows")]
+    pub max_rows: usize,
+    /// 单次查询超时（秒，包括建立连接）
+    #[serde(default = "default_sql_timeout_secs")]
+    pub timeout_secs: u64,
+    /// 是否启用
+    #[serde(default = "default_sql_connection_enabled")]
+    pub enabled: bool,
+}
+
+fn default_sql_max_rows() -> usize {
+    200
+}
+
+fn default_sql_timeout_secs() -> u64 {
+    15
+}
+
+fn default_sql_connection_enabled() -> bool {
+    true
+}
+
+/// 无头浏览器工具配置
+///
+/// 启用后 Agent 可以调用 `browser_*` 工具，用本地 Chrome / Chromium 打开需要执行 JavaScript 的页面
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BrowserToolConfig {
+    /// 是否注册浏览器工具
+    #[serde(default)]
+    pub enabled: bool,
+    /// Chrome / Chromium 可执行文件路径（为空时自动查找）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub executable: Option<String>,
+    /// 是否以无头模式运行（关闭后显示浏览器窗口，便于调试）
+    #[serde(default = "default_browser_headless")]
+    pub headless: bool,
+    /// 单次操作超时（秒）
+    #[serde(default = "default_browser_timeout_secs")]
+    pub timeout_secs: u64,
+    /// 是否允许访问本机和内网地址（默认禁止，页面内的子请求和跳转同样受限）
+    #[serde(default)]
+    pub allow_private_network: bool,
+}
+
+fn default_browser_headless() -> bool {
+    true
+}
+
+fn default_browser_timeout_secs() -> u64 {
+    30
+}
+
+impl Default for BrowserToolConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            executable: None,
+            headless: default_browser_headless(),
+            timeout_secs: default_browser_timeout_secs(),
+            allow_private_network: false,
+        }
+    }
+}
+
+/// 会话同步后端
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionSyncBackend {
+    #[default]
+    Webdav,
+    /// S3 兼容存储（AWS S3、MinIO、Cloudflare R2 等，使用 path-style 地址）
+    S3,
+}
+
+/// Agent 会话同步
+///
+/// 将会话推送到 WebDAV 或 S3 兼容存储并拉取其他设备的会话，
+/// 同一会话两边都有修改时以 `updated_at` 较新的一方为准。`password` 支持钥匙串和环境变量引用
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SessionSyncConfig {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub backend: SessionSyncBackend,
+    /// WebDAV 目录 URL，或 S3 服务地址（如 `https://s3.us-east-1.amazonaws.com`）
+    #[serde(default)]
+    pub endpoint: String,
+    /// S3 存储桶
+    #[serde(default)]
+    pub bucket: String,
+    /// S3 区域
+    #[serde(default = "default_sync_region")]
+    pub region: String,
+    /// WebDAV 用户名 / S3 Access Key ID
+    #[serde(default)]
+    pub username: String,
+    /// WebDAV 密码 / S3 Secret Access Key
+    #[serde(default)]
+    pub password: String,
+    /// 远端路径前缀
+    #[serde(default = "default_sync_prefix")]
+    pub prefix: String,
+    /// 自动同步间隔（分钟，0 表示只手动同步）
+    #[serde(default)]
+    pub interval_minutes: u32,
+}
+
+fn default_sync_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_sync_prefix() -> String {
+    "proxycast/sessions".to_string()
+}
+
+impl Default for SessionSyncConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: SessionSyncBackend::default(),
+            endpoint: String::new(),
+            bucket: String::new(),
+            region: default_sync_region(),
+            username: String::new(),
+            password: String::new(),
+            prefix: default_sync_prefix(),
+            interval_minutes: 0,
+        }
+    }
+}
+
+/// Agent 工具权限模式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum PermissionMode {
+    /// 所有工具直接执行
+    #[default]
+    Auto,
+    /// 写文件、执行命令等会修改状态的工具需用户批准后执行
+    Approve,
+    /// 禁止调用会修改状态的工具，只允许只读工具
+    ReadOnly,
+}
+
+/// 休眠期间错过的计划任务的处理方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedTaskPolicy {
+    /// 唤醒后立即执行一次
+    #[default]
+    RunOnce,
+    /// 按休眠时长顺延执行时间
+    Shift,
+    /// 跳过（标记为已取消）
+    Skip,
+}
+
+/// 系统钥匙串配置
+///
+/// 启用后保存配置时 API Key 写入系统钥匙串（macOS Keychain、Windows 凭据管理器、
+/// Linux Secret Service），配置文件中只保存 `keychain:<名称>` 引用
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct KeychainConfig {
+    /// 是否将 API Key 存入系统钥匙串
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// Agent 定时任务
+///
+/// 按 cron 表达式（5 字段：分 时 日 月 周，本地时间）在临时会话中执行提示词，
+/// 执行记录保存在数据库中
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScheduledTaskConfig {
+    /// 任务名称（唯一）
+    pub name: String,
+    /// 发送给 Agent 的提示词
+    pub prompt: String,
+    /// cron 表达式，例如 `0 9 * * 1-5`（工作日 9:00）
+    pub cron: String,
+    /// 使用的 Provider 配置档案（为空时使用当前激活的档案）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// 使用的模型（为空时使用档案或 Agent 的默认模型）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// 是否启用
+    #[serde(default = "default_scheduled_task_enabled")]
+    pub enabled: bool,
+    /// 执行完成后是否发送系统通知
+    #[serde(default)]
+    pub notify: bool,
+}
+
+fn default_scheduled_task_enabled() -> bool {
+    true
+}
+
+/// 用户自定义的命令工具
+///
+/// 模型调用工具时将参数代入命令模板中的 `{{参数名}}` 占位符（按 shell 规则转义），
+/// 在 shell 中执行并返回 stdout/stderr
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CustomToolConfig {
+    /// 工具名称（唯一，只能包含字母、数字、`_` 和 `-`）
+    pub name: String,
+    /// 工具描述（供模型理解用途）
+    pub description: String,
+    /// 参数 JSON Schema（`type: object`），为空时工具没有参数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<serde_json::Value>,
+    /// 命令模板，例如 `rg --json {{pattern}} {{path}}`
+    pub command: String,
+    /// 工作目录（为空时使用用户 home 目录）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
+    /// 超时时间（秒），为空时使用 bash 工具的默认超时
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    /// 是否启用
+    #[serde(default = "default_custom_tool_enabled")]
+    pub enabled: bool,
+}
+
+fn default_custom_tool_enabled() -> bool {
+    true
+}
+
+/// WASM 工具插件配置
+///
+/// 从插件目录加载 `.wasm` 工具插件，插件运行在 WASI 沙箱中，
+/// 默认不能访问文件系统和环境变量，需在 `plugins` 中按插件授予
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WasmPluginsConfig {
+    /// 是否加载 WASM 插件
+    #[serde(default = "default_wasm_plugins_enabled")]
+    pub enabled: bool,
+    /// 插件目录（为空时使用 `~/.proxycast/plugins`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>,
+    /// 按插件文件名（不含 `.wasm`）配置的权限
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub plugins: HashMap<String, WasmPluginPermissions>,
+}
+
+impl Default for WasmPluginsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_wasm_plugins_enabled(),
+            dir: None,
+            plugins: HashMap::new(),
+        }
+    }
+}
+
+fn default_wasm_plugins_enabled() -> bool {
+    true
+}
+
+/// 单个 WASM 插件的 WASI 权限和资源限制
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WasmPluginPermissions {
+    /// 是否启用
+    #[serde(default = "default_wasm_plugins_enabled")]
+    pub enabled: bool,
+    /// 允许访问的目录
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dirs: Vec<WasmPluginDir>,
+    /// 传给插件的环境变量
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    /// 单次调用的超时时间（秒），为空时为 30 秒
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    /// 内存上限（MB），为空时为 64 MB
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory_mb: Option<u64>,
+}
+
+impl Default for WasmPluginPermissions {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dirs: Vec::new(),
+            env: HashMap::new(),
+            timeout_secs: None,
+            max_memory_mb: None,
+        }
+    }
+}
+
+/// 映射给 WASM 插件的目录
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WasmPluginDir {
+    /// 本机目录（支持 `~`）
+    pub host: String,
+    /// 插件内看到的路径（为空时与本机路径相同）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guest: Option<String>,
+    /// 是否允许写入（默认只读）
+    #[serde(default)]
+    pub writable: bool,
+}
+
+/// MCP 服务端配置
+///
+/// 在本机端口上提供 MCP（SSE 传输），认证使用 `server.api_key`；
+/// stdio 客户端通过 `proxycast --mcp-stdio` 桥接
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct McpHostConfig {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    /// 监听端口（仅监听 127.0.0.1）
+    #[serde(default = "default_mcp_host_port")]
+    pub port: u16,
+}
+
+impl Default for McpHostConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_mcp_host_port(),
+        }
+    }
+}
+
+fn default_mcp_host_port() -> u16 {
+    8997
+}
+
+/// 向量化配置
+///
+/// 向量化命令未指定模型时使用的模型，也用于检索 Agent 对话的参考资料
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EmbeddingsConfig {
+    /// 向量化模型
+    #[serde(default = "default_embedding_model")]
+    pub model: String,
+}
+
+fn default_embedding_model() -> String {
+    crate::embeddings::DEFAULT_EMBEDDING_MODEL.to_string()
+}
+
+impl Default for EmbeddingsConfig {
+    fn default() -> Self {
+        Self {
+            model: default_embedding_model(),
+        }
+    }
+}
+
+/// 系统休眠/唤醒处理配置
+///
+/// 唤醒后会暂停流式响应的卡顿检测、重建 Agent 连接，
+/// 并按 `missed_task_policy` 处理休眠期间到期的计划任务
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SleepResumeConfig {
+    /// 休眠期间错过的计划任务的处理方式
+    #[serde(default)]
+    pub missed_task_policy: MissedTaskPolicy,
+}
+
+/// 凭证轮换策略
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationStrategy {
+    /// 综合健康状态、使用次数、错误率和最近使用时间打分
+    #[default]
+    Weighted,
+    /// 轮询
+    RoundRobin,
+    /// 最久未使用优先
+    LeastRecentlyUsed,
+}
+
+/// 凭证池轮换与冷却配置
+///
+/// 上游返回 429 或 401/403 的凭证会在冷却期内暂停调度，冷却结束后自动恢复
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct KeyRotationConfig {
+    /// 轮换策略
+    #[serde(default)]
+    pub strategy: RotationStrategy,
+    /// 限流（429）冷却时间（秒）
+    #[serde(default = "default_rate_limit_cooldown_secs")]
+    pub rate_limit_cooldown_secs: u64,
+    /// 认证失败（401/403）冷却时间（秒）
+    #[serde(default = "default_auth_cooldown_secs")]
+    pub auth_cooldown_secs: u64,
+}
+
+fn default_rate_limit_cooldown_secs() -> u64 {
+    60
+}
+
+fn default_auth_cooldown_secs() -> u64 {
+    600
+}
+
+impl Default for KeyRotationConfig {
+    fn default() -> Self {
+        Self {
+            strategy: RotationStrategy::default(),
+            rate_limit_cooldown_secs: default_rate_limit_cooldown_secs(),
+            auth_cooldown_secs: default_auth_cooldown_secs(),
+        }
+    }
+}
+
+/// Agent 备用端点
+///
+/// 主端点（本地 API Server）返回 429/5xx、网络错误或超时时，
+/// NativeAgent 按配置顺序将同一请求转发到下一个备用端点
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AgentFallbackEndpoint {
+    /// 端点名称（记录在响应的 served_by 中）
+    pub name: String,
+    /// API 基础地址（不含 /v1）
+    pub base_url: String,
+    /// API Key
+    #[serde(default)]
+    pub api_key: String,
+    /// Provider 类型，决定流式请求使用的协议
+    #[serde(default = "default_fallback_provider")]
+    pub provider: String,
+    /// 覆盖模型名称（为空时沿用原请求的模型）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+fn default_fallback_provider() -> String {
+    "openai".to_string()
+}
+
+/// Provider 配置档案
+///
+/// 一组命名的端点、Key、默认模型和生成参数。Agent 使用会话指定的档案，
+/// 会话未指定时使用 `active`；都未设置时连接本地 API Server
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ProviderProfilesConfig {
+    /// 默认使用的档案名称
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active: Option<String>,
+    /// 档案列表（名称唯一）
+    #[serde(default)]
+    pub profiles: Vec<ProviderProfile>,
+}
+
+impl ProviderProfilesConfig {
+    /// 按名称查找档案
+    pub fn get(&self, name: &str) -> Option<&ProviderProfile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
 }
 
-fn default_minimize_to_tray() -> bool {
-    true
+/// 单个 Provider 配置档案
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProviderProfile {
+    /// 档案名称
+    pub name: String,
+    /// Provider 类型，决定使用的协议
+    #[serde(default = "default_fallback_provider")]
+    pub provider: String,
+    /// API 基础地址（不含 /v1）
+    pub base_url: String,
+    /// API Key（导出时可排除）
+    #[serde(default)]
+    pub api_key: String,
+    /// 默认模型（请求未指定模型时使用）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_model: Option<String>,
+    /// 温度参数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// 最大输出 Token 数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Top-p 采样参数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
 }
 
 /// 服务器配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ServerConfig {
-    /// 监听地址
+    /// 监听地址（非本机地址如 `0.0.0.0` 需要同时配置 `allowed_ips`）
     #[serde(default = "default_host")]
     pub host: String,
     /// 监听端口
@@ -332,20 +1294,577 @@ pub struct ServerConfig {
     /// TLS 配置
     #[serde(default)]
     pub tls: TlsConfig,
+    /// 按客户端 API Key 的限流配置
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    /// 额外的客户端 API Key（可限定模型和路由）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub client_keys: Vec<ClientApiKey>,
+    /// 相同请求的响应缓存
+    #[serde(default)]
+    pub response_cache: ResponseCacheConfig,
+    /// 请求中间件（按顺序执行）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub request_middlewares: Vec<RequestMiddlewareConfig>,
+    /// 允许访问的客户端 IP 或 CIDR（如 `192.168.1.0/24`），本机始终允许
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_ips: Vec<String>,
+    /// Token 预算（按客户端 Key / Provider 限制每日或每月用量）
+    #[serde(default)]
+    pub token_budget: TokenBudgetConfig,
+    /// 按 Provider 限制同时转发到上游的请求数
+    #[serde(default)]
+    pub provider_concurrency: ProviderConcurrencyConfig,
+    /// HMAC 请求签名（对局域网开放时比固定 API Key 更安全）
+    #[serde(default)]
+    pub request_signing: RequestSigningConfig,
+    /// 浏览器跨域访问（CORS）
+    #[serde(default)]
+    pub cors: CorsConfig,
+}
+
+impl ServerConfig {
+    /// 监听地址是否只接受本机连接
+    pub fn is_loopback(&self) -> bool {
+        self.host == "localhost"
+            || self
+                .host
+                .parse::<std::net::IpAddr>()
+                .map_or(false, |addr| addr.is_loopback())
+    }
+
+    /// 服务器 URL 协议（启用 TLS 时为 https）
+    pub fn scheme(&self) -> &'static str {
+        if self.tls.enable {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    /// 解析客户端 IP 白名单
+    pub fn parse_allowed_ips(&self) -> Result<Vec<ipnet::IpNet>, String> {
+        self.allowed_ips
+            .iter()
+            .map(|entry| {
+                let entry = entry.trim();
+                entry
+                    .parse::<ipnet::IpNet>()
+                    .or_else(|_| entry.parse::<std::net::IpAddr>().map(ipnet::IpNet::from))
+                    .map_err(|_| format!("无效的 IP 或 CIDR: {}", entry))
+            })
+            .collect()
+    }
+
+    /// 校验监听地址、IP 白名单和请求签名
+    ///
+    /// 对局域网开放（监听非本机地址）时必须配置白名单，避免把代理暴露给任意来源
+    pub fn validate_network(&self) -> Result<(), String> {
+        let allowed = self.parse_allowed_ips()?;
+        if !self.is_loopback() && allowed.is_empty() {
+            return Err(format!(
+                "监听地址 {} 允许其他设备访问，请先配置 allowed_ips（如 192.168.1.0/24）",
+                self.host
+            ));
+        }
+        if self.request_signing.enabled && self.request_signing.secret.trim().is_empty() {
+            return Err("已启用请求签名，请先配置 request_signing.secret".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Token 预算配置
+///
+/// 启用后，按天汇总各客户端 Key 在各 Provider 上的 Token 用量和费用，
+/// 任一适用的预算耗尽时拒绝请求（429）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct TokenBudgetConfig {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    /// 预算列表
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub limits: Vec<TokenBudgetLimit>,
+    /// 模型单价（用于计算费用，未配置单价的模型只计 Token）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prices: Vec<ModelPrice>,
+}
+
+impl TokenBudgetConfig {
+    /// 查找模型单价（按配置顺序匹配第一个）
+    pub fn price_for(&self, model: &str) -> Option<&ModelPrice> {
+        self.prices.iter().find(|price| price.matches(model))
+    }
+}
+
+/// 单个 Token 预算
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TokenBudgetLimit {
+    /// 名称
+    pub name: String,
+    /// 限定的客户端 Key ID（为空表示所有请求共用）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<String>,
+    /// 限定的 Provider（为空表示所有 Provider 共用）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// 统计周期
+    #[serde(default)]
+    pub period: BudgetPeriod,
+    /// 最大 Token 数（输入 + 输出，0 表示不限制）
+    #[serde(default)]
+    pub max_tokens: u64,
+    /// 最大费用（美元，0 表示不限制）
+    #[serde(default)]
+    pub max_cost_usd: f64,
+}
+
+impl TokenBudgetLimit {
+    /// 预算是否适用于该请求
+    pub fn applies_to(&self, client_key: Option<&str>, provider: &str) -> bool {
+        self.client_key
+            .as_deref()
+            .map_or(true, |key| client_key == Some(key))
+            && self.provider.as_deref().map_or(true, |p| p == provider)
+    }
+}
+
+/// 预算统计周期（按本地时间）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetPeriod {
+    /// 每日
+    #[default]
+    Daily,
+    /// 每月
+    Monthly,
+}
+
+/// 模型单价（美元 / 百万 Token）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModelPrice {
+    /// 模型名（支持 `*` 后缀通配，如 `claude-*`）
+    pub model: String,
+    /// 输入单价
+    #[serde(default)]
+    pub input_per_million: f64,
+    /// 输出单价
+    #[serde(default)]
+    pub output_per_million: f64,
+}
+
+impl ModelPrice {
+    /// 单价是否适用于该模型（`*` 后缀按前缀匹配）
+    pub fn matches(&self, model: &str) -> bool {
+        match self.model.strip_suffix('*') {
+            Some(prefix) => model.starts_with(prefix),
+            None => self.model == model,
+        }
+    }
+
+    /// 按单价计算一次请求的费用（美元）
+    pub fn cost_usd(&self, input_tokens: u32, output_tokens: u32) -> f64 {
+        (input_tokens as f64 * self.input_per_million
+            + output_tokens as f64 * self.output_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// 响应缓存配置
+///
+/// 启用后，TTL 内完全相同的非流式 chat completions / messages 请求
+/// （模型、消息和参数均相同）直接返回缓存的响应，适合重复的评测和调试
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResponseCacheConfig {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    /// 缓存有效期（秒）
+    #[serde(default = "default_response_cache_ttl_secs")]
+    pub ttl_secs: u64,
+    /// 最大缓存条目数
+    #[serde(default = "default_response_cache_max_entries")]
+    pub max_entries: usize,
+}
+
+fn default_response_cache_ttl_secs() -> u64 {
+    300
+}
+
+fn default_response_cache_max_entries() -> usize {
+    500
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: default_response_cache_ttl_secs(),
+            max_entries: default_response_cache_max_entries(),
+        }
+    }
+}
+
+/// 请求中间件配置
+///
+/// 在请求转发到上游之前按配置顺序执行，可以改写请求头、注入系统提示词、
+/// 删除请求体字段或直接拦截请求
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RequestMiddlewareConfig {
+    /// 名称（用于日志和拦截提示，为空时使用类型名）
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    /// 是否启用
+    #[serde(default = "default_request_middleware_enabled")]
+    pub enabled: bool,
+    /// 只作用于这些路由前缀（为空表示不限制，如 `/v1/messages`）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    /// 只作用于这些模型（为空表示不限制，支持 `*` 后缀通配）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub models: Vec<String>,
+    /// 中间件动作
+    #[serde(flatten)]
+    pub action: RequestMiddlewareAction,
+}
+
+fn default_request_middleware_enabled() -> bool {
+    true
+}
+
+impl RequestMiddlewareConfig {
+    /// 是否作用于该请求（限定了模型但请求中没有模型时不作用）
+    pub fn applies_to(&self, path: &str, model: Option<&str>) -> bool {
+        let path_matches = self.paths.is_empty()
+            || self
+                .paths
+                .iter()
+                .any(|prefix| path.starts_with(prefix.as_str()));
+        let model_matches = self.models.is_empty()
+            || model.map_or(false, |model| {
+                self.models
+                    .iter()
+                    .any(|pattern| match pattern.strip_suffix('*') {
+                        Some(prefix) => model.starts_with(prefix),
+                        None => pattern == model,
+                    })
+            });
+        self.enabled && path_matches && model_matches
+    }
+}
+
+/// 请求中间件动作
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RequestMiddlewareAction {
+    /// 设置请求头（已存在时覆盖）
+    SetHeader { header: String, value: String },
+    /// 删除请求头
+    RemoveHeader { header: String },
+    /// 注入系统提示词（兼容 OpenAI、Anthropic 和 Gemini 请求格式）
+    InjectSystemPrompt {
+        prompt: String,
+        /// 放在已有系统提示词之前还是之后
+        #[serde(default)]
+        position: PromptPosition,
+    },
+    /// 删除请求体字段（`a.b` 表示嵌套字段）
+    StripFields { fields: Vec<String> },
+    /// 拦截请求，直接返回错误
+    Block {
+        #[serde(default = "default_block_status")]
+        status: u16,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        message: String,
+    },
+}
+
+fn default_block_status() -> u16 {
+    403
+}
+
+/// 注入的系统提示词位置
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptPosition {
+    #[default]
+    Prepend,
+    Append,
+}
+
+/// 客户端 API Key
+///
+/// 与 `api_key` 并存，便于给团队成员或脚本分别发放、吊销 Key，并按 Key 统计流量。
+/// 吊销后保留记录，使用该 Key 的请求返回 401。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ClientApiKey {
+    /// Key ID
+    pub id: String,
+    /// 名称
+    pub name: String,
+    /// Key 内容
+    pub key: String,
+    /// 允许使用的模型（为空表示不限制，支持 `*` 后缀通配，如 `claude-*`）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_models: Vec<String>,
+    /// 允许访问的路由前缀（为空表示不限制，按路径段匹配，如 `/v1/messages`）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_routes: Vec<String>,
+    /// 创建时间（RFC 3339）
+    pub created_at: String,
+    /// 是否已吊销
+    #[serde(default)]
+    pub revoked: bool,
+}
+
+impl ClientApiKey {
+    /// 是否允许使用该模型
+    pub fn allows_model(&self, model: &str) -> bool {
+        self.allowed_models.is_empty()
+            || self
+                .allowed_models
+                .iter()
+                .any(|pattern| match pattern.strip_suffix('*') {
+                    Some(prefix) => model.starts_with(prefix),
+                    None => pattern == model,
+                })
+    }
+
+    /// 是否允许访问该路由（`/v1/messages` 匹配 `/v1/messages/count_tokens`，不匹配 `/v1/messages2`）
+    pub fn allows_route(&self, path: &str) -> bool {
+        self.allowed_routes.is_empty()
+            || self.allowed_routes.iter().any(|prefix| {
+                let prefix = prefix.trim_end_matches('/');
+                prefix.is_empty()
+                    || path
+                        .strip_prefix(prefix)
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+    }
+}
+
+/// Provider 并发限制配置
+///
+/// 每个 Provider 使用独立的并发名额，某个 Provider 响应缓慢或被限流时，
+/// 排队的请求不会占用其他 Provider 的名额
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProviderConcurrencyConfig {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    /// 未单独配置的 Provider 的最大并发请求数（0 表示不限制）
+    #[serde(default = "default_provider_concurrency_limit")]
+    pub default_limit: u32,
+    /// 按 Provider 单独配置的最大并发请求数（键为 Provider 类型，如 `claude`、`openai`）
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub limits: HashMap<String, u32>,
+    /// 排队等待名额的最长时间（秒），超时返回 503
+    #[serde(default = "default_provider_queue_timeout_secs")]
+    pub queue_timeout_secs: u64,
+}
+
+fn default_provider_concurrency_limit() -> u32 {
+    8
+}
+
+fn default_provider_queue_timeout_secs() -> u64 {
+    30
+}
+
+impl Default for ProviderConcurrencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            default_limit: default_provider_concurrency_limit(),
+            limits: HashMap::new(),
+            queue_timeout_secs: default_provider_queue_timeout_secs(),
+        }
+    }
+}
+
+impl ProviderConcurrencyConfig {
+    /// Provider 的最大并发请求数（0 表示不限制）
+    pub fn limit_for(&self, provider: &str) -> u32 {
+        self.limits
+            .get(provider)
+            .copied()
+            .unwrap_or(self.default_limit)
+    }
+}
+
+/// HMAC 请求签名配置
+///
+/// 启用后，API 请求需要携带时间戳和用共享密钥计算的 HMAC-SHA256 签名，
+/// 签名覆盖请求方法、路径和请求体，时间戳超出允许偏差或签名重复使用时拒绝（401）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RequestSigningConfig {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    /// 共享密钥
+    #[serde(default)]
+    pub secret: String,
+    /// 允许的时间戳偏差（秒）
+    #[serde(default = "default_signing_max_skew_secs")]
+    pub max_skew_secs: u64,
+    /// 本机（回环地址）发起的请求是否也要求签名（关闭时内置 Agent 等本机客户端无需签名）
+    #[serde(default)]
+    pub require_for_loopback: bool,
+}
+
+fn default_signing_max_skew_secs() -> u64 {
+    300
+}
+
+impl Default for RequestSigningConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            secret: String::new(),
+            max_skew_secs: default_signing_max_skew_secs(),
+            require_for_loopback: false,
+        }
+    }
+}
+
+/// 浏览器跨域访问（CORS）配置
+///
+/// 允许网页工具把 ProxyCast 作为 OpenAI / Anthropic Base URL 直接调用。
+/// 默认只允许本机页面（`localhost` / `127.0.0.1` 任意端口）跨域访问
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CorsConfig {
+    /// 是否启用（关闭时不返回任何 CORS 响应头）
+    #[serde(default = "default_cors_enabled")]
+    pub enabled: bool,
+    /// 允许的来源，如 `https://chat.example.com`；`http://host:*` 匹配任意端口，`*` 匹配所有来源
+    #[serde(default = "default_cors_allowed_origins")]
+    pub allowed_origins: Vec<String>,
+    /// 允许的请求方法
+    #[serde(default = "default_cors_allowed_methods")]
+    pub allowed_methods: Vec<String>,
+    /// 允许的请求头（`*` 表示允许浏览器请求的所有请求头）
+    #[serde(default = "default_cors_allowed_headers")]
+    pub allowed_headers: Vec<String>,
+    /// 预检结果的缓存时间（秒）
+    #[serde(default = "default_cors_max_age_secs")]
+    pub max_age_secs: u64,
+}
+
+fn default_cors_enabled() -> bool {
+    true
+}
+
+fn default_cors_allowed_origins() -> Vec<String> {
+    [
+        "http://localhost:*",
+        "http://127.0.0.1:*",
+        "https://localhost:*",
+        "https://127.0.0.1:*",
+    ]
+    .map(str::to_string)
+    .to_vec()
+}
+
+fn default_cors_allowed_methods() -> Vec<String> {
+    ["GET", "POST", "PUT", "DELETE", "OPTIONS"]
+        .map(str::to_string)
+        .to_vec()
+}
+
+fn default_cors_allowed_headers() -> Vec<String> {
+    vec!["*".to_string()]
+}
+
+fn default_cors_max_age_secs() -> u64 {
+    600
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_cors_enabled(),
+            allowed_origins: default_cors_allowed_origins(),
+            allowed_methods: default_cors_allowed_methods(),
+            allowed_headers: default_cors_allowed_headers(),
+            max_age_secs: default_cors_max_age_secs(),
+        }
+    }
+}
+
+impl CorsConfig {
+    /// 是否允许该来源跨域访问
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        let origin = origin.trim_end_matches('/').to_ascii_lowercase();
+        self.allowed_origins.iter().any(|allowed| {
+            let allowed = allowed.trim().trim_end_matches('/').to_ascii_lowercase();
+            if allowed == "*" || allowed == origin {
+                return true;
+            }
+            // `scheme://host:*` 匹配该主机的任意端口（包括省略端口）
+            allowed.strip_suffix(":*").is_some_and(|base| {
+                origin == base
+                    || origin.strip_prefix(base).is_some_and(|rest| {
+                        rest.strip_prefix(':').is_some_and(|port| {
+                            !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit())
+                        })
+                    })
+            })
+        })
+    }
+
+    /// 是否允许该请求方法
+    pub fn allows_method(&self, method: &str) -> bool {
+        self.allowed_methods
+            .iter()
+            .any(|m| m.trim().eq_ignore_ascii_case(method))
+    }
+}
+
+/// 本地 API 服务器限流配置
+///
+/// 按请求携带的 API Key 分别统计最近一分钟的请求数和估算 token 数，
+/// 超出后返回 429 和 `Retry-After`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RateLimitConfig {
+    /// 是否启用限流
+    #[serde(default)]
+    pub enabled: bool,
+    /// 每分钟最大请求数（0 表示不限制）
+    #[serde(default = "default_rate_limit_requests_per_minute")]
+    pub requests_per_minute: u32,
+    /// 每分钟最大 token 数（按请求体大小估算，0 表示不限制）
+    #[serde(default)]
+    pub tokens_per_minute: u32,
+}
+
+fn default_rate_limit_requests_per_minute() -> u32 {
+    60
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            requests_per_minute: default_rate_limit_requests_per_minute(),
+            tokens_per_minute: 0,
+        }
+    }
 }
 
 /// TLS 配置
 ///
-/// 用于启用 HTTPS 支持
+/// 用于启用 HTTPS 支持。未配置证书和私钥时，首次启动会在
+/// `~/.proxycast/tls/` 下生成自签名证书
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct TlsConfig {
     /// 是否启用 TLS
     #[serde(default)]
     pub enable: bool,
-    /// 证书文件路径
+    /// 证书文件路径（PEM，可选）
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub cert_path: Option<String>,
-    /// 私钥文件路径
+    /// 私钥文件路径（PEM，可选）
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub key_path: Option<String>,
 }
@@ -468,6 +1987,15 @@ impl Default for ServerConfig {
             port: default_port(),
             api_key: default_api_key(),
             tls: TlsConfig::default(),
+            rate_limit: RateLimitConfig::default(),
+            client_keys: Vec::new(),
+            response_cache: ResponseCacheConfig::default(),
+            request_middlewares: Vec::new(),
+            allowed_ips: Vec::new(),
+            token_budget: TokenBudgetConfig::default(),
+            provider_concurrency: ProviderConcurrencyConfig::default(),
+            request_signing: RequestSigningConfig::default(),
+            cors: CorsConfig::default(),
         }
     }
 }
@@ -573,6 +2101,9 @@ pub struct RoutingConfig {
     /// 排除列表（按 Provider）
     #[serde(default)]
     pub exclusions: HashMap<String, Vec<String>>,
+    /// 模型路由（按顺序匹配，命中后覆盖 Provider 和模型）
+    #[serde(default)]
+    pub model_routes: Vec<ModelRouteConfig>,
 }
 
 fn default_provider() -> String {
@@ -586,6 +2117,7 @@ impl Default for RoutingConfig {
             rules: Vec::new(),
             model_aliases: HashMap::new(),
             exclusions: HashMap::new(),
+            model_routes: Vec::new(),
         }
     }
 }
@@ -606,6 +2138,69 @@ fn default_priority() -> i32 {
     100
 }
 
+/// 模型路由配置
+///
+/// 将客户端请求的模型名映射到一个或多个 (Provider, 实际模型) 目标，
+/// 可附加条件；多个目标时按 `strategy` 选择
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModelRouteConfig {
+    /// 客户端请求的模型名（支持通配符）
+    pub model: String,
+    /// 匹配条件（全部满足才命中）
+    #[serde(default)]
+    pub conditions: ModelRouteConditions,
+    /// 多目标选择策略
+    #[serde(default)]
+    pub strategy: ModelRouteStrategy,
+    /// 路由目标
+    pub targets: Vec<ModelRouteTarget>,
+}
+
+/// 模型路由多目标选择策略
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelRouteStrategy {
+    /// 按权重随机选择
+    #[default]
+    Weighted,
+    /// 按最近的延迟和错误率选择最快的健康目标（带迟滞，避免来回切换）
+    Fastest,
+}
+
+/// 模型路由条件
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ModelRouteConditions {
+    /// 请求头必须等于指定值（请求头名称不区分大小写）
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    /// 仅对这些客户端 Key（ID）生效，空表示不限
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub client_keys: Vec<String>,
+    /// 估算的提示词 Token 数下限
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_prompt_tokens: Option<u32>,
+    /// 估算的提示词 Token 数上限
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_prompt_tokens: Option<u32>,
+}
+
+/// 模型路由目标
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModelRouteTarget {
+    /// 目标 Provider
+    pub provider: String,
+    /// 实际模型名，留空时沿用请求的模型名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// 权重（多个目标时按权重随机选择，0 表示不参与）
+    #[serde(default = "default_route_weight")]
+    pub weight: u32,
+}
+
+fn default_route_weight() -> u32 {
+    1
+}
+
 /// 重试配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct RetrySettings {
@@ -665,12 +2260,26 @@ pub struct LoggingConfig {
     /// 是否包含请求体
     #[serde(default)]
     pub include_request_body: bool,
+    /// 单个后端日志文件大小上限（MB，超过后切换到新文件，0 表示不限制）
+    #[serde(default = "default_log_max_file_size_mb")]
+    pub max_file_size_mb: u64,
+    /// 后端日志文件总大小上限（MB，超过后删除最早的文件，0 表示不限制）
+    #[serde(default = "default_log_max_total_size_mb")]
+    pub max_total_size_mb: u64,
 }
 
 fn default_logging_enabled() -> bool {
     true
 }
 
+fn default_log_max_file_size_mb() -> u64 {
+    10
+}
+
+fn default_log_max_total_size_mb() -> u64 {
+    200
+}
+
 fn default_log_level() -> String {
     "info".to_string()
 }
@@ -686,6 +2295,48 @@ impl Default for LoggingConfig {
             level: default_log_level(),
             retention_days: default_retention_days(),
             include_request_body: false,
+            max_file_size_mb: default_log_max_file_size_mb(),
+            max_total_size_mb: default_log_max_total_size_mb(),
+        }
+    }
+}
+
+/// OpenTelemetry 链路追踪导出配置（OTLP/HTTP，修改后需重启生效）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OtlpConfig {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    /// Collector 地址（如 `http://localhost:4318`，未包含 `/v1/traces` 时自动追加）
+    #[serde(default = "default_otlp_endpoint")]
+    pub endpoint: String,
+    /// 上报的服务名（`service.name`）
+    #[serde(default = "default_otlp_service_name")]
+    pub service_name: String,
+    /// 采样比例（0.0 - 1.0）
+    #[serde(default = "default_otlp_sample_ratio")]
+    pub sample_ratio: f64,
+}
+
+fn default_otlp_endpoint() -> String {
+    "http://localhost:4318".to_string()
+}
+
+fn default_otlp_service_name() -> String {
+    "proxycast".to_string()
+}
+
+fn default_otlp_sample_ratio() -> f64 {
+    1.0
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: default_otlp_endpoint(),
+            service_name: default_otlp_service_name(),
+            sample_ratio: default_otlp_sample_ratio(),
         }
     }
 }
@@ -770,6 +2421,7 @@ impl Default for Config {
             routing: RoutingConfig::default(),
             retry: RetrySettings::default(),
             logging: LoggingConfig::default(),
+            otlp: OtlpConfig::default(),
             injection: InjectionSettings::default(),
             auth_dir: default_auth_dir(),
             credential_pool: CredentialPoolConfig::default(),
@@ -779,6 +2431,29 @@ impl Default for Config {
             ampcode: AmpConfig::default(),
             endpoint_providers: EndpointProvidersConfig::default(),
             minimize_to_tray: default_minimize_to_tray(),
+            background_model: BackgroundModelConfig::default(),
+            image_processing: ImageProcessingConfig::default(),
+            agent_fallbacks: Vec::new(),
+            key_rotation: KeyRotationConfig::default(),
+            session_quota: SessionQuotaConfig::default(),
+            transcripts: TranscriptConfig::default(),
+            context_fallback: ContextFallbackConfig::default(),
+            session_sync: SessionSyncConfig::default(),
+            stream_coalesce: StreamCoalesceConfig::default(),
+            quick_ask: QuickAskConfig::default(),
+            ocr: OcrConfig::default(),
+            code_interpreter: CodeInterpreterConfig::default(),
+            sql_connections: Vec::new(),
+            browser: BrowserToolConfig::default(),
+            permission_mode: PermissionMode::default(),
+            sleep_resume: SleepResumeConfig::default(),
+            provider_profiles: ProviderProfilesConfig::default(),
+            keychain: KeychainConfig::default(),
+            scheduled_tasks: Vec::new(),
+            custom_tools: Vec::new(),
+            wasm_plugins: WasmPluginsConfig::default(),
+            mcp_host: McpHostConfig::default(),
+            embeddings: EmbeddingsConfig::default(),
         }
     }
 }
@@ -1060,4 +2735,52 @@ mod unit_tests {
         let parsed: EndpointProvidersConfig = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed, config);
     }
+
+    #[test]
+    fn test_server_config_validate_network() {
+        let mut config = ServerConfig::default();
+        assert!(config.validate_network().is_ok());
+
+        // 对局域网开放时必须配置白名单
+        config.host = "0.0.0.0".to_string();
+        assert!(config.validate_network().is_err());
+
+        config.allowed_ips = vec!["192.168.1.0/24".to_string(), "10.0.0.8".to_string()];
+        assert!(config.validate_network().is_ok());
+        assert_eq!(config.parse_allowed_ips().unwrap().len(), 2);
+
+        config.allowed_ips.push("192.168.1.300".to_string());
+        assert!(config
+            .validate_network()
+            .unwrap_err()
+            .contains("192.168.1.300"));
+        config.allowed_ips.pop();
+
+        // 启用请求签名时必须配置密钥
+        config.request_signing.enabled = true;
+        assert!(config.validate_network().is_err());
+        config.request_signing.secret = "shared-secret".to_string();
+        assert!(config.validate_network().is_ok());
+    }
+
+    #[test]
+    fn test_cors_allows_origin() {
+        let mut config = CorsConfig::default();
+        assert!(config.allows_origin("http://localhost:5173"));
+        assert!(config.allows_origin("http://127.0.0.1"));
+        assert!(config.allows_origin("https://LOCALHOST:8443/"));
+        assert!(!config.allows_origin("http://localhost.evil.com"));
+        assert!(!config.allows_origin("http://localhost:80@evil.com"));
+        assert!(!config.allows_origin("https://chat.example.com"));
+        assert!(config.allows_method("post"));
+        assert!(!config.allows_method("PATCH"));
+
+        config.allowed_origins = vec!["https://chat.example.com".to_string()];
+        assert!(config.allows_origin("https://chat.example.com"));
+        assert!(!config.allows_origin("http://chat.example.com"));
+        assert!(!config.allows_origin("http://localhost:5173"));
+
+        config.allowed_origins = vec!["*".to_string()];
+        assert!(config.allows_origin("https://any.example.org"));
+    }
 }
@@ -5,6 +5,7 @@
 
 #![allow(dead_code)]
 
+use super::secrets;
 use super::types::Config;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -22,6 +23,8 @@ pub enum ConfigError {
     SerializeError(String),
     /// 配置验证错误
     ValidationError(String),
+    /// 引用（环境变量、文件、钥匙串）解析错误
+    SecretError(String),
 }
 
 impl std::fmt::Display for ConfigError {
@@ -32,6 +35,7 @@ impl std::fmt::Display for ConfigError {
             ConfigError::ParseError(msg) => write!(f, "YAML 解析错误: {}", msg),
             ConfigError::SerializeError(msg) => write!(f, "YAML 序列化错误: {}", msg),
             ConfigError::ValidationError(msg) => write!(f, "配置验证错误: {}", msg),
+            ConfigError::SecretError(msg) => write!(f, "配置引用解析错误: {}", msg),
         }
     }
 }
@@ -73,7 +77,7 @@ impl ConfigManager {
         let config = if path.exists() {
             let content =
                 std::fs::read_to_string(path).map_err(|e| ConfigError::ReadError(e.to_string()))?;
-            Self::parse_yaml(&content)?
+            Self::parse_config_file(&content)?
         } else {
             Config::default()
         };
@@ -89,6 +93,13 @@ impl ConfigManager {
         serde_yaml::from_str(yaml).map_err(|e| ConfigError::ParseError(e.to_string()))
     }
 
+    /// 解析配置文件内容，并展开环境变量、文件和钥匙串引用
+    pub fn parse_config_file(yaml: &str) -> Result<Config, ConfigError> {
+        let mut config = Self::parse_yaml(yaml)?;
+        secrets::resolve_references(&mut config).map_err(ConfigError::SecretError)?;
+        Ok(config)
+    }
+
     /// 将配置序列化为 YAML 字符串
     pub fn to_yaml(config: &Config) -> Result<String, ConfigError> {
         serde_yaml::to_string(config).map_err(|e| ConfigError::SerializeError(e.to_string()))
@@ -106,19 +117,23 @@ impl ConfigManager {
             std::fs::create_dir_all(parent).map_err(|e| ConfigError::WriteError(e.to_string()))?;
         }
 
+        let backup_path = path.with_extension("yaml.backup");
         if path.exists() {
-            let backup_path = path.with_extension("yaml.backup");
-            let _ = std::fs::copy(path, backup_path);
+            let _ = std::fs::copy(path, &backup_path);
         }
-        let yaml = Self::to_yaml(&self.config)?;
-        std::fs::write(path, yaml).map_err(|e| ConfigError::WriteError(e.to_string()))
+        let yaml = Self::to_yaml(&secrets::config_for_file(&self.config, path))?;
+        std::fs::write(path, yaml).map_err(|e| ConfigError::WriteError(e.to_string()))?;
+        if self.config.keychain.enabled {
+            remove_plaintext_backup(&backup_path);
+        }
+        Ok(())
     }
 
     /// 重新加载配置
     pub fn reload(&mut self) -> Result<(), ConfigError> {
         let content = std::fs::read_to_string(&self.config_path)
             .map_err(|e| ConfigError::ReadError(e.to_string()))?;
-        self.config = Self::parse_yaml(&content)?;
+        self.config = Self::parse_config_file(&content)?;
         Ok(())
     }
 
@@ -291,7 +306,7 @@ impl YamlService {
         };
 
         // 序列化新配置
-        let new_yaml = ConfigManager::to_yaml(config)?;
+        let new_yaml = ConfigManager::to_yaml(&secrets::config_for_file(config, path))?;
 
         // 如果原文件存在，尝试保留注释
         let final_content = if let Some(original) = original_content {
@@ -673,16 +688,23 @@ pub fn load_config() -> Result<Config, Box<dyn std::error::Error>> {
     if yaml_path.exists() {
         let content = std::fs::read_to_string(&yaml_path)?;
         let mut config: Config = serde_yaml::from_str(&content)?;
+        let migrate_secrets = config.keychain.enabled && secrets::has_plaintext_secrets(&config);
+        secrets::resolve_references(&mut config)?;
         // 如果配置中使用默认 API Key，生成强随机 Key 并保存
         if is_default_api_key(&config.server.api_key) {
             let new_key = generate_secure_api_key();
             tracing::warn!("[CONFIG] 检测到默认 API Key，已自动生成强随机 Key");
             config.server.api_key = new_key;
             // 保存更新后的配置
-            if let Err(e) = save_config_yaml(&config) {
-                tracing::error!("[CONFIG] 保存配置失败: {}", e);
+            if !migrate_secrets {
+                if let Err(e) = save_config_yaml(&config) {
+                    tracing::error!("[CONFIG] 保存配置失败: {}", e);
+                }
             }
         }
+        if migrate_secrets {
+            migrate_plaintext_secrets(&config);
+        }
         return Ok(config);
     }
 
@@ -690,6 +712,7 @@ pub fn load_config() -> Result<Config, Box<dyn std::error::Error>> {
     if json_path.exists() {
         let content = std::fs::read_to_string(&json_path)?;
         let mut config: Config = serde_json::from_str(&content)?;
+        secrets::resolve_references(&mut config)?;
         // 如果配置中使用默认 API Key，生成强随机 Key 并保存
         if is_default_api_key(&config.server.api_key) {
             let new_key = generate_secure_api_key();
@@ -715,36 +738,70 @@ pub fn load_config() -> Result<Config, Box<dyn std::error::Error>> {
     Ok(config)
 }
 
+/// 将配置文件中残留的明文 Key 迁移到系统钥匙串（同时改写 JSON 配置）
+fn migrate_plaintext_secrets(config: &Config) {
+    match save_config(config) {
+        Ok(()) => tracing::info!("[KEYCHAIN] 已将配置文件中的明文 Key 迁移到系统钥匙串"),
+        Err(e) => tracing::error!("[KEYCHAIN] 迁移明文 Key 失败: {}", e),
+    }
+}
+
 /// 保存配置（同时写入 YAML 与 JSON，兼容旧版）
 pub fn save_config(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let config = secrets::config_for_file(config, &ConfigManager::default_config_path());
+
     // 主配置优先写入 YAML
-    save_config_yaml(config)?;
+    write_config_yaml(&config)?;
 
     // 兼容旧版 JSON 配置
     let path = json_config_path();
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)?;
     }
-    let content = serde_json::to_string_pretty(config)?;
+    let content = serde_json::to_string_pretty(&config)?;
     std::fs::write(&path, content)?;
     Ok(())
 }
 
 /// 保存配置为 YAML 格式
 pub fn save_config_yaml(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    write_config_yaml(&secrets::config_for_file(
+        config,
+        &ConfigManager::default_config_path(),
+    ))
+}
+
+/// 写入 YAML 配置文件（Key 已按钥匙串配置处理）
+fn write_config_yaml(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
     let path = ConfigManager::default_config_path();
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)?;
     }
+    let backup_path = path.with_extension("yaml.backup");
     if path.exists() {
-        let backup_path = path.with_extension("yaml.backup");
         let _ = std::fs::copy(&path, &backup_path);
     }
     let content = serde_yaml::to_string(config)?;
     std::fs::write(&path, content)?;
+    if config.keychain.enabled {
+        remove_plaintext_backup(&backup_path);
+    }
     Ok(())
 }
 
+/// 启用钥匙串后删除仍包含明文 Key 的配置备份
+fn remove_plaintext_backup(backup_path: &Path) {
+    let Ok(content) = std::fs::read_to_string(backup_path) else {
+        return;
+    };
+    let has_plaintext = ConfigManager::parse_yaml(&content)
+        .map(|backup| secrets::has_plaintext_secrets(&backup))
+        .unwrap_or(false);
+    if has_plaintext {
+        let _ = std::fs::remove_file(backup_path);
+    }
+}
+
 #[cfg(test)]
 mod unit_tests {
     use super::*;
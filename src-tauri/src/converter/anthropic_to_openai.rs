@@ -1,4 +1,5 @@
 //! Anthropic 格式转换为 OpenAI 格式 (支持 Claude Code)
+use crate::agent::attachments::document_as_text;
 use crate::models::anthropic::*;
 use crate::models::openai::*;
 use uuid::Uuid;
@@ -35,6 +36,7 @@ pub fn convert_anthropic_to_openai(request: &AnthropicMessagesRequest) -> ChatCo
                     name: t.name.clone(),
                     description: t.description.clone(),
                     parameters: t.input_schema.clone(),
+                    strict: None,
                 },
             })
             .collect()
@@ -50,6 +52,7 @@ pub fn convert_anthropic_to_openai(request: &AnthropicMessagesRequest) -> ChatCo
         tools,
         tool_choice: request.tool_choice.clone(),
         reasoning_effort: None,
+        stream_options: None,
     }
 }
 
@@ -73,6 +76,26 @@ fn extract_system_text(system: &serde_json::Value) -> String {
     }
 }
 
+/// 提取文档块文本（base64 PDF 等需要解析，text 来源直接使用）
+fn document_text(part: &serde_json::Value) -> String {
+    let title = part.get("title").and_then(|t| t.as_str());
+    let source = part.get("source");
+    let field = |key: &str| {
+        source
+            .and_then(|s| s.get(key))
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+    };
+    match field("type") {
+        "base64" => document_as_text(title, field("media_type"), field("data")),
+        "text" => format!("{}\n\n", field("data")),
+        _ => format!(
+            "[文档附件 {} 无法提取文本]\n\n",
+            title.unwrap_or("document")
+        ),
+    }
+}
+
 fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ChatMessage> {
     let mut result: Vec<ChatMessage> = Vec::new();
 
@@ -125,6 +148,8 @@ fn convert_anthropic_message(msg: &AnthropicMessage) -> Vec<ChatMessage> {
                         let content = extract_tool_result_content(part.get("content"));
                         tool_results.push((tool_use_id.to_string(), content));
                     }
+                    // OpenAI 格式没有通用的文档输入，提取文本后作为用户消息内容
+                    "document" => text_parts.push(document_text(part)),
                     _ => {}
                 }
             }
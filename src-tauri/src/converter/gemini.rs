@@ -0,0 +1,341 @@
+//! Gemini 原生格式与 OpenAI 格式互相转换
+//!
+//! 以 OpenAI 格式作为中间格式，配合 `anthropic_to_openai` 实现三种协议之间的互通：
+//! - OpenAI/Anthropic 入口使用 Gemini API Key 凭证时：OpenAI 请求 → Gemini 请求，Gemini 响应 → OpenAI 响应
+//! - Gemini 入口（`/v1/gemini/*`）使用其他凭证时：Gemini 请求 → OpenAI 请求，OpenAI 响应 → Gemini 响应
+//!
+//! 流式响应的转换见 [`crate::streaming::converter`]。
+
+use crate::converter::openai_to_antigravity::{
+    convert_antigravity_to_openai_response, convert_openai_to_antigravity_with_context,
+};
+use crate::models::openai::*;
+use uuid::Uuid;
+
+/// 将 OpenAI ChatCompletionRequest 转换为 Gemini `generateContent` 请求体
+///
+/// 复用 Antigravity 转换的内层请求（两者的 contents/tools/generationConfig 结构一致），
+/// 去掉 Gemini API 不接受的 sessionId。模型名放在 URL 中，不在请求体里。
+pub fn convert_openai_to_gemini(request: &ChatCompletionRequest) -> serde_json::Value {
+    let mut body = convert_openai_to_antigravity_with_context(request, "");
+    let mut inner = body["request"].take();
+    if let Some(obj) = inner.as_object_mut() {
+        obj.remove("sessionId");
+    }
+    inner
+}
+
+/// 将 Gemini 响应转换为 OpenAI ChatCompletion 响应
+pub fn convert_gemini_response_to_openai(
+    gemini_resp: &serde_json::Value,
+    model: &str,
+) -> serde_json::Value {
+    let mut response = convert_antigravity_to_openai_response(gemini_resp, model);
+
+    // Gemini 调用函数时 finishReason 仍为 STOP
+    if let Some(choices) = response["choices"].as_array_mut() {
+        for choice in choices {
+            if choice["message"]["tool_calls"].is_array() && choice["finish_reason"] == "stop" {
+                choice["finish_reason"] = serde_json::json!("tool_calls");
+            }
+        }
+    }
+    response
+}
+
+/// 将 Gemini `generateContent` 请求体转换为 OpenAI ChatCompletionRequest
+///
+/// functionCall 没有 ID，按出现顺序生成，后续的 functionResponse 按函数名依次匹配。
+pub fn convert_gemini_to_openai_request(
+    body: &serde_json::Value,
+    model: &str,
+    stream: bool,
+) -> ChatCompletionRequest {
+    let mut messages = Vec::new();
+
+    let system_instruction = body
+        .get("systemInstruction")
+        .or_else(|| body.get("system_instruction"));
+    if let Some(system) = system_instruction {
+        let text = parts_text(&system["parts"]);
+        if !text.is_empty() {
+            messages.push(ChatMessage {
+                role: "system".to_string(),
+                content: Some(MessageContent::Text(text)),
+                tool_calls: None,
+                tool_call_id: None,
+            });
+        }
+    }
+
+    // 等待 functionResponse 的调用 (name, id)
+    let mut pending_calls: Vec<(String, String)> = Vec::new();
+
+    for content in body["contents"].as_array().into_iter().flatten() {
+        let parts = content["parts"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or(&[]);
+
+        if content["role"].as_str() == Some("model") {
+            let text = parts_text(&content["parts"]);
+            let tool_calls: Vec<ToolCall> = parts
+                .iter()
+                .filter_map(|part| part.get("functionCall"))
+                .map(|call| {
+                    let name = call["name"].as_str().unwrap_or_default().to_string();
+                    let id = format!("call_{}", &Uuid::new_v4().simple().to_string()[..24]);
+                    pending_calls.push((name.clone(), id.clone()));
+                    ToolCall {
+                        id,
+                        call_type: "function".to_string(),
+                        function: FunctionCall {
+                            name,
+                            arguments: call
+                                .get("args")
+                                .map(|args| args.to_string())
+                                .unwrap_or_else(|| "{}".to_string()),
+                        },
+                    }
+                })
+                .collect();
+
+            messages.push(ChatMessage {
+                role: "assistant".to_string(),
+                content: (!text.is_empty()).then_some(MessageContent::Text(text)),
+                tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+                tool_call_id: None,
+            });
+            continue;
+        }
+
+        // user 消息：functionResponse 转为 tool 消息，其余内容合并为一条 user 消息
+        let mut content_parts = Vec::new();
+        for part in parts {
+            if let Some(response) = part.get("functionResponse") {
+                let name = response["name"].as_str().unwrap_or_default();
+                let id = match pending_calls.iter().position(|(n, _)| n == name) {
+                    Some(pos) => pending_calls.remove(pos).1,
+                    None => format!("call_{}", &Uuid::new_v4().simple().to_string()[..24]),
+                };
+                let output = match &response["response"] {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                messages.push(ChatMessage {
+                    role: "tool".to_string(),
+                    content: Some(MessageContent::Text(output)),
+                    tool_calls: None,
+                    tool_call_id: Some(id),
+                });
+            } else if let Some(text) = part["text"].as_str() {
+                content_parts.push(ContentPart::Text {
+                    text: text.to_string(),
+                });
+            } else if let Some(inline) = part.get("inlineData").or_else(|| part.get("inline_data"))
+            {
+                let mime_type = inline
+                    .get("mimeType")
+                    .or_else(|| inline.get("mime_type"))
+                    .and_then(|m| m.as_str())
+                    .unwrap_or("image/png");
+                content_parts.push(ContentPart::ImageUrl {
+                    image_url: ImageUrl {
+                        url: format!(
+                            "data:{};base64,{}",
+                            mime_type,
+                            inline["data"].as_str().unwrap_or_default()
+                        ),
+                        detail: None,
+                    },
+                });
+            }
+        }
+
+        let content = match content_parts.as_slice() {
+            [] => continue,
+            [ContentPart::Text { text }] => MessageContent::Text(text.clone()),
+            _ => MessageContent::Parts(content_parts),
+        };
+        messages.push(ChatMessage {
+            role: "user".to_string(),
+            content: Some(content),
+            tool_calls: None,
+            tool_call_id: None,
+        });
+    }
+
+    let tools: Vec<Tool> = body["tools"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|tool| {
+            tool.get("functionDeclarations")
+                .or_else(|| tool.get("function_declarations"))
+                .and_then(|d| d.as_array())
+                .cloned()
+                .unwrap_or_default()
+        })
+        .map(|decl| Tool::Function {
+            function: FunctionDef {
+                name: decl["name"].as_str().unwrap_or_default().to_string(),
+                description: decl["description"].as_str().map(str::to_string),
+                parameters: decl
+                    .get("parametersJsonSchema")
+                    .or_else(|| decl.get("parameters"))
+                    .cloned(),
+                strict: None,
+            },
+        })
+        .collect();
+
+    let config = body
+        .get("generationConfig")
+        .or_else(|| body.get("generation_config"))
+        .cloned()
+        .unwrap_or_default();
+
+    ChatCompletionRequest {
+        model: model.to_string(),
+        messages,
+        temperature: config["temperature"].as_f64().map(|v| v as f32),
+        max_tokens: config["maxOutputTokens"].as_u64().map(|v| v as u32),
+        top_p: config["topP"].as_f64().map(|v| v as f32),
+        stream,
+        tools: (!tools.is_empty()).then_some(tools),
+        tool_choice: None,
+        reasoning_effort: None,
+        stream_options: None,
+    }
+}
+
+/// 将 OpenAI ChatCompletion 响应转换为 Gemini `generateContent` 响应
+pub fn convert_openai_response_to_gemini(openai_resp: &serde_json::Value) -> serde_json::Value {
+    let candidates: Vec<serde_json::Value> = openai_resp["choices"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|choice| {
+            let message = &choice["message"];
+            let mut parts = Vec::new();
+            if let Some(text) = message["content"].as_str().filter(|t| !t.is_empty()) {
+                parts.push(serde_json::json!({"text": text}));
+            }
+            for tc in message["tool_calls"].as_array().into_iter().flatten() {
+                let arguments = tc["function"]["arguments"].as_str().unwrap_or("{}");
+                let args = serde_json::from_str::<serde_json::Value>(arguments)
+                    .unwrap_or_else(|_| serde_json::json!({}));
+                parts.push(serde_json::json!({
+                    "functionCall": {
+                        "name": tc["function"]["name"],
+                        "args": args
+                    }
+                }));
+            }
+
+            let finish_reason = match choice["finish_reason"].as_str() {
+                Some("length") => "MAX_TOKENS",
+                Some("content_filter") => "SAFETY",
+                _ => "STOP",
+            };
+            serde_json::json!({
+                "content": {"role": "model", "parts": parts},
+                "finishReason": finish_reason,
+                "index": choice["index"].as_u64().unwrap_or(0)
+            })
+        })
+        .collect();
+
+    let usage = &openai_resp["usage"];
+    serde_json::json!({
+        "candidates": candidates,
+        "usageMetadata": {
+            "promptTokenCount": usage["prompt_tokens"].as_u64().unwrap_or(0),
+            "candidatesTokenCount": usage["completion_tokens"].as_u64().unwrap_or(0),
+            "totalTokenCount": usage["total_tokens"].as_u64().unwrap_or(0)
+        },
+        "modelVersion": openai_resp["model"]
+    })
+}
+
+/// 拼接 parts 中的文本（跳过思维内容）
+fn parts_text(parts: &serde_json::Value) -> String {
+    parts
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|part| part["thought"].as_bool() != Some(true))
+        .filter_map(|part| part["text"].as_str())
+        .collect::<Vec<_>>()
+        .join("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gemini_request_to_openai() {
+        let body = serde_json::json!({
+            "systemInstruction": {"parts": [{"text": "Be brief."}]},
+            "contents": [
+                {"role": "user", "parts": [{"text": "Read a.rs"}]},
+                {"role": "model", "parts": [{"functionCall": {"name": "read_file", "args": {"path": "a.rs"}}}]},
+                {"role": "user", "parts": [{"functionResponse": {"name": "read_file", "response": {"content": "fn main() {}"}}}]}
+            ],
+            "tools": [{"functionDeclarations": [{"name": "read_file", "parameters": {"type": "object"}}]}],
+            "generationConfig": {"temperature": 0.5, "maxOutputTokens": 256}
+        });
+
+        let request = convert_gemini_to_openai_request(&body, "gpt-4o", true);
+
+        assert_eq!(request.model, "gpt-4o");
+        assert!(request.stream);
+        assert_eq!(request.max_tokens, Some(256));
+        assert_eq!(request.tools.as_ref().map(Vec::len), Some(1));
+        let roles: Vec<&str> = request.messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["system", "user", "assistant", "tool"]);
+        let call = &request.messages[2].tool_calls.as_ref().unwrap()[0];
+        assert_eq!(call.function.arguments, r#"{"path":"a.rs"}"#);
+        assert_eq!(
+            request.messages[3].tool_call_id.as_deref(),
+            Some(call.id.as_str())
+        );
+    }
+
+    #[test]
+    fn test_openai_round_trip() {
+        let request = convert_gemini_to_openai_request(
+            &serde_json::json!({"contents": [{"role": "user", "parts": [{"text": "Hi"}]}]}),
+            "gemini-2.5-flash",
+            false,
+        );
+        let body = convert_openai_to_gemini(&request);
+        assert_eq!(body["contents"][0]["parts"][0]["text"], "Hi");
+        assert!(body.get("sessionId").is_none());
+
+        let openai_resp = serde_json::json!({
+            "model": "gpt-4o",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": "Checking.",
+                    "tool_calls": [{"id": "call_1", "type": "function", "function": {"name": "read_file", "arguments": "{\"path\":\"a.rs\"}"}}]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": {"prompt_tokens": 3, "completion_tokens": 5, "total_tokens": 8}
+        });
+        let gemini_resp = convert_openai_response_to_gemini(&openai_resp);
+        assert_eq!(gemini_resp["usageMetadata"]["totalTokenCount"], 8);
+        assert_eq!(
+            gemini_resp["candidates"][0]["content"]["parts"][1]["functionCall"]["args"]["path"],
+            "a.rs"
+        );
+
+        let back = convert_gemini_response_to_openai(&gemini_resp, "gpt-4o");
+        assert_eq!(back["choices"][0]["finish_reason"], "tool_calls");
+        assert_eq!(back["choices"][0]["message"]["content"], "Checking.");
+    }
+}
@@ -703,6 +703,32 @@ fn convert_user_content(msg: &ChatMessage) -> Vec<GeminiPart> {
                             });
                         }
                     }
+                    ContentPart::InputAudio { input_audio } => {
+                        parts.push(GeminiPart {
+                            text: None,
+                            inline_data: Some(InlineData {
+                                mime_type: format!("audio/{}", input_audio.format),
+                                data: input_audio.data.clone(),
+                            }),
+                            function_call: None,
+                            function_response: None,
+                            thought_signature: None,
+                        });
+                    }
+                    ContentPart::File { file } => {
+                        if let Some((mime, data)) = file.parse_data_url() {
+                            parts.push(GeminiPart {
+                                text: None,
+                                inline_data: Some(InlineData {
+                                    mime_type: mime.to_string(),
+                                    data: data.to_string(),
+                                }),
+                                function_call: None,
+                                function_response: None,
+                                thought_signature: None,
+                            });
+                        }
+                    }
                 }
             }
         }
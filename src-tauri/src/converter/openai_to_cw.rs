@@ -8,6 +8,7 @@
 
 #![allow(dead_code)]
 
+use crate::agent::attachments::document_as_text;
 use crate::models::codewhisperer::*;
 use crate::models::openai::*;
 use std::collections::HashMap;
@@ -72,6 +73,30 @@ pub fn get_supported_models() -> Vec<&'static str> {
 
 pub const DEFAULT_MODEL: &str = "CLAUDE_SONNET_4_5_20250929_V1_0";
 
+/// 用户消息文本：CodeWhisperer 不支持文档输入，文件先提取文本再拼接到消息前
+fn user_content_text(msg: &ChatMessage) -> String {
+    let Some(MessageContent::Parts(parts)) = &msg.content else {
+        return msg.get_content_text();
+    };
+    let mut content: String = parts
+        .iter()
+        .filter_map(|part| match part {
+            ContentPart::File { file } => Some(match file.parse_data_url() {
+                Some((media_type, data)) => {
+                    document_as_text(file.filename.as_deref(), media_type, data)
+                }
+                None => format!(
+                    "[文档附件 {} 无法提取文本]\n\n",
+                    file.filename.as_deref().unwrap_or("document")
+                ),
+            }),
+            _ => None,
+        })
+        .collect();
+    content.push_str(&msg.get_content_text());
+    content
+}
+
 /// 预处理消息：合并连续的 tool 消息到前一个 assistant 消息后的 user 消息
 fn preprocess_messages(messages: &[&ChatMessage]) -> Vec<ProcessedMessage> {
     let mut result: Vec<ProcessedMessage> = Vec::new();
@@ -91,7 +116,7 @@ fn preprocess_messages(messages: &[&ChatMessage]) -> Vec<ProcessedMessage> {
             }
             "user" => {
                 // 如果有待处理的 tool results，合并到这个 user 消息
-                let content = msg.get_content_text();
+                let content = user_content_text(msg);
                 let mut tool_results = pending_tool_results.clone();
                 pending_tool_results.clear();
 
@@ -0,0 +1,167 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+/// Agent 长期记忆
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentMemory {
+    pub id: String,
+    /// 记忆内容
+    pub content: String,
+    /// 来源：manual（用户手动添加）、tool（save_memory 工具）、auto（自动提取）
+    pub source: String,
+    /// 保存记忆的会话
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// 用户是否已审核（模型保存的记忆默认未审核）
+    #[serde(default = "default_reviewed")]
+    pub reviewed: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+fn default_reviewed() -> bool {
+    true
+}
+
+pub struct AgentMemoryDao;
+
+impl AgentMemoryDao {
+    pub fn insert(conn: &Connection, memory: &AgentMemory) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "INSERT INTO agent_memories
+             (id, content, source, session_id, reviewed, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                memory.id,
+                memory.content,
+                memory.source,
+                memory.session_id,
+                memory.reviewed,
+                memory.created_at,
+                memory.updated_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 获取所有记忆（按更新时间倒序）
+    pub fn list(conn: &Connection) -> Result<Vec<AgentMemory>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, content, source, session_id, reviewed, created_at, updated_at
+             FROM agent_memories ORDER BY updated_at DESC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(AgentMemory {
+                id: row.get(0)?,
+                content: row.get(1)?,
+                source: row.get(2)?,
+                session_id: row.get(3)?,
+                reviewed: row.get(4)?,
+                created_at: row.get(5)?,
+                updated_at: row.get(6)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// 检查是否已存在相同内容的记忆
+    pub fn exists_content(conn: &Connection, content: &str) -> Result<bool, rusqlite::Error> {
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM agent_memories WHERE content = ?",
+            [content],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    pub fn update_content(
+        conn: &Connection,
+        id: &str,
+        content: &str,
+        updated_at: &str,
+    ) -> Result<bool, rusqlite::Error> {
+        let affected = conn.execute(
+            "UPDATE agent_memories SET content = ?1, updated_at = ?2 WHERE id = ?3",
+            params![content, updated_at, id],
+        )?;
+        Ok(affected > 0)
+    }
+
+    /// 标记记忆为已审核
+    pub fn mark_reviewed(conn: &Connection, id: &str) -> Result<bool, rusqlite::Error> {
+        let affected = conn.execute("UPDATE agent_memories SET reviewed = 1 WHERE id = ?", [id])?;
+        Ok(affected > 0)
+    }
+
+    pub fn delete(conn: &Connection, id: &str) -> Result<bool, rusqlite::Error> {
+        let affected = conn.execute("DELETE FROM agent_memories WHERE id = ?", [id])?;
+        Ok(affected > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_connection() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS agent_memories (
+                id TEXT PRIMARY KEY,
+                content TEXT NOT NULL,
+                source TEXT NOT NULL DEFAULT 'manual',
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                session_id TEXT,
+                reviewed INTEGER NOT NULL DEFAULT 1
+            )",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn create_test_memory(id: &str, content: &str, updated_at: &str) -> AgentMemory {
+        AgentMemory {
+            id: id.to_string(),
+            content: content.to_string(),
+            source: "manual".to_string(),
+            session_id: None,
+            reviewed: true,
+            created_at: updated_at.to_string(),
+            updated_at: updated_at.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_insert_list_order() {
+        let conn = create_test_connection();
+        AgentMemoryDao::insert(&conn, &create_test_memory("a", "旧记忆", "2025-01-01")).unwrap();
+        AgentMemoryDao::insert(&conn, &create_test_memory("b", "新记忆", "2025-02-01")).unwrap();
+
+        let memories = AgentMemoryDao::list(&conn).unwrap();
+        let ids: Vec<_> = memories.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["b", "a"]);
+        assert!(AgentMemoryDao::exists_content(&conn, "旧记忆").unwrap());
+    }
+
+    #[test]
+    fn test_update_and_delete() {
+        let conn = create_test_connection();
+        AgentMemoryDao::insert(&conn, &create_test_memory("a", "内容", "2025-01-01")).unwrap();
+
+        assert!(AgentMemoryDao::update_content(&conn, "a", "新内容", "2025-03-01").unwrap());
+        assert_eq!(AgentMemoryDao::list(&conn).unwrap()[0].content, "新内容");
+
+        let mut saved = create_test_memory("b", "模型保存", "2025-03-02");
+        saved.session_id = Some("s1".to_string());
+        saved.reviewed = false;
+        AgentMemoryDao::insert(&conn, &saved).unwrap();
+        assert_eq!(AgentMemoryDao::list(&conn).unwrap()[0], saved);
+        assert!(AgentMemoryDao::mark_reviewed(&conn, "b").unwrap());
+        assert!(AgentMemoryDao::list(&conn).unwrap()[0].reviewed);
+
+        assert!(AgentMemoryDao::delete(&conn, "a").unwrap());
+        assert!(!AgentMemoryDao::delete(&conn, "a").unwrap());
+    }
+}
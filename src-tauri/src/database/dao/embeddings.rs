@@ -0,0 +1,186 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+/// 向量记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingRecord {
+    pub id: String,
+    pub collection: String,
+    pub text: String,
+    pub metadata: Option<serde_json::Value>,
+    pub model: String,
+    pub vector: Vec<f32>,
+    pub created_at: String,
+}
+
+pub struct EmbeddingDao;
+
+impl EmbeddingDao {
+    /// 插入一条向量记录
+    pub fn insert(conn: &Connection, record: &EmbeddingRecord) -> Result<(), rusqlite::Error> {
+        let metadata = record
+            .metadata
+            .as_ref()
+            .map(|m| serde_json::to_string(m).unwrap_or_default());
+
+        conn.execute(
+            "INSERT OR REPLACE INTO embeddings (id, collection, text, metadata, model, dims, vector, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                record.id,
+                record.collection,
+                record.text,
+                metadata,
+                record.model,
+                record.vector.len() as i64,
+                encode_vector(&record.vector),
+                record.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 获取集合中的所有向量记录
+    pub fn get_by_collection(
+        conn: &Connection,
+        collection: &str,
+    ) -> Result<Vec<EmbeddingRecord>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, collection, text, metadata, model, vector, created_at
+             FROM embeddings WHERE collection = ? ORDER BY created_at",
+        )?;
+
+        let records = stmt.query_map([collection], |row| {
+            let metadata: Option<String> = row.get(3)?;
+            let vector: Vec<u8> = row.get(5)?;
+            Ok(EmbeddingRecord {
+                id: row.get(0)?,
+                collection: row.get(1)?,
+                text: row.get(2)?,
+                metadata: metadata.and_then(|m| serde_json::from_str(&m).ok()),
+                model: row.get(4)?,
+                vector: decode_vector(&vector),
+                created_at: row.get(6)?,
+            })
+        })?;
+
+        records.collect()
+    }
+
+    /// 集合建立索引时使用的向量化模型（集合为空时返回 None）
+    pub fn get_collection_model(
+        conn: &Connection,
+        collection: &str,
+    ) -> Result<Option<String>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT model FROM embeddings WHERE collection = ? ORDER BY created_at LIMIT 1",
+        )?;
+        let mut rows = stmt.query_map([collection], |row| row.get(0))?;
+        rows.next().transpose()
+    }
+
+    /// 列出所有集合及其记录数
+    pub fn list_collections(conn: &Connection) -> Result<Vec<(String, i64)>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT collection, COUNT(*) FROM embeddings GROUP BY collection ORDER BY collection",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// 删除单条记录
+    pub fn delete(conn: &Connection, id: &str) -> Result<bool, rusqlite::Error> {
+        let affected = conn.execute("DELETE FROM embeddings WHERE id = ?", [id])?;
+        Ok(affected > 0)
+    }
+
+    /// 删除整个集合
+    pub fn delete_collection(
+        conn: &Connection,
+        collection: &str,
+    ) -> Result<usize, rusqlite::Error> {
+        conn.execute("DELETE FROM embeddings WHERE collection = ?", [collection])
+    }
+}
+
+/// 将向量编码为小端 f32 字节序列
+fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// 从小端 f32 字节序列解码向量
+fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_connection() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS embeddings (
+                id TEXT PRIMARY KEY,
+                collection TEXT NOT NULL,
+                text TEXT NOT NULL,
+                metadata TEXT,
+                model TEXT NOT NULL,
+                dims INTEGER NOT NULL,
+                vector BLOB NOT NULL,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn create_test_record(id: &str, collection: &str, vector: Vec<f32>) -> EmbeddingRecord {
+        EmbeddingRecord {
+            id: id.to_string(),
+            collection: collection.to_string(),
+            text: format!("text {}", id),
+            metadata: Some(serde_json::json!({"source": "test"})),
+            model: "text-embedding-3-small".to_string(),
+            vector,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    #[test]
+    fn test_insert_and_get_roundtrip() {
+        let conn = create_test_connection();
+        let record = create_test_record("a", "docs", vec![0.5, -1.25, 3.0]);
+        EmbeddingDao::insert(&conn, &record).unwrap();
+
+        let records = EmbeddingDao::get_by_collection(&conn, "docs").unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].vector, vec![0.5, -1.25, 3.0]);
+        assert_eq!(records[0].metadata, record.metadata);
+    }
+
+    #[test]
+    fn test_delete_collection() {
+        let conn = create_test_connection();
+        EmbeddingDao::insert(&conn, &create_test_record("a", "docs", vec![1.0])).unwrap();
+        EmbeddingDao::insert(&conn, &create_test_record("b", "docs", vec![2.0])).unwrap();
+        EmbeddingDao::insert(&conn, &create_test_record("c", "notes", vec![3.0])).unwrap();
+
+        assert_eq!(
+            EmbeddingDao::get_collection_model(&conn, "docs")
+                .unwrap()
+                .as_deref(),
+            Some("text-embedding-3-small")
+        );
+        assert_eq!(EmbeddingDao::delete_collection(&conn, "docs").unwrap(), 2);
+        assert!(EmbeddingDao::get_collection_model(&conn, "docs")
+            .unwrap()
+            .is_none());
+        let collections = EmbeddingDao::list_collections(&conn).unwrap();
+        assert_eq!(collections, vec![("notes".to_string(), 1)]);
+    }
+}
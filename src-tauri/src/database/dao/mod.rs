@@ -1,7 +1,11 @@
+pub mod agent_memory;
 pub mod api_key_provider;
+pub mod embeddings;
 pub mod installed_plugins;
 pub mod mcp;
 pub mod prompts;
 pub mod provider_pool;
 pub mod providers;
+pub mod scheduled_task;
 pub mod skills;
+pub mod token_budget;
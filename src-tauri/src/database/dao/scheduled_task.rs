@@ -0,0 +1,181 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+/// Agent 定时任务的一次执行记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledTaskRun {
+    pub id: String,
+    /// 任务名称
+    pub task_name: String,
+    /// 触发方式：schedule（按计划）、missed（休眠唤醒后补执行）、manual（手动执行）
+    pub trigger: String,
+    /// 状态：running、completed、failed
+    pub status: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    /// Agent 的最终回复
+    pub output: Option<String>,
+    /// 失败原因
+    pub error: Option<String>,
+}
+
+pub struct ScheduledTaskDao;
+
+impl ScheduledTaskDao {
+    pub fn insert(conn: &Connection, run: &ScheduledTaskRun) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "INSERT INTO scheduled_task_runs
+                (id, task_name, trigger_type, status, started_at, finished_at, output, error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                run.id,
+                run.task_name,
+                run.trigger,
+                run.status,
+                run.started_at,
+                run.finished_at,
+                run.output,
+                run.error,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 记录执行结果
+    pub fn finish(
+        conn: &Connection,
+        id: &str,
+        status: &str,
+        finished_at: &str,
+        output: Option<&str>,
+        error: Option<&str>,
+    ) -> Result<bool, rusqlite::Error> {
+        let affected = conn.execute(
+            "UPDATE scheduled_task_runs
+             SET status = ?2, finished_at = ?3, output = ?4, error = ?5
+             WHERE id = ?1",
+            params![id, status, finished_at, output, error],
+        )?;
+        Ok(affected > 0)
+    }
+
+    /// 将仍处于执行中的记录标记为失败（应用退出时未执行完的任务），返回修改数量
+    pub fn fail_unfinished(conn: &Connection, finished_at: &str) -> Result<usize, rusqlite::Error> {
+        conn.execute(
+            "UPDATE scheduled_task_runs
+             SET status = 'failed', finished_at = ?1, error = '应用退出，任务未执行完成'
+             WHERE status = 'running'",
+            [finished_at],
+        )
+    }
+
+    /// 获取执行记录（按开始时间倒序），可按任务过滤
+    pub fn list(
+        conn: &Connection,
+        task_name: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<ScheduledTaskRun>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT id, task_name, trigger_type, status, started_at, finished_at, output, error
+             FROM scheduled_task_runs
+             WHERE ?1 IS NULL OR task_name = ?1
+             ORDER BY started_at DESC
+             LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![task_name, limit], |row| {
+            Ok(ScheduledTaskRun {
+                id: row.get(0)?,
+                task_name: row.get(1)?,
+                trigger: row.get(2)?,
+                status: row.get(3)?,
+                started_at: row.get(4)?,
+                finished_at: row.get(5)?,
+                output: row.get(6)?,
+                error: row.get(7)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// 只保留任务最近的 `keep` 条记录，返回删除数量
+    pub fn prune(conn: &Connection, task_name: &str, keep: u32) -> Result<usize, rusqlite::Error> {
+        conn.execute(
+            "DELETE FROM scheduled_task_runs
+             WHERE task_name = ?1 AND id NOT IN (
+                SELECT id FROM scheduled_task_runs
+                WHERE task_name = ?1
+                ORDER BY started_at DESC
+                LIMIT ?2
+             )",
+            params![task_name, keep],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_connection() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS scheduled_task_runs (
+                id TEXT PRIMARY KEY,
+                task_name TEXT NOT NULL,
+                trigger_type TEXT NOT NULL,
+                status TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                finished_at TEXT,
+                output TEXT,
+                error TEXT
+            )",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn create_test_run(id: &str, task_name: &str, started_at: &str) -> ScheduledTaskRun {
+        ScheduledTaskRun {
+            id: id.to_string(),
+            task_name: task_name.to_string(),
+            trigger: "schedule".to_string(),
+            status: "running".to_string(),
+            started_at: started_at.to_string(),
+            finished_at: None,
+            output: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_insert_finish_prune() {
+        let conn = create_test_connection();
+        for (id, started_at) in [
+            ("a", "2026-03-01"),
+            ("b", "2026-03-02"),
+            ("c", "2026-03-03"),
+        ] {
+            ScheduledTaskDao::insert(&conn, &create_test_run(id, "daily", started_at)).unwrap();
+        }
+        ScheduledTaskDao::insert(&conn, &create_test_run("x", "weekly", "2026-03-01")).unwrap();
+
+        assert!(
+            ScheduledTaskDao::finish(&conn, "c", "completed", "2026-03-03", Some("ok"), None)
+                .unwrap()
+        );
+        assert_eq!(
+            ScheduledTaskDao::fail_unfinished(&conn, "2026-03-04").unwrap(),
+            3
+        );
+
+        let runs = ScheduledTaskDao::list(&conn, Some("daily"), 10).unwrap();
+        let ids: Vec<_> = runs.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["c", "b", "a"]);
+        assert_eq!(runs[0].output.as_deref(), Some("ok"));
+        assert_eq!(runs[1].status, "failed");
+
+        assert_eq!(ScheduledTaskDao::prune(&conn, "daily", 2).unwrap(), 1);
+        assert_eq!(ScheduledTaskDao::list(&conn, None, 10).unwrap().len(), 3);
+    }
+}
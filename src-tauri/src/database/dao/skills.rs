@@ -1,8 +1,8 @@
 //! Skills 数据访问对象
 //!
-//! 提供 Skills 和 Skill Repos 的 CRUD 操作。
+//! 提供 Skills 和 Skill Repos 的 CRUD 操作，以及 Skill 使用统计。
 
-use crate::models::{SkillRepo, SkillState, SkillStates};
+use crate::models::{SkillRepo, SkillState, SkillStates, SkillUsageStats};
 use chrono::DateTime;
 use rusqlite::{params, Connection};
 use std::collections::HashMap;
@@ -116,6 +116,45 @@ impl SkillDao {
         Ok(())
     }
 
+    /// 记录一次 Skill 使用（`invoked` 为 true 时计入调用次数，否则计入引用次数）
+    pub fn record_skill_use(
+        conn: &Connection,
+        directory: &str,
+        invoked: bool,
+        used_at: i64,
+    ) -> Result<(), rusqlite::Error> {
+        let (reference, invoke) = if invoked { (0, 1) } else { (1, 0) };
+        conn.execute(
+            "INSERT INTO skill_usage (directory, reference_count, invoke_count, last_used_at)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(directory) DO UPDATE SET
+                reference_count = reference_count + excluded.reference_count,
+                invoke_count = invoke_count + excluded.invoke_count,
+                last_used_at = MAX(last_used_at, excluded.last_used_at)",
+            params![directory, reference, invoke, used_at],
+        )?;
+        Ok(())
+    }
+
+    /// 获取所有 Skill 使用统计（`installed` 由调用方根据安装目录填充）
+    pub fn get_skill_usage(conn: &Connection) -> Result<Vec<SkillUsageStats>, rusqlite::Error> {
+        let mut stmt = conn.prepare(
+            "SELECT directory, reference_count, invoke_count, last_used_at
+             FROM skill_usage ORDER BY directory ASC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let last_used_at: i64 = row.get(3)?;
+            Ok(SkillUsageStats {
+                directory: row.get(0)?,
+                installed: false,
+                reference_count: row.get::<_, i64>(1)? as u64,
+                invoke_count: row.get::<_, i64>(2)? as u64,
+                last_used_at: DateTime::from_timestamp(last_used_at, 0),
+            })
+        })?;
+        rows.collect()
+    }
+
     /// 初始化默认 Skill 仓库
     pub fn init_default_skill_repos(conn: &Connection) -> Result<usize, rusqlite::Error> {
         use crate::models::skill_model::get_default_skill_repos;
@@ -0,0 +1,60 @@
+use rusqlite::{params, Connection};
+
+/// Token 预算用量汇总
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BudgetUsage {
+    /// Token 数（输入 + 输出）
+    pub tokens: u64,
+    /// 费用（美元）
+    pub cost_usd: f64,
+}
+
+pub struct TokenBudgetDao;
+
+impl TokenBudgetDao {
+    /// 累加一次请求的用量（主 API Key 的请求 `client_key` 为空字符串）
+    pub fn add_usage(
+        conn: &Connection,
+        day: &str,
+        client_key: &str,
+        provider: &str,
+        input_tokens: u32,
+        output_tokens: u32,
+        cost_usd: f64,
+    ) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "INSERT INTO token_budget_usage (day, client_key, provider, input_tokens, output_tokens, cost_usd)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(day, client_key, provider) DO UPDATE SET
+                input_tokens = input_tokens + excluded.input_tokens,
+                output_tokens = output_tokens + excluded.output_tokens,
+                cost_usd = cost_usd + excluded.cost_usd",
+            params![day, client_key, provider, input_tokens, output_tokens, cost_usd],
+        )?;
+        Ok(())
+    }
+
+    /// 汇总 `[from, to]` 日期范围内的用量，`client_key` / `provider` 为空时不限定
+    pub fn sum_usage(
+        conn: &Connection,
+        from: &str,
+        to: &str,
+        client_key: Option<&str>,
+        provider: Option<&str>,
+    ) -> Result<BudgetUsage, rusqlite::Error> {
+        conn.query_row(
+            "SELECT COALESCE(SUM(input_tokens + output_tokens), 0), COALESCE(SUM(cost_usd), 0)
+             FROM token_budget_usage
+             WHERE day >= ?1 AND day <= ?2
+               AND (?3 IS NULL OR client_key = ?3)
+               AND (?4 IS NULL OR provider = ?4)",
+            params![from, to, client_key, provider],
+            |row| {
+                Ok(BudgetUsage {
+                    tokens: row.get::<_, i64>(0)?.max(0) as u64,
+                    cost_usd: row.get(1)?,
+                })
+            },
+        )
+    }
+}
@@ -144,6 +144,17 @@ pub fn create_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
         [],
     )?;
 
+    // Skill 使用统计表（模型引用或调用技能时累计）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS skill_usage (
+            directory TEXT PRIMARY KEY,
+            reference_count INTEGER NOT NULL DEFAULT 0,
+            invoke_count INTEGER NOT NULL DEFAULT 0,
+            last_used_at INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+
     // Skill Repos 表
     conn.execute(
         "CREATE TABLE IF NOT EXISTS skill_repos (
@@ -246,6 +257,78 @@ pub fn create_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
         [],
     )?;
 
+    // 向量存储表（embeddings 模块使用）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS embeddings (
+            id TEXT PRIMARY KEY,
+            collection TEXT NOT NULL,
+            text TEXT NOT NULL,
+            metadata TEXT,
+            model TEXT NOT NULL,
+            dims INTEGER NOT NULL,
+            vector BLOB NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_embeddings_collection ON embeddings(collection)",
+        [],
+    )?;
+
+    // Agent 长期记忆表（跨会话保存的重要事实）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_memories (
+            id TEXT PRIMARY KEY,
+            content TEXT NOT NULL,
+            source TEXT NOT NULL DEFAULT 'manual',
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // Migration: 记录保存记忆的会话，模型保存的记忆需用户审核
+    let _ = conn.execute("ALTER TABLE agent_memories ADD COLUMN session_id TEXT", []);
+    let _ = conn.execute(
+        "ALTER TABLE agent_memories ADD COLUMN reviewed INTEGER NOT NULL DEFAULT 1",
+        [],
+    );
+
+    // Token 预算用量表（按天、客户端 Key、Provider 汇总）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS token_budget_usage (
+            day TEXT NOT NULL,
+            client_key TEXT NOT NULL DEFAULT '',
+            provider TEXT NOT NULL,
+            input_tokens INTEGER NOT NULL DEFAULT 0,
+            output_tokens INTEGER NOT NULL DEFAULT 0,
+            cost_usd REAL NOT NULL DEFAULT 0,
+            PRIMARY KEY (day, client_key, provider)
+        )",
+        [],
+    )?;
+
+    // Agent 定时任务执行记录表
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS scheduled_task_runs (
+            id TEXT PRIMARY KEY,
+            task_name TEXT NOT NULL,
+            trigger_type TEXT NOT NULL,
+            status TEXT NOT NULL,
+            started_at TEXT NOT NULL,
+            finished_at TEXT,
+            output TEXT,
+            error TEXT
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_scheduled_task_runs_task ON scheduled_task_runs(task_name, started_at)",
+        [],
+    )?;
+
     Ok(())
 }
 
@@ -0,0 +1,137 @@
+//! 向量化 API 客户端
+//!
+//! 调用 ProxyCast 服务器的 `/v1/embeddings` 端点
+
+use reqwest::Client;
+use std::time::Duration;
+
+/// 默认向量化模型
+pub const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+
+/// 向量化 API 客户端
+#[derive(Clone)]
+pub struct EmbeddingClient {
+    client: Client,
+    base_url: String,
+    api_key: String,
+}
+
+impl EmbeddingClient {
+    pub fn new(base_url: String, api_key: String) -> Result<Self, String> {
+        let client = crate::server::tls::local_client_builder(&base_url)
+            .timeout(Duration::from_secs(120))
+            .connect_timeout(Duration::from_secs(30))
+            .build()
+            .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+
+        Ok(Self {
+            client,
+            base_url,
+            api_key,
+        })
+    }
+
+    /// 批量生成向量，返回顺序与输入一致
+    pub async fn embed(&self, inputs: &[String], model: &str) -> Result<Vec<Vec<f32>>, String> {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let url = format!("{}/v1/embeddings", self.base_url);
+        let response = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .json(&serde_json::json!({
+                "model": model,
+                "input": inputs,
+            }))
+            .send()
+            .await
+            .map_err(|e| format!("请求失败: {}", e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("API 错误 ({}): {}", status, body));
+        }
+
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+
+        parse_embeddings_response(&body, inputs.len())
+    }
+
+    /// 生成单条文本的向量
+    pub async fn embed_one(&self, input: &str, model: &str) -> Result<Vec<f32>, String> {
+        self.embed(&[input.to_string()], model)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| "响应中没有向量数据".to_string())
+    }
+}
+
+/// 解析 OpenAI 格式的 embeddings 响应（按 index 排序）
+fn parse_embeddings_response(
+    body: &serde_json::Value,
+    expected: usize,
+) -> Result<Vec<Vec<f32>>, String> {
+    let data = body["data"]
+        .as_array()
+        .ok_or_else(|| "响应中没有 data 字段".to_string())?;
+
+    let mut items: Vec<(u64, Vec<f32>)> = data
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let index = item["index"].as_u64().unwrap_or(i as u64);
+            let vector = item["embedding"]
+                .as_array()
+                .map(|v| {
+                    v.iter()
+                        .filter_map(|x| x.as_f64())
+                        .map(|x| x as f32)
+                        .collect()
+                })
+                .unwrap_or_default();
+            (index, vector)
+        })
+        .collect();
+    items.sort_by_key(|(index, _)| *index);
+
+    if items.len() != expected {
+        return Err(format!(
+            "向量数量不匹配: 期望 {}，实际 {}",
+            expected,
+            items.len()
+        ));
+    }
+
+    Ok(items.into_iter().map(|(_, v)| v).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_embeddings_response_sorts_by_index() {
+        let body = serde_json::json!({
+            "data": [
+                {"index": 1, "embedding": [0.3, 0.4]},
+                {"index": 0, "embedding": [0.1, 0.2]}
+            ]
+        });
+        let vectors = parse_embeddings_response(&body, 2).unwrap();
+        assert_eq!(vectors, vec![vec![0.1, 0.2], vec![0.3, 0.4]]);
+    }
+
+    #[test]
+    fn test_parse_embeddings_response_count_mismatch() {
+        let body = serde_json::json!({"data": [{"index": 0, "embedding": [0.1]}]});
+        assert!(parse_embeddings_response(&body, 2).is_err());
+    }
+}
@@ -0,0 +1,15 @@
+//! 向量化与本地向量存储模块
+//!
+//! 通过 ProxyCast 服务器的 `/v1/embeddings` 端点生成向量，
+//! 并持久化到本地 SQLite 数据库，支持最近邻检索。
+//! 检索结果可作为 Agent 对话的补充上下文。
+//!
+//! ## 架构设计
+//! - client - 向量化 API 客户端
+//! - store - 基于 SQLite 的向量存储（暴力余弦相似度检索）
+
+pub mod client;
+pub mod store;
+
+pub use client::{EmbeddingClient, DEFAULT_EMBEDDING_MODEL};
+pub use store::{build_retrieval_context, cosine_similarity, ScoredEmbedding, VectorStore};
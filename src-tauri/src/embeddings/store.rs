@@ -0,0 +1,240 @@
+//! 本地向量存储
+//!
+//! 向量保存在 ProxyCast 数据库的 `embeddings` 表中，
+//! 检索时加载整个集合并计算余弦相似度（适用于个人规模的数据量）。
+//! 同一集合中的向量必须由同一个模型生成，查询时也使用该模型。
+
+use crate::database::dao::embeddings::{EmbeddingDao, EmbeddingRecord};
+use crate::database::DbConnection;
+use serde::{Deserialize, Serialize};
+
+/// 带相似度分数的检索结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoredEmbedding {
+    /// 记录 ID
+    pub id: String,
+    /// 原始文本
+    pub text: String,
+    /// 附加元数据
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+    /// 余弦相似度
+    pub score: f32,
+}
+
+/// 向量存储
+#[derive(Clone)]
+pub struct VectorStore {
+    db: DbConnection,
+}
+
+impl VectorStore {
+    pub fn new(db: DbConnection) -> Self {
+        Self { db }
+    }
+
+    /// 集合建立索引时使用的向量化模型（集合为空时返回 None）
+    pub fn collection_model(&self, collection: &str) -> Result<Option<String>, String> {
+        let conn = self.db.lock().map_err(|e| e.to_string())?;
+        EmbeddingDao::get_collection_model(&conn, collection).map_err(|e| e.to_string())
+    }
+
+    /// 集合使用的向量化模型
+    ///
+    /// 已有记录的集合使用建立索引时的模型（`requested` 与之不同时返回错误），
+    /// 空集合使用 `requested`，未指定时使用 `default`
+    pub fn model_for(
+        &self,
+        collection: &str,
+        requested: Option<&str>,
+        default: &str,
+    ) -> Result<String, String> {
+        let indexed = self.collection_model(collection)?;
+        if let Some(requested) = requested {
+            ensure_same_model(collection, indexed.as_deref(), requested)?;
+        }
+        Ok(indexed.unwrap_or_else(|| requested.unwrap_or(default).to_string()))
+    }
+
+    /// 添加一条向量记录，返回记录 ID
+    ///
+    /// 集合中已有其他模型生成的向量时返回错误
+    pub fn add(
+        &self,
+        collection: &str,
+        text: &str,
+        metadata: Option<serde_json::Value>,
+        model: &str,
+        vector: Vec<f32>,
+    ) -> Result<String, String> {
+        let record = EmbeddingRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            collection: collection.to_string(),
+            text: text.to_string(),
+            metadata,
+            model: model.to_string(),
+            vector,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+
+        let conn = self.db.lock().map_err(|e| e.to_string())?;
+        let indexed =
+            EmbeddingDao::get_collection_model(&conn, collection).map_err(|e| e.to_string())?;
+        ensure_same_model(collection, indexed.as_deref(), model)?;
+        EmbeddingDao::insert(&conn, &record).map_err(|e| e.to_string())?;
+        Ok(record.id)
+    }
+
+    /// 检索与查询向量最相似的 top_k 条记录
+    pub fn query(
+        &self,
+        collection: &str,
+        query: &[f32],
+        top_k: usize,
+    ) -> Result<Vec<ScoredEmbedding>, String> {
+        let records = {
+            let conn = self.db.lock().map_err(|e| e.to_string())?;
+            EmbeddingDao::get_by_collection(&conn, collection).map_err(|e| e.to_string())?
+        };
+
+        Ok(rank_records(records, query, top_k))
+    }
+
+    /// 列出所有集合及其记录数
+    pub fn list_collections(&self) -> Result<Vec<(String, i64)>, String> {
+        let conn = self.db.lock().map_err(|e| e.to_string())?;
+        EmbeddingDao::list_collections(&conn).map_err(|e| e.to_string())
+    }
+
+    /// 删除单条记录
+    pub fn delete(&self, id: &str) -> Result<bool, String> {
+        let conn = self.db.lock().map_err(|e| e.to_string())?;
+        EmbeddingDao::delete(&conn, id).map_err(|e| e.to_string())
+    }
+
+    /// 删除整个集合
+    pub fn delete_collection(&self, collection: &str) -> Result<usize, String> {
+        let conn = self.db.lock().map_err(|e| e.to_string())?;
+        EmbeddingDao::delete_collection(&conn, collection).map_err(|e| e.to_string())
+    }
+}
+
+/// 检查模型与集合建立索引时的模型一致（空集合不检查）
+fn ensure_same_model(collection: &str, indexed: Option<&str>, model: &str) -> Result<(), String> {
+    match indexed {
+        Some(indexed) if indexed != model => Err(format!(
+            "集合 {} 使用模型 {} 建立索引，不能使用 {}",
+            collection, indexed, model
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// 按余弦相似度排序并截取 top_k（跳过维度不一致的记录）
+fn rank_records(
+    records: Vec<EmbeddingRecord>,
+    query: &[f32],
+    top_k: usize,
+) -> Vec<ScoredEmbedding> {
+    let mut scored: Vec<ScoredEmbedding> = records
+        .into_iter()
+        .filter(|r| r.vector.len() == query.len())
+        .map(|r| ScoredEmbedding {
+            score: cosine_similarity(&r.vector, query),
+            id: r.id,
+            text: r.text,
+            metadata: r.metadata,
+        })
+        .collect();
+
+    scored.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    scored.truncate(top_k);
+    scored
+}
+
+/// 计算余弦相似度（任一向量为零向量时返回 0）
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f32, 0.0f32, 0.0f32);
+    for (x, y) in a.iter().zip(b.iter()) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+/// 将检索结果格式化为可注入对话的上下文
+pub fn build_retrieval_context(results: &[ScoredEmbedding]) -> String {
+    if results.is_empty() {
+        return String::new();
+    }
+
+    let mut context = String::from("以下是与问题相关的参考资料：\n");
+    for (i, r) in results.iter().enumerate() {
+        context.push_str(&format!("\n[{}] {}\n", i + 1, r.text));
+    }
+    context
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: &str, vector: Vec<f32>) -> EmbeddingRecord {
+        EmbeddingRecord {
+            id: id.to_string(),
+            collection: "docs".to_string(),
+            text: id.to_string(),
+            metadata: None,
+            model: "test".to_string(),
+            vector,
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[1.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn test_rank_records_orders_and_truncates() {
+        let records = vec![
+            record("far", vec![0.0, 1.0]),
+            record("near", vec![1.0, 0.1]),
+            record("mid", vec![1.0, 1.0]),
+            record("wrong_dims", vec![1.0, 0.0, 0.0]),
+        ];
+        let ranked = rank_records(records, &[1.0, 0.0], 2);
+        let ids: Vec<_> = ranked.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["near", "mid"]);
+    }
+
+    #[test]
+    fn test_ensure_same_model() {
+        assert!(ensure_same_model("docs", None, "a").is_ok());
+        assert!(ensure_same_model("docs", Some("a"), "a").is_ok());
+        assert!(ensure_same_model("docs", Some("a"), "b").is_err());
+    }
+
+    #[test]
+    fn test_build_retrieval_context() {
+        assert!(build_retrieval_context(&[]).is_empty());
+        let context = build_retrieval_context(&[ScoredEmbedding {
+            id: "1".to_string(),
+            text: "Rust 是一门系统编程语言".to_string(),
+            metadata: None,
+            score: 0.9,
+        }]);
+        assert!(context.contains("[1] Rust 是一门系统编程语言"));
+    }
+}
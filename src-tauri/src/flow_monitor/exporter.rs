@@ -2,6 +2,11 @@
 //!
 //! 提供多种格式的 Flow 导出功能，包括 HAR、JSON、JSONL、Markdown 和 CSV。
 //! 支持敏感数据脱敏和导出前过滤。
+//!
+//! 开启 `include_provenance` 后，Markdown 导出会在文档开头写入 front-matter、
+//! 在每个 Flow 前写入 HTML 注释，HAR 导出会在 `log.comment` 中写入来源信息
+//! （模型、提供商、时间戳、ProxyCast 版本），便于下游追溯 AI 生成内容。
+//! 会话 JSON 导出通过 [`FlowExporter::provenance_json`] 附加 `provenance` 字段。
 
 use regex::Regex;
 use serde::{Deserialize, Serialize};
@@ -67,6 +72,9 @@ pub struct ExportOptions {
     /// 是否压缩输出
     #[serde(default)]
     pub compress: bool,
+    /// 是否嵌入来源信息（模型、提供商、时间戳、ProxyCast 版本）
+    #[serde(default)]
+    pub include_provenance: bool,
 }
 
 fn default_true() -> bool {
@@ -83,6 +91,7 @@ impl Default for ExportOptions {
             redact_sensitive: false,
             redaction_rules: Vec::new(),
             compress: false,
+            include_provenance: false,
         }
     }
 }
@@ -670,11 +679,80 @@ impl FlowExporter {
                     comment: Some("LLM API Flow Export".to_string()),
                 },
                 entries,
-                comment: Some(format!("Exported {} flows", flows.len())),
+                comment: Some(match self.provenance_summary(&processed) {
+                    Some(provenance) => format!("Exported {} flows; {}", flows.len(), provenance),
+                    None => format!("Exported {} flows", flows.len()),
+                }),
             },
         }
     }
 
+    /// 生成 HAR 注释中的来源信息
+    fn provenance_summary(&self, flows: &[LLMFlow]) -> Option<String> {
+        if !self.options.include_provenance {
+            return None;
+        }
+        let (models, providers) = collect_models_and_providers(flows);
+        Some(format!(
+            "generator: ProxyCast {}; exported_at: {}; models: {}; providers: {}",
+            env!("CARGO_PKG_VERSION"),
+            chrono::Utc::now().to_rfc3339(),
+            models.join(", "),
+            providers.join(", ")
+        ))
+    }
+
+    /// 生成 JSON 形式的来源信息，未开启时返回 None
+    pub fn provenance_json(&self, flows: &[LLMFlow]) -> Option<serde_json::Value> {
+        if !self.options.include_provenance {
+            return None;
+        }
+        let (models, providers) = collect_models_and_providers(flows);
+        Some(serde_json::json!({
+            "generator": format!("ProxyCast {}", env!("CARGO_PKG_VERSION")),
+            "exported_at": chrono::Utc::now().to_rfc3339(),
+            "ai_generated": true,
+            "models": models,
+            "providers": providers,
+        }))
+    }
+
+    /// 生成 Markdown front-matter 形式的来源信息，未开启时返回 None
+    ///
+    /// front-matter 必须位于文档开头，拼接自定义标题时请先写入此内容。
+    pub fn provenance_front_matter(&self, flows: &[LLMFlow]) -> Option<String> {
+        if !self.options.include_provenance {
+            return None;
+        }
+        let (models, providers) = collect_models_and_providers(flows);
+        let yaml_list = |items: &[String]| {
+            if items.is_empty() {
+                " []\n".to_string()
+            } else {
+                items
+                    .iter()
+                    .map(|item| format!("\n  - {}", yaml_string(item)))
+                    .collect::<String>()
+                    + "\n"
+            }
+        };
+
+        let mut fm = String::from("---\n");
+        fm.push_str(&format!(
+            "generator: {}\n",
+            yaml_string(&format!("ProxyCast {}", env!("CARGO_PKG_VERSION")))
+        ));
+        fm.push_str(&format!(
+            "exported_at: {}\n",
+            yaml_string(&chrono::Utc::now().to_rfc3339())
+        ));
+        fm.push_str("ai_generated: true\n");
+        fm.push_str(&format!("models:{}", yaml_list(&models)));
+        fm.push_str(&format!("providers:{}", yaml_list(&providers)));
+        fm.push_str("---\n\n");
+        Some(fm)
+    }
+
     /// 将 Flow 转换为 HAR Entry
     fn flow_to_har_entry(&self, flow: &LLMFlow) -> HarEntry {
         let request = &flow.request;
@@ -868,11 +946,20 @@ impl FlowExporter {
     /// 导出单个 Flow 为 Markdown 格式
     pub fn export_markdown(&self, flow: &LLMFlow) -> String {
         let processed = self.preprocess_flow(flow);
-        self.flow_to_markdown(&processed)
+        let front_matter = self
+            .provenance_front_matter(std::slice::from_ref(&processed))
+            .unwrap_or_default();
+        front_matter + &self.flow_to_markdown(&processed)
     }
 
     /// 导出多个 Flow 为 Markdown 格式
     pub fn export_markdown_multiple(&self, flows: &[LLMFlow]) -> String {
+        let front_matter = self.provenance_front_matter(flows).unwrap_or_default();
+        front_matter + &self.export_markdown_sections(flows)
+    }
+
+    /// 导出多个 Flow 的 Markdown 正文（不含 front-matter）
+    pub fn export_markdown_sections(&self, flows: &[LLMFlow]) -> String {
         let processed = self.preprocess_flows(flows);
         processed
             .iter()
@@ -893,6 +980,17 @@ impl FlowExporter {
     fn flow_to_markdown(&self, flow: &LLMFlow) -> String {
         let mut md = String::new();
 
+        // 来源信息
+        if self.options.include_provenance {
+            md.push_str(&format!(
+                "<!-- proxycast-provenance model=\"{}\" provider=\"{:?}\" timestamp=\"{}\" generator=\"ProxyCast {}\" -->\n\n",
+                html_comment_safe(&flow.request.model),
+                flow.metadata.provider,
+                flow.timestamps.created.to_rfc3339(),
+                env!("CARGO_PKG_VERSION")
+            ));
+        }
+
         // 标题
         md.push_str(&format!("# LLM Flow: {}\n\n", flow.id));
 
@@ -1135,6 +1233,32 @@ impl FlowExporter {
     }
 }
 
+/// 收集去重后的模型和提供商列表（保持出现顺序）
+fn collect_models_and_providers(flows: &[LLMFlow]) -> (Vec<String>, Vec<String>) {
+    let mut models: Vec<String> = Vec::new();
+    let mut providers: Vec<String> = Vec::new();
+    for flow in flows {
+        if !models.contains(&flow.request.model) {
+            models.push(flow.request.model.clone());
+        }
+        let provider = format!("{:?}", flow.metadata.provider);
+        if !providers.contains(&provider) {
+            providers.push(provider);
+        }
+    }
+    (models, providers)
+}
+
+/// YAML 字符串（JSON 字符串是合法的 YAML 标量）
+fn yaml_string(s: &str) -> String {
+    serde_json::to_string(s).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+/// HTML 注释中不能出现 `--`
+fn html_comment_safe(s: &str) -> String {
+    s.replace("--", "- -").replace('"', "'")
+}
+
 /// CSV 字段转义
 fn escape_csv(s: &str) -> String {
     if s.contains(',') || s.contains('"') || s.contains('\n') {
@@ -1397,6 +1521,43 @@ mod tests {
         assert!(md.contains("## 响应"));
     }
 
+    #[test]
+    fn test_export_markdown_provenance() {
+        let flow = create_test_flow();
+        let exporter = FlowExporter::new(ExportOptions {
+            format: ExportFormat::Markdown,
+            include_provenance: true,
+            ..Default::default()
+        });
+        let md = exporter.export_markdown_multiple(&[flow.clone()]);
+
+        assert!(md.starts_with("---\ngenerator: \"ProxyCast "));
+        assert!(md.contains("ai_generated: true"));
+        assert!(md.contains("models:\n  - \"gpt-4\"\n"));
+        assert!(md.contains(&format!(
+            "<!-- proxycast-provenance model=\"gpt-4\" provider=\"{:?}\"",
+            flow.metadata.provider
+        )));
+
+        // 默认不包含来源信息
+        let md = FlowExporter::with_defaults().export_markdown_multiple(&[flow]);
+        assert!(md.starts_with("# LLM Flow:"));
+        assert!(!md.contains("proxycast-provenance"));
+    }
+
+    #[test]
+    fn test_export_har_provenance() {
+        let flow = create_test_flow();
+        let exporter = FlowExporter::new(ExportOptions {
+            format: ExportFormat::HAR,
+            include_provenance: true,
+            ..Default::default()
+        });
+        let comment = exporter.export_har(&[flow]).log.comment.unwrap();
+        assert!(comment.contains("generator: ProxyCast"));
+        assert!(comment.contains("models: gpt-4"));
+    }
+
     #[test]
     fn test_export_csv() {
         let flow = create_test_flow();
@@ -714,6 +714,7 @@ impl SessionManager {
     /// * `session_id` - 会话 ID
     /// * `flows` - 会话中的 Flow 列表
     /// * `format` - 导出格式
+    /// * `include_provenance` - 是否嵌入来源信息（Markdown front-matter / HAR 注释）
     ///
     /// # Returns
     /// 导出结果
@@ -722,6 +723,7 @@ impl SessionManager {
         session_id: &str,
         flows: &[LLMFlow],
         format: ExportFormat,
+        include_provenance: bool,
     ) -> Result<SessionExportResult> {
         // 获取会话信息
         let session = self
@@ -737,6 +739,7 @@ impl SessionManager {
             redact_sensitive: false,
             redaction_rules: Vec::new(),
             compress: false,
+            include_provenance,
         };
         let exporter = FlowExporter::new(options);
 
@@ -748,26 +751,30 @@ impl SessionManager {
             }
             ExportFormat::JSON => {
                 // 包含会话信息的完整导出
-                let export_data = serde_json::json!({
+                let mut export_data = serde_json::json!({
                     "session": session,
                     "flows": flows,
                 });
+                if let Some(provenance) = exporter.provenance_json(flows) {
+                    export_data["provenance"] = provenance;
+                }
                 serde_json::to_string_pretty(&export_data)?
             }
             ExportFormat::JSONL => exporter.export_jsonl(flows),
             ExportFormat::Markdown => {
-                let mut md = format!(
+                let mut md = exporter.provenance_front_matter(flows).unwrap_or_default();
+                md.push_str(&format!(
                     "# 会话: {}\n\n**ID**: {}\n**创建时间**: {}\n**Flow 数量**: {}\n\n",
                     session.name,
                     session.id,
                     session.created_at.format("%Y-%m-%d %H:%M:%S UTC"),
                     flows.len()
-                );
+                ));
                 if let Some(ref desc) = session.description {
                     md.push_str(&format!("**描述**: {}\n\n", desc));
                 }
                 md.push_str("---\n\n");
-                md.push_str(&exporter.export_markdown_multiple(flows));
+                md.push_str(&exporter.export_markdown_sections(flows));
                 md
             }
             ExportFormat::CSV => exporter.export_csv(flows),
@@ -1110,7 +1117,7 @@ mod property_tests {
             );
 
             // 导出会话（使用空 Flow 列表测试导出功能）
-            let result = manager.export_session(&session.id, &[], ExportFormat::JSON).unwrap();
+            let result = manager.export_session(&session.id, &[], ExportFormat::JSON, false).unwrap();
             prop_assert_eq!(result.session.id, session.id);
             prop_assert_eq!(result.session.name, name);
             prop_assert_eq!(result.flow_count, 0);
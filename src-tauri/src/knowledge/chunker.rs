@@ -0,0 +1,122 @@
+//! 文本切分
+//!
+//! 先按空行切分段落，再把段落聚合到目标长度；
+//! 超长段落按字符强制切分。相邻文本块之间保留少量重叠，避免语义在边界处断开。
+
+/// 切分参数（单位：字符）
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkOptions {
+    /// 单个文本块的目标长度
+    pub chunk_size: usize,
+    /// 相邻文本块的重叠长度
+    pub overlap: usize,
+}
+
+impl Default for ChunkOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: 1200,
+            overlap: 150,
+        }
+    }
+}
+
+/// 将文本切分为多个文本块
+pub fn chunk_text(text: &str, options: ChunkOptions) -> Vec<String> {
+    let chunk_size = options.chunk_size.max(1);
+    let overlap = options.overlap.min(chunk_size / 2);
+
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        let para_len = paragraph.chars().count();
+
+        // 超长段落：先输出当前块，再按字符强制切分
+        if para_len > chunk_size {
+            flush(&mut chunks, &mut current, overlap);
+            current.clear();
+            let chars: Vec<char> = paragraph.chars().collect();
+            let step = chunk_size - overlap;
+            let mut start = 0;
+            while start < chars.len() {
+                let end = (start + chunk_size).min(chars.len());
+                chunks.push(chars[start..end].iter().collect());
+                if end == chars.len() {
+                    break;
+                }
+                start += step;
+            }
+            continue;
+        }
+
+        let current_len = current.chars().count();
+        if current_len > 0 && current_len + para_len + 2 > chunk_size {
+            flush(&mut chunks, &mut current, overlap);
+        }
+        if !current.is_empty() {
+            current.push_str("\n\n");
+        }
+        current.push_str(paragraph);
+    }
+
+    if !current.trim().is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// 输出当前块，并保留末尾 overlap 个字符作为下一块的开头
+fn flush(chunks: &mut Vec<String>, current: &mut String, overlap: usize) {
+    if current.trim().is_empty() {
+        current.clear();
+        return;
+    }
+    let chars: Vec<char> = current.chars().collect();
+    let tail: String = chars[chars.len().saturating_sub(overlap)..]
+        .iter()
+        .collect();
+    chunks.push(std::mem::replace(current, tail));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(chunk_size: usize, overlap: usize) -> ChunkOptions {
+        ChunkOptions {
+            chunk_size,
+            overlap,
+        }
+    }
+
+    #[test]
+    fn test_small_text_single_chunk() {
+        let chunks = chunk_text("第一段\n\n第二段", ChunkOptions::default());
+        assert_eq!(chunks, vec!["第一段\n\n第二段".to_string()]);
+    }
+
+    #[test]
+    fn test_empty_text() {
+        assert!(chunk_text("  \n\n  ", ChunkOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_paragraphs_grouped_with_overlap() {
+        let text = ["a".repeat(40), "b".repeat(40), "c".repeat(40)].join("\n\n");
+        let chunks = chunk_text(&text, options(90, 10));
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].starts_with('a') && chunks[0].ends_with('b'));
+        // 第二块以上一块末尾的重叠内容开头
+        assert!(chunks[1].starts_with(&"b".repeat(10)));
+        assert!(chunks[1].ends_with('c'));
+    }
+
+    #[test]
+    fn test_long_paragraph_split_by_chars() {
+        let text = "x".repeat(250);
+        let chunks = chunk_text(&text, options(100, 20));
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks.iter().all(|c| c.chars().count() <= 100));
+    }
+}
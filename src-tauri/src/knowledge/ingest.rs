@@ -0,0 +1,73 @@
+//! 文档导入流程
+//!
+//! 加载 → 切分 → 批量向量化 → 写入向量存储
+
+use super::chunker::{chunk_text, ChunkOptions};
+use super::loader::load_document;
+use crate::embeddings::{EmbeddingClient, VectorStore};
+use serde::Serialize;
+use std::path::Path;
+
+/// 单次向量化请求的文本块数量
+const EMBED_BATCH_SIZE: usize = 32;
+
+/// 单个文件的导入结果
+#[derive(Debug, Clone, Serialize)]
+pub struct IngestResult {
+    /// 文件路径
+    pub path: String,
+    /// 写入的文本块数量
+    pub chunks: usize,
+    /// 失败原因（成功时为空）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 导入单个文件到指定集合
+pub async fn ingest_file(
+    client: &EmbeddingClient,
+    store: &VectorStore,
+    collection: &str,
+    path: &Path,
+    model: &str,
+    options: ChunkOptions,
+) -> Result<usize, String> {
+    // PDF 解析是同步的 CPU 密集操作，放到阻塞线程池中执行
+    let owned_path = path.to_path_buf();
+    let (kind, text) = tokio::task::spawn_blocking(move || load_document(&owned_path))
+        .await
+        .map_err(|e| format!("加载文件任务失败: {}", e))??;
+    let chunks = chunk_text(&text, options);
+    if chunks.is_empty() {
+        return Err(format!("文件中没有可导入的文本: {}", path.display()));
+    }
+
+    let source = path.to_string_lossy().to_string();
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| source.clone());
+
+    let mut index = 0;
+    for batch in chunks.chunks(EMBED_BATCH_SIZE) {
+        let vectors = client.embed(batch, model).await?;
+        for (text, vector) in batch.iter().zip(vectors) {
+            let metadata = serde_json::json!({
+                "source": source,
+                "file_name": file_name,
+                "kind": format!("{:?}", kind).to_lowercase(),
+                "chunk": index,
+            });
+            store.add(collection, text, Some(metadata), model, vector)?;
+            index += 1;
+        }
+    }
+
+    tracing::info!(
+        "[Knowledge] 已导入文件: collection={}, path={}, chunks={}",
+        collection,
+        source,
+        index
+    );
+    Ok(index)
+}
@@ -0,0 +1,94 @@
+//! 文档加载
+//!
+//! 根据扩展名提取文档的纯文本内容
+
+use std::path::Path;
+
+/// 单个文档大小上限（50MB）
+const MAX_DOCUMENT_SIZE: u64 = 50 * 1024 * 1024;
+
+/// 支持的文档类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    Pdf,
+    Markdown,
+    Text,
+}
+
+impl DocumentKind {
+    /// 根据文件扩展名判断文档类型
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "pdf" => Some(Self::Pdf),
+            "md" | "markdown" | "mdx" => Some(Self::Markdown),
+            "txt" | "text" | "log" | "csv" | "rst" | "org" => Some(Self::Text),
+            _ => None,
+        }
+    }
+}
+
+/// 加载文档并返回文本内容
+pub fn load_document(path: &Path) -> Result<(DocumentKind, String), String> {
+    let kind = DocumentKind::from_path(path)
+        .ok_or_else(|| format!("不支持的文件类型: {}", path.display()))?;
+
+    let size = std::fs::metadata(path)
+        .map_err(|e| format!("读取文件信息失败: {}", e))?
+        .len();
+    if size > MAX_DOCUMENT_SIZE {
+        return Err(format!(
+            "文件过大: {} ({} MB)",
+            path.display(),
+            size / 1024 / 1024
+        ));
+    }
+
+    let text = match kind {
+        DocumentKind::Pdf => {
+            let bytes = std::fs::read(path).map_err(|e| format!("读取文件失败: {}", e))?;
+            pdf_extract::extract_text_from_mem(&bytes)
+                .map_err(|e| format!("解析 PDF 失败: {}", e))?
+        }
+        DocumentKind::Markdown | DocumentKind::Text => {
+            let bytes = std::fs::read(path).map_err(|e| format!("读取文件失败: {}", e))?;
+            String::from_utf8_lossy(&bytes).into_owned()
+        }
+    };
+
+    Ok((kind, text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_kind_from_path() {
+        assert_eq!(
+            DocumentKind::from_path(Path::new("a/Guide.PDF")),
+            Some(DocumentKind::Pdf)
+        );
+        assert_eq!(
+            DocumentKind::from_path(Path::new("README.md")),
+            Some(DocumentKind::Markdown)
+        );
+        assert_eq!(
+            DocumentKind::from_path(Path::new("notes.txt")),
+            Some(DocumentKind::Text)
+        );
+        assert_eq!(DocumentKind::from_path(Path::new("image.png")), None);
+        assert_eq!(DocumentKind::from_path(Path::new("Makefile")), None);
+    }
+
+    #[test]
+    fn test_load_text_document() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.md");
+        std::fs::write(&path, "# 标题\n\n内容").unwrap();
+
+        let (kind, text) = load_document(&path).unwrap();
+        assert_eq!(kind, DocumentKind::Markdown);
+        assert_eq!(text, "# 标题\n\n内容");
+    }
+}
@@ -0,0 +1,20 @@
+//! 知识库（RAG）模块
+//!
+//! 将 PDF、Markdown、纯文本文件切分为文本块，向量化后按“集合”保存到本地向量存储。
+//! 会话关联集合后，Agent 在每轮对话前检索 top-k 文本块并注入到提示词中。
+//!
+//! ## 架构设计
+//! - loader - 文件加载与文本提取
+//! - chunker - 文本切分（按段落聚合，带重叠）
+//! - ingest - 导入流程（加载 → 切分 → 向量化 → 存储）
+//! - retrieve - 检索增强（按集合的向量化模型检索 top-k 文本块）
+
+pub mod chunker;
+pub mod ingest;
+pub mod loader;
+pub mod retrieve;
+
+pub use chunker::{chunk_text, ChunkOptions};
+pub use ingest::{ingest_file, IngestResult};
+pub use loader::{load_document, DocumentKind};
+pub use retrieve::{retrieve_context, RETRIEVAL_TOP_K};
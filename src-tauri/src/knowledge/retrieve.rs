@@ -0,0 +1,25 @@
+//! 检索增强
+//!
+//! 用集合建立索引时的模型向量化查询文本，取最相似的 top-k 文本块作为对话的参考资料
+
+use crate::embeddings::{build_retrieval_context, EmbeddingClient, VectorStore};
+
+/// 每轮对话注入的文本块数量
+pub const RETRIEVAL_TOP_K: usize = 5;
+
+/// 检索集合中与查询文本相关的内容，格式化为对话上下文（没有结果时返回空字符串）
+///
+/// `default_model` 仅在集合为空时使用
+pub async fn retrieve_context(
+    client: &EmbeddingClient,
+    store: &VectorStore,
+    collection: &str,
+    query: &str,
+    default_model: &str,
+    top_k: usize,
+) -> Result<String, String> {
+    let model = store.model_for(collection, None, default_model)?;
+    let vector = client.embed_one(query, &model).await?;
+    let results = store.query(collection, &vector, top_k)?;
+    Ok(build_retrieval_context(&results))
+}
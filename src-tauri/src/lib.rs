@@ -6,12 +6,16 @@ mod config;
 mod converter;
 pub mod credential;
 pub mod database;
+pub mod embeddings;
 pub mod flow_monitor;
 pub mod injection;
+pub mod knowledge;
 mod logger;
+pub mod mcp_host;
 pub mod middleware;
 mod models;
 pub mod plugin;
+pub mod power;
 pub mod processor;
 mod providers;
 pub mod proxy;
@@ -34,6 +38,7 @@ use tokio::sync::RwLock;
 
 use agent::NativeAgentState;
 use commands::api_key_provider_cmd::ApiKeyProviderServiceState;
+use commands::bridge_cmd::ChatBridgeState;
 use commands::browser_interceptor_cmd::BrowserInterceptorState;
 use commands::flow_monitor_cmd::{
     BatchOperationsState, BookmarkManagerState, EnhancedStatsServiceState, FlowInterceptorState,
@@ -41,6 +46,7 @@ use commands::flow_monitor_cmd::{
     SessionManagerState,
 };
 use commands::machine_id_cmd::MachineIdState;
+use commands::mcp_cmd::McpSupervisorState;
 use commands::plugin_cmd::PluginManagerState;
 use commands::plugin_install_cmd::PluginInstallerState;
 use commands::provider_pool_cmd::{CredentialSyncServiceState, ProviderPoolServiceState};
@@ -52,7 +58,9 @@ use flow_monitor::{
     FlowMonitor, FlowMonitorConfig, FlowQueryService, FlowReplayer, InterceptConfig,
     QuickFilterManager, SessionManager,
 };
+use mcp_host::McpHostState;
 use services::api_key_provider_service::ApiKeyProviderService;
+use services::mcp_supervisor::McpSupervisor;
 use services::provider_pool_service::ProviderPoolService;
 use services::skill_service::SkillService;
 use services::token_cache_service::TokenCacheService;
@@ -276,28 +284,91 @@ async fn get_config(state: tauri::State<'_, AppState>) -> Result<config::Config,
     Ok(s.config.clone())
 }
 
-#[tauri::command]
-async fn save_config(
-    state: tauri::State<'_, AppState>,
+/// 将配置应用到应用状态和运行中的服务器
+async fn apply_config(
+    state: &AppState,
+    native_agent: &NativeAgentState,
+    pool_service: &ProviderPoolServiceState,
     config: config::Config,
-) -> Result<(), String> {
-    // P0 安全修复：禁止危险的网络配置
-    let host = config.server.host.to_lowercase();
-    if host == "0.0.0.0" || host == "::" {
-        return Err(
-            "安全限制：不允许监听所有网络接口 (0.0.0.0 或 ::)。请使用 127.0.0.1 或 localhost"
-                .to_string(),
-        );
-    }
+) -> Result<server::ConfigReloadReport, String> {
+    // P0 安全修复：对局域网开放时必须配置客户端 IP 白名单
+    config.server.validate_network()?;
 
     // 禁止开启远程管理
     if config.remote_management.allow_remote {
         return Err("安全限制：不允许开启远程管理功能".to_string());
     }
 
-    let mut s = state.write().await;
-    s.config = config.clone();
-    config::save_config(&config).map_err(|e| e.to_string())
+    agent::scheduled_tasks::validate_tasks(&config.scheduled_tasks)?;
+    agent::tools::validate_custom_tools(&config.custom_tools)?;
+    agent::tools::validate_sql_connections(&config.sql_connections)?;
+
+    native_agent.set_image_options(config.image_processing.clone());
+    native_agent.set_fallbacks(config.agent_fallbacks.clone());
+    native_agent.set_provider_profiles(config.provider_profiles.clone());
+    native_agent.set_session_quota(config.session_quota.clone());
+    native_agent.set_transcripts(&config.transcripts);
+    native_agent.set_context_fallback(config.context_fallback.clone());
+    native_agent.set_scheduled_tasks(config.scheduled_tasks.clone());
+    native_agent.set_custom_tools(config.custom_tools.clone());
+    native_agent.set_ocr_config(config.ocr.clone());
+    native_agent.set_embedding_model(config.embeddings.model.clone());
+    native_agent.set_code_interpreter_config(config.code_interpreter.clone());
+    native_agent.set_permission_mode(config.permission_mode);
+    native_agent.set_sql_connections(config.sql_connections.clone());
+    native_agent.browser().set_config(config.browser.clone());
+    if native_agent
+        .wasm_plugins()
+        .set_config(config.wasm_plugins.clone())
+    {
+        let host = native_agent.wasm_plugins().clone();
+        tauri::async_runtime::spawn_blocking(move || host.reload());
+    }
+    native_agent.set_missed_task_policy(config.sleep_resume.missed_task_policy);
+    pool_service
+        .0
+        .set_rotation_config(config.key_rotation.clone());
+    state.write().await.apply_config(config).await
+}
+
+#[tauri::command]
+async fn save_config(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    native_agent: tauri::State<'_, NativeAgentState>,
+    pool_service: tauri::State<'_, ProviderPoolServiceState>,
+    mcp_host: tauri::State<'_, McpHostState>,
+    config: config::Config,
+) -> Result<(), String> {
+    apply_config(&state, &native_agent, &pool_service, config.clone()).await?;
+    config::save_config(&config).map_err(|e| e.to_string())?;
+    mcp_host
+        .apply(&app, &config.mcp_host, &config.server.api_key)
+        .await
+}
+
+/// 从配置文件重新加载配置，Provider、路由和 Key 的变更直接应用到运行中的服务器
+#[tauri::command]
+async fn server_reload_config(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    native_agent: tauri::State<'_, NativeAgentState>,
+    pool_service: tauri::State<'_, ProviderPoolServiceState>,
+    mcp_host: tauri::State<'_, McpHostState>,
+    logs: tauri::State<'_, LogState>,
+) -> Result<server::ConfigReloadReport, String> {
+    let config = config::load_config().map_err(|e| e.to_string())?;
+    let mcp_config = config.mcp_host.clone();
+    let api_key = config.server.api_key.clone();
+    let report = apply_config(&state, &native_agent, &pool_service, config).await?;
+    mcp_host.apply(&app, &mcp_config, &api_key).await?;
+    let message = if report.restart_required {
+        "配置已重新加载，监听地址或 TLS 变更需重启服务器后生效"
+    } else {
+        "配置已重新加载"
+    };
+    logs.write().await.add("info", message);
+    Ok(report)
 }
 
 #[tauri::command]
@@ -1176,10 +1247,12 @@ async fn check_api_compatibility(
                                 },
                                 "required": ["expression"]
                             })),
+                            strict: None,
                         },
                     }]),
                     tool_choice: None,
                     reasoning_effort: None,
+                    stream_options: None,
                 }
             }
             _ => {
@@ -1201,6 +1274,7 @@ async fn check_api_compatibility(
                     tools: None,
                     tool_choice: None,
                     reasoning_effort: None,
+                    stream_options: None,
                 }
             }
         };
@@ -1409,7 +1483,7 @@ async fn test_api(
     auth: bool,
 ) -> Result<TestResult, String> {
     let s = state.read().await;
-    let base_url = format!("http://{}:{}", s.config.server.host, s.config.server.port);
+    let base_url = s.local_base_url();
     // 优先使用服务器运行时的 API key，确保测试使用的 key 和服务器一致
     // 如果服务器未运行，则使用配置中的 key
     let api_key = s
@@ -1417,9 +1491,8 @@ async fn test_api(
         .as_ref()
         .unwrap_or(&s.config.server.api_key);
 
-    // 创建一个禁用代理的客户端
-    let client = reqwest::Client::builder()
-        .no_proxy()
+    // 创建一个禁用代理的客户端（启用 TLS 时允许本机自签名证书）
+    let client = server::tls::local_client_builder(&base_url)
         .build()
         .map_err(|e| e.to_string())?;
 
@@ -1473,6 +1546,12 @@ async fn test_api(
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // stdio 模式：作为 MCP 客户端与运行中应用之间的桥接，不启动界面
+    if std::env::args().any(|arg| arg == mcp_host::STDIO_FLAG) {
+        mcp_host::run_bridge();
+        return;
+    }
+
     let mut config = match config::load_config() {
         Ok(cfg) => cfg,
         Err(err) => {
@@ -1492,9 +1571,9 @@ pub fn run() {
         tracing::info!("检测到默认 API key，已自动生成并保存新密钥");
         eprintln!("检测到默认 API key，已自动生成并保存新密钥");
     }
-    if !is_loopback_host(&config.server.host) {
-        tracing::error!("当前版本仅支持本地监听，请使用 127.0.0.1/localhost/::1。");
-        eprintln!("当前版本仅支持本地监听，请使用 127.0.0.1/localhost/::1。");
+    if let Err(err) = config.server.validate_network() {
+        tracing::error!("网络配置无效，已中止启动: {}", err);
+        eprintln!("网络配置无效，已中止启动: {}", err);
         return;
     }
     if config.server.api_key == config::DEFAULT_API_KEY {
@@ -1502,17 +1581,28 @@ pub fn run() {
         eprintln!("检测到使用默认 API key，已中止启动。请配置强密钥。");
         return;
     }
-    if config.server.tls.enable {
-        tracing::error!("检测到 TLS 配置已启用，但当前版本尚未支持 TLS，已中止启动。");
-        eprintln!("检测到 TLS 配置已启用，但当前版本尚未支持 TLS，已中止启动。");
-        return;
-    }
     if config.remote_management.allow_remote {
         tracing::error!("检测到远程管理已开启，但当前版本未启用 TLS，已中止启动。");
         eprintln!("检测到远程管理已开启，但当前版本未启用 TLS，已中止启动。");
         return;
     }
-    let state: AppState = Arc::new(RwLock::new(server::ServerState::new(config.clone())));
+    // tracing 订阅器：实时日志缓冲和日志文件 + OpenTelemetry 链路追踪导出（批量导出任务运行在 Tauri 的 tokio 运行时上）
+    let live_logs = Arc::new(telemetry::LiveLogBuffer::default());
+    let mut live_log_layer = live_logs.layer();
+    if config.logging.enabled {
+        live_log_layer = live_log_layer.with_file(telemetry::LogFileWriter::new(
+            telemetry::LogFileConfig::from_logging(&config.logging),
+        ));
+    }
+    let (otlp_guard, otlp_error) = tauri::async_runtime::block_on(async {
+        telemetry::init_tracing(&config.otlp, &config.logging.level, live_log_layer)
+    });
+    if let Some(err) = otlp_error {
+        eprintln!("OpenTelemetry 导出初始化失败，已跳过: {}", err);
+    }
+    let server_state = server::ServerState::new(config.clone());
+    let usage_recorder = server_state.usage_recorder.clone();
+    let state: AppState = Arc::new(RwLock::new(server_state));
     let logs: LogState = Arc::new(RwLock::new(logger::LogStore::with_config(&config.logging)));
 
     // Initialize database for Switch functionality
@@ -1531,6 +1621,7 @@ pub fn run() {
 
     // Initialize ProviderPoolService
     let provider_pool_service = ProviderPoolService::new();
+    provider_pool_service.set_rotation_config(config.key_rotation.clone());
     let provider_pool_service_state = ProviderPoolServiceState(Arc::new(provider_pool_service));
 
     // Initialize ApiKeyProviderService
@@ -1617,6 +1708,8 @@ pub fn run() {
         retention_days: config.logging.retention_days,
         max_file_size: 10 * 1024 * 1024,
         enable_file_logging: config.logging.enabled,
+        capture_bodies: config.logging.include_request_body,
+        max_body_bytes: telemetry::DEFAULT_MAX_BODY_BYTES,
     };
     let shared_logger = Arc::new(
         telemetry::RequestLogger::new(log_rotation).expect("Failed to create RequestLogger"),
@@ -1702,8 +1795,38 @@ pub fn run() {
     // Initialize BrowserInterceptorState
     let browser_interceptor_state = BrowserInterceptorState::default();
 
-    // Initialize NativeAgentState
-    let native_agent_state = NativeAgentState::new();
+    // Initialize NativeAgentState（启用长期记忆、知识库检索和 Skill 使用统计）
+    let native_agent_state = NativeAgentState::new()
+        .with_memory(agent::MemoryStore::new(db.clone()))
+        .with_knowledge(embeddings::VectorStore::new(db.clone()))
+        .with_skill_usage(agent::SkillUsageStore::new(db.clone()))
+        .with_usage_recorder(usage_recorder);
+    native_agent_state.set_image_options(config.image_processing.clone());
+    native_agent_state.set_fallbacks(config.agent_fallbacks.clone());
+    native_agent_state.set_provider_profiles(config.provider_profiles.clone());
+    native_agent_state.set_session_quota(config.session_quota.clone());
+    native_agent_state.set_transcripts(&config.transcripts);
+    native_agent_state.set_context_fallback(config.context_fallback.clone());
+    native_agent_state.set_scheduled_tasks(config.scheduled_tasks.clone());
+    native_agent_state.set_custom_tools(config.custom_tools.clone());
+    native_agent_state.set_ocr_config(config.ocr.clone());
+    native_agent_state.set_embedding_model(config.embeddings.model.clone());
+    native_agent_state.set_code_interpreter_config(config.code_interpreter.clone());
+    native_agent_state.set_permission_mode(config.permission_mode);
+    native_agent_state.set_sql_connections(config.sql_connections.clone());
+    native_agent_state
+        .browser()
+        .set_config(config.browser.clone());
+    native_agent_state
+        .wasm_plugins()
+        .set_config(config.wasm_plugins.clone());
+    native_agent_state.set_missed_task_policy(config.sleep_resume.missed_task_policy);
+
+    // Initialize ChatBridgeState
+    let chat_bridge_state = ChatBridgeState::default();
+
+    // Initialize McpSupervisorState（stdio MCP 服务器子进程）
+    let mcp_supervisor_state = McpSupervisorState(McpSupervisor::new(logs.clone()));
 
     // FlowQueryService 需要 file_store，如果没有则创建一个临时的
     let flow_query_service_state = if let Some(file_store) = flow_file_store {
@@ -1730,6 +1853,7 @@ pub fn run() {
 
     // Clone for setup hook
     let state_clone = state.clone();
+    let quick_ask_config = config.quick_ask.clone();
     let logs_clone = logs.clone();
     let db_clone = db.clone();
     let pool_service_clone = provider_pool_service_state.0.clone();
@@ -1743,6 +1867,8 @@ pub fn run() {
     let mut builder = tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_autostart::init(
             tauri_plugin_autostart::MacosLauncher::LaunchAgent,
             Some(vec!["--minimized"]),
@@ -1805,6 +1931,10 @@ pub fn run() {
         .manage(batch_operations_state)
         .manage(browser_interceptor_state)
         .manage(native_agent_state)
+        .manage(chat_bridge_state)
+        .manage(mcp_supervisor_state)
+        .manage(McpHostState::new())
+        .manage(commands::logs_cmd::LiveLogState(live_logs.clone()))
         .on_window_event(move |window, event| {
             // 处理窗口关闭事件
             if let tauri::WindowEvent::CloseRequested { api, .. } = event {
@@ -1864,6 +1994,55 @@ pub fn run() {
                     app.manage(tray_state);
                 }
             }
+
+            // 启动会话分析器（定期检测过期上下文并推送压缩建议）
+            agent::session_lint::spawn_session_linter(app.handle().clone());
+
+            // 启动会话配额检查（接近上限时推送提醒和归档建议）
+            agent::session_quota::spawn_session_quota_monitor(app.handle().clone());
+
+            // 启动实时日志推送（新日志通过 log_line 事件推送到前端）
+            commands::logs_cmd::spawn_log_line_forwarder(app.handle().clone(), live_logs.clone());
+
+            // 启动会话自动同步（按 session_sync.interval_minutes 推送和拉取会话）
+            services::session_sync_service::spawn_session_sync(app.handle().clone());
+
+            // 注册快速提问快捷键（quick_ask.enabled 时）
+            if let Err(e) = agent::quick_ask::register_shortcut(app.handle(), &quick_ask_config) {
+                tracing::warn!("[启动] {}", e);
+            }
+
+            // 待审核请求（补丁、脚本、工具调用、后续任务）状态变化时推送到前端
+            app.state::<NativeAgentState>()
+                .set_app_handle(app.handle().clone());
+
+            // 启动后续任务调度器（执行到期的已批准任务）
+            agent::followup::spawn_followup_scheduler(app.handle().clone());
+
+            // 启动定时任务调度器（按 cron 表达式执行用户定义的任务）
+            agent::scheduled_tasks::spawn_task_scheduler(app.handle().clone());
+
+            // 加载 WASM 插件工具（编译较耗时，在后台线程执行）
+            let wasm_plugins = app.state::<NativeAgentState>().wasm_plugins().clone();
+            tauri::async_runtime::spawn_blocking(move || wasm_plugins.reload());
+
+            // 启动 MCP 服务端（mcp_host.enabled 时）
+            let mcp_host = app.state::<McpHostState>().inner().clone();
+            let mcp_app = app.handle().clone();
+            let state = state_clone.clone();
+            tauri::async_runtime::spawn(async move {
+                let config = state.read().await.config.clone();
+                if let Err(e) = mcp_host
+                    .apply(&mcp_app, &config.mcp_host, &config.server.api_key)
+                    .await
+                {
+                    tracing::error!("[MCP] {}", e);
+                }
+            });
+
+            // 启动休眠/唤醒监控（唤醒后重建连接并推送事件）
+            power::spawn_power_monitor(app.handle().clone());
+
             // 自动启动服务器
             let state = state_clone.clone();
             let logs = logs_clone.clone();
@@ -2011,8 +2190,21 @@ pub fn run() {
             start_server,
             stop_server,
             get_server_status,
+            commands::loadtest_cmd::proxy_loadtest,
+            commands::benchmark_cmd::provider_benchmark,
+            commands::session_sync_cmd::session_sync_now,
+            commands::client_key_cmd::list_client_keys,
+            commands::client_key_cmd::create_client_key,
+            commands::client_key_cmd::revoke_client_key,
+            commands::response_cache_cmd::get_response_cache_stats,
+            commands::response_cache_cmd::clear_response_cache,
+            commands::token_budget_cmd::get_token_budget_status,
+            commands::usage_recorder_cmd::list_usage_events,
+            commands::usage_recorder_cmd::get_usage_summary,
+            commands::upstream_health_cmd::probe_upstream_health,
             get_config,
             save_config,
+            server_reload_config,
             get_default_provider,
             set_default_provider,
             get_endpoint_providers,
@@ -2055,6 +2247,8 @@ pub fn run() {
             // Common
             get_logs,
             clear_logs,
+            commands::logs_cmd::logs_tail,
+            commands::logs_cmd::logs_export,
             test_api,
             get_available_models,
             // API Compatibility
@@ -2081,6 +2275,8 @@ pub fn run() {
             commands::config_cmd::export_config,
             commands::config_cmd::validate_config_yaml,
             commands::config_cmd::import_config,
+            commands::config_cmd::export_provider_profiles,
+            commands::config_cmd::import_provider_profiles,
             commands::config_cmd::get_config_paths,
             // Enhanced export/import commands (using ExportService/ImportService)
             commands::config_cmd::export_bundle,
@@ -2100,6 +2296,15 @@ pub fn run() {
             commands::mcp_cmd::toggle_mcp_server,
             commands::mcp_cmd::import_mcp_from_app,
             commands::mcp_cmd::sync_all_mcp_to_live,
+            commands::mcp_cmd::mcp_host_status,
+            commands::mcp_cmd::mcp_server_start,
+            commands::mcp_cmd::mcp_server_stop,
+            commands::mcp_cmd::mcp_server_restart,
+            commands::mcp_cmd::mcp_server_status,
+            commands::mcp_cmd::mcp_server_list_tools,
+            // Claude Code import commands
+            commands::claude_import_cmd::scan_claude_config,
+            commands::claude_import_cmd::import_claude_config,
             // Prompt commands
             commands::prompt_cmd::get_prompts,
             commands::prompt_cmd::upsert_prompt,
@@ -2118,6 +2323,9 @@ pub fn run() {
             commands::skill_cmd::install_skill_for_app,
             commands::skill_cmd::uninstall_skill,
             commands::skill_cmd::uninstall_skill_for_app,
+            commands::skill_cmd::skill_check_updates,
+            commands::skill_cmd::skill_update,
+            commands::skill_cmd::skill_stats,
             commands::skill_cmd::get_skill_repos,
             commands::skill_cmd::add_skill_repo,
             commands::skill_cmd::remove_skill_repo,
@@ -2131,6 +2339,8 @@ pub fn run() {
             commands::provider_pool_cmd::toggle_provider_pool_credential,
             commands::provider_pool_cmd::reset_provider_pool_credential,
             commands::provider_pool_cmd::reset_provider_pool_health,
+            commands::provider_pool_cmd::get_provider_pool_key_health,
+            commands::provider_pool_cmd::clear_provider_pool_cooldown,
             commands::provider_pool_cmd::check_provider_pool_credential_health,
             commands::provider_pool_cmd::check_provider_pool_type_health,
             commands::provider_pool_cmd::add_kiro_oauth_credential,
@@ -2226,6 +2436,7 @@ pub fn run() {
             commands::resilience_cmd::clear_switch_log,
             // Telemetry commands
             commands::telemetry_cmd::get_request_logs,
+            commands::telemetry_cmd::query_request_logs,
             commands::telemetry_cmd::get_request_log_detail,
             commands::telemetry_cmd::clear_request_logs,
             commands::telemetry_cmd::get_stats_summary,
@@ -2444,29 +2655,101 @@ pub fn run() {
             commands::agent_cmd::agent_list_sessions,
             commands::agent_cmd::agent_get_session,
             commands::agent_cmd::agent_delete_session,
+            commands::agent_cmd::agent_submit_task,
+            commands::agent_cmd::agent_list_tasks,
+            commands::agent_cmd::agent_get_task,
+            commands::agent_cmd::agent_cancel_task,
+            commands::agent_cmd::agent_batch_run,
+            commands::agent_cmd::quick_ask,
+            commands::agent_cmd::skill_create_from_session,
             // Native Agent commands
             commands::native_agent_cmd::native_agent_init,
             commands::native_agent_cmd::native_agent_status,
             commands::native_agent_cmd::native_agent_reset,
             commands::native_agent_cmd::native_agent_chat,
             commands::native_agent_cmd::native_agent_chat_stream,
+            commands::native_agent_cmd::native_agent_cancel_stream,
+            commands::native_agent_cmd::native_agent_list_streams,
+            commands::native_agent_cmd::native_agent_get_stream,
+            commands::native_agent_cmd::native_agent_cancel_tool,
+            commands::native_agent_cmd::native_agent_list_wasm_plugins,
+            commands::native_agent_cmd::native_agent_reload_wasm_plugins,
+            commands::native_agent_cmd::native_agent_prepare_paste,
+            commands::native_agent_cmd::native_agent_generate_image,
             commands::native_agent_cmd::native_agent_create_session,
             commands::native_agent_cmd::native_agent_get_session,
             commands::native_agent_cmd::native_agent_delete_session,
             commands::native_agent_cmd::native_agent_list_sessions,
+            commands::native_agent_cmd::native_agent_update_session_meta,
+            commands::native_agent_cmd::native_agent_list_session_folders,
+            commands::native_agent_cmd::native_agent_lint_session,
+            commands::native_agent_cmd::native_agent_get_session_quota,
+            commands::native_agent_cmd::native_agent_archive_sessions,
+            commands::native_agent_cmd::native_agent_get_transcript,
+            commands::native_agent_cmd::native_agent_export_transcript,
+            commands::native_agent_cmd::native_agent_export_session,
+            commands::native_agent_cmd::sessions_bulk_delete,
+            commands::native_agent_cmd::sessions_bulk_tag,
+            commands::native_agent_cmd::sessions_bulk_export,
+            commands::native_agent_cmd::native_agent_list_scheduled_followups,
+            commands::native_agent_cmd::native_agent_review_scheduled_followup,
+            commands::native_agent_cmd::native_agent_cancel_scheduled_followup,
+            commands::native_agent_cmd::native_agent_list_patch_proposals,
+            commands::native_agent_cmd::native_agent_review_patch_proposal,
+            commands::native_agent_cmd::native_agent_list_script_requests,
+            commands::native_agent_cmd::native_agent_review_script_request,
+            commands::native_agent_cmd::native_agent_list_tool_approvals,
+            commands::native_agent_cmd::native_agent_review_tool_approval,
+            commands::native_agent_cmd::native_agent_list_scheduled_tasks,
+            commands::native_agent_cmd::native_agent_run_scheduled_task,
+            commands::native_agent_cmd::native_agent_list_scheduled_task_runs,
+            commands::native_agent_cmd::native_agent_edit_message,
+            commands::native_agent_cmd::native_agent_delete_message,
+            commands::native_agent_cmd::native_agent_regenerate,
+            commands::native_agent_cmd::native_agent_preview_request,
+            commands::native_agent_cmd::native_agent_replay_turn,
+            commands::native_agent_cmd::native_agent_set_strict_tools,
+            commands::native_agent_cmd::native_agent_set_session_profile,
+            commands::native_agent_cmd::native_agent_set_session_repo,
+            commands::native_agent_cmd::native_agent_set_session_http_domains,
+            commands::native_agent_cmd::native_agent_set_session_permission_mode,
+            commands::native_agent_cmd::native_agent_summarize_session,
+            commands::native_agent_cmd::native_agent_generate_title,
+            commands::native_agent_cmd::native_agent_suggest_followups,
+            commands::native_agent_cmd::native_agent_extract_memories,
+            // Chat bridge commands
+            commands::bridge_cmd::bridge_attach_session,
+            commands::bridge_cmd::bridge_detach_session,
+            commands::bridge_cmd::bridge_list_sessions,
+            // Embeddings commands
+            commands::embeddings_cmd::embeddings_embed_text,
+            commands::embeddings_cmd::embeddings_query,
+            commands::embeddings_cmd::embeddings_list_collections,
+            commands::embeddings_cmd::embeddings_delete,
+            commands::embeddings_cmd::embeddings_delete_collection,
+            // Agent memory commands
+            commands::memory_cmd::memory_list,
+            commands::memory_cmd::memory_add,
+            commands::memory_cmd::memory_update,
+            commands::memory_cmd::memory_delete,
+            commands::memory_cmd::memory_review,
+            // Knowledge base commands
+            commands::knowledge_cmd::knowledge_ingest_files,
+            commands::knowledge_cmd::knowledge_attach_collection,
             // Network commands
             commands::network_cmd::get_network_info,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
-}
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                // 退出前停止 MCP 子进程，避免残留孤儿进程
+                let supervisor = app_handle.state::<McpSupervisorState>().0.clone();
+                tauri::async_runtime::block_on(supervisor.stop_all());
+            }
+        });
 
-fn is_loopback_host(host: &str) -> bool {
-    if host == "localhost" {
-        return true;
-    }
-    match host.parse::<std::net::IpAddr>() {
-        Ok(addr) => addr.is_loopback(),
-        Err(_) => false,
+    if let Some(guard) = otlp_guard {
+        guard.shutdown();
     }
 }
@@ -0,0 +1,141 @@
+//! MCP HTTP 传输
+//!
+//! - `GET /sse`: 建立 SSE 连接，首先推送 `endpoint` 事件（消息提交地址），
+//!   之后以 `message` 事件推送 JSON-RPC 响应
+//! - `POST /messages?sessionId=...`: 提交 SSE 会话的 JSON-RPC 消息，返回 202
+//! - `POST /mcp`: 直接在响应体中返回 JSON-RPC 响应（stdio 桥接使用）
+//!
+//! 所有请求需携带 ProxyCast 的 API Key（`Authorization: Bearer` 或 `x-api-key`）。
+
+use super::protocol::{handle_message, parse_error, ToolProvider};
+use crate::middleware::rate_limit::extract_api_key;
+use axum::extract::{Query, Request, State};
+use axum::http::StatusCode;
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures::Stream;
+use parking_lot::RwLock;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use tokio::sync::mpsc;
+
+/// SSE 会话（会话 ID -> 响应推送通道）
+pub type SseSessions = Arc<RwLock<HashMap<String, mpsc::Sender<Value>>>>;
+
+#[derive(Clone)]
+struct HttpState {
+    provider: Arc<dyn ToolProvider>,
+    api_key: Arc<String>,
+    sessions: SseSessions,
+}
+
+/// SSE 连接断开时移除会话
+struct SessionGuard {
+    id: String,
+    sessions: SseSessions,
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        self.sessions.write().remove(&self.id);
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MessageQuery {
+    session_id: String,
+}
+
+/// 创建 MCP 路由，返回路由和 SSE 会话表（清空会话表会关闭所有 SSE 连接）
+pub fn router(provider: Arc<dyn ToolProvider>, api_key: String) -> (Router, SseSessions) {
+    let sessions = SseSessions::default();
+    let state = HttpState {
+        provider,
+        api_key: Arc::new(api_key),
+        sessions: sessions.clone(),
+    };
+    let router = Router::new()
+        .route("/sse", get(sse_handler))
+        .route("/messages", post(message_handler))
+        .route("/mcp", post(mcp_handler))
+        .layer(middleware::from_fn_with_state(state.clone(), auth))
+        .with_state(state);
+    (router, sessions)
+}
+
+async fn auth(State(state): State<HttpState>, request: Request, next: Next) -> Response {
+    let authorized = extract_api_key(request.headers())
+        .is_some_and(|key| key.as_bytes().ct_eq(state.api_key.as_bytes()).into());
+    if !authorized {
+        return (StatusCode::UNAUTHORIZED, "Invalid API key").into_response();
+    }
+    next.run(request).await
+}
+
+async fn sse_handler(
+    State(state): State<HttpState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let (tx, mut rx) = mpsc::channel::<Value>(32);
+    state.sessions.write().insert(session_id.clone(), tx);
+    tracing::info!("[MCP] SSE 客户端已连接: {}", session_id);
+
+    let guard = SessionGuard {
+        id: session_id.clone(),
+        sessions: state.sessions.clone(),
+    };
+    let stream = async_stream::stream! {
+        let _guard = guard;
+        yield Ok(Event::default()
+            .event("endpoint")
+            .data(format!("/messages?sessionId={}", session_id)));
+        while let Some(message) = rx.recv().await {
+            yield Ok(Event::default().event("message").data(message.to_string()));
+        }
+    };
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+async fn message_handler(
+    State(state): State<HttpState>,
+    Query(query): Query<MessageQuery>,
+    body: String,
+) -> StatusCode {
+    let Some(tx) = state.sessions.read().get(&query.session_id).cloned() else {
+        return StatusCode::NOT_FOUND;
+    };
+    let message: Value = match serde_json::from_str(&body) {
+        Ok(message) => message,
+        Err(e) => {
+            let _ = tx.send(parse_error(e.to_string())).await;
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+    // 工具调用可能耗时较长，在后台处理并通过 SSE 推送响应
+    let provider = state.provider.clone();
+    tokio::spawn(async move {
+        if let Some(response) = handle_message(provider.as_ref(), message).await {
+            let _ = tx.send(response).await;
+        }
+    });
+    StatusCode::ACCEPTED
+}
+
+async fn mcp_handler(State(state): State<HttpState>, body: String) -> Response {
+    let message: Value = match serde_json::from_str(&body) {
+        Ok(message) => message,
+        Err(e) => return Json(parse_error(e.to_string())).into_response(),
+    };
+    match handle_message(state.provider.as_ref(), message).await {
+        Some(response) => Json(response).into_response(),
+        None => StatusCode::ACCEPTED.into_response(),
+    }
+}
@@ -0,0 +1,117 @@
+//! MCP 服务端模块
+//!
+//! 将 ProxyCast 作为 MCP 服务端暴露给其他 MCP 客户端（Claude Desktop、Cursor 等），
+//! 提供发送对话、查询会话和用量、调用 Skill 等工具：
+//! - SSE 传输：在 `127.0.0.1:{mcp_host.port}` 上监听（`/sse` + `/messages`）
+//! - stdio 传输：`proxycast --mcp-stdio` 桥接到运行中的应用
+//!
+//! 所有请求使用 `server.api_key` 认证。
+
+pub mod http;
+pub mod protocol;
+pub mod stdio;
+pub mod tools;
+
+use crate::config::McpHostConfig;
+use http::SseSessions;
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::AppHandle;
+use tokio::sync::{oneshot, Mutex};
+
+pub use stdio::{run_bridge, STDIO_FLAG};
+
+/// 运行中的监听器
+struct Running {
+    port: u16,
+    api_key: String,
+    shutdown: oneshot::Sender<()>,
+    sessions: SseSessions,
+}
+
+/// MCP 服务端状态
+#[derive(Debug, Clone, Serialize)]
+pub struct McpHostStatus {
+    pub running: bool,
+    pub port: Option<u16>,
+    /// SSE 客户端连接数
+    pub sse_clients: usize,
+}
+
+/// MCP 服务端（Tauri 托管状态）
+#[derive(Clone, Default)]
+pub struct McpHostState {
+    running: Arc<Mutex<Option<Running>>>,
+}
+
+impl McpHostState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 按配置启动、重启或停止监听器
+    pub async fn apply(
+        &self,
+        app: &AppHandle,
+        config: &McpHostConfig,
+        api_key: &str,
+    ) -> Result<(), String> {
+        let mut running = self.running.lock().await;
+        if let Some(current) = running.as_ref() {
+            if config.enabled && current.port == config.port && current.api_key == api_key {
+                return Ok(());
+            }
+        }
+        if let Some(current) = running.take() {
+            // 清空会话表会结束所有 SSE 流，使优雅关闭不被长连接阻塞
+            current.sessions.write().clear();
+            let _ = current.shutdown.send(());
+            tracing::info!("[MCP] 服务端已停止 (端口 {})", current.port);
+        }
+        if !config.enabled {
+            return Ok(());
+        }
+
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", config.port))
+            .await
+            .map_err(|e| format!("MCP 服务端监听端口 {} 失败: {}", config.port, e))?;
+        let provider = Arc::new(tools::AppTools::new(app.clone()));
+        let (router, sessions) = http::router(provider, api_key.to_string());
+        let (shutdown, shutdown_rx) = oneshot::channel();
+        let port = config.port;
+        tokio::spawn(async move {
+            let result = axum::serve(listener, router)
+                .with_graceful_shutdown(async {
+                    let _ = shutdown_rx.await;
+                })
+                .await;
+            if let Err(e) = result {
+                tracing::error!("[MCP] 服务端异常退出: {}", e);
+            }
+        });
+        tracing::info!("[MCP] 服务端已启动: http://127.0.0.1:{}/sse", port);
+
+        *running = Some(Running {
+            port,
+            api_key: api_key.to_string(),
+            shutdown,
+            sessions,
+        });
+        Ok(())
+    }
+
+    pub async fn status(&self) -> McpHostStatus {
+        match self.running.lock().await.as_ref() {
+            Some(running) => McpHostStatus {
+                running: true,
+                port: Some(running.port),
+                sse_clients: running.sessions.read().len(),
+            },
+            None => McpHostStatus {
+                running: false,
+                port: None,
+                sse_clients: 0,
+            },
+        }
+    }
+}
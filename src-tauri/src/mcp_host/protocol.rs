@@ -0,0 +1,215 @@
+//! MCP 协议处理
+//!
+//! 实现 MCP（Model Context Protocol）服务端的 JSON-RPC 2.0 消息处理，
+//! 支持 `initialize`、`ping`、`tools/list`、`tools/call`。
+//! 工具由 [`ToolProvider`] 提供，与传输方式（SSE / stdio）无关。
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// 支持的协议版本
+pub const MCP_PROTOCOL_VERSION: &str = "2024-11-05";
+
+// JSON-RPC 错误码
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// 服务端错误（stdio 桥接无法连接应用时使用）
+pub const SERVER_ERROR: i64 = -32000;
+
+/// MCP 工具定义
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpTool {
+    pub name: String,
+    pub description: String,
+    /// 参数 JSON Schema
+    pub input_schema: Value,
+}
+
+/// 对外暴露的工具集合
+#[async_trait]
+pub trait ToolProvider: Send + Sync {
+    /// 工具列表
+    fn tools(&self) -> Vec<McpTool>;
+
+    /// 调用工具，返回文本结果；错误作为 `isError` 结果返回给客户端
+    async fn call(&self, name: &str, arguments: Value) -> Result<String, String>;
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolCallParams {
+    name: String,
+    #[serde(default)]
+    arguments: Option<Value>,
+}
+
+fn success(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+/// JSON-RPC 错误响应
+pub fn error_response(id: Value, code: i64, message: impl Into<String>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message.into() }
+    })
+}
+
+/// 解析失败时的错误响应
+pub fn parse_error(message: impl Into<String>) -> Value {
+    error_response(Value::Null, PARSE_ERROR, message)
+}
+
+/// 处理一条 JSON-RPC 消息（也支持批量数组），通知消息不返回响应
+pub async fn handle_message(provider: &dyn ToolProvider, message: Value) -> Option<Value> {
+    match message {
+        Value::Array(batch) => {
+            let mut responses = Vec::new();
+            for item in batch {
+                if let Some(response) = handle_single(provider, item).await {
+                    responses.push(response);
+                }
+            }
+            (!responses.is_empty()).then_some(Value::Array(responses))
+        }
+        message => handle_single(provider, message).await,
+    }
+}
+
+async fn handle_single(provider: &dyn ToolProvider, message: Value) -> Option<Value> {
+    let Some(method) = message.get("method").and_then(|m| m.as_str()) else {
+        // 客户端对服务端请求的响应，或格式错误的消息
+        return message
+            .get("id")
+            .filter(|_| message.get("result").is_none() && message.get("error").is_none())
+            .map(|id| error_response(id.clone(), INVALID_REQUEST, "缺少 method 字段"));
+    };
+    // 没有 id 的是通知，不需要响应
+    let id = message.get("id").cloned()?;
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    let response = match method {
+        "initialize" => success(
+            id,
+            json!({
+                "protocolVersion": MCP_PROTOCOL_VERSION,
+                "capabilities": { "tools": {} },
+                "serverInfo": {
+                    "name": "proxycast",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+            }),
+        ),
+        "ping" => success(id, json!({})),
+        "tools/list" => success(id, json!({ "tools": provider.tools() })),
+        "tools/call" => match serde_json::from_value::<ToolCallParams>(params) {
+            Ok(params) => {
+                if !provider.tools().iter().any(|t| t.name == params.name) {
+                    return Some(error_response(
+                        id,
+                        INVALID_PARAMS,
+                        format!("未知的工具: {}", params.name),
+                    ));
+                }
+                let arguments = params.arguments.unwrap_or_else(|| json!({}));
+                let (text, is_error) = match provider.call(&params.name, arguments).await {
+                    Ok(text) => (text, false),
+                    Err(e) => (e, true),
+                };
+                success(
+                    id,
+                    json!({
+                        "content": [{ "type": "text", "text": text }],
+                        "isError": is_error,
+                    }),
+                )
+            }
+            Err(e) => error_response(id, INVALID_PARAMS, format!("参数无效: {}", e)),
+        },
+        other => error_response(id, METHOD_NOT_FOUND, format!("不支持的方法: {}", other)),
+    };
+    Some(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct EchoTools;
+
+    #[async_trait]
+    impl ToolProvider for EchoTools {
+        fn tools(&self) -> Vec<McpTool> {
+            vec![McpTool {
+                name: "echo".to_string(),
+                description: "Echo the text".to_string(),
+                input_schema: json!({"type": "object"}),
+            }]
+        }
+
+        async fn call(&self, _name: &str, arguments: Value) -> Result<String, String> {
+            arguments["text"]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| "missing text".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_message() {
+        let init = handle_message(
+            &EchoTools,
+            json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(init["result"]["protocolVersion"], MCP_PROTOCOL_VERSION);
+
+        // 通知不返回响应
+        assert!(handle_message(
+            &EchoTools,
+            json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+        )
+        .await
+        .is_none());
+
+        let list = handle_message(
+            &EchoTools,
+            json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(list["result"]["tools"][0]["inputSchema"]["type"], "object");
+
+        let call = handle_message(
+            &EchoTools,
+            json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call",
+                   "params": {"name": "echo", "arguments": {"text": "hi"}}}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(call["result"]["content"][0]["text"], "hi");
+        assert_eq!(call["result"]["isError"], false);
+
+        let failed = handle_message(
+            &EchoTools,
+            json!({"jsonrpc": "2.0", "id": 4, "method": "tools/call",
+                   "params": {"name": "echo"}}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(failed["result"]["isError"], true);
+
+        let unknown = handle_message(
+            &EchoTools,
+            json!([{"jsonrpc": "2.0", "id": 5, "method": "resources/list"}]),
+        )
+        .await
+        .unwrap();
+        assert_eq!(unknown[0]["error"]["code"], METHOD_NOT_FOUND);
+    }
+}
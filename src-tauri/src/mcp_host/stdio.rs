@@ -0,0 +1,109 @@
+//! MCP stdio 传输
+//!
+//! `proxycast --mcp-stdio` 以 stdio 模式运行：逐行读取 stdin 中的 JSON-RPC 消息，
+//! 转发到运行中的 ProxyCast 的 `POST /mcp`，并将响应逐行写入 stdout。
+//! 需要 ProxyCast 已启动并开启 `mcp_host`。
+
+use super::protocol::{error_response, SERVER_ERROR};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+
+/// 命令行参数
+pub const STDIO_FLAG: &str = "--mcp-stdio";
+
+/// 运行 stdio 桥接，直到 stdin 关闭
+pub fn run_bridge() {
+    let config = match crate::config::load_config() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("配置加载失败: {}", e);
+            return;
+        }
+    };
+    if !config.mcp_host.enabled {
+        eprintln!("MCP 服务端未启用，请在配置中设置 mcp_host.enabled: true");
+        return;
+    }
+    let url = format!("http://127.0.0.1:{}/mcp", config.mcp_host.port);
+    let runtime = match tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("创建运行时失败: {}", e);
+            return;
+        }
+    };
+    runtime.block_on(bridge(url, config.server.api_key));
+}
+
+async fn bridge(url: String, api_key: String) {
+    let client = reqwest::Client::new();
+    let target = Arc::new((url, api_key));
+
+    // 响应由单独的任务按完成顺序写出，长时间的工具调用不阻塞其他请求
+    let (tx, mut rx) = mpsc::channel::<String>(32);
+    let writer = tokio::spawn(async move {
+        let mut stdout = tokio::io::stdout();
+        while let Some(line) = rx.recv().await {
+            if stdout.write_all(line.as_bytes()).await.is_err()
+                || stdout.write_all(b"\n").await.is_err()
+                || stdout.flush().await.is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let client = client.clone();
+        let target = target.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            if let Some(response) = forward(&client, &target.0, &target.1, line).await {
+                let _ = tx.send(response).await;
+            }
+        });
+    }
+    drop(tx);
+    let _ = writer.await;
+}
+
+/// 转发一条消息，通知消息返回 `None`
+async fn forward(
+    client: &reqwest::Client,
+    url: &str,
+    api_key: &str,
+    line: String,
+) -> Option<String> {
+    let id = serde_json::from_str::<Value>(&line)
+        .ok()
+        .and_then(|message| message.get("id").cloned());
+    let result = client
+        .post(url)
+        .bearer_auth(api_key)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(line)
+        .send()
+        .await;
+    let message = match result {
+        Ok(response) if response.status() == reqwest::StatusCode::ACCEPTED => return None,
+        Ok(response) if response.status().is_success() => match response.text().await {
+            Ok(text) => return Some(text),
+            Err(e) => format!("读取 ProxyCast 响应失败: {}", e),
+        },
+        Ok(response) => format!("ProxyCast 返回错误: {}", response.status()),
+        Err(e) => format!(
+            "无法连接 ProxyCast，请确认应用已启动并开启 MCP 服务端: {}",
+            e
+        ),
+    };
+    id.map(|id| error_response(id, SERVER_ERROR, message).to_string())
+}
@@ -0,0 +1,278 @@
+//! MCP 服务端暴露的 ProxyCast 工具
+//!
+//! - `send_chat`: 向 Agent 发送消息（可指定会话继续对话）
+//! - `list_sessions`: 列出 Agent 会话
+//! - `get_usage_stats`: 查询最近 N 天的请求和 Token 统计
+//! - `list_skills` / `invoke_skill`: 列出和调用 `~/.proxycast/skills` 中的 Skill
+
+use super::protocol::{McpTool, ToolProvider};
+use crate::agent::types::{NativeChatRequest, StreamResult};
+use crate::agent::{NativeAgentState, ToolLoopEngine};
+use crate::commands::native_agent_cmd::ensure_agent_initialized;
+use crate::commands::skill_cmd::scan_installed_skills;
+use crate::commands::telemetry_cmd::TelemetryState;
+use crate::telemetry::TimeRange;
+use crate::AppState;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+use tokio::sync::mpsc;
+
+/// `list_sessions` 默认返回的会话数
+const DEFAULT_SESSION_LIMIT: u64 = 20;
+
+/// `get_usage_stats` 默认统计的天数
+const DEFAULT_USAGE_DAYS: i64 = 7;
+
+fn str_arg(args: &Value, name: &str) -> Option<String> {
+    args.get(name)
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.trim().is_empty())
+        .map(str::to_string)
+}
+
+fn required_str_arg(args: &Value, name: &str) -> Result<String, String> {
+    str_arg(args, name).ok_or_else(|| format!("缺少 {} 参数", name))
+}
+
+fn skills_dir() -> Result<PathBuf, String> {
+    dirs::home_dir()
+        .map(|home| home.join(".proxycast").join("skills"))
+        .ok_or_else(|| "无法获取用户 home 目录".to_string())
+}
+
+fn to_text(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+/// 基于应用状态的工具实现
+pub struct AppTools {
+    app: AppHandle,
+}
+
+impl AppTools {
+    pub fn new(app: AppHandle) -> Self {
+        Self { app }
+    }
+
+    fn agent_state(&self) -> Result<tauri::State<'_, NativeAgentState>, String> {
+        self.app
+            .try_state::<NativeAgentState>()
+            .ok_or_else(|| "Agent 状态不可用".to_string())
+    }
+
+    /// 在会话中执行一轮带工具的对话
+    async fn run_turn(
+        &self,
+        session_id: &str,
+        message: String,
+        model: Option<String>,
+    ) -> Result<StreamResult, String> {
+        let agent_state = self.agent_state()?;
+        let request = NativeChatRequest {
+            session_id: Some(session_id.to_string()),
+            message,
+            model,
+            images: None,
+            audio: None,
+            attachments: None,
+            context: None,
+            stream: true,
+        };
+        let engine = ToolLoopEngine::new(agent_state.get_tool_registry(Some(session_id))?)
+            .with_cancellations(agent_state.tool_cancellations().clone(), Some(session_id));
+        let (tx, mut rx) = mpsc::channel(100);
+        let drain = tokio::spawn(async move { while rx.recv().await.is_some() {} });
+        let result = agent_state
+            .chat_stream_with_tools(request, tx, &engine)
+            .await;
+        let _ = drain.await;
+        result.map_err(String::from)
+    }
+
+    async fn ensure_agent(&self) -> Result<(), String> {
+        let agent_state = self.agent_state()?;
+        let app_state = self
+            .app
+            .try_state::<AppState>()
+            .ok_or_else(|| "应用状态不可用".to_string())?;
+        ensure_agent_initialized(&agent_state, &app_state, false).await?;
+        Ok(())
+    }
+
+    async fn send_chat(&self, args: &Value) -> Result<String, String> {
+        let message = required_str_arg(args, "message")?;
+        let model = str_arg(args, "model");
+        self.ensure_agent().await?;
+
+        let session_id = match str_arg(args, "session_id") {
+            Some(id) => id,
+            None => self.agent_state()?.create_session(model.clone(), None)?,
+        };
+        let result = self.run_turn(&session_id, message, model).await?;
+        Ok(to_text(&json!({
+            "session_id": session_id,
+            "content": result.content,
+            "model": result.model,
+        })))
+    }
+
+    fn list_sessions(&self, args: &Value) -> Result<String, String> {
+        let limit = args
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_SESSION_LIMIT) as usize;
+        let mut sessions = self.agent_state()?.list_sessions();
+        sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        let sessions: Vec<Value> = sessions
+            .into_iter()
+            .take(limit)
+            .map(|s| {
+                json!({
+                    "id": s.id,
+                    "model": s.model,
+                    "messages": s.messages.len(),
+                    "tags": s.tags,
+                    "profile": s.profile,
+                    "created_at": s.created_at,
+                    "updated_at": s.updated_at,
+                })
+            })
+            .collect();
+        Ok(to_text(&Value::Array(sessions)))
+    }
+
+    fn get_usage_stats(&self, args: &Value) -> Result<String, String> {
+        let days = args
+            .get("days")
+            .and_then(|v| v.as_i64())
+            .filter(|d| *d > 0)
+            .unwrap_or(DEFAULT_USAGE_DAYS);
+        let telemetry = self
+            .app
+            .try_state::<TelemetryState>()
+            .ok_or_else(|| "统计服务不可用".to_string())?;
+        let range = TimeRange::last_days(days);
+        let (summary, by_model) = {
+            let stats = telemetry.stats.read();
+            (stats.summary(Some(range)), stats.by_model(Some(range)))
+        };
+        let tokens = telemetry
+            .tokens
+            .read()
+            .summary(Some(range.start), Some(range.end));
+        Ok(to_text(&json!({
+            "days": days,
+            "requests": summary,
+            "tokens": tokens,
+            "by_model": by_model,
+        })))
+    }
+
+    fn list_skills(&self) -> Result<String, String> {
+        let mut skills = scan_installed_skills(&skills_dir()?);
+        skills.sort();
+        Ok(to_text(&json!(skills)))
+    }
+
+    /// 以 Skill 的 SKILL.md 作为系统提示词，在临时会话中处理输入
+    async fn invoke_skill(&self, args: &Value) -> Result<String, String> {
+        let skill = required_str_arg(args, "skill")?;
+        let input = required_str_arg(args, "input")?;
+        let model = str_arg(args, "model");
+
+        let dir = skills_dir()?;
+        // 只允许调用已安装的 Skill，避免通过名称访问其他路径
+        if !scan_installed_skills(&dir).contains(&skill) {
+            return Err(format!("Skill 不存在: {}", skill));
+        }
+        let instructions = std::fs::read_to_string(dir.join(&skill).join("SKILL.md"))
+            .map_err(|e| format!("读取 Skill 失败: {}", e))?;
+
+        self.ensure_agent().await?;
+        let agent_state = self.agent_state()?;
+        let session_id = agent_state.create_session(model.clone(), Some(instructions))?;
+        let result = self.run_turn(&session_id, input, model).await;
+        agent_state.delete_session(&session_id);
+        result.map(|r| r.content)
+    }
+}
+
+#[async_trait]
+impl ToolProvider for AppTools {
+    fn tools(&self) -> Vec<McpTool> {
+        vec![
+            McpTool {
+                name: "send_chat".to_string(),
+                description: "Send a message to the ProxyCast agent and return its reply. \
+                              Pass session_id to continue an existing conversation; otherwise \
+                              a new session is created and its id is returned."
+                    .to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "message": {"type": "string", "description": "The user message"},
+                        "session_id": {"type": "string", "description": "Existing session to continue"},
+                        "model": {"type": "string", "description": "Model override"}
+                    },
+                    "required": ["message"]
+                }),
+            },
+            McpTool {
+                name: "list_sessions".to_string(),
+                description: "List ProxyCast agent sessions, most recently active first."
+                    .to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "limit": {"type": "integer", "description": "Maximum number of sessions (default 20)"}
+                    }
+                }),
+            },
+            McpTool {
+                name: "get_usage_stats".to_string(),
+                description: "Get request and token usage statistics of the ProxyCast proxy."
+                    .to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "days": {"type": "integer", "description": "Number of recent days (default 7)"}
+                    }
+                }),
+            },
+            McpTool {
+                name: "list_skills".to_string(),
+                description: "List the skills installed in ProxyCast.".to_string(),
+                input_schema: json!({"type": "object", "properties": {}}),
+            },
+            McpTool {
+                name: "invoke_skill".to_string(),
+                description: "Run an installed ProxyCast skill on the given input and return \
+                              the agent's result."
+                    .to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "skill": {"type": "string", "description": "Skill name from list_skills"},
+                        "input": {"type": "string", "description": "Task or content for the skill"},
+                        "model": {"type": "string", "description": "Model override"}
+                    },
+                    "required": ["skill", "input"]
+                }),
+            },
+        ]
+    }
+
+    async fn call(&self, name: &str, arguments: Value) -> Result<String, String> {
+        tracing::info!("[MCP] 调用工具: {}", name);
+        match name {
+            "send_chat" => self.send_chat(&arguments).await,
+            "list_sessions" => self.list_sessions(&arguments),
+            "get_usage_stats" => self.get_usage_stats(&arguments),
+            "list_skills" => self.list_skills(),
+            "invoke_skill" => self.invoke_skill(&arguments).await,
+            other => Err(format!("未知的工具: {}", other)),
+        }
+    }
+}
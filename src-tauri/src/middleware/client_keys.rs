@@ -0,0 +1,466 @@
+//! 客户端 API Key 中间件
+//!
+//! 在主 API Key 之外支持多个命名的客户端 Key：
+//! - 已吊销的 Key 返回 401
+//! - 限定了路由或模型的 Key 访问范围外的接口/模型时返回 403，
+//!   限定了模型的 Key 发起的 POST 请求（以及 Realtime 升级请求）未指定模型时同样返回 403
+//! - 浏览器 Realtime 客户端可以通过 `openai-insecure-api-key.<key>` 子协议传递 Key
+//! - 校验通过后把认证头替换为主 API Key，后续处理器沿用原有的认证逻辑，
+//!   并通过 `x-proxycast-client-key` 请求头传递 Key ID，用于按 Key 统计流量
+//!
+//! 未匹配任何客户端 Key 的请求原样放行，由后续的认证逻辑处理。
+
+use super::rate_limit::extract_api_key;
+use crate::config::ClientApiKey;
+use axum::{
+    body::Body,
+    http::{HeaderMap, HeaderValue, Method, Request, Response, StatusCode},
+};
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+
+/// 传递客户端 Key ID 的内部请求头（客户端自带的同名请求头会被移除）
+pub const CLIENT_KEY_HEADER: &str = "x-proxycast-client-key";
+/// 浏览器 WebSocket 客户端通过子协议传递 Key 时使用的前缀
+pub(crate) const INSECURE_API_KEY_PROTOCOL: &str = "openai-insecure-api-key.";
+/// OpenAI Realtime 升级路由（模型在 `?model=` 查询参数中）
+const REALTIME_PATH: &str = "/v1/realtime";
+
+/// 与运行中的服务器共享的主 API Key，修改配置后立即生效
+pub type SharedApiKey = Arc<RwLock<String>>;
+
+/// 单个客户端 Key 的使用统计（进程内，重启后清零）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClientKeyUsage {
+    /// 放行的请求数
+    pub requests: u64,
+    /// 被拒绝的请求数（已吊销或超出范围）
+    pub rejected: u64,
+    /// 最近一次使用时间
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// 客户端 Key 校验结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientKeyCheck {
+    /// 未匹配任何客户端 Key
+    Unknown,
+    /// 允许访问
+    Allowed(ClientApiKey),
+    /// 拒绝访问（状态码、原因）
+    Denied(StatusCode, String),
+}
+
+/// 客户端 Key 存储
+///
+/// 服务器运行期间与配置同步，修改 Key 后立即生效，无需重启服务器
+#[derive(Debug, Default)]
+pub struct ClientKeyStore {
+    keys: RwLock<Vec<ClientApiKey>>,
+    usage: RwLock<HashMap<String, ClientKeyUsage>>,
+}
+
+impl ClientKeyStore {
+    pub fn new(keys: Vec<ClientApiKey>) -> Self {
+        Self {
+            keys: RwLock::new(keys),
+            usage: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 替换全部 Key（配置变更后调用）
+    pub fn set_keys(&self, keys: Vec<ClientApiKey>) {
+        *self.keys.write() = keys;
+    }
+
+    /// 获取某个 Key 的使用统计
+    pub fn usage(&self, id: &str) -> ClientKeyUsage {
+        self.usage.read().get(id).cloned().unwrap_or_default()
+    }
+
+    /// 校验 Key 是否可以访问 `path`
+    ///
+    /// `model` 为请求中的模型（请求未指定时为 None），`requires_model` 表示该请求应当指定模型，
+    /// 此时限定了模型的 Key 在请求未指定模型时被拒绝
+    pub fn check(
+        &self,
+        key: &str,
+        path: &str,
+        model: Option<&str>,
+        requires_model: bool,
+    ) -> ClientKeyCheck {
+        let Some(client) = self.find(key) else {
+            return ClientKeyCheck::Unknown;
+        };
+
+        let denied = if client.revoked {
+            Some((
+                StatusCode::UNAUTHORIZED,
+                "API key has been revoked".to_string(),
+            ))
+        } else if !client.allows_route(path) {
+            Some((
+                StatusCode::FORBIDDEN,
+                format!("API key is not allowed to access {}", path),
+            ))
+        } else {
+            match model {
+                Some(m) if !client.allows_model(m) => Some((
+                    StatusCode::FORBIDDEN,
+                    format!("API key is not allowed to use model {}", m),
+                )),
+                None if requires_model && !client.allowed_models.is_empty() => Some((
+                    StatusCode::FORBIDDEN,
+                    "API key is restricted to specific models, but the request does not specify one"
+                        .to_string(),
+                )),
+                _ => None,
+            }
+        };
+
+        self.record(&client.id, denied.is_some());
+        match denied {
+            Some((status, message)) => ClientKeyCheck::Denied(status, message),
+            None => ClientKeyCheck::Allowed(client),
+        }
+    }
+
+    /// 是否需要读取请求体中的模型才能完成校验
+    fn needs_model(&self, key: &str) -> bool {
+        self.find(key).map_or(false, |client| {
+            !client.revoked && !client.allowed_models.is_empty()
+        })
+    }
+
+    fn find(&self, key: &str) -> Option<ClientApiKey> {
+        self.keys.read().iter().find(|k| k.key == key).cloned()
+    }
+
+    fn record(&self, id: &str, rejected: bool) {
+        let mut usage = self.usage.write();
+        let entry = usage.entry(id.to_string()).or_default();
+        if rejected {
+            entry.rejected += 1;
+        } else {
+            entry.requests += 1;
+        }
+        entry.last_used_at = Some(Utc::now());
+    }
+}
+
+/// 客户端 Key 校验层
+#[derive(Clone)]
+pub struct ClientKeyLayer {
+    master_key: SharedApiKey,
+    store: Arc<ClientKeyStore>,
+    /// 检查模型时缓冲请求体的大小上限（与服务器请求体限制一致）
+    body_limit: usize,
+}
+
+impl ClientKeyLayer {
+    /// 创建新的客户端 Key 校验层
+    pub fn new(master_key: SharedApiKey, store: Arc<ClientKeyStore>, body_limit: usize) -> Self {
+        Self {
+            master_key,
+            store,
+            body_limit,
+        }
+    }
+}
+
+impl<S> Layer<S> for ClientKeyLayer {
+    type Service = ClientKeyService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ClientKeyService {
+            inner,
+            master_key: self.master_key.clone(),
+            store: self.store.clone(),
+            body_limit: self.body_limit,
+        }
+    }
+}
+
+/// 客户端 Key 校验服务
+#[derive(Clone)]
+pub struct ClientKeyService<S> {
+    inner: S,
+    master_key: SharedApiKey,
+    store: Arc<ClientKeyStore>,
+    body_limit: usize,
+}
+
+impl<S> Service<Request<Body>> for ClientKeyService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        let master_key = self.master_key.read().clone();
+        let store = self.store.clone();
+        let body_limit = self.body_limit;
+        let mut inner = self.inner.clone();
+
+        Box::pin(async move {
+            req.headers_mut().remove(CLIENT_KEY_HEADER);
+
+            let key =
+                extract_api_key(req.headers()).or_else(|| websocket_protocol_key(req.headers()));
+            let key = match key {
+                Some(key) if key != master_key => key,
+                _ => return inner.call(req).await,
+            };
+
+            // 限定了模型的 Key 需要读取请求中的模型（Gemini 路由在路径中，Realtime 在查询参数中，
+            // 其余在请求体的 model 字段）
+            let is_realtime = req.uri().path() == REALTIME_PATH;
+            let requires_model = req.method() == Method::POST || is_realtime;
+            let mut model = if is_realtime {
+                query_model(req.uri().query())
+            } else {
+                gemini_path_model(req.uri().path()).map(str::to_string)
+            };
+            if requires_model && model.is_none() && store.needs_model(&key) {
+                let (parts, body) = req.into_parts();
+                let bytes = match axum::body::to_bytes(body, body_limit).await {
+                    Ok(bytes) => bytes,
+                    Err(_) => {
+                        return Ok(create_error_response(
+                            StatusCode::PAYLOAD_TOO_LARGE,
+                            "Request body too large",
+                        ))
+                    }
+                };
+                model = extract_model(&bytes);
+                req = Request::from_parts(parts, Body::from(bytes));
+            }
+
+            let path = req.uri().path().to_string();
+            match store.check(&key, &path, model.as_deref(), requires_model) {
+                ClientKeyCheck::Unknown => inner.call(req).await,
+                ClientKeyCheck::Denied(status, message) => {
+                    tracing::warn!("[CLIENT_KEY] {} {} 被拒绝: {}", req.method(), path, message);
+                    Ok(create_error_response(status, &message))
+                }
+                ClientKeyCheck::Allowed(client) => {
+                    replace_auth_headers(req.headers_mut(), &master_key);
+                    if let Ok(value) = HeaderValue::from_str(&client.id) {
+                        req.headers_mut().insert(CLIENT_KEY_HEADER, value);
+                    }
+                    inner.call(req).await
+                }
+            }
+        })
+    }
+}
+
+/// 从 Gemini 路由路径中读取模型（`/v1/gemini/{model}:{method}`）
+pub(crate) fn gemini_path_model(path: &str) -> Option<&str> {
+    let rest = path.strip_prefix("/v1/gemini/")?;
+    let model = rest.split(':').next().unwrap_or(rest);
+    (!model.is_empty()).then_some(model)
+}
+
+/// 从查询字符串中读取 model 参数（`?model=...`）
+fn query_model(query: Option<&str>) -> Option<String> {
+    serde_urlencoded::from_str::<HashMap<String, String>>(query?)
+        .ok()?
+        .remove("model")
+        .filter(|model| !model.is_empty())
+}
+
+/// 从 `Sec-WebSocket-Protocol` 中读取 `openai-insecure-api-key.<key>` 携带的 Key
+pub(crate) fn websocket_protocol_key(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("sec-websocket-protocol")
+        .and_then(|v| v.to_str().ok())?
+        .split(',')
+        .map(str::trim)
+        .find_map(|p| p.strip_prefix(INSECURE_API_KEY_PROTOCOL))
+        .filter(|key| !key.is_empty())
+        .map(|key| key.to_string())
+}
+
+/// 从 JSON 请求体中读取 model 字段
+pub(crate) fn extract_model(body: &[u8]) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_slice(body).ok()?;
+    value.get("model")?.as_str().map(|s| s.to_string())
+}
+
+/// 把请求中携带的认证头替换为主 API Key
+fn replace_auth_headers(headers: &mut HeaderMap, master_key: &str) {
+    let Ok(plain) = HeaderValue::from_str(master_key) else {
+        return;
+    };
+    if headers.contains_key("authorization") {
+        if let Ok(bearer) = HeaderValue::from_str(&format!("Bearer {}", master_key)) {
+            headers.insert("authorization", bearer);
+        }
+    }
+    for name in ["x-api-key", "x-goog-api-key"] {
+        if headers.contains_key(name) {
+            headers.insert(name, plain.clone());
+        }
+    }
+}
+
+/// 从请求头中读取客户端 Key ID（由本中间件写入）
+pub fn client_key_id(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(CLIENT_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string())
+}
+
+/// 创建错误响应（同时兼容 OpenAI 和 Anthropic 客户端的错误格式）
+fn create_error_response(status: StatusCode, message: &str) -> Response<Body> {
+    let error_type = match status {
+        StatusCode::UNAUTHORIZED => "authentication_error",
+        StatusCode::FORBIDDEN => "permission_error",
+        _ => "invalid_request_error",
+    };
+    let body = serde_json::json!({
+        "type": "error",
+        "error": {
+            "type": error_type,
+            "message": message
+        }
+    });
+
+    Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client_key(key: &str) -> ClientApiKey {
+        ClientApiKey {
+            id: format!("id-{}", key),
+            name: key.to_string(),
+            key: key.to_string(),
+            allowed_models: Vec::new(),
+            allowed_routes: Vec::new(),
+            created_at: Utc::now().to_rfc3339(),
+            revoked: false,
+        }
+    }
+
+    #[test]
+    fn test_check_scopes() {
+        let mut scoped = client_key("scoped");
+        scoped.allowed_models = vec!["claude-*".to_string(), "gpt-4o".to_string()];
+        scoped.allowed_routes = vec!["/v1/messages".to_string()];
+        let mut revoked = client_key("revoked");
+        revoked.revoked = true;
+        let store = ClientKeyStore::new(vec![client_key("open"), scoped, revoked]);
+
+        assert_eq!(
+            store.check("unknown", "/v1/messages", None, true),
+            ClientKeyCheck::Unknown
+        );
+        assert!(matches!(
+            store.check("open", "/v1/chat/completions", Some("gpt-4"), true),
+            ClientKeyCheck::Allowed(_)
+        ));
+        assert!(matches!(
+            store.check("revoked", "/v1/messages", None, true),
+            ClientKeyCheck::Denied(StatusCode::UNAUTHORIZED, _)
+        ));
+        assert!(matches!(
+            store.check("scoped", "/v1/messages", Some("claude-sonnet-4-5"), true),
+            ClientKeyCheck::Allowed(_)
+        ));
+        assert!(matches!(
+            store.check("scoped", "/v1/messages", Some("gpt-4o-mini"), true),
+            ClientKeyCheck::Denied(StatusCode::FORBIDDEN, _)
+        ));
+        assert!(matches!(
+            store.check("scoped", "/v1/chat/completions", Some("gpt-4o"), true),
+            ClientKeyCheck::Denied(StatusCode::FORBIDDEN, _)
+        ));
+        // 路由按路径段匹配
+        assert!(matches!(
+            store.check("scoped", "/v1/messages/count_tokens", Some("gpt-4o"), true),
+            ClientKeyCheck::Allowed(_)
+        ));
+        assert!(matches!(
+            store.check("scoped", "/v1/messages2", Some("gpt-4o"), true),
+            ClientKeyCheck::Denied(StatusCode::FORBIDDEN, _)
+        ));
+        // 限定了模型的 Key 不能省略模型
+        assert!(matches!(
+            store.check("scoped", "/v1/messages", None, true),
+            ClientKeyCheck::Denied(StatusCode::FORBIDDEN, _)
+        ));
+        assert!(matches!(
+            store.check("scoped", "/v1/messages", None, false),
+            ClientKeyCheck::Allowed(_)
+        ));
+
+        let usage = store.usage("id-scoped");
+        assert_eq!(usage.requests, 3);
+        assert_eq!(usage.rejected, 4);
+        assert!(store.needs_model("scoped"));
+        assert!(!store.needs_model("open"));
+    }
+
+    #[test]
+    fn test_replace_auth_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", HeaderValue::from_static("Bearer client"));
+        headers.insert("x-api-key", HeaderValue::from_static("client"));
+
+        replace_auth_headers(&mut headers, "master");
+
+        assert_eq!(headers["authorization"], "Bearer master");
+        assert_eq!(headers["x-api-key"], "master");
+        assert!(!headers.contains_key("x-goog-api-key"));
+        assert_eq!(
+            extract_model(br#"{"model":"gpt-4o","messages":[]}"#).as_deref(),
+            Some("gpt-4o")
+        );
+        assert_eq!(
+            gemini_path_model("/v1/gemini/gemini-2.5-pro:streamGenerateContent"),
+            Some("gemini-2.5-pro")
+        );
+        assert_eq!(gemini_path_model("/v1/gemini/"), None);
+        assert_eq!(gemini_path_model("/v1/messages"), None);
+    }
+
+    #[test]
+    fn test_realtime_key_and_model() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "sec-websocket-protocol",
+            HeaderValue::from_static("realtime, openai-insecure-api-key.client"),
+        );
+        assert_eq!(websocket_protocol_key(&headers).as_deref(), Some("client"));
+        assert_eq!(
+            query_model(Some("model=gpt-4o-realtime-preview&x=1")).as_deref(),
+            Some("gpt-4o-realtime-preview")
+        );
+        assert_eq!(query_model(Some("model=")), None);
+        assert_eq!(query_model(None), None);
+    }
+}
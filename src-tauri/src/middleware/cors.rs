@@ -0,0 +1,257 @@
+//! 浏览器跨域访问（CORS）中间件
+//!
+//! 网页工具直接调用本地服务器时，按 `server.cors` 配置返回 CORS 响应头：
+//! - 预检请求（带 `Access-Control-Request-Method` 的 OPTIONS）在此直接应答，不经过认证和签名校验
+//! - 允许的来源的其他请求在响应中回显 `Access-Control-Allow-Origin`，错误响应同样带上，
+//!   浏览器可以读到认证失败等错误信息
+//! - 不带 `Origin` 的请求（命令行工具、SDK）和不允许的来源不添加任何 CORS 响应头
+
+use crate::config::CorsConfig;
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderValue, Method, Request, Response, StatusCode},
+};
+use futures::future::BoxFuture;
+use parking_lot::RwLock;
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+
+/// CORS 策略
+///
+/// 服务器运行期间与配置同步，修改配置后立即生效
+#[derive(Debug, Default)]
+pub struct CorsPolicy {
+    config: RwLock<CorsConfig>,
+}
+
+impl CorsPolicy {
+    pub fn new(config: CorsConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+        }
+    }
+
+    /// 更新 CORS 配置（配置变更后调用）
+    pub fn set_config(&self, config: CorsConfig) {
+        *self.config.write() = config;
+    }
+
+    /// 允许跨域访问时返回请求的来源
+    fn allowed_origin(&self, headers: &HeaderMap) -> Option<HeaderValue> {
+        let origin = headers.get(header::ORIGIN)?;
+        let config = self.config.read();
+        (config.enabled && config.allows_origin(origin.to_str().ok()?)).then(|| origin.clone())
+    }
+
+    /// 预检请求的响应头，方法或请求头不允许时返回 None
+    fn preflight_headers(&self, origin: HeaderValue, request: &HeaderMap) -> Option<HeaderMap> {
+        let config = self.config.read();
+        let method = request
+            .get(header::ACCESS_CONTROL_REQUEST_METHOD)
+            .and_then(|v| v.to_str().ok())?;
+        if !config.allows_method(method) {
+            return None;
+        }
+
+        let requested_headers = request
+            .get(header::ACCESS_CONTROL_REQUEST_HEADERS)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        let allow_any_header = config.allowed_headers.iter().any(|h| h.trim() == "*");
+        let all_allowed = requested_headers
+            .split(',')
+            .map(str::trim)
+            .filter(|h| !h.is_empty())
+            .all(|h| {
+                allow_any_header
+                    || config
+                        .allowed_headers
+                        .iter()
+                        .any(|allowed| allowed.trim().eq_ignore_ascii_case(h))
+            });
+        if !all_allowed {
+            return None;
+        }
+        let allow_headers = if allow_any_header {
+            requested_headers.to_string()
+        } else {
+            config.allowed_headers.join(", ")
+        };
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+        headers.insert(header::VARY, HeaderValue::from_static("Origin"));
+        if let Ok(value) = HeaderValue::from_str(&config.allowed_methods.join(", ")) {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_METHODS, value);
+        }
+        if let Ok(value) = HeaderValue::from_str(&allow_headers) {
+            if !value.is_empty() {
+                headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, value);
+            }
+        }
+        headers.insert(
+            header::ACCESS_CONTROL_MAX_AGE,
+            HeaderValue::from(config.max_age_secs),
+        );
+        Some(headers)
+    }
+}
+
+/// CORS 层
+#[derive(Clone)]
+pub struct CorsLayer {
+    policy: Arc<CorsPolicy>,
+}
+
+impl CorsLayer {
+    pub fn new(policy: Arc<CorsPolicy>) -> Self {
+        Self { policy }
+    }
+}
+
+impl<S> Layer<S> for CorsLayer {
+    type Service = CorsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CorsService {
+            inner,
+            policy: self.policy.clone(),
+        }
+    }
+}
+
+/// CORS 服务
+#[derive(Clone)]
+pub struct CorsService<S> {
+    inner: S,
+    policy: Arc<CorsPolicy>,
+}
+
+impl<S> Service<Request<Body>> for CorsService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let mut inner = self.inner.clone();
+        let Some(origin) = self.policy.allowed_origin(req.headers()) else {
+            return Box::pin(async move { inner.call(req).await });
+        };
+
+        if req.method() == Method::OPTIONS
+            && req
+                .headers()
+                .contains_key(header::ACCESS_CONTROL_REQUEST_METHOD)
+        {
+            let preflight = self.policy.preflight_headers(origin, req.headers());
+            return Box::pin(async move {
+                let mut response = Response::new(Body::empty());
+                match preflight {
+                    Some(headers) => {
+                        *response.status_mut() = StatusCode::NO_CONTENT;
+                        response.headers_mut().extend(headers);
+                    }
+                    None => {
+                        tracing::debug!("[CORS] 拒绝预检请求: {:?}", req.headers());
+                        *response.status_mut() = StatusCode::FORBIDDEN;
+                    }
+                }
+                Ok(response)
+            });
+        }
+
+        Box::pin(async move {
+            let mut response = inner.call(req).await?;
+            let headers = response.headers_mut();
+            headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+            headers.append(header::VARY, HeaderValue::from_static("Origin"));
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preflight(origin: &str, method: &str, headers: &str) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        map.insert(header::ORIGIN, HeaderValue::from_str(origin).unwrap());
+        map.insert(
+            header::ACCESS_CONTROL_REQUEST_METHOD,
+            HeaderValue::from_str(method).unwrap(),
+        );
+        map.insert(
+            header::ACCESS_CONTROL_REQUEST_HEADERS,
+            HeaderValue::from_str(headers).unwrap(),
+        );
+        map
+    }
+
+    #[test]
+    fn test_preflight() {
+        let policy = CorsPolicy::new(CorsConfig::default());
+        let request = preflight(
+            "http://localhost:5173",
+            "POST",
+            "authorization, content-type",
+        );
+        let origin = policy.allowed_origin(&request).unwrap();
+        let headers = policy.preflight_headers(origin, &request).unwrap();
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "http://localhost:5173"
+        );
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_HEADERS],
+            "authorization, content-type"
+        );
+        assert_eq!(headers[header::ACCESS_CONTROL_MAX_AGE], "600");
+
+        // 默认只允许本机页面
+        assert!(policy
+            .allowed_origin(&preflight("https://chat.example.com", "POST", ""))
+            .is_none());
+        assert!(policy
+            .preflight_headers(
+                HeaderValue::from_static("http://localhost:5173"),
+                &preflight("http://localhost:5173", "PATCH", "")
+            )
+            .is_none());
+
+        // 限定请求头时只允许列出的请求头
+        policy.set_config(CorsConfig {
+            allowed_origins: vec!["https://chat.example.com".to_string()],
+            allowed_headers: vec!["Authorization".to_string(), "Content-Type".to_string()],
+            ..CorsConfig::default()
+        });
+        let request = preflight("https://chat.example.com", "POST", "authorization");
+        let origin = policy.allowed_origin(&request).unwrap();
+        let headers = policy.preflight_headers(origin.clone(), &request).unwrap();
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_HEADERS],
+            "Authorization, Content-Type"
+        );
+        let request = preflight("https://chat.example.com", "POST", "x-custom");
+        assert!(policy.preflight_headers(origin, &request).is_none());
+
+        policy.set_config(CorsConfig {
+            enabled: false,
+            ..CorsConfig::default()
+        });
+        assert!(policy
+            .allowed_origin(&preflight("http://localhost:5173", "POST", ""))
+            .is_none());
+    }
+}
@@ -0,0 +1,156 @@
+//! 客户端 IP 白名单中间件
+//!
+//! 服务器对局域网开放时，只允许白名单内的客户端访问：
+//! - 按真实连接地址判断，不信任 `X-Forwarded-For` 等可伪造的请求头
+//! - 本机（回环地址）始终允许
+//! - IPv4 映射的 IPv6 地址（监听 `::` 时）按 IPv4 地址匹配
+//! - 无法获取连接地址时拒绝
+
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{Request, Response, StatusCode},
+};
+use futures::future::BoxFuture;
+use ipnet::IpNet;
+use parking_lot::RwLock;
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+
+/// 客户端 IP 白名单
+///
+/// 服务器运行期间与配置同步，修改配置后立即生效
+#[derive(Debug, Default)]
+pub struct IpAllowlist {
+    networks: RwLock<Vec<IpNet>>,
+}
+
+impl IpAllowlist {
+    pub fn new(networks: Vec<IpNet>) -> Self {
+        Self {
+            networks: RwLock::new(networks),
+        }
+    }
+
+    /// 更新白名单（配置变更后调用）
+    pub fn set_networks(&self, networks: Vec<IpNet>) {
+        *self.networks.write() = networks;
+    }
+
+    /// 是否允许该地址访问
+    pub fn allows(&self, ip: IpAddr) -> bool {
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+            IpAddr::V4(_) => ip,
+        };
+        ip.is_loopback() || self.networks.read().iter().any(|net| net.contains(&ip))
+    }
+}
+
+/// IP 白名单层
+#[derive(Clone)]
+pub struct IpAllowlistLayer {
+    allowlist: Arc<IpAllowlist>,
+}
+
+impl IpAllowlistLayer {
+    pub fn new(allowlist: Arc<IpAllowlist>) -> Self {
+        Self { allowlist }
+    }
+}
+
+impl<S> Layer<S> for IpAllowlistLayer {
+    type Service = IpAllowlistService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        IpAllowlistService {
+            inner,
+            allowlist: self.allowlist.clone(),
+        }
+    }
+}
+
+/// IP 白名单服务
+#[derive(Clone)]
+pub struct IpAllowlistService<S> {
+    inner: S,
+    allowlist: Arc<IpAllowlist>,
+}
+
+impl<S> Service<Request<Body>> for IpAllowlistService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let client_ip = req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|info| info.0.ip());
+        let allowed = client_ip.map_or(false, |ip| self.allowlist.allows(ip));
+        if !allowed {
+            tracing::warn!(
+                "[IP_ALLOWLIST] 拒绝不在白名单内的客户端: {}",
+                client_ip.map_or_else(|| "unknown".to_string(), |ip| ip.to_string())
+            );
+            return Box::pin(async move {
+                Ok(Response::builder()
+                    .status(StatusCode::FORBIDDEN)
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        serde_json::json!({
+                            "error": {
+                                "type": "permission_error",
+                                "message": "Client IP is not allowed"
+                            }
+                        })
+                        .to_string(),
+                    ))
+                    .unwrap())
+            });
+        }
+
+        let mut inner = self.inner.clone();
+        Box::pin(async move { inner.call(req).await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowlist(entries: &[&str]) -> IpAllowlist {
+        IpAllowlist::new(entries.iter().map(|e| e.parse().unwrap()).collect())
+    }
+
+    #[test]
+    fn test_allows() {
+        let list = allowlist(&["192.168.1.0/24", "10.0.0.5/32"]);
+        assert!(list.allows("192.168.1.42".parse().unwrap()));
+        assert!(list.allows("10.0.0.5".parse().unwrap()));
+        assert!(!list.allows("10.0.0.6".parse().unwrap()));
+        assert!(!list.allows("192.168.2.1".parse().unwrap()));
+
+        // 本机始终允许，IPv4 映射地址按 IPv4 匹配
+        assert!(list.allows("127.0.0.1".parse().unwrap()));
+        assert!(list.allows("::1".parse().unwrap()));
+        assert!(list.allows("::ffff:192.168.1.7".parse().unwrap()));
+
+        // 空白名单只允许本机
+        let empty = IpAllowlist::default();
+        assert!(empty.allows("127.0.0.1".parse().unwrap()));
+        assert!(!empty.allows("192.168.1.42".parse().unwrap()));
+    }
+}
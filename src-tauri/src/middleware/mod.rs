@@ -2,9 +2,30 @@
 //!
 //! 提供 HTTP 请求处理的中间件组件
 
+pub mod client_keys;
+pub mod cors;
+pub mod ip_allowlist;
 pub mod management_auth;
+pub mod rate_limit;
+pub mod request_pipeline;
+pub mod request_signing;
+pub mod response_cache;
 
 #[cfg(test)]
 mod tests;
 
+pub use client_keys::{
+    ClientKeyLayer, ClientKeyService, ClientKeyStore, ClientKeyUsage, SharedApiKey,
+};
+pub use cors::{CorsLayer, CorsPolicy, CorsService};
+pub use ip_allowlist::{IpAllowlist, IpAllowlistLayer, IpAllowlistService};
 pub use management_auth::{ManagementAuthLayer, ManagementAuthService};
+pub use rate_limit::{RateLimitLayer, RateLimitService, RateLimiter};
+pub use request_pipeline::{
+    MiddlewareOutcome, MiddlewareRequest, RequestMiddleware, RequestPipeline, RequestPipelineLayer,
+};
+pub use request_signing::{RequestSigner, RequestSigningLayer, RequestSigningService};
+pub use response_cache::{
+    CacheHit, CacheHitSink, ResponseCache, ResponseCacheLayer, ResponseCacheService,
+    ResponseCacheStats,
+};
@@ -0,0 +1,426 @@
+//! API 限流中间件
+//!
+//! 按请求携带的 API Key 分别限制最近一分钟内的请求数和 token 数，
+//! 防止失控的本地脚本耗尽上游额度：
+//! - 使用滑动窗口记录每个 Key 最近一分钟的请求
+//! - token 数按请求体大小估算（约 4 字节 / token），在请求进入时计入；
+//!   没有 `Content-Length` 的请求（分块传输等）先读取请求体再计数
+//! - 超出限制时返回 429 Too Many Requests 和 `Retry-After`（秒）
+//!
+//! 未携带 API Key 的请求不计数，由后续的认证逻辑拒绝。
+//! 限流配置与运行中的服务器共享，修改后立即生效。
+
+use crate::config::RateLimitConfig;
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, Request, Response, StatusCode},
+};
+use futures::future::BoxFuture;
+use parking_lot::RwLock;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tower::{Layer, Service};
+
+/// 滑动窗口长度
+const WINDOW: Duration = Duration::from_secs(60);
+/// 估算 token 时每个 token 对应的字节数
+const BYTES_PER_TOKEN: u64 = 4;
+// 限制窗口表最大条目数，防止大量不同 Key 导致内存无界增长
+const MAX_TRACKED_KEYS: usize = 10000;
+
+/// 单个 Key 最近一分钟的请求记录（时间、估算 token 数）
+#[derive(Default)]
+struct KeyWindow {
+    entries: VecDeque<(Instant, u32)>,
+    tokens: u64,
+}
+
+impl KeyWindow {
+    /// 移除窗口外的记录
+    fn prune(&mut self, now: Instant) {
+        while let Some(&(at, tokens)) = self.entries.front() {
+            if now.duration_since(at) < WINDOW {
+                break;
+            }
+            self.entries.pop_front();
+            self.tokens -= tokens as u64;
+        }
+    }
+}
+
+/// 按 API Key 的限流器
+pub struct RateLimiter {
+    config: RwLock<RateLimitConfig>,
+    windows: Mutex<HashMap<String, KeyWindow>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 更新限流配置（配置变更后调用，已记录的窗口保留）
+    pub fn set_config(&self, config: RateLimitConfig) {
+        *self.config.write() = config;
+    }
+
+    /// 是否需要限流
+    pub fn is_enabled(&self) -> bool {
+        let config = self.config.read();
+        config.enabled && (config.requests_per_minute > 0 || config.tokens_per_minute > 0)
+    }
+
+    /// 检查并记录一次请求
+    ///
+    /// 允许时计入窗口并返回 `Ok(())`，超出限制时返回需要等待的时长。
+    /// 窗口为空时单个请求即使超过 token 上限也会放行，避免大请求永远无法通过。
+    pub fn check(&self, key: &str, tokens: u32, now: Instant) -> Result<(), Duration> {
+        let config = self.config.read().clone();
+        let mut windows = self.windows.lock().unwrap();
+
+        if windows.len() > MAX_TRACKED_KEYS {
+            windows.retain(|_, window| {
+                window.prune(now);
+                !window.entries.is_empty()
+            });
+        }
+
+        let window = windows.entry(key.to_string()).or_default();
+        window.prune(now);
+
+        let rpm = config.requests_per_minute as usize;
+        if rpm > 0 && window.entries.len() >= rpm {
+            // 最早的记录移出窗口后才有名额
+            let index = window.entries.len() - rpm;
+            return Err(Self::wait_until(window.entries[index].0, now));
+        }
+
+        let tpm = config.tokens_per_minute as u64;
+        if tpm > 0 && !window.entries.is_empty() && window.tokens + tokens as u64 > tpm {
+            // 从最早的记录开始，直到移出的 token 足够容纳本次请求
+            let mut remaining = window.tokens + tokens as u64;
+            for &(at, used) in &window.entries {
+                remaining -= used as u64;
+                if remaining <= tpm {
+                    return Err(Self::wait_until(at, now));
+                }
+            }
+        }
+
+        window.entries.push_back((now, tokens));
+        window.tokens += tokens as u64;
+        Ok(())
+    }
+
+    /// 记录 `at` 移出窗口还需等待的时长
+    fn wait_until(at: Instant, now: Instant) -> Duration {
+        (at + WINDOW).saturating_duration_since(now)
+    }
+
+    /// 是否限制 token 数（需要估算每个请求的 token 数）
+    fn limits_tokens(&self) -> bool {
+        self.config.read().tokens_per_minute > 0
+    }
+
+    /// 按 Content-Length 估算请求的 token 数（没有该请求头时返回 None）
+    fn estimate_tokens(req: &Request<Body>) -> Option<u32> {
+        req.headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(Self::tokens_for_bytes)
+    }
+
+    /// 按字节数估算 token 数
+    fn tokens_for_bytes(len: u64) -> u32 {
+        len.div_ceil(BYTES_PER_TOKEN).min(u32::MAX as u64) as u32
+    }
+}
+
+/// 从请求头中提取客户端 API Key（与接口认证使用的请求头一致）
+pub(crate) fn extract_api_key(headers: &HeaderMap) -> Option<String> {
+    let value = headers
+        .get("authorization")
+        .or_else(|| headers.get("x-api-key"))
+        .or_else(|| headers.get("x-goog-api-key"))
+        .and_then(|v| v.to_str().ok())?;
+    let key = value.strip_prefix("Bearer ").unwrap_or(value).trim();
+    (!key.is_empty()).then(|| key.to_string())
+}
+
+/// API 限流层
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    limiter: Arc<RateLimiter>,
+    /// 计数时缓冲请求体的大小上限（与服务器请求体限制一致）
+    body_limit: usize,
+}
+
+impl RateLimitLayer {
+    /// 创建新的限流层
+    pub fn new(limiter: Arc<RateLimiter>, body_limit: usize) -> Self {
+        Self {
+            limiter,
+            body_limit,
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            limiter: self.limiter.clone(),
+            body_limit: self.body_limit,
+        }
+    }
+}
+
+/// API 限流服务
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    limiter: Arc<RateLimiter>,
+    body_limit: usize,
+}
+
+impl<S> Service<Request<Body>> for RateLimitService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        let limiter = self.limiter.clone();
+        let body_limit = self.body_limit;
+        let mut inner = self.inner.clone();
+
+        Box::pin(async move {
+            if limiter.is_enabled() {
+                if let Some(key) = extract_api_key(req.headers()) {
+                    let tokens = match RateLimiter::estimate_tokens(&req) {
+                        Some(tokens) => tokens,
+                        None if limiter.limits_tokens() => {
+                            // 流式上传的请求体没有 Content-Length，读取后按实际大小计数
+                            let (parts, body) = req.into_parts();
+                            let bytes = match axum::body::to_bytes(body, body_limit).await {
+                                Ok(bytes) => bytes,
+                                Err(_) => return Ok(create_body_too_large_response()),
+                            };
+                            req = Request::from_parts(parts, Body::from(bytes.clone()));
+                            RateLimiter::tokens_for_bytes(bytes.len() as u64)
+                        }
+                        None => 0,
+                    };
+                    if let Err(retry_after) = limiter.check(&key, tokens, Instant::now()) {
+                        tracing::warn!(
+                            "[RATE_LIMIT] {} {} 超出限流，{} 秒后重试",
+                            req.method(),
+                            req.uri().path(),
+                            retry_after.as_secs_f64().ceil()
+                        );
+                        return Ok(create_rate_limit_response(retry_after));
+                    }
+                }
+            }
+            inner.call(req).await
+        })
+    }
+}
+
+/// 创建 429 响应（同时兼容 OpenAI 和 Anthropic 客户端的错误格式）
+fn create_rate_limit_response(retry_after: Duration) -> Response<Body> {
+    let seconds = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    let body = serde_json::json!({
+        "type": "error",
+        "error": {
+            "type": "rate_limit_error",
+            "code": "rate_limit_exceeded",
+            "message": format!("Rate limit exceeded for this API key, retry after {} seconds", seconds)
+        }
+    });
+
+    Response::builder()
+        .status(StatusCode::TOO_MANY_REQUESTS)
+        .header("content-type", "application/json")
+        .header(header::RETRY_AFTER, seconds.to_string())
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+/// 创建 413 响应（请求体超过缓冲上限）
+fn create_body_too_large_response() -> Response<Body> {
+    let body = serde_json::json!({
+        "type": "error",
+        "error": {
+            "type": "invalid_request_error",
+            "message": "Request body too large"
+        }
+    });
+
+    Response::builder()
+        .status(StatusCode::PAYLOAD_TOO_LARGE)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled_limiter(requests_per_minute: u32, tokens_per_minute: u32) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            enabled: true,
+            requests_per_minute,
+            tokens_per_minute,
+        })
+    }
+
+    #[test]
+    fn test_requests_per_minute() {
+        let limiter = enabled_limiter(2, 0);
+        let start = Instant::now();
+
+        assert!(limiter.check("key", 0, start).is_ok());
+        assert!(limiter
+            .check("key", 0, start + Duration::from_secs(10))
+            .is_ok());
+        let retry = limiter
+            .check("key", 0, start + Duration::from_secs(20))
+            .unwrap_err();
+        assert_eq!(retry, Duration::from_secs(40));
+
+        // 其他 Key 不受影响
+        assert!(limiter.check("other", 0, start).is_ok());
+
+        // 最早的请求移出窗口后恢复
+        assert!(limiter
+            .check("key", 0, start + Duration::from_secs(60))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_tokens_per_minute() {
+        let limiter = enabled_limiter(0, 1000);
+        let start = Instant::now();
+
+        // 窗口为空时超大请求也放行
+        assert!(limiter.check("key", 1500, start).is_ok());
+        assert!(limiter
+            .check("key", 100, start + Duration::from_secs(5))
+            .is_err());
+
+        let limiter = enabled_limiter(0, 1000);
+        assert!(limiter.check("key", 600, start).is_ok());
+        assert!(limiter
+            .check("key", 300, start + Duration::from_secs(30))
+            .is_ok());
+        let retry = limiter
+            .check("key", 200, start + Duration::from_secs(45))
+            .unwrap_err();
+        assert_eq!(retry, Duration::from_secs(15));
+    }
+
+    #[test]
+    fn test_disabled_limiter() {
+        let limiter = RateLimiter::new(RateLimitConfig::default());
+        assert!(!limiter.is_enabled());
+        assert!(!enabled_limiter(0, 0).is_enabled());
+    }
+
+    #[test]
+    fn test_set_config() {
+        let limiter = enabled_limiter(1, 0);
+        let start = Instant::now();
+        assert!(limiter.check("key", 0, start).is_ok());
+        assert!(limiter.check("key", 0, start).is_err());
+
+        limiter.set_config(RateLimitConfig {
+            enabled: true,
+            requests_per_minute: 2,
+            tokens_per_minute: 0,
+        });
+        assert!(limiter.check("key", 0, start).is_ok());
+
+        limiter.set_config(RateLimitConfig::default());
+        assert!(!limiter.is_enabled());
+    }
+
+    #[test]
+    fn test_extract_api_key_and_tokens() {
+        let req = Request::builder()
+            .header("authorization", "Bearer sk-test")
+            .header("content-length", "401")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(extract_api_key(req.headers()).as_deref(), Some("sk-test"));
+        assert_eq!(RateLimiter::estimate_tokens(&req), Some(101));
+
+        let req = Request::builder().body(Body::empty()).unwrap();
+        assert!(extract_api_key(req.headers()).is_none());
+        assert_eq!(RateLimiter::estimate_tokens(&req), None);
+    }
+
+    #[tokio::test]
+    async fn test_chunked_body_counts_tokens() {
+        use futures::stream;
+        use tower::ServiceExt;
+
+        let echo = tower::service_fn(|req: Request<Body>| async move {
+            let body = axum::body::to_bytes(req.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            Ok::<_, std::convert::Infallible>(Response::new(Body::from(body)))
+        });
+        let service = RateLimitLayer::new(Arc::new(enabled_limiter(0, 100)), 1024).layer(echo);
+
+        let chunked = || {
+            let chunks: Vec<Result<_, std::io::Error>> =
+                vec![Ok(vec![b'a'; 300]), Ok(vec![b'b'; 300])];
+            Request::builder()
+                .header("authorization", "Bearer sk-test")
+                .body(Body::from_stream(stream::iter(chunks)))
+                .unwrap()
+        };
+
+        // 第一个请求（150 token）放行且请求体保持不变，第二个请求超出每分钟 100 token
+        let response = service.clone().oneshot(chunked()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body.len(), 600);
+
+        let response = service.oneshot(chunked()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // 请求体超过配置的大小上限时直接拒绝
+        let service = RateLimitLayer::new(Arc::new(enabled_limiter(0, 1000)), 500).layer(echo);
+        let response = service.oneshot(chunked()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[test]
+    fn test_rate_limit_response() {
+        let response = create_rate_limit_response(Duration::from_millis(1500));
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "2");
+    }
+}
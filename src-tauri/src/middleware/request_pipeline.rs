@@ -0,0 +1,681 @@
+//! 请求中间件管道
+//!
+//! 按配置顺序对 API 请求执行一组中间件，在请求到达处理器（转发上游）之前：
+//! - 改写或删除请求头
+//! - 注入系统提示词（OpenAI / Anthropic / Gemini 请求格式）
+//! - 删除请求体字段
+//! - 直接拦截请求
+//!
+//! 每个中间件实现 [`RequestMiddleware`]，内置实现由配置构建，
+//! 也可以通过 [`RequestPipeline::push`] 注册自定义实现。
+//! 任一中间件拦截时后续中间件不再执行。
+
+use crate::config::{PromptPosition, RequestMiddlewareAction, RequestMiddlewareConfig};
+use axum::{
+    body::Body,
+    http::{
+        header::CONTENT_LENGTH, HeaderMap, HeaderName, HeaderValue, Request, Response, StatusCode,
+    },
+};
+use futures::future::BoxFuture;
+use parking_lot::RwLock;
+use serde_json::Value;
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+
+/// 缓冲请求体的大小上限（与服务器请求体限制一致）
+const MAX_BUFFERED_BODY: usize = 100 * 1024 * 1024;
+
+/// 交给中间件处理的请求
+#[derive(Debug)]
+pub struct MiddlewareRequest {
+    pub path: String,
+    pub headers: HeaderMap,
+    body: Option<Value>,
+    body_modified: bool,
+}
+
+impl MiddlewareRequest {
+    pub fn new(path: impl Into<String>, headers: HeaderMap, body: Option<Value>) -> Self {
+        Self {
+            path: path.into(),
+            headers,
+            body,
+            body_modified: false,
+        }
+    }
+
+    /// JSON 请求体（非 JSON 请求为 None）
+    pub fn body(&self) -> Option<&Value> {
+        self.body.as_ref()
+    }
+
+    /// 可修改的 JSON 请求体，调用后请求体会被重新序列化
+    pub fn body_mut(&mut self) -> Option<&mut Value> {
+        self.body_modified = true;
+        self.body.as_mut()
+    }
+
+    /// 请求的模型（请求体的 `model` 字段，Gemini 路由取路径中的模型）
+    pub fn model(&self) -> Option<&str> {
+        if let Some(model) = self
+            .body
+            .as_ref()
+            .and_then(|b| b.get("model"))
+            .and_then(|m| m.as_str())
+        {
+            return Some(model);
+        }
+        super::client_keys::gemini_path_model(&self.path)
+    }
+
+    /// Anthropic 路由（`/v1/messages` 及其子路由，如 `/v1/messages/count_tokens`）
+    fn is_anthropic(&self) -> bool {
+        self.path.ends_with("/v1/messages") || self.path.contains("/v1/messages/")
+    }
+
+    fn is_gemini(&self) -> bool {
+        self.body
+            .as_ref()
+            .map_or(false, |b| b.get("contents").is_some())
+    }
+}
+
+/// 中间件处理结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum MiddlewareOutcome {
+    /// 继续执行后续中间件
+    Continue,
+    /// 拦截请求
+    Block { status: StatusCode, message: String },
+}
+
+/// 请求中间件
+pub trait RequestMiddleware: Send + Sync {
+    /// 中间件名称（用于日志和拦截提示）
+    fn name(&self) -> &str;
+
+    /// 是否作用于该请求
+    fn applies_to(&self, _request: &MiddlewareRequest) -> bool {
+        true
+    }
+
+    /// 处理请求
+    fn process(&self, request: &mut MiddlewareRequest) -> MiddlewareOutcome;
+}
+
+/// 设置请求头
+pub struct SetHeaderMiddleware {
+    name: String,
+    header: HeaderName,
+    value: HeaderValue,
+}
+
+impl RequestMiddleware for SetHeaderMiddleware {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn process(&self, request: &mut MiddlewareRequest) -> MiddlewareOutcome {
+        request
+            .headers
+            .insert(self.header.clone(), self.value.clone());
+        MiddlewareOutcome::Continue
+    }
+}
+
+/// 删除请求头
+pub struct RemoveHeaderMiddleware {
+    name: String,
+    header: HeaderName,
+}
+
+impl RequestMiddleware for RemoveHeaderMiddleware {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn process(&self, request: &mut MiddlewareRequest) -> MiddlewareOutcome {
+        request.headers.remove(&self.header);
+        MiddlewareOutcome::Continue
+    }
+}
+
+/// 注入系统提示词
+pub struct SystemPromptMiddleware {
+    name: String,
+    prompt: String,
+    position: PromptPosition,
+}
+
+impl SystemPromptMiddleware {
+    /// 合并到已有的文本系统提示词
+    fn merge(&self, existing: &str) -> String {
+        match self.position {
+            PromptPosition::Prepend => format!("{}\n\n{}", self.prompt, existing),
+            PromptPosition::Append => format!("{}\n\n{}", existing, self.prompt),
+        }
+    }
+
+    /// 插入到内容块数组中
+    fn insert_part(&self, parts: &mut Vec<Value>, part: Value) {
+        match self.position {
+            PromptPosition::Prepend => parts.insert(0, part),
+            PromptPosition::Append => parts.push(part),
+        }
+    }
+
+    fn inject_openai(&self, body: &mut Value) {
+        let Some(messages) = body.get_mut("messages").and_then(|m| m.as_array_mut()) else {
+            return;
+        };
+        let system = messages
+            .iter_mut()
+            .find(|m| m.get("role").and_then(|r| r.as_str()) == Some("system"));
+        match system.and_then(|m| m.get_mut("content")) {
+            Some(Value::String(content)) => *content = self.merge(content),
+            Some(Value::Array(parts)) => self.insert_part(
+                parts,
+                serde_json::json!({"type": "text", "text": self.prompt}),
+            ),
+            _ => messages.insert(
+                0,
+                serde_json::json!({"role": "system", "content": self.prompt}),
+            ),
+        }
+    }
+
+    fn inject_anthropic(&self, body: &mut Value) {
+        let Some(object) = body.as_object_mut() else {
+            return;
+        };
+        match object.get_mut("system") {
+            Some(Value::String(system)) => *system = self.merge(system),
+            Some(Value::Array(blocks)) => self.insert_part(
+                blocks,
+                serde_json::json!({"type": "text", "text": self.prompt}),
+            ),
+            _ => {
+                object.insert("system".to_string(), Value::String(self.prompt.clone()));
+            }
+        }
+    }
+
+    fn inject_gemini(&self, body: &mut Value) {
+        let Some(object) = body.as_object_mut() else {
+            return;
+        };
+        let key = if object.contains_key("system_instruction") {
+            "system_instruction"
+        } else {
+            "systemInstruction"
+        };
+        let instruction = object
+            .entry(key)
+            .or_insert_with(|| serde_json::json!({"parts": []}));
+        if let Some(parts) = instruction.get_mut("parts").and_then(|p| p.as_array_mut()) {
+            self.insert_part(parts, serde_json::json!({"text": self.prompt}));
+        }
+    }
+}
+
+impl RequestMiddleware for SystemPromptMiddleware {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn applies_to(&self, request: &MiddlewareRequest) -> bool {
+        request.body().map_or(false, |b| b.is_object())
+    }
+
+    fn process(&self, request: &mut MiddlewareRequest) -> MiddlewareOutcome {
+        let (anthropic, gemini) = (request.is_anthropic(), request.is_gemini());
+        if let Some(body) = request.body_mut() {
+            if gemini {
+                self.inject_gemini(body);
+            } else if anthropic {
+                self.inject_anthropic(body);
+            } else {
+                self.inject_openai(body);
+            }
+        }
+        MiddlewareOutcome::Continue
+    }
+}
+
+/// 删除请求体字段
+pub struct StripFieldsMiddleware {
+    name: String,
+    fields: Vec<String>,
+}
+
+impl RequestMiddleware for StripFieldsMiddleware {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn applies_to(&self, request: &MiddlewareRequest) -> bool {
+        request.body().is_some()
+    }
+
+    fn process(&self, request: &mut MiddlewareRequest) -> MiddlewareOutcome {
+        if let Some(body) = request.body_mut() {
+            for field in &self.fields {
+                let (parent, key) = match field.rsplit_once('.') {
+                    Some((parent, key)) => (
+                        parent
+                            .split('.')
+                            .try_fold(&mut *body, |value, segment| value.get_mut(segment)),
+                        key,
+                    ),
+                    None => (Some(&mut *body), field.as_str()),
+                };
+                if let Some(Value::Object(object)) = parent {
+                    object.remove(key);
+                }
+            }
+        }
+        MiddlewareOutcome::Continue
+    }
+}
+
+/// 拦截请求
+pub struct BlockMiddleware {
+    name: String,
+    status: StatusCode,
+    message: String,
+}
+
+impl RequestMiddleware for BlockMiddleware {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn process(&self, _request: &mut MiddlewareRequest) -> MiddlewareOutcome {
+        MiddlewareOutcome::Block {
+            status: self.status,
+            message: self.message.clone(),
+        }
+    }
+}
+
+/// 按配置的路由和模型过滤后执行内置中间件
+struct ConfiguredMiddleware {
+    config: RequestMiddlewareConfig,
+    inner: Box<dyn RequestMiddleware>,
+}
+
+impl RequestMiddleware for ConfiguredMiddleware {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn applies_to(&self, request: &MiddlewareRequest) -> bool {
+        self.config.applies_to(&request.path, request.model()) && self.inner.applies_to(request)
+    }
+
+    fn process(&self, request: &mut MiddlewareRequest) -> MiddlewareOutcome {
+        self.inner.process(request)
+    }
+}
+
+/// 根据配置构建内置中间件
+pub fn build_middleware(
+    config: &RequestMiddlewareConfig,
+) -> Result<Box<dyn RequestMiddleware>, String> {
+    let kind = match &config.action {
+        RequestMiddlewareAction::SetHeader { .. } => "set_header",
+        RequestMiddlewareAction::RemoveHeader { .. } => "remove_header",
+        RequestMiddlewareAction::InjectSystemPrompt { .. } => "inject_system_prompt",
+        RequestMiddlewareAction::StripFields { .. } => "strip_fields",
+        RequestMiddlewareAction::Block { .. } => "block",
+    };
+    let name = if config.name.is_empty() {
+        kind.to_string()
+    } else {
+        config.name.clone()
+    };
+    let header_name = |header: &str| {
+        HeaderName::from_bytes(header.as_bytes())
+            .map_err(|_| format!("中间件 {} 的请求头名称无效: {}", name, header))
+    };
+
+    let inner: Box<dyn RequestMiddleware> = match &config.action {
+        RequestMiddlewareAction::SetHeader { header, value } => Box::new(SetHeaderMiddleware {
+            header: header_name(header)?,
+            value: HeaderValue::from_str(value)
+                .map_err(|_| format!("中间件 {} 的请求头值无效", name))?,
+            name: name.clone(),
+        }),
+        RequestMiddlewareAction::RemoveHeader { header } => Box::new(RemoveHeaderMiddleware {
+            header: header_name(header)?,
+            name: name.clone(),
+        }),
+        RequestMiddlewareAction::InjectSystemPrompt { prompt, position } => {
+            Box::new(SystemPromptMiddleware {
+                name: name.clone(),
+                prompt: prompt.clone(),
+                position: *position,
+            })
+        }
+        RequestMiddlewareAction::StripFields { fields } => Box::new(StripFieldsMiddleware {
+            name: name.clone(),
+            fields: fields.clone(),
+        }),
+        RequestMiddlewareAction::Block { status, message } => Box::new(BlockMiddleware {
+            status: StatusCode::from_u16(*status)
+                .map_err(|_| format!("中间件 {} 的状态码无效: {}", name, status))?,
+            message: if message.is_empty() {
+                format!("Request blocked by middleware {}", name)
+            } else {
+                message.clone()
+            },
+            name: name.clone(),
+        }),
+    };
+
+    Ok(Box::new(ConfiguredMiddleware {
+        config: config.clone(),
+        inner,
+    }))
+}
+
+/// 请求中间件管道
+///
+/// 服务器运行期间与配置同步，修改配置后立即生效
+#[derive(Default)]
+pub struct RequestPipeline {
+    middlewares: RwLock<Vec<Box<dyn RequestMiddleware>>>,
+}
+
+impl RequestPipeline {
+    pub fn new(configs: &[RequestMiddlewareConfig]) -> Self {
+        let pipeline = Self::default();
+        pipeline.set_config(configs);
+        pipeline
+    }
+
+    /// 按配置重建管道（配置变更后调用），无效的配置项跳过并记录警告
+    pub fn set_config(&self, configs: &[RequestMiddlewareConfig]) {
+        let middlewares = configs
+            .iter()
+            .filter(|c| c.enabled)
+            .filter_map(|c| {
+                build_middleware(c)
+                    .map_err(|e| tracing::warn!("[MIDDLEWARE] 跳过无效的中间件配置: {}", e))
+                    .ok()
+            })
+            .collect();
+        *self.middlewares.write() = middlewares;
+    }
+
+    /// 在管道末尾注册自定义中间件（重新加载配置后需要重新注册）
+    pub fn push(&self, middleware: Box<dyn RequestMiddleware>) {
+        self.middlewares.write().push(middleware);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.middlewares.read().is_empty()
+    }
+
+    /// 依次执行中间件，遇到拦截时立即返回
+    pub fn run(&self, request: &mut MiddlewareRequest) -> MiddlewareOutcome {
+        for middleware in self.middlewares.read().iter() {
+            if !middleware.applies_to(request) {
+                continue;
+            }
+            if let MiddlewareOutcome::Block { status, message } = middleware.process(request) {
+                tracing::info!(
+                    "[MIDDLEWARE] {} 拦截请求: {} ({})",
+                    middleware.name(),
+                    request.path,
+                    status
+                );
+                return MiddlewareOutcome::Block { status, message };
+            }
+        }
+        MiddlewareOutcome::Continue
+    }
+}
+
+/// 请求中间件层
+#[derive(Clone)]
+pub struct RequestPipelineLayer {
+    pipeline: Arc<RequestPipeline>,
+}
+
+impl RequestPipelineLayer {
+    pub fn new(pipeline: Arc<RequestPipeline>) -> Self {
+        Self { pipeline }
+    }
+}
+
+impl<S> Layer<S> for RequestPipelineLayer {
+    type Service = RequestPipelineService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestPipelineService {
+            inner,
+            pipeline: self.pipeline.clone(),
+        }
+    }
+}
+
+/// 请求中间件服务
+#[derive(Clone)]
+pub struct RequestPipelineService<S> {
+    inner: S,
+    pipeline: Arc<RequestPipeline>,
+}
+
+impl<S> Service<Request<Body>> for RequestPipelineService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let pipeline = self.pipeline.clone();
+        let mut inner = self.inner.clone();
+
+        Box::pin(async move {
+            if pipeline.is_empty() {
+                return inner.call(req).await;
+            }
+
+            let (mut parts, body) = req.into_parts();
+            let bytes = match axum::body::to_bytes(body, MAX_BUFFERED_BODY).await {
+                Ok(bytes) => bytes,
+                Err(_) => {
+                    return Ok(Response::builder()
+                        .status(StatusCode::PAYLOAD_TOO_LARGE)
+                        .body(Body::from("Request body too large"))
+                        .unwrap())
+                }
+            };
+            let json = serde_json::from_slice::<Value>(&bytes).ok();
+            let mut request =
+                MiddlewareRequest::new(parts.uri.path(), std::mem::take(&mut parts.headers), json);
+
+            if let MiddlewareOutcome::Block { status, message } = pipeline.run(&mut request) {
+                return Ok(create_error_response(status, &message));
+            }
+
+            parts.headers = request.headers;
+            let body = match request.body {
+                Some(value) if request.body_modified => {
+                    let bytes = value.to_string();
+                    parts
+                        .headers
+                        .insert(CONTENT_LENGTH, HeaderValue::from(bytes.len()));
+                    Body::from(bytes)
+                }
+                _ => Body::from(bytes),
+            };
+            inner.call(Request::from_parts(parts, body)).await
+        })
+    }
+}
+
+/// 创建拦截响应（同时兼容 OpenAI 和 Anthropic 客户端的错误格式）
+fn create_error_response(status: StatusCode, message: &str) -> Response<Body> {
+    let body = serde_json::json!({
+        "type": "error",
+        "error": {
+            "type": "request_blocked",
+            "message": message
+        }
+    });
+
+    Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config(action: RequestMiddlewareAction) -> RequestMiddlewareConfig {
+        RequestMiddlewareConfig {
+            name: String::new(),
+            enabled: true,
+            paths: Vec::new(),
+            models: Vec::new(),
+            action,
+        }
+    }
+
+    fn request(path: &str, body: Value) -> MiddlewareRequest {
+        MiddlewareRequest::new(path, HeaderMap::new(), Some(body))
+    }
+
+    #[test]
+    fn test_headers_and_strip_fields() {
+        let pipeline = RequestPipeline::new(&[
+            config(RequestMiddlewareAction::SetHeader {
+                header: "x-team".to_string(),
+                value: "ml".to_string(),
+            }),
+            config(RequestMiddlewareAction::RemoveHeader {
+                header: "user-agent".to_string(),
+            }),
+            config(RequestMiddlewareAction::StripFields {
+                fields: vec!["user".to_string(), "metadata.trace".to_string()],
+            }),
+        ]);
+
+        let mut req = request(
+            "/v1/chat/completions",
+            json!({"model": "gpt-4o", "user": "u1", "metadata": {"trace": 1, "keep": 2}}),
+        );
+        req.headers
+            .insert("user-agent", HeaderValue::from_static("sdk"));
+        assert_eq!(pipeline.run(&mut req), MiddlewareOutcome::Continue);
+
+        assert_eq!(req.headers["x-team"], "ml");
+        assert!(req.headers.get("user-agent").is_none());
+        assert_eq!(
+            req.body().unwrap(),
+            &json!({"model": "gpt-4o", "metadata": {"keep": 2}})
+        );
+        assert!(req.body_modified);
+    }
+
+    #[test]
+    fn test_inject_system_prompt() {
+        let pipeline =
+            RequestPipeline::new(&[config(RequestMiddlewareAction::InjectSystemPrompt {
+                prompt: "Be brief.".to_string(),
+                position: PromptPosition::Prepend,
+            })]);
+
+        let mut openai = request(
+            "/v1/chat/completions",
+            json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "hi"}]}),
+        );
+        pipeline.run(&mut openai);
+        assert_eq!(
+            openai.body().unwrap()["messages"][0],
+            json!({"role": "system", "content": "Be brief."})
+        );
+
+        let mut anthropic = request(
+            "/v1/messages",
+            json!({"model": "claude", "system": "You are helpful.", "messages": []}),
+        );
+        pipeline.run(&mut anthropic);
+        assert_eq!(
+            anthropic.body().unwrap()["system"],
+            "Be brief.\n\nYou are helpful."
+        );
+
+        // count_tokens 使用 Anthropic 格式，提示词注入到 system 字段
+        let mut count_tokens = request(
+            "/v1/messages/count_tokens",
+            json!({"model": "claude", "messages": [{"role": "user", "content": "hi"}]}),
+        );
+        pipeline.run(&mut count_tokens);
+        assert_eq!(count_tokens.body().unwrap()["system"], "Be brief.");
+        assert_eq!(count_tokens.body().unwrap()["messages"][0]["role"], "user");
+
+        let mut gemini = request(
+            "/v1/gemini/gemini-2.5-pro:generateContent",
+            json!({"contents": []}),
+        );
+        assert_eq!(gemini.model(), Some("gemini-2.5-pro"));
+        pipeline.run(&mut gemini);
+        assert_eq!(
+            gemini.body().unwrap()["systemInstruction"]["parts"][0]["text"],
+            "Be brief."
+        );
+    }
+
+    #[test]
+    fn test_block_with_filters() {
+        let mut block = config(RequestMiddlewareAction::Block {
+            status: 403,
+            message: "o1 is not allowed".to_string(),
+        });
+        block.models = vec!["o1-*".to_string()];
+        let mut strip = config(RequestMiddlewareAction::StripFields {
+            fields: vec!["user".to_string()],
+        });
+        strip.paths = vec!["/v1/messages".to_string()];
+        let pipeline = RequestPipeline::new(&[block, strip]);
+
+        let mut req = request(
+            "/v1/chat/completions",
+            json!({"model": "o1-mini", "user": "u"}),
+        );
+        assert_eq!(
+            pipeline.run(&mut req),
+            MiddlewareOutcome::Block {
+                status: StatusCode::FORBIDDEN,
+                message: "o1 is not allowed".to_string()
+            }
+        );
+
+        // 路由不匹配的中间件不执行
+        let mut req = request(
+            "/v1/chat/completions",
+            json!({"model": "gpt-4o", "user": "u"}),
+        );
+        assert_eq!(pipeline.run(&mut req), MiddlewareOutcome::Continue);
+        assert!(req.body().unwrap().get("user").is_some());
+        assert!(!req.body_modified);
+    }
+}
@@ -0,0 +1,378 @@
+//! HMAC 请求签名中间件
+//!
+//! 服务器对局域网开放时，可要求客户端用共享密钥对请求签名：
+//! - 请求头 `x-proxycast-timestamp` 为 Unix 时间戳（秒），`x-proxycast-signature` 为
+//!   HMAC-SHA256(secret, "{timestamp}\n{METHOD}\n{path?query}\n{hex(sha256(body))}") 的十六进制
+//! - 时间戳与服务器时间相差超过 `max_skew_secs` 时拒绝，允许窗口内同一签名只能使用一次
+//! - 缺少签名请求头或时间戳超出窗口时直接拒绝，不读取请求体
+//! - 本机（回环地址）发起的请求默认无需签名，内置 Agent 等本机客户端不受影响
+//! - 健康检查接口无需签名
+//!
+//! 签名校验与 API Key 认证相互独立，客户端仍需携带 API Key。
+
+use crate::config::RequestSigningConfig;
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{HeaderMap, Request, Response, StatusCode},
+};
+use futures::future::BoxFuture;
+use hmac::{Hmac, Mac};
+use parking_lot::{Mutex, RwLock};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    task::{Context, Poll},
+};
+use subtle::ConstantTimeEq;
+use tower::{Layer, Service};
+
+/// 请求时间戳（Unix 秒）请求头
+pub const TIMESTAMP_HEADER: &str = "x-proxycast-timestamp";
+/// 请求签名请求头
+pub const SIGNATURE_HEADER: &str = "x-proxycast-signature";
+/// 无需签名的路由
+const UNSIGNED_PATHS: &[&str] = &["/health", "/healthz"];
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 计算请求签名（十六进制）
+pub fn sign_request(
+    secret: &str,
+    timestamp: i64,
+    method: &str,
+    path_and_query: &str,
+    body: &[u8],
+) -> String {
+    let payload = format!(
+        "{}\n{}\n{}\n{:x}",
+        timestamp,
+        method.to_ascii_uppercase(),
+        path_and_query,
+        Sha256::digest(body)
+    );
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC 可以接受任意长度的密钥");
+    mac.update(payload.as_bytes());
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+/// 请求签名校验器
+///
+/// 服务器运行期间与配置同步，修改密钥后立即生效
+#[derive(Debug, Default)]
+pub struct RequestSigner {
+    config: RwLock<RequestSigningConfig>,
+    /// 允许窗口内已使用的签名及其时间戳，用于拒绝重放
+    seen: Mutex<HashMap<String, i64>>,
+}
+
+impl RequestSigner {
+    pub fn new(config: RequestSigningConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 更新签名配置（配置变更后调用）
+    pub fn set_config(&self, config: RequestSigningConfig) {
+        *self.config.write() = config;
+    }
+
+    /// 该客户端的请求是否需要签名（无法获取连接地址时需要）
+    pub fn requires_signature(&self, path: &str, client_ip: Option<IpAddr>) -> bool {
+        let config = self.config.read();
+        if !config.enabled || UNSIGNED_PATHS.contains(&path) {
+            return false;
+        }
+        let loopback = client_ip.is_some_and(|ip| match ip {
+            IpAddr::V6(v6) => v6
+                .to_ipv4_mapped()
+                .map_or(v6.is_loopback(), |v4| v4.is_loopback()),
+            IpAddr::V4(v4) => v4.is_loopback(),
+        });
+        !loopback || config.require_for_loopback
+    }
+
+    /// 检查签名请求头是否齐全、时间戳是否在允许窗口内（读取请求体之前调用），
+    /// 返回时间戳和签名
+    pub fn check_headers(&self, headers: &HeaderMap, now: i64) -> Result<(i64, String), String> {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        let (Some(timestamp), Some(signature)) =
+            (header(TIMESTAMP_HEADER), header(SIGNATURE_HEADER))
+        else {
+            return Err("Missing request signature".to_string());
+        };
+        let timestamp: i64 = timestamp
+            .trim()
+            .parse()
+            .map_err(|_| "Invalid request timestamp".to_string())?;
+        if now.abs_diff(timestamp) > self.config.read().max_skew_secs {
+            return Err("Request timestamp is outside the allowed window".to_string());
+        }
+        Ok((timestamp, signature.trim().to_ascii_lowercase()))
+    }
+
+    /// 校验请求签名，`now` 为当前 Unix 时间戳（秒）
+    pub fn verify(
+        &self,
+        method: &str,
+        path_and_query: &str,
+        headers: &HeaderMap,
+        body: &[u8],
+        now: i64,
+    ) -> Result<(), String> {
+        let (timestamp, signature) = self.check_headers(headers, now)?;
+        let config = self.config.read().clone();
+        let expected = sign_request(&config.secret, timestamp, method, path_and_query, body);
+        if config.secret.is_empty() || !bool::from(expected.as_bytes().ct_eq(signature.as_bytes()))
+        {
+            return Err("Invalid request signature".to_string());
+        }
+
+        let mut seen = self.seen.lock();
+        seen.retain(|_, ts| now.abs_diff(*ts) <= config.max_skew_secs);
+        if seen.insert(signature, timestamp).is_some() {
+            return Err("Request signature has already been used".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// 请求签名校验层
+#[derive(Clone)]
+pub struct RequestSigningLayer {
+    signer: Arc<RequestSigner>,
+    /// 计算签名时缓冲请求体的大小上限（与服务器请求体限制一致）
+    body_limit: usize,
+}
+
+impl RequestSigningLayer {
+    pub fn new(signer: Arc<RequestSigner>, body_limit: usize) -> Self {
+        Self { signer, body_limit }
+    }
+}
+
+impl<S> Layer<S> for RequestSigningLayer {
+    type Service = RequestSigningService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestSigningService {
+            inner,
+            signer: self.signer.clone(),
+            body_limit: self.body_limit,
+        }
+    }
+}
+
+/// 请求签名校验服务
+#[derive(Clone)]
+pub struct RequestSigningService<S> {
+    inner: S,
+    signer: Arc<RequestSigner>,
+    body_limit: usize,
+}
+
+impl<S> Service<Request<Body>> for RequestSigningService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let client_ip = req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|info| info.0.ip());
+        let signer = self.signer.clone();
+        let body_limit = self.body_limit;
+        let mut inner = self.inner.clone();
+
+        Box::pin(async move {
+            if !signer.requires_signature(req.uri().path(), client_ip) {
+                return inner.call(req).await;
+            }
+
+            // 缺少签名或时间戳过期的请求不读取请求体，直接拒绝
+            let now = chrono::Utc::now().timestamp();
+            let reject = |parts: &axum::http::request::Parts, message: String| {
+                tracing::warn!(
+                    "[REQUEST_SIGNING] {} {} 签名校验失败（客户端 {}）: {}",
+                    parts.method,
+                    parts.uri.path(),
+                    client_ip.map_or_else(|| "unknown".to_string(), |ip| ip.to_string()),
+                    message
+                );
+                create_error_response(StatusCode::UNAUTHORIZED, &message)
+            };
+            let (parts, body) = req.into_parts();
+            if let Err(message) = signer.check_headers(&parts.headers, now) {
+                return Ok(reject(&parts, message));
+            }
+
+            let bytes = match axum::body::to_bytes(body, body_limit).await {
+                Ok(bytes) => bytes,
+                Err(_) => {
+                    return Ok(create_error_response(
+                        StatusCode::PAYLOAD_TOO_LARGE,
+                        "Request body too large",
+                    ))
+                }
+            };
+            let path_and_query = parts
+                .uri
+                .path_and_query()
+                .map_or_else(|| parts.uri.path(), |pq| pq.as_str());
+            if let Err(message) = signer.verify(
+                parts.method.as_str(),
+                path_and_query,
+                &parts.headers,
+                &bytes,
+                now,
+            ) {
+                return Ok(reject(&parts, message));
+            }
+
+            inner
+                .call(Request::from_parts(parts, Body::from(bytes)))
+                .await
+        })
+    }
+}
+
+/// 创建错误响应（同时兼容 OpenAI 和 Anthropic 客户端的错误格式）
+fn create_error_response(status: StatusCode, message: &str) -> Response<Body> {
+    let error_type = match status {
+        StatusCode::UNAUTHORIZED => "authentication_error",
+        _ => "invalid_request_error",
+    };
+    let body = serde_json::json!({
+        "type": "error",
+        "error": {
+            "type": error_type,
+            "message": message
+        }
+    });
+
+    Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn signer() -> RequestSigner {
+        RequestSigner::new(RequestSigningConfig {
+            enabled: true,
+            secret: "shared-secret".to_string(),
+            ..RequestSigningConfig::default()
+        })
+    }
+
+    fn signed_headers(timestamp: i64, signature: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            TIMESTAMP_HEADER,
+            HeaderValue::from_str(&timestamp.to_string()).unwrap(),
+        );
+        headers.insert(SIGNATURE_HEADER, HeaderValue::from_str(signature).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_verify() {
+        let signer = signer();
+        let body = br#"{"model":"gpt-4o"}"#;
+        let signature = sign_request("shared-secret", NOW, "post", "/v1/chat/completions", body);
+        let headers = signed_headers(NOW, &signature);
+
+        // 请求体、路径或密钥不一致时签名无效
+        assert!(signer
+            .verify("POST", "/v1/chat/completions", &headers, b"{}", NOW)
+            .is_err());
+        assert!(signer
+            .verify("POST", "/v1/messages", &headers, body, NOW)
+            .is_err());
+        assert!(signer
+            .verify("POST", "/v1/chat/completions", &HeaderMap::new(), body, NOW)
+            .is_err());
+
+        assert!(signer
+            .verify("POST", "/v1/chat/completions", &headers, body, NOW + 60)
+            .is_ok());
+        // 同一签名不能重复使用
+        assert!(signer
+            .verify("POST", "/v1/chat/completions", &headers, body, NOW + 60)
+            .unwrap_err()
+            .contains("already been used"));
+
+        // 超出时间窗口（读取请求体之前即可判断）
+        assert!(signer
+            .check_headers(&signed_headers(NOW + 600, "sig"), NOW)
+            .unwrap_err()
+            .contains("window"));
+        assert!(signer
+            .check_headers(&HeaderMap::new(), NOW)
+            .unwrap_err()
+            .contains("Missing"));
+        let stale = sign_request("shared-secret", NOW - 600, "GET", "/v1/models", b"");
+        assert!(signer
+            .verify(
+                "GET",
+                "/v1/models",
+                &signed_headers(NOW - 600, &stale),
+                b"",
+                NOW
+            )
+            .unwrap_err()
+            .contains("window"));
+
+        let forged = sign_request("other-secret", NOW, "GET", "/v1/models", b"");
+        assert!(signer
+            .verify("GET", "/v1/models", &signed_headers(NOW, &forged), b"", NOW)
+            .is_err());
+    }
+
+    #[test]
+    fn test_requires_signature() {
+        let signer = signer();
+        let lan: IpAddr = "192.168.1.42".parse().unwrap();
+        assert!(signer.requires_signature("/v1/messages", Some(lan)));
+        assert!(signer.requires_signature("/v1/messages", None));
+        assert!(!signer.requires_signature("/health", Some(lan)));
+
+        // 本机请求默认无需签名，IPv4 映射地址按 IPv4 判断
+        assert!(!signer.requires_signature("/v1/messages", Some("127.0.0.1".parse().unwrap())));
+        assert!(
+            !signer.requires_signature("/v1/messages", Some("::ffff:127.0.0.1".parse().unwrap()))
+        );
+
+        signer.set_config(RequestSigningConfig {
+            enabled: true,
+            secret: "shared-secret".to_string(),
+            require_for_loopback: true,
+            ..RequestSigningConfig::default()
+        });
+        assert!(signer.requires_signature("/v1/messages", Some("::1".parse().unwrap())));
+
+        signer.set_config(RequestSigningConfig::default());
+        assert!(!signer.requires_signature("/v1/messages", Some(lan)));
+    }
+}
@@ -0,0 +1,597 @@
+//! 响应缓存中间件
+//!
+//! 对完全相同的非流式 chat completions / messages 请求（同一路由、模型、消息和参数）
+//! 在 TTL 内直接返回缓存的响应，不再请求上游：
+//! - 缓存键为路由和请求体（去掉 `stream` 字段后规范化）的 SHA-256
+//! - 只缓存 2xx 的 JSON 响应，流式请求始终透传，超过 8MB 的响应透传但不缓存
+//! - 只对通过主 API Key 认证的请求生效（客户端 Key 已由前一层替换为主 Key），
+//!   未认证的请求不会命中缓存
+//! - 响应带 `x-proxycast-cache: hit|miss` 头
+//! - 命中的请求不经过处理器，由 [`CacheHitSink`] 记录遥测和用量
+
+use super::client_keys::{client_key_id, extract_model, SharedApiKey};
+use super::rate_limit::extract_api_key;
+use crate::config::ResponseCacheConfig;
+use crate::processor::RequestContext;
+use crate::services::usage_recorder::UsageSource;
+use axum::{
+    body::{Body, Bytes},
+    extract::ConnectInfo,
+    http::{HeaderValue, Method, Request, Response, StatusCode},
+};
+use futures::future::BoxFuture;
+use futures::StreamExt;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tower::{Layer, Service};
+
+/// 标记缓存命中情况的响应头
+pub const CACHE_STATUS_HEADER: &str = "x-proxycast-cache";
+/// 可缓存的路由后缀
+const CACHEABLE_PATHS: [&str; 2] = ["/v1/chat/completions", "/v1/messages"];
+/// 缓冲请求体的大小上限（与服务器请求体限制一致）
+const MAX_BUFFERED_BODY: usize = 100 * 1024 * 1024;
+/// 单个响应的缓存大小上限，超过时不缓存
+const MAX_CACHED_RESPONSE: usize = 8 * 1024 * 1024;
+
+/// 缓存统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResponseCacheStats {
+    pub enabled: bool,
+    /// 当前缓存条目数（含尚未清理的过期条目）
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// 命中率（0-1）
+    pub hit_rate: f64,
+    pub ttl_secs: u64,
+    pub max_entries: usize,
+}
+
+#[derive(Debug, Clone)]
+struct CachedResponse {
+    status: StatusCode,
+    content_type: Option<HeaderValue>,
+    body: Bytes,
+    /// 写入缓存的那次请求的上下文（处理器放在响应扩展中，未提供时为空）
+    context: Option<RequestContext>,
+    inserted_at: Instant,
+}
+
+/// 一次缓存命中
+#[derive(Debug, Clone)]
+pub struct CacheHit {
+    /// 写入缓存的那次请求的上下文（Provider、解析后的模型等，未知时为空）
+    pub context: Option<RequestContext>,
+    pub method: String,
+    pub path: String,
+    /// 请求中的模型
+    pub model: String,
+    pub client_key: Option<String>,
+    pub source: Option<UsageSource>,
+    /// 缓存的响应体
+    pub body: Bytes,
+    pub started: Instant,
+}
+
+/// 缓存命中的记录方（由服务器注入，记录命中请求的遥测和用量）
+pub trait CacheHitSink: Send + Sync {
+    fn on_hit(&self, hit: CacheHit);
+}
+
+/// 响应缓存（进程内，重启后清空）
+///
+/// 服务器运行期间与配置同步，修改配置后立即生效
+#[derive(Debug, Default)]
+pub struct ResponseCache {
+    config: RwLock<ResponseCacheConfig>,
+    entries: RwLock<HashMap<String, CachedResponse>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl ResponseCache {
+    pub fn new(config: ResponseCacheConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            ..Default::default()
+        }
+    }
+
+    /// 更新配置（配置变更后调用），禁用时清空缓存
+    pub fn set_config(&self, config: ResponseCacheConfig) {
+        if !config.enabled {
+            self.entries.write().clear();
+        }
+        *self.config.write() = config;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.read().enabled
+    }
+
+    /// 获取缓存统计
+    pub fn stats(&self) -> ResponseCacheStats {
+        let config = self.config.read().clone();
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let total = hits + misses;
+        ResponseCacheStats {
+            enabled: config.enabled,
+            entries: self.entries.read().len(),
+            hits,
+            misses,
+            hit_rate: if total > 0 {
+                hits as f64 / total as f64
+            } else {
+                0.0
+            },
+            ttl_secs: config.ttl_secs,
+            max_entries: config.max_entries,
+        }
+    }
+
+    /// 清空缓存和统计，返回清除的条目数
+    pub fn clear(&self) -> usize {
+        let cleared = {
+            let mut entries = self.entries.write();
+            let count = entries.len();
+            entries.clear();
+            count
+        };
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+        cleared
+    }
+
+    fn ttl(&self) -> Duration {
+        Duration::from_secs(self.config.read().ttl_secs)
+    }
+
+    /// 查找未过期的缓存响应，并记录命中/未命中
+    fn get(&self, key: &str) -> Option<CachedResponse> {
+        let ttl = self.ttl();
+        let cached = self
+            .entries
+            .read()
+            .get(key)
+            .filter(|entry| entry.inserted_at.elapsed() < ttl)
+            .cloned();
+        let counter = if cached.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        cached
+    }
+
+    fn insert(&self, key: String, response: CachedResponse) {
+        let (ttl, max_entries) = {
+            let config = self.config.read();
+            (Duration::from_secs(config.ttl_secs), config.max_entries)
+        };
+        if max_entries == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.write();
+        entries.retain(|_, entry| entry.inserted_at.elapsed() < ttl);
+        while entries.len() >= max_entries {
+            let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.inserted_at)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            entries.remove(&oldest);
+        }
+        entries.insert(key, response);
+    }
+}
+
+/// 计算缓存键，流式请求和无法解析的请求体返回 None
+fn cache_key(path: &str, body: &[u8]) -> Option<String> {
+    let mut value: serde_json::Value = serde_json::from_slice(body).ok()?;
+    let object = value.as_object_mut()?;
+    if object
+        .get("stream")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+    {
+        return None;
+    }
+    object.remove("stream");
+
+    let mut hasher = Sha256::new();
+    hasher.update(path.as_bytes());
+    hasher.update(b"\n");
+    // serde_json 的 Map 按键排序，字段顺序不同的相同请求得到相同的键
+    hasher.update(value.to_string().as_bytes());
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+fn is_cacheable_path(path: &str) -> bool {
+    CACHEABLE_PATHS.iter().any(|suffix| path.ends_with(suffix))
+}
+
+/// 响应缓存层
+#[derive(Clone)]
+pub struct ResponseCacheLayer {
+    master_key: SharedApiKey,
+    cache: Arc<ResponseCache>,
+    hit_sink: Option<Arc<dyn CacheHitSink>>,
+}
+
+impl ResponseCacheLayer {
+    /// 创建新的响应缓存层
+    pub fn new(master_key: SharedApiKey, cache: Arc<ResponseCache>) -> Self {
+        Self {
+            master_key,
+            cache,
+            hit_sink: None,
+        }
+    }
+
+    /// 缓存命中时通知 `sink`
+    pub fn with_hit_sink(mut self, sink: Arc<dyn CacheHitSink>) -> Self {
+        self.hit_sink = Some(sink);
+        self
+    }
+}
+
+impl<S> Layer<S> for ResponseCacheLayer {
+    type Service = ResponseCacheService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ResponseCacheService {
+            inner,
+            master_key: self.master_key.clone(),
+            cache: self.cache.clone(),
+            hit_sink: self.hit_sink.clone(),
+        }
+    }
+}
+
+/// 响应缓存服务
+#[derive(Clone)]
+pub struct ResponseCacheService<S> {
+    inner: S,
+    master_key: SharedApiKey,
+    cache: Arc<ResponseCache>,
+    hit_sink: Option<Arc<dyn CacheHitSink>>,
+}
+
+impl<S> Service<Request<Body>> for ResponseCacheService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let master_key = self.master_key.read().clone();
+        let cache = self.cache.clone();
+        let hit_sink = self.hit_sink.clone();
+        let mut inner = self.inner.clone();
+
+        Box::pin(async move {
+            let started = Instant::now();
+            let path = req.uri().path().to_string();
+            if !cache.is_enabled()
+                || req.method() != Method::POST
+                || !is_cacheable_path(&path)
+                || extract_api_key(req.headers()).as_deref() != Some(master_key.as_str())
+            {
+                return inner.call(req).await;
+            }
+
+            let (parts, body) = req.into_parts();
+            let bytes = match axum::body::to_bytes(body, MAX_BUFFERED_BODY).await {
+                Ok(bytes) => bytes,
+                Err(_) => {
+                    return Ok(Response::builder()
+                        .status(StatusCode::PAYLOAD_TOO_LARGE)
+                        .body(Body::from("Request body too large"))
+                        .unwrap())
+                }
+            };
+            let key = cache_key(&path, &bytes);
+            let Some(key) = key else {
+                return inner
+                    .call(Request::from_parts(parts, Body::from(bytes)))
+                    .await;
+            };
+
+            if let Some(cached) = cache.get(&key) {
+                tracing::debug!("[RESPONSE_CACHE] 命中缓存: {}", path);
+                if let Some(sink) = &hit_sink {
+                    sink.on_hit(CacheHit {
+                        context: cached.context,
+                        method: parts.method.to_string(),
+                        path,
+                        model: extract_model(&bytes).unwrap_or_default(),
+                        client_key: client_key_id(&parts.headers),
+                        source: UsageSource::from_request(
+                            &parts.headers,
+                            parts
+                                .extensions
+                                .get::<ConnectInfo<SocketAddr>>()
+                                .map(|info| info.0),
+                        ),
+                        body: cached.body.clone(),
+                        started,
+                    });
+                }
+                let mut builder = Response::builder()
+                    .status(cached.status)
+                    .header(CACHE_STATUS_HEADER, "hit");
+                if let Some(content_type) = cached.content_type {
+                    builder = builder.header("content-type", content_type);
+                }
+                return Ok(builder.body(Body::from(cached.body)).unwrap());
+            }
+
+            let req = Request::from_parts(parts, Body::from(bytes));
+
+            let response = inner.call(req).await?;
+            let is_json = response
+                .headers()
+                .get("content-type")
+                .and_then(|v| v.to_str().ok())
+                .map_or(false, |v| v.starts_with("application/json"));
+            if !response.status().is_success() || !is_json {
+                return Ok(response);
+            }
+
+            let too_large = response
+                .headers()
+                .get("content-length")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<usize>().ok())
+                .is_some_and(|len| len > MAX_CACHED_RESPONSE);
+            if too_large {
+                return Ok(response);
+            }
+
+            let (mut parts, body) = response.into_parts();
+            let mut stream = body.into_data_stream();
+            let mut buffered = Vec::new();
+            while let Some(chunk) = stream.next().await {
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        tracing::warn!("[RESPONSE_CACHE] 读取响应失败: {}", e);
+                        return Ok(Response::builder()
+                            .status(StatusCode::BAD_GATEWAY)
+                            .body(Body::from("Failed to read upstream response"))
+                            .unwrap());
+                    }
+                };
+                if buffered.len() + chunk.len() > MAX_CACHED_RESPONSE {
+                    // 超过缓存上限：不缓存，已读取的部分和剩余数据一起透传
+                    tracing::debug!("[RESPONSE_CACHE] 响应超过缓存上限，不缓存: {}", path);
+                    let head = futures::stream::iter([Ok(Bytes::from(buffered)), Ok(chunk)]);
+                    return Ok(Response::from_parts(
+                        parts,
+                        Body::from_stream(head.chain(stream)),
+                    ));
+                }
+                buffered.extend_from_slice(&chunk);
+            }
+            let bytes = Bytes::from(buffered);
+            cache.insert(
+                key,
+                CachedResponse {
+                    status: parts.status,
+                    content_type: parts.headers.get("content-type").cloned(),
+                    body: bytes.clone(),
+                    context: parts.extensions.get::<RequestContext>().cloned(),
+                    inserted_at: Instant::now(),
+                },
+            );
+            parts
+                .headers
+                .insert(CACHE_STATUS_HEADER, HeaderValue::from_static("miss"));
+            Ok(Response::from_parts(parts, Body::from(bytes)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+
+    /// 返回固定 JSON 响应的上游（`padding` 控制响应大小）
+    #[derive(Clone)]
+    struct JsonUpstream {
+        padding: usize,
+        calls: Arc<AtomicU64>,
+    }
+
+    impl Service<Request<Body>> for JsonUpstream {
+        type Response = Response<Body>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _req: Request<Body>) -> Self::Future {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            let body = format!(
+                r#"{{"usage":{{"prompt_tokens":3,"completion_tokens":4}},"padding":"{}"}}"#,
+                "x".repeat(self.padding)
+            );
+            Box::pin(async move {
+                Ok(Response::builder()
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap())
+            })
+        }
+    }
+
+    #[derive(Default)]
+    struct HitCounter(Mutex<Vec<String>>);
+
+    impl CacheHitSink for HitCounter {
+        fn on_hit(&self, hit: CacheHit) {
+            self.0.lock().push(hit.model);
+        }
+    }
+
+    fn chat_request() -> Request<Body> {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/v1/chat/completions")
+            .header("authorization", "Bearer master")
+            .body(Body::from(r#"{"model":"gpt-4o","messages":[]}"#))
+            .unwrap()
+    }
+
+    async fn send(service: &mut ResponseCacheService<JsonUpstream>) -> (Option<String>, usize) {
+        let response = service.call(chat_request()).await.unwrap();
+        let status = response
+            .headers()
+            .get(CACHE_STATUS_HEADER)
+            .map(|v| v.to_str().unwrap().to_string());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, body.len())
+    }
+
+    #[tokio::test]
+    async fn test_hits_are_reported_and_large_responses_pass_through() {
+        let cache = Arc::new(ResponseCache::new(ResponseCacheConfig {
+            enabled: true,
+            ttl_secs: 60,
+            max_entries: 10,
+        }));
+        let master_key: SharedApiKey = Arc::new(RwLock::new("master".to_string()));
+        let hits = Arc::new(HitCounter::default());
+        let layer = ResponseCacheLayer::new(master_key, cache.clone()).with_hit_sink(hits.clone());
+
+        let small = JsonUpstream {
+            padding: 10,
+            calls: Arc::new(AtomicU64::new(0)),
+        };
+        let mut service = layer.layer(small.clone());
+        let (status, len) = send(&mut service).await;
+        assert_eq!(status.as_deref(), Some("miss"));
+        assert_eq!(send(&mut service).await, (Some("hit".to_string()), len));
+        assert_eq!(small.calls.load(Ordering::Relaxed), 1);
+        assert_eq!(*hits.0.lock(), vec!["gpt-4o".to_string()]);
+
+        // 超过缓存上限的响应完整透传，不缓存
+        cache.clear();
+        let large = JsonUpstream {
+            padding: MAX_CACHED_RESPONSE,
+            calls: Arc::new(AtomicU64::new(0)),
+        };
+        let mut service = layer.layer(large.clone());
+        let (status, len) = send(&mut service).await;
+        assert_eq!(status, None);
+        assert!(len > MAX_CACHED_RESPONSE);
+        assert_eq!(send(&mut service).await, (None, len));
+        assert_eq!(large.calls.load(Ordering::Relaxed), 2);
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    fn cached(body: &'static str) -> CachedResponse {
+        CachedResponse {
+            status: StatusCode::OK,
+            content_type: None,
+            body: Bytes::from_static(body.as_bytes()),
+            context: None,
+            inserted_at: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_cache_key() {
+        let path = "/v1/chat/completions";
+        let a = cache_key(
+            path,
+            br#"{"model":"gpt-4o","messages":[{"role":"user","content":"hi"}],"temperature":0}"#,
+        );
+        let b = cache_key(
+            path,
+            br#"{"temperature":0,"stream":false,"messages":[{"role":"user","content":"hi"}],"model":"gpt-4o"}"#,
+        );
+        assert!(a.is_some());
+        assert_eq!(a, b);
+
+        // 参数、模型或路由不同时键不同
+        let c = cache_key(
+            path,
+            br#"{"model":"gpt-4o","messages":[{"role":"user","content":"hi"}],"temperature":1}"#,
+        );
+        assert_ne!(a, c);
+        let d = cache_key(
+            "/v1/messages",
+            br#"{"model":"gpt-4o","messages":[{"role":"user","content":"hi"}],"temperature":0}"#,
+        );
+        assert_ne!(a, d);
+
+        // 流式请求和非 JSON 请求不缓存
+        assert!(cache_key(path, br#"{"model":"gpt-4o","stream":true}"#).is_none());
+        assert!(cache_key(path, b"not json").is_none());
+    }
+
+    #[test]
+    fn test_cache_ttl_and_capacity() {
+        let cache = ResponseCache::new(ResponseCacheConfig {
+            enabled: true,
+            ttl_secs: 60,
+            max_entries: 2,
+        });
+
+        assert!(cache.get("a").is_none());
+        cache.insert("a".to_string(), cached("a"));
+        cache.insert("b".to_string(), cached("b"));
+        cache.insert("c".to_string(), cached("c"));
+        assert_eq!(cache.stats().entries, 2);
+        assert!(cache.get("a").is_none());
+        assert_eq!(cache.get("c").unwrap().body, Bytes::from_static(b"c"));
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 2);
+
+        // TTL 为 0 时所有条目立即过期
+        cache.set_config(ResponseCacheConfig {
+            enabled: true,
+            ttl_secs: 0,
+            max_entries: 2,
+        });
+        assert!(cache.get("c").is_none());
+
+        assert_eq!(cache.clear(), 2);
+        assert_eq!(cache.stats().hits, 0);
+
+        cache.set_config(ResponseCacheConfig::default());
+        assert!(!cache.is_enabled());
+    }
+}
@@ -36,6 +36,22 @@ pub struct ImageSource {
     pub data: String,
 }
 
+/// 构建文档块（base64 编码的 PDF 等），title 为文件名
+pub fn document_block(media_type: &str, data: &str, title: Option<&str>) -> serde_json::Value {
+    let mut block = serde_json::json!({
+        "type": "document",
+        "source": {
+            "type": "base64",
+            "media_type": media_type,
+            "data": data
+        }
+    });
+    if let Some(title) = title {
+        block["title"] = serde_json::json!(title);
+    }
+    block
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnthropicMessage {
     pub role: String,
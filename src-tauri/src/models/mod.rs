@@ -23,4 +23,6 @@ pub use prompt_model::Prompt;
 pub use provider_model::Provider;
 #[allow(unused_imports)]
 pub use provider_pool_model::*;
-pub use skill_model::{Skill, SkillMetadata, SkillRepo, SkillState, SkillStates};
+pub use skill_model::{
+    Skill, SkillMetadata, SkillRepo, SkillState, SkillStates, SkillUpdateInfo, SkillUsageStats,
+};
@@ -26,6 +26,34 @@ pub enum ContentPart {
     Text { text: String },
     #[serde(rename = "image_url")]
     ImageUrl { image_url: ImageUrl },
+    #[serde(rename = "input_audio")]
+    InputAudio { input_audio: InputAudio },
+    #[serde(rename = "file")]
+    File { file: FileData },
+}
+
+/// 音频输入（base64 编码的 wav / mp3）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputAudio {
+    pub data: String,
+    pub format: String,
+}
+
+/// 文件输入（如 PDF），file_data 为 `data:<media_type>;base64,...`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileData {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    pub file_data: String,
+}
+
+impl FileData {
+    /// 解析 data URL，返回 (media_type, base64 数据)
+    pub fn parse_data_url(&self) -> Option<(&str, &str)> {
+        let (header, data) = self.file_data.strip_prefix("data:")?.split_once(',')?;
+        let media_type = header.strip_suffix(";base64")?;
+        Some((media_type, data))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -87,6 +115,9 @@ pub struct FunctionDef {
     pub description: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parameters: Option<serde_json::Value>,
+    /// 严格模式（OpenAI Structured Outputs），要求参数完全符合 schema
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
 }
 
 /// 工具定义
@@ -128,6 +159,17 @@ pub struct ChatCompletionRequest {
     /// 思维链强度：none, low, medium, high
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reasoning_effort: Option<String>,
+    /// 流式选项
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<StreamOptions>,
+}
+
+/// 流式选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamOptions {
+    /// 在最后一个数据块中返回用量
+    #[serde(default)]
+    pub include_usage: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -400,6 +400,34 @@ pub struct HealthCheckResult {
     pub duration_ms: u64,
 }
 
+/// 凭证冷却信息（上游返回 429 或 401/403 后暂停调度）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialCooldown {
+    /// 触发冷却的 HTTP 状态码
+    pub status: u16,
+    /// 冷却结束时间
+    pub until: DateTime<Utc>,
+    /// 触发冷却的错误信息
+    pub reason: String,
+}
+
+/// 凭证健康状态（用于前端查看 Key 池状态）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialHealth {
+    pub uuid: String,
+    pub name: Option<String>,
+    pub is_healthy: bool,
+    pub is_disabled: bool,
+    pub usage_count: u64,
+    pub error_count: u32,
+    pub last_used: Option<DateTime<Utc>>,
+    pub last_error_message: Option<String>,
+    /// 冷却信息（未冷却时为空）
+    pub cooldown: Option<CredentialCooldown>,
+    /// 当前是否可被调度
+    pub available: bool,
+}
+
 /// OAuth 凭证状态
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OAuthStatus {
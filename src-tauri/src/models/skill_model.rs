@@ -1,5 +1,5 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +17,9 @@ pub struct Skill {
     pub repo_name: Option<String>,
     #[serde(rename = "repoBranch", skip_serializing_if = "Option::is_none")]
     pub repo_branch: Option<String>,
+    /// SKILL.md frontmatter 中的版本号（仓库技能为仓库中的版本，本地技能为已安装版本）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,6 +40,53 @@ pub struct SkillState {
 pub struct SkillMetadata {
     pub name: Option<String>,
     pub description: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_version")]
+    pub version: Option<String>,
+}
+
+/// 版本号可以写成字符串或数字（`version: 1.2`），统一转为字符串
+fn deserialize_version<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(
+        match Option::<serde_yaml::Value>::deserialize(deserializer)? {
+            Some(serde_yaml::Value::String(s)) => {
+                Some(s.trim().to_string()).filter(|s| !s.is_empty())
+            }
+            Some(serde_yaml::Value::Number(n)) => Some(n.to_string()),
+            _ => None,
+        },
+    )
+}
+
+/// 已安装技能的更新检查结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkillUpdateInfo {
+    pub directory: String,
+    pub name: String,
+    /// 更新来源：`git`（技能目录是 git 仓库）或 `registry`（技能仓库列表）
+    pub source: String,
+    pub installed_version: Option<String>,
+    pub latest_version: Option<String>,
+    pub update_available: bool,
+    /// 新版本的更新说明（CHANGELOG.md 中已安装版本之后的部分，或 git 提交记录）
+    pub changelog: Option<String>,
+}
+
+/// 技能使用统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkillUsageStats {
+    pub directory: String,
+    /// 是否仍安装在 `~/.proxycast/skills`
+    pub installed: bool,
+    /// 模型读取 SKILL.md 或在回复中引用的次数（每轮对话最多计一次）
+    pub reference_count: u64,
+    /// 通过 `run_skill_script` 调用的次数（每轮对话最多计一次）
+    pub invoke_count: u64,
+    pub last_used_at: Option<DateTime<Utc>>,
 }
 
 impl Default for SkillRepo {
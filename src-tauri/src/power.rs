@@ -0,0 +1,185 @@
+//! 系统休眠/唤醒检测
+//!
+//! 不依赖平台电源事件 API，而是周期性比较墙钟时间和单调时钟的流逝：
+//! - macOS/Linux 休眠期间单调时钟停止计时，唤醒后墙钟时间会明显多于单调时钟
+//! - Windows 休眠期间单调时钟继续计时，唤醒后两次检查的间隔会远大于检查周期
+//!
+//! 检测到唤醒后：
+//! - 记录唤醒时间，流式响应的卡顿检测和 WebSocket 心跳超时在宽限期内暂停
+//! - 重建 Agent 的 HTTP 连接（休眠前的连接池中的连接通常已失效）
+//! - 推送 [`POWER_RESUME_EVENT`] 事件
+//!
+//! 休眠期间到期的后续任务由 [`crate::agent::followup`] 调度器按配置的策略处理。
+
+use crate::agent::NativeAgentState;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// 推送到前端的事件名
+pub const POWER_RESUME_EVENT: &str = "system-resume";
+
+/// 检查间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 超出预期的时间差达到该值时视为发生过休眠
+pub const SLEEP_THRESHOLD: Duration = Duration::from_secs(15);
+
+/// 唤醒次数
+static RESUME_COUNT: AtomicU64 = AtomicU64::new(0);
+/// 最近一次唤醒时间（Unix 时间戳毫秒，0 表示未发生过）
+static LAST_RESUME_MS: AtomicU64 = AtomicU64::new(0);
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// 记录一次唤醒
+pub fn record_resume() {
+    LAST_RESUME_MS.store(now_ms(), Ordering::Relaxed);
+    RESUME_COUNT.fetch_add(1, Ordering::Relaxed);
+}
+
+/// 应用启动以来检测到的唤醒次数
+pub fn resume_count() -> u64 {
+    RESUME_COUNT.load(Ordering::Relaxed)
+}
+
+/// 最近 `window` 内是否发生过唤醒（用于暂停超时判断）
+pub fn resumed_within(window: Duration) -> bool {
+    let last = LAST_RESUME_MS.load(Ordering::Relaxed);
+    last != 0 && now_ms().saturating_sub(last) <= window.as_millis() as u64
+}
+
+/// 休眠检测器
+///
+/// 每次调用 [`SleepDetector::observe`] 时与上一次观察比较，
+/// `expected` 为两次观察之间的预期间隔（即调用方的检查周期）
+#[derive(Debug, Clone)]
+pub struct SleepDetector {
+    expected: Duration,
+    last_wall: SystemTime,
+    last_mono: Instant,
+}
+
+impl SleepDetector {
+    pub fn new(expected: Duration) -> Self {
+        Self {
+            expected,
+            last_wall: SystemTime::now(),
+            last_mono: Instant::now(),
+        }
+    }
+
+    /// 观察当前时间，检测到休眠时返回估算的休眠时长
+    pub fn observe(&mut self, wall: SystemTime, mono: Instant) -> Option<Duration> {
+        let wall_elapsed = wall.duration_since(self.last_wall).unwrap_or_default();
+        let mono_elapsed = mono.saturating_duration_since(self.last_mono);
+        self.last_wall = wall;
+        self.last_mono = mono;
+
+        // 单调时钟停止计时的平台：墙钟多出来的部分
+        let frozen = wall_elapsed.saturating_sub(mono_elapsed);
+        // 单调时钟继续计时的平台：超出检查周期的部分
+        let stalled = mono_elapsed.saturating_sub(self.expected);
+        let slept = frozen.max(stalled);
+        (slept >= SLEEP_THRESHOLD).then_some(slept)
+    }
+
+    /// 使用当前时间观察
+    pub fn check(&mut self) -> Option<Duration> {
+        self.observe(SystemTime::now(), Instant::now())
+    }
+}
+
+/// 唤醒事件
+#[derive(Debug, Clone, Serialize)]
+pub struct ResumeEvent {
+    /// 估算的休眠时长（秒）
+    pub slept_secs: u64,
+    pub resumed_at: DateTime<Utc>,
+}
+
+/// 启动休眠/唤醒监控
+pub fn spawn_power_monitor(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut detector = SleepDetector::new(CHECK_INTERVAL);
+
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+
+            let Some(slept) = detector.check() else {
+                continue;
+            };
+            record_resume();
+            tracing::info!("[Power] 检测到系统唤醒，休眠约 {} 秒", slept.as_secs());
+
+            if let Some(agent_state) = app_handle.try_state::<NativeAgentState>() {
+                if let Err(e) = agent_state.reset_connections() {
+                    tracing::warn!("[Power] 重建 Agent 连接失败: {}", e);
+                }
+            }
+
+            let event = ResumeEvent {
+                slept_secs: slept.as_secs(),
+                resumed_at: Utc::now(),
+            };
+            if let Err(e) = app_handle.emit(POWER_RESUME_EVENT, &event) {
+                tracing::warn!("[Power] 推送唤醒事件失败: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_frozen_monotonic_clock() {
+        let mut detector = SleepDetector::new(CHECK_INTERVAL);
+        let wall = detector.last_wall;
+        let mono = detector.last_mono;
+
+        // 正常流逝
+        assert!(detector
+            .observe(wall + CHECK_INTERVAL, mono + CHECK_INTERVAL)
+            .is_none());
+
+        // 墙钟前进 1 小时，单调时钟只前进 5 秒
+        let slept = detector
+            .observe(
+                wall + CHECK_INTERVAL + Duration::from_secs(3600),
+                mono + CHECK_INTERVAL * 2,
+            )
+            .unwrap();
+        assert_eq!(slept, Duration::from_secs(3595));
+    }
+
+    #[test]
+    fn test_detects_stalled_check_interval() {
+        let mut detector = SleepDetector::new(CHECK_INTERVAL);
+        let wall = detector.last_wall;
+        let mono = detector.last_mono;
+
+        // 两个时钟都前进 10 分钟（Windows 休眠）
+        let gap = Duration::from_secs(600);
+        assert_eq!(
+            detector.observe(wall + gap, mono + gap),
+            Some(gap - CHECK_INTERVAL)
+        );
+
+        // 轻微延迟不算休眠
+        assert!(detector
+            .observe(
+                wall + gap + Duration::from_secs(8),
+                mono + gap + Duration::from_secs(8)
+            )
+            .is_none());
+    }
+}
@@ -3,6 +3,7 @@
 //! 定义请求处理过程中的上下文信息
 
 use crate::plugin::PluginContext;
+use crate::services::usage_recorder::UsageSource;
 use crate::ProviderType;
 use chrono::{DateTime, Utc};
 use std::time::Instant;
@@ -34,6 +35,20 @@ pub struct RequestContext {
     pub plugin_ctx: Option<PluginContext>,
     /// 元数据
     pub metadata: std::collections::HashMap<String, serde_json::Value>,
+    /// HTTP 方法
+    pub method: Option<String>,
+    /// 请求路径
+    pub path: Option<String>,
+    /// 请求体（已截断，仅在启用请求体记录时保存）
+    pub request_body: Option<String>,
+    /// 响应体（已截断，仅在启用请求体记录时保存）
+    pub response_body: Option<String>,
+    /// 客户端 API Key ID（使用主 API Key 时为空）
+    pub client_key: Option<String>,
+    /// 实际选择的 Provider 名称（凭证池类型，用于 Token 预算）
+    pub provider_name: Option<String>,
+    /// 请求来源（`x-proxycast-source` 请求头，外部客户端为空）
+    pub source: Option<UsageSource>,
 }
 
 impl RequestContext {
@@ -52,6 +67,13 @@ impl RequestContext {
             is_stream: false,
             plugin_ctx: None,
             metadata: std::collections::HashMap::new(),
+            method: None,
+            path: None,
+            request_body: None,
+            response_body: None,
+            client_key: None,
+            provider_name: None,
+            source: None,
         }
     }
 
@@ -61,6 +83,25 @@ impl RequestContext {
         self
     }
 
+    /// 设置请求方法和路径
+    pub fn with_route(mut self, method: &str, path: &str) -> Self {
+        self.method = Some(method.to_string());
+        self.path = Some(path.to_string());
+        self
+    }
+
+    /// 设置客户端 API Key ID
+    pub fn with_client_key(mut self, client_key: Option<String>) -> Self {
+        self.client_key = client_key;
+        self
+    }
+
+    /// 设置请求来源
+    pub fn with_source(mut self, source: Option<UsageSource>) -> Self {
+        self.source = source;
+        self
+    }
+
     /// 设置 Provider
     pub fn set_provider(&mut self, provider: ProviderType) {
         self.provider = Some(provider);
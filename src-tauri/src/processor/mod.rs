@@ -27,9 +27,12 @@ pub use steps::{
 use crate::injection::Injector;
 use crate::plugin::PluginManager;
 use crate::resilience::{Failover, Retrier, TimeoutController};
-use crate::router::{ModelMapper, Router};
+use crate::router::{
+    ModelMapper, ModelRouteMatch, ModelRouteRequest, ModelRouteTable, Router, TargetStats,
+    LATENCY_STATS_WINDOW_SECS,
+};
 use crate::services::provider_pool_service::ProviderPoolService;
-use crate::telemetry::{StatsAggregator, TokenTracker};
+use crate::telemetry::{StatsAggregator, TimeRange, TokenTracker};
 use parking_lot::RwLock as ParkingLotRwLock;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -42,6 +45,8 @@ pub struct RequestProcessor {
     pub router: Arc<RwLock<Router>>,
     /// 模型映射器
     pub mapper: Arc<RwLock<ModelMapper>>,
+    /// 模型路由表
+    pub model_routes: Arc<RwLock<ModelRouteTable>>,
     /// 参数注入器
     pub injector: Arc<RwLock<Injector>>,
     /// 重试器
@@ -79,6 +84,7 @@ impl RequestProcessor {
         Self {
             router,
             mapper,
+            model_routes: Arc::new(RwLock::new(ModelRouteTable::default())),
             injector,
             retrier,
             failover,
@@ -97,6 +103,7 @@ impl RequestProcessor {
         Self {
             router: Arc::new(RwLock::new(Router::new(ProviderType::Kiro))),
             mapper: Arc::new(RwLock::new(ModelMapper::new())),
+            model_routes: Arc::new(RwLock::new(ModelRouteTable::default())),
             injector: Arc::new(RwLock::new(Injector::new())),
             retrier: Arc::new(Retrier::with_defaults()),
             failover: Arc::new(Failover::with_defaults()),
@@ -122,6 +129,7 @@ impl RequestProcessor {
         Self {
             router: Arc::new(RwLock::new(Router::new(ProviderType::Kiro))),
             mapper: Arc::new(RwLock::new(ModelMapper::new())),
+            model_routes: Arc::new(RwLock::new(ModelRouteTable::default())),
             injector: Arc::new(RwLock::new(Injector::new())),
             retrier: Arc::new(Retrier::with_defaults()),
             failover: Arc::new(Failover::with_defaults()),
@@ -223,6 +231,48 @@ impl RequestProcessor {
         self.route_for_context(ctx).await
     }
 
+    /// 按模型路由表改投请求
+    ///
+    /// 命中路由时更新上下文中的解析模型和 Provider，返回目标 Provider 名称；
+    /// 未命中时返回 None，沿用别名映射和客户端 Provider 选择的结果
+    pub async fn apply_model_route(
+        &self,
+        ctx: &mut RequestContext,
+        req: &ModelRouteRequest<'_>,
+    ) -> Option<ModelRouteMatch> {
+        let routes = self.model_routes.read().await;
+        // fastest 策略的路由首次查询统计时才汇总统计窗口内的请求
+        let window = std::cell::OnceCell::new();
+        let stats = |provider: &str, model: &str| {
+            let summaries = window.get_or_init(|| {
+                let end = chrono::Utc::now();
+                let start = end - chrono::Duration::seconds(LATENCY_STATS_WINDOW_SECS);
+                self.stats
+                    .read()
+                    .by_provider_and_model(Some(TimeRange::new(start, end)))
+            });
+            let provider = provider.parse::<crate::ProviderType>().ok()?;
+            summaries
+                .get(&(provider, model.to_string()))
+                .map(TargetStats::from_summary)
+        };
+        let matched = routes.resolve_with_stats(&ctx.original_model, req, stats)?;
+        ctx.set_resolved_model(matched.model.clone());
+        if let Ok(provider) = matched.provider.parse::<crate::ProviderType>() {
+            ctx.set_provider(provider);
+        }
+
+        tracing::info!(
+            "[MODEL_ROUTE] request_id={} model={} -> provider={} model={}",
+            ctx.request_id,
+            ctx.original_model,
+            matched.provider,
+            matched.model
+        );
+
+        Some(matched)
+    }
+
     /// 检查模型是否被指定 Provider 排除
     ///
     /// # Arguments
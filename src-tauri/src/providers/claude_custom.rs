@@ -69,6 +69,19 @@ impl ClaudeCustomProvider {
         }
     }
 
+    /// 将 OpenAI 文件输入转换为 Claude 文档块
+    fn convert_file_to_claude(file: &crate::models::openai::FileData) -> Option<serde_json::Value> {
+        let Some((media_type, data)) = file.parse_data_url() else {
+            tracing::warn!("[CLAUDE_DOCUMENT] 无法解析文件数据: {:?}", file.filename);
+            return None;
+        };
+        Some(crate::models::anthropic::document_block(
+            media_type,
+            data,
+            file.filename.as_deref(),
+        ))
+    }
+
     /// 将 OpenAI 图片 URL 格式转换为 Claude 图片格式
     ///
     /// 支持两种格式：
@@ -189,6 +202,9 @@ impl ClaudeCustomProvider {
                                 // 转换 OpenAI 图片格式为 Claude 图片格式
                                 Self::convert_image_url_to_claude(&image_url.url)
                             }
+                            // Claude 不支持音频输入
+                            ContentPart::InputAudio { .. } => None,
+                            ContentPart::File { file } => Self::convert_file_to_claude(file),
                         })
                         .collect()
                 }
@@ -461,6 +477,9 @@ impl StreamingProvider for ClaudeCustomProvider {
                                 // 转换 OpenAI 图片格式为 Claude 图片格式
                                 Self::convert_image_url_to_claude(&image_url.url)
                             }
+                            // Claude 不支持音频输入
+                            ContentPart::InputAudio { .. } => None,
+                            ContentPart::File { file } => Self::convert_file_to_claude(file),
                         })
                         .collect()
                 }
@@ -558,6 +558,36 @@ impl GeminiApiKeyCredential {
     }
 }
 
+/// Non-2xx response returned by the Gemini API
+#[derive(Debug, Clone)]
+pub struct GeminiApiError {
+    /// Which request failed, e.g. "call" or "stream call"
+    pub operation: &'static str,
+    /// Upstream HTTP status code
+    pub status: u16,
+    /// Upstream response body
+    pub body: String,
+}
+
+impl GeminiApiError {
+    /// Upstream status code of a provider error, if it came from a non-2xx response
+    pub fn status_of(err: &(dyn Error + Send + Sync + 'static)) -> Option<u16> {
+        err.downcast_ref::<GeminiApiError>().map(|e| e.status)
+    }
+}
+
+impl std::fmt::Display for GeminiApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Gemini API {} failed: {} - {}",
+            self.operation, self.status, self.body
+        )
+    }
+}
+
+impl Error for GeminiApiError {}
+
 /// Gemini API Key Provider
 ///
 /// Manages multiple Gemini API keys with load balancing support.
@@ -605,9 +635,11 @@ impl GeminiApiKeyProvider {
             .await?;
 
         if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(format!("Gemini API call failed: {status} - {body}").into());
+            return Err(Box::new(GeminiApiError {
+                operation: "call",
+                status: resp.status().as_u16(),
+                body: resp.text().await.unwrap_or_default(),
+            }));
         }
 
         let data: serde_json::Value = resp.json().await?;
@@ -636,9 +668,11 @@ impl GeminiApiKeyProvider {
             .await?;
 
         if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(format!("Gemini API stream call failed: {status} - {body}").into());
+            return Err(Box::new(GeminiApiError {
+                operation: "stream call",
+                status: resp.status().as_u16(),
+                body: resp.text().await.unwrap_or_default(),
+            }));
         }
 
         Ok(resp)
@@ -659,9 +693,11 @@ impl GeminiApiKeyProvider {
             .await?;
 
         if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(format!("Gemini API list models failed: {status} - {body}").into());
+            return Err(Box::new(GeminiApiError {
+                operation: "list models",
+                status: resp.status().as_u16(),
+                body: resp.text().await.unwrap_or_default(),
+            }));
         }
 
         let data: serde_json::Value = resp.json().await?;
@@ -697,6 +733,20 @@ mod gemini_api_key_tests {
         assert_eq!(cred.get_base_url(), GEMINI_API_BASE_URL);
     }
 
+    #[test]
+    fn test_gemini_api_error_status() {
+        let err: Box<dyn Error + Send + Sync> = Box::new(GeminiApiError {
+            operation: "call",
+            status: 400,
+            body: "bad request".to_string(),
+        });
+        assert_eq!(GeminiApiError::status_of(err.as_ref()), Some(400));
+        assert_eq!(err.to_string(), "Gemini API call failed: 400 - bad request");
+
+        let err: Box<dyn Error + Send + Sync> = "connection reset".into();
+        assert_eq!(GeminiApiError::status_of(err.as_ref()), None);
+    }
+
     #[test]
     fn test_gemini_api_key_credential_is_available() {
         let cred = GeminiApiKeyCredential::new("test-id".to_string(), "test-key".to_string());
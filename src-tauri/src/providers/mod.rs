@@ -29,7 +29,7 @@ pub use codex::CodexProvider;
 #[allow(unused_imports)]
 pub use error::ProviderError;
 #[allow(unused_imports)]
-pub use gemini::{GeminiApiKeyCredential, GeminiApiKeyProvider, GeminiProvider};
+pub use gemini::{GeminiApiError, GeminiApiKeyCredential, GeminiApiKeyProvider, GeminiProvider};
 #[allow(unused_imports)]
 pub use iflow::IFlowProvider;
 #[allow(unused_imports)]
@@ -68,6 +68,19 @@ impl OpenAICustomProvider {
         }
     }
 
+    /// 构建 Realtime API 的 WebSocket URL（https → wss，http → ws）
+    pub fn build_realtime_url(&self, model: &str) -> String {
+        let url = self.build_url("realtime");
+        let url = if let Some(rest) = url.strip_prefix("https://") {
+            format!("wss://{}", rest)
+        } else if let Some(rest) = url.strip_prefix("http://") {
+            format!("ws://{}", rest)
+        } else {
+            url
+        };
+        format!("{}?model={}", url, urlencoding::encode(model))
+    }
+
     /// 调用 OpenAI API（使用类型化请求）
     pub async fn call_api(
         &self,
@@ -117,6 +130,56 @@ impl OpenAICustomProvider {
         Ok(resp)
     }
 
+    /// 调用图片生成 API（/v1/images/generations）
+    pub async fn images_generations(
+        &self,
+        request: &serde_json::Value,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let api_key = self
+            .config
+            .api_key
+            .as_ref()
+            .ok_or("OpenAI API key not configured")?;
+
+        let url = self.build_url("images/generations");
+
+        let resp = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Bearer {api_key}"))
+            .header("Content-Type", "application/json")
+            .json(request)
+            .send()
+            .await?;
+
+        Ok(resp)
+    }
+
+    /// 调用向量化 API（/v1/embeddings）
+    pub async fn embeddings(
+        &self,
+        request: &serde_json::Value,
+    ) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let api_key = self
+            .config
+            .api_key
+            .as_ref()
+            .ok_or("OpenAI API key not configured")?;
+
+        let url = self.build_url("embeddings");
+
+        let resp = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Bearer {api_key}"))
+            .header("Content-Type", "application/json")
+            .json(request)
+            .send()
+            .await?;
+
+        Ok(resp)
+    }
+
     pub async fn list_models(&self) -> Result<serde_json::Value, Box<dyn Error + Send + Sync>> {
         let api_key = self
             .config
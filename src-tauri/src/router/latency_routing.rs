@@ -0,0 +1,225 @@
+//! 延迟感知路由
+//!
+//! `strategy: fastest` 的模型路由按代理最近 5 分钟收集的延迟和错误率选择目标：
+//! - 样本数不少于 [`MIN_SAMPLES`] 且错误率不超过 [`MAX_ERROR_RATE`] 的目标视为健康
+//! - 当前目标保持不变，直到它不再健康，或另一个健康目标的平均延迟低 [`SWITCH_MARGIN`] 以上（迟滞）
+//! - 样本不足的目标以 [`EXPLORE_RATE`] 的概率获得流量，以便积累统计并在恢复后重新参与选择
+
+use crate::config::ModelRouteTarget;
+use crate::telemetry::StatsSummary;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+
+/// 统计窗口（秒）
+pub const STATS_WINDOW_SECS: i64 = 300;
+/// 参与比较所需的最少样本数
+pub const MIN_SAMPLES: u64 = 5;
+/// 健康目标的最大错误率
+pub const MAX_ERROR_RATE: f64 = 0.5;
+/// 切换所需的最小延迟优势（比例）
+pub const SWITCH_MARGIN: f64 = 0.2;
+/// 样本不足的目标获得的流量比例
+pub const EXPLORE_RATE: f64 = 0.05;
+
+/// 单个目标的近期统计
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TargetStats {
+    pub requests: u64,
+    pub avg_latency_ms: f64,
+    /// 错误率（0.0 - 1.0）
+    pub error_rate: f64,
+}
+
+impl TargetStats {
+    pub fn from_summary(summary: &StatsSummary) -> Self {
+        Self {
+            requests: summary.total_requests,
+            avg_latency_ms: summary.avg_latency_ms,
+            error_rate: 1.0 - summary.success_rate,
+        }
+    }
+
+    fn is_measured(&self) -> bool {
+        self.requests >= MIN_SAMPLES
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.is_measured() && self.error_rate <= MAX_ERROR_RATE
+    }
+}
+
+/// 延迟感知选择器，按路由记录当前选中的目标
+#[derive(Debug, Default)]
+pub struct LatencySelector {
+    current: Mutex<HashMap<String, usize>>,
+}
+
+impl LatencySelector {
+    /// 清空已选目标（路由配置变更后调用）
+    pub fn reset(&self) {
+        self.current.lock().clear();
+    }
+
+    /// 选择目标下标
+    ///
+    /// `stats` 与 `targets` 一一对应；`explore` 为 `[0, 1)` 内的随机数，`pick` 为任意随机数
+    pub fn select(
+        &self,
+        route_key: &str,
+        targets: &[ModelRouteTarget],
+        stats: &[Option<TargetStats>],
+        explore: f64,
+        pick: usize,
+    ) -> Option<usize> {
+        let candidates: Vec<usize> = (0..targets.len())
+            .filter(|&i| targets[i].weight > 0)
+            .collect();
+        if candidates.is_empty() {
+            return None;
+        }
+        let stat = |i: usize| stats.get(i).copied().flatten();
+        let healthy = |i: usize| stat(i).is_some_and(|s| s.is_healthy());
+
+        let unmeasured: Vec<usize> = candidates
+            .iter()
+            .copied()
+            .filter(|&i| !stat(i).is_some_and(|s| s.is_measured()))
+            .collect();
+        if !unmeasured.is_empty() && explore < EXPLORE_RATE {
+            return Some(unmeasured[pick % unmeasured.len()]);
+        }
+
+        let latency = |i: usize| stat(i).map_or(f64::MAX, |s| s.avg_latency_ms);
+        let best = candidates
+            .iter()
+            .copied()
+            .filter(|&i| healthy(i))
+            .min_by(|&a, &b| latency(a).total_cmp(&latency(b)));
+
+        let mut current = self.current.lock();
+        let previous = current
+            .get(route_key)
+            .copied()
+            .filter(|i| candidates.contains(i));
+        let choice = match (previous, best) {
+            (Some(prev), Some(best)) if healthy(prev) => {
+                if latency(best) < latency(prev) * (1.0 - SWITCH_MARGIN) {
+                    best
+                } else {
+                    prev
+                }
+            }
+            (_, Some(best)) => best,
+            // 没有健康的目标：优先沿用未确认异常的目标，否则选错误率最低的
+            (Some(prev), None) if !stat(prev).is_some_and(|s| s.is_measured()) => prev,
+            (_, None) => unmeasured.first().copied().unwrap_or_else(|| {
+                candidates
+                    .iter()
+                    .copied()
+                    .min_by(|&a, &b| {
+                        let rate = |i: usize| stat(i).map_or(0.0, |s| s.error_rate);
+                        rate(a).total_cmp(&rate(b))
+                    })
+                    .unwrap_or(candidates[0])
+            }),
+        };
+
+        if previous != Some(choice) {
+            tracing::info!(
+                "[MODEL_ROUTE] 最快目标变更: route={} -> provider={} (avg={:.0}ms)",
+                route_key,
+                targets[choice].provider,
+                stat(choice).map_or(0.0, |s| s.avg_latency_ms)
+            );
+            current.insert(route_key.to_string(), choice);
+        }
+        Some(choice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn targets(n: usize) -> Vec<ModelRouteTarget> {
+        (0..n)
+            .map(|i| ModelRouteTarget {
+                provider: format!("p{}", i),
+                model: None,
+                weight: 1,
+            })
+            .collect()
+    }
+
+    fn stats(avg_latency_ms: f64, error_rate: f64) -> Option<TargetStats> {
+        Some(TargetStats {
+            requests: 20,
+            avg_latency_ms,
+            error_rate,
+        })
+    }
+
+    #[test]
+    fn test_select_fastest_with_hysteresis() {
+        let selector = LatencySelector::default();
+        let targets = targets(2);
+
+        // 选择最快的健康目标
+        let choice = selector.select(
+            "r",
+            &targets,
+            &[stats(800.0, 0.0), stats(500.0, 0.0)],
+            0.9,
+            0,
+        );
+        assert_eq!(choice, Some(1));
+
+        // 另一目标只快 10%，不切换
+        let choice = selector.select(
+            "r",
+            &targets,
+            &[stats(450.0, 0.0), stats(500.0, 0.0)],
+            0.9,
+            0,
+        );
+        assert_eq!(choice, Some(1));
+
+        // 快 20% 以上时切换
+        let choice = selector.select(
+            "r",
+            &targets,
+            &[stats(350.0, 0.0), stats(500.0, 0.0)],
+            0.9,
+            0,
+        );
+        assert_eq!(choice, Some(0));
+
+        // 当前目标错误率过高时立即切换
+        let choice = selector.select(
+            "r",
+            &targets,
+            &[stats(350.0, 0.8), stats(900.0, 0.1)],
+            0.9,
+            0,
+        );
+        assert_eq!(choice, Some(1));
+    }
+
+    #[test]
+    fn test_explore_unmeasured_targets() {
+        let selector = LatencySelector::default();
+        let targets = targets(3);
+        let current = [stats(300.0, 0.0), None, stats(200.0, 0.0)];
+
+        assert_eq!(selector.select("r", &targets, &current, 0.01, 0), Some(1));
+        assert_eq!(selector.select("r", &targets, &current, 0.5, 0), Some(2));
+
+        // 都没有统计时沿用第一个目标
+        let selector = LatencySelector::default();
+        assert_eq!(
+            selector.select("r", &targets, &[None, None, None], 0.5, 7),
+            Some(0)
+        );
+        assert_eq!(selector.select("r", &[], &[], 0.5, 0), None);
+    }
+}
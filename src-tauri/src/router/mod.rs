@@ -11,18 +11,28 @@
 //! 模型映射：
 //! - 支持模型别名映射（如 `gpt-4` -> `claude-sonnet-4-5-20250514`）
 //!
+//! 模型路由：
+//! - 模型名映射到 (Provider, 实际模型)，支持条件和加权目标
+//! - `fastest` 策略按近期延迟和错误率选择最快的健康目标（带迟滞）
+//!
 //! 路由规则：
 //! - 支持通配符模式匹配（前缀、后缀、包含）
 //! - 支持规则优先级排序
 
 mod amp_router;
+mod latency_routing;
 mod mapper;
+mod model_routes;
 mod provider_router;
 mod route_registry;
 mod rules;
 
 pub use amp_router::{AmpRouteMatch, AmpRouter};
+pub use latency_routing::{
+    LatencySelector, TargetStats, STATS_WINDOW_SECS as LATENCY_STATS_WINDOW_SECS,
+};
 pub use mapper::{ModelInfo, ModelMapper};
+pub use model_routes::{ModelRouteMatch, ModelRouteRequest, ModelRouteTable};
 pub use provider_router::ProviderRouter;
 pub use route_registry::{RegisteredRoute, RouteRegistry, RouteType};
 pub use rules::{RouteResult, Router, RoutingRule};
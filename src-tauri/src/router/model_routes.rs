@@ -0,0 +1,276 @@
+//! 模型路由
+//!
+//! 按配置顺序匹配客户端请求的模型名，命中后将请求改投到指定的
+//! (Provider, 实际模型)：
+//! - 条件支持请求头、客户端 Key 和估算的提示词大小，全部满足才命中
+//! - 一个路由可配置多个目标，按权重随机选择，或按近期延迟选择最快的健康目标
+//! - 未命中任何路由时沿用原有的别名映射和 Provider 选择
+
+use super::latency_routing::{LatencySelector, TargetStats};
+use super::Router;
+use crate::config::{ModelRouteConfig, ModelRouteStrategy, ModelRouteTarget};
+use axum::http::HeaderMap;
+use rand::Rng;
+use std::sync::Arc;
+
+/// 参与路由匹配的请求信息
+#[derive(Debug, Clone, Copy)]
+pub struct ModelRouteRequest<'a> {
+    /// 请求头
+    pub headers: &'a HeaderMap,
+    /// 客户端 Key ID
+    pub client_key: Option<&'a str>,
+    /// 估算的提示词 Token 数
+    pub prompt_tokens: u32,
+}
+
+/// 路由命中结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelRouteMatch {
+    /// 目标 Provider
+    pub provider: String,
+    /// 实际模型名
+    pub model: String,
+}
+
+/// 模型路由表
+#[derive(Debug, Clone, Default)]
+pub struct ModelRouteTable {
+    routes: Vec<ModelRouteConfig>,
+    /// `fastest` 策略的目标选择状态
+    latency: Arc<LatencySelector>,
+}
+
+impl ModelRouteTable {
+    /// 创建模型路由表
+    pub fn new(routes: Vec<ModelRouteConfig>) -> Self {
+        Self {
+            routes,
+            latency: Arc::default(),
+        }
+    }
+
+    /// 替换全部路由（配置变更后调用）
+    pub fn set_routes(&mut self, routes: Vec<ModelRouteConfig>) {
+        self.routes = routes;
+        self.latency.reset();
+    }
+
+    /// 获取全部路由
+    pub fn routes(&self) -> &[ModelRouteConfig] {
+        &self.routes
+    }
+
+    /// 解析请求的路由目标
+    pub fn resolve(&self, model: &str, req: &ModelRouteRequest<'_>) -> Option<ModelRouteMatch> {
+        self.resolve_with_stats(model, req, |_, _| None)
+    }
+
+    /// 解析请求的路由目标，`fastest` 策略通过 `stats(provider, 实际模型)` 查询目标的近期统计
+    pub fn resolve_with_stats(
+        &self,
+        model: &str,
+        req: &ModelRouteRequest<'_>,
+        stats: impl Fn(&str, &str) -> Option<TargetStats>,
+    ) -> Option<ModelRouteMatch> {
+        let mut rng = rand::thread_rng();
+        self.resolve_with(model, req, stats, |total| rng.gen_range(0..total))
+    }
+
+    /// 解析请求的路由目标（`roll` 返回 `[0, total)` 内的随机数）
+    fn resolve_with(
+        &self,
+        model: &str,
+        req: &ModelRouteRequest<'_>,
+        stats: impl Fn(&str, &str) -> Option<TargetStats>,
+        mut roll: impl FnMut(u32) -> u32,
+    ) -> Option<ModelRouteMatch> {
+        self.routes
+            .iter()
+            .filter(|route| Router::pattern_matches(&route.model, model))
+            .filter(|route| conditions_match(route, req))
+            .find_map(|route| {
+                let target = match route.strategy {
+                    ModelRouteStrategy::Weighted => {
+                        let total: u32 = route.targets.iter().map(|t| t.weight).sum();
+                        if total == 0 {
+                            return None;
+                        }
+                        pick_weighted(&route.targets, roll(total))?
+                    }
+                    ModelRouteStrategy::Fastest => {
+                        let target_stats: Vec<_> = route
+                            .targets
+                            .iter()
+                            .map(|t| stats(&t.provider, t.model.as_deref().unwrap_or(model)))
+                            .collect();
+                        let explore = roll(10_000) as f64 / 10_000.0;
+                        let index = self.latency.select(
+                            &route.model,
+                            &route.targets,
+                            &target_stats,
+                            explore,
+                            roll(u32::MAX) as usize,
+                        )?;
+                        &route.targets[index]
+                    }
+                };
+                Some(ModelRouteMatch {
+                    provider: target.provider.clone(),
+                    model: target.model.clone().unwrap_or_else(|| model.to_string()),
+                })
+            })
+    }
+}
+
+/// 检查路由条件
+fn conditions_match(route: &ModelRouteConfig, req: &ModelRouteRequest<'_>) -> bool {
+    let cond = &route.conditions;
+    let headers_match = cond.headers.iter().all(|(name, value)| {
+        req.headers
+            .get(name.to_ascii_lowercase().as_str())
+            .and_then(|v| v.to_str().ok())
+            .map_or(false, |v| v == value)
+    });
+    let key_match = cond.client_keys.is_empty()
+        || req
+            .client_key
+            .map_or(false, |key| cond.client_keys.iter().any(|k| k == key));
+    let size_match = cond
+        .min_prompt_tokens
+        .map_or(true, |min| req.prompt_tokens >= min)
+        && cond
+            .max_prompt_tokens
+            .map_or(true, |max| req.prompt_tokens <= max);
+    headers_match && key_match && size_match
+}
+
+/// 按权重选择目标，`point` 为 `[0, 总权重)` 内的值
+fn pick_weighted(targets: &[ModelRouteTarget], mut point: u32) -> Option<&ModelRouteTarget> {
+    for target in targets {
+        if point < target.weight {
+            return Some(target);
+        }
+        point -= target.weight;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ModelRouteConditions;
+
+    fn target(provider: &str, model: Option<&str>, weight: u32) -> ModelRouteTarget {
+        ModelRouteTarget {
+            provider: provider.to_string(),
+            model: model.map(|m| m.to_string()),
+            weight,
+        }
+    }
+
+    #[test]
+    fn test_conditions_and_fallthrough() {
+        let mut conditions = ModelRouteConditions {
+            max_prompt_tokens: Some(1000),
+            ..Default::default()
+        };
+        conditions
+            .headers
+            .insert("x-tier".to_string(), "cheap".to_string());
+        let table = ModelRouteTable::new(vec![
+            ModelRouteConfig {
+                model: "gpt-4o".to_string(),
+                conditions,
+                strategy: ModelRouteStrategy::Weighted,
+                targets: vec![target("deepseek", Some("deepseek-chat"), 1)],
+            },
+            ModelRouteConfig {
+                model: "gpt-*".to_string(),
+                conditions: ModelRouteConditions {
+                    client_keys: vec!["team-a".to_string()],
+                    ..Default::default()
+                },
+                strategy: ModelRouteStrategy::Weighted,
+                targets: vec![target("openai", None, 1)],
+            },
+        ]);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-tier", "cheap".parse().unwrap());
+        let req = ModelRouteRequest {
+            headers: &headers,
+            client_key: Some("team-a"),
+            prompt_tokens: 200,
+        };
+        assert_eq!(
+            table.resolve("gpt-4o", &req),
+            Some(ModelRouteMatch {
+                provider: "deepseek".to_string(),
+                model: "deepseek-chat".to_string(),
+            })
+        );
+
+        // 提示词过大时落到下一条路由，模型名沿用请求值
+        let large = ModelRouteRequest {
+            prompt_tokens: 5000,
+            ..req
+        };
+        assert_eq!(
+            table.resolve("gpt-4o", &large),
+            Some(ModelRouteMatch {
+                provider: "openai".to_string(),
+                model: "gpt-4o".to_string(),
+            })
+        );
+
+        let other_key = ModelRouteRequest {
+            client_key: Some("team-b"),
+            ..large
+        };
+        assert_eq!(table.resolve("gpt-4o", &other_key), None);
+        assert_eq!(table.resolve("claude-sonnet-4-5", &req), None);
+    }
+
+    #[test]
+    fn test_weighted_targets() {
+        let table = ModelRouteTable::new(vec![ModelRouteConfig {
+            model: "smart".to_string(),
+            conditions: ModelRouteConditions::default(),
+            strategy: ModelRouteStrategy::Weighted,
+            targets: vec![
+                target("claude", Some("claude-sonnet-4-5"), 3),
+                target("disabled", None, 0),
+                target("gemini", Some("gemini-2.5-pro"), 1),
+            ],
+        }]);
+        let headers = HeaderMap::new();
+        let req = ModelRouteRequest {
+            headers: &headers,
+            client_key: None,
+            prompt_tokens: 0,
+        };
+
+        let pick = |point| {
+            table
+                .resolve_with("smart", &req, |_, _| None, |_| point)
+                .unwrap()
+                .provider
+        };
+        assert_eq!(pick(0), "claude");
+        assert_eq!(pick(2), "claude");
+        assert_eq!(pick(3), "gemini");
+
+        let mut totals = Vec::new();
+        table.resolve_with(
+            "smart",
+            &req,
+            |_, _| None,
+            |total| {
+                totals.push(total);
+                0
+            },
+        );
+        assert_eq!(totals, vec![4]);
+    }
+}
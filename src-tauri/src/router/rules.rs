@@ -253,7 +253,7 @@ impl Router {
     /// - 前缀匹配: `claude-*`
     /// - 后缀匹配: `*-preview`
     /// - 包含匹配: `*flash*`
-    fn pattern_matches(pattern: &str, model: &str) -> bool {
+    pub(crate) fn pattern_matches(pattern: &str, model: &str) -> bool {
         // 精确匹配
         if !pattern.contains('*') {
             return pattern == model;
@@ -17,13 +17,14 @@
 
 use axum::{
     body::Body,
-    extract::State,
+    extract::{ConnectInfo, State},
     http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use chrono::Utc;
 use std::collections::HashMap;
+use std::net::SocketAddr;
 
 use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
 use crate::flow_monitor::{
@@ -34,12 +35,16 @@ use crate::flow_monitor::{
 use crate::models::anthropic::AnthropicMessagesRequest;
 use crate::models::openai::ChatCompletionRequest;
 use crate::processor::RequestContext;
+use crate::router::{ModelRouteMatch, ModelRouteRequest};
 use crate::server::client_detector::ClientType;
-use crate::server::{record_request_telemetry, record_token_usage, AppState};
+use crate::server::{
+    capture_body, capture_response_body, instrument_stream_ttft, record_request_telemetry,
+    record_response_usage, record_token_usage, AppState,
+};
 use crate::server_utils::{
-    build_anthropic_response, build_anthropic_stream_response, message_content_len,
-    parse_cw_response, safe_truncate,
+    build_anthropic_response, build_anthropic_stream_response, parse_cw_response, safe_truncate,
 };
+use crate::services::usage_recorder::UsageSource;
 use crate::streaming::StreamFormat as StreamingFormat;
 use crate::ProviderType;
 
@@ -89,6 +94,19 @@ fn build_llm_request_from_openai(
                                         },
                                     }
                                 }
+                                crate::models::openai::ContentPart::InputAudio { input_audio } => {
+                                    crate::flow_monitor::ContentPart::Text {
+                                        text: format!("[audio: {}]", input_audio.format),
+                                    }
+                                }
+                                crate::models::openai::ContentPart::File { file } => {
+                                    crate::flow_monitor::ContentPart::Text {
+                                        text: format!(
+                                            "[document: {}]",
+                                            file.filename.as_deref().unwrap_or("file")
+                                        ),
+                                    }
+                                }
                             })
                             .collect();
                         MessageContent::MultiModal(flow_parts)
@@ -369,6 +387,84 @@ async fn select_provider_for_client(headers: &HeaderMap, state: &AppState) -> (S
     (selected_provider, client_type)
 }
 
+/// 按模型路由表改投请求
+///
+/// 提示词大小按序列化后的请求体估算（约 4 字节 1 个 Token）
+async fn route_model_request<T: serde::Serialize>(
+    state: &AppState,
+    ctx: &mut RequestContext,
+    headers: &HeaderMap,
+    request: &T,
+) -> Option<ModelRouteMatch> {
+    if state
+        .processor
+        .model_routes
+        .read()
+        .await
+        .routes()
+        .is_empty()
+    {
+        return None;
+    }
+    let prompt_tokens = serde_json::to_vec(request)
+        .map(|body| (body.len() / 4).min(u32::MAX as usize) as u32)
+        .unwrap_or(0);
+    let client_key = ctx.client_key.clone();
+    let route_request = ModelRouteRequest {
+        headers,
+        client_key: client_key.as_deref(),
+        prompt_tokens,
+    };
+    let matched = state
+        .processor
+        .apply_model_route(ctx, &route_request)
+        .await?;
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[MODEL_ROUTE] request_id={} model={} -> provider={} model={}",
+            ctx.request_id, ctx.original_model, matched.provider, matched.model
+        ),
+    );
+    Some(matched)
+}
+
+/// 检查 Token 预算，耗尽时返回 429 响应
+///
+/// 同时记录实际使用的 Provider，供请求完成后累计用量
+async fn check_token_budget(
+    state: &AppState,
+    ctx: &mut RequestContext,
+    provider: &str,
+) -> Option<Response> {
+    ctx.provider_name = Some(provider.to_string());
+    let db = state.db.as_ref()?;
+    let message = state
+        .token_budget
+        .check(db, ctx.client_key.as_deref(), provider)?;
+    state.logs.write().await.add(
+        "warn",
+        &format!(
+            "[TOKEN_BUDGET] request_id={} provider={} rejected: {}",
+            ctx.request_id, provider, message
+        ),
+    );
+    Some(
+        (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(serde_json::json!({
+                "type": "error",
+                "error": {
+                    "type": "rate_limit_error",
+                    "code": "budget_exceeded",
+                    "message": message
+                }
+            })),
+        )
+            .into_response(),
+    )
+}
+
 // ============================================================================
 // 拦截检查辅助函数
 // ============================================================================
@@ -619,10 +715,11 @@ pub async fn verify_api_key_anthropic(
 
 pub async fn chat_completions(
     State(state): State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     headers: HeaderMap,
     Json(mut request): Json<ChatCompletionRequest>,
 ) -> Response {
-    if let Err(e) = verify_api_key(&headers, &state.api_key).await {
+    if let Err(e) = verify_api_key(&headers, &state.api_key()).await {
         state
             .logs
             .write()
@@ -632,7 +729,14 @@ pub async fn chat_completions(
     }
 
     // 创建请求上下文
-    let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
+    let mut ctx = RequestContext::new(request.model.clone())
+        .with_stream(request.stream)
+        .with_route("POST", "/v1/chat/completions")
+        .with_client_key(crate::middleware::client_keys::client_key_id(&headers))
+        .with_source(UsageSource::from_request(
+            &headers,
+            connect_info.map(|ConnectInfo(addr)| addr),
+        ));
 
     state.logs.write().await.add(
         "info",
@@ -643,7 +747,13 @@ pub async fn chat_completions(
     );
 
     // 使用 RequestProcessor 解析模型别名和路由
-    let provider = state.processor.resolve_and_route(&mut ctx).await;
+    let mut provider = state.processor.resolve_and_route(&mut ctx).await;
+
+    // 模型路由（命中时覆盖别名映射和 Provider 选择）
+    let model_route = route_model_request(&state, &mut ctx, &headers, &request).await;
+    if model_route.is_some() {
+        provider = ctx.provider.unwrap_or(provider);
+    }
 
     // 更新请求中的模型名为解析后的模型
     if ctx.resolved_model != ctx.original_model {
@@ -681,6 +791,13 @@ pub async fn chat_completions(
     // 根据客户端类型选择 Provider
     // **Validates: Requirements 3.1, 3.3, 3.4**
     let (selected_provider, client_type) = select_provider_for_client(&headers, &state).await;
+    // 模型路由指定的 Provider 优先
+    let selected_provider = model_route.map_or(selected_provider, |route| route.provider);
+
+    // Token 预算检查
+    if let Some(resp) = check_token_budget(&state, &mut ctx, &selected_provider).await {
+        return resp;
+    }
 
     // 记录客户端检测和 Provider 选择结果
     state.logs.write().await.add(
@@ -764,7 +881,10 @@ pub async fn chat_completions(
             }
         }
 
+        ctx.request_body = capture_body(&state, &request);
         let response = call_provider_openai(&state, &cred, &request, flow_id.as_deref()).await;
+        let response = capture_response_body(&state, &mut ctx, response).await;
+        let response = instrument_stream_ttft(&state, &ctx, response);
 
         // 记录请求统计
         let is_success = response.status().is_success();
@@ -775,38 +895,15 @@ pub async fn chat_completions(
         };
         record_request_telemetry(&state, &ctx, status, None);
 
-        // 如果成功，记录估算的 Token 使用量
-        let estimated_input_tokens = request
-            .messages
-            .iter()
-            .map(|m| {
-                let content_len = match &m.content {
-                    Some(c) => message_content_len(c),
-                    None => 0,
-                };
-                content_len / 4
-            })
-            .sum::<usize>() as u32;
-        let estimated_output_tokens = if is_success { 100u32 } else { 0u32 };
-
-        if is_success {
-            record_token_usage(
-                &state,
-                &ctx,
-                Some(estimated_input_tokens),
-                Some(estimated_output_tokens),
-            );
-        }
+        // 记录上游报告的 Token 使用量（流式响应在数据流结束时记录）
+        let (response, usage) = record_response_usage(&state, &ctx, response).await;
 
         // 完成 Flow 捕获并检查响应拦截
         // **Validates: Requirements 2.1, 2.5**
         if let Some(fid) = flow_id {
             if is_success {
-                let llm_response = build_llm_response(
-                    200,
-                    "", // 内容在 provider_calls 中处理
-                    Some((estimated_input_tokens, estimated_output_tokens)),
-                );
+                // 内容在 provider_calls 中处理
+                let llm_response = build_llm_response(200, "", usage);
 
                 // 检查是否需要拦截响应
                 if let Some(modified_response) = check_response_intercept(
@@ -974,6 +1071,7 @@ pub async fn chat_completions(
 
     let kiro = state.kiro.read().await;
 
+    ctx.request_body = capture_body(&state, &request);
     match kiro.call_api(&request).await {
         Ok(resp) => {
             let status = resp.status();
@@ -1015,21 +1113,8 @@ pub async fn chat_completions(
                             })
                         };
 
-                        // 估算 Token 数量（基于字符数，约 4 字符 = 1 token）
-                        let estimated_output_tokens = (parsed.content.len() / 4) as u32;
-                        // 估算输入 Token（基于请求消息）
-                        let estimated_input_tokens = request
-                            .messages
-                            .iter()
-                            .map(|m| {
-                                let content_len = match &m.content {
-                                    Some(c) => message_content_len(c),
-                                    None => 0,
-                                };
-                                content_len / 4
-                            })
-                            .sum::<usize>()
-                            as u32;
+                        // Kiro 不返回 Token 数，按上游报告的上下文占用比例和输出内容换算
+                        let (input_tokens, output_tokens) = parsed.estimate_tokens();
 
                         let response = serde_json::json!({
                             "id": format!("chatcmpl-{}", uuid::Uuid::new_v4()),
@@ -1045,12 +1130,13 @@ pub async fn chat_completions(
                                 "finish_reason": if has_tool_calls { "tool_calls" } else { "stop" }
                             }],
                             "usage": {
-                                "prompt_tokens": estimated_input_tokens,
-                                "completion_tokens": estimated_output_tokens,
-                                "total_tokens": estimated_input_tokens + estimated_output_tokens
+                                "prompt_tokens": input_tokens,
+                                "completion_tokens": output_tokens,
+                                "total_tokens": input_tokens + output_tokens
                             }
                         });
                         // 记录成功请求统计
+                        ctx.response_body = capture_body(&state, &response);
                         record_request_telemetry(
                             &state,
                             &ctx,
@@ -1058,19 +1144,14 @@ pub async fn chat_completions(
                             None,
                         );
                         // 记录 Token 使用量
-                        record_token_usage(
-                            &state,
-                            &ctx,
-                            Some(estimated_input_tokens),
-                            Some(estimated_output_tokens),
-                        );
+                        record_token_usage(&state, &ctx, Some(input_tokens), Some(output_tokens));
                         // 完成 Flow 捕获并检查响应拦截
                         // **Validates: Requirements 2.1, 2.5**
                         if let Some(fid) = &flow_id {
                             let llm_response = build_llm_response(
                                 200,
                                 &parsed.content,
-                                Some((estimated_input_tokens, estimated_output_tokens)),
+                                Some((input_tokens, output_tokens)),
                             );
 
                             // 检查是否需要拦截响应
@@ -1415,11 +1496,12 @@ pub async fn chat_completions(
 
 pub async fn anthropic_messages(
     State(state): State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     headers: HeaderMap,
     Json(mut request): Json<AnthropicMessagesRequest>,
 ) -> Response {
     // 使用 Anthropic 格式的认证验证（优先检查 x-api-key）
-    if let Err(e) = verify_api_key_anthropic(&headers, &state.api_key).await {
+    if let Err(e) = verify_api_key_anthropic(&headers, &state.api_key()).await {
         state
             .logs
             .write()
@@ -1429,7 +1511,14 @@ pub async fn anthropic_messages(
     }
 
     // 创建请求上下文
-    let mut ctx = RequestContext::new(request.model.clone()).with_stream(request.stream);
+    let mut ctx = RequestContext::new(request.model.clone())
+        .with_stream(request.stream)
+        .with_route("POST", "/v1/messages")
+        .with_client_key(crate::middleware::client_keys::client_key_id(&headers))
+        .with_source(UsageSource::from_request(
+            &headers,
+            connect_info.map(|ConnectInfo(addr)| addr),
+        ));
 
     // 详细记录请求信息
     let msg_count = request.messages.len();
@@ -1444,7 +1533,13 @@ pub async fn anthropic_messages(
     );
 
     // 使用 RequestProcessor 解析模型别名和路由
-    let provider = state.processor.resolve_and_route(&mut ctx).await;
+    let mut provider = state.processor.resolve_and_route(&mut ctx).await;
+
+    // 模型路由（命中时覆盖别名映射和 Provider 选择）
+    let model_route = route_model_request(&state, &mut ctx, &headers, &request).await;
+    if model_route.is_some() {
+        provider = ctx.provider.unwrap_or(provider);
+    }
 
     // 更新请求中的模型名为解析后的模型
     if ctx.resolved_model != ctx.original_model {
@@ -1508,6 +1603,13 @@ pub async fn anthropic_messages(
     // 根据客户端类型选择 Provider
     // **Validates: Requirements 3.1, 3.3, 3.4**
     let (selected_provider, client_type) = select_provider_for_client(&headers, &state).await;
+    // 模型路由指定的 Provider 优先
+    let selected_provider = model_route.map_or(selected_provider, |route| route.provider);
+
+    // Token 预算检查
+    if let Some(resp) = check_token_budget(&state, &mut ctx, &selected_provider).await {
+        return resp;
+    }
 
     // 记录客户端检测和 Provider 选择结果
     state.logs.write().await.add(
@@ -1598,7 +1700,10 @@ pub async fn anthropic_messages(
             }
         }
 
+        ctx.request_body = capture_body(&state, &request);
         let response = call_provider_anthropic(&state, &cred, &request, flow_id.as_deref()).await;
+        let response = capture_response_body(&state, &mut ctx, response).await;
+        let response = instrument_stream_ttft(&state, &ctx, response);
 
         // 记录请求统计
         let is_success = response.status().is_success();
@@ -1609,43 +1714,14 @@ pub async fn anthropic_messages(
         };
         record_request_telemetry(&state, &ctx, status, None);
 
-        // 估算 Token 使用量
-        let estimated_input_tokens = request
-            .messages
-            .iter()
-            .map(|m| {
-                let content_len = match &m.content {
-                    serde_json::Value::String(s) => s.len(),
-                    serde_json::Value::Array(arr) => arr
-                        .iter()
-                        .filter_map(|v| v.get("text").and_then(|t| t.as_str()))
-                        .map(|s| s.len())
-                        .sum(),
-                    _ => 0,
-                };
-                content_len / 4
-            })
-            .sum::<usize>() as u32;
-        let estimated_output_tokens = if is_success { 100u32 } else { 0u32 };
-
-        if is_success {
-            record_token_usage(
-                &state,
-                &ctx,
-                Some(estimated_input_tokens),
-                Some(estimated_output_tokens),
-            );
-        }
+        // 记录上游报告的 Token 使用量（流式响应在数据流结束时记录）
+        let (response, usage) = record_response_usage(&state, &ctx, response).await;
 
         // 完成 Flow 捕获并检查响应拦截
         // **Validates: Requirements 2.1, 2.5**
         if let Some(fid) = flow_id {
             if is_success {
-                let llm_response = build_llm_response(
-                    200,
-                    "",
-                    Some((estimated_input_tokens, estimated_output_tokens)),
-                );
+                let llm_response = build_llm_response(200, "", usage);
 
                 // 检查是否需要拦截响应
                 if let Some(modified_response) = check_response_intercept(
@@ -1898,6 +1974,10 @@ pub async fn anthropic_messages(
                             );
                         }
 
+                        // Kiro 不返回 Token 数，按上游报告的上下文占用比例和输出内容换算后计入预算
+                        let (input_tokens, output_tokens) = parsed.estimate_tokens();
+                        record_token_usage(&state, &ctx, Some(input_tokens), Some(output_tokens));
+
                         // 如果请求流式响应，返回 SSE 格式
                         if request.stream {
                             // 完成 Flow 捕获并检查响应拦截（流式）
@@ -2424,8 +2504,7 @@ fn build_stream_error_response(
                 })
             )
         }
-        // TODO: 任务 6 完成后，添加 GeminiStream 分支
-        StreamingFormat::OpenAiSse => {
+        StreamingFormat::OpenAiSse | StreamingFormat::GeminiSse => {
             format!(
                 "data: {}\n\n",
                 serde_json::json!({
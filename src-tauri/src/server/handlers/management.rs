@@ -165,7 +165,7 @@ pub async fn management_status(State(state): State<AppState>) -> impl IntoRespon
         requests,
         uptime_secs: 0, // TODO: Track actual uptime
         version: env!("CARGO_PKG_VERSION").to_string(),
-        tls_enabled: false,
+        tls_enabled: state.base_url.starts_with("https://"),
         default_provider,
     };
 
@@ -493,7 +493,7 @@ pub async fn management_get_config(State(state): State<AppState>) -> impl IntoRe
         server: ManagementServerConfigInfo {
             host: "0.0.0.0".to_string(),
             port: 8999,
-            tls_enabled: false,
+            tls_enabled: state.base_url.starts_with("https://"),
         },
         routing: ManagementRoutingConfigInfo {
             default_provider,
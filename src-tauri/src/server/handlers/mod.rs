@@ -6,12 +6,16 @@ pub mod api;
 pub mod credentials_api;
 pub mod kiro_credential;
 pub mod management;
+pub mod passthrough;
 pub mod provider_calls;
+pub mod realtime;
 pub mod websocket;
 
 pub use api::*;
 pub use credentials_api::*;
 pub use kiro_credential::*;
 pub use management::*;
+pub use passthrough::*;
 pub use provider_calls::*;
+pub use realtime::*;
 pub use websocket::*;
@@ -0,0 +1,179 @@
+//! OpenAI 透传端点处理器
+//!
+//! 处理聊天之外的 OpenAI 兼容端点（图片生成、文本向量化），
+//! 从凭证池中选择 OpenAI API Key 凭证并原样转发请求体。
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::models::provider_pool_model::{CredentialData, ProviderCredential};
+use crate::providers::openai_custom::OpenAICustomProvider;
+use crate::server::AppState;
+
+use super::verify_api_key;
+
+/// 构建 OpenAI 格式的错误响应
+fn openai_error(status: StatusCode, message: impl Into<String>) -> Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "error": {
+                "message": message.into()
+            }
+        })),
+    )
+        .into_response()
+}
+
+/// 从凭证池选择 OpenAI API Key 凭证
+fn select_openai_key_credential(
+    state: &AppState,
+    model: Option<&str>,
+) -> Result<ProviderCredential, Response> {
+    let db = state
+        .db
+        .as_ref()
+        .ok_or_else(|| openai_error(StatusCode::SERVICE_UNAVAILABLE, "Database not available"))?;
+
+    match state.pool_service.select_credential(db, "openai", model) {
+        Ok(Some(cred)) => Ok(cred),
+        Ok(None) => Err(openai_error(
+            StatusCode::NOT_FOUND,
+            "没有可用的 OpenAI API Key 凭证，请先添加凭证",
+        )),
+        Err(e) => Err(openai_error(StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+}
+
+/// 选择 OpenAI API Key 凭证并创建对应的 Provider
+fn select_openai_provider(
+    state: &AppState,
+    model: Option<&str>,
+) -> Result<(ProviderCredential, OpenAICustomProvider), Response> {
+    let credential = select_openai_key_credential(state, model)?;
+
+    let provider = match &credential.credential {
+        CredentialData::OpenAIKey { api_key, base_url } => {
+            OpenAICustomProvider::with_config(api_key.clone(), base_url.clone())
+        }
+        _ => {
+            return Err(openai_error(
+                StatusCode::BAD_REQUEST,
+                "该端点只支持 OpenAI API Key 凭证",
+            ))
+        }
+    };
+
+    Ok((credential, provider))
+}
+
+/// 将上游响应原样转换为 axum 响应，并更新凭证健康状态
+async fn relay_upstream_response(
+    state: &AppState,
+    credential: &ProviderCredential,
+    model: Option<&str>,
+    resp: reqwest::Response,
+) -> Response {
+    let status = StatusCode::from_u16(resp.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    let body = resp.text().await.unwrap_or_default();
+
+    if let Some(db) = &state.db {
+        if status.is_success() {
+            let _ = state.pool_service.mark_healthy(db, &credential.uuid, model);
+        } else {
+            let _ = state
+                .pool_service
+                .mark_unhealthy(db, &credential.uuid, Some(&body));
+        }
+    }
+
+    match serde_json::from_str::<serde_json::Value>(&body) {
+        Ok(json) => (status, Json(json)).into_response(),
+        Err(_) => (status, body).into_response(),
+    }
+}
+
+/// POST /v1/images/generations - 图片生成
+pub async fn images_generations(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<serde_json::Value>,
+) -> Response {
+    if let Err(e) = verify_api_key(&headers, &state.api_key()).await {
+        state
+            .logs
+            .write()
+            .await
+            .add("warn", "Unauthorized request to /v1/images/generations");
+        return e.into_response();
+    }
+
+    let model = request["model"].as_str().map(|s| s.to_string());
+
+    state.logs.write().await.add(
+        "info",
+        &format!("POST /v1/images/generations model={:?}", model),
+    );
+
+    let (credential, provider) = match select_openai_provider(&state, model.as_deref()) {
+        Ok(selected) => selected,
+        Err(resp) => return resp,
+    };
+
+    match provider.images_generations(&request).await {
+        Ok(resp) => relay_upstream_response(&state, &credential, model.as_deref(), resp).await,
+        Err(e) => {
+            state
+                .logs
+                .write()
+                .await
+                .add("error", &format!("[IMAGES] 请求失败: {}", e));
+            openai_error(StatusCode::BAD_GATEWAY, e.to_string())
+        }
+    }
+}
+
+/// POST /v1/embeddings - 文本向量化
+pub async fn embeddings(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<serde_json::Value>,
+) -> Response {
+    if let Err(e) = verify_api_key(&headers, &state.api_key()).await {
+        state
+            .logs
+            .write()
+            .await
+            .add("warn", "Unauthorized request to /v1/embeddings");
+        return e.into_response();
+    }
+
+    let model = request["model"].as_str().map(|s| s.to_string());
+
+    state
+        .logs
+        .write()
+        .await
+        .add("info", &format!("POST /v1/embeddings model={:?}", model));
+
+    let (credential, provider) = match select_openai_provider(&state, model.as_deref()) {
+        Ok(selected) => selected,
+        Err(resp) => return resp,
+    };
+
+    match provider.embeddings(&request).await {
+        Ok(resp) => relay_upstream_response(&state, &credential, model.as_deref(), resp).await,
+        Err(e) => {
+            state
+                .logs
+                .write()
+                .await
+                .add("error", &format!("[EMBEDDINGS] 请求失败: {}", e));
+            openai_error(StatusCode::BAD_GATEWAY, e.to_string())
+        }
+    }
+}
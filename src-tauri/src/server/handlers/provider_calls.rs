@@ -50,16 +50,18 @@ use axum::{
 use futures::StreamExt;
 
 use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
+use crate::converter::gemini::{convert_gemini_response_to_openai, convert_openai_to_gemini};
 use crate::converter::openai_to_antigravity::{
     convert_antigravity_to_openai_response, convert_openai_to_antigravity_with_context,
 };
 use crate::flow_monitor::models::{FlowError, FlowErrorType};
 use crate::flow_monitor::stream_rebuilder::StreamFormat;
 use crate::models::anthropic::AnthropicMessagesRequest;
-use crate::models::openai::ChatCompletionRequest;
+use crate::models::openai::{ChatCompletionRequest, ToolCall};
 use crate::models::provider_pool_model::{CredentialData, ProviderCredential};
 use crate::providers::{
-    AntigravityProvider, ClaudeCustomProvider, KiroProvider, OpenAICustomProvider, VertexProvider,
+    AntigravityProvider, ClaudeCustomProvider, GeminiApiError, GeminiApiKeyCredential,
+    GeminiApiKeyProvider, KiroProvider, OpenAICustomProvider, VertexProvider,
 };
 use crate::server::AppState;
 use crate::server_utils::{
@@ -72,6 +74,58 @@ use crate::streaming::{
     StreamConfig, StreamContext, StreamError, StreamFormat as StreamingFormat, StreamManager,
     StreamResponse,
 };
+use tokio::sync::OwnedSemaphorePermit;
+
+/// 等待凭证所属 Provider 的并发名额，排队超时时按路由的错误格式返回 503
+async fn acquire_provider_slot(
+    state: &AppState,
+    credential: &ProviderCredential,
+    format: StreamingFormat,
+) -> Result<Option<OwnedSemaphorePermit>, Response> {
+    let provider = credential.provider_type.to_string();
+    state
+        .provider_concurrency
+        .acquire(&provider)
+        .await
+        .map_err(|message| {
+            tracing::warn!("[CONCURRENCY] {}", message);
+            let body = match format {
+                StreamingFormat::AnthropicSse => serde_json::json!({
+                    "type": "error",
+                    "error": {
+                        "type": "overloaded_error",
+                        "message": message
+                    }
+                }),
+                _ => serde_json::json!({
+                    "error": {
+                        "type": "server_error",
+                        "code": "provider_concurrency_limit",
+                        "message": message
+                    }
+                }),
+            };
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                [(header::RETRY_AFTER, "1")],
+                Json(body),
+            )
+                .into_response()
+        })
+}
+
+/// 响应体传输完成（或客户端断开）后再释放并发名额，流式响应在整个传输期间占用名额
+fn release_slot_after_body(response: Response, permit: Option<OwnedSemaphorePermit>) -> Response {
+    let Some(permit) = permit else {
+        return response;
+    };
+    let (parts, body) = response.into_parts();
+    let body = body.into_data_stream().map(move |chunk| {
+        let _permit = &permit;
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(body))
+}
 
 /// 根据凭证调用 Provider (Anthropic 格式)
 ///
@@ -80,11 +134,36 @@ use crate::streaming::{
 /// - `credential`: 凭证信息
 /// - `request`: Anthropic 格式请求
 /// - `flow_id`: Flow ID（可选，用于流式响应处理）
+#[tracing::instrument(
+    name = "proxy.upstream",
+    skip_all,
+    fields(
+        provider = ?credential.provider_type,
+        model = %request.model,
+        stream = request.stream,
+        format = "anthropic",
+    )
+)]
 pub async fn call_provider_anthropic(
     state: &AppState,
     credential: &ProviderCredential,
     request: &AnthropicMessagesRequest,
     flow_id: Option<&str>,
+) -> Response {
+    let permit = match acquire_provider_slot(state, credential, StreamingFormat::AnthropicSse).await
+    {
+        Ok(permit) => permit,
+        Err(resp) => return resp,
+    };
+    let response = send_provider_anthropic(state, credential, request, flow_id).await;
+    release_slot_after_body(response, permit)
+}
+
+async fn send_provider_anthropic(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &AnthropicMessagesRequest,
+    flow_id: Option<&str>,
 ) -> Response {
     // 如果是流式请求且有 flow_id，设置流式状态
     if request.stream {
@@ -94,6 +173,8 @@ pub async fn call_provider_anthropic(
             let format = match &credential.credential {
                 CredentialData::KiroOAuth { .. } => StreamFormat::Anthropic, // Kiro 流式响应被转换为 Anthropic SSE 格式
                 CredentialData::ClaudeKey { .. } => StreamFormat::Anthropic,
+                CredentialData::OpenAIKey { .. } => StreamFormat::Anthropic, // OpenAI SSE 被转换为 Anthropic SSE 格式
+                CredentialData::GeminiApiKey { .. } => StreamFormat::Anthropic, // Gemini SSE 被转换为 Anthropic SSE 格式
                 CredentialData::AntigravityOAuth { .. } => StreamFormat::Gemini,
                 _ => StreamFormat::Unknown,
             };
@@ -429,6 +510,46 @@ pub async fn call_provider_anthropic(
         CredentialData::OpenAIKey { api_key, base_url } => {
             let openai = OpenAICustomProvider::with_config(api_key.clone(), base_url.clone());
             let openai_request = convert_anthropic_to_openai(request);
+
+            // 流式请求：OpenAI SSE 实时转换为 Anthropic SSE 事件
+            if request.stream {
+                return match openai.call_api_stream(&openai_request).await {
+                    Ok(stream_response) => {
+                        if let Some(db) = &state.db {
+                            let _ = state.pool_service.mark_healthy(
+                                db,
+                                &credential.uuid,
+                                Some(&request.model),
+                            );
+                            let _ = state.pool_service.record_usage(db, &credential.uuid);
+                        }
+                        handle_streaming_response(
+                            state,
+                            flow_id,
+                            stream_response,
+                            StreamingFormat::OpenAiSse,
+                            StreamingFormat::AnthropicSse,
+                            &request.model,
+                        )
+                        .await
+                    }
+                    Err(e) => {
+                        if let Some(db) = &state.db {
+                            let _ = state.pool_service.mark_unhealthy(
+                                db,
+                                &credential.uuid,
+                                Some(&e.to_string()),
+                            );
+                        }
+                        (
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            Json(serde_json::json!({"error": {"message": e.to_string()}})),
+                        )
+                            .into_response()
+                    }
+                };
+            }
+
             match openai.call_api(&openai_request).await {
                 Ok(resp) => {
                     if resp.status().is_success() {
@@ -437,15 +558,7 @@ pub async fn call_provider_anthropic(
                                 if let Ok(openai_resp) =
                                     serde_json::from_str::<serde_json::Value>(&body)
                                 {
-                                    let content = openai_resp["choices"][0]["message"]["content"]
-                                        .as_str()
-                                        .unwrap_or("");
-                                    let parsed = CWParsedResponse {
-                                        content: content.to_string(),
-                                        tool_calls: Vec::new(),
-                                        usage_credits: 0.0,
-                                        context_usage_percentage: 0.0,
-                                    };
+                                    let parsed = parse_openai_response(&openai_resp);
                                     // 记录成功
                                     if let Some(db) = &state.db {
                                         let _ = state.pool_service.mark_healthy(
@@ -456,11 +569,7 @@ pub async fn call_provider_anthropic(
                                         let _ =
                                             state.pool_service.record_usage(db, &credential.uuid);
                                     }
-                                    if request.stream {
-                                        build_anthropic_stream_response(&request.model, &parsed)
-                                    } else {
-                                        build_anthropic_response(&request.model, &parsed)
-                                    }
+                                    build_anthropic_response(&request.model, &parsed)
                                 } else {
                                     // 记录解析失败
                                     if let Some(db) = &state.db {
@@ -493,16 +602,19 @@ pub async fn call_provider_anthropic(
                             }
                         }
                     } else {
+                        let status = resp.status();
                         let body = resp.text().await.unwrap_or_default();
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_unhealthy(
+                            let _ = state.pool_service.record_http_failure(
                                 db,
                                 &credential.uuid,
-                                Some(&body),
+                                status.as_u16(),
+                                &body,
                             );
                         }
                         (
-                            StatusCode::INTERNAL_SERVER_ERROR,
+                            StatusCode::from_u16(status.as_u16())
+                                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
                             Json(serde_json::json!({"error": {"message": body}})),
                         )
                             .into_response()
@@ -636,10 +748,11 @@ pub async fn call_provider_anthropic(
                                     ),
                                 );
                                 if let Some(db) = &state.db {
-                                    let _ = state.pool_service.mark_unhealthy(
+                                    let _ = state.pool_service.record_http_failure(
                                         db,
                                         &credential.uuid,
-                                        Some(&body),
+                                        status.as_u16(),
+                                        &body,
                                     );
                                 }
                                 (
@@ -730,13 +843,26 @@ pub async fn call_provider_anthropic(
                 }
             }
         }
-        // Gemini API Key credentials - not supported for Anthropic format
+        // Gemini API Key：Anthropic → OpenAI → Gemini，响应按相反方向转换
         CredentialData::GeminiApiKey { .. } => {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({"error": {"message": "Gemini API Key credentials do not support Anthropic format"}})),
-            )
-                .into_response()
+            let openai_request = convert_anthropic_to_openai(request);
+            match call_gemini_api_key(state, credential, &openai_request).await {
+                Ok(GeminiApiKeyResult::Stream(stream_response)) => {
+                    handle_streaming_response(
+                        state,
+                        flow_id,
+                        stream_response,
+                        StreamingFormat::GeminiSse,
+                        StreamingFormat::AnthropicSse,
+                        &request.model,
+                    )
+                    .await
+                }
+                Ok(GeminiApiKeyResult::Json(openai_resp)) => {
+                    build_anthropic_response(&request.model, &parse_openai_response(&openai_resp))
+                }
+                Err(resp) => resp,
+            }
         }
         // 新增的凭证类型暂不支持 Anthropic 格式
         CredentialData::CodexOAuth { .. }
@@ -759,11 +885,35 @@ pub async fn call_provider_anthropic(
 /// - `credential`: 凭证信息
 /// - `request`: OpenAI 格式请求
 /// - `flow_id`: Flow ID（可选，用于流式响应处理）
+#[tracing::instrument(
+    name = "proxy.upstream",
+    skip_all,
+    fields(
+        provider = ?credential.provider_type,
+        model = %request.model,
+        stream = request.stream,
+        format = "openai",
+    )
+)]
 pub async fn call_provider_openai(
     state: &AppState,
     credential: &ProviderCredential,
     request: &ChatCompletionRequest,
-    _flow_id: Option<&str>,
+    flow_id: Option<&str>,
+) -> Response {
+    let permit = match acquire_provider_slot(state, credential, StreamingFormat::OpenAiSse).await {
+        Ok(permit) => permit,
+        Err(resp) => return resp,
+    };
+    let response = send_provider_openai(state, credential, request, flow_id).await;
+    release_slot_after_body(response, permit)
+}
+
+async fn send_provider_openai(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
+    flow_id: Option<&str>,
 ) -> Response {
     let _start_time = std::time::Instant::now();
 
@@ -1031,13 +1181,19 @@ pub async fn call_provider_openai(
                                 .into_response(),
                         }
                     } else {
-                        // 记录 API 调用失败
+                        // 记录 API 调用失败（只有认证、额度和 5xx 错误计入凭证错误次数）
                         let body = resp.text().await.unwrap_or_default();
                         if let Some(db) = &state.db {
-                            let _ = state.pool_service.mark_unhealthy(db, &credential.uuid, Some(&format!("HTTP {}: {}", status, safe_truncate(&body, 100))));
+                            let _ = state.pool_service.record_http_failure(
+                                db,
+                                &credential.uuid,
+                                status.as_u16(),
+                                &format!("HTTP {}: {}", status, safe_truncate(&body, 100)),
+                            );
                         }
                         (
-                            StatusCode::INTERNAL_SERVER_ERROR,
+                            StatusCode::from_u16(status.as_u16())
+                                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
                             Json(serde_json::json!({"error": {"message": body}})),
                         )
                             .into_response()
@@ -1182,9 +1338,19 @@ pub async fn call_provider_openai(
                                 .into_response(),
                         }
                     } else {
+                        let status = resp.status();
                         let body = resp.text().await.unwrap_or_default();
+                        if let Some(db) = &state.db {
+                            let _ = state.pool_service.record_http_failure(
+                                db,
+                                &credential.uuid,
+                                status.as_u16(),
+                                &body,
+                            );
+                        }
                         (
-                            StatusCode::INTERNAL_SERVER_ERROR,
+                            StatusCode::from_u16(status.as_u16())
+                                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
                             Json(serde_json::json!({"error": {"message": body}})),
                         )
                             .into_response()
@@ -1335,13 +1501,23 @@ pub async fn call_provider_openai(
                 Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": {"message": e.to_string()}}))).into_response(),
             }
         }
-        // Gemini API Key credentials - not supported for OpenAI format yet
+        // Gemini API Key：OpenAI → Gemini，响应按相反方向转换
         CredentialData::GeminiApiKey { .. } => {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({"error": {"message": "Gemini API Key credentials do not support OpenAI format yet"}})),
-            )
-                .into_response()
+            match call_gemini_api_key(state, credential, request).await {
+                Ok(GeminiApiKeyResult::Stream(stream_response)) => {
+                    handle_streaming_response(
+                        state,
+                        flow_id,
+                        stream_response,
+                        StreamingFormat::GeminiSse,
+                        StreamingFormat::OpenAiSse,
+                        &request.model,
+                    )
+                    .await
+                }
+                Ok(GeminiApiKeyResult::Json(openai_resp)) => Json(openai_resp).into_response(),
+                Err(resp) => resp,
+            }
         }
         // 新增的凭证类型暂不支持 OpenAI 格式
         CredentialData::CodexOAuth { .. }
@@ -1357,6 +1533,124 @@ pub async fn call_provider_openai(
     }
 }
 
+// ============================================================================
+// Gemini API Key 支持
+// ============================================================================
+
+/// Gemini API Key 调用结果
+enum GeminiApiKeyResult {
+    /// Gemini SSE 流（由调用方转换为入口协议的流式格式）
+    Stream(StreamResponse),
+    /// 已转换为 OpenAI 格式的非流式响应
+    Json(serde_json::Value),
+}
+
+/// 使用 Gemini API Key 凭证调用 Gemini API
+///
+/// 请求以 OpenAI 格式传入，转换为 Gemini 原生格式后发送。失败时直接返回错误响应：
+/// 上游的错误状态码原样返回，网络等错误返回 502。
+async fn call_gemini_api_key(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
+) -> Result<GeminiApiKeyResult, Response> {
+    let CredentialData::GeminiApiKey {
+        api_key,
+        base_url,
+        excluded_models,
+    } = &credential.credential
+    else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": {"message": "Not a Gemini API Key credential"}})),
+        )
+            .into_response());
+    };
+
+    let gemini_credential = GeminiApiKeyCredential::new(credential.uuid.clone(), api_key.clone())
+        .with_base_url(base_url.clone())
+        .with_excluded_models(excluded_models.clone());
+    if !gemini_credential.supports_model(&request.model) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": {"message": format!("Model {} is excluded for this Gemini API Key credential", request.model)}})),
+        )
+            .into_response());
+    }
+
+    let provider = GeminiApiKeyProvider::new();
+    let body = convert_openai_to_gemini(request);
+    tracing::info!(
+        "[GEMINI_API_KEY] model={}, stream={}, uuid={}",
+        request.model,
+        request.stream,
+        &credential.uuid[..8]
+    );
+
+    let result = if request.stream {
+        provider
+            .stream_generate_content(&gemini_credential, &request.model, &body)
+            .await
+            .map(|resp| GeminiApiKeyResult::Stream(response_to_stream(resp)))
+    } else {
+        provider
+            .generate_content(&gemini_credential, &request.model, &body)
+            .await
+            .map(|resp| {
+                GeminiApiKeyResult::Json(convert_gemini_response_to_openai(&resp, &request.model))
+            })
+    };
+
+    match result {
+        Ok(result) => {
+            if let Some(db) = &state.db {
+                let _ = state
+                    .pool_service
+                    .mark_healthy(db, &credential.uuid, Some(&request.model));
+                let _ = state.pool_service.record_usage(db, &credential.uuid);
+            }
+            Ok(result)
+        }
+        Err(e) => {
+            let upstream_status = GeminiApiError::status_of(e.as_ref());
+            if let Some(db) = &state.db {
+                let _ = match upstream_status {
+                    Some(status) => state.pool_service.record_http_failure(
+                        db,
+                        &credential.uuid,
+                        status,
+                        &e.to_string(),
+                    ),
+                    None => state.pool_service.mark_unhealthy(
+                        db,
+                        &credential.uuid,
+                        Some(&e.to_string()),
+                    ),
+                };
+            }
+            Err((
+                upstream_status
+                    .and_then(|status| StatusCode::from_u16(status).ok())
+                    .unwrap_or(StatusCode::BAD_GATEWAY),
+                Json(serde_json::json!({"error": {"message": e.to_string()}})),
+            )
+                .into_response())
+        }
+    }
+}
+
+/// 从 OpenAI 非流式响应中提取文本和工具调用（用于构建 Anthropic 响应）
+fn parse_openai_response(openai_resp: &serde_json::Value) -> CWParsedResponse {
+    let message = &openai_resp["choices"][0]["message"];
+    CWParsedResponse {
+        content: message["content"].as_str().unwrap_or("").to_string(),
+        tool_calls: serde_json::from_value::<Vec<ToolCall>>(message["tool_calls"].clone())
+            .unwrap_or_default(),
+        usage_credits: 0.0,
+        context_usage_percentage: 0.0,
+    }
+}
+
 // ============================================================================
 // 流式传输支持
 // ============================================================================
@@ -1375,10 +1669,10 @@ pub fn get_stream_format_for_credential(credential: &ProviderCredential) -> Stre
         CredentialData::KiroOAuth { .. } => StreamingFormat::AwsEventStream,
         CredentialData::ClaudeKey { .. } => StreamingFormat::AnthropicSse,
         CredentialData::OpenAIKey { .. } => StreamingFormat::OpenAiSse,
+        CredentialData::GeminiApiKey { .. } => StreamingFormat::GeminiSse,
         // TODO: 任务 6 完成后，将这些改为 GeminiStream
         CredentialData::AntigravityOAuth { .. } => StreamingFormat::OpenAiSse,
         CredentialData::GeminiOAuth { .. } => StreamingFormat::OpenAiSse,
-        CredentialData::GeminiApiKey { .. } => StreamingFormat::OpenAiSse,
         CredentialData::VertexKey { .. } => StreamingFormat::OpenAiSse,
         _ => StreamingFormat::OpenAiSse,
     }
@@ -0,0 +1,304 @@
+//! OpenAI Realtime API 透传
+//!
+//! 将客户端的 `/v1/realtime` WebSocket 会话代理到上游 OpenAI 兼容端点：
+//! - 客户端使用 ProxyCast 的 API 密钥认证（请求头，或浏览器使用的
+//!   `openai-insecure-api-key.<key>` 子协议）；客户端 Key 由客户端 Key 中间件
+//!   按 `?model=` 校验范围，通过后与主密钥同样放行
+//! - 从凭证池选择 OpenAI 凭证，连接上游时注入 `Authorization` 头
+//! - 先连接上游再完成升级，上游拒绝时客户端直接收到对应的 HTTP 状态码
+//! - 双向原样转发文本（事件 JSON）和二进制（音频）帧，任一端关闭时关闭另一端
+
+use axum::{
+    body::Body,
+    extract::{
+        ws::{CloseFrame, Message as WsMessage, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use futures::{SinkExt, StreamExt as FuturesStreamExt};
+use serde::Deserialize;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::{
+    self,
+    client::IntoClientRequest,
+    http::HeaderValue,
+    protocol::{frame::coding::CloseCode, CloseFrame as UpstreamCloseFrame},
+    Message as UpstreamMessage,
+};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::middleware::client_keys::{client_key_id, websocket_protocol_key};
+use crate::models::provider_pool_model::CredentialData;
+use crate::providers::OpenAICustomProvider;
+use crate::server::AppState;
+
+type UpstreamSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// 浏览器客户端通过子协议声明 beta 版本时使用的值
+const BETA_PROTOCOL: &str = "openai-beta.realtime-v1";
+
+/// Realtime 查询参数
+#[derive(Debug, Deserialize)]
+pub struct RealtimeQueryParams {
+    pub model: String,
+}
+
+/// 客户端提供的 API 密钥（请求头优先，其次子协议）
+fn client_api_key(headers: &HeaderMap) -> Option<String> {
+    let auth = headers
+        .get("authorization")
+        .or_else(|| headers.get("x-api-key"))
+        .and_then(|v| v.to_str().ok());
+    match auth {
+        Some(s) => Some(s.strip_prefix("Bearer ").unwrap_or(s).to_string()),
+        None => websocket_protocol_key(headers),
+    }
+}
+
+/// 客户端要求的 `OpenAI-Beta` 值（请求头或子协议）
+fn client_beta(headers: &HeaderMap) -> Option<String> {
+    if let Some(beta) = headers.get("openai-beta").and_then(|v| v.to_str().ok()) {
+        return Some(beta.to_string());
+    }
+    headers
+        .get("sec-websocket-protocol")
+        .and_then(|v| v.to_str().ok())?
+        .split(',')
+        .any(|p| p.trim() == BETA_PROTOCOL)
+        .then(|| "realtime=v1".to_string())
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    axum::http::Response::builder()
+        .status(status)
+        .body(Body::from(message.to_string()))
+        .unwrap()
+        .into_response()
+}
+
+/// Realtime WebSocket 升级处理器
+pub async fn realtime_upgrade_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Query(params): Query<RealtimeQueryParams>,
+    headers: HeaderMap,
+) -> Response {
+    // 客户端 Key 中间件校验通过后会写入 Key ID（客户端自带的同名请求头已被移除）
+    match client_api_key(&headers) {
+        Some(_) if client_key_id(&headers).is_some() => {}
+        Some(k) if k == state.api_key() => {}
+        Some(_) => return error_response(StatusCode::UNAUTHORIZED, "Invalid API key"),
+        None => return error_response(StatusCode::UNAUTHORIZED, "No API key provided"),
+    }
+
+    let Some(db) = state.db.as_ref() else {
+        return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database not available");
+    };
+    let credential = match state
+        .pool_service
+        .select_credential(db, "openai", Some(&params.model))
+    {
+        Ok(Some(c)) => c,
+        Ok(None) => {
+            return error_response(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "No OpenAI credential available for realtime",
+            )
+        }
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, &e),
+    };
+    let CredentialData::OpenAIKey { api_key, base_url } = &credential.credential else {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "Realtime API requires an OpenAI API key credential",
+        );
+    };
+
+    let provider = OpenAICustomProvider::with_config(api_key.clone(), base_url.clone());
+    let url = provider.build_realtime_url(&params.model);
+    let mut request = match url.as_str().into_client_request() {
+        Ok(r) => r,
+        Err(e) => {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                &format!("Invalid realtime URL: {}", e),
+            )
+        }
+    };
+    match HeaderValue::from_str(&format!("Bearer {}", api_key)) {
+        Ok(v) => {
+            request.headers_mut().insert("authorization", v);
+        }
+        Err(_) => return error_response(StatusCode::BAD_REQUEST, "Invalid upstream API key"),
+    }
+    if let Some(beta) = client_beta(&headers).and_then(|b| HeaderValue::from_str(&b).ok()) {
+        request.headers_mut().insert("openai-beta", beta);
+    }
+
+    let upstream = match tokio_tungstenite::connect_async(request).await {
+        Ok((socket, _)) => socket,
+        Err(e) => {
+            let status = match &e {
+                tungstenite::Error::Http(resp) => {
+                    StatusCode::from_u16(resp.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY)
+                }
+                _ => StatusCode::BAD_GATEWAY,
+            };
+            if matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) {
+                let _ = state.pool_service.mark_unhealthy(
+                    db,
+                    &credential.uuid,
+                    Some(&format!("Realtime connect failed: {}", e)),
+                );
+            }
+            state.logs.write().await.add(
+                "error",
+                &format!("[REALTIME] Upstream connect failed: {}", e),
+            );
+            return error_response(status, &format!("Realtime upstream error: {}", e));
+        }
+    };
+    let _ = state
+        .pool_service
+        .mark_healthy(db, &credential.uuid, Some(&params.model));
+
+    state.logs.write().await.add(
+        "info",
+        &format!(
+            "[REALTIME] Session opened: model={} credential={}",
+            params.model,
+            &credential.uuid[..8]
+        ),
+    );
+
+    let model = params.model;
+    ws.protocols(["realtime"])
+        .on_upgrade(move |socket| async move {
+            proxy_realtime(socket, upstream).await;
+            state.logs.write().await.add(
+                "info",
+                &format!("[REALTIME] Session closed: model={}", model),
+            );
+        })
+        .into_response()
+}
+
+/// 双向转发客户端与上游之间的帧，任一方向结束时关闭另一端
+async fn proxy_realtime(client: WebSocket, upstream: UpstreamSocket) {
+    let (mut client_tx, mut client_rx) = client.split();
+    let (mut upstream_tx, mut upstream_rx) = upstream.split();
+
+    let client_to_upstream = async {
+        while let Some(Ok(msg)) = client_rx.next().await {
+            let closing = matches!(msg, WsMessage::Close(_));
+            if upstream_tx.send(to_upstream(msg)).await.is_err() || closing {
+                break;
+            }
+        }
+        let _ = upstream_tx.close().await;
+    };
+
+    let upstream_to_client = async {
+        while let Some(Ok(msg)) = upstream_rx.next().await {
+            let Some(msg) = to_client(msg) else {
+                continue;
+            };
+            let closing = matches!(msg, WsMessage::Close(_));
+            if client_tx.send(msg).await.is_err() || closing {
+                break;
+            }
+        }
+        let _ = client_tx.close().await;
+    };
+
+    tokio::select! {
+        _ = client_to_upstream => {}
+        _ = upstream_to_client => {}
+    }
+}
+
+/// 客户端帧 → 上游帧
+fn to_upstream(msg: WsMessage) -> UpstreamMessage {
+    match msg {
+        WsMessage::Text(text) => UpstreamMessage::Text(text),
+        WsMessage::Binary(data) => UpstreamMessage::Binary(data),
+        WsMessage::Ping(data) => UpstreamMessage::Ping(data),
+        WsMessage::Pong(data) => UpstreamMessage::Pong(data),
+        WsMessage::Close(frame) => UpstreamMessage::Close(frame.map(|f| UpstreamCloseFrame {
+            code: CloseCode::from(f.code),
+            reason: f.reason,
+        })),
+    }
+}
+
+/// 上游帧 → 客户端帧（原始帧不会在读取时出现，直接忽略）
+fn to_client(msg: UpstreamMessage) -> Option<WsMessage> {
+    Some(match msg {
+        UpstreamMessage::Text(text) => WsMessage::Text(text),
+        UpstreamMessage::Binary(data) => WsMessage::Binary(data),
+        UpstreamMessage::Ping(data) => WsMessage::Ping(data),
+        UpstreamMessage::Pong(data) => WsMessage::Pong(data),
+        UpstreamMessage::Close(frame) => WsMessage::Close(frame.map(|f| CloseFrame {
+            code: f.code.into(),
+            reason: f.reason,
+        })),
+        UpstreamMessage::Frame(_) => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_api_key_and_beta_from_protocols() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "sec-websocket-protocol",
+            "realtime, openai-insecure-api-key.pc-123, openai-beta.realtime-v1"
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(client_api_key(&headers).as_deref(), Some("pc-123"));
+        assert_eq!(client_beta(&headers).as_deref(), Some("realtime=v1"));
+
+        headers.insert("authorization", "Bearer pc-456".parse().unwrap());
+        assert_eq!(client_api_key(&headers).as_deref(), Some("pc-456"));
+    }
+
+    #[test]
+    fn test_realtime_url() {
+        let provider = OpenAICustomProvider::with_config(
+            "sk".to_string(),
+            Some("https://api.example.com/v1/".to_string()),
+        );
+        assert_eq!(
+            provider.build_realtime_url("gpt-4o-realtime-preview"),
+            "wss://api.example.com/v1/realtime?model=gpt-4o-realtime-preview"
+        );
+        let provider = OpenAICustomProvider::with_config(
+            "sk".to_string(),
+            Some("http://localhost:8080".to_string()),
+        );
+        assert!(provider
+            .build_realtime_url("m")
+            .starts_with("ws://localhost:8080/v1/realtime"));
+    }
+
+    #[test]
+    fn test_close_frame_roundtrip() {
+        let msg = to_upstream(WsMessage::Close(Some(CloseFrame {
+            code: 1000,
+            reason: "bye".into(),
+        })));
+        match to_client(msg) {
+            Some(WsMessage::Close(Some(f))) => {
+                assert_eq!(f.code, 1000);
+                assert_eq!(f.reason, "bye");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -14,7 +14,7 @@ use axum::{
 use futures::{SinkExt, StreamExt as FuturesStreamExt};
 use serde::Deserialize;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, OwnedSemaphorePermit};
 
 use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
 use crate::converter::openai_to_antigravity::{
@@ -67,7 +67,7 @@ pub async fn ws_upgrade_handler(
     // 如果没有提供任何认证信息，允许连接（用于内部 Flow Monitor）
     // 但会在日志中记录
     let authenticated = match key {
-        Some(k) if k == state.api_key => true,
+        Some(k) if k == state.api_key() => true,
         Some(_) => {
             return axum::http::Response::builder()
                 .status(401)
@@ -668,11 +668,33 @@ async fn handle_ws_anthropic_messages(
     }
 }
 
+/// 等待凭证所属 Provider 的并发名额（与 HTTP 路由共用，排队超时时返回错误）
+async fn acquire_provider_slot_for_ws(
+    state: &AppState,
+    credential: &ProviderCredential,
+) -> Result<Option<OwnedSemaphorePermit>, String> {
+    let provider = credential.provider_type.to_string();
+    state
+        .provider_concurrency
+        .acquire(&provider)
+        .await
+        .inspect_err(|message| tracing::warn!("[CONCURRENCY] {}", message))
+}
+
 /// WebSocket 专用的 OpenAI 格式 Provider 调用
 pub async fn call_provider_openai_for_ws(
     state: &AppState,
     credential: &ProviderCredential,
     request: &ChatCompletionRequest,
+) -> Result<serde_json::Value, String> {
+    let _permit = acquire_provider_slot_for_ws(state, credential).await?;
+    send_provider_openai_for_ws(state, credential, request).await
+}
+
+async fn send_provider_openai_for_ws(
+    state: &AppState,
+    credential: &ProviderCredential,
+    request: &ChatCompletionRequest,
 ) -> Result<serde_json::Value, String> {
     use crate::models::provider_pool_model::CredentialData;
 
@@ -934,6 +956,7 @@ pub async fn call_provider_anthropic_for_ws(
 ) -> Result<serde_json::Value, String> {
     use crate::models::provider_pool_model::CredentialData;
 
+    let _permit = acquire_provider_slot_for_ws(state, credential).await?;
     match &credential.credential {
         CredentialData::ClaudeKey { api_key, base_url } => {
             // 打印 Claude 代理 URL 用于调试
@@ -980,9 +1003,9 @@ pub async fn call_provider_anthropic_for_ws(
             }
         }
         _ => {
-            // 转换为 OpenAI 格式并调用（健康状态更新在 call_provider_openai_for_ws 中处理）
+            // 转换为 OpenAI 格式并调用（健康状态更新在 send_provider_openai_for_ws 中处理，已占用并发名额）
             let openai_request = convert_anthropic_to_openai(request);
-            let result = call_provider_openai_for_ws(state, credential, &openai_request).await?;
+            let result = send_provider_openai_for_ws(state, credential, &openai_request).await?;
 
             // 转换响应为 Anthropic 格式
             Ok(serde_json::json!({
@@ -0,0 +1,386 @@
+//! 代理服务器压测
+//!
+//! 在本机启动一个 OpenAI 兼容的模拟上游，通过本地 API Server 的
+//! `/{uuid}/v1/chat/completions` 路由向它发送合成请求，统计吞吐量和延迟分位数，
+//! 用于评估 ProxyCast 作为团队网关时的承载能力。压测流量不会到达真实 Provider。
+
+use axum::{
+    body::Body,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{oneshot, Semaphore};
+use tokio::task::JoinSet;
+
+/// 单次压测最大请求数
+pub const MAX_LOADTEST_REQUESTS: u32 = 10000;
+/// 最大并发数
+pub const MAX_LOADTEST_CONCURRENCY: u32 = 256;
+/// 单个请求超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+/// 报告中保留的不同错误信息数量
+const MAX_ERROR_SAMPLES: usize = 5;
+/// 模拟上游使用的模型名
+pub const MOCK_MODEL: &str = "proxycast-loadtest";
+
+/// 请求负载类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadProfile {
+    /// 短消息（约 100 字节）
+    #[default]
+    Small,
+    /// 中等上下文（约 4KB）
+    Medium,
+    /// 长上下文（约 64KB）
+    Large,
+    /// 短消息 + 流式响应
+    Streaming,
+}
+
+impl PayloadProfile {
+    /// 构造该类型的请求体
+    pub fn request_body(&self) -> serde_json::Value {
+        let prompt = match self {
+            Self::Small | Self::Streaming => "Reply with a short greeting.".to_string(),
+            Self::Medium => filler_text(4 * 1024),
+            Self::Large => filler_text(64 * 1024),
+        };
+        serde_json::json!({
+            "model": MOCK_MODEL,
+            "messages": [
+                {"role": "system", "content": "You are a load test target."},
+                {"role": "user", "content": prompt}
+            ],
+            "max_tokens": 64,
+            "stream": *self == Self::Streaming,
+        })
+    }
+}
+
+/// 生成约 `bytes` 字节的填充文本
+fn filler_text(bytes: usize) -> String {
+    const SENTENCE: &str = "The quick brown fox jumps over the lazy dog. ";
+    SENTENCE.repeat(bytes / SENTENCE.len() + 1)[..bytes].to_string()
+}
+
+/// 延迟统计（毫秒）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencySummary {
+    pub min_ms: f64,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencySummary {
+    /// 根据所有成功请求的延迟计算统计值
+    pub fn from_latencies(mut latencies: Vec<f64>) -> Self {
+        if latencies.is_empty() {
+            return Self::default();
+        }
+        latencies.sort_by(|a, b| a.total_cmp(b));
+        let mean = latencies.iter().sum::<f64>() / latencies.len() as f64;
+        Self {
+            min_ms: latencies[0],
+            mean_ms: mean,
+            p50_ms: percentile(&latencies, 50.0),
+            p90_ms: percentile(&latencies, 90.0),
+            p99_ms: percentile(&latencies, 99.0),
+            max_ms: latencies[latencies.len() - 1],
+        }
+    }
+}
+
+/// 最近秩法计算分位数（`sorted` 需已升序排列且非空）
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// 压测报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadTestReport {
+    pub requests: u32,
+    pub concurrency: u32,
+    pub payload_profile: PayloadProfile,
+    pub succeeded: u32,
+    pub failed: u32,
+    /// 总耗时（毫秒）
+    pub duration_ms: u64,
+    /// 每秒完成的请求数（含失败）
+    pub requests_per_second: f64,
+    /// 成功请求的延迟统计
+    pub latency: LatencySummary,
+    /// 失败原因示例（去重后最多 5 条）
+    pub errors: Vec<String>,
+}
+
+/// 校验压测参数
+pub fn validate_params(requests: u32, concurrency: u32) -> Result<(), String> {
+    if requests == 0 || requests > MAX_LOADTEST_REQUESTS {
+        return Err(format!("请求数必须在 1-{} 之间", MAX_LOADTEST_REQUESTS));
+    }
+    if concurrency == 0 || concurrency > MAX_LOADTEST_CONCURRENCY {
+        return Err(format!("并发数必须在 1-{} 之间", MAX_LOADTEST_CONCURRENCY));
+    }
+    Ok(())
+}
+
+/// 模拟上游（OpenAI 兼容），Drop 时自动关闭
+pub struct MockUpstream {
+    base_url: String,
+    shutdown_tx: Option<oneshot::Sender<()>>,
+}
+
+impl MockUpstream {
+    /// 在 127.0.0.1 的随机端口上启动模拟上游
+    pub async fn start() -> Result<Self, String> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| format!("启动模拟上游失败: {}", e))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|e| format!("获取模拟上游地址失败: {}", e))?;
+
+        let app = Router::new().route("/v1/chat/completions", post(mock_chat_completions));
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        tokio::spawn(async move {
+            let server = axum::serve(listener, app).with_graceful_shutdown(async move {
+                let _ = shutdown_rx.await;
+            });
+            if let Err(e) = server.await {
+                tracing::error!("[LOADTEST] 模拟上游异常退出: {}", e);
+            }
+        });
+
+        Ok(Self {
+            base_url: format!("http://{}", addr),
+            shutdown_tx: Some(shutdown_tx),
+        })
+    }
+
+    /// 模拟上游地址（作为 OpenAI 凭证的 base_url）
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+}
+
+impl Drop for MockUpstream {
+    fn drop(&mut self) {
+        if let Some(tx) = self.shutdown_tx.take() {
+            let _ = tx.send(());
+        }
+    }
+}
+
+/// 模拟上游的 chat completions 接口，按 `stream` 返回 JSON 或 SSE
+async fn mock_chat_completions(Json(request): Json<serde_json::Value>) -> Response {
+    let stream = request
+        .get("stream")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let id = format!("chatcmpl-{}", uuid::Uuid::new_v4().simple());
+    let created = chrono::Utc::now().timestamp();
+
+    if !stream {
+        return Json(serde_json::json!({
+            "id": id,
+            "object": "chat.completion",
+            "created": created,
+            "model": MOCK_MODEL,
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "Hello from the ProxyCast load test."},
+                "finish_reason": "stop"
+            }],
+            "usage": {"prompt_tokens": 16, "completion_tokens": 8, "total_tokens": 24}
+        }))
+        .into_response();
+    }
+
+    let mut body = String::new();
+    for word in ["Hello", " from", " the", " ProxyCast", " load", " test."] {
+        let chunk = serde_json::json!({
+            "id": id,
+            "object": "chat.completion.chunk",
+            "created": created,
+            "model": MOCK_MODEL,
+            "choices": [{"index": 0, "delta": {"content": word}, "finish_reason": null}]
+        });
+        body.push_str(&format!("data: {}\n\n", chunk));
+    }
+    let last = serde_json::json!({
+        "id": id,
+        "object": "chat.completion.chunk",
+        "created": created,
+        "model": MOCK_MODEL,
+        "choices": [{"index": 0, "delta": {}, "finish_reason": "stop"}]
+    });
+    body.push_str(&format!("data: {}\n\ndata: [DONE]\n\n", last));
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/event-stream")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+/// 向 `endpoint` 发送 `requests` 个请求（最多 `concurrency` 个同时进行）并生成报告
+///
+/// 流式请求的延迟按读取完整响应体计算。
+pub async fn run_load_test(
+    endpoint: &str,
+    api_key: &str,
+    requests: u32,
+    concurrency: u32,
+    profile: PayloadProfile,
+) -> Result<LoadTestReport, String> {
+    validate_params(requests, concurrency)?;
+
+    let client = reqwest::Client::builder()
+        .no_proxy()
+        .timeout(REQUEST_TIMEOUT)
+        .pool_max_idle_per_host(concurrency as usize)
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+    let body = Arc::new(serde_json::to_vec(&profile.request_body()).map_err(|e| e.to_string())?);
+    let semaphore = Arc::new(Semaphore::new(concurrency as usize));
+    let mut tasks = JoinSet::new();
+
+    let started = Instant::now();
+    for _ in 0..requests {
+        let permit = semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|e| e.to_string())?;
+        let client = client.clone();
+        let body = body.clone();
+        let endpoint = endpoint.to_string();
+        let api_key = api_key.to_string();
+        tasks.spawn(async move {
+            let _permit = permit;
+            let request_started = Instant::now();
+            let result = send_request(&client, &endpoint, &api_key, body.as_ref().clone()).await;
+            result.map(|_| request_started.elapsed().as_secs_f64() * 1000.0)
+        });
+    }
+
+    let mut latencies = Vec::with_capacity(requests as usize);
+    let mut errors: Vec<String> = Vec::new();
+    let mut failed = 0u32;
+    while let Some(joined) = tasks.join_next().await {
+        match joined.map_err(|e| e.to_string()).and_then(|r| r) {
+            Ok(latency) => latencies.push(latency),
+            Err(e) => {
+                failed += 1;
+                if errors.len() < MAX_ERROR_SAMPLES && !errors.contains(&e) {
+                    errors.push(e);
+                }
+            }
+        }
+    }
+    let elapsed = started.elapsed();
+
+    Ok(LoadTestReport {
+        requests,
+        concurrency,
+        payload_profile: profile,
+        succeeded: latencies.len() as u32,
+        failed,
+        duration_ms: elapsed.as_millis() as u64,
+        requests_per_second: requests as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+        latency: LatencySummary::from_latencies(latencies),
+        errors,
+    })
+}
+
+/// 发送单个请求并读取完整响应体
+async fn send_request(
+    client: &reqwest::Client,
+    endpoint: &str,
+    api_key: &str,
+    body: Vec<u8>,
+) -> Result<(), String> {
+    let response = client
+        .post(endpoint)
+        .bearer_auth(api_key)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+    let status = response.status();
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("读取响应失败: {}", e))?;
+    if !status.is_success() {
+        let text = String::from_utf8_lossy(&bytes);
+        let snippet: String = text.chars().take(200).collect();
+        return Err(format!("HTTP {}: {}", status.as_u16(), snippet));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_summary() {
+        let latencies: Vec<f64> = (1..=100).rev().map(|v| v as f64).collect();
+        let summary = LatencySummary::from_latencies(latencies);
+        assert_eq!(summary.min_ms, 1.0);
+        assert_eq!(summary.max_ms, 100.0);
+        assert_eq!(summary.mean_ms, 50.5);
+        assert_eq!(summary.p50_ms, 50.0);
+        assert_eq!(summary.p90_ms, 90.0);
+        assert_eq!(summary.p99_ms, 99.0);
+
+        assert_eq!(
+            LatencySummary::from_latencies(vec![]),
+            LatencySummary::default()
+        );
+        assert_eq!(LatencySummary::from_latencies(vec![7.0]).p99_ms, 7.0);
+    }
+
+    #[test]
+    fn test_payload_profiles() {
+        let small = PayloadProfile::Small.request_body();
+        assert_eq!(small["stream"], false);
+        assert_eq!(PayloadProfile::Streaming.request_body()["stream"], true);
+
+        let large = PayloadProfile::Large.request_body();
+        assert_eq!(
+            large["messages"][1]["content"].as_str().unwrap().len(),
+            64 * 1024
+        );
+        assert!(validate_params(0, 1).is_err());
+        assert!(validate_params(10, MAX_LOADTEST_CONCURRENCY + 1).is_err());
+        assert!(validate_params(10, 4).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_run_load_test_against_mock_upstream() {
+        let mock = MockUpstream::start().await.unwrap();
+        let endpoint = format!("{}/v1/chat/completions", mock.base_url());
+
+        for profile in [PayloadProfile::Small, PayloadProfile::Streaming] {
+            let report = run_load_test(&endpoint, "test-key", 20, 4, profile)
+                .await
+                .unwrap();
+            assert_eq!(report.succeeded, 20);
+            assert_eq!(report.failed, 0);
+            assert!(report.latency.max_ms >= report.latency.p50_ms);
+        }
+    }
+}
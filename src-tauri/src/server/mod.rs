@@ -1,18 +1,27 @@
 //! HTTP API 服务器
 
 pub mod client_detector;
+pub mod loadtest;
+pub mod tls;
 
 use crate::config::{
     Config, ConfigChangeEvent, ConfigChangeKind, ConfigManager, EndpointProvidersConfig,
-    FileWatcher, HotReloadManager, ReloadResult,
+    FileWatcher, HotReloadManager, ReloadResult, ServerConfig,
 };
 use crate::converter::anthropic_to_openai::convert_anthropic_to_openai;
+use crate::converter::gemini::{
+    convert_gemini_to_openai_request, convert_openai_response_to_gemini,
+};
 use crate::credential::CredentialSyncService;
 use crate::database::dao::provider_pool::ProviderPoolDao;
 use crate::database::DbConnection;
 use crate::flow_monitor::{FlowInterceptor, FlowMonitor, FlowMonitorConfig};
 use crate::injection::Injector;
 use crate::logger::LogStore;
+use crate::middleware::{
+    ClientKeyStore, CorsPolicy, IpAllowlist, RateLimiter, RequestPipeline, RequestSigner,
+    ResponseCache, SharedApiKey,
+};
 use crate::models::anthropic::*;
 use crate::models::openai::*;
 use crate::models::provider_pool_model::CredentialData;
@@ -20,7 +29,9 @@ use crate::models::route_model::{RouteInfo, RouteListResponse};
 use crate::processor::{RequestContext, RequestProcessor};
 use crate::providers::antigravity::AntigravityProvider;
 use crate::providers::claude_custom::ClaudeCustomProvider;
-use crate::providers::gemini::GeminiProvider;
+use crate::providers::gemini::{
+    GeminiApiError, GeminiApiKeyCredential, GeminiApiKeyProvider, GeminiProvider,
+};
 use crate::providers::kiro::KiroProvider;
 use crate::providers::openai_custom::OpenAICustomProvider;
 use crate::providers::qwen::QwenProvider;
@@ -29,12 +40,22 @@ use crate::server_utils::{
     models, parse_cw_response,
 };
 use crate::services::kiro_event_service::KiroEventService;
+use crate::services::provider_concurrency_service::ProviderConcurrencyService;
 use crate::services::provider_pool_service::ProviderPoolService;
+use crate::services::token_budget_service::TokenBudgetService;
 use crate::services::token_cache_service::TokenCacheService;
+use crate::services::upstream_probe_service::{
+    probe_upstreams, HealthStatus, UpstreamHealthReport,
+};
+use crate::services::usage_recorder::{
+    ReportedUsage, SseUsageScanner, TokenBudgetSink, UsageEvent, UsageRecorder, UsageSink,
+    UsageSource,
+};
+use crate::streaming::converter::{StreamConverter, StreamFormat};
 use crate::websocket::{WsConfig, WsConnectionManager, WsStats};
 use axum::{
     body::Body,
-    extract::{DefaultBodyLimit, Path, State},
+    extract::{DefaultBodyLimit, Path, Query, State},
     http::{HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     routing::{get, post},
@@ -43,7 +64,7 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::{oneshot, RwLock};
+use tokio::sync::{mpsc, oneshot, RwLock};
 
 /// 记录请求统计到遥测系统
 pub fn record_request_telemetry(
@@ -85,6 +106,20 @@ pub fn record_request_telemetry(
     // 设置重试次数
     log.retry_count = ctx.retry_count;
 
+    // 设置请求路由和截断后的请求/响应体
+    log.method = ctx.method.clone();
+    log.path = ctx.path.clone();
+    log.request_body = ctx.request_body.clone();
+    log.response_body = ctx.response_body.clone();
+    log.client_key = ctx.client_key.clone();
+
+    state.metrics.record_request(
+        provider,
+        &ctx.resolved_model,
+        status,
+        ctx.start_time.elapsed(),
+    );
+
     // 记录到统计聚合器
     {
         let stats = state.processor.stats.write();
@@ -106,35 +141,222 @@ pub fn record_request_telemetry(
     );
 }
 
-/// 记录 Token 使用量到遥测系统
+/// 启用请求体记录时，返回序列化并截断后的请求体或响应体
+pub fn capture_body<T: Serialize>(state: &AppState, value: &T) -> Option<String> {
+    let logger = state.request_logger.as_ref()?;
+    if !logger.captures_bodies() {
+        return None;
+    }
+    serde_json::to_string(value)
+        .ok()
+        .and_then(|body| logger.capture_body(&body))
+}
+
+/// 启用请求体记录时，读取非流式响应体保存到请求上下文，并返回重建后的响应
+pub async fn capture_response_body(
+    state: &AppState,
+    ctx: &mut RequestContext,
+    response: Response,
+) -> Response {
+    let Some(logger) = state
+        .request_logger
+        .as_ref()
+        .filter(|l| l.captures_bodies())
+    else {
+        return response;
+    };
+    if ctx.is_stream {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => {
+            ctx.response_body = logger.capture_body(&String::from_utf8_lossy(&bytes));
+            Response::from_parts(parts, Body::from(bytes))
+        }
+        Err(e) => {
+            tracing::warn!("[TELEMETRY] 读取响应体失败: {}", e);
+            Response::from_parts(parts, Body::empty())
+        }
+    }
+}
+
+/// 流式响应输出第一块数据时记录首 Token 时间（从收到请求开始计时）
+pub fn instrument_stream_ttft(
+    state: &AppState,
+    ctx: &RequestContext,
+    response: Response,
+) -> Response {
+    if !ctx.is_stream || !response.status().is_success() {
+        return response;
+    }
+
+    let metrics = state.metrics.clone();
+    let provider = ctx.provider.unwrap_or(crate::ProviderType::Kiro);
+    let model = ctx.resolved_model.clone();
+    let start_time = ctx.start_time;
+    let mut recorded = false;
+
+    let (parts, body) = response.into_parts();
+    let stream = futures::StreamExt::inspect(body.into_data_stream(), move |chunk| {
+        if !recorded && chunk.as_ref().map_or(false, |bytes| !bytes.is_empty()) {
+            recorded = true;
+            metrics.record_ttft(provider, &model, start_time.elapsed());
+        }
+    });
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+/// 读取上游响应中报告的实际 Token 用量并记录
+///
+/// 非流式响应解析响应体后立即记录，并返回 (input, output) 供 Flow 使用；
+/// 流式响应在数据流结束（或客户端断开）时记录 SSE 事件中累计的用量，返回 None。
+/// 上游没有报告用量时不记录。
+pub async fn record_response_usage(
+    state: &AppState,
+    ctx: &RequestContext,
+    response: Response,
+) -> (Response, Option<(u32, u32)>) {
+    if !response.status().is_success() {
+        return (response, None);
+    }
+
+    if ctx.is_stream {
+        let mut tap = StreamUsageTap {
+            state: state.clone(),
+            ctx: ctx.clone(),
+            scanner: Some(SseUsageScanner::default()),
+        };
+        let (parts, body) = response.into_parts();
+        let stream = futures::StreamExt::inspect(body.into_data_stream(), move |chunk| {
+            if let (Ok(bytes), Some(scanner)) = (chunk, tap.scanner.as_mut()) {
+                scanner.feed(bytes);
+            }
+        });
+        return (Response::from_parts(parts, Body::from_stream(stream)), None);
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!("[TOKEN] 读取响应体失败: {}", e);
+            return (Response::from_parts(parts, Body::empty()), None);
+        }
+    };
+    // 响应缓存命中时按写入缓存的请求记录 Provider 和模型
+    parts.extensions.insert(ctx.clone());
+    let usage = serde_json::from_slice::<serde_json::Value>(&bytes)
+        .map(|value| ReportedUsage::from_json(&value))
+        .unwrap_or_default();
+    let tokens = record_reported_usage(state, ctx, usage);
+    (Response::from_parts(parts, Body::from(bytes)), tokens)
+}
+
+/// 记录上游报告的用量，返回 (input, output)（未报告时返回 None）
+fn record_reported_usage(
+    state: &AppState,
+    ctx: &RequestContext,
+    usage: ReportedUsage,
+) -> Option<(u32, u32)> {
+    if usage.is_empty() {
+        tracing::debug!("[TOKEN] request_id={} 上游未报告用量", ctx.request_id);
+        return None;
+    }
+    record_token_usage(state, ctx, usage.input_tokens, usage.output_tokens);
+    Some((
+        usage.input_tokens.unwrap_or(0),
+        usage.output_tokens.unwrap_or(0),
+    ))
+}
+
+/// 流式响应的用量累计器，数据流被释放时记录累计的用量
+struct StreamUsageTap {
+    state: AppState,
+    ctx: RequestContext,
+    scanner: Option<SseUsageScanner>,
+}
+
+impl Drop for StreamUsageTap {
+    fn drop(&mut self) {
+        if let Some(scanner) = self.scanner.take() {
+            record_reported_usage(&self.state, &self.ctx, scanner.finish());
+        }
+    }
+}
+
+/// 响应缓存命中时记录遥测和用量（命中的请求不经过处理器）
+struct CacheHitRecorder {
+    state: AppState,
+}
+
+impl crate::middleware::CacheHitSink for CacheHitRecorder {
+    fn on_hit(&self, hit: crate::middleware::CacheHit) {
+        // 写入缓存的请求未提供上下文时（如 Kiro 直连），按请求中的模型记录
+        let mut ctx = hit
+            .context
+            .unwrap_or_else(|| RequestContext::new(hit.model.clone()));
+        ctx.request_id = uuid::Uuid::new_v4().to_string();
+        ctx.start_time = hit.started;
+        ctx.timestamp = chrono::Utc::now();
+        ctx.retry_count = 0;
+        ctx.method = Some(hit.method);
+        ctx.path = Some(hit.path);
+        ctx.request_body = None;
+        ctx.response_body = None;
+        ctx.client_key = hit.client_key;
+        ctx.source = hit.source;
+
+        record_request_telemetry(
+            &self.state,
+            &ctx,
+            crate::telemetry::RequestStatus::Success,
+            None,
+        );
+        let usage = serde_json::from_slice::<serde_json::Value>(&hit.body)
+            .map(|value| ReportedUsage::from_json(&value))
+            .unwrap_or_default();
+        record_reported_usage(&self.state, &ctx, usage);
+    }
+}
+
+/// 记录 Token 使用量
+///
+/// 上报到统一用量记录，由其更新遥测 Token 追踪、Prometheus 指标和 Token 预算
 pub fn record_token_usage(
     state: &AppState,
     ctx: &RequestContext,
     input_tokens: Option<u32>,
     output_tokens: Option<u32>,
 ) {
-    use crate::telemetry::{TokenSource, TokenUsageRecord};
-
     // 只有当至少有一个 Token 值时才记录
     if input_tokens.is_none() && output_tokens.is_none() {
         return;
     }
 
-    let provider = ctx.provider.unwrap_or(crate::ProviderType::Kiro);
-    let record = TokenUsageRecord::new(
-        uuid::Uuid::new_v4().to_string(),
-        provider,
-        ctx.resolved_model.clone(),
-        input_tokens.unwrap_or(0),
-        output_tokens.unwrap_or(0),
-        TokenSource::Actual,
-    )
-    .with_request_id(ctx.request_id.clone());
+    // Agent 经本地 API Server 转发的请求由 Agent 自行上报
+    let source = ctx.source.unwrap_or(UsageSource::Proxy);
+    if !source.reports_itself() {
+        let mut event = UsageEvent::new(
+            source,
+            &ctx.resolved_model,
+            input_tokens.unwrap_or(0),
+            output_tokens.unwrap_or(0),
+        )
+        .with_provider_type(ctx.provider.unwrap_or(crate::ProviderType::Kiro))
+        .with_client_key(ctx.client_key.clone())
+        .with_latency_ms(ctx.elapsed_ms())
+        .with_request_id(ctx.request_id.clone());
+        if let Some(provider) = &ctx.provider_name {
+            event = event.with_provider(provider.clone());
+        }
+        state.usage_recorder.record(event);
+    }
 
-    // 记录到 Token 追踪器
-    {
-        let tokens = state.processor.tokens.write();
-        tokens.record(record);
+    // 回填到请求日志
+    if let Some(logger) = &state.request_logger {
+        logger.set_tokens(&ctx.request_id, input_tokens, output_tokens);
     }
 
     tracing::debug!(
@@ -154,6 +376,15 @@ pub struct ServerStatus {
     pub uptime_secs: u64,
 }
 
+/// 配置重载结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigReloadReport {
+    /// 服务器是否正在运行（未运行时配置在下次启动时生效）
+    pub running: bool,
+    /// 监听地址、端口或 TLS 配置已变更，需要重启服务器才能生效
+    pub restart_required: bool,
+}
+
 pub struct ServerState {
     pub config: Config,
     pub running: bool,
@@ -169,6 +400,30 @@ pub struct ServerState {
     /// 服务器运行时使用的 API key（启动时从配置复制）
     /// 用于 test_api 命令，确保测试使用的 API key 和服务器一致
     pub running_api_key: Option<String>,
+    /// 客户端 API Key（与运行中的服务器共享，修改后立即生效）
+    pub client_keys: Arc<ClientKeyStore>,
+    /// 响应缓存（与运行中的服务器共享）
+    pub response_cache: Arc<ResponseCache>,
+    /// 请求中间件管道（与运行中的服务器共享）
+    pub request_pipeline: Arc<RequestPipeline>,
+    /// 客户端 IP 白名单（与运行中的服务器共享）
+    pub ip_allowlist: Arc<IpAllowlist>,
+    /// 请求签名校验（与运行中的服务器共享）
+    pub request_signer: Arc<RequestSigner>,
+    /// CORS 策略（与运行中的服务器共享）
+    pub cors_policy: Arc<CorsPolicy>,
+    /// 按 API Key 限流（与运行中的服务器共享）
+    pub rate_limiter: Arc<RateLimiter>,
+    /// Token 预算（与运行中的服务器共享）
+    pub token_budget: Arc<TokenBudgetService>,
+    /// Provider 并发限制（与运行中的服务器共享）
+    pub provider_concurrency: Arc<ProviderConcurrencyService>,
+    /// 统一用量记录（与运行中的服务器和 NativeAgent 共享）
+    pub usage_recorder: Arc<UsageRecorder>,
+    /// 向运行中的服务器推送新配置
+    reload_tx: Option<mpsc::UnboundedSender<Config>>,
+    /// 服务器启动时的配置（用于判断变更是否需要重启）
+    running_server_config: Option<ServerConfig>,
 }
 
 impl ServerState {
@@ -179,6 +434,22 @@ impl ServerState {
         let openai_custom = OpenAICustomProvider::new();
         let claude_custom = ClaudeCustomProvider::new();
         let default_provider_ref = Arc::new(RwLock::new(config.default_provider.clone()));
+        let client_keys = Arc::new(ClientKeyStore::new(config.server.client_keys.clone()));
+        let response_cache = Arc::new(ResponseCache::new(config.server.response_cache.clone()));
+        let request_pipeline = Arc::new(RequestPipeline::new(&config.server.request_middlewares));
+        let ip_allowlist = Arc::new(IpAllowlist::new(
+            config.server.parse_allowed_ips().unwrap_or_default(),
+        ));
+        let request_signer = Arc::new(RequestSigner::new(config.server.request_signing.clone()));
+        let cors_policy = Arc::new(CorsPolicy::new(config.server.cors.clone()));
+        let rate_limiter = Arc::new(RateLimiter::new(config.server.rate_limit.clone()));
+        let token_budget = Arc::new(TokenBudgetService::new(config.server.token_budget.clone()));
+        let provider_concurrency = Arc::new(ProviderConcurrencyService::new(
+            config.server.provider_concurrency.clone(),
+        ));
+        let usage_recorder = Arc::new(UsageRecorder::new(
+            config.server.token_budget.prices.clone(),
+        ));
 
         Self {
             config,
@@ -193,6 +464,18 @@ impl ServerState {
             default_provider_ref,
             shutdown_tx: None,
             running_api_key: None,
+            client_keys,
+            response_cache,
+            request_pipeline,
+            ip_allowlist,
+            request_signer,
+            cors_policy,
+            rate_limiter,
+            token_budget,
+            provider_concurrency,
+            usage_recorder,
+            reload_tx: None,
+            running_server_config: None,
         }
     }
 
@@ -211,6 +494,58 @@ impl ServerState {
         self.requests = self.requests.saturating_add(1);
     }
 
+    /// 将当前配置同步到与服务器共享的组件
+    fn sync_shared_components(&self) -> Result<(), String> {
+        let server = &self.config.server;
+        self.client_keys.set_keys(server.client_keys.clone());
+        self.response_cache
+            .set_config(server.response_cache.clone());
+        self.request_pipeline
+            .set_config(&server.request_middlewares);
+        self.ip_allowlist.set_networks(server.parse_allowed_ips()?);
+        self.request_signer
+            .set_config(server.request_signing.clone());
+        self.cors_policy.set_config(server.cors.clone());
+        self.rate_limiter.set_config(server.rate_limit.clone());
+        self.token_budget.set_config(server.token_budget.clone());
+        self.provider_concurrency
+            .set_config(server.provider_concurrency.clone());
+        self.usage_recorder
+            .set_prices(server.token_budget.prices.clone());
+        Ok(())
+    }
+
+    /// 应用新配置
+    ///
+    /// 服务器运行中时，Provider、路由、Key 等变更直接应用到运行中的服务器，
+    /// 无需重启；只有监听地址、端口和 TLS 的变更需要重启服务器
+    pub async fn apply_config(&mut self, config: Config) -> Result<ConfigReloadReport, String> {
+        config.server.validate_network()?;
+
+        let restart_required = self
+            .running_server_config
+            .as_ref()
+            .map_or(false, |running| {
+                running.host != config.server.host
+                    || running.port != config.server.port
+                    || running.tls != config.server.tls
+            });
+
+        self.config = config;
+        self.sync_shared_components()?;
+        *self.default_provider_ref.write().await = self.config.default_provider.clone();
+        if let Some(tx) = &self.reload_tx {
+            if tx.send(self.config.clone()).is_ok() {
+                self.running_api_key = Some(self.config.server.api_key.clone());
+            }
+        }
+
+        Ok(ConfigReloadReport {
+            running: self.running,
+            restart_required,
+        })
+    }
+
     pub async fn start(
         &mut self,
         logs: Arc<RwLock<LogStore>>,
@@ -270,6 +605,7 @@ impl ServerState {
         if self.running {
             return Ok(());
         }
+        self.config.server.validate_network()?;
 
         let (tx, rx) = oneshot::channel();
         self.shutdown_tx = Some(tx);
@@ -279,6 +615,18 @@ impl ServerState {
         let api_key = self.config.server.api_key.clone();
         let api_key_for_state = api_key.clone(); // 用于保存到 running_api_key
         let default_provider_ref = self.default_provider_ref.clone();
+        self.sync_shared_components()?;
+        let client_keys = self.client_keys.clone();
+        let response_cache = self.response_cache.clone();
+        let request_pipeline = self.request_pipeline.clone();
+        let ip_allowlist = self.ip_allowlist.clone();
+        let request_signer = self.request_signer.clone();
+        let cors_policy = self.cors_policy.clone();
+        let rate_limiter = self.rate_limiter.clone();
+        let token_budget = self.token_budget.clone();
+        let provider_concurrency = self.provider_concurrency.clone();
+        let usage_recorder = self.usage_recorder.clone();
+        let (reload_tx, reload_rx) = mpsc::unbounded_channel();
 
         // 重新加载凭证
         let _ = self.kiro_provider.load_credentials().await;
@@ -320,6 +668,17 @@ impl ServerState {
                 shared_flow_interceptor,
                 Some(config),
                 Some(config_path),
+                client_keys,
+                response_cache,
+                request_pipeline,
+                ip_allowlist,
+                request_signer,
+                cors_policy,
+                rate_limiter,
+                token_budget,
+                provider_concurrency,
+                usage_recorder,
+                reload_rx,
             )
             .await
             {
@@ -327,6 +686,8 @@ impl ServerState {
             }
         });
 
+        self.reload_tx = Some(reload_tx);
+        self.running_server_config = Some(self.config.server.clone());
         self.running = true;
         self.start_time = Some(std::time::Instant::now());
         // 保存服务器运行时使用的 API key，用于 test_api 命令
@@ -334,6 +695,21 @@ impl ServerState {
         Ok(())
     }
 
+    /// 当前生效的监听配置
+    ///
+    /// 服务器运行中时为启动时的配置（地址、端口和 TLS 变更需要重启后才生效）
+    pub fn listen_config(&self) -> &ServerConfig {
+        self.running_server_config
+            .as_ref()
+            .unwrap_or(&self.config.server)
+    }
+
+    /// 本机客户端访问 API Server 的基础 URL
+    pub fn local_base_url(&self) -> String {
+        let server = self.listen_config();
+        tls::local_base_url(server.tls.enable, &server.host, server.port)
+    }
+
     pub async fn stop(&mut self) {
         if let Some(tx) = self.shutdown_tx.take() {
             let _ = tx.send(());
@@ -341,6 +717,8 @@ impl ServerState {
         self.running = false;
         self.start_time = None;
         self.running_api_key = None;
+        self.reload_tx = None;
+        self.running_server_config = None;
     }
 }
 
@@ -359,7 +737,8 @@ pub mod handlers;
 #[derive(Clone)]
 #[allow(dead_code)]
 pub struct AppState {
-    pub api_key: String,
+    /// 主 API Key（配置重载后立即生效，读取请使用 [`AppState::api_key`]）
+    pub api_key: SharedApiKey,
     pub base_url: String,
     pub default_provider: Arc<RwLock<String>>,
     pub kiro: Arc<RwLock<KiroProvider>>,
@@ -394,6 +773,35 @@ pub struct AppState {
     pub endpoint_providers: Arc<RwLock<EndpointProvidersConfig>>,
     /// Kiro 事件服务
     pub kiro_event_service: Arc<KiroEventService>,
+    /// Prometheus 指标
+    pub metrics: Arc<crate::telemetry::PrometheusMetrics>,
+    /// Token 预算
+    pub token_budget: Arc<TokenBudgetService>,
+    /// Provider 并发限制
+    pub provider_concurrency: Arc<ProviderConcurrencyService>,
+    /// 统一用量记录
+    pub usage_recorder: Arc<UsageRecorder>,
+    /// 客户端 API Key
+    pub client_keys: Arc<ClientKeyStore>,
+    /// 响应缓存
+    pub response_cache: Arc<ResponseCache>,
+    /// 请求中间件管道
+    pub request_pipeline: Arc<RequestPipeline>,
+    /// 客户端 IP 白名单
+    pub ip_allowlist: Arc<IpAllowlist>,
+    /// 请求签名校验
+    pub request_signer: Arc<RequestSigner>,
+    /// CORS 策略
+    pub cors_policy: Arc<CorsPolicy>,
+    /// 按 API Key 限流
+    pub rate_limiter: Arc<RateLimiter>,
+}
+
+impl AppState {
+    /// 当前的主 API Key
+    pub fn api_key(&self) -> String {
+        self.api_key.read().clone()
+    }
 }
 
 /// 启动配置文件监控
@@ -409,10 +817,7 @@ pub struct AppState {
 /// - HTTP 和 WebSocket 连接保持活跃
 async fn start_config_watcher(
     config_path: PathBuf,
-    hot_reload_manager: Option<Arc<HotReloadManager>>,
-    processor: Arc<RequestProcessor>,
-    logs: Arc<RwLock<LogStore>>,
-    db: Option<DbConnection>,
+    state: AppState,
     config_manager: Option<Arc<std::sync::RwLock<ConfigManager>>>,
 ) -> Option<FileWatcher> {
     let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<ConfigChangeEvent>();
@@ -435,12 +840,6 @@ async fn start_config_watcher(
     tracing::info!("[HOT_RELOAD] 配置文件监控已启动: {:?}", config_path);
 
     // 启动事件处理任务
-    let hot_reload_manager_clone = hot_reload_manager.clone();
-    let processor_clone = processor.clone();
-    let logs_clone = logs.clone();
-    let db_clone = db.clone();
-    let config_manager_clone = config_manager.clone();
-
     tokio::spawn(async move {
         while let Some(event) = rx.recv().await {
             // 只处理修改事件
@@ -449,59 +848,29 @@ async fn start_config_watcher(
             }
 
             tracing::info!("[HOT_RELOAD] 检测到配置文件变更: {:?}", event.path);
-            logs_clone.write().await.add(
+            state.logs.write().await.add(
                 "info",
                 &format!("[HOT_RELOAD] 检测到配置文件变更: {:?}", event.path),
             );
 
             // 执行热重载
-            if let Some(ref manager) = hot_reload_manager_clone {
+            if let Some(ref manager) = state.hot_reload_manager {
                 let result = manager.reload();
                 match &result {
                     ReloadResult::Success { .. } => {
                         tracing::info!("[HOT_RELOAD] 配置热重载成功");
-                        logs_clone
+                        state
+                            .logs
                             .write()
                             .await
                             .add("info", "[HOT_RELOAD] 配置热重载成功");
 
-                        // 更新处理器中的组件
                         let new_config = manager.config();
-                        update_processor_config(&processor_clone, &new_config).await;
-
-                        // 同步凭证池
-                        if let (Some(ref db), Some(ref cfg_manager)) =
-                            (&db_clone, &config_manager_clone)
-                        {
-                            match sync_credential_pool_from_config(db, cfg_manager, &logs_clone)
-                                .await
-                            {
-                                Ok(count) => {
-                                    tracing::info!(
-                                        "[HOT_RELOAD] 凭证池同步完成，共 {} 个凭证",
-                                        count
-                                    );
-                                    logs_clone.write().await.add(
-                                        "info",
-                                        &format!(
-                                            "[HOT_RELOAD] 凭证池同步完成，共 {} 个凭证",
-                                            count
-                                        ),
-                                    );
-                                }
-                                Err(e) => {
-                                    tracing::warn!("[HOT_RELOAD] 凭证池同步失败: {}", e);
-                                    logs_clone.write().await.add(
-                                        "warn",
-                                        &format!("[HOT_RELOAD] 凭证池同步失败: {}", e),
-                                    );
-                                }
-                            }
-                        }
+                        apply_runtime_config(&state, &new_config, config_manager.as_ref()).await;
                     }
                     ReloadResult::RolledBack { error, .. } => {
                         tracing::warn!("[HOT_RELOAD] 配置热重载失败，已回滚: {}", error);
-                        logs_clone.write().await.add(
+                        state.logs.write().await.add(
                             "warn",
                             &format!("[HOT_RELOAD] 配置热重载失败，已回滚: {}", error),
                         );
@@ -516,7 +885,7 @@ async fn start_config_watcher(
                             error,
                             rollback_error
                         );
-                        logs_clone.write().await.add(
+                        state.logs.write().await.add(
                             "error",
                             &format!(
                                 "[HOT_RELOAD] 配置热重载失败: {}, 回滚错误: {:?}",
@@ -532,6 +901,84 @@ async fn start_config_watcher(
     Some(watcher)
 }
 
+/// 将新配置应用到运行中的服务器
+///
+/// 配置文件变更和 `server_reload_config` 命令共用。API Key、客户端 Key、限流、
+/// 路由、参数注入、端点 Provider 和凭证池立即生效；监听地址、端口和 TLS
+/// 在服务器启动时绑定，变更后需要重启。
+async fn apply_runtime_config(
+    state: &AppState,
+    config: &Config,
+    config_manager: Option<&Arc<std::sync::RwLock<ConfigManager>>>,
+) {
+    // 更新 Key 和共享的中间件组件
+    *state.api_key.write() = config.server.api_key.clone();
+    state
+        .client_keys
+        .set_keys(config.server.client_keys.clone());
+    state
+        .response_cache
+        .set_config(config.server.response_cache.clone());
+    state
+        .request_pipeline
+        .set_config(&config.server.request_middlewares);
+    match config.server.parse_allowed_ips() {
+        Ok(networks) => state.ip_allowlist.set_networks(networks),
+        Err(e) => tracing::warn!("[HOT_RELOAD] IP 白名单无效，保持原配置: {}", e),
+    }
+    state
+        .request_signer
+        .set_config(config.server.request_signing.clone());
+    state.cors_policy.set_config(config.server.cors.clone());
+    state
+        .rate_limiter
+        .set_config(config.server.rate_limit.clone());
+    state
+        .token_budget
+        .set_config(config.server.token_budget.clone());
+    state
+        .provider_concurrency
+        .set_config(config.server.provider_concurrency.clone());
+    state
+        .usage_recorder
+        .set_prices(config.server.token_budget.prices.clone());
+
+    // 更新 Provider 选择
+    *state.default_provider.write().await = config.default_provider.clone();
+    *state.endpoint_providers.write().await = config.endpoint_providers.clone();
+    *state.injection_enabled.write().await = config.injection.enabled;
+
+    // 更新处理器中的组件
+    update_processor_config(&state.processor, config).await;
+    if let Some(manager) = &state.hot_reload_manager {
+        manager.update_config(config.clone());
+    }
+
+    // 同步凭证池
+    if let (Some(db), Some(cfg_manager)) = (&state.db, config_manager) {
+        if let Ok(mut manager) = cfg_manager.write() {
+            manager.set_config(config.clone());
+        }
+        match sync_credential_pool_from_config(db, cfg_manager, &state.logs).await {
+            Ok(count) => {
+                tracing::info!("[HOT_RELOAD] 凭证池同步完成，共 {} 个凭证", count);
+                state.logs.write().await.add(
+                    "info",
+                    &format!("[HOT_RELOAD] 凭证池同步完成，共 {} 个凭证", count),
+                );
+            }
+            Err(e) => {
+                tracing::warn!("[HOT_RELOAD] 凭证池同步失败: {}", e);
+                state
+                    .logs
+                    .write()
+                    .await
+                    .add("warn", &format!("[HOT_RELOAD] 凭证池同步失败: {}", e));
+            }
+        }
+    }
+}
+
 /// 更新处理器配置
 ///
 /// 当配置热重载成功后，更新 RequestProcessor 中的各个组件。
@@ -592,6 +1039,16 @@ async fn update_processor_config(processor: &RequestProcessor, config: &Config)
         );
     }
 
+    // 更新模型路由
+    {
+        let mut model_routes = processor.model_routes.write().await;
+        model_routes.set_routes(config.routing.model_routes.clone());
+        tracing::debug!(
+            "[HOT_RELOAD] 模型路由已更新: {} 条路由",
+            config.routing.model_routes.len()
+        );
+    }
+
     // 注意：重试配置目前不支持热更新，因为 Retrier 是不可变的
     // 如果需要更新重试配置，需要重启服务器
     tracing::debug!(
@@ -675,8 +1132,24 @@ async fn run_server(
     shared_flow_interceptor: Option<Arc<FlowInterceptor>>,
     config: Option<Config>,
     config_path: Option<PathBuf>,
+    client_keys: Arc<ClientKeyStore>,
+    response_cache: Arc<ResponseCache>,
+    request_pipeline: Arc<RequestPipeline>,
+    ip_allowlist: Arc<IpAllowlist>,
+    request_signer: Arc<RequestSigner>,
+    cors_policy: Arc<CorsPolicy>,
+    rate_limiter: Arc<RateLimiter>,
+    token_budget: Arc<TokenBudgetService>,
+    provider_concurrency: Arc<ProviderConcurrencyService>,
+    usage_recorder: Arc<UsageRecorder>,
+    mut reload_rx: mpsc::UnboundedReceiver<Config>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let base_url = format!("http://{}:{}", host, port);
+    let tls_config = config
+        .as_ref()
+        .map(|c| c.server.tls.clone())
+        .unwrap_or_default();
+    // 监听所有网络接口时，本机客户端仍通过回环地址访问
+    let base_url = tls::local_base_url(tls_config.enable, host, port);
 
     // 创建请求处理器（使用共享的遥测实例或默认实例）
     let processor = match (shared_stats, shared_tokens) {
@@ -696,6 +1169,15 @@ async fn run_server(
         }
     }
 
+    // 将模型路由同步到处理器
+    if let Some(cfg) = &config {
+        processor
+            .model_routes
+            .write()
+            .await
+            .set_routes(cfg.routing.model_routes.clone());
+    }
+
     // 初始化 WebSocket 管理器
     let ws_manager = Arc::new(WsConnectionManager::new(WsConfig::default()));
     let ws_stats = ws_manager.stats().clone();
@@ -715,9 +1197,6 @@ async fn run_server(
             _ => None,
         };
 
-    let logs_clone = logs.clone();
-    let db_clone = db.clone();
-
     // 初始化 Amp CLI 路由器
     let amp_router = Arc::new(crate::router::AmpRouter::new(
         config
@@ -745,8 +1224,21 @@ async fn run_server(
     // 创建 Kiro 事件服务
     let kiro_event_service = Arc::new(KiroEventService::new());
 
+    let api_key: SharedApiKey = Arc::new(parking_lot::RwLock::new(api_key.to_string()));
+
+    // 统一用量记录的订阅者：Token 追踪、Prometheus 指标和 Token 预算
+    let metrics = Arc::new(crate::telemetry::PrometheusMetrics::new());
+    let mut usage_sinks: Vec<Arc<dyn UsageSink>> = vec![processor.tokens.clone(), metrics.clone()];
+    if let Some(db) = &db {
+        usage_sinks.push(Arc::new(TokenBudgetSink {
+            service: token_budget.clone(),
+            db: db.clone(),
+        }));
+    }
+    usage_recorder.set_sinks(usage_sinks);
+
     let state = AppState {
-        api_key: api_key.to_string(),
+        api_key: api_key.clone(),
         base_url,
         default_provider,
         kiro: Arc::new(RwLock::new(kiro)),
@@ -759,33 +1251,47 @@ async fn run_server(
         db,
         injector: Arc::new(RwLock::new(injector)),
         injection_enabled: Arc::new(RwLock::new(injection_enabled)),
-        processor: processor.clone(),
+        processor,
         ws_manager,
         ws_stats,
-        hot_reload_manager: hot_reload_manager.clone(),
+        hot_reload_manager,
         request_logger: shared_logger,
         amp_router,
         flow_monitor,
         flow_interceptor,
         endpoint_providers,
         kiro_event_service,
+        metrics,
+        token_budget,
+        provider_concurrency,
+        usage_recorder,
+        client_keys: client_keys.clone(),
+        response_cache: response_cache.clone(),
+        request_pipeline: request_pipeline.clone(),
+        ip_allowlist: ip_allowlist.clone(),
+        request_signer: request_signer.clone(),
+        cors_policy: cors_policy.clone(),
+        rate_limiter: rate_limiter.clone(),
     };
 
     // 启动配置文件监控
     let _file_watcher = if let Some(path) = config_path {
-        start_config_watcher(
-            path,
-            hot_reload_manager,
-            processor,
-            logs_clone,
-            db_clone,
-            config_manager,
-        )
-        .await
+        start_config_watcher(path, state.clone(), config_manager.clone()).await
     } else {
         None
     };
 
+    // 应用 server_reload_config 命令推送的配置（服务器停止时通道关闭，任务随之结束）
+    {
+        let state = state.clone();
+        tokio::spawn(async move {
+            while let Some(new_config) = reload_rx.recv().await {
+                tracing::info!("[HOT_RELOAD] 应用新配置到运行中的服务器");
+                apply_runtime_config(&state, &new_config, config_manager.as_ref()).await;
+            }
+        });
+    }
+
     // 设置请求体大小限制为 100MB，支持大型上下文请求（如 Claude Code 的 /compact 命令）
     let body_limit = 100 * 1024 * 1024; // 100MB
 
@@ -846,16 +1352,22 @@ async fn run_server(
 
     let app = Router::new()
         .route("/health", get(health))
+        .route("/healthz", get(healthz))
+        .route("/metrics", get(prometheus_metrics))
         .route("/v1/models", get(models))
         .route("/v1/routes", get(list_routes))
         .route("/v1/chat/completions", post(handlers::chat_completions))
         .route("/v1/messages", post(handlers::anthropic_messages))
         .route("/v1/messages/count_tokens", post(count_tokens))
+        .route("/v1/images/generations", post(handlers::images_generations))
+        .route("/v1/embeddings", post(handlers::embeddings))
         // Gemini 原生协议路由
         .route("/v1/gemini/*path", post(gemini_generate_content))
         // WebSocket 路由
         .route("/v1/ws", get(handlers::ws_upgrade_handler))
         .route("/ws", get(handlers::ws_upgrade_handler))
+        // OpenAI Realtime API 透传
+        .route("/v1/realtime", get(handlers::realtime_upgrade_handler))
         // 多供应商路由
         .route(
             "/:selector/v1/messages",
@@ -880,25 +1392,82 @@ async fn run_server(
             "/api/user/*path",
             axum::routing::any(amp_management_proxy_user),
         )
+        // 相同请求的响应缓存（在客户端 Key 校验之后执行，按主 Key 判断是否已认证）
+        .layer(
+            crate::middleware::ResponseCacheLayer::new(api_key.clone(), response_cache)
+                .with_hit_sink(Arc::new(CacheHitRecorder {
+                    state: state.clone(),
+                })),
+        )
+        // 请求中间件（在响应缓存之前执行，缓存键基于改写后的请求）
+        .layer(crate::middleware::RequestPipelineLayer::new(
+            request_pipeline,
+        ))
+        // 客户端 API Key 校验（在限流之后执行，限流仍按客户端原始 Key 统计）
+        .layer(crate::middleware::ClientKeyLayer::new(
+            api_key,
+            client_keys,
+            body_limit,
+        ))
+        // 按客户端 API Key 限流（只作用于以上 API 路由）
+        .layer(crate::middleware::RateLimitLayer::new(
+            rate_limiter,
+            body_limit,
+        ))
         // 管理 API 路由
         .merge(management_routes)
         // Kiro凭证管理API路由
         .merge(kiro_api_routes)
         // 凭证 API 路由（用于 aster Agent 集成）
         .merge(credentials_api_routes)
+        // HMAC 请求签名校验（作用于除健康检查外的全部路由，本机请求默认无需签名）
+        .layer(crate::middleware::RequestSigningLayer::new(
+            request_signer,
+            body_limit,
+        ))
         .layer(DefaultBodyLimit::max(body_limit))
+        // 浏览器跨域访问（预检请求在认证和签名校验之前应答）
+        .layer(crate::middleware::CorsLayer::new(cors_policy))
+        // 客户端 IP 白名单（作用于全部路由）
+        .layer(crate::middleware::IpAllowlistLayer::new(ip_allowlist))
         .with_state(state);
 
-    let addr: std::net::SocketAddr = format!("{host}:{port}").parse()?;
+    let ip: std::net::IpAddr = match host {
+        "localhost" => std::net::Ipv4Addr::LOCALHOST.into(),
+        _ => host.parse()?,
+    };
+    let addr = std::net::SocketAddr::new(ip, port);
+
+    if tls_config.enable {
+        let rustls_config = tls::load_rustls_config(&tls_config, host).await?;
+        let handle = axum_server::Handle::new();
+        let shutdown_handle = handle.clone();
+        tokio::spawn(async move {
+            let _ = shutdown.await;
+            shutdown_handle.graceful_shutdown(Some(std::time::Duration::from_secs(10)));
+        });
+
+        tracing::info!("Server listening on {} (TLS)", addr);
+
+        axum_server::bind_rustls(addr, rustls_config)
+            .handle(handle)
+            .serve(app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+            .await?;
+        return Ok(());
+    }
+
     let listener = tokio::net::TcpListener::bind(addr).await?;
 
     tracing::info!("Server listening on {}", addr);
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(async move {
-            let _ = shutdown.await;
-        })
-        .await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        let _ = shutdown.await;
+    })
+    .await?;
 
     Ok(())
 }
@@ -908,7 +1477,7 @@ async fn count_tokens(
     headers: HeaderMap,
     Json(_request): Json<serde_json::Value>,
 ) -> Response {
-    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key).await {
+    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key()).await {
         return e.into_response();
     }
 
@@ -928,7 +1497,7 @@ async fn gemini_generate_content(
     Path(path): Path<String>,
     Json(request): Json<serde_json::Value>,
 ) -> Response {
-    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key).await {
+    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key()).await {
         return e.into_response();
     }
 
@@ -976,7 +1545,7 @@ async fn gemini_generate_content(
     // 获取默认 provider
     let default_provider = state.default_provider.read().await.clone();
 
-    // 尝试从凭证池中选择 Antigravity 凭证
+    // 从凭证池中选择凭证（Antigravity / Gemini API Key 使用原生协议，其他凭证经 OpenAI 格式转换）
     let credential = match &state.db {
         Some(db) => state
             .pool_service
@@ -993,7 +1562,7 @@ async fn gemini_generate_content(
                 StatusCode::NOT_FOUND,
                 Json(serde_json::json!({
                     "error": {
-                        "message": "没有可用的 Antigravity 凭证，请先添加凭证"
+                        "message": "没有可用的凭证，请先添加凭证"
                     }
                 })),
             )
@@ -1011,7 +1580,6 @@ async fn gemini_generate_content(
         ),
     );
 
-    // 调用 Antigravity Provider
     match &cred.credential {
         CredentialData::AntigravityOAuth {
             creds_file_path,
@@ -1144,11 +1712,120 @@ async fn gemini_generate_content(
                 }
             }
         }
-        _ => (
-            StatusCode::BAD_REQUEST,
+        CredentialData::GeminiApiKey {
+            api_key,
+            base_url,
+            excluded_models,
+        } => {
+            let gemini_credential = GeminiApiKeyCredential::new(cred.uuid.clone(), api_key.clone())
+                .with_base_url(base_url.clone())
+                .with_excluded_models(excluded_models.clone());
+            if !gemini_credential.supports_model(model) {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({
+                        "error": {
+                            "message": format!("模型 {} 已被该 Gemini API Key 凭证排除", model)
+                        }
+                    })),
+                )
+                    .into_response();
+            }
+
+            // 原生协议直通
+            let provider = GeminiApiKeyProvider::new();
+            let result = if is_stream {
+                provider
+                    .stream_generate_content(&gemini_credential, model, &request)
+                    .await
+                    .map(|resp| {
+                        Response::builder()
+                            .status(StatusCode::OK)
+                            .header("content-type", "text/event-stream")
+                            .header("cache-control", "no-cache")
+                            .body(Body::from_stream(resp.bytes_stream()))
+                            .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+                    })
+            } else {
+                provider
+                    .generate_content(&gemini_credential, model, &request)
+                    .await
+                    .map(|resp| Json(resp).into_response())
+            };
+
+            result.unwrap_or_else(|e| {
+                (
+                    GeminiApiError::status_of(e.as_ref())
+                        .and_then(|status| StatusCode::from_u16(status).ok())
+                        .unwrap_or(StatusCode::BAD_GATEWAY),
+                    Json(serde_json::json!({
+                        "error": {
+                            "message": e.to_string()
+                        }
+                    })),
+                )
+                    .into_response()
+            })
+        }
+        // 其他凭证：转换为 OpenAI 格式调用，再把响应转换回 Gemini 格式
+        _ => {
+            let openai_request = convert_gemini_to_openai_request(&request, model, is_stream);
+            let response =
+                handlers::call_provider_openai(&state, &cred, &openai_request, None).await;
+            openai_response_to_gemini(response, is_stream, model).await
+        }
+    }
+}
+
+/// 转换为 Gemini 格式时最多读取的非流式响应体大小（32MB）
+const MAX_CONVERTED_RESPONSE_BYTES: usize = 32 * 1024 * 1024;
+
+/// 将 OpenAI 格式的响应转换为 Gemini 格式（错误响应原样返回）
+async fn openai_response_to_gemini(response: Response, is_stream: bool, model: &str) -> Response {
+    if !response.status().is_success() {
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+
+    if is_stream {
+        let mut converter =
+            StreamConverter::with_model(StreamFormat::OpenAiSse, StreamFormat::GeminiSse, model);
+        let mut body_stream = body.into_data_stream();
+        let gemini_stream = async_stream::stream! {
+            use futures::StreamExt;
+
+            while let Some(chunk) = body_stream.next().await {
+                match chunk {
+                    Ok(bytes) => {
+                        for event in converter.convert(&bytes) {
+                            yield Ok::<_, std::io::Error>(axum::body::Bytes::from(event));
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("[GEMINI] 转换流式响应失败: {}", e);
+                        return;
+                    }
+                }
+            }
+            for event in converter.finish() {
+                yield Ok(axum::body::Bytes::from(event));
+            }
+        };
+        return Response::from_parts(parts, Body::from_stream(gemini_stream));
+    }
+
+    match axum::body::to_bytes(body, MAX_CONVERTED_RESPONSE_BYTES)
+        .await
+        .map_err(|e| tracing::warn!("[GEMINI] 读取上游响应失败: {}", e))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok())
+    {
+        Some(openai_resp) => Json(convert_openai_response_to_gemini(&openai_resp)).into_response(),
+        None => (
+            StatusCode::BAD_GATEWAY,
             Json(serde_json::json!({
                 "error": {
-                    "message": "Gemini 原生协议只支持 Antigravity 凭证"
+                    "message": "无法解析上游响应"
                 }
             })),
         )
@@ -1156,6 +1833,64 @@ async fn gemini_generate_content(
     }
 }
 
+#[derive(Debug, Deserialize)]
+struct HealthzQuery {
+    /// 是否探测上游 Provider
+    #[serde(default)]
+    probe: bool,
+}
+
+/// 健康检查
+///
+/// 默认只报告服务存活；`?probe=true` 时探测所有上游 Provider（需要 API Key），
+/// 全部上游不可用时返回 503
+async fn healthz(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<HealthzQuery>,
+) -> Response {
+    if !query.probe {
+        return Json(UpstreamHealthReport::from_probes(Vec::new())).into_response();
+    }
+    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key()).await {
+        return e.into_response();
+    }
+    let report = match &state.db {
+        Some(db) => match probe_upstreams(db).await {
+            Ok(report) => report,
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({"error": {"message": e}})),
+                )
+                    .into_response()
+            }
+        },
+        None => UpstreamHealthReport::from_probes(Vec::new()),
+    };
+    let status = if report.status == HealthStatus::Unhealthy {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    (status, Json(report)).into_response()
+}
+
+/// Prometheus 指标（需要 API Key，抓取时配置 `authorization` 或 `bearer_token`）
+async fn prometheus_metrics(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key()).await {
+        return e.into_response();
+    }
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        state.metrics.render(),
+    )
+        .into_response()
+}
+
 /// 列出所有可用路由
 async fn list_routes(State(state): State<AppState>) -> impl IntoResponse {
     let routes = match &state.db {
@@ -1208,7 +1943,7 @@ async fn anthropic_messages_with_selector(
     Json(request): Json<AnthropicMessagesRequest>,
 ) -> Response {
     // 使用 Anthropic 格式的认证验证
-    if let Err(e) = handlers::verify_api_key_anthropic(&headers, &state.api_key).await {
+    if let Err(e) = handlers::verify_api_key_anthropic(&headers, &state.api_key()).await {
         state.logs.write().await.add(
             "warn",
             &format!("Unauthorized request to /{}/v1/messages", selector),
@@ -1287,7 +2022,7 @@ async fn chat_completions_with_selector(
     headers: HeaderMap,
     Json(request): Json<ChatCompletionRequest>,
 ) -> Response {
-    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key).await {
+    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key()).await {
         state.logs.write().await.add(
             "warn",
             &format!("Unauthorized request to /{}/v1/chat/completions", selector),
@@ -1363,7 +2098,7 @@ async fn amp_chat_completions(
     headers: HeaderMap,
     Json(mut request): Json<ChatCompletionRequest>,
 ) -> Response {
-    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key).await {
+    if let Err(e) = handlers::verify_api_key(&headers, &state.api_key()).await {
         state.logs.write().await.add(
             "warn",
             &format!(
@@ -1459,7 +2194,7 @@ async fn amp_messages(
     Json(mut request): Json<AnthropicMessagesRequest>,
 ) -> Response {
     // 使用 Anthropic 格式的认证验证
-    if let Err(e) = handlers::verify_api_key_anthropic(&headers, &state.api_key).await {
+    if let Err(e) = handlers::verify_api_key_anthropic(&headers, &state.api_key()).await {
         state.logs.write().await.add(
             "warn",
             &format!(
@@ -0,0 +1,169 @@
+//! HTTPS 支持
+//!
+//! 启用 TLS 时加载证书和私钥：
+//! - 同时配置了 `cert_path` 和 `key_path` 时使用用户提供的证书
+//! - 都未配置时使用 `~/.proxycast/tls/` 下的自签名证书，首次启动自动生成
+//!   （SAN 包含 localhost、127.0.0.1、::1 和监听地址）
+//!
+//! 应用内访问本机 API Server 的客户端（Agent、向量化、基准测试等）通过
+//! [`local_base_url`] 和 [`local_client_builder`] 跟随 TLS 设置。
+
+use crate::config::{expand_tilde, TlsConfig};
+use axum_server::tls_rustls::RustlsConfig;
+use std::path::{Path, PathBuf};
+
+const CERT_FILE: &str = "cert.pem";
+const KEY_FILE: &str = "key.pem";
+
+/// 自签名证书的默认存放目录
+fn default_tls_dir() -> Result<PathBuf, String> {
+    dirs::home_dir()
+        .map(|home| home.join(".proxycast").join("tls"))
+        .ok_or_else(|| "无法获取用户主目录".to_string())
+}
+
+/// 解析证书和私钥路径，必要时生成自签名证书
+pub fn resolve_cert_paths(tls: &TlsConfig, host: &str) -> Result<(PathBuf, PathBuf), String> {
+    let non_empty = |p: &Option<String>| p.as_deref().filter(|p| !p.trim().is_empty());
+    match (non_empty(&tls.cert_path), non_empty(&tls.key_path)) {
+        (Some(cert), Some(key)) => Ok((expand_tilde(cert), expand_tilde(key))),
+        (None, None) => ensure_self_signed(&default_tls_dir()?, host),
+        _ => Err("TLS 证书和私钥路径需要同时配置，或都留空以使用自签名证书".to_string()),
+    }
+}
+
+/// 确保目录下存在自签名证书，不存在时生成
+fn ensure_self_signed(dir: &Path, host: &str) -> Result<(PathBuf, PathBuf), String> {
+    let cert_path = dir.join(CERT_FILE);
+    let key_path = dir.join(KEY_FILE);
+    if cert_path.exists() && key_path.exists() {
+        return Ok((cert_path, key_path));
+    }
+
+    let mut names = vec![
+        "localhost".to_string(),
+        "127.0.0.1".to_string(),
+        "::1".to_string(),
+    ];
+    if !host.is_empty() && host != "0.0.0.0" && host != "::" && !names.iter().any(|n| n == host) {
+        names.push(host.to_string());
+    }
+    let certified = rcgen::generate_simple_self_signed(names)
+        .map_err(|e| format!("生成自签名证书失败: {}", e))?;
+
+    std::fs::create_dir_all(dir).map_err(|e| format!("创建证书目录失败: {}", e))?;
+    std::fs::write(&cert_path, certified.cert.pem()).map_err(|e| format!("写入证书失败: {}", e))?;
+    std::fs::write(&key_path, certified.key_pair.serialize_pem())
+        .map_err(|e| format!("写入私钥失败: {}", e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = std::fs::set_permissions(&key_path, std::fs::Permissions::from_mode(0o600));
+    }
+
+    tracing::info!("[TLS] 已生成自签名证书: {}", cert_path.display());
+    Ok((cert_path, key_path))
+}
+
+/// 加载 rustls 配置
+pub async fn load_rustls_config(tls: &TlsConfig, host: &str) -> Result<RustlsConfig, String> {
+    let (cert_path, key_path) = resolve_cert_paths(tls, host)?;
+    RustlsConfig::from_pem_file(&cert_path, &key_path)
+        .await
+        .map_err(|e| {
+            format!(
+                "加载 TLS 证书失败 ({}, {}): {}",
+                cert_path.display(),
+                key_path.display(),
+                e
+            )
+        })
+}
+
+/// 本机客户端访问 API Server 的基础 URL
+///
+/// 协议跟随 `tls.enable`；监听所有网络接口时使用回环地址
+pub fn local_base_url(tls_enabled: bool, host: &str, port: u16) -> String {
+    let scheme = if tls_enabled { "https" } else { "http" };
+    match host {
+        "0.0.0.0" | "::" => format!("{}://127.0.0.1:{}", scheme, port),
+        host if host.contains(':') => format!("{}://[{}]:{}", scheme, host, port),
+        host => format!("{}://{}:{}", scheme, host, port),
+    }
+}
+
+/// 创建访问本机 API Server 的 HTTP 客户端构建器
+///
+/// 不走系统代理；`base_url` 为本机 https 地址时接受自签名证书
+pub fn local_client_builder(base_url: &str) -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .no_proxy()
+        .danger_accept_invalid_certs(is_loopback_https(base_url))
+}
+
+/// 是否为回环地址上的 https URL
+fn is_loopback_https(base_url: &str) -> bool {
+    let Ok(url) = url::Url::parse(base_url) else {
+        return false;
+    };
+    url.scheme() == "https"
+        && match url.host() {
+            Some(url::Host::Domain(domain)) => domain == "localhost",
+            Some(url::Host::Ipv4(ip)) => ip.is_loopback(),
+            Some(url::Host::Ipv6(ip)) => ip.is_loopback(),
+            None => false,
+        }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_signed_generated_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let (cert, key) = ensure_self_signed(dir.path(), "192.168.1.10").unwrap();
+        let cert_pem = std::fs::read_to_string(&cert).unwrap();
+        assert!(cert_pem.starts_with("-----BEGIN CERTIFICATE-----"));
+        assert!(std::fs::read_to_string(&key)
+            .unwrap()
+            .contains("PRIVATE KEY"));
+
+        // 已存在时复用，不重新生成
+        ensure_self_signed(dir.path(), "192.168.1.10").unwrap();
+        assert_eq!(std::fs::read_to_string(&cert).unwrap(), cert_pem);
+    }
+
+    #[test]
+    fn test_partial_paths_rejected() {
+        let tls = TlsConfig {
+            enable: true,
+            cert_path: Some("/tmp/cert.pem".to_string()),
+            key_path: None,
+        };
+        assert!(resolve_cert_paths(&tls, "127.0.0.1").is_err());
+    }
+
+    #[test]
+    fn test_local_base_url() {
+        assert_eq!(
+            local_base_url(false, "0.0.0.0", 8999),
+            "http://127.0.0.1:8999"
+        );
+        assert_eq!(
+            local_base_url(true, "127.0.0.1", 8999),
+            "https://127.0.0.1:8999"
+        );
+        assert_eq!(local_base_url(true, "::1", 8999), "https://[::1]:8999");
+        assert_eq!(
+            local_base_url(true, "192.168.1.10", 8999),
+            "https://192.168.1.10:8999"
+        );
+
+        assert!(is_loopback_https("https://127.0.0.1:8999"));
+        assert!(is_loopback_https("https://[::1]:8999"));
+        assert!(is_loopback_https("https://localhost:8999"));
+        assert!(!is_loopback_https("http://127.0.0.1:8999"));
+        assert!(!is_loopback_https("https://api.example.com"));
+    }
+}
@@ -2,7 +2,7 @@
 //!
 //! 包含响应解析、字符串处理、响应构建等公共工具函数。
 
-use crate::models::openai::{ContentPart, FunctionCall, MessageContent, ToolCall};
+use crate::models::openai::{FunctionCall, ToolCall};
 use axum::{
     body::Body,
     http::{header, StatusCode},
@@ -55,23 +55,6 @@ pub fn safe_truncate(s: &str, max_chars: usize) -> String {
     }
 }
 
-/// 计算 MessageContent 的字符长度
-pub fn message_content_len(content: &MessageContent) -> usize {
-    match content {
-        MessageContent::Text(s) => s.len(),
-        MessageContent::Parts(parts) => parts
-            .iter()
-            .filter_map(|p| {
-                if let ContentPart::Text { text } = p {
-                    Some(text.len())
-                } else {
-                    None
-                }
-            })
-            .sum(),
-    }
-}
-
 /// 解析 CodeWhisperer AWS Event Stream 响应
 ///
 /// AWS Event Stream 是二进制格式，JSON payload 嵌入在二进制头部之间
@@ -0,0 +1,545 @@
+//! 聊天桥接服务
+//!
+//! 将指定的 Agent 会话转发到用户配置的 Telegram / Matrix 机器人，
+//! 并把机器人收到的回复注入回会话，便于在手机上监控长时间运行的任务。
+
+use crate::agent::{NativeAgentState, NativeChatRequest};
+use parking_lot::RwLock;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Telegram / Matrix 单条消息的最大长度（超出部分截断）
+const MAX_BRIDGE_MESSAGE_LEN: usize = 4000;
+
+/// 长轮询超时（秒）
+const POLL_TIMEOUT_SECS: u64 = 30;
+
+/// 桥接目标配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BridgeTarget {
+    /// Telegram Bot
+    Telegram {
+        /// Bot Token（来自 @BotFather）
+        bot_token: String,
+        /// 目标聊天 ID
+        chat_id: String,
+    },
+    /// Matrix 机器人账号
+    Matrix {
+        /// Homeserver 地址，如 https://matrix.org
+        homeserver: String,
+        /// 机器人账号的 Access Token
+        access_token: String,
+        /// 目标房间 ID
+        room_id: String,
+    },
+}
+
+impl BridgeTarget {
+    /// 桥接类型名称
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BridgeTarget::Telegram { .. } => "telegram",
+            BridgeTarget::Matrix { .. } => "matrix",
+        }
+    }
+}
+
+/// 桥接信息（返回给前端，不包含密钥）
+#[derive(Debug, Clone, Serialize)]
+pub struct BridgeInfo {
+    /// 会话 ID
+    pub session_id: String,
+    /// 桥接类型
+    pub kind: String,
+    /// 目标（chat_id 或 room_id）
+    pub target: String,
+    /// 是否接收回复
+    pub accept_replies: bool,
+    /// 建立时间
+    pub attached_at: String,
+}
+
+struct BridgeHandle {
+    target: BridgeTarget,
+    accept_replies: bool,
+    attached_at: String,
+    cancel: CancellationToken,
+}
+
+/// 聊天桥接服务
+#[derive(Clone, Default)]
+pub struct ChatBridgeService {
+    client: Client,
+    bridges: Arc<RwLock<HashMap<String, BridgeHandle>>>,
+}
+
+impl ChatBridgeService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 将会话绑定到桥接目标
+    ///
+    /// 如果 `accept_replies` 为 true，会启动后台轮询任务，
+    /// 把机器人收到的消息作为用户消息发送到会话，并把回复转发回去。
+    pub async fn attach(
+        &self,
+        session_id: String,
+        target: BridgeTarget,
+        accept_replies: bool,
+        agent_state: NativeAgentState,
+    ) -> Result<BridgeInfo, String> {
+        self.detach(&session_id);
+
+        // Matrix 需要知道机器人自身的 user_id，以过滤自己发送的消息，
+        // 获取失败时拒绝桥接，避免把转发出去的消息当作回复注入会话
+        let self_user_id = match &target {
+            BridgeTarget::Matrix {
+                homeserver,
+                access_token,
+                ..
+            } if accept_replies => Some(
+                matrix_whoami(&self.client, homeserver, access_token)
+                    .await
+                    .map_err(|e| format!("获取 Matrix 机器人账号失败: {}", e))?,
+            ),
+            _ => None,
+        };
+
+        send_to_target(
+            &self.client,
+            &target,
+            &format!("🔗 ProxyCast 会话 {} 已桥接", short_id(&session_id)),
+        )
+        .await?;
+
+        let cancel = CancellationToken::new();
+        let attached_at = chrono::Utc::now().to_rfc3339();
+
+        if accept_replies {
+            let service = self.clone();
+            let session_id = session_id.clone();
+            let target = target.clone();
+            let cancel = cancel.clone();
+            tokio::spawn(async move {
+                service
+                    .poll_replies(session_id, target, self_user_id, agent_state, cancel)
+                    .await;
+            });
+        }
+
+        let info = BridgeInfo {
+            session_id: session_id.clone(),
+            kind: target.kind().to_string(),
+            target: target_label(&target),
+            accept_replies,
+            attached_at: attached_at.clone(),
+        };
+
+        self.bridges.write().insert(
+            session_id.clone(),
+            BridgeHandle {
+                target,
+                accept_replies,
+                attached_at,
+                cancel,
+            },
+        );
+
+        tracing::info!("[ChatBridge] 会话已桥接: {} -> {}", session_id, info.kind);
+        Ok(info)
+    }
+
+    /// 解除会话桥接
+    pub fn detach(&self, session_id: &str) -> bool {
+        if let Some(handle) = self.bridges.write().remove(session_id) {
+            handle.cancel.cancel();
+            tracing::info!("[ChatBridge] 会话桥接已解除: {}", session_id);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 列出所有桥接
+    pub fn list(&self) -> Vec<BridgeInfo> {
+        self.bridges
+            .read()
+            .iter()
+            .map(|(session_id, handle)| BridgeInfo {
+                session_id: session_id.clone(),
+                kind: handle.target.kind().to_string(),
+                target: target_label(&handle.target),
+                accept_replies: handle.accept_replies,
+                attached_at: handle.attached_at.clone(),
+            })
+            .collect()
+    }
+
+    /// 会话是否已桥接
+    pub fn is_bridged(&self, session_id: &str) -> bool {
+        self.bridges.read().contains_key(session_id)
+    }
+
+    /// 将会话中的消息转发到桥接目标（未桥接时忽略）
+    pub async fn forward(&self, session_id: &str, text: &str) {
+        let target = match self.bridges.read().get(session_id) {
+            Some(handle) => handle.target.clone(),
+            None => return,
+        };
+
+        if let Err(e) = send_to_target(&self.client, &target, text).await {
+            tracing::warn!("[ChatBridge] 转发消息失败: session={}, {}", session_id, e);
+        }
+    }
+
+    /// 轮询机器人收到的回复并注入会话
+    ///
+    /// `self_user_id` 为 Matrix 机器人自身的 user_id，用于过滤自己发送的消息
+    async fn poll_replies(
+        &self,
+        session_id: String,
+        target: BridgeTarget,
+        self_user_id: Option<String>,
+        agent_state: NativeAgentState,
+        cancel: CancellationToken,
+    ) {
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let poll = fetch_replies(&self.client, &target, cursor.as_deref(), &self_user_id);
+            let (replies, next_cursor) = tokio::select! {
+                _ = cancel.cancelled() => break,
+                result = poll => match result {
+                    Ok(r) => r,
+                    Err(e) => {
+                        tracing::warn!("[ChatBridge] 轮询回复失败: {}", e);
+                        tokio::select! {
+                            _ = cancel.cancelled() => break,
+                            _ = tokio::time::sleep(Duration::from_secs(5)) => continue,
+                        }
+                    }
+                },
+            };
+            cursor = next_cursor.or(cursor);
+
+            for reply in replies {
+                tracing::info!(
+                    "[ChatBridge] 收到回复: session={}, len={}",
+                    session_id,
+                    reply.len()
+                );
+                let request = NativeChatRequest {
+                    session_id: Some(session_id.clone()),
+                    message: reply,
+                    model: None,
+                    images: None,
+                    audio: None,
+                    attachments: None,
+                    context: None,
+                    stream: false,
+                };
+                let text = match agent_state.chat(request).await {
+                    Ok(resp) if resp.success => resp.content,
+                    Ok(resp) => format!("❌ {}", resp.error.unwrap_or_default()),
+                    Err(e) => format!("❌ {}", e),
+                };
+                if let Err(e) = send_to_target(&self.client, &target, &text).await {
+                    tracing::warn!("[ChatBridge] 发送回复失败: {}", e);
+                }
+            }
+        }
+
+        tracing::debug!("[ChatBridge] 轮询任务结束: {}", session_id);
+    }
+}
+
+fn short_id(id: &str) -> &str {
+    &id[..id.len().min(8)]
+}
+
+fn target_label(target: &BridgeTarget) -> String {
+    match target {
+        BridgeTarget::Telegram { chat_id, .. } => chat_id.clone(),
+        BridgeTarget::Matrix { room_id, .. } => room_id.clone(),
+    }
+}
+
+/// 截断过长的消息（按字符边界）
+fn truncate_message(text: &str) -> String {
+    if text.chars().count() <= MAX_BRIDGE_MESSAGE_LEN {
+        return text.to_string();
+    }
+    let truncated: String = text.chars().take(MAX_BRIDGE_MESSAGE_LEN).collect();
+    format!("{}…", truncated)
+}
+
+/// 发送消息到桥接目标
+async fn send_to_target(client: &Client, target: &BridgeTarget, text: &str) -> Result<(), String> {
+    let text = truncate_message(text);
+    let resp = match target {
+        BridgeTarget::Telegram { bot_token, chat_id } => {
+            client
+                .post(format!(
+                    "https://api.telegram.org/bot{}/sendMessage",
+                    bot_token
+                ))
+                .json(&serde_json::json!({ "chat_id": chat_id, "text": text }))
+                .send()
+                .await
+        }
+        BridgeTarget::Matrix {
+            homeserver,
+            access_token,
+            room_id,
+        } => {
+            client
+                .put(format!(
+                    "{}/_matrix/client/v3/rooms/{}/send/m.room.message/{}",
+                    homeserver.trim_end_matches('/'),
+                    urlencoding::encode(room_id),
+                    uuid::Uuid::new_v4()
+                ))
+                .bearer_auth(access_token)
+                .json(&serde_json::json!({ "msgtype": "m.text", "body": text }))
+                .send()
+                .await
+        }
+    }
+    .map_err(|e| format!("请求失败: {}", e))?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("{} 返回错误 ({}): {}", target.kind(), status, body));
+    }
+    Ok(())
+}
+
+/// 拉取新回复，返回 (回复列表, 新游标)
+async fn fetch_replies(
+    client: &Client,
+    target: &BridgeTarget,
+    cursor: Option<&str>,
+    self_user_id: &Option<String>,
+) -> Result<(Vec<String>, Option<String>), String> {
+    match target {
+        BridgeTarget::Telegram { bot_token, chat_id } => {
+            // 首次轮询用 offset=-1 只取最新一条更新作为游标，不重放积压的历史消息
+            let (offset, timeout) = match cursor {
+                Some(offset) => (offset, POLL_TIMEOUT_SECS),
+                None => ("-1", 0),
+            };
+            let url = format!(
+                "https://api.telegram.org/bot{}/getUpdates?timeout={}&offset={}",
+                bot_token, timeout, offset
+            );
+            let body: serde_json::Value = client
+                .get(&url)
+                .timeout(Duration::from_secs(POLL_TIMEOUT_SECS + 10))
+                .send()
+                .await
+                .map_err(|e| format!("请求失败: {}", e))?
+                .json()
+                .await
+                .map_err(|e| format!("解析响应失败: {}", e))?;
+            let (replies, next) = parse_telegram_updates(&body, chat_id);
+            if cursor.is_none() {
+                // 没有任何更新时从头开始，之后收到的都是新消息
+                Ok((Vec::new(), next.or_else(|| Some("0".to_string()))))
+            } else {
+                Ok((replies, next))
+            }
+        }
+        BridgeTarget::Matrix {
+            homeserver,
+            access_token,
+            room_id,
+        } => {
+            let mut url = format!(
+                "{}/_matrix/client/v3/sync?timeout={}",
+                homeserver.trim_end_matches('/'),
+                POLL_TIMEOUT_SECS * 1000
+            );
+            if let Some(since) = cursor {
+                url.push_str(&format!("&since={}", urlencoding::encode(since)));
+            }
+            let body: serde_json::Value = client
+                .get(&url)
+                .bearer_auth(access_token)
+                .timeout(Duration::from_secs(POLL_TIMEOUT_SECS + 10))
+                .send()
+                .await
+                .map_err(|e| format!("请求失败: {}", e))?
+                .json()
+                .await
+                .map_err(|e| format!("解析响应失败: {}", e))?;
+            // 首次同步只用于获取游标，不处理历史消息
+            let (replies, next) = parse_matrix_sync(&body, room_id, self_user_id.as_deref());
+            if cursor.is_none() {
+                Ok((Vec::new(), next))
+            } else {
+                Ok((replies, next))
+            }
+        }
+    }
+}
+
+/// 解析 Telegram getUpdates 响应
+fn parse_telegram_updates(
+    body: &serde_json::Value,
+    chat_id: &str,
+) -> (Vec<String>, Option<String>) {
+    let mut replies = Vec::new();
+    let mut max_update_id: Option<i64> = None;
+
+    for update in body["result"].as_array().into_iter().flatten() {
+        if let Some(id) = update["update_id"].as_i64() {
+            max_update_id = Some(max_update_id.map_or(id, |m| m.max(id)));
+        }
+        let message = &update["message"];
+        let from_chat = message["chat"]["id"]
+            .as_i64()
+            .map(|id| id.to_string())
+            .unwrap_or_default();
+        if from_chat != chat_id {
+            continue;
+        }
+        if let Some(text) = message["text"].as_str() {
+            replies.push(text.to_string());
+        }
+    }
+
+    (replies, max_update_id.map(|id| (id + 1).to_string()))
+}
+
+/// 解析 Matrix sync 响应
+fn parse_matrix_sync(
+    body: &serde_json::Value,
+    room_id: &str,
+    self_user_id: Option<&str>,
+) -> (Vec<String>, Option<String>) {
+    let replies = body["rooms"]["join"][room_id]["timeline"]["events"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|e| e["type"] == "m.room.message")
+        .filter(|e| self_user_id.map_or(true, |me| e["sender"] != me))
+        .filter_map(|e| e["content"]["body"].as_str().map(|s| s.to_string()))
+        .collect();
+
+    (replies, body["next_batch"].as_str().map(|s| s.to_string()))
+}
+
+async fn matrix_whoami(
+    client: &Client,
+    homeserver: &str,
+    access_token: &str,
+) -> Result<String, String> {
+    let body: serde_json::Value = client
+        .get(format!(
+            "{}/_matrix/client/v3/account/whoami",
+            homeserver.trim_end_matches('/')
+        ))
+        .bearer_auth(access_token)
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("解析响应失败: {}", e))?;
+    body["user_id"]
+        .as_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| "无法获取 Matrix user_id".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_telegram_updates_filters_chat() {
+        let body = serde_json::json!({
+            "ok": true,
+            "result": [
+                {"update_id": 10, "message": {"chat": {"id": 42}, "text": "hello"}},
+                {"update_id": 11, "message": {"chat": {"id": 7}, "text": "other"}}
+            ]
+        });
+        let (replies, cursor) = parse_telegram_updates(&body, "42");
+        assert_eq!(replies, vec!["hello".to_string()]);
+        assert_eq!(cursor, Some("12".to_string()));
+    }
+
+    #[test]
+    fn test_parse_matrix_sync_skips_own_messages() {
+        let body = serde_json::json!({
+            "next_batch": "s1",
+            "rooms": {"join": {"!room:x": {"timeline": {"events": [
+                {"type": "m.room.message", "sender": "@bot:x", "content": {"body": "echo"}},
+                {"type": "m.room.message", "sender": "@me:x", "content": {"body": "hi"}},
+                {"type": "m.room.member", "sender": "@me:x", "content": {}}
+            ]}}}}
+        });
+        let (replies, cursor) = parse_matrix_sync(&body, "!room:x", Some("@bot:x"));
+        assert_eq!(replies, vec!["hi".to_string()]);
+        assert_eq!(cursor, Some("s1".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_matrix_attach_fails_without_whoami() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // whoami 返回 401，桥接应直接失败且不发送任何消息
+        let sent = Arc::new(AtomicUsize::new(0));
+        let counter = sent.clone();
+        let app = axum::Router::new()
+            .route(
+                "/_matrix/client/v3/account/whoami",
+                axum::routing::get(|| async {
+                    (
+                        axum::http::StatusCode::UNAUTHORIZED,
+                        axum::Json(serde_json::json!({"errcode": "M_UNKNOWN_TOKEN"})),
+                    )
+                }),
+            )
+            .fallback(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async { axum::Json(serde_json::json!({})) }
+            });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+
+        let service = ChatBridgeService::new();
+        let target = BridgeTarget::Matrix {
+            homeserver: format!("http://127.0.0.1:{}", port),
+            access_token: "token".to_string(),
+            room_id: "!room:x".to_string(),
+        };
+        let err = service
+            .attach("s1".to_string(), target, true, NativeAgentState::new())
+            .await
+            .unwrap_err();
+        assert!(err.contains("Matrix"), "{}", err);
+        assert!(!service.is_bridged("s1"));
+        assert_eq!(sent.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_truncate_message() {
+        let long = "a".repeat(MAX_BRIDGE_MESSAGE_LEN + 10);
+        let truncated = truncate_message(&long);
+        assert_eq!(truncated.chars().count(), MAX_BRIDGE_MESSAGE_LEN + 1);
+        assert_eq!(truncate_message("short"), "short");
+    }
+}
@@ -0,0 +1,396 @@
+//! Claude Code 配置导入服务
+//!
+//! 读取已有的 Claude Code 配置并映射为 ProxyCast 配置：
+//! - `~/.claude/skills/*/SKILL.md` → 复制到 `~/.proxycast/skills/`
+//! - `~/.claude.json` 与 `~/.claude/settings.json` 中的 `mcpServers` → MCP 服务器
+//! - `~/.claude/CLAUDE.md` 与各项目的 `CLAUDE.md` → ProxyCast 提示词
+//!
+//! 已存在的同名 Skill、MCP 服务器和提示词会被跳过，导入可重复执行。
+
+use crate::database::dao::mcp::McpDao;
+use crate::database::dao::prompts::PromptDao;
+use crate::database::dao::skills::SkillDao;
+use crate::database::DbConnection;
+use crate::models::{AppType, McpServer, Prompt, SkillState};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 导入选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaudeImportOptions {
+    #[serde(default = "default_true")]
+    pub skills: bool,
+    #[serde(default = "default_true")]
+    pub mcp_servers: bool,
+    #[serde(default = "default_true")]
+    pub prompts: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for ClaudeImportOptions {
+    fn default() -> Self {
+        Self {
+            skills: true,
+            mcp_servers: true,
+            prompts: true,
+        }
+    }
+}
+
+/// 扫描到的 CLAUDE.md 提示词
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaudePromptSource {
+    /// 导入后的提示词 ID
+    pub id: String,
+    /// 提示词名称
+    pub name: String,
+    /// CLAUDE.md 路径
+    pub path: String,
+}
+
+/// 扫描结果（用于导入前预览）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClaudeImportPlan {
+    /// Skill 目录名
+    pub skills: Vec<String>,
+    /// MCP 服务器 ID
+    pub mcp_servers: Vec<String>,
+    /// 提示词
+    pub prompts: Vec<ClaudePromptSource>,
+}
+
+/// 导入结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClaudeImportReport {
+    pub skills_imported: Vec<String>,
+    pub mcp_servers_imported: Vec<String>,
+    pub prompts_imported: Vec<String>,
+    /// 已存在而跳过的条目
+    pub skipped: Vec<String>,
+    /// 导入失败的条目
+    pub errors: Vec<String>,
+}
+
+pub struct ClaudeImportService;
+
+impl ClaudeImportService {
+    /// 扫描 home 目录下的 Claude Code 配置
+    pub fn scan(home: &Path) -> ClaudeImportPlan {
+        ClaudeImportPlan {
+            skills: scan_skills(home)
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect(),
+            mcp_servers: read_mcp_servers(home).into_keys().collect(),
+            prompts: scan_prompts(home)
+                .into_iter()
+                .map(|(source, _)| source)
+                .collect(),
+        }
+    }
+
+    /// 执行导入
+    pub fn import(
+        db: &DbConnection,
+        home: &Path,
+        options: &ClaudeImportOptions,
+    ) -> Result<ClaudeImportReport, String> {
+        let mut report = ClaudeImportReport::default();
+        let conn = db.lock().map_err(|e| e.to_string())?;
+
+        if options.skills {
+            let target_root = home.join(".proxycast").join("skills");
+            for (name, source) in scan_skills(home) {
+                let target = target_root.join(&name);
+                if target.exists() {
+                    report.skipped.push(format!("skill:{}", name));
+                    continue;
+                }
+                if let Err(e) = copy_dir(&source, &target) {
+                    report.errors.push(format!("skill:{}: {}", name, e));
+                    continue;
+                }
+                let key = format!("{}:{}", AppType::ProxyCast.as_str(), name);
+                let state = SkillState {
+                    installed: true,
+                    installed_at: chrono::Utc::now(),
+                };
+                SkillDao::update_skill_state(&conn, &key, &state).map_err(|e| e.to_string())?;
+                report.skills_imported.push(name);
+            }
+        }
+
+        if options.mcp_servers {
+            let existing: HashSet<String> = McpDao::get_all(&conn)
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .map(|s| s.id)
+                .collect();
+            for (id, config) in read_mcp_servers(home) {
+                if existing.contains(&id) {
+                    report.skipped.push(format!("mcp:{}", id));
+                    continue;
+                }
+                let server = McpServer {
+                    id: id.clone(),
+                    name: id.clone(),
+                    server_config: config,
+                    description: Some("Imported from Claude Code".to_string()),
+                    enabled_proxycast: true,
+                    enabled_claude: true,
+                    enabled_codex: false,
+                    enabled_gemini: false,
+                    created_at: Some(chrono::Utc::now().timestamp()),
+                };
+                match McpDao::insert(&conn, &server) {
+                    Ok(()) => report.mcp_servers_imported.push(id),
+                    Err(e) => report.errors.push(format!("mcp:{}: {}", id, e)),
+                }
+            }
+        }
+
+        if options.prompts {
+            let app_type = AppType::ProxyCast.as_str();
+            for (source, content) in scan_prompts(home) {
+                if PromptDao::get_by_id(&conn, app_type, &source.id)
+                    .map_err(|e| e.to_string())?
+                    .is_some()
+                {
+                    report.skipped.push(format!("prompt:{}", source.id));
+                    continue;
+                }
+                let mut prompt = Prompt::new(
+                    source.id.clone(),
+                    app_type.to_string(),
+                    source.name.clone(),
+                    content,
+                );
+                prompt.description = Some(format!("Imported from {}", source.path));
+                match PromptDao::insert(&conn, &prompt) {
+                    Ok(()) => report.prompts_imported.push(source.id),
+                    Err(e) => report.errors.push(format!("prompt:{}: {}", source.id, e)),
+                }
+            }
+        }
+
+        tracing::info!(
+            "[ClaudeImport] 导入完成: skills={}, mcp={}, prompts={}, skipped={}, errors={}",
+            report.skills_imported.len(),
+            report.mcp_servers_imported.len(),
+            report.prompts_imported.len(),
+            report.skipped.len(),
+            report.errors.len()
+        );
+        Ok(report)
+    }
+}
+
+/// 扫描 `~/.claude/skills` 下包含 SKILL.md 的目录
+fn scan_skills(home: &Path) -> Vec<(String, PathBuf)> {
+    let skills_dir = home.join(".claude").join("skills");
+    let Ok(entries) = fs::read_dir(&skills_dir) else {
+        return Vec::new();
+    };
+
+    let mut skills: Vec<(String, PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.join("SKILL.md").is_file())
+        .filter_map(|path| {
+            let name = path.file_name()?.to_str()?.to_string();
+            Some((name, path))
+        })
+        .collect();
+    skills.sort();
+    skills
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    let content = fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            tracing::warn!("[ClaudeImport] 解析 {} 失败: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// 读取 MCP 服务器定义（`~/.claude.json` 优先于 `~/.claude/settings.json`）
+fn read_mcp_servers(home: &Path) -> BTreeMap<String, Value> {
+    let mut servers = BTreeMap::new();
+    for path in [
+        home.join(".claude").join("settings.json"),
+        home.join(".claude.json"),
+    ] {
+        let Some(config) = read_json(&path) else {
+            continue;
+        };
+        if let Some(map) = config.get("mcpServers").and_then(|v| v.as_object()) {
+            for (id, server) in map {
+                servers.insert(id.clone(), server.clone());
+            }
+        }
+    }
+    servers
+}
+
+/// 扫描用户级和项目级 CLAUDE.md
+fn scan_prompts(home: &Path) -> Vec<(ClaudePromptSource, String)> {
+    let mut candidates = vec![(
+        "claude-import-user".to_string(),
+        "Claude Code 全局指令".to_string(),
+        home.join(".claude").join("CLAUDE.md"),
+    )];
+
+    // ~/.claude.json 的 projects 记录了使用过 Claude Code 的项目目录
+    if let Some(projects) = read_json(&home.join(".claude.json"))
+        .as_ref()
+        .and_then(|c| c.get("projects"))
+        .and_then(|p| p.as_object())
+    {
+        for project in projects.keys() {
+            let dir = PathBuf::from(project);
+            let Some(dir_name) = dir.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            candidates.push((
+                format!("claude-import-project-{}", slugify(project)),
+                format!("项目指令: {}", dir_name),
+                dir.join("CLAUDE.md"),
+            ));
+        }
+    }
+
+    candidates
+        .into_iter()
+        .filter_map(|(id, name, path)| {
+            let content = fs::read_to_string(&path).ok()?;
+            if content.trim().is_empty() {
+                return None;
+            }
+            Some((
+                ClaudePromptSource {
+                    id,
+                    name,
+                    path: path.to_string_lossy().to_string(),
+                },
+                content,
+            ))
+        })
+        .collect()
+}
+
+/// 将路径转换为可用作 ID 的短标识
+fn slugify(path: &str) -> String {
+    let slug: String = path
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    slug.split('-')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn copy_dir(source: &Path, target: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let dest = target.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &dest)?;
+        } else {
+            fs::copy(entry.path(), dest)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+    use std::sync::{Arc, Mutex};
+
+    fn setup_home() -> tempfile::TempDir {
+        let home = tempfile::tempdir().unwrap();
+        let root = home.path();
+
+        let skill = root.join(".claude").join("skills").join("pdf");
+        fs::create_dir_all(skill.join("scripts")).unwrap();
+        fs::write(skill.join("SKILL.md"), "---\nname: pdf\n---\n").unwrap();
+        fs::write(skill.join("scripts").join("run.py"), "print(1)").unwrap();
+        fs::create_dir_all(root.join(".claude").join("skills").join("empty")).unwrap();
+
+        fs::write(root.join(".claude").join("CLAUDE.md"), "Use pnpm.").unwrap();
+
+        let project = root.join("work").join("app");
+        fs::create_dir_all(&project).unwrap();
+        fs::write(project.join("CLAUDE.md"), "Run cargo test.").unwrap();
+
+        let claude_json = serde_json::json!({
+            "mcpServers": { "fs": { "command": "npx", "args": ["fs-server"] } },
+            "projects": { project.to_string_lossy(): {} }
+        });
+        fs::write(root.join(".claude.json"), claude_json.to_string()).unwrap();
+        home
+    }
+
+    fn setup_db() -> DbConnection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::schema::create_tables(&conn).unwrap();
+        Arc::new(Mutex::new(conn))
+    }
+
+    #[test]
+    fn test_scan() {
+        let home = setup_home();
+        let plan = ClaudeImportService::scan(home.path());
+        assert_eq!(plan.skills, vec!["pdf"]);
+        assert_eq!(plan.mcp_servers, vec!["fs"]);
+        assert_eq!(plan.prompts.len(), 2);
+        assert_eq!(plan.prompts[0].id, "claude-import-user");
+        assert_eq!(plan.prompts[1].name, "项目指令: app");
+    }
+
+    #[test]
+    fn test_import_is_idempotent() {
+        let home = setup_home();
+        let db = setup_db();
+        let options = ClaudeImportOptions::default();
+
+        let report = ClaudeImportService::import(&db, home.path(), &options).unwrap();
+        assert_eq!(report.skills_imported, vec!["pdf"]);
+        assert_eq!(report.mcp_servers_imported, vec!["fs"]);
+        assert_eq!(report.prompts_imported.len(), 2);
+        assert!(report.errors.is_empty());
+        assert!(home
+            .path()
+            .join(".proxycast/skills/pdf/scripts/run.py")
+            .is_file());
+
+        let again = ClaudeImportService::import(&db, home.path(), &options).unwrap();
+        assert!(again.skills_imported.is_empty());
+        assert!(again.mcp_servers_imported.is_empty());
+        assert!(again.prompts_imported.is_empty());
+        assert_eq!(again.skipped.len(), 4);
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("/Users/me/My Project"), "users-me-my-project");
+    }
+}
@@ -0,0 +1,684 @@
+//! MCP 子进程管理
+//!
+//! 管理 stdio 类型 MCP 服务器的子进程生命周期：
+//! - 按需启动（首次请求或手动启动时），并完成 MCP 初始化握手
+//! - 定期发送 `ping` 检查健康状态，连续失败时重启进程
+//! - 进程退出后按指数退避重启，连续失败过多时停止重启
+//! - 进程的 stderr 输出写入日志查看器
+
+use crate::config::expand_tilde;
+use crate::mcp_host::protocol::{error_response, MCP_PROTOCOL_VERSION, METHOD_NOT_FOUND};
+use crate::models::McpServer;
+use crate::LogState;
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{ChildStdin, ChildStdout, Command};
+use tokio::sync::{oneshot, watch};
+use tokio_util::sync::CancellationToken;
+
+/// 请求超时（包括启动后的初始化握手）
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 健康检查间隔
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 健康检查 `ping` 超时
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 健康检查连续失败该次数后重启进程
+const MAX_HEALTH_FAILURES: u32 = 3;
+
+/// 重启退避的初始值和上限
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// 运行超过该时长视为稳定，重置退避和失败计数
+const STABLE_RUN: Duration = Duration::from_secs(60);
+
+/// 连续失败该次数后不再重启
+const MAX_CONSECUTIVE_FAILURES: u32 = 5;
+
+/// 应用退出时等待进程结束的最长时间
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 进程状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum McpProcessState {
+    /// 正在启动（含初始化握手）
+    Starting,
+    /// 运行中
+    Running,
+    /// 健康检查失败，达到上限后重启
+    Unhealthy,
+    /// 等待退避后重启
+    Restarting,
+    /// 已停止
+    Stopped,
+    /// 连续失败过多，已放弃重启
+    Failed,
+}
+
+/// MCP 服务器进程状态（返回给前端）
+#[derive(Debug, Clone, Serialize)]
+pub struct McpServerStatus {
+    pub id: String,
+    pub name: String,
+    pub state: McpProcessState,
+    pub pid: Option<u32>,
+    /// 自动重启次数
+    pub restart_count: u32,
+    /// 本次启动时间
+    pub started_at: Option<String>,
+    /// 上次退出原因
+    pub last_exit: Option<String>,
+    /// 最近的错误
+    pub last_error: Option<String>,
+}
+
+/// stdio 启动参数（来自 `server_config`）
+#[derive(Debug, Clone, PartialEq)]
+struct LaunchSpec {
+    command: String,
+    args: Vec<String>,
+    env: HashMap<String, String>,
+    cwd: Option<String>,
+}
+
+impl LaunchSpec {
+    fn from_server(server: &McpServer) -> Result<Self, String> {
+        let config = server
+            .server_config
+            .as_object()
+            .ok_or_else(|| format!("MCP 服务器 {} 的配置格式无效", server.name))?;
+        let transport = config
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or("stdio");
+        if transport != "stdio" {
+            return Err(format!(
+                "MCP 服务器 {} 不是 stdio 类型（{}），无需启动进程",
+                server.name, transport
+            ));
+        }
+        let command = config
+            .get("command")
+            .and_then(Value::as_str)
+            .filter(|c| !c.trim().is_empty())
+            .ok_or_else(|| format!("MCP 服务器 {} 缺少 command", server.name))?;
+        let args = config
+            .get("args")
+            .and_then(Value::as_array)
+            .map(|args| {
+                args.iter()
+                    .filter_map(|a| a.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let env = config
+            .get("env")
+            .and_then(Value::as_object)
+            .map(|env| {
+                env.iter()
+                    .filter_map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let cwd = config
+            .get("cwd")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        Ok(Self {
+            command: command.to_string(),
+            args,
+            env,
+            cwd,
+        })
+    }
+}
+
+type PendingRequests = Mutex<HashMap<u64, oneshot::Sender<Result<Value, String>>>>;
+
+/// 处理子进程 stdout 中的一条消息
+///
+/// 响应交给等待中的请求；服务器发起的请求返回需要写回的响应（只支持 `ping`）
+fn dispatch(pending: &PendingRequests, message: Value) -> Option<Value> {
+    if let Some(method) = message.get("method").and_then(Value::as_str) {
+        // 通知消息不需要响应
+        let id = message.get("id")?.clone();
+        return Some(if method == "ping" {
+            json!({"jsonrpc": "2.0", "id": id, "result": {}})
+        } else {
+            error_response(id, METHOD_NOT_FOUND, format!("不支持的方法: {}", method))
+        });
+    }
+    let id = message.get("id").and_then(Value::as_u64)?;
+    let tx = pending.lock().remove(&id)?;
+    let result = match message.get("error") {
+        Some(error) => Err(error
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or("未知错误")
+            .to_string()),
+        None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+    };
+    let _ = tx.send(result);
+    None
+}
+
+/// 与子进程的 JSON-RPC 连接（按行分隔）
+struct Connection {
+    stdin: tokio::sync::Mutex<ChildStdin>,
+    pending: PendingRequests,
+    next_id: AtomicU64,
+}
+
+impl Connection {
+    fn new(stdin: ChildStdin) -> Self {
+        Self {
+            stdin: tokio::sync::Mutex::new(stdin),
+            pending: PendingRequests::default(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    async fn send(&self, message: &Value) -> Result<(), String> {
+        let mut line = message.to_string();
+        line.push('\n');
+        let mut stdin = self.stdin.lock().await;
+        stdin
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|e| format!("写入 MCP 服务器失败: {}", e))?;
+        stdin
+            .flush()
+            .await
+            .map_err(|e| format!("写入 MCP 服务器失败: {}", e))
+    }
+
+    async fn request(
+        &self,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value, String> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().insert(id, tx);
+        let message = json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params});
+        if let Err(e) = self.send(&message).await {
+            self.pending.lock().remove(&id);
+            return Err(e);
+        }
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err("MCP 服务器进程已退出".to_string()),
+            Err(_) => {
+                self.pending.lock().remove(&id);
+                Err(format!("MCP 请求 {} 超时", method))
+            }
+        }
+    }
+
+    async fn notify(&self, method: &str, params: Value) -> Result<(), String> {
+        self.send(&json!({"jsonrpc": "2.0", "method": method, "params": params}))
+            .await
+    }
+
+    /// 读取 stdout 直到进程关闭输出，之后所有等待中的请求失败
+    async fn read_loop(self: Arc<Self>, stdout: ChildStdout) {
+        let mut lines = BufReader::new(stdout).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            // 忽略非 JSON 输出
+            let Ok(message) = serde_json::from_str::<Value>(&line) else {
+                continue;
+            };
+            if let Some(reply) = dispatch(&self.pending, message) {
+                let _ = self.send(&reply).await;
+            }
+        }
+        self.pending.lock().clear();
+    }
+
+    async fn initialize(&self) -> Result<(), String> {
+        self.request(
+            "initialize",
+            json!({
+                "protocolVersion": MCP_PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": {"name": "proxycast", "version": env!("CARGO_PKG_VERSION")}
+            }),
+            REQUEST_TIMEOUT,
+        )
+        .await?;
+        self.notify("notifications/initialized", json!({})).await
+    }
+}
+
+/// 进程是否仍在运行或等待重启
+fn managed_alive(state: McpProcessState) -> bool {
+    !matches!(state, McpProcessState::Stopped | McpProcessState::Failed)
+}
+
+/// 受管理的 MCP 服务器
+struct Managed {
+    spec: LaunchSpec,
+    status: watch::Sender<McpServerStatus>,
+    connection: RwLock<Option<Arc<Connection>>>,
+    cancel: CancellationToken,
+    logs: LogState,
+}
+
+impl Managed {
+    fn update(&self, f: impl FnOnce(&mut McpServerStatus)) {
+        self.status.send_modify(f);
+    }
+
+    fn name(&self) -> String {
+        self.status.borrow().name.clone()
+    }
+
+    fn is_alive(&self) -> bool {
+        managed_alive(self.status.borrow().state)
+    }
+
+    async fn log(&self, level: &str, message: String) {
+        self.logs.write().await.add(level, &message);
+    }
+
+    /// 监督循环：启动进程，退出后按退避重启，直到取消或连续失败过多
+    async fn supervise(self: Arc<Self>) {
+        let mut backoff = INITIAL_BACKOFF;
+        let mut failures = 0u32;
+        loop {
+            self.update(|s| s.state = McpProcessState::Starting);
+            let started = Instant::now();
+            let reason = tokio::select! {
+                _ = self.cancel.cancelled() => break,
+                reason = self.run_once() => reason,
+            };
+            if self.cancel.is_cancelled() {
+                break;
+            }
+
+            if started.elapsed() >= STABLE_RUN {
+                failures = 0;
+                backoff = INITIAL_BACKOFF;
+            }
+            failures += 1;
+            let name = self.name();
+            if failures >= MAX_CONSECUTIVE_FAILURES {
+                self.update(|s| {
+                    s.state = McpProcessState::Failed;
+                    s.last_error = Some(reason.clone());
+                });
+                self.log(
+                    "error",
+                    format!(
+                        "[MCP] {} 连续失败 {} 次，已停止重启: {}",
+                        name, failures, reason
+                    ),
+                )
+                .await;
+                return;
+            }
+
+            self.update(|s| {
+                s.state = McpProcessState::Restarting;
+                s.restart_count += 1;
+                s.last_error = Some(reason.clone());
+            });
+            self.log(
+                "warn",
+                format!(
+                    "[MCP] {} 已退出（{}），{} 秒后重启",
+                    name,
+                    reason,
+                    backoff.as_secs()
+                ),
+            )
+            .await;
+            tokio::select! {
+                _ = self.cancel.cancelled() => break,
+                _ = tokio::time::sleep(backoff) => {}
+            }
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+        *self.connection.write() = None;
+        self.update(|s| {
+            s.state = McpProcessState::Stopped;
+            s.pid = None;
+        });
+    }
+
+    /// 启动一次进程并运行到退出，返回退出原因
+    async fn run_once(&self) -> String {
+        let name = self.name();
+        let mut command = Command::new(&self.spec.command);
+        command
+            .args(&self.spec.args)
+            .envs(&self.spec.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(cwd) = &self.spec.cwd {
+            command.current_dir(expand_tilde(cwd));
+        }
+        #[cfg(target_os = "windows")]
+        command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => return format!("启动失败: {}", e),
+        };
+        let (Some(stdin), Some(stdout), Some(stderr)) =
+            (child.stdin.take(), child.stdout.take(), child.stderr.take())
+        else {
+            return "无法获取进程的标准输入输出".to_string();
+        };
+        let pid = child.id();
+        self.update(|s| s.pid = pid);
+        self.log(
+            "info",
+            format!("[MCP] {} 已启动 (PID {})", name, pid.unwrap_or_default()),
+        )
+        .await;
+
+        // stderr 写入日志查看器；进程退出后管道关闭，任务随之结束
+        let logs = self.logs.clone();
+        let stderr_name = name.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if !line.trim().is_empty() {
+                    logs.write()
+                        .await
+                        .add("info", &format!("[MCP:{}] {}", stderr_name, line));
+                }
+            }
+        });
+        let connection = Arc::new(Connection::new(stdin));
+        tokio::spawn(connection.clone().read_loop(stdout));
+
+        let reason = tokio::select! {
+            status = child.wait() => match status {
+                Ok(status) => format!("进程已退出: {}", status),
+                Err(e) => format!("等待进程失败: {}", e),
+            },
+            reason = self.monitor(&connection) => reason,
+        };
+        let _ = child.kill().await;
+        *self.connection.write() = None;
+        self.update(|s| {
+            s.pid = None;
+            s.last_exit = Some(reason.clone());
+        });
+        reason
+    }
+
+    /// 完成初始化后定期健康检查，返回需要重启的原因
+    async fn monitor(&self, connection: &Arc<Connection>) -> String {
+        if let Err(e) = connection.initialize().await {
+            return format!("初始化失败: {}", e);
+        }
+        *self.connection.write() = Some(connection.clone());
+        self.update(|s| {
+            s.state = McpProcessState::Running;
+            s.started_at = Some(chrono::Utc::now().to_rfc3339());
+            s.last_error = None;
+        });
+
+        let mut failures = 0u32;
+        let mut interval = tokio::time::interval(HEALTH_CHECK_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            match connection.request("ping", json!({}), PING_TIMEOUT).await {
+                Ok(_) => {
+                    if failures > 0 {
+                        self.update(|s| s.state = McpProcessState::Running);
+                    }
+                    failures = 0;
+                }
+                Err(e) => {
+                    failures += 1;
+                    self.update(|s| {
+                        s.state = McpProcessState::Unhealthy;
+                        s.last_error = Some(e.clone());
+                    });
+                    if failures >= MAX_HEALTH_FAILURES {
+                        return format!("健康检查连续失败 {} 次: {}", failures, e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// MCP 子进程管理器（克隆后共享同一组进程）
+#[derive(Clone)]
+pub struct McpSupervisor {
+    servers: Arc<RwLock<HashMap<String, Arc<Managed>>>>,
+    logs: LogState,
+}
+
+impl McpSupervisor {
+    pub fn new(logs: LogState) -> Self {
+        Self {
+            servers: Arc::default(),
+            logs,
+        }
+    }
+
+    /// 启动进程（已在运行且配置未变时直接返回），不等待就绪
+    fn start(&self, server: &McpServer) -> Result<Arc<Managed>, String> {
+        let spec = LaunchSpec::from_server(server)?;
+        let mut servers = self.servers.write();
+        if let Some(managed) = servers.get(&server.id) {
+            if managed.spec == spec && managed.is_alive() {
+                managed.update(|s| s.name = server.name.clone());
+                return Ok(managed.clone());
+            }
+            managed.cancel.cancel();
+        }
+
+        let (status, _) = watch::channel(McpServerStatus {
+            id: server.id.clone(),
+            name: server.name.clone(),
+            state: McpProcessState::Starting,
+            pid: None,
+            restart_count: 0,
+            started_at: None,
+            last_exit: None,
+            last_error: None,
+        });
+        let managed = Arc::new(Managed {
+            spec,
+            status,
+            connection: RwLock::new(None),
+            cancel: CancellationToken::new(),
+            logs: self.logs.clone(),
+        });
+        servers.insert(server.id.clone(), managed.clone());
+        tauri::async_runtime::spawn(managed.clone().supervise());
+        Ok(managed)
+    }
+
+    /// 确保进程已启动并完成初始化
+    pub async fn ensure_started(&self, server: &McpServer) -> Result<McpServerStatus, String> {
+        let managed = self.start(server)?;
+        let mut rx = managed.status.subscribe();
+        let ready = tokio::time::timeout(REQUEST_TIMEOUT, async {
+            rx.wait_for(|s| s.state != McpProcessState::Starting)
+                .await
+                .map(|_| ())
+        })
+        .await;
+        let status = managed.status.borrow().clone();
+        match ready {
+            Ok(Ok(())) if managed.connection.read().is_some() => Ok(status),
+            Err(_) => Err(format!("MCP 服务器 {} 启动超时", server.name)),
+            _ => Err(format!(
+                "MCP 服务器 {} 启动失败: {}",
+                server.name,
+                status
+                    .last_error
+                    .unwrap_or_else(|| "进程已停止".to_string())
+            )),
+        }
+    }
+
+    /// 向 MCP 服务器发送请求（未运行时自动启动）
+    pub async fn request(
+        &self,
+        server: &McpServer,
+        method: &str,
+        params: Value,
+    ) -> Result<Value, String> {
+        self.ensure_started(server).await?;
+        let connection = self
+            .servers
+            .read()
+            .get(&server.id)
+            .and_then(|managed| managed.connection.read().clone())
+            .ok_or_else(|| format!("MCP 服务器 {} 未就绪", server.name))?;
+        connection.request(method, params, REQUEST_TIMEOUT).await
+    }
+
+    /// 配置变更后重启运行中的进程（未运行的不启动）
+    pub fn refresh(&self, server: &McpServer) {
+        if !self.servers.read().contains_key(&server.id) {
+            return;
+        }
+        if let Err(e) = self.start(server) {
+            tracing::warn!("[MCP] 重启 {} 失败: {}", server.name, e);
+            self.stop(&server.id);
+        }
+    }
+
+    /// 停止进程，返回是否存在
+    pub fn stop(&self, id: &str) -> bool {
+        match self.servers.write().remove(id) {
+            Some(managed) => {
+                managed.cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 停止所有进程并等待其退出（应用退出时调用）
+    pub async fn stop_all(&self) {
+        let stopped: Vec<Arc<Managed>> = self
+            .servers
+            .write()
+            .drain()
+            .map(|(_, managed)| managed)
+            .collect();
+        let waits: Vec<_> = stopped
+            .iter()
+            .map(|managed| {
+                managed.cancel.cancel();
+                let mut rx = managed.status.subscribe();
+                async move {
+                    let _ = rx.wait_for(|s| !managed_alive(s.state)).await;
+                }
+            })
+            .collect();
+        if tokio::time::timeout(SHUTDOWN_TIMEOUT, futures::future::join_all(waits))
+            .await
+            .is_err()
+        {
+            tracing::warn!("[MCP] 等待 MCP 服务器进程退出超时");
+        }
+    }
+
+    pub fn status(&self, id: &str) -> Option<McpServerStatus> {
+        self.servers
+            .read()
+            .get(id)
+            .map(|managed| managed.status.borrow().clone())
+    }
+
+    pub fn list_status(&self) -> Vec<McpServerStatus> {
+        let mut statuses: Vec<McpServerStatus> = self
+            .servers
+            .read()
+            .values()
+            .map(|managed| managed.status.borrow().clone())
+            .collect();
+        statuses.sort_by(|a, b| a.name.cmp(&b.name));
+        statuses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_launch_spec_from_server() {
+        let server = McpServer::new(
+            "fs".to_string(),
+            "filesystem".to_string(),
+            json!({
+                "command": "npx",
+                "args": ["-y", "@modelcontextprotocol/server-filesystem", "/tmp"],
+                "env": {"DEBUG": "1"}
+            }),
+        );
+        let spec = LaunchSpec::from_server(&server).unwrap();
+        assert_eq!(spec.command, "npx");
+        assert_eq!(spec.args.len(), 3);
+        assert_eq!(spec.env.get("DEBUG").map(String::as_str), Some("1"));
+
+        let remote = McpServer::new(
+            "remote".to_string(),
+            "remote".to_string(),
+            json!({"type": "sse", "url": "http://localhost:3000/sse"}),
+        );
+        assert!(LaunchSpec::from_server(&remote).is_err());
+    }
+
+    #[test]
+    fn test_dispatch_routes_responses() {
+        let pending = PendingRequests::default();
+        let (tx, mut rx) = oneshot::channel();
+        pending.lock().insert(7, tx);
+
+        assert!(dispatch(
+            &pending,
+            json!({"jsonrpc": "2.0", "id": 7, "result": {"ok": true}})
+        )
+        .is_none());
+        assert_eq!(rx.try_recv().unwrap().unwrap(), json!({"ok": true}));
+        assert!(pending.lock().is_empty());
+
+        // 服务器发起的 ping 需要回复，其他请求返回方法不存在
+        let reply = dispatch(
+            &pending,
+            json!({"jsonrpc": "2.0", "id": "s1", "method": "ping"}),
+        )
+        .unwrap();
+        assert_eq!(reply["result"], json!({}));
+        let reply = dispatch(
+            &pending,
+            json!({"jsonrpc": "2.0", "id": 2, "method": "roots/list"}),
+        )
+        .unwrap();
+        assert_eq!(reply["error"]["code"], METHOD_NOT_FOUND);
+        assert!(dispatch(
+            &pending,
+            json!({"jsonrpc": "2.0", "method": "notifications/message"})
+        )
+        .is_none());
+    }
+}
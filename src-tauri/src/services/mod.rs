@@ -1,14 +1,23 @@
 pub mod api_key_provider_service;
 pub mod backup_service;
+pub mod chat_bridge_service;
+pub mod claude_import_service;
 pub mod kiro_event_service;
 pub mod live_sync;
 pub mod machine_id_service;
 pub mod mcp_service;
+pub mod mcp_supervisor;
 pub mod mcp_sync;
 pub mod prompt_service;
 pub mod prompt_sync;
+pub mod provider_benchmark_service;
+pub mod provider_concurrency_service;
 pub mod provider_pool_service;
+pub mod session_sync_service;
 pub mod skill_service;
 pub mod switch;
+pub mod token_budget_service;
 pub mod token_cache_service;
+pub mod upstream_probe_service;
+pub mod usage_recorder;
 pub mod usage_service;
@@ -0,0 +1,383 @@
+//! Provider 延迟基准测试
+//!
+//! 通过本地 API Server 的 `/{uuid}/v1/chat/completions` 路由，对每个已启用的凭证和每个模型
+//! 依次发送若干次流式请求，统计首 Token 延迟（TTFT）、总延迟、输出速度（tokens/s）和错误率，
+//! 生成可供界面绘制对比图表的报告。
+//!
+//! 同一凭证和模型的请求串行执行，不同组合之间最多 [`MAX_CONCURRENT_ENTRIES`] 个并发执行。
+//! 首 Token 按第一个正文或推理内容（`reasoning_content`）分片计时。上游未返回 usage 时，
+//! 输出 token 数按收到的内容分片数估算。
+
+use crate::database::dao::provider_pool::ProviderPoolDao;
+use crate::database::DbConnection;
+use crate::server::loadtest::LatencySummary;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+/// 每个组合的最大迭代次数
+pub const MAX_BENCHMARK_ITERATIONS: u32 = 20;
+/// 单次基准测试的最大请求总数
+pub const MAX_BENCHMARK_REQUESTS: usize = 500;
+/// 同时测试的凭证 + 模型组合数
+pub const MAX_CONCURRENT_ENTRIES: usize = 4;
+/// 单个请求超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+/// 每个组合保留的不同错误信息数量
+const MAX_ERROR_SAMPLES: usize = 3;
+/// 默认提示词
+pub const DEFAULT_BENCHMARK_PROMPT: &str = "Write a short paragraph about the ocean.";
+
+/// 单次请求的测量结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchmarkSample {
+    /// 首 Token 延迟（毫秒）
+    pub ttft_ms: f64,
+    /// 总延迟（毫秒）
+    pub total_ms: f64,
+    pub output_tokens: u32,
+}
+
+impl BenchmarkSample {
+    /// 输出速度（首 Token 之后的 tokens/s）
+    pub fn tokens_per_second(&self) -> f64 {
+        let generation_secs = (self.total_ms - self.ttft_ms) / 1000.0;
+        if self.output_tokens == 0 || generation_secs <= 0.0 {
+            return 0.0;
+        }
+        self.output_tokens as f64 / generation_secs
+    }
+}
+
+/// 单个凭证 + 模型的测试结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkEntry {
+    pub credential_uuid: String,
+    pub credential_name: Option<String>,
+    pub provider: String,
+    pub model: String,
+    pub iterations: u32,
+    pub succeeded: u32,
+    pub failed: u32,
+    /// 错误率（0.0 - 1.0）
+    pub error_rate: f64,
+    /// 首 Token 延迟统计
+    pub ttft: LatencySummary,
+    /// 总延迟统计
+    pub latency: LatencySummary,
+    /// 平均输出速度（tokens/s）
+    pub tokens_per_second: f64,
+    /// 失败原因示例
+    pub errors: Vec<String>,
+}
+
+/// 基准测试报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    pub prompt: String,
+    pub iterations: u32,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    /// 按平均首 Token 延迟升序排列（全部失败的排在最后）
+    pub entries: Vec<BenchmarkEntry>,
+}
+
+/// 参与测试的凭证
+#[derive(Debug, Clone)]
+pub struct BenchmarkTarget {
+    pub uuid: String,
+    pub name: Option<String>,
+    pub provider: String,
+}
+
+/// 校验基准测试参数
+pub fn validate_params(models: &[String], targets: usize, iterations: u32) -> Result<(), String> {
+    if models.iter().all(|m| m.trim().is_empty()) {
+        return Err("至少需要一个模型".to_string());
+    }
+    if iterations == 0 || iterations > MAX_BENCHMARK_ITERATIONS {
+        return Err(format!(
+            "迭代次数必须在 1-{} 之间",
+            MAX_BENCHMARK_ITERATIONS
+        ));
+    }
+    if targets == 0 {
+        return Err("没有可用的 Provider 凭证".to_string());
+    }
+    let total = models.len() * targets * iterations as usize;
+    if total > MAX_BENCHMARK_REQUESTS {
+        return Err(format!(
+            "请求总数 {} 超过上限 {}，请减少模型、凭证或迭代次数",
+            total, MAX_BENCHMARK_REQUESTS
+        ));
+    }
+    Ok(())
+}
+
+/// 读取参与测试的凭证（已禁用的凭证不参与；指定 uuid 时只测试这些凭证）
+pub fn load_targets(
+    db: &DbConnection,
+    credential_uuids: Option<&[String]>,
+) -> Result<Vec<BenchmarkTarget>, String> {
+    let credentials = {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?
+    };
+    Ok(credentials
+        .into_iter()
+        .filter(|c| !c.is_disabled)
+        .filter(|c| credential_uuids.map_or(true, |uuids| uuids.contains(&c.uuid)))
+        .map(|c| BenchmarkTarget {
+            uuid: c.uuid,
+            name: c.name,
+            provider: c.provider_type.to_string(),
+        })
+        .collect())
+}
+
+/// 解析一行 SSE 数据，返回（是否包含正文或推理内容，上游报告的输出 token 数）
+fn parse_sse_line(line: &str) -> (bool, Option<u32>) {
+    let Some(data) = line.trim().strip_prefix("data:") else {
+        return (false, None);
+    };
+    let Ok(chunk) = serde_json::from_str::<serde_json::Value>(data.trim()) else {
+        return (false, None);
+    };
+    let has_content = chunk["choices"]
+        .as_array()
+        .map(|choices| {
+            choices.iter().any(|c| {
+                ["content", "reasoning_content"].iter().any(|field| {
+                    c["delta"][*field]
+                        .as_str()
+                        .is_some_and(|text| !text.is_empty())
+                })
+            })
+        })
+        .unwrap_or(false);
+    let usage = chunk["usage"]["completion_tokens"]
+        .as_u64()
+        .map(|tokens| tokens as u32);
+    (has_content, usage)
+}
+
+/// 发送一次流式请求并测量
+pub async fn measure_once(
+    client: &reqwest::Client,
+    endpoint: &str,
+    api_key: &str,
+    model: &str,
+    prompt: &str,
+) -> Result<BenchmarkSample, String> {
+    let body = serde_json::json!({
+        "model": model,
+        "messages": [{"role": "user", "content": prompt}],
+        "stream": true,
+        "stream_options": {"include_usage": true},
+    });
+
+    let started = Instant::now();
+    let response = client
+        .post(endpoint)
+        .bearer_auth(api_key)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        let text = response.text().await.unwrap_or_default();
+        let snippet: String = text.chars().take(200).collect();
+        return Err(format!("HTTP {}: {}", status.as_u16(), snippet));
+    }
+
+    let mut stream = response.bytes_stream();
+    let mut buffer = String::new();
+    let mut ttft_ms = None;
+    let mut chunks = 0u32;
+    let mut reported_tokens = None;
+    while let Some(bytes) = stream.next().await {
+        let bytes = bytes.map_err(|e| format!("读取响应失败: {}", e))?;
+        buffer.push_str(&String::from_utf8_lossy(&bytes));
+        while let Some(pos) = buffer.find('\n') {
+            let line: String = buffer.drain(..=pos).collect();
+            let (has_content, usage) = parse_sse_line(&line);
+            if has_content {
+                chunks += 1;
+                ttft_ms.get_or_insert_with(|| started.elapsed().as_secs_f64() * 1000.0);
+            }
+            if usage.is_some() {
+                reported_tokens = usage;
+            }
+        }
+    }
+
+    let total_ms = started.elapsed().as_secs_f64() * 1000.0;
+    let ttft_ms = ttft_ms.ok_or_else(|| "响应中没有内容".to_string())?;
+    Ok(BenchmarkSample {
+        ttft_ms,
+        total_ms,
+        output_tokens: reported_tokens.unwrap_or(chunks),
+    })
+}
+
+/// 对一个凭证 + 模型串行执行 `iterations` 次请求
+async fn run_entry(
+    client: &reqwest::Client,
+    base_url: &str,
+    api_key: &str,
+    target: &BenchmarkTarget,
+    model: &str,
+    prompt: &str,
+    iterations: u32,
+) -> BenchmarkEntry {
+    let endpoint = format!("{}/{}/v1/chat/completions", base_url, target.uuid);
+    let mut samples = Vec::with_capacity(iterations as usize);
+    let mut errors: Vec<String> = Vec::new();
+    for _ in 0..iterations {
+        match measure_once(client, &endpoint, api_key, model, prompt).await {
+            Ok(sample) => samples.push(sample),
+            Err(e) => {
+                if errors.len() < MAX_ERROR_SAMPLES && !errors.contains(&e) {
+                    errors.push(e);
+                }
+            }
+        }
+    }
+    summarize(target, model, iterations, &samples, errors)
+}
+
+/// 汇总测量结果
+fn summarize(
+    target: &BenchmarkTarget,
+    model: &str,
+    iterations: u32,
+    samples: &[BenchmarkSample],
+    errors: Vec<String>,
+) -> BenchmarkEntry {
+    let succeeded = samples.len() as u32;
+    let tokens_per_second = if samples.is_empty() {
+        0.0
+    } else {
+        samples.iter().map(|s| s.tokens_per_second()).sum::<f64>() / samples.len() as f64
+    };
+    BenchmarkEntry {
+        credential_uuid: target.uuid.clone(),
+        credential_name: target.name.clone(),
+        provider: target.provider.clone(),
+        model: model.to_string(),
+        iterations,
+        succeeded,
+        failed: iterations - succeeded,
+        error_rate: (iterations - succeeded) as f64 / iterations as f64,
+        ttft: LatencySummary::from_latencies(samples.iter().map(|s| s.ttft_ms).collect()),
+        latency: LatencySummary::from_latencies(samples.iter().map(|s| s.total_ms).collect()),
+        tokens_per_second,
+        errors,
+    }
+}
+
+/// 运行基准测试
+///
+/// `base_url` 为本地 API Server 地址（如 `http://127.0.0.1:8999`，启用 TLS 时为 https）
+pub async fn run_benchmark(
+    base_url: &str,
+    api_key: &str,
+    targets: &[BenchmarkTarget],
+    models: &[String],
+    prompt: &str,
+    iterations: u32,
+) -> Result<BenchmarkReport, String> {
+    let models: Vec<String> = models
+        .iter()
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty())
+        .collect();
+    validate_params(&models, targets.len(), iterations)?;
+
+    let client = crate::server::tls::local_client_builder(base_url)
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+
+    let started_at = Utc::now();
+    let started = Instant::now();
+    let permits = Semaphore::new(MAX_CONCURRENT_ENTRIES);
+    let runs = targets.iter().flat_map(|target| {
+        let (client, permits) = (&client, &permits);
+        models.iter().map(move |model| async move {
+            let _permit = permits.acquire().await;
+            run_entry(client, base_url, api_key, target, model, prompt, iterations).await
+        })
+    });
+    let mut entries = futures::future::join_all(runs).await;
+    entries.sort_by(|a, b| {
+        (a.succeeded == 0)
+            .cmp(&(b.succeeded == 0))
+            .then(a.ttft.mean_ms.total_cmp(&b.ttft.mean_ms))
+    });
+
+    Ok(BenchmarkReport {
+        prompt: prompt.to_string(),
+        iterations,
+        started_at,
+        duration_ms: started.elapsed().as_millis() as u64,
+        entries,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::loadtest::MockUpstream;
+
+    #[test]
+    fn test_parse_sse_line_and_params() {
+        assert_eq!(
+            parse_sse_line(r#"data: {"choices":[{"delta":{"content":"Hi"}}]}"#),
+            (true, None)
+        );
+        assert_eq!(
+            parse_sse_line(r#"data: {"choices":[],"usage":{"completion_tokens":42}}"#),
+            (false, Some(42))
+        );
+        assert_eq!(
+            parse_sse_line(r#"data: {"choices":[{"delta":{"reasoning_content":"Hmm"}}]}"#),
+            (true, None)
+        );
+        assert_eq!(parse_sse_line("data: [DONE]"), (false, None));
+        assert_eq!(parse_sse_line(": keep-alive"), (false, None));
+
+        let models = vec!["gpt-4o".to_string()];
+        assert!(validate_params(&models, 2, 5).is_ok());
+        assert!(validate_params(&models, 0, 5).is_err());
+        assert!(validate_params(&models, 2, MAX_BENCHMARK_ITERATIONS + 1).is_err());
+        assert!(validate_params(&[" ".to_string()], 2, 5).is_err());
+        assert!(validate_params(&models, 100, 10).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_measure_once_against_mock_upstream() {
+        let mock = MockUpstream::start().await.unwrap();
+        let client = reqwest::Client::builder().no_proxy().build().unwrap();
+        let endpoint = format!("{}/v1/chat/completions", mock.base_url());
+
+        let sample = measure_once(&client, &endpoint, "key", "any", "hello")
+            .await
+            .unwrap();
+        // 模拟上游返回 6 个内容分片且不带 usage
+        assert_eq!(sample.output_tokens, 6);
+        assert!(sample.ttft_ms <= sample.total_ms);
+
+        let target = BenchmarkTarget {
+            uuid: "u1".to_string(),
+            name: None,
+            provider: "openai".to_string(),
+        };
+        let entry = summarize(&target, "any", 2, &[sample], vec!["HTTP 500".to_string()]);
+        assert_eq!(entry.failed, 1);
+        assert_eq!(entry.error_rate, 0.5);
+    }
+}
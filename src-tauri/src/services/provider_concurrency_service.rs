@@ -0,0 +1,148 @@
+//! Provider 并发限制服务
+//!
+//! 每个 Provider 使用独立的信号量限制同时转发到上游的请求数：
+//! - 名额用完时请求排队等待，超过 `queue_timeout_secs` 后拒绝
+//! - 不同 Provider 的名额互不影响，某个 Provider 变慢或被限流时不会阻塞其他 Provider 的请求
+//! - 名额由调用方持有，流式响应在响应体传输完成（或客户端断开）后才释放
+//! - 修改并发数后新请求使用新的名额，进行中的请求继续占用原名额直到结束
+
+use crate::config::ProviderConcurrencyConfig;
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// 单个 Provider 的并发名额
+struct ProviderSlots {
+    limit: u32,
+    semaphore: Arc<Semaphore>,
+}
+
+/// Provider 并发限制服务
+///
+/// 服务器运行期间与配置同步，修改并发数后立即生效
+#[derive(Default)]
+pub struct ProviderConcurrencyService {
+    config: RwLock<ProviderConcurrencyConfig>,
+    slots: Mutex<HashMap<String, ProviderSlots>>,
+}
+
+impl ProviderConcurrencyService {
+    pub fn new(config: ProviderConcurrencyConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 更新并发配置（配置变更后调用）
+    pub fn set_config(&self, config: ProviderConcurrencyConfig) {
+        *self.config.write() = config;
+    }
+
+    /// 获取 Provider 的并发名额
+    ///
+    /// 未启用或该 Provider 不限制时返回 `Ok(None)`；排队超时返回拒绝原因
+    pub async fn acquire(&self, provider: &str) -> Result<Option<OwnedSemaphorePermit>, String> {
+        let (semaphore, limit, timeout) = {
+            let config = self.config.read();
+            let limit = config.limit_for(provider);
+            if !config.enabled || limit == 0 {
+                return Ok(None);
+            }
+            let mut slots = self.slots.lock();
+            let slot = slots
+                .entry(provider.to_string())
+                .or_insert_with(|| ProviderSlots {
+                    limit,
+                    semaphore: Arc::new(Semaphore::new(limit as usize)),
+                });
+            if slot.limit != limit {
+                *slot = ProviderSlots {
+                    limit,
+                    semaphore: Arc::new(Semaphore::new(limit as usize)),
+                };
+            }
+            (
+                slot.semaphore.clone(),
+                limit,
+                Duration::from_secs(config.queue_timeout_secs),
+            )
+        };
+
+        match tokio::time::timeout(timeout, semaphore.acquire_owned()).await {
+            Ok(Ok(permit)) => Ok(Some(permit)),
+            // 信号量不会被关闭，出错时直接放行
+            Ok(Err(_)) => Ok(None),
+            Err(_) => Err(format!(
+                "Provider {} 的并发请求已达上限 {}，排队超过 {} 秒",
+                provider,
+                limit,
+                timeout.as_secs()
+            )),
+        }
+    }
+
+    /// Provider 正在进行的请求数
+    pub fn in_flight(&self, provider: &str) -> usize {
+        self.slots.lock().get(provider).map_or(0, |slot| {
+            (slot.limit as usize).saturating_sub(slot.semaphore.available_permits())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(limits: &[(&str, u32)]) -> ProviderConcurrencyConfig {
+        ProviderConcurrencyConfig {
+            enabled: true,
+            default_limit: 0,
+            limits: limits
+                .iter()
+                .map(|(provider, limit)| (provider.to_string(), *limit))
+                .collect(),
+            queue_timeout_secs: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_providers_have_independent_slots() {
+        let service = ProviderConcurrencyService::new(config(&[("claude", 1), ("openai", 1)]));
+
+        let claude = service.acquire("claude").await.unwrap();
+        assert!(claude.is_some());
+        assert_eq!(service.in_flight("claude"), 1);
+        // claude 名额已满时排队超时，不影响 openai
+        assert!(service.acquire("claude").await.is_err());
+        let openai = service.acquire("openai").await.unwrap();
+        assert!(openai.is_some());
+
+        drop(claude);
+        assert_eq!(service.in_flight("claude"), 0);
+        assert!(service.acquire("claude").await.unwrap().is_some());
+
+        // 未配置的 Provider 使用 default_limit（0 表示不限制）
+        assert!(service.acquire("gemini").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_disabled_and_limit_change() {
+        let mut cfg = config(&[("claude", 1)]);
+        cfg.enabled = false;
+        let service = ProviderConcurrencyService::new(cfg.clone());
+        assert!(service.acquire("claude").await.unwrap().is_none());
+
+        cfg.enabled = true;
+        service.set_config(cfg.clone());
+        let _first = service.acquire("claude").await.unwrap();
+        assert!(service.acquire("claude").await.is_err());
+
+        // 调大并发数后立即生效
+        cfg.limits.insert("claude".to_string(), 2);
+        service.set_config(cfg);
+        assert!(service.acquire("claude").await.unwrap().is_some());
+    }
+}
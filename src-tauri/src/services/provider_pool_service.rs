@@ -4,19 +4,20 @@
 
 #![allow(dead_code)]
 
+use crate::config::{KeyRotationConfig, RotationStrategy};
 use crate::database::dao::provider_pool::ProviderPoolDao;
 use crate::database::DbConnection;
 use crate::models::provider_pool_model::{
-    get_default_check_model, get_oauth_creds_path, CredentialData, CredentialDisplay,
-    HealthCheckResult, OAuthStatus, PoolProviderType, PoolStats, ProviderCredential,
-    ProviderPoolOverview,
+    get_default_check_model, get_oauth_creds_path, CredentialCooldown, CredentialData,
+    CredentialDisplay, CredentialHealth, HealthCheckResult, OAuthStatus, PoolProviderType,
+    PoolStats, ProviderCredential, ProviderPoolOverview,
 };
 use crate::models::route_model::RouteInfo;
 use crate::providers::kiro::KiroProvider;
 use chrono::Utc;
 use reqwest::Client;
 use std::collections::HashMap;
-use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
 
 /// 凭证池管理服务
@@ -29,6 +30,10 @@ pub struct ProviderPoolService {
     max_error_count: u32,
     /// 健康检查超时时间
     health_check_timeout: Duration,
+    /// 轮换与冷却配置
+    rotation: std::sync::RwLock<KeyRotationConfig>,
+    /// 冷却中的凭证（uuid -> 冷却信息），仅保存在内存中
+    cooldowns: std::sync::RwLock<HashMap<String, CredentialCooldown>>,
 }
 
 impl Default for ProviderPoolService {
@@ -47,9 +52,22 @@ impl ProviderPoolService {
             round_robin_index: std::sync::RwLock::new(HashMap::new()),
             max_error_count: 3,
             health_check_timeout: Duration::from_secs(30),
+            rotation: std::sync::RwLock::new(KeyRotationConfig::default()),
+            cooldowns: std::sync::RwLock::new(HashMap::new()),
         }
     }
 
+    /// 更新轮换与冷却配置
+    pub fn set_rotation_config(&self, config: KeyRotationConfig) {
+        if let Ok(mut rotation) = self.rotation.write() {
+            *rotation = config;
+        }
+    }
+
+    fn rotation_config(&self) -> KeyRotationConfig {
+        self.rotation.read().map(|r| r.clone()).unwrap_or_default()
+    }
+
     /// 获取所有凭证概览
     pub fn get_overview(&self, db: &DbConnection) -> Result<Vec<ProviderPoolOverview>, String> {
         let conn = db.lock().map_err(|e| e.to_string())?;
@@ -173,13 +191,12 @@ impl ProviderPoolService {
         ProviderPoolDao::delete(&conn, uuid).map_err(|e| e.to_string())
     }
 
-    /// 选择一个可用的凭证（智能轮换策略）
+    /// 选择一个可用的凭证
     ///
-    /// 增强版轮换策略，考虑以下因素：
-    /// - 健康状态：优先选择健康的凭证
-    /// - 使用频率：优先选择使用次数较少的凭证
-    /// - 错误率：避免选择错误次数过多的凭证
-    /// - 冷却时间：避免短时间内重复使用同一凭证
+    /// 冷却中的凭证（最近返回 429/401/403）不参与选择，其余凭证按 `key_rotation.strategy` 选择：
+    /// - `weighted`（默认）：综合健康状态、使用频率、错误率和最近使用时间打分
+    /// - `round_robin`：轮询
+    /// - `least_recently_used`：最久未使用优先
     pub fn select_credential(
         &self,
         db: &DbConnection,
@@ -191,10 +208,10 @@ impl ProviderPoolService {
         let credentials = ProviderPoolDao::get_by_type(&conn, &pt).map_err(|e| e.to_string())?;
         drop(conn);
 
-        // 过滤可用的凭证
+        // 过滤可用的凭证（跳过冷却中的凭证）
         let mut available: Vec<_> = credentials
             .into_iter()
-            .filter(|c| c.is_available())
+            .filter(|c| c.is_available() && self.get_cooldown(&c.uuid).is_none())
             .collect();
 
         // 如果指定了模型，进一步过滤支持该模型的凭证
@@ -211,12 +228,38 @@ impl ProviderPoolService {
             return Ok(Some(available.into_iter().next().unwrap()));
         }
 
-        // 智能选择：基于权重分数选择最优凭证
-        let selected = self.select_best_credential_by_weight(&available);
+        let selected = match self.rotation_config().strategy {
+            // 智能选择：基于权重分数选择最优凭证
+            RotationStrategy::Weighted => self.select_best_credential_by_weight(&available),
+            RotationStrategy::RoundRobin => {
+                let key = format!("{}:{}", provider_type, model.unwrap_or_default());
+                self.select_round_robin(&key, &available)
+            }
+            RotationStrategy::LeastRecentlyUsed => select_least_recently_used(&available),
+        };
 
         Ok(Some(selected))
     }
 
+    /// 轮询选择凭证（按 provider_type 和 model 分组计数）
+    fn select_round_robin(
+        &self,
+        key: &str,
+        credentials: &[ProviderCredential],
+    ) -> ProviderCredential {
+        let index = {
+            let mut indices = self
+                .round_robin_index
+                .write()
+                .unwrap_or_else(|e| e.into_inner());
+            indices
+                .entry(key.to_string())
+                .or_insert_with(|| AtomicUsize::new(0))
+                .fetch_add(1, Ordering::SeqCst)
+        };
+        credentials[index % credentials.len()].clone()
+    }
+
     /// 基于权重分数选择最优凭证
     fn select_best_credential_by_weight(
         &self,
@@ -355,8 +398,109 @@ impl ProviderPoolService {
         .map_err(|e| e.to_string())
     }
 
-    /// 重置凭证计数器
+    /// 根据上游状态码让凭证进入冷却
+    ///
+    /// 429 使用限流冷却时间，401/403 使用认证失败冷却时间，其他状态码不冷却
+    pub fn cool_down(&self, uuid: &str, status: u16, reason: &str) -> Option<CredentialCooldown> {
+        let config = self.rotation_config();
+        let secs = match status {
+            429 => config.rate_limit_cooldown_secs,
+            401 | 403 => config.auth_cooldown_secs,
+            _ => return None,
+        };
+        if secs == 0 {
+            return None;
+        }
+
+        let cooldown = CredentialCooldown {
+            status,
+            until: Utc::now() + chrono::Duration::seconds(secs as i64),
+            reason: reason.chars().take(200).collect(),
+        };
+        tracing::warn!("[POOL] 凭证 {} 返回 {}，冷却 {} 秒", uuid, status, secs);
+        self.cooldowns
+            .write()
+            .ok()?
+            .insert(uuid.to_string(), cooldown.clone());
+        Some(cooldown)
+    }
+
+    /// 获取凭证当前的冷却信息（已过期的冷却会被清除）
+    pub fn get_cooldown(&self, uuid: &str) -> Option<CredentialCooldown> {
+        let mut cooldowns = self.cooldowns.write().ok()?;
+        match cooldowns.get(uuid) {
+            Some(cooldown) if cooldown.until > Utc::now() => Some(cooldown.clone()),
+            Some(_) => {
+                cooldowns.remove(uuid);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// 清除凭证冷却，返回凭证之前是否处于冷却中
+    pub fn clear_cooldown(&self, uuid: &str) -> bool {
+        self.cooldowns
+            .write()
+            .map(|mut cooldowns| cooldowns.remove(uuid).is_some())
+            .unwrap_or(false)
+    }
+
+    /// 上游状态码是否说明凭证本身有问题（认证失败、额度耗尽或上游服务错误）
+    ///
+    /// 400/404/422 等由请求内容引起的错误不应计入凭证的错误次数
+    pub fn is_credential_failure(status: u16) -> bool {
+        matches!(status, 401 | 402 | 403 | 429) || (500..600).contains(&status)
+    }
+
+    /// 记录上游 HTTP 错误：429/401/403 进入冷却，认证、额度和 5xx 错误累计错误次数
+    pub fn record_http_failure(
+        &self,
+        db: &DbConnection,
+        uuid: &str,
+        status: u16,
+        body: &str,
+    ) -> Result<(), String> {
+        self.cool_down(uuid, status, body);
+        if !Self::is_credential_failure(status) {
+            return Ok(());
+        }
+        self.mark_unhealthy(db, uuid, Some(body))
+    }
+
+    /// 获取指定类型所有凭证的健康状态（含冷却信息）
+    pub fn get_key_health(
+        &self,
+        db: &DbConnection,
+        provider_type: &str,
+    ) -> Result<Vec<CredentialHealth>, String> {
+        let pt: PoolProviderType = provider_type.parse().map_err(|e: String| e)?;
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        let credentials = ProviderPoolDao::get_by_type(&conn, &pt).map_err(|e| e.to_string())?;
+
+        Ok(credentials
+            .into_iter()
+            .map(|c| {
+                let cooldown = self.get_cooldown(&c.uuid);
+                CredentialHealth {
+                    available: c.is_available() && cooldown.is_none(),
+                    uuid: c.uuid,
+                    name: c.name,
+                    is_healthy: c.is_healthy,
+                    is_disabled: c.is_disabled,
+                    usage_count: c.usage_count,
+                    error_count: c.error_count,
+                    last_used: c.last_used,
+                    last_error_message: c.last_error_message,
+                    cooldown,
+                }
+            })
+            .collect())
+    }
+
+    /// 重置凭证计数器（同时清除冷却）
     pub fn reset_counters(&self, db: &DbConnection, uuid: &str) -> Result<(), String> {
+        self.clear_cooldown(uuid);
         let conn = db.lock().map_err(|e| e.to_string())?;
         ProviderPoolDao::reset_counters(&conn, uuid).map_err(|e| e.to_string())
     }
@@ -1702,3 +1846,76 @@ pub struct MigrationResult {
     /// 错误信息列表
     pub errors: Vec<String>,
 }
+
+/// 选择最久未使用的凭证（从未使用过的优先）
+fn select_least_recently_used(credentials: &[ProviderCredential]) -> ProviderCredential {
+    credentials
+        .iter()
+        .min_by_key(|c| c.last_used)
+        .cloned()
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn openai_key(key: &str) -> ProviderCredential {
+        ProviderCredential::new(
+            PoolProviderType::OpenAI,
+            CredentialData::OpenAIKey {
+                api_key: key.to_string(),
+                base_url: None,
+            },
+        )
+    }
+
+    #[test]
+    fn test_round_robin_cycles_through_keys() {
+        let service = ProviderPoolService::new();
+        let keys = vec![openai_key("sk-a"), openai_key("sk-b")];
+
+        let picked: Vec<String> = (0..4)
+            .map(|_| service.select_round_robin("openai:", &keys).uuid)
+            .collect();
+        assert_eq!(picked[0], keys[0].uuid);
+        assert_eq!(picked[1], keys[1].uuid);
+        assert_eq!(picked[2], keys[0].uuid);
+    }
+
+    #[test]
+    fn test_least_recently_used_prefers_unused() {
+        let mut used = openai_key("sk-a");
+        used.last_used = Some(Utc::now());
+        let unused = openai_key("sk-b");
+
+        let selected = select_least_recently_used(&[used, unused.clone()]);
+        assert_eq!(selected.uuid, unused.uuid);
+    }
+
+    #[test]
+    fn test_cool_down_only_for_rate_limit_and_auth() {
+        let service = ProviderPoolService::new();
+        assert!(service.cool_down("a", 500, "boom").is_none());
+        assert!(service.cool_down("a", 429, "slow down").is_some());
+        assert_eq!(service.get_cooldown("a").unwrap().status, 429);
+        assert!(service.clear_cooldown("a"));
+        assert!(service.get_cooldown("a").is_none());
+
+        service.set_rotation_config(KeyRotationConfig {
+            auth_cooldown_secs: 0,
+            ..Default::default()
+        });
+        assert!(service.cool_down("b", 401, "invalid key").is_none());
+    }
+
+    #[test]
+    fn test_is_credential_failure() {
+        for status in [401, 402, 403, 429, 500, 503] {
+            assert!(ProviderPoolService::is_credential_failure(status));
+        }
+        for status in [400, 404, 413, 422] {
+            assert!(!ProviderPoolService::is_credential_failure(status));
+        }
+    }
+}
@@ -0,0 +1,649 @@
+//! Agent 会话同步服务
+//!
+//! 将会话同步到 WebDAV 或 S3 兼容存储，让对话跟随用户在多台设备间切换。
+//!
+//! 远端布局（`prefix` 下）：
+//! - `index.json` - 会话 ID 到 `updated_at` 的索引
+//! - `<session_id>.json` - 单个会话
+//!
+//! 每次同步先读取远端索引，与本地会话按 `updated_at` 比较：本地较新的推送，远端较新的拉取
+//! （同一会话两边都有修改时以较新的一方为准），最后合并并写回索引。删除的会话不会同步。
+//!
+//! 写入使用基于 ETag 的条件请求（`If-Match` / `If-None-Match: *`）：推送前确认远端会话文件
+//! 不比本地新，其他设备在此期间写入时放弃本次推送（计为冲突，下次同步再处理）；索引被并发修改时
+//! 重新读取合并后重试。拉取的会话只覆盖比它旧的本地版本，同步期间本地的修改不会丢失。
+
+use crate::agent::types::AgentSession;
+use crate::agent::NativeAgentState;
+use crate::config::{SessionSyncBackend, SessionSyncConfig};
+use crate::AppState;
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// 远端索引文件名
+const INDEX_FILE: &str = "index.json";
+
+/// 请求超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 自动同步检查间隔
+const AUTO_SYNC_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 索引条件写入冲突时的最大尝试次数
+const INDEX_WRITE_ATTEMPTS: usize = 3;
+
+/// 远端会话索引
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncIndex {
+    /// 会话 ID -> updated_at
+    #[serde(default)]
+    pub sessions: HashMap<String, String>,
+}
+
+/// 同步计划
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SyncPlan {
+    /// 需要推送的会话 ID
+    pub push: Vec<String>,
+    /// 需要拉取的会话 ID
+    pub pull: Vec<String>,
+}
+
+/// 同步结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionSyncReport {
+    /// 推送的会话数
+    pub pushed: usize,
+    /// 拉取的会话数
+    pub pulled: usize,
+    /// 两边一致的会话数
+    pub unchanged: usize,
+    /// 因其他设备同时写入而放弃推送的会话数
+    #[serde(default)]
+    pub conflicts: usize,
+    /// 同步完成时间
+    pub synced_at: String,
+}
+
+/// 写入条件（基于远端对象的 ETag）
+#[derive(Debug, Clone, PartialEq)]
+enum Precondition {
+    /// 远端对象不存在时才写入
+    Absent,
+    /// 远端对象未被修改时才写入（服务端没有返回 ETag 时无条件写入）
+    Unchanged(Option<String>),
+}
+
+/// 比较两个 RFC 3339 时间，无法解析时按字符串比较
+pub fn is_newer(a: &str, b: &str) -> bool {
+    match (
+        chrono::DateTime::parse_from_rfc3339(a),
+        chrono::DateTime::parse_from_rfc3339(b),
+    ) {
+        (Ok(a), Ok(b)) => a > b,
+        _ => a > b,
+    }
+}
+
+/// 根据本地和远端的 updated_at 生成同步计划（结果按会话 ID 排序）
+pub fn plan_sync(local: &HashMap<String, String>, remote: &HashMap<String, String>) -> SyncPlan {
+    let mut plan = SyncPlan::default();
+    for (id, updated_at) in local {
+        match remote.get(id) {
+            Some(remote_updated) if !is_newer(updated_at, remote_updated) => {}
+            _ => plan.push.push(id.clone()),
+        }
+    }
+    for (id, updated_at) in remote {
+        match local.get(id) {
+            Some(local_updated) if !is_newer(updated_at, local_updated) => {}
+            _ => plan.pull.push(id.clone()),
+        }
+    }
+    plan.push.sort();
+    plan.pull.sort();
+    plan
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC 可以接受任意长度的密钥");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// AWS Signature V4 签名密钥
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let k_date = hmac_sha256(format!("AWS4{}", secret).as_bytes(), date);
+    let k_region = hmac_sha256(&k_date, region);
+    let k_service = hmac_sha256(&k_region, service);
+    hmac_sha256(&k_service, "aws4_request")
+}
+
+/// 同步客户端
+pub struct SessionSyncClient {
+    client: Client,
+    config: SessionSyncConfig,
+}
+
+impl SessionSyncClient {
+    pub fn new(config: &SessionSyncConfig) -> Result<Self, String> {
+        if config.endpoint.trim().is_empty() {
+            return Err("未配置同步地址".to_string());
+        }
+        if config.backend == SessionSyncBackend::S3 && config.bucket.trim().is_empty() {
+            return Err("未配置 S3 存储桶".to_string());
+        }
+        let client = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+        Ok(Self {
+            client,
+            config: config.clone(),
+        })
+    }
+
+    fn key(&self, name: &str) -> String {
+        let prefix = self.config.prefix.trim_matches('/');
+        if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", prefix, name)
+        }
+    }
+
+    fn url(&self, key: &str) -> Result<url::Url, String> {
+        let endpoint = self.config.endpoint.trim().trim_end_matches('/');
+        let path = match self.config.backend {
+            SessionSyncBackend::Webdav => key.to_string(),
+            SessionSyncBackend::S3 => format!("{}/{}", self.config.bucket.trim(), key),
+        };
+        let encoded: Vec<String> = path
+            .split('/')
+            .map(|segment| urlencoding::encode(segment).into_owned())
+            .collect();
+        url::Url::parse(&format!("{}/{}", endpoint, encoded.join("/")))
+            .map_err(|e| format!("无效的同步地址: {}", e))
+    }
+
+    /// 构建请求（S3 使用 Signature V4 签名，WebDAV 使用 Basic 认证）
+    fn request(
+        &self,
+        method: Method,
+        key: &str,
+        body: Vec<u8>,
+    ) -> Result<reqwest::RequestBuilder, String> {
+        let url = self.url(key)?;
+        match self.config.backend {
+            SessionSyncBackend::Webdav => {
+                let mut request = self.client.request(method, url).body(body);
+                if !self.config.username.is_empty() {
+                    request =
+                        request.basic_auth(&self.config.username, Some(&self.config.password));
+                }
+                Ok(request)
+            }
+            SessionSyncBackend::S3 => {
+                let now = chrono::Utc::now();
+                let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+                let date = now.format("%Y%m%d").to_string();
+                let host = match url.port() {
+                    Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+                    None => url.host_str().unwrap_or_default().to_string(),
+                };
+                let payload_hash = sha256_hex(&body);
+                let canonical_request = format!(
+                    "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+                    method.as_str(),
+                    url.path(),
+                    host,
+                    payload_hash,
+                    amz_date,
+                    payload_hash
+                );
+                let region = self.config.region.trim();
+                let scope = format!("{}/{}/s3/aws4_request", date, region);
+                let string_to_sign = format!(
+                    "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+                    amz_date,
+                    scope,
+                    sha256_hex(canonical_request.as_bytes())
+                );
+                let key = signing_key(&self.config.password, &date, region, "s3");
+                let signature = hmac_sha256(&key, &string_to_sign)
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<String>();
+                let authorization = format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+                    self.config.username, scope, signature
+                );
+                Ok(self
+                    .client
+                    .request(method, url)
+                    .header("x-amz-date", amz_date)
+                    .header("x-amz-content-sha256", payload_hash)
+                    .header("Authorization", authorization)
+                    .body(body))
+            }
+        }
+    }
+
+    /// 读取对象及其 ETag，不存在时返回 None
+    async fn get(&self, name: &str) -> Result<Option<(Vec<u8>, Option<String>)>, String> {
+        let key = self.key(name);
+        let response = self
+            .request(Method::GET, &key, Vec::new())?
+            .send()
+            .await
+            .map_err(|e| format!("读取 {} 失败: {}", key, e))?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(format!("读取 {} 失败: HTTP {}", key, response.status()));
+        }
+        let etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| format!("读取 {} 失败: {}", key, e))?;
+        Ok(Some((bytes.to_vec(), etag)))
+    }
+
+    /// 条件写入对象，远端对象已被修改（HTTP 412）时返回 false
+    async fn put(
+        &self,
+        name: &str,
+        body: Vec<u8>,
+        precondition: &Precondition,
+    ) -> Result<bool, String> {
+        let key = self.key(name);
+        let mut request = self
+            .request(Method::PUT, &key, body)?
+            .header("Content-Type", "application/json");
+        request = match precondition {
+            Precondition::Absent => request.header(reqwest::header::IF_NONE_MATCH, "*"),
+            Precondition::Unchanged(Some(etag)) => {
+                request.header(reqwest::header::IF_MATCH, etag.as_str())
+            }
+            Precondition::Unchanged(None) => request,
+        };
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("写入 {} 失败: {}", key, e))?;
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            return Ok(false);
+        }
+        if !response.status().is_success() {
+            return Err(format!("写入 {} 失败: HTTP {}", key, response.status()));
+        }
+        Ok(true)
+    }
+
+    /// WebDAV 逐级创建前缀目录（已存在时服务端返回 405，忽略）
+    async fn ensure_collections(&self) -> Result<(), String> {
+        if self.config.backend != SessionSyncBackend::Webdav {
+            return Ok(());
+        }
+        let mut path = String::new();
+        for segment in self.config.prefix.split('/').filter(|s| !s.is_empty()) {
+            if !path.is_empty() {
+                path.push('/');
+            }
+            path.push_str(segment);
+            let method = Method::from_bytes(b"MKCOL").map_err(|e| e.to_string())?;
+            let response = self
+                .request(method, &format!("{}/", path), Vec::new())?
+                .send()
+                .await
+                .map_err(|e| format!("创建目录 {} 失败: {}", path, e))?;
+            let status = response.status();
+            if !status.is_success() && status != StatusCode::METHOD_NOT_ALLOWED {
+                return Err(format!("创建目录 {} 失败: HTTP {}", path, status));
+            }
+        }
+        Ok(())
+    }
+
+    /// 读取远端索引，同时返回写回索引时使用的条件
+    async fn read_index(&self) -> Result<(SyncIndex, Precondition), String> {
+        match self.get(INDEX_FILE).await? {
+            Some((bytes, etag)) => {
+                let index = serde_json::from_slice(&bytes)
+                    .map_err(|e| format!("解析远端索引失败: {}", e))?;
+                Ok((index, Precondition::Unchanged(etag)))
+            }
+            None => Ok((SyncIndex::default(), Precondition::Absent)),
+        }
+    }
+
+    /// 把推送的会话合并进远端索引（索引被其他设备并发修改时重新读取后重试）
+    async fn update_index(&self, pushed: &[&AgentSession]) -> Result<(), String> {
+        for _ in 0..INDEX_WRITE_ATTEMPTS {
+            let (mut index, precondition) = self.read_index().await?;
+            for session in pushed {
+                let newer = index
+                    .sessions
+                    .get(&session.id)
+                    .is_none_or(|remote| is_newer(&session.updated_at, remote));
+                if newer {
+                    index
+                        .sessions
+                        .insert(session.id.clone(), session.updated_at.clone());
+                }
+            }
+            let body = serde_json::to_vec_pretty(&index).map_err(|e| e.to_string())?;
+            if self.put(INDEX_FILE, body, &precondition).await? {
+                return Ok(());
+            }
+            tracing::debug!("[SessionSync] 远端索引已被其他设备修改，重新合并");
+        }
+        Err("写入远端索引失败: 其他设备正在同步，请稍后重试".to_string())
+    }
+
+    /// 推送单个会话，远端文件比本地新或在此期间被其他设备修改时返回 false
+    async fn push_session(&self, session: &AgentSession) -> Result<bool, String> {
+        let name = format!("{}.json", session.id);
+        let precondition = match self.get(&name).await? {
+            Some((bytes, etag)) => {
+                let remote_updated = serde_json::from_slice::<serde_json::Value>(&bytes)
+                    .ok()
+                    .and_then(|v| v.get("updated_at")?.as_str().map(str::to_string));
+                if remote_updated.is_some_and(|remote| !is_newer(&session.updated_at, &remote)) {
+                    return Ok(false);
+                }
+                Precondition::Unchanged(etag)
+            }
+            None => Precondition::Absent,
+        };
+        let body = serde_json::to_vec(session).map_err(|e| format!("序列化会话失败: {}", e))?;
+        self.put(&name, body, &precondition).await
+    }
+
+    /// 同步会话，返回同步结果和需要写入本地的会话
+    pub async fn sync(
+        &self,
+        local: &[AgentSession],
+    ) -> Result<(SessionSyncReport, Vec<AgentSession>), String> {
+        let (remote, _) = self.read_index().await?;
+        let local_index: HashMap<String, String> = local
+            .iter()
+            .map(|s| (s.id.clone(), s.updated_at.clone()))
+            .collect();
+        let plan = plan_sync(&local_index, &remote.sessions);
+
+        let mut pulled = Vec::with_capacity(plan.pull.len());
+        for id in &plan.pull {
+            match self.get(&format!("{}.json", id)).await? {
+                Some((bytes, _)) => pulled.push(
+                    serde_json::from_slice::<AgentSession>(&bytes)
+                        .map_err(|e| format!("解析远端会话 {} 失败: {}", id, e))?,
+                ),
+                None => tracing::warn!("[SessionSync] 远端缺少会话文件，跳过: {}", id),
+            }
+        }
+
+        if !plan.push.is_empty() {
+            self.ensure_collections().await?;
+        }
+        let mut pushed = Vec::with_capacity(plan.push.len());
+        let mut conflicts = 0;
+        for session in local.iter().filter(|s| plan.push.contains(&s.id)) {
+            if self.push_session(session).await? {
+                pushed.push(session);
+            } else {
+                tracing::info!(
+                    "[SessionSync] 会话 {} 已被其他设备修改，本次不推送",
+                    session.id
+                );
+                conflicts += 1;
+            }
+        }
+
+        if !pushed.is_empty() {
+            self.update_index(&pushed).await?;
+        }
+
+        let report = SessionSyncReport {
+            pushed: pushed.len(),
+            pulled: pulled.len(),
+            unchanged: local_index
+                .keys()
+                .filter(|id| !plan.push.contains(id) && !plan.pull.contains(id))
+                .count(),
+            conflicts,
+            synced_at: chrono::Utc::now().to_rfc3339(),
+        };
+        Ok((report, pulled))
+    }
+}
+
+/// 执行一次同步：推送本地会话，拉取的会话写入 Agent
+pub async fn sync_now(
+    agent_state: &NativeAgentState,
+    config: &SessionSyncConfig,
+) -> Result<SessionSyncReport, String> {
+    let client = SessionSyncClient::new(config)?;
+    let local = agent_state.list_sessions();
+    let (mut report, pulled) = client.sync(&local).await?;
+    if !pulled.is_empty() {
+        report.pulled = agent_state.upsert_newer_sessions(pulled)?;
+    }
+    tracing::info!(
+        "[SessionSync] 同步完成: 推送 {}, 拉取 {}, 未变化 {}, 冲突 {}",
+        report.pushed,
+        report.pulled,
+        report.unchanged,
+        report.conflicts
+    );
+    Ok(report)
+}
+
+/// 启动自动同步任务（`interval_minutes` 为 0 或未启用时跳过）
+pub fn spawn_session_sync(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(AUTO_SYNC_CHECK_INTERVAL);
+        let mut last_sync: Option<std::time::Instant> = None;
+
+        loop {
+            interval.tick().await;
+
+            let Some(app_state) = app_handle.try_state::<AppState>() else {
+                continue;
+            };
+            let config = app_state.read().await.config.session_sync.clone();
+            if !config.enabled || config.interval_minutes == 0 {
+                continue;
+            }
+            let due = Duration::from_secs(u64::from(config.interval_minutes) * 60);
+            if last_sync.is_some_and(|t| t.elapsed() < due) {
+                continue;
+            }
+            let Some(agent_state) = app_handle.try_state::<NativeAgentState>() else {
+                continue;
+            };
+            // Agent 未初始化时没有会话可同步
+            if !agent_state.is_initialized() {
+                continue;
+            }
+            last_sync = Some(std::time::Instant::now());
+            if let Err(e) = sync_now(&agent_state, &config).await {
+                tracing::warn!("[SessionSync] 自动同步失败: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::State;
+    use std::sync::{Arc, Mutex};
+
+    fn index(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(id, t)| (id.to_string(), t.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_sync_by_updated_at() {
+        let local = index(&[
+            ("a", "2024-05-01T10:00:00Z"),
+            ("b", "2024-05-01T10:00:00Z"),
+            ("c", "2024-05-01T10:00:00+00:00"),
+            ("d", "2024-05-01T10:00:00Z"),
+        ]);
+        let remote = index(&[
+            ("a", "2024-05-01T09:00:00Z"),
+            ("b", "2024-05-01T11:00:00Z"),
+            ("c", "2024-05-01T10:00:00Z"),
+            ("e", "2024-05-01T08:00:00Z"),
+        ]);
+        let plan = plan_sync(&local, &remote);
+        assert_eq!(plan.push, vec!["a", "d"]);
+        assert_eq!(plan.pull, vec!["b", "e"]);
+    }
+
+    /// 带 ETag 和条件写入的 WebDAV 模拟服务：路径 -> (内容, 版本号)
+    type MockStore = Arc<Mutex<HashMap<String, (Vec<u8>, u64)>>>;
+
+    async fn mock_webdav(
+        State(store): State<MockStore>,
+        method: axum::http::Method,
+        uri: axum::http::Uri,
+        headers: axum::http::HeaderMap,
+        body: axum::body::Bytes,
+    ) -> axum::response::Response {
+        use axum::response::IntoResponse;
+        let path = uri.path().to_string();
+        let mut store = store.lock().unwrap();
+        match method.as_str() {
+            "GET" => match store.get(&path) {
+                Some((content, version)) => (
+                    [(axum::http::header::ETAG, format!("\"{}\"", version))],
+                    content.clone(),
+                )
+                    .into_response(),
+                None => StatusCode::NOT_FOUND.into_response(),
+            },
+            "PUT" => {
+                let current = store.get(&path).map(|(_, v)| format!("\"{}\"", v));
+                let if_match = headers.get("if-match").and_then(|v| v.to_str().ok());
+                let if_none_match = headers.get("if-none-match").is_some();
+                let allowed = match (if_match, current.as_deref()) {
+                    (Some(expected), Some(current)) => expected == current,
+                    (Some(_), None) => false,
+                    (None, current) => !(if_none_match && current.is_some()),
+                };
+                if !allowed {
+                    return StatusCode::PRECONDITION_FAILED.into_response();
+                }
+                let version = store.get(&path).map_or(1, |(_, v)| v + 1);
+                store.insert(path, (body.to_vec(), version));
+                StatusCode::CREATED.into_response()
+            }
+            _ => StatusCode::CREATED.into_response(),
+        }
+    }
+
+    fn synced_session(id: &str, updated_at: &str) -> AgentSession {
+        AgentSession {
+            updated_at: updated_at.to_string(),
+            ..crate::agent::test_support::session(id, Vec::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_skips_sessions_changed_remotely() {
+        let store: MockStore = Arc::new(Mutex::new(HashMap::new()));
+        let put = |name: &str, value: serde_json::Value| {
+            store.lock().unwrap().insert(
+                format!("/sync/{}", name),
+                (serde_json::to_vec(&value).unwrap(), 1),
+            );
+        };
+        // 索引中 a 的时间较旧，但其他设备已写入了更新的 a.json
+        put(
+            "index.json",
+            serde_json::json!({"sessions": {"a": "2024-05-01T09:00:00Z", "b": "2024-05-01T12:00:00Z"}}),
+        );
+        put(
+            "a.json",
+            serde_json::to_value(synced_session("a", "2024-05-01T13:00:00Z")).unwrap(),
+        );
+        put(
+            "b.json",
+            serde_json::to_value(synced_session("b", "2024-05-01T12:00:00Z")).unwrap(),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let app = axum::Router::new()
+            .fallback(mock_webdav)
+            .with_state(store.clone());
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+
+        let client = SessionSyncClient::new(&SessionSyncConfig {
+            endpoint,
+            prefix: "sync".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        let local = vec![
+            synced_session("a", "2024-05-01T10:00:00Z"),
+            synced_session("b", "2024-05-01T08:00:00Z"),
+            synced_session("c", "2024-05-01T10:00:00Z"),
+        ];
+        let (report, pulled) = client.sync(&local).await.unwrap();
+
+        assert_eq!((report.pushed, report.conflicts), (1, 1));
+        assert_eq!(pulled.len(), 1);
+        assert_eq!(pulled[0].id, "b");
+
+        let store = store.lock().unwrap();
+        let remote_a: AgentSession = serde_json::from_slice(&store["/sync/a.json"].0).unwrap();
+        assert_eq!(remote_a.updated_at, "2024-05-01T13:00:00Z");
+        assert!(store.contains_key("/sync/c.json"));
+        let index: SyncIndex = serde_json::from_slice(&store["/sync/index.json"].0).unwrap();
+        assert_eq!(index.sessions["c"], "2024-05-01T10:00:00Z");
+        assert_eq!(index.sessions["a"], "2024-05-01T09:00:00Z");
+        assert_eq!(store["/sync/index.json"].1, 2);
+    }
+
+    #[test]
+    fn test_sigv4_signing_key() {
+        // AWS 文档中的签名密钥示例
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+        let hex: String = key.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(
+            hex,
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+}
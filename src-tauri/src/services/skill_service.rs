@@ -1,15 +1,207 @@
 use anyhow::{anyhow, Context, Result};
 use reqwest::Client;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::io::{Cursor, Read};
 use std::path::{Path, PathBuf};
 use std::time::Duration;
+use tokio::process::Command;
 use tokio::time::timeout;
 
-use crate::models::{AppType, Skill, SkillMetadata, SkillRepo, SkillState};
+use crate::models::{AppType, Skill, SkillMetadata, SkillRepo, SkillState, SkillUpdateInfo};
 
 const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(60);
 
+/// git 命令超时
+const GIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 更新说明最大字符数
+const MAX_CHANGELOG_CHARS: usize = 4000;
+
+/// git 来源的更新说明最多列出的提交数
+const MAX_CHANGELOG_COMMITS: usize = 50;
+
+type RepoArchive = zip::ZipArchive<Cursor<Vec<u8>>>;
+
+/// 仓库中某个技能的最新信息
+struct RemoteSkill {
+    repo: SkillRepo,
+    version: Option<String>,
+    changelog: Option<String>,
+}
+
+/// 比较版本号：`latest` 是否比 `installed` 新
+///
+/// 两者都是点分数字（可带 `v` 前缀）时按 semver 规则比较：先比较数字部分，数字相同时
+/// 预发布版本（`-beta.1` 等）低于正式版本，预发布标识逐段比较（数字段按数值），
+/// `+xxx` 构建元数据不参与比较。否则只要不同就视为有更新。
+/// 仓库中没有版本号时无法判断，视为没有更新。
+pub fn is_newer_version(latest: Option<&str>, installed: Option<&str>) -> bool {
+    /// 拆分为（数字部分, 预发布标识）
+    fn parse(version: &str) -> Option<(Vec<u64>, Option<&str>)> {
+        let version = version.trim().trim_start_matches(['v', 'V']);
+        let version = version.split_once('+').map_or(version, |(v, _)| v);
+        let (core, pre) = match version.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (version, None),
+        };
+        let numbers: Option<Vec<u64>> = core.split('.').map(|part| part.parse().ok()).collect();
+        numbers.map(|numbers| (numbers, pre))
+    }
+
+    /// 比较预发布标识（没有预发布标识的正式版本最大）
+    fn compare_pre(a: Option<&str>, b: Option<&str>) -> Ordering {
+        let (a, b) = match (a, b) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Greater,
+            (Some(_), None) => return Ordering::Less,
+            (Some(a), Some(b)) => (a, b),
+        };
+        let mut a_parts = a.split('.');
+        let mut b_parts = b.split('.');
+        loop {
+            let ordering = match (a_parts.next(), b_parts.next()) {
+                (None, None) => return Ordering::Equal,
+                (None, Some(_)) => return Ordering::Less,
+                (Some(_), None) => return Ordering::Greater,
+                (Some(x), Some(y)) => match (x.parse::<u64>(), y.parse::<u64>()) {
+                    (Ok(x), Ok(y)) => x.cmp(&y),
+                    (Ok(_), Err(_)) => Ordering::Less,
+                    (Err(_), Ok(_)) => Ordering::Greater,
+                    (Err(_), Err(_)) => x.cmp(y),
+                },
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+    }
+
+    let Some(latest) = latest else {
+        return false;
+    };
+    let Some(installed) = installed else {
+        return true;
+    };
+    match (parse(latest), parse(installed)) {
+        (Some((mut a, a_pre)), Some((mut b, b_pre))) => {
+            let len = a.len().max(b.len());
+            a.resize(len, 0);
+            b.resize(len, 0);
+            a.cmp(&b).then_with(|| compare_pre(a_pre, b_pre)) == Ordering::Greater
+        }
+        _ => latest.trim() != installed.trim(),
+    }
+}
+
+/// 在仓库的技能目录中选出与本地技能对应的一个
+///
+/// 目录名相同的候选按路径深度优先取最浅的；两边的 SKILL.md 都声明了 name 时必须一致，
+/// 避免把同名目录的无关技能当作本地技能的新版本。
+fn pick_remote_skill<'a>(
+    candidates: &'a HashMap<PathBuf, SkillMetadata>,
+    directory: &str,
+    local: &SkillMetadata,
+) -> Option<&'a Path> {
+    candidates
+        .iter()
+        .filter(|(dir, _)| dir.file_name().and_then(|n| n.to_str()) == Some(directory))
+        .filter(|(_, remote)| match (&local.name, &remote.name) {
+            (Some(local), Some(remote)) => local.trim() == remote.trim(),
+            _ => true,
+        })
+        .min_by_key(|(dir, _)| (dir.components().count(), dir.to_path_buf()))
+        .map(|(dir, _)| dir.as_path())
+}
+
+/// 用下载好的目录替换已安装的技能目录
+///
+/// 先把旧目录移到一边再放入新目录，失败时恢复旧目录，不会留下半个技能。
+fn replace_dir(staging: &Path, target: &Path) -> Result<()> {
+    let backup = target.with_file_name(format!(
+        ".{}.old",
+        target
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("skill")
+    ));
+    if backup.exists() {
+        fs::remove_dir_all(&backup).context("Failed to remove stale skill backup")?;
+    }
+    let had_target = target.exists();
+    if had_target {
+        fs::rename(target, &backup).context("Failed to move existing skill aside")?;
+    }
+    if let Err(e) = fs::rename(staging, target) {
+        if had_target {
+            let _ = fs::rename(&backup, target);
+        }
+        return Err(e).context("Failed to move downloaded skill into place");
+    }
+    if had_target {
+        if let Err(e) = fs::remove_dir_all(&backup) {
+            tracing::warn!("删除旧技能目录 {} 失败: {}", backup.display(), e);
+        }
+    }
+    Ok(())
+}
+
+/// 截取 CHANGELOG 中已安装版本之前（即更新的）部分
+///
+/// 遇到包含已安装版本号的标题行时停止；没有已安装版本号时返回全文。结果超过
+/// [`MAX_CHANGELOG_CHARS`] 时截断。
+pub fn changelog_since(changelog: &str, installed_version: Option<&str>) -> Option<String> {
+    let installed = installed_version.map(|v| v.trim().trim_start_matches(['v', 'V']));
+    let mut lines = Vec::new();
+    for line in changelog.lines() {
+        if let Some(installed) = installed {
+            let is_installed_heading = line.trim_start().starts_with('#')
+                && line
+                    .split(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '-'))
+                    .any(|token| token.trim_start_matches(['v', 'V']) == installed);
+            if is_installed_heading {
+                break;
+            }
+        }
+        lines.push(line);
+    }
+
+    let mut text = lines.join("\n").trim().to_string();
+    if text.is_empty() {
+        return None;
+    }
+    if let Some((index, _)) = text.char_indices().nth(MAX_CHANGELOG_CHARS) {
+        text.truncate(index);
+        text.push_str("\n...");
+    }
+    Some(text)
+}
+
+/// 在技能目录中运行 git 命令，返回 stdout
+async fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = timeout(
+        GIT_TIMEOUT,
+        Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .map_err(|_| anyhow!("git {} timed out", args.join(" ")))?
+    .context("Failed to run git")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
 pub struct SkillService {
     client: Client,
 }
@@ -90,17 +282,18 @@ impl SkillService {
                         if !already_exists {
                             let key = format!("local:{}", directory);
                             let skill_md = entry.path().join("SKILL.md");
-                            let (name, description) = if skill_md.exists() {
+                            let (name, description, version) = if skill_md.exists() {
                                 self.parse_skill_metadata(&skill_md)
                                     .map(|m| {
                                         (
                                             m.name.unwrap_or_else(|| directory.clone()),
                                             m.description.unwrap_or_default(),
+                                            m.version,
                                         )
                                     })
-                                    .unwrap_or_else(|_| (directory.clone(), String::new()))
+                                    .unwrap_or_else(|_| (directory.clone(), String::new(), None))
                             } else {
-                                (directory.clone(), String::new())
+                                (directory.clone(), String::new(), None)
                             };
 
                             all_skills.insert(
@@ -115,6 +308,7 @@ impl SkillService {
                                     repo_owner: None,
                                     repo_name: None,
                                     repo_branch: None,
+                                    version,
                                 },
                             );
                         }
@@ -137,29 +331,9 @@ impl SkillService {
         app_type: &AppType,
         installed_states: &HashMap<String, SkillState>,
     ) -> Result<Vec<Skill>> {
-        let zip_url = format!(
-            "https://github.com/{}/{}/archive/refs/heads/{}.zip",
-            repo.owner, repo.name, repo.branch
-        );
-
-        // 下载 ZIP
-        let response = self
-            .client
-            .get(&zip_url)
-            .send()
-            .await
-            .context("Failed to download repository")?;
-
-        if !response.status().is_success() {
-            return Err(anyhow!("HTTP {}: {}", response.status(), zip_url));
-        }
-
-        let bytes = response.bytes().await.context("Failed to read response")?;
-
-        // 解压并扫描
-        let cursor = std::io::Cursor::new(bytes);
-        let mut archive = zip::ZipArchive::new(cursor).context("Failed to open ZIP archive")?;
+        let mut archive = self.download_repo_archive(repo).await?;
 
+        // 扫描
         let mut skills = Vec::new();
         let repo_key_prefix = format!("{}/{}:", repo.owner, repo.name);
 
@@ -178,11 +352,11 @@ impl SkillService {
 
                 // 读取并解析 SKILL.md
                 let mut content = String::new();
-                use std::io::Read;
                 file.read_to_string(&mut content)
                     .context("Failed to read SKILL.md")?;
 
                 let metadata = self.parse_skill_metadata_from_content(&content)?;
+                let version = metadata.version;
                 let name = metadata.name.unwrap_or_else(|| directory.clone());
                 let description = metadata.description.unwrap_or_default();
 
@@ -211,6 +385,7 @@ impl SkillService {
                     repo_owner: Some(repo.owner.clone()),
                     repo_name: Some(repo.name.clone()),
                     repo_branch: Some(repo.branch.clone()),
+                    version,
                 });
             }
         }
@@ -218,6 +393,277 @@ impl SkillService {
         Ok(skills)
     }
 
+    /// 下载仓库 ZIP
+    async fn download_repo_archive(&self, repo: &SkillRepo) -> Result<RepoArchive> {
+        let zip_url = repo.zip_url();
+        let response = self
+            .client
+            .get(&zip_url)
+            .send()
+            .await
+            .context("Failed to download repository")?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("HTTP {}: {}", response.status(), zip_url));
+        }
+
+        let bytes = response.bytes().await.context("Failed to read response")?;
+        zip::ZipArchive::new(Cursor::new(bytes.to_vec())).context("Failed to open ZIP archive")
+    }
+
+    /// 检查已安装技能的更新
+    ///
+    /// - 技能目录是 git 仓库时，`git fetch` 后与上游分支比较，更新说明为新提交列表
+    /// - 否则在启用的技能仓库中按目录名查找（先找到的仓库优先），比较 SKILL.md 的版本号，
+    ///   更新说明取自仓库中技能目录下的 CHANGELOG.md
+    ///
+    /// 不在任何仓库中的本地技能不会出现在结果中。
+    pub async fn check_updates(
+        &self,
+        app_type: &AppType,
+        repos: &[SkillRepo],
+    ) -> Result<Vec<SkillUpdateInfo>> {
+        Ok(self
+            .collect_updates(app_type, repos, None)
+            .await?
+            .into_iter()
+            .map(|(info, _)| info)
+            .collect())
+    }
+
+    /// 更新单个技能，返回更新前的检查结果（含更新说明）
+    pub async fn update_skill(
+        &self,
+        app_type: &AppType,
+        repos: &[SkillRepo],
+        directory: &str,
+    ) -> Result<SkillUpdateInfo> {
+        let (info, repo) = self
+            .collect_updates(app_type, repos, Some(directory))
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("Skill has no update source: {}", directory))?;
+        if !info.update_available {
+            return Err(anyhow!("Skill is already up to date: {}", directory));
+        }
+
+        match repo {
+            Some(repo) => {
+                self.install_skill(app_type, &repo.owner, &repo.name, &repo.branch, directory)
+                    .await?
+            }
+            None => {
+                let dir = Self::get_skills_dir(app_type)?.join(directory);
+                git(&dir, &["pull", "--ff-only", "--quiet"]).await?;
+            }
+        }
+        tracing::info!(
+            "[SkillService] 已更新技能 {}: {:?} -> {:?}",
+            directory,
+            info.installed_version,
+            info.latest_version
+        );
+        Ok(info)
+    }
+
+    /// 收集更新信息，registry 来源同时返回所在仓库（git 来源为 None）
+    async fn collect_updates(
+        &self,
+        app_type: &AppType,
+        repos: &[SkillRepo],
+        only: Option<&str>,
+    ) -> Result<Vec<(SkillUpdateInfo, Option<SkillRepo>)>> {
+        let skills_dir = Self::get_skills_dir(app_type)?;
+        let mut updates = Vec::new();
+        // 非 git 技能：目录名 -> 本地元数据
+        let mut pending: HashMap<String, SkillMetadata> = HashMap::new();
+
+        let Ok(entries) = fs::read_dir(&skills_dir) else {
+            return Ok(updates);
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let directory = entry.file_name().to_string_lossy().to_string();
+            if only.is_some_and(|only| only != directory) || !path.join("SKILL.md").is_file() {
+                continue;
+            }
+            let metadata = self
+                .parse_skill_metadata(&path.join("SKILL.md"))
+                .unwrap_or_else(|_| empty_metadata());
+
+            if path.join(".git").exists() {
+                match self.check_git_update(&path, &directory, metadata).await {
+                    Ok(info) => updates.push((info, None)),
+                    Err(e) => tracing::warn!("检查技能 {} 的 git 更新失败: {}", directory, e),
+                }
+            } else {
+                pending.insert(directory, metadata);
+            }
+        }
+
+        let remote = self.find_remote_skills(repos, &pending).await;
+        for (directory, metadata) in pending {
+            let Some(remote) = remote.get(&directory) else {
+                continue;
+            };
+            let update_available =
+                is_newer_version(remote.version.as_deref(), metadata.version.as_deref());
+            let changelog = if update_available {
+                remote
+                    .changelog
+                    .as_deref()
+                    .and_then(|c| changelog_since(c, metadata.version.as_deref()))
+            } else {
+                None
+            };
+            updates.push((
+                SkillUpdateInfo {
+                    name: metadata.name.unwrap_or_else(|| directory.clone()),
+                    directory,
+                    source: "registry".to_string(),
+                    installed_version: metadata.version,
+                    latest_version: remote.version.clone(),
+                    update_available,
+                    changelog,
+                },
+                Some(remote.repo.clone()),
+            ));
+        }
+
+        updates.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+        Ok(updates)
+    }
+
+    /// 检查 git 仓库形式安装的技能
+    async fn check_git_update(
+        &self,
+        dir: &Path,
+        directory: &str,
+        metadata: SkillMetadata,
+    ) -> Result<SkillUpdateInfo> {
+        git(dir, &["fetch", "--quiet"]).await?;
+        let behind: usize = git(dir, &["rev-list", "--count", "HEAD..@{u}"])
+            .await?
+            .trim()
+            .parse()
+            .context("Failed to parse git rev-list output")?;
+
+        let latest_version = match git(dir, &["show", "@{u}:SKILL.md"]).await {
+            Ok(content) => self
+                .parse_skill_metadata_from_content(&content)
+                .ok()
+                .and_then(|m| m.version),
+            Err(_) => None,
+        };
+        let changelog = if behind > 0 {
+            let max_count = format!("--max-count={}", MAX_CHANGELOG_COMMITS);
+            let log = git(dir, &["log", "--oneline", &max_count, "HEAD..@{u}"]).await?;
+            changelog_since(&log, None)
+        } else {
+            None
+        };
+
+        Ok(SkillUpdateInfo {
+            directory: directory.to_string(),
+            name: metadata.name.unwrap_or_else(|| directory.to_string()),
+            source: "git".to_string(),
+            installed_version: metadata.version,
+            latest_version,
+            update_available: behind > 0,
+            changelog,
+        })
+    }
+
+    /// 在启用的仓库中查找技能的最新版本和 CHANGELOG
+    async fn find_remote_skills(
+        &self,
+        repos: &[SkillRepo],
+        wanted: &HashMap<String, SkillMetadata>,
+    ) -> HashMap<String, RemoteSkill> {
+        let mut found: HashMap<String, RemoteSkill> = HashMap::new();
+        if wanted.is_empty() {
+            return found;
+        }
+
+        for repo in repos.iter().filter(|r| r.enabled) {
+            if found.len() == wanted.len() {
+                break;
+            }
+            let mut archive =
+                match timeout(DOWNLOAD_TIMEOUT, self.download_repo_archive(repo)).await {
+                    Ok(Ok(archive)) => archive,
+                    Ok(Err(e)) => {
+                        tracing::warn!(
+                            "Failed to fetch skills from {}/{}: {}",
+                            repo.owner,
+                            repo.name,
+                            e
+                        );
+                        continue;
+                    }
+                    Err(_) => {
+                        tracing::warn!("Timeout fetching skills from {}/{}", repo.owner, repo.name);
+                        continue;
+                    }
+                };
+
+            // 本仓库中的技能目录（按完整路径）-> SKILL.md 元数据 / CHANGELOG.md
+            let mut skill_dirs: HashMap<PathBuf, SkillMetadata> = HashMap::new();
+            let mut changelogs: HashMap<PathBuf, String> = HashMap::new();
+            let already_found: HashSet<String> = found.keys().cloned().collect();
+            for i in 0..archive.len() {
+                let Ok(mut file) = archive.by_index(i) else {
+                    continue;
+                };
+                let path = Path::new(file.name()).to_path_buf();
+                let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                if file_name != "SKILL.md" && file_name != "CHANGELOG.md" {
+                    continue;
+                }
+                let Some(dir) = path.parent().map(Path::to_path_buf) else {
+                    continue;
+                };
+                let Some(directory) = dir.file_name().and_then(|n| n.to_str()) else {
+                    continue;
+                };
+                if !wanted.contains_key(directory) || already_found.contains(directory) {
+                    continue;
+                }
+                let mut content = String::new();
+                if file.read_to_string(&mut content).is_err() {
+                    continue;
+                }
+                if file_name == "SKILL.md" {
+                    let metadata = self
+                        .parse_skill_metadata_from_content(&content)
+                        .unwrap_or_else(|_| empty_metadata());
+                    skill_dirs.insert(dir, metadata);
+                } else {
+                    changelogs.insert(dir, content);
+                }
+            }
+
+            for (directory, local) in wanted {
+                if found.contains_key(directory) {
+                    continue;
+                }
+                let Some(dir) = pick_remote_skill(&skill_dirs, directory, local) else {
+                    continue;
+                };
+                found.insert(
+                    directory.clone(),
+                    RemoteSkill {
+                        repo: repo.clone(),
+                        version: skill_dirs[dir].version.clone(),
+                        changelog: changelogs.remove(dir),
+                    },
+                );
+            }
+        }
+        found
+    }
+
     /// 安装技能
     pub async fn install_skill(
         &self,
@@ -230,9 +676,11 @@ impl SkillService {
         let skills_dir = Self::get_skills_dir(app_type)?;
         fs::create_dir_all(&skills_dir).context("Failed to create skills directory")?;
 
+        // 先下载到临时目录，成功后再替换已安装的版本，下载失败时保留原技能
         let target_dir = skills_dir.join(directory);
-        if target_dir.exists() {
-            fs::remove_dir_all(&target_dir).context("Failed to remove existing skill")?;
+        let staging_dir = skills_dir.join(format!(".{}.download", directory));
+        if staging_dir.exists() {
+            fs::remove_dir_all(&staging_dir).context("Failed to remove stale download")?;
         }
 
         // 尝试多个分支
@@ -251,11 +699,20 @@ impl SkillService {
             );
 
             match self
-                .download_and_extract(&zip_url, &target_dir, directory)
+                .download_and_extract(&zip_url, &staging_dir, directory)
                 .await
             {
-                Ok(_) => return Ok(()),
+                Ok(_) => {
+                    let result = replace_dir(&staging_dir, &target_dir);
+                    if result.is_err() {
+                        let _ = fs::remove_dir_all(&staging_dir);
+                    }
+                    return result;
+                }
                 Err(e) => {
+                    if staging_dir.exists() {
+                        let _ = fs::remove_dir_all(&staging_dir);
+                    }
                     last_error = Some(e);
                     continue;
                 }
@@ -287,42 +744,41 @@ impl SkillService {
         let cursor = std::io::Cursor::new(bytes);
         let mut archive = zip::ZipArchive::new(cursor).context("Failed to open ZIP")?;
 
-        // 查找技能目录
-        let skill_prefix = format!("/{}/", directory);
-        let mut found = false;
+        // 查找技能目录：目录名匹配且包含 SKILL.md，多个时取路径最浅的
+        let skill_root = archive
+            .file_names()
+            .filter_map(|name| name.strip_suffix("/SKILL.md"))
+            .filter(|dir| dir.rsplit('/').next() == Some(directory))
+            .min_by_key(|dir| (dir.matches('/').count(), dir.to_string()))
+            .map(|dir| format!("{}/", dir));
+        let Some(skill_root) = skill_root else {
+            return Err(anyhow!("Skill directory not found in archive"));
+        };
 
         for i in 0..archive.len() {
             let mut file = archive.by_index(i)?;
-            let file_path = file.name().to_string();
-
-            if file_path.contains(&skill_prefix) {
-                found = true;
-                let relative_path = file_path
-                    .split(&skill_prefix)
-                    .nth(1)
-                    .unwrap_or("")
-                    .to_string();
-
-                if !relative_path.is_empty() {
-                    let output_path = target_dir.join(&relative_path);
-
-                    if file.is_dir() {
-                        fs::create_dir_all(&output_path)?;
-                    } else {
-                        if let Some(parent) = output_path.parent() {
-                            fs::create_dir_all(parent)?;
-                        }
-                        let mut output_file = fs::File::create(&output_path)?;
-                        std::io::copy(&mut file, &mut output_file)?;
+            let Some(relative_path) = file
+                .enclosed_name()
+                .and_then(|path| path.strip_prefix(&skill_root).ok().map(Path::to_path_buf))
+            else {
+                continue;
+            };
+
+            if !relative_path.as_os_str().is_empty() {
+                let output_path = target_dir.join(&relative_path);
+
+                if file.is_dir() {
+                    fs::create_dir_all(&output_path)?;
+                } else {
+                    if let Some(parent) = output_path.parent() {
+                        fs::create_dir_all(parent)?;
                     }
+                    let mut output_file = fs::File::create(&output_path)?;
+                    std::io::copy(&mut file, &mut output_file)?;
                 }
             }
         }
 
-        if !found {
-            return Err(anyhow!("Skill directory not found in archive"));
-        }
-
         Ok(())
     }
 
@@ -350,10 +806,7 @@ impl SkillService {
         let parts: Vec<&str> = content.splitn(3, "---").collect();
 
         if parts.len() < 3 {
-            return Ok(SkillMetadata {
-                name: None,
-                description: None,
-            });
+            return Ok(empty_metadata());
         }
 
         let front_matter = parts[1].trim();
@@ -363,3 +816,99 @@ impl SkillService {
         Ok(meta)
     }
 }
+
+fn empty_metadata() -> SkillMetadata {
+    SkillMetadata {
+        name: None,
+        description: None,
+        version: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skill_version_and_changelog() {
+        let service = SkillService::new().unwrap();
+        let meta = service
+            .parse_skill_metadata_from_content("---\nname: pdf\nversion: 1.2\n---\n# PDF\n")
+            .unwrap();
+        assert_eq!(meta.version.as_deref(), Some("1.2"));
+        let meta = service
+            .parse_skill_metadata_from_content("---\nname: pdf\nversion: \"v2.0.1\"\n---\n")
+            .unwrap();
+        assert_eq!(meta.version.as_deref(), Some("v2.0.1"));
+
+        assert!(is_newer_version(Some("1.10.0"), Some("1.9")));
+        assert!(!is_newer_version(Some("v1.2"), Some("1.2.0")));
+        assert!(!is_newer_version(Some("1.2.0"), Some("1.3.0")));
+        assert!(is_newer_version(Some("2024-06"), Some("2024-05")));
+        assert!(is_newer_version(Some("1.0.0"), Some("1.0.0-beta.2")));
+        assert!(!is_newer_version(Some("1.0.0-rc.1"), Some("1.0.0")));
+        assert!(is_newer_version(
+            Some("1.0.0-beta.10"),
+            Some("1.0.0-beta.9")
+        ));
+        assert!(is_newer_version(Some("1.0.0-rc.1"), Some("1.0.0-beta.9")));
+        assert!(!is_newer_version(
+            Some("1.0.0+build.2"),
+            Some("1.0.0+build.1")
+        ));
+        assert!(is_newer_version(Some("1.1.0-alpha"), Some("1.0.0")));
+        assert!(is_newer_version(Some("1.0"), None));
+        assert!(!is_newer_version(None, Some("1.0")));
+
+        let changelog = "# Changelog\n\n## 1.2.0\n- Add forms\n\n## 1.1.0\n- Fix tables\n\n## 1.0.0\n- Initial\n";
+        let since = changelog_since(changelog, Some("1.1.0")).unwrap();
+        assert!(since.contains("Add forms"));
+        assert!(!since.contains("Fix tables"));
+        assert!(changelog_since(changelog, None)
+            .unwrap()
+            .contains("Initial"));
+    }
+
+    #[test]
+    fn test_pick_remote_skill() {
+        let meta = |name: Option<&str>| SkillMetadata {
+            name: name.map(str::to_string),
+            description: None,
+            version: None,
+        };
+        let mut candidates = HashMap::new();
+        candidates.insert(PathBuf::from("repo-main/skills/pdf"), meta(Some("pdf")));
+        candidates.insert(
+            PathBuf::from("repo-main/skills/pdf/examples/pdf"),
+            meta(Some("pdf")),
+        );
+        candidates.insert(PathBuf::from("repo-main/other/pdf-tools"), meta(None));
+
+        let picked = pick_remote_skill(&candidates, "pdf", &meta(Some("pdf"))).unwrap();
+        assert_eq!(picked, Path::new("repo-main/skills/pdf"));
+        // 同名目录但技能名不同，不是同一个技能
+        assert!(pick_remote_skill(&candidates, "pdf", &meta(Some("my-pdf"))).is_none());
+        assert!(pick_remote_skill(&candidates, "pdf-tools", &meta(Some("pdf-tools"))).is_some());
+        assert!(pick_remote_skill(&candidates, "docx", &meta(None)).is_none());
+    }
+
+    #[test]
+    fn test_replace_dir_keeps_old_skill_until_swap() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("pdf");
+        let staging = dir.path().join(".pdf.download");
+        fs::create_dir_all(&target).unwrap();
+        fs::write(target.join("SKILL.md"), "old").unwrap();
+
+        // 临时目录不存在（下载失败）时保留原技能
+        assert!(replace_dir(&staging, &target).is_err());
+        assert_eq!(fs::read_to_string(target.join("SKILL.md")).unwrap(), "old");
+
+        fs::create_dir_all(&staging).unwrap();
+        fs::write(staging.join("SKILL.md"), "new").unwrap();
+        replace_dir(&staging, &target).unwrap();
+        assert_eq!(fs::read_to_string(target.join("SKILL.md")).unwrap(), "new");
+        assert!(!staging.exists());
+        assert!(!dir.path().join(".pdf.old").exists());
+    }
+}
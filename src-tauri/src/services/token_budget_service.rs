@@ -0,0 +1,264 @@
+//! Token 预算服务
+//!
+//! 按客户端 Key 和 Provider 限制每日 / 每月的 Token 用量或费用：
+//! - 用量按天汇总保存在数据库，服务器重启后不会清零
+//! - 费用在记录时按配置的模型单价计算，未配置单价的模型只计 Token
+//! - 请求前检查所有适用的预算，任一耗尽即拒绝
+//! - 检查失败（如数据库错误）时放行请求，只记录警告
+
+use crate::config::{BudgetPeriod, TokenBudgetConfig, TokenBudgetLimit};
+use crate::database::dao::token_budget::{BudgetUsage, TokenBudgetDao};
+use crate::database::DbConnection;
+use chrono::{Datelike, Local, NaiveDate};
+use parking_lot::RwLock;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+/// 单个预算的当前状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenBudgetStatus {
+    pub name: String,
+    pub client_key: Option<String>,
+    pub provider: Option<String>,
+    pub period: BudgetPeriod,
+    /// 当前周期的起始日期（YYYY-MM-DD）
+    pub period_start: String,
+    pub used_tokens: u64,
+    /// 最大 Token 数（0 表示不限制）
+    pub max_tokens: u64,
+    /// 剩余 Token 数（不限制时为空）
+    pub remaining_tokens: Option<u64>,
+    pub used_cost_usd: f64,
+    /// 最大费用（0 表示不限制）
+    pub max_cost_usd: f64,
+    /// 剩余费用（不限制时为空）
+    pub remaining_cost_usd: Option<f64>,
+    /// 是否已耗尽
+    pub exhausted: bool,
+}
+
+/// Token 预算服务
+///
+/// 服务器运行期间与配置同步，修改预算后立即生效
+#[derive(Debug, Default)]
+pub struct TokenBudgetService {
+    config: RwLock<TokenBudgetConfig>,
+}
+
+fn format_day(day: NaiveDate) -> String {
+    day.format("%Y-%m-%d").to_string()
+}
+
+/// 预算周期的起始日期
+fn period_start(period: BudgetPeriod, today: NaiveDate) -> NaiveDate {
+    match period {
+        BudgetPeriod::Daily => today,
+        BudgetPeriod::Monthly => today.with_day(1).unwrap_or(today),
+    }
+}
+
+impl TokenBudgetService {
+    pub fn new(config: TokenBudgetConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+        }
+    }
+
+    /// 更新预算配置（配置变更后调用）
+    pub fn set_config(&self, config: TokenBudgetConfig) {
+        *self.config.write() = config;
+    }
+
+    /// 是否启用
+    pub fn is_enabled(&self) -> bool {
+        self.config.read().enabled
+    }
+
+    /// 检查请求是否超出预算，超出时返回拒绝原因
+    pub fn check(
+        &self,
+        db: &DbConnection,
+        client_key: Option<&str>,
+        provider: &str,
+    ) -> Option<String> {
+        let config = self.config.read().clone();
+        if !config.enabled {
+            return None;
+        }
+        let conn = match db.lock() {
+            Ok(conn) => conn,
+            Err(e) => {
+                tracing::warn!("[TOKEN_BUDGET] 数据库锁定失败，跳过预算检查: {}", e);
+                return None;
+            }
+        };
+        let today = Local::now().date_naive();
+        for limit in config
+            .limits
+            .iter()
+            .filter(|l| l.applies_to(client_key, provider))
+        {
+            match limit_status(&conn, limit, today) {
+                Ok(status) if status.exhausted => {
+                    let period = match limit.period {
+                        BudgetPeriod::Daily => "daily",
+                        BudgetPeriod::Monthly => "monthly",
+                    };
+                    return Some(format!(
+                        "Token budget '{}' exhausted for the {} period",
+                        limit.name, period
+                    ));
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!("[TOKEN_BUDGET] 查询预算 {} 失败: {}", limit.name, e);
+                }
+            }
+        }
+        None
+    }
+
+    /// 记录一次请求的用量
+    pub fn record(
+        &self,
+        db: &DbConnection,
+        client_key: Option<&str>,
+        provider: &str,
+        model: &str,
+        input_tokens: u32,
+        output_tokens: u32,
+    ) {
+        let cost_usd = {
+            let config = self.config.read();
+            if !config.enabled {
+                return;
+            }
+            config
+                .price_for(model)
+                .map_or(0.0, |price| price.cost_usd(input_tokens, output_tokens))
+        };
+        let result = db.lock().map_err(|e| e.to_string()).and_then(|conn| {
+            TokenBudgetDao::add_usage(
+                &conn,
+                &format_day(Local::now().date_naive()),
+                client_key.unwrap_or(""),
+                provider,
+                input_tokens,
+                output_tokens,
+                cost_usd,
+            )
+            .map_err(|e| e.to_string())
+        });
+        if let Err(e) = result {
+            tracing::warn!("[TOKEN_BUDGET] 记录用量失败: {}", e);
+        }
+    }
+
+    /// 获取所有预算的当前状态
+    pub fn status(&self, conn: &Connection) -> Result<Vec<TokenBudgetStatus>, String> {
+        let today = Local::now().date_naive();
+        let config = self.config.read().clone();
+        config
+            .limits
+            .iter()
+            .map(|limit| limit_status(conn, limit, today).map_err(|e| e.to_string()))
+            .collect()
+    }
+}
+
+/// 计算单个预算在 `today` 所在周期的状态
+fn limit_status(
+    conn: &Connection,
+    limit: &TokenBudgetLimit,
+    today: NaiveDate,
+) -> Result<TokenBudgetStatus, rusqlite::Error> {
+    let start = format_day(period_start(limit.period, today));
+    let BudgetUsage { tokens, cost_usd } = TokenBudgetDao::sum_usage(
+        conn,
+        &start,
+        &format_day(today),
+        limit.client_key.as_deref(),
+        limit.provider.as_deref(),
+    )?;
+    let remaining_tokens = (limit.max_tokens > 0).then(|| limit.max_tokens.saturating_sub(tokens));
+    let remaining_cost_usd =
+        (limit.max_cost_usd > 0.0).then(|| (limit.max_cost_usd - cost_usd).max(0.0));
+    Ok(TokenBudgetStatus {
+        name: limit.name.clone(),
+        client_key: limit.client_key.clone(),
+        provider: limit.provider.clone(),
+        period: limit.period,
+        period_start: start,
+        used_tokens: tokens,
+        max_tokens: limit.max_tokens,
+        remaining_tokens,
+        used_cost_usd: cost_usd,
+        max_cost_usd: limit.max_cost_usd,
+        remaining_cost_usd,
+        exhausted: remaining_tokens == Some(0) || remaining_cost_usd == Some(0.0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(name: &str, client_key: Option<&str>, period: BudgetPeriod) -> TokenBudgetLimit {
+        TokenBudgetLimit {
+            name: name.to_string(),
+            client_key: client_key.map(|k| k.to_string()),
+            provider: None,
+            period,
+            max_tokens: 1000,
+            max_cost_usd: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_limit_status_by_period_and_key() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::schema::create_tables(&conn).unwrap();
+        TokenBudgetDao::add_usage(&conn, "2026-03-01", "team-a", "claude", 300, 100, 0.5).unwrap();
+        TokenBudgetDao::add_usage(&conn, "2026-03-15", "team-a", "openai", 400, 200, 0.2).unwrap();
+        TokenBudgetDao::add_usage(&conn, "2026-03-15", "", "claude", 50, 50, 0.0).unwrap();
+
+        let today = NaiveDate::from_ymd_opt(2026, 3, 15).unwrap();
+        let daily = limit_status(
+            &conn,
+            &limit("daily", Some("team-a"), BudgetPeriod::Daily),
+            today,
+        )
+        .unwrap();
+        assert_eq!(daily.period_start, "2026-03-15");
+        assert_eq!(daily.used_tokens, 600);
+        assert_eq!(daily.remaining_tokens, Some(400));
+        assert!(!daily.exhausted);
+
+        let monthly = limit_status(
+            &conn,
+            &limit("monthly", Some("team-a"), BudgetPeriod::Monthly),
+            today,
+        )
+        .unwrap();
+        assert_eq!(monthly.period_start, "2026-03-01");
+        assert_eq!(monthly.used_tokens, 1000);
+        assert_eq!(monthly.remaining_tokens, Some(0));
+        assert!(monthly.exhausted);
+
+        // 不限定 Key 时统计所有请求；只限制 Token 时不计费用上限
+        let all = limit_status(&conn, &limit("all", None, BudgetPeriod::Monthly), today).unwrap();
+        assert_eq!(all.used_tokens, 1100);
+        assert!((all.used_cost_usd - 0.7).abs() < 1e-9);
+        assert_eq!(all.remaining_cost_usd, None);
+    }
+
+    #[test]
+    fn test_limit_applies_to() {
+        let mut l = limit("k", Some("team-a"), BudgetPeriod::Daily);
+        l.provider = Some("claude".to_string());
+        assert!(l.applies_to(Some("team-a"), "claude"));
+        assert!(!l.applies_to(Some("team-a"), "openai"));
+        assert!(!l.applies_to(None, "claude"));
+        assert!(limit("all", None, BudgetPeriod::Daily).applies_to(None, "openai"));
+    }
+}
@@ -0,0 +1,335 @@
+//! 上游健康探测
+//!
+//! 为 `/healthz` 和状态页检查各上游 Provider 的连通性：
+//! - API Key 凭证请求模型列表（不消耗 Token），同时验证 Key 是否有效
+//! - OAuth 凭证只对服务端点发送 HEAD 请求，收到任意 HTTP 响应即视为可达
+//! - 同一 Provider 的相同端点只探测一次，优先使用健康的凭证
+//! - 已禁用的凭证不参与探测；所有探测并发执行
+//!
+//! 与凭证池的健康检查不同，探测不发送对话请求，也不修改凭证的健康状态。
+
+use crate::database::dao::provider_pool::ProviderPoolDao;
+use crate::database::DbConnection;
+use crate::models::provider_pool_model::{CredentialData, ProviderCredential};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+/// 单个探测的超时时间
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+const KIRO_ENDPOINT: &str = "https://codewhisperer.us-east-1.amazonaws.com";
+const GEMINI_CODE_ASSIST_ENDPOINT: &str = "https://cloudcode-pa.googleapis.com";
+const GEMINI_API_ENDPOINT: &str = "https://generativelanguage.googleapis.com";
+const QWEN_ENDPOINT: &str = "https://portal.qwen.ai";
+const ANTIGRAVITY_ENDPOINT: &str = "https://daily-cloudcode-pa.sandbox.googleapis.com";
+const OPENAI_ENDPOINT: &str = "https://api.openai.com";
+const ANTHROPIC_ENDPOINT: &str = "https://api.anthropic.com";
+const CODEX_ENDPOINT: &str = "https://chatgpt.com";
+const IFLOW_ENDPOINT: &str = "https://apis.iflow.cn";
+
+/// 探测结果状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeStatus {
+    Up,
+    Down,
+}
+
+/// 整体健康状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    /// 全部上游可用（或未探测）
+    Healthy,
+    /// 部分上游不可用
+    Degraded,
+    /// 全部上游不可用
+    Unhealthy,
+}
+
+/// 单个上游的探测结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamProbe {
+    pub provider: String,
+    /// 探测的 URL
+    pub endpoint: String,
+    /// 用于探测的凭证
+    pub credential_uuid: String,
+    pub credential_name: Option<String>,
+    pub status: ProbeStatus,
+    pub latency_ms: u64,
+    /// 上游返回的 HTTP 状态码（请求失败时为空）
+    pub http_status: Option<u16>,
+    pub error: Option<String>,
+}
+
+/// 健康检查报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamHealthReport {
+    pub status: HealthStatus,
+    pub version: String,
+    pub checked_at: DateTime<Utc>,
+    pub upstreams: Vec<UpstreamProbe>,
+}
+
+impl UpstreamHealthReport {
+    /// 根据探测结果生成报告
+    pub fn from_probes(upstreams: Vec<UpstreamProbe>) -> Self {
+        let up = upstreams
+            .iter()
+            .filter(|p| p.status == ProbeStatus::Up)
+            .count();
+        let status = if up == upstreams.len() {
+            HealthStatus::Healthy
+        } else if up == 0 {
+            HealthStatus::Unhealthy
+        } else {
+            HealthStatus::Degraded
+        };
+        Self {
+            status,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            checked_at: Utc::now(),
+            upstreams,
+        }
+    }
+}
+
+/// 探测方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProbeMethod {
+    /// 请求模型列表，需要 2xx 响应
+    ListModels,
+    /// HEAD 请求，任意 HTTP 响应即可
+    Head,
+}
+
+/// 单个凭证对应的探测请求
+#[derive(Debug, Clone)]
+struct ProbeTarget {
+    method: ProbeMethod,
+    url: String,
+    headers: Vec<(&'static str, String)>,
+}
+
+/// 拼接 `/v1/{endpoint}`，兼容 base_url 自带 `/v1` 的情况
+fn v1_url(base: &str, endpoint: &str) -> String {
+    let base = base.trim_end_matches('/');
+    if base.ends_with("/v1") {
+        format!("{}/{}", base, endpoint)
+    } else {
+        format!("{}/v1/{}", base, endpoint)
+    }
+}
+
+fn head(url: &str) -> ProbeTarget {
+    ProbeTarget {
+        method: ProbeMethod::Head,
+        url: url.to_string(),
+        headers: Vec::new(),
+    }
+}
+
+/// 构建凭证的探测请求
+fn probe_target(credential: &CredentialData) -> ProbeTarget {
+    let base_or = |base_url: &Option<String>, default: &str| {
+        base_url
+            .as_deref()
+            .filter(|b| !b.trim().is_empty())
+            .unwrap_or(default)
+            .to_string()
+    };
+    match credential {
+        CredentialData::OpenAIKey { api_key, base_url } => ProbeTarget {
+            method: ProbeMethod::ListModels,
+            url: v1_url(&base_or(base_url, OPENAI_ENDPOINT), "models"),
+            headers: vec![("authorization", format!("Bearer {}", api_key))],
+        },
+        CredentialData::ClaudeKey { api_key, base_url } => ProbeTarget {
+            method: ProbeMethod::ListModels,
+            url: v1_url(&base_or(base_url, ANTHROPIC_ENDPOINT), "models"),
+            headers: vec![
+                ("x-api-key", api_key.clone()),
+                ("anthropic-version", "2023-06-01".to_string()),
+            ],
+        },
+        CredentialData::GeminiApiKey {
+            api_key, base_url, ..
+        } => ProbeTarget {
+            method: ProbeMethod::ListModels,
+            url: format!(
+                "{}/v1beta/models",
+                base_or(base_url, GEMINI_API_ENDPOINT).trim_end_matches('/')
+            ),
+            headers: vec![("x-goog-api-key", api_key.clone())],
+        },
+        CredentialData::VertexKey { base_url, .. } => head(&base_or(base_url, GEMINI_API_ENDPOINT)),
+        CredentialData::KiroOAuth { .. } => head(KIRO_ENDPOINT),
+        CredentialData::GeminiOAuth { .. } => head(GEMINI_CODE_ASSIST_ENDPOINT),
+        CredentialData::QwenOAuth { .. } => head(QWEN_ENDPOINT),
+        CredentialData::AntigravityOAuth { .. } => head(ANTIGRAVITY_ENDPOINT),
+        CredentialData::CodexOAuth { api_base_url, .. } => {
+            head(&base_or(api_base_url, CODEX_ENDPOINT))
+        }
+        CredentialData::ClaudeOAuth { .. } => head(ANTHROPIC_ENDPOINT),
+        CredentialData::IFlowOAuth { .. } | CredentialData::IFlowCookie { .. } => {
+            head(IFLOW_ENDPOINT)
+        }
+    }
+}
+
+/// 执行单个探测
+async fn run_probe(
+    client: &reqwest::Client,
+    credential: &ProviderCredential,
+    target: ProbeTarget,
+) -> UpstreamProbe {
+    let mut request = match target.method {
+        ProbeMethod::ListModels => client.get(&target.url),
+        ProbeMethod::Head => client.head(&target.url),
+    };
+    for (name, value) in &target.headers {
+        request = request.header(*name, value);
+    }
+
+    let start = Instant::now();
+    let result = request.send().await;
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    let (status, http_status, error) = match result {
+        Ok(resp) => {
+            let code = resp.status();
+            if target.method == ProbeMethod::Head || code.is_success() {
+                (ProbeStatus::Up, Some(code.as_u16()), None)
+            } else {
+                (
+                    ProbeStatus::Down,
+                    Some(code.as_u16()),
+                    Some(format!("HTTP {}", code)),
+                )
+            }
+        }
+        Err(e) if e.is_timeout() => (
+            ProbeStatus::Down,
+            None,
+            Some(format!("请求超时（{} 秒）", PROBE_TIMEOUT.as_secs())),
+        ),
+        Err(e) => (ProbeStatus::Down, None, Some(e.to_string())),
+    };
+
+    UpstreamProbe {
+        provider: credential.provider_type.to_string(),
+        endpoint: target.url,
+        credential_uuid: credential.uuid.clone(),
+        credential_name: credential.name.clone(),
+        status,
+        latency_ms,
+        http_status,
+        error,
+    }
+}
+
+/// 探测所有已配置的上游 Provider
+pub async fn probe_upstreams(db: &DbConnection) -> Result<UpstreamHealthReport, String> {
+    let mut credentials = {
+        let conn = db.lock().map_err(|e| e.to_string())?;
+        ProviderPoolDao::get_all(&conn).map_err(|e| e.to_string())?
+    };
+    credentials.retain(|c| !c.is_disabled);
+    // 健康的凭证排在前面，去重时优先保留
+    credentials.sort_by_key(|c| !c.is_healthy);
+
+    let mut seen = HashSet::new();
+    let targets: Vec<_> = credentials
+        .iter()
+        .filter_map(|credential| {
+            let target = probe_target(&credential.credential);
+            seen.insert((credential.provider_type.to_string(), target.url.clone()))
+                .then_some((credential, target))
+        })
+        .collect();
+
+    let client = reqwest::Client::builder()
+        .timeout(PROBE_TIMEOUT)
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+    let mut probes = futures::future::join_all(
+        targets
+            .into_iter()
+            .map(|(credential, target)| run_probe(&client, credential, target)),
+    )
+    .await;
+    probes.sort_by(|a, b| a.provider.cmp(&b.provider));
+
+    let down = probes
+        .iter()
+        .filter(|p| p.status == ProbeStatus::Down)
+        .count();
+    if down > 0 {
+        tracing::warn!("[HEALTHZ] {}/{} 个上游不可用", down, probes.len());
+    }
+    Ok(UpstreamHealthReport::from_probes(probes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_target() {
+        let openai = probe_target(&CredentialData::OpenAIKey {
+            api_key: "sk-test".to_string(),
+            base_url: Some("https://api.deepseek.com/v1/".to_string()),
+        });
+        assert_eq!(openai.method, ProbeMethod::ListModels);
+        assert_eq!(openai.url, "https://api.deepseek.com/v1/models");
+        assert_eq!(
+            openai.headers,
+            vec![("authorization", "Bearer sk-test".to_string())]
+        );
+
+        let claude = probe_target(&CredentialData::ClaudeKey {
+            api_key: "key".to_string(),
+            base_url: None,
+        });
+        assert_eq!(claude.url, "https://api.anthropic.com/v1/models");
+
+        let kiro = probe_target(&CredentialData::KiroOAuth {
+            creds_file_path: "~/.aws/sso/cache/kiro-auth-token.json".to_string(),
+        });
+        assert_eq!(kiro.method, ProbeMethod::Head);
+        assert!(kiro.headers.is_empty());
+    }
+
+    #[test]
+    fn test_report_status() {
+        let probe = |status| UpstreamProbe {
+            provider: "openai".to_string(),
+            endpoint: "https://api.openai.com/v1/models".to_string(),
+            credential_uuid: "uuid".to_string(),
+            credential_name: None,
+            status,
+            latency_ms: 10,
+            http_status: None,
+            error: None,
+        };
+        assert_eq!(
+            UpstreamHealthReport::from_probes(Vec::new()).status,
+            HealthStatus::Healthy
+        );
+        assert_eq!(
+            UpstreamHealthReport::from_probes(vec![
+                probe(ProbeStatus::Up),
+                probe(ProbeStatus::Down)
+            ])
+            .status,
+            HealthStatus::Degraded
+        );
+        assert_eq!(
+            UpstreamHealthReport::from_probes(vec![probe(ProbeStatus::Down)]).status,
+            HealthStatus::Unhealthy
+        );
+    }
+}
@@ -0,0 +1,656 @@
+//! 统一用量记录服务
+//!
+//! NativeAgent、Goose Agent 和 API Server 转发的请求都向 [`UsageRecorder`] 上报用量
+//! （模型、Token、费用、延迟、来源），用于统一的用量看板：
+//! - 费用按 `server.token_budget.prices` 中的模型单价计算，未配置单价的模型只计 Token
+//! - 遥测 Token 追踪、Prometheus 指标和 Token 预算作为 [`UsageSink`] 订阅记录，不再各自上报
+//! - Agent 请求经本地 API Server 转发时带 [`USAGE_SOURCE_HEADER`] 请求头，
+//!   由 Agent 自行上报（带会话信息），API Server 不重复记录；只信任来自本机回环地址的该请求头
+//! - 用量取上游响应中报告的实际值（[`ReportedUsage`]），上游未报告时不记录
+//! - 记录只保存在内存中，超过 [`MAX_USAGE_EVENTS`] 条后丢弃最早的记录
+
+use crate::config::ModelPrice;
+use crate::database::DbConnection;
+use crate::services::token_budget_service::TokenBudgetService;
+use crate::telemetry::{PrometheusMetrics, TokenSource, TokenTracker, TokenUsageRecord};
+use crate::ProviderType;
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// 标记请求来源的请求头（值为 [`UsageSource`] 的 snake_case 名称）
+pub const USAGE_SOURCE_HEADER: &str = "x-proxycast-source";
+
+/// 内存中保留的用量记录数
+pub const MAX_USAGE_EVENTS: usize = 10_000;
+
+/// 用量来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageSource {
+    /// 原生 Agent 对话
+    NativeAgent,
+    /// Goose Agent 对话
+    GooseAgent,
+    /// API Server 转发的外部客户端请求
+    Proxy,
+}
+
+impl UsageSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UsageSource::NativeAgent => "native_agent",
+            UsageSource::GooseAgent => "goose_agent",
+            UsageSource::Proxy => "proxy",
+        }
+    }
+
+    /// 解析 [`USAGE_SOURCE_HEADER`] 请求头的值
+    pub fn from_header(value: &str) -> Option<Self> {
+        match value.trim() {
+            "native_agent" => Some(UsageSource::NativeAgent),
+            "goose_agent" => Some(UsageSource::GooseAgent),
+            "proxy" => Some(UsageSource::Proxy),
+            _ => None,
+        }
+    }
+
+    /// 从请求头读取请求来源（未设置、无法识别或请求不是来自本机回环地址时返回 None）
+    ///
+    /// 外部客户端可以伪造该请求头来跳过用量记录，因此只信任本机 Agent 发出的请求
+    pub fn from_request(headers: &HeaderMap, peer: Option<SocketAddr>) -> Option<Self> {
+        if !peer.is_some_and(|addr| addr.ip().is_loopback()) {
+            return None;
+        }
+        headers
+            .get(USAGE_SOURCE_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(Self::from_header)
+    }
+
+    /// 是否由 Agent 自行上报（经 API Server 转发时不重复记录）
+    pub fn reports_itself(&self) -> bool {
+        !matches!(self, UsageSource::Proxy)
+    }
+}
+
+/// 一次请求的用量
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageEvent {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    pub source: UsageSource,
+    /// Provider（Agent 为 Provider 类型，API Server 为实际选择的凭证池类型）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// 处理请求的 Provider 类型（用于 Token 追踪和 Prometheus 指标）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_type: Option<ProviderType>,
+    /// API Server 客户端 Key ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<String>,
+    pub model: String,
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    /// 费用（美元，记录时按模型单价计算）
+    pub cost_usd: f64,
+    /// 请求耗时（毫秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// Agent 会话 ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// API Server 请求 ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl UsageEvent {
+    pub fn new(source: UsageSource, model: &str, input_tokens: u32, output_tokens: u32) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            source,
+            provider: None,
+            provider_type: None,
+            client_key: None,
+            model: model.to_string(),
+            input_tokens,
+            output_tokens,
+            cost_usd: 0.0,
+            latency_ms: None,
+            session_id: None,
+            request_id: None,
+        }
+    }
+
+    pub fn with_provider(mut self, provider: impl Into<String>) -> Self {
+        self.provider = Some(provider.into());
+        self
+    }
+
+    pub fn with_provider_type(mut self, provider_type: ProviderType) -> Self {
+        self.provider_type = Some(provider_type);
+        self
+    }
+
+    pub fn with_client_key(mut self, client_key: Option<String>) -> Self {
+        self.client_key = client_key;
+        self
+    }
+
+    pub fn with_latency_ms(mut self, latency_ms: u64) -> Self {
+        self.latency_ms = Some(latency_ms);
+        self
+    }
+
+    pub fn with_session_id(mut self, session_id: Option<&str>) -> Self {
+        self.session_id = session_id.map(str::to_string);
+        self
+    }
+
+    pub fn with_request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request_id = Some(request_id.into());
+        self
+    }
+}
+
+/// 上游响应中报告的 Token 用量（字段缺失时为 None）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReportedUsage {
+    pub input_tokens: Option<u32>,
+    pub output_tokens: Option<u32>,
+}
+
+impl ReportedUsage {
+    /// 从响应 JSON 读取用量，兼容 OpenAI、Anthropic（含流式 `message_start`）、
+    /// Responses API（`response.completed`）和 Gemini 格式
+    pub fn from_json(value: &serde_json::Value) -> Self {
+        let count = |object: &serde_json::Value, key: &str| {
+            object
+                .get(key)
+                .and_then(|v| v.as_u64())
+                .map(|v| v.min(u32::MAX as u64) as u32)
+        };
+
+        if let Some(metadata) = value.get("usageMetadata") {
+            return Self {
+                input_tokens: count(metadata, "promptTokenCount"),
+                output_tokens: count(metadata, "candidatesTokenCount"),
+            };
+        }
+        let usage = value
+            .get("usage")
+            .or_else(|| value.pointer("/message/usage"))
+            .or_else(|| value.pointer("/response/usage"));
+        match usage {
+            Some(usage) if usage.is_object() => Self {
+                input_tokens: count(usage, "prompt_tokens")
+                    .or_else(|| count(usage, "input_tokens")),
+                output_tokens: count(usage, "completion_tokens")
+                    .or_else(|| count(usage, "output_tokens")),
+            },
+            _ => Self::default(),
+        }
+    }
+
+    /// 合并流式事件中的用量（后到的值覆盖先到的值）
+    fn merge(&mut self, other: Self) {
+        self.input_tokens = other.input_tokens.or(self.input_tokens);
+        self.output_tokens = other.output_tokens.or(self.output_tokens);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.input_tokens.is_none() && self.output_tokens.is_none()
+    }
+}
+
+/// 从 SSE 响应流中累计上游报告的用量（数据块可能在任意位置被切分）
+#[derive(Debug, Default)]
+pub struct SseUsageScanner {
+    pending: Vec<u8>,
+    usage: ReportedUsage,
+}
+
+impl SseUsageScanner {
+    pub fn feed(&mut self, chunk: &[u8]) {
+        self.pending.extend_from_slice(chunk);
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            self.scan_line(&line);
+        }
+    }
+
+    /// 处理剩余的不完整行并返回累计的用量
+    pub fn finish(mut self) -> ReportedUsage {
+        let line = std::mem::take(&mut self.pending);
+        self.scan_line(&line);
+        self.usage
+    }
+
+    fn scan_line(&mut self, line: &[u8]) {
+        let Some(data) = line.strip_prefix(b"data:") else {
+            return;
+        };
+        if !data.windows(5).any(|w| w == b"usage") {
+            return;
+        }
+        if let Ok(value) = serde_json::from_slice::<serde_json::Value>(data.trim_ascii()) {
+            self.usage.merge(ReportedUsage::from_json(&value));
+        }
+    }
+}
+
+/// 用量订阅者：记录用量时依次通知，用于更新各自的统计
+pub trait UsageSink: Send + Sync {
+    fn on_usage(&self, event: &UsageEvent);
+}
+
+/// 遥测 Token 追踪
+impl UsageSink for RwLock<TokenTracker> {
+    fn on_usage(&self, event: &UsageEvent) {
+        let mut record = TokenUsageRecord::new(
+            event.id.clone(),
+            event.provider_type.unwrap_or(ProviderType::Kiro),
+            event.model.clone(),
+            event.input_tokens,
+            event.output_tokens,
+            TokenSource::Actual,
+        );
+        if let Some(request_id) = &event.request_id {
+            record = record.with_request_id(request_id.clone());
+        }
+        self.write().record(record);
+    }
+}
+
+/// Prometheus Token 指标
+impl UsageSink for PrometheusMetrics {
+    fn on_usage(&self, event: &UsageEvent) {
+        self.record_tokens(
+            event.provider_type.unwrap_or(ProviderType::Kiro),
+            &event.model,
+            event.input_tokens,
+            event.output_tokens,
+        );
+    }
+}
+
+/// Token 预算（按客户端 Key 和 Provider 累计用量，没有 Provider 的记录不计入）
+pub struct TokenBudgetSink {
+    pub service: Arc<TokenBudgetService>,
+    pub db: DbConnection,
+}
+
+impl UsageSink for TokenBudgetSink {
+    fn on_usage(&self, event: &UsageEvent) {
+        if let Some(provider) = &event.provider {
+            self.service.record(
+                &self.db,
+                event.client_key.as_deref(),
+                provider,
+                &event.model,
+                event.input_tokens,
+                event.output_tokens,
+            );
+        }
+    }
+}
+
+/// 用量查询条件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageQuery {
+    #[serde(default)]
+    pub source: Option<UsageSource>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub start: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub end: Option<DateTime<Utc>>,
+    /// 最多返回的记录数（默认全部，按时间倒序）
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+impl UsageQuery {
+    fn matches(&self, event: &UsageEvent) -> bool {
+        self.source.is_none_or(|source| event.source == source)
+            && self
+                .model
+                .as_deref()
+                .is_none_or(|model| event.model == model)
+            && self
+                .session_id
+                .as_deref()
+                .is_none_or(|sid| event.session_id.as_deref() == Some(sid))
+            && self.start.is_none_or(|start| event.timestamp >= start)
+            && self.end.is_none_or(|end| event.timestamp <= end)
+    }
+}
+
+/// 用量汇总
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageTotals {
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+    /// 平均耗时（毫秒，没有耗时记录时为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avg_latency_ms: Option<u64>,
+    #[serde(skip)]
+    latency_total_ms: u64,
+    #[serde(skip)]
+    latency_count: u64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, event: &UsageEvent) {
+        self.requests += 1;
+        self.input_tokens += event.input_tokens as u64;
+        self.output_tokens += event.output_tokens as u64;
+        self.cost_usd += event.cost_usd;
+        if let Some(latency) = event.latency_ms {
+            self.latency_total_ms += latency;
+            self.latency_count += 1;
+            self.avg_latency_ms = Some(self.latency_total_ms / self.latency_count);
+        }
+    }
+}
+
+/// 按来源和模型分组的用量汇总
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageSummary {
+    pub total: UsageTotals,
+    pub by_source: BTreeMap<UsageSource, UsageTotals>,
+    pub by_model: BTreeMap<String, UsageTotals>,
+}
+
+/// 统一用量记录服务
+#[derive(Default)]
+pub struct UsageRecorder {
+    prices: RwLock<Vec<ModelPrice>>,
+    events: RwLock<VecDeque<UsageEvent>>,
+    sinks: RwLock<Vec<Arc<dyn UsageSink>>>,
+}
+
+impl UsageRecorder {
+    pub fn new(prices: Vec<ModelPrice>) -> Self {
+        Self {
+            prices: RwLock::new(prices),
+            events: RwLock::new(VecDeque::new()),
+            sinks: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// 设置订阅者（服务器启动时调用，替换上一次运行的订阅者）
+    pub fn set_sinks(&self, sinks: Vec<Arc<dyn UsageSink>>) {
+        *self.sinks.write() = sinks;
+    }
+
+    /// 更新模型单价（配置变更后调用，只影响之后的记录）
+    pub fn set_prices(&self, prices: Vec<ModelPrice>) {
+        *self.prices.write() = prices;
+    }
+
+    /// 记录一次请求的用量（按模型单价计算费用）并通知订阅者
+    pub fn record(&self, mut event: UsageEvent) {
+        event.cost_usd = self
+            .prices
+            .read()
+            .iter()
+            .find(|price| price.matches(&event.model))
+            .map_or(0.0, |price| {
+                price.cost_usd(event.input_tokens, event.output_tokens)
+            });
+        tracing::debug!(
+            "[USAGE] source={} model={} input={} output={} cost_usd={:.6}",
+            event.source.as_str(),
+            event.model,
+            event.input_tokens,
+            event.output_tokens,
+            event.cost_usd
+        );
+
+        for sink in self.sinks.read().iter() {
+            sink.on_usage(&event);
+        }
+
+        let mut events = self.events.write();
+        if events.len() >= MAX_USAGE_EVENTS {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    /// 查询用量记录（按时间倒序）
+    pub fn list(&self, query: &UsageQuery) -> Vec<UsageEvent> {
+        self.events
+            .read()
+            .iter()
+            .rev()
+            .filter(|event| query.matches(event))
+            .take(query.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    /// 汇总满足条件的用量（忽略 `limit`）
+    pub fn summary(&self, query: &UsageQuery) -> UsageSummary {
+        let mut summary = UsageSummary::default();
+        for event in self.events.read().iter().filter(|e| query.matches(e)) {
+            summary.total.add(event);
+            summary
+                .by_source
+                .entry(event.source)
+                .or_default()
+                .add(event);
+            summary
+                .by_model
+                .entry(event.model.clone())
+                .or_default()
+                .add(event);
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(model: &str, input: f64, output: f64) -> ModelPrice {
+        ModelPrice {
+            model: model.to_string(),
+            input_per_million: input,
+            output_per_million: output,
+        }
+    }
+
+    #[test]
+    fn test_record_computes_cost_and_summarizes() {
+        let recorder = UsageRecorder::new(vec![price("claude-*", 3.0, 15.0)]);
+        recorder.record(
+            UsageEvent::new(
+                UsageSource::NativeAgent,
+                "claude-sonnet-4",
+                1_000_000,
+                100_000,
+            )
+            .with_session_id(Some("s1"))
+            .with_latency_ms(1000),
+        );
+        recorder.record(
+            UsageEvent::new(UsageSource::Proxy, "gpt-4o", 500, 100)
+                .with_provider("openai")
+                .with_latency_ms(3000),
+        );
+        recorder.record(UsageEvent::new(UsageSource::Proxy, "claude-sonnet-4", 0, 0));
+
+        let events = recorder.list(&UsageQuery::default());
+        assert_eq!(events.len(), 3);
+        // 最新的记录在前，未配置单价的模型费用为 0
+        assert_eq!(events[1].model, "gpt-4o");
+        assert_eq!(events[1].cost_usd, 0.0);
+        assert!((events[2].cost_usd - 4.5).abs() < 1e-9);
+
+        let summary = recorder.summary(&UsageQuery::default());
+        assert_eq!(summary.total.requests, 3);
+        assert_eq!(summary.total.avg_latency_ms, Some(2000));
+        assert_eq!(summary.by_source[&UsageSource::Proxy].requests, 2);
+        assert_eq!(summary.by_model["claude-sonnet-4"].requests, 2);
+
+        let session = recorder.list(&UsageQuery {
+            session_id: Some("s1".to_string()),
+            ..UsageQuery::default()
+        });
+        assert_eq!(session.len(), 1);
+        let limited = recorder.list(&UsageQuery {
+            source: Some(UsageSource::Proxy),
+            limit: Some(1),
+            ..UsageQuery::default()
+        });
+        assert_eq!(limited.len(), 1);
+        assert_eq!(limited[0].model, "claude-sonnet-4");
+    }
+
+    #[test]
+    fn test_source_header() {
+        assert_eq!(
+            UsageSource::from_header("native_agent"),
+            Some(UsageSource::NativeAgent)
+        );
+        assert_eq!(UsageSource::from_header("curl"), None);
+        assert!(UsageSource::GooseAgent.reports_itself());
+        assert!(!UsageSource::Proxy.reports_itself());
+    }
+
+    #[test]
+    fn test_source_header_trusted_only_from_loopback() {
+        let mut headers = HeaderMap::new();
+        headers.insert(USAGE_SOURCE_HEADER, "native_agent".parse().unwrap());
+        let local: SocketAddr = "127.0.0.1:50000".parse().unwrap();
+        let remote: SocketAddr = "192.168.1.20:50000".parse().unwrap();
+
+        assert_eq!(
+            UsageSource::from_request(&headers, Some(local)),
+            Some(UsageSource::NativeAgent)
+        );
+        assert_eq!(UsageSource::from_request(&headers, Some(remote)), None);
+        assert_eq!(UsageSource::from_request(&headers, None), None);
+    }
+
+    #[test]
+    fn test_reported_usage_formats() {
+        let openai = serde_json::json!({"usage": {"prompt_tokens": 10, "completion_tokens": 5}});
+        let anthropic = serde_json::json!({"usage": {"input_tokens": 12, "output_tokens": 7}});
+        let gemini = serde_json::json!({
+            "usageMetadata": {"promptTokenCount": 3, "candidatesTokenCount": 4}
+        });
+        let missing = serde_json::json!({"choices": []});
+
+        let usage = |i, o| ReportedUsage {
+            input_tokens: Some(i),
+            output_tokens: Some(o),
+        };
+        assert_eq!(ReportedUsage::from_json(&openai), usage(10, 5));
+        assert_eq!(ReportedUsage::from_json(&anthropic), usage(12, 7));
+        assert_eq!(ReportedUsage::from_json(&gemini), usage(3, 4));
+        assert!(ReportedUsage::from_json(&missing).is_empty());
+    }
+
+    #[test]
+    fn test_sse_scanner_merges_split_events() {
+        let stream = concat!(
+            "event: message_start\n",
+            "data: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":25,\"output_tokens\":1}}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"delta\":{\"text\":\"hi\"}}\n\n",
+            "data: {\"type\":\"message_delta\",\"usage\":{\"output_tokens\":42}}\n\n",
+        );
+        let mut scanner = SseUsageScanner::default();
+        for chunk in stream.as_bytes().chunks(7) {
+            scanner.feed(chunk);
+        }
+        assert_eq!(
+            scanner.finish(),
+            ReportedUsage {
+                input_tokens: Some(25),
+                output_tokens: Some(42),
+            }
+        );
+
+        // 最后一行没有换行符
+        let mut scanner = SseUsageScanner::default();
+        scanner.feed(
+            b"data: {\"choices\":[],\"usage\":{\"prompt_tokens\":1,\"completion_tokens\":2}}",
+        );
+        assert_eq!(scanner.finish().output_tokens, Some(2));
+    }
+
+    #[test]
+    fn test_record_notifies_sinks() {
+        struct Counter(parking_lot::Mutex<Vec<(Option<String>, u32)>>);
+        impl UsageSink for Counter {
+            fn on_usage(&self, event: &UsageEvent) {
+                self.0
+                    .lock()
+                    .push((event.client_key.clone(), event.output_tokens));
+            }
+        }
+
+        let recorder = UsageRecorder::new(Vec::new());
+        let counter = Arc::new(Counter(parking_lot::Mutex::new(Vec::new())));
+        recorder.set_sinks(vec![counter.clone()]);
+        recorder.record(
+            UsageEvent::new(UsageSource::Proxy, "gpt-4o", 1, 9)
+                .with_client_key(Some("team-a".to_string())),
+        );
+        assert_eq!(*counter.0.lock(), vec![(Some("team-a".to_string()), 9)]);
+    }
+
+    #[test]
+    fn test_token_budget_sink_charges_reported_usage() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::database::schema::create_tables(&conn).unwrap();
+        let db: DbConnection = Arc::new(std::sync::Mutex::new(conn));
+        let service = Arc::new(TokenBudgetService::new(crate::config::TokenBudgetConfig {
+            enabled: true,
+            limits: vec![crate::config::TokenBudgetLimit {
+                name: "team-a".to_string(),
+                client_key: Some("team-a".to_string()),
+                provider: None,
+                period: crate::config::BudgetPeriod::Daily,
+                max_tokens: 1000,
+                max_cost_usd: 0.0,
+            }],
+            prices: Vec::new(),
+        }));
+        let recorder = UsageRecorder::new(Vec::new());
+        recorder.set_sinks(vec![Arc::new(TokenBudgetSink {
+            service: service.clone(),
+            db: db.clone(),
+        })]);
+
+        // 没有 Provider 的记录不计入预算
+        recorder.record(
+            UsageEvent::new(UsageSource::Proxy, "gpt-4o", 5000, 0)
+                .with_client_key(Some("team-a".to_string())),
+        );
+        assert!(service.check(&db, Some("team-a"), "openai").is_none());
+
+        recorder.record(
+            UsageEvent::new(UsageSource::Proxy, "gpt-4o", 600, 400)
+                .with_provider("openai")
+                .with_client_key(Some("team-a".to_string())),
+        );
+        assert!(service.check(&db, Some("team-a"), "openai").is_some());
+        assert!(service.check(&db, Some("team-b"), "openai").is_none());
+    }
+}
@@ -1,6 +1,6 @@
 //! 流式格式转换器
 //!
-//! 在不同流式格式之间转换，支持 AWS Event Stream、Anthropic SSE 和 OpenAI SSE。
+//! 在不同流式格式之间转换，支持 AWS Event Stream、Anthropic SSE、OpenAI SSE 和 Gemini SSE。
 //!
 //! # 需求覆盖
 
@@ -9,11 +9,13 @@
 //! - 需求 3.1: AWS Event Stream 到 Anthropic SSE 转换
 //! - 需求 3.2: AWS Event Stream 到 OpenAI SSE 转换
 //! - 需求 3.3: Anthropic SSE 到 OpenAI SSE 转换
+//! - OpenAI SSE 到 Anthropic SSE 转换（`/v1/messages` 使用 OpenAI 兼容凭证时）
+//! - Gemini SSE 与 OpenAI/Anthropic SSE 互相转换（以 OpenAI chunk 为中间格式）
 //! - 需求 3.5: 处理工具调用参数中的部分 JSON
 
 use crate::streaming::aws_parser::{AwsEvent, AwsEventStreamParser};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::time::{SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
@@ -26,6 +28,8 @@ pub enum StreamFormat {
     AnthropicSse,
     /// OpenAI SSE 格式
     OpenAiSse,
+    /// Gemini SSE 格式（`streamGenerateContent?alt=sse`）
+    GeminiSse,
 }
 
 /// 转换器状态
@@ -139,6 +143,15 @@ impl PartialJsonAccumulator {
     }
 }
 
+/// 当前打开的 Anthropic 内容块（OpenAI SSE 转 Anthropic SSE 时使用）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OpenContentBlock {
+    /// 文本块
+    Text(u32),
+    /// 工具调用块
+    ToolUse(u32),
+}
+
 /// 流式格式转换器
 ///
 /// 支持在不同流式格式之间转换。
@@ -164,6 +177,18 @@ pub struct StreamConverter {
     message_started: bool,
     /// 累积的内容（用于重建完整响应）
     accumulated_content: String,
+    /// 未处理完的 SSE 行（chunk 可能在行中间截断）
+    line_buffer: String,
+    /// 当前打开的内容块
+    open_block: Option<OpenContentBlock>,
+    /// 结束原因（Anthropic 格式，默认 end_turn）
+    stop_reason: Option<String>,
+    /// 输出 token 数（上游返回 usage 时记录）
+    output_tokens: u64,
+    /// 结束原因（OpenAI 格式，未设置时按是否有工具调用推断）
+    finish_reason: Option<String>,
+    /// 等待在结束时发送的函数调用（转换为 Gemini SSE 时使用，按 OpenAI 工具调用索引排序）
+    pending_function_calls: BTreeMap<u64, (String, String)>,
 }
 
 impl StreamConverter {
@@ -186,6 +211,12 @@ impl StreamConverter {
             next_content_block_index: 0,
             message_started: false,
             accumulated_content: String::new(),
+            line_buffer: String::new(),
+            open_block: None,
+            stop_reason: None,
+            output_tokens: 0,
+            finish_reason: None,
+            pending_function_calls: BTreeMap::new(),
         }
     }
 
@@ -222,6 +253,12 @@ impl StreamConverter {
         self.next_content_block_index = 0;
         self.message_started = false;
         self.accumulated_content.clear();
+        self.line_buffer.clear();
+        self.open_block = None;
+        self.stop_reason = None;
+        self.output_tokens = 0;
+        self.finish_reason = None;
+        self.pending_function_calls.clear();
     }
 
     /// 转换 chunk
@@ -244,6 +281,7 @@ impl StreamConverter {
             StreamFormat::AwsEventStream => self.convert_aws_event_stream(chunk),
             StreamFormat::AnthropicSse => self.convert_anthropic_sse(chunk),
             StreamFormat::OpenAiSse => self.convert_openai_sse(chunk),
+            StreamFormat::GeminiSse => self.convert_gemini_sse(chunk),
         }
     }
 
@@ -261,6 +299,37 @@ impl StreamConverter {
             }
         }
 
+        // 处理 OpenAI SSE 中未以换行结尾的最后一行
+        if self.source_format == StreamFormat::OpenAiSse
+            && self.target_format == StreamFormat::AnthropicSse
+        {
+            let rest = std::mem::take(&mut self.line_buffer);
+            events.extend(self.openai_to_anthropic_line(&rest));
+            if !self.message_started {
+                events.push(self.create_anthropic_message_start());
+                self.message_started = true;
+            }
+            self.close_open_block(&mut events);
+        }
+
+        // 处理 Gemini SSE 中未以换行结尾的最后一行
+        if self.source_format == StreamFormat::GeminiSse {
+            let rest = std::mem::take(&mut self.line_buffer);
+            events.extend(self.gemini_line_to_target(&rest));
+            if self.target_format == StreamFormat::AnthropicSse {
+                if !self.message_started {
+                    events.push(self.create_anthropic_message_start());
+                    self.message_started = true;
+                }
+                self.close_open_block(&mut events);
+            }
+        } else if self.source_format == StreamFormat::OpenAiSse
+            && self.target_format == StreamFormat::GeminiSse
+        {
+            let rest = std::mem::take(&mut self.line_buffer);
+            events.extend(self.openai_to_gemini_line(&rest));
+        }
+
         // 生成结束事件
         events.extend(self.generate_end_events());
 
@@ -285,6 +354,10 @@ impl StreamConverter {
         match self.target_format {
             StreamFormat::AnthropicSse => self.aws_to_anthropic(event),
             StreamFormat::OpenAiSse => self.aws_to_openai(event),
+            StreamFormat::GeminiSse => {
+                let chunks = self.aws_to_openai(event);
+                self.openai_chunks_to_gemini(&chunks)
+            }
             StreamFormat::AwsEventStream => {
                 // 源和目标相同，直接序列化
                 if let Some(json) = crate::streaming::aws_parser::serialize_event(event) {
@@ -461,6 +534,11 @@ impl StreamConverter {
                 // 转换为 OpenAI 格式
                 self.anthropic_to_openai(&data)
             }
+            StreamFormat::GeminiSse => {
+                // 先转换为 OpenAI 格式，再转换为 Gemini 格式
+                let chunks = self.anthropic_to_openai(&data);
+                self.openai_chunks_to_gemini(&chunks)
+            }
             StreamFormat::AwsEventStream => {
                 // 不支持反向转换
                 vec![]
@@ -568,11 +646,135 @@ impl StreamConverter {
         sse_events
     }
 
-    /// 转换 OpenAI SSE（直通）
+    /// 转换 OpenAI SSE（直通或转换为 Anthropic）
     fn convert_openai_sse(&mut self, chunk: &[u8]) -> Vec<String> {
-        match String::from_utf8(chunk.to_vec()) {
-            Ok(s) => vec![s],
-            Err(_) => vec![],
+        let data = match String::from_utf8(chunk.to_vec()) {
+            Ok(s) => s,
+            Err(_) => return vec![],
+        };
+
+        match self.target_format {
+            StreamFormat::AnthropicSse => self.openai_to_anthropic(&data),
+            StreamFormat::OpenAiSse => vec![data],
+            StreamFormat::GeminiSse => self.openai_to_gemini(&data),
+            StreamFormat::AwsEventStream => vec![],
+        }
+    }
+
+    /// OpenAI SSE 到 Anthropic SSE 转换
+    ///
+    /// 按行缓冲，只处理完整的 `data:` 行；`[DONE]` 忽略，结束事件在 finish() 中生成。
+    fn openai_to_anthropic(&mut self, data: &str) -> Vec<String> {
+        self.line_buffer.push_str(data);
+
+        let mut sse_events = Vec::new();
+        while let Some(pos) = self.line_buffer.find('\n') {
+            let line: String = self.line_buffer.drain(..=pos).collect();
+            sse_events.extend(self.openai_to_anthropic_line(line.trim_end()));
+        }
+        sse_events
+    }
+
+    /// 转换单行 OpenAI SSE
+    fn openai_to_anthropic_line(&mut self, line: &str) -> Vec<String> {
+        let mut sse_events = Vec::new();
+
+        let Some(json_str) = line.strip_prefix("data:").map(str::trim) else {
+            return sse_events;
+        };
+        if json_str.is_empty() || json_str == "[DONE]" {
+            return sse_events;
+        }
+        let Ok(chunk) = serde_json::from_str::<serde_json::Value>(json_str) else {
+            return sse_events;
+        };
+
+        if !self.message_started {
+            sse_events.push(self.create_anthropic_message_start());
+            self.message_started = true;
+        }
+
+        if let Some(tokens) = chunk["usage"]["completion_tokens"].as_u64() {
+            self.output_tokens = tokens;
+        }
+
+        let choice = &chunk["choices"][0];
+        let delta = &choice["delta"];
+
+        if let Some(text) = delta["content"].as_str().filter(|t| !t.is_empty()) {
+            self.accumulated_content.push_str(text);
+            let index = match self.open_block {
+                Some(OpenContentBlock::Text(index)) => index,
+                _ => {
+                    self.close_open_block(&mut sse_events);
+                    let index = self.next_content_block_index;
+                    self.next_content_block_index += 1;
+                    sse_events.push(self.create_anthropic_content_block_start_text(index));
+                    self.open_block = Some(OpenContentBlock::Text(index));
+                    index
+                }
+            };
+            sse_events.push(self.create_anthropic_text_delta(index, text));
+        }
+
+        if let Some(tool_calls) = delta["tool_calls"].as_array() {
+            for tc in tool_calls {
+                // OpenAI 只在首个分片中携带 id 和 name，后续分片通过 index 关联
+                let key = format!("openai:{}", tc["index"].as_u64().unwrap_or(0));
+                if !self.tool_accumulators.contains_key(&key) {
+                    self.close_open_block(&mut sse_events);
+                    let index = self.next_content_block_index;
+                    self.next_content_block_index += 1;
+                    let id = tc["id"]
+                        .as_str()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| format!("toolu_{}", Uuid::new_v4().simple()));
+                    let name = tc["function"]["name"].as_str().unwrap_or_default();
+                    sse_events
+                        .push(self.create_anthropic_content_block_start_tool(index, &id, name));
+                    self.tool_accumulators.insert(
+                        key.clone(),
+                        ToolCallAccumulator {
+                            id,
+                            name: name.to_string(),
+                            input: String::new(),
+                            started: true,
+                            index,
+                        },
+                    );
+                    self.open_block = Some(OpenContentBlock::ToolUse(index));
+                }
+
+                let arguments = tc["function"]["arguments"].as_str().unwrap_or_default();
+                if arguments.is_empty() {
+                    continue;
+                }
+                if let Some(acc) = self.tool_accumulators.get_mut(&key) {
+                    acc.input.push_str(arguments);
+                    let index = acc.index;
+                    sse_events.push(self.create_anthropic_input_json_delta(index, arguments));
+                }
+            }
+        }
+
+        if let Some(finish_reason) = choice["finish_reason"].as_str() {
+            let stop_reason = match finish_reason {
+                "tool_calls" | "function_call" => "tool_use",
+                "length" => "max_tokens",
+                _ => "end_turn",
+            };
+            self.stop_reason = Some(stop_reason.to_string());
+        }
+
+        sse_events
+    }
+
+    /// 关闭当前打开的内容块
+    fn close_open_block(&mut self, sse_events: &mut Vec<String>) {
+        if let Some(OpenContentBlock::Text(index) | OpenContentBlock::ToolUse(index)) =
+            self.open_block.take()
+        {
+            sse_events.push(self.create_anthropic_content_block_stop(index));
         }
     }
 
@@ -588,22 +790,273 @@ impl StreamConverter {
                 events
             }
             StreamFormat::OpenAiSse => {
-                let finish_reason = if self.tool_accumulators.is_empty() {
-                    "stop"
-                } else {
-                    "tool_calls"
+                let finish_reason = match &self.finish_reason {
+                    Some(reason) => reason.as_str(),
+                    None if self.tool_accumulators.is_empty() => "stop",
+                    None => "tool_calls",
                 };
                 vec![
                     self.create_openai_finish_chunk(finish_reason),
                     "data: [DONE]\n\n".to_string(),
                 ]
             }
+            StreamFormat::GeminiSse => {
+                // Gemini SSE 没有结束标记，最后一个 chunk 携带函数调用、finishReason 和用量
+                let function_calls = std::mem::take(&mut self.pending_function_calls);
+                let parts: Vec<serde_json::Value> = function_calls
+                    .into_values()
+                    .map(|(name, arguments)| {
+                        let args = serde_json::from_str::<serde_json::Value>(&arguments)
+                            .unwrap_or_else(|_| serde_json::json!({}));
+                        serde_json::json!({"functionCall": {"name": name, "args": args}})
+                    })
+                    .collect();
+                let finish_reason = match self.finish_reason.as_deref() {
+                    Some("length") => "MAX_TOKENS",
+                    Some("content_filter") => "SAFETY",
+                    _ => "STOP",
+                };
+                vec![self.create_gemini_chunk(parts, Some(finish_reason))]
+            }
             StreamFormat::AwsEventStream => {
                 vec![]
             }
         }
     }
 
+    // ========================================================================
+    // Gemini SSE 转换
+    // ========================================================================
+
+    /// 转换 Gemini SSE（直通或经 OpenAI chunk 转换为目标格式）
+    fn convert_gemini_sse(&mut self, chunk: &[u8]) -> Vec<String> {
+        let data = match String::from_utf8(chunk.to_vec()) {
+            Ok(s) => s,
+            Err(_) => return vec![],
+        };
+
+        if self.target_format == StreamFormat::GeminiSse {
+            return vec![data];
+        }
+
+        self.line_buffer.push_str(&data);
+        let mut sse_events = Vec::new();
+        while let Some(pos) = self.line_buffer.find('\n') {
+            let line: String = self.line_buffer.drain(..=pos).collect();
+            sse_events.extend(self.gemini_line_to_target(line.trim_end()));
+        }
+        sse_events
+    }
+
+    /// 转换单行 Gemini SSE 到目标格式
+    fn gemini_line_to_target(&mut self, line: &str) -> Vec<String> {
+        let Some(json_str) = line.strip_prefix("data:").map(str::trim) else {
+            return vec![];
+        };
+        let Ok(chunk) = serde_json::from_str::<serde_json::Value>(json_str) else {
+            return vec![];
+        };
+        let Some(mut openai_chunk) = self.gemini_chunk_to_openai(&chunk) else {
+            return vec![];
+        };
+
+        match self.target_format {
+            StreamFormat::AnthropicSse => {
+                self.openai_to_anthropic_line(&format!("data: {}", openai_chunk))
+            }
+            StreamFormat::OpenAiSse => {
+                if let Some(reason) = openai_chunk["choices"][0]["finish_reason"].as_str() {
+                    self.finish_reason = Some(reason.to_string());
+                }
+                if let Some(tokens) = openai_chunk["usage"]["completion_tokens"].as_u64() {
+                    self.output_tokens = tokens;
+                }
+                let delta = &openai_chunk["choices"][0]["delta"];
+                if delta.get("content").is_none() && delta.get("tool_calls").is_none() {
+                    return vec![];
+                }
+                openai_chunk["choices"][0]["finish_reason"] = serde_json::Value::Null;
+                if let Some(obj) = openai_chunk.as_object_mut() {
+                    obj.remove("usage");
+                }
+                vec![format!("data: {}\n\n", openai_chunk)]
+            }
+            StreamFormat::GeminiSse | StreamFormat::AwsEventStream => vec![],
+        }
+    }
+
+    /// 把一个 Gemini chunk 转换为 OpenAI chunk（无可用内容时返回 None）
+    ///
+    /// Gemini 的函数调用总是完整出现在一个 part 中，直接生成带完整参数的 tool_call。
+    fn gemini_chunk_to_openai(&mut self, chunk: &serde_json::Value) -> Option<serde_json::Value> {
+        // Antigravity 等上游会把响应包在 response 字段中
+        let chunk = chunk.get("response").unwrap_or(chunk);
+        let candidate = &chunk["candidates"][0];
+
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+        for part in candidate["content"]["parts"]
+            .as_array()
+            .into_iter()
+            .flatten()
+        {
+            if part["thought"].as_bool() == Some(true) {
+                continue;
+            }
+            if let Some(t) = part["text"].as_str() {
+                text.push_str(t);
+            } else if let Some(call) = part.get("functionCall") {
+                let index = self
+                    .tool_accumulators
+                    .keys()
+                    .filter(|key| key.starts_with("gemini:"))
+                    .count() as u32;
+                let id = format!("call_{}", Uuid::new_v4().simple());
+                let name = call["name"].as_str().unwrap_or_default().to_string();
+                let arguments = call
+                    .get("args")
+                    .map(|args| args.to_string())
+                    .unwrap_or_else(|| "{}".to_string());
+                tool_calls.push(serde_json::json!({
+                    "index": index,
+                    "id": id,
+                    "type": "function",
+                    "function": {"name": name, "arguments": arguments}
+                }));
+                self.tool_accumulators.insert(
+                    format!("gemini:{}", index),
+                    ToolCallAccumulator {
+                        id,
+                        name,
+                        input: arguments,
+                        started: true,
+                        index,
+                    },
+                );
+            }
+        }
+
+        let finish_reason = candidate["finishReason"].as_str().map(|reason| {
+            if !self.tool_accumulators.is_empty() {
+                return "tool_calls";
+            }
+            match reason {
+                "STOP" => "stop",
+                "MAX_TOKENS" => "length",
+                _ => "content_filter",
+            }
+        });
+        let usage = chunk.get("usageMetadata");
+
+        if text.is_empty() && tool_calls.is_empty() && finish_reason.is_none() && usage.is_none() {
+            return None;
+        }
+
+        let mut delta = serde_json::Map::new();
+        if !text.is_empty() {
+            self.accumulated_content.push_str(&text);
+            delta.insert("content".to_string(), serde_json::json!(text));
+        }
+        if !tool_calls.is_empty() {
+            delta.insert("tool_calls".to_string(), serde_json::json!(tool_calls));
+        }
+
+        let mut openai_chunk = serde_json::json!({
+            "id": self.response_id,
+            "object": "chat.completion.chunk",
+            "created": self.get_created_timestamp(),
+            "model": self.model,
+            "choices": [{
+                "index": 0,
+                "delta": delta,
+                "finish_reason": finish_reason
+            }]
+        });
+        if let Some(usage) = usage {
+            let prompt_tokens = usage["promptTokenCount"].as_u64().unwrap_or(0);
+            let completion_tokens = usage["candidatesTokenCount"].as_u64().unwrap_or(0);
+            openai_chunk["usage"] = serde_json::json!({
+                "prompt_tokens": prompt_tokens,
+                "completion_tokens": completion_tokens,
+                "total_tokens": prompt_tokens + completion_tokens
+            });
+        }
+        Some(openai_chunk)
+    }
+
+    /// OpenAI SSE 到 Gemini SSE 转换
+    ///
+    /// 按行缓冲；文本立即输出，工具调用参数累积到结束时作为完整的 functionCall 输出。
+    fn openai_to_gemini(&mut self, data: &str) -> Vec<String> {
+        self.line_buffer.push_str(data);
+
+        let mut sse_events = Vec::new();
+        while let Some(pos) = self.line_buffer.find('\n') {
+            let line: String = self.line_buffer.drain(..=pos).collect();
+            sse_events.extend(self.openai_to_gemini_line(line.trim_end()));
+        }
+        sse_events
+    }
+
+    /// 转换已经拆分好的 OpenAI SSE 事件（来自 Anthropic/AWS 的中间结果）
+    fn openai_chunks_to_gemini(&mut self, chunks: &[String]) -> Vec<String> {
+        let mut sse_events = Vec::new();
+        for line in chunks.iter().flat_map(|chunk| chunk.lines()) {
+            sse_events.extend(self.openai_to_gemini_line(line));
+        }
+        sse_events
+    }
+
+    /// 转换单行 OpenAI SSE 到 Gemini SSE
+    fn openai_to_gemini_line(&mut self, line: &str) -> Vec<String> {
+        let Some(json_str) = line.strip_prefix("data:").map(str::trim) else {
+            return vec![];
+        };
+        if json_str.is_empty() || json_str == "[DONE]" {
+            return vec![];
+        }
+        let Ok(chunk) = serde_json::from_str::<serde_json::Value>(json_str) else {
+            return vec![];
+        };
+
+        if let Some(tokens) = chunk["usage"]["completion_tokens"].as_u64() {
+            self.output_tokens = tokens;
+        }
+
+        let choice = &chunk["choices"][0];
+        if let Some(finish_reason) = choice["finish_reason"].as_str() {
+            self.finish_reason = Some(finish_reason.to_string());
+        }
+
+        for tc in choice["delta"]["tool_calls"]
+            .as_array()
+            .into_iter()
+            .flatten()
+        {
+            let entry = self
+                .pending_function_calls
+                .entry(tc["index"].as_u64().unwrap_or(0))
+                .or_default();
+            if let Some(name) = tc["function"]["name"].as_str() {
+                entry.0 = name.to_string();
+            }
+            if let Some(arguments) = tc["function"]["arguments"].as_str() {
+                entry.1.push_str(arguments);
+            }
+        }
+
+        match choice["delta"]["content"]
+            .as_str()
+            .filter(|t| !t.is_empty())
+        {
+            Some(text) => {
+                self.accumulated_content.push_str(text);
+                vec![self.create_gemini_chunk(vec![serde_json::json!({"text": text})], None)]
+            }
+            None => vec![],
+        }
+    }
+
     // ========================================================================
     // Anthropic SSE 事件创建辅助方法
     // ========================================================================
@@ -695,11 +1148,11 @@ impl StreamConverter {
         let event = serde_json::json!({
             "type": "message_delta",
             "delta": {
-                "stop_reason": "end_turn",
+                "stop_reason": self.stop_reason.as_deref().unwrap_or("end_turn"),
                 "stop_sequence": null
             },
             "usage": {
-                "output_tokens": 0
+                "output_tokens": self.output_tokens
             }
         });
         format!("event: message_delta\ndata: {}\n\n", event)
@@ -798,6 +1251,36 @@ impl StreamConverter {
         });
         format!("data: {}\n\n", chunk)
     }
+
+    // ========================================================================
+    // Gemini SSE 事件创建辅助方法
+    // ========================================================================
+
+    fn create_gemini_chunk(
+        &self,
+        parts: Vec<serde_json::Value>,
+        finish_reason: Option<&str>,
+    ) -> String {
+        let mut candidate = serde_json::json!({
+            "content": {
+                "role": "model",
+                "parts": parts
+            },
+            "index": 0
+        });
+        let mut chunk = serde_json::json!({
+            "candidates": [],
+            "modelVersion": self.model
+        });
+        if let Some(reason) = finish_reason {
+            candidate["finishReason"] = serde_json::json!(reason);
+            chunk["usageMetadata"] = serde_json::json!({
+                "candidatesTokenCount": self.output_tokens
+            });
+        }
+        chunk["candidates"] = serde_json::json!([candidate]);
+        format!("data: {}\n\n", chunk)
+    }
 }
 
 // ============================================================================
@@ -849,6 +1332,20 @@ pub fn extract_content_from_sse(events: &[String], format: StreamFormat) -> Stri
                     }
                 }
             }
+            StreamFormat::GeminiSse => {
+                for line in event.lines() {
+                    if let Some(json_str) = line.strip_prefix("data: ") {
+                        if let Ok(chunk) = serde_json::from_str::<serde_json::Value>(json_str) {
+                            let parts = chunk["candidates"][0]["content"]["parts"].as_array();
+                            for part in parts.into_iter().flatten() {
+                                if let Some(text) = part["text"].as_str() {
+                                    content.push_str(text);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
             StreamFormat::AwsEventStream => {
                 // AWS Event Stream 不是 SSE 格式
             }
@@ -918,7 +1415,7 @@ pub fn extract_tool_calls_from_sse(
                     }
                 }
             }
-            StreamFormat::AnthropicSse | StreamFormat::AwsEventStream => {
+            StreamFormat::AnthropicSse | StreamFormat::GeminiSse | StreamFormat::AwsEventStream => {
                 // 简化处理
             }
         }
@@ -1084,6 +1581,172 @@ mod tests {
         assert_eq!(converter.state(), &ConverterState::Completed);
     }
 
+    #[test]
+    fn test_openai_to_anthropic_content() {
+        let mut converter = StreamConverter::with_model(
+            StreamFormat::OpenAiSse,
+            StreamFormat::AnthropicSse,
+            "test-model",
+        );
+
+        // chunk 在行中间截断
+        let mut events = converter.convert(
+            b"data: {\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"Hel",
+        );
+        assert!(events.is_empty());
+        events.extend(converter.convert(
+            b"lo\"},\"finish_reason\":null}]}\n\ndata: {\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"stop\"}]}\n\ndata: [DONE]\n\n",
+        ));
+        events.extend(converter.finish());
+
+        let types: Vec<&str> = events
+            .iter()
+            .filter_map(|e| e.lines().next()?.strip_prefix("event: "))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                "message_start",
+                "content_block_start",
+                "content_block_delta",
+                "content_block_stop",
+                "message_delta",
+                "message_stop"
+            ]
+        );
+        assert_eq!(
+            extract_content_from_sse(&events, StreamFormat::AnthropicSse),
+            "Hello"
+        );
+    }
+
+    #[test]
+    fn test_openai_to_anthropic_tool_call() {
+        let mut converter = StreamConverter::with_model(
+            StreamFormat::OpenAiSse,
+            StreamFormat::AnthropicSse,
+            "test-model",
+        );
+
+        let mut events = converter.convert(
+            b"data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Let me check.\"}}]}\n\n",
+        );
+        events.extend(converter.convert(
+            b"data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"type\":\"function\",\"function\":{\"name\":\"read_file\",\"arguments\":\"\"}}]}}]}\n\n",
+        ));
+        events.extend(converter.convert(
+            b"data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"{\\\"path\\\":\\\"a.rs\\\"}\"}}]}}]}\n\n",
+        ));
+        events.extend(converter.convert(
+            b"data: {\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"tool_calls\"}]}\n\n",
+        ));
+        events.extend(converter.finish());
+
+        let tool_start = events
+            .iter()
+            .find(|e| e.contains("\"tool_use\"") && e.contains("content_block_start"))
+            .expect("tool_use block");
+        assert!(tool_start.contains("\"index\":1"));
+        assert!(tool_start.contains("call_1"));
+        assert!(events.iter().any(|e| e.contains("input_json_delta")));
+        assert_eq!(
+            events
+                .iter()
+                .filter(|e| e.starts_with("event: content_block_stop"))
+                .count(),
+            2
+        );
+        assert!(events
+            .iter()
+            .any(|e| e.contains("message_delta") && e.contains("tool_use")));
+    }
+
+    #[test]
+    fn test_gemini_to_openai_and_anthropic() {
+        let text_chunk = b"data: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"text\":\"Hel\"}]}}]}\r\n\r\ndata: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"text\":\"lo\"}]}}]}\r\n\r\n";
+        let call_chunk = b"data: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"functionCall\":{\"name\":\"read_file\",\"args\":{\"path\":\"a.rs\"}}}]},\"finishReason\":\"STOP\"}],\"usageMetadata\":{\"promptTokenCount\":3,\"candidatesTokenCount\":7}}\r\n\r\n";
+
+        let mut converter = StreamConverter::with_model(
+            StreamFormat::GeminiSse,
+            StreamFormat::OpenAiSse,
+            "gemini-2.5-pro",
+        );
+        let mut events = converter.convert(text_chunk);
+        // chunk 在行中间截断
+        events.extend(converter.convert(&call_chunk[..40]));
+        events.extend(converter.convert(&call_chunk[40..]));
+        events.extend(converter.finish());
+
+        assert_eq!(
+            extract_content_from_sse(&events, StreamFormat::OpenAiSse),
+            "Hello"
+        );
+        let tool_calls = extract_tool_calls_from_sse(&events, StreamFormat::OpenAiSse);
+        assert_eq!(tool_calls.len(), 1);
+        assert_eq!(tool_calls[0].1, "read_file");
+        assert_eq!(tool_calls[0].2, "{\"path\":\"a.rs\"}");
+        assert!(events[events.len() - 2].contains("\"finish_reason\":\"tool_calls\""));
+        assert_eq!(events.last().unwrap(), "data: [DONE]\n\n");
+
+        let mut converter = StreamConverter::with_model(
+            StreamFormat::GeminiSse,
+            StreamFormat::AnthropicSse,
+            "gemini-2.5-pro",
+        );
+        let mut events = converter.convert(text_chunk);
+        events.extend(converter.convert(call_chunk));
+        events.extend(converter.finish());
+
+        assert!(events[0].starts_with("event: message_start"));
+        assert_eq!(
+            extract_content_from_sse(&events, StreamFormat::AnthropicSse),
+            "Hello"
+        );
+        assert!(events
+            .iter()
+            .any(|e| e.contains("\"tool_use\"") && e.contains("read_file")));
+        let message_delta = events
+            .iter()
+            .find(|e| e.starts_with("event: message_delta"))
+            .unwrap();
+        assert!(message_delta.contains("tool_use"));
+        assert!(message_delta.contains("\"output_tokens\":7"));
+    }
+
+    #[test]
+    fn test_openai_to_gemini() {
+        let mut converter =
+            StreamConverter::with_model(StreamFormat::OpenAiSse, StreamFormat::GeminiSse, "gpt-4o");
+
+        let mut events = converter.convert(
+            b"data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Let me check.\"}}]}\n\n",
+        );
+        events.extend(converter.convert(
+            b"data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"type\":\"function\",\"function\":{\"name\":\"read_file\",\"arguments\":\"{\\\"path\\\"\"}}]}}]}\n\n",
+        ));
+        events.extend(converter.convert(
+            b"data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\":\\\"a.rs\\\"}\"}}]}}]}\n\n",
+        ));
+        events.extend(converter.convert(
+            b"data: {\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"tool_calls\"}]}\n\ndata: [DONE]\n\n",
+        ));
+        events.extend(converter.finish());
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            extract_content_from_sse(&events, StreamFormat::GeminiSse),
+            "Let me check."
+        );
+        let last: serde_json::Value =
+            serde_json::from_str(events[1].trim().strip_prefix("data: ").unwrap()).unwrap();
+        let candidate = &last["candidates"][0];
+        assert_eq!(candidate["finishReason"], "STOP");
+        assert_eq!(
+            candidate["content"]["parts"][0]["functionCall"],
+            serde_json::json!({"name": "read_file", "args": {"path": "a.rs"}})
+        );
+    }
+
     #[test]
     fn test_partial_json_accumulator() {
         let mut acc = PartialJsonAccumulator::new();
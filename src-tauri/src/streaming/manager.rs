@@ -807,6 +807,8 @@ where
     start_time: Instant,
     last_chunk_time: Option<Instant>,
     finished: bool,
+    /// 上次检查时的系统唤醒次数
+    resume_count: u64,
 }
 
 impl<S> TimeoutStream<S>
@@ -821,11 +823,22 @@ where
             start_time: Instant::now(),
             last_chunk_time: None,
             finished: false,
+            resume_count: crate::power::resume_count(),
         }
     }
 
     /// 检查是否超时
-    fn check_timeout(&self) -> Option<StreamError> {
+    ///
+    /// 系统休眠后重新开始计时，避免休眠时长被误判为卡顿（连接确实断开时由下游报错）
+    fn check_timeout(&mut self) -> Option<StreamError> {
+        let resume_count = crate::power::resume_count();
+        if resume_count != self.resume_count {
+            self.resume_count = resume_count;
+            self.start_time = Instant::now();
+            self.last_chunk_time = self.last_chunk_time.map(|_| Instant::now());
+            return None;
+        }
+
         // 检查总超时
         if self.start_time.elapsed() > self.config.timeout_duration() {
             return Some(StreamError::Timeout);
@@ -1135,7 +1148,7 @@ mod tests {
             .with_timeout_ms(1) // 1ms 超时
             .with_chunk_timeout_ms(1);
 
-        let timeout_stream = TimeoutStream::new(inner_stream, config);
+        let mut timeout_stream = TimeoutStream::new(inner_stream, config);
 
         // 等待一小段时间让超时发生
         std::thread::sleep(std::time::Duration::from_millis(10));
@@ -0,0 +1,278 @@
+//! 实时日志缓冲
+//!
+//! 作为 tracing 订阅器的一层，将后端日志（Agent、服务器、工具等）写入内存环形缓冲：
+//! - 前端通过 `logs_tail` 按级别 / 模块查询最近的日志
+//! - 新日志通过广播通道推送，由命令层转发为 `log_line` 事件
+//! - 启用文件日志时同时写入按天轮转的日志文件（见 [`LogFileWriter`]）
+//! - 日志内容按 [`sanitize_log_message`] 脱敏，缓冲满后丢弃最早的日志
+
+use super::log_files::LogFileWriter;
+use crate::logger::sanitize_log_message;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::Layer;
+
+/// 环形缓冲保留的日志条数
+pub const LIVE_LOG_CAPACITY: usize = 2000;
+
+/// 广播通道容量（订阅方处理不过来时丢弃较早的推送）
+const BROADCAST_CAPACITY: usize = 256;
+
+/// crate 自身的 target 前缀，按模块过滤时可省略
+const CRATE_TARGET_PREFIX: &str = "proxycast_lib::";
+
+/// 一条实时日志
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogLine {
+    /// 递增序号，前端可据此去重
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    /// 日志级别（ERROR / WARN / INFO / DEBUG / TRACE）
+    pub level: String,
+    /// 产生日志的模块路径，如 `proxycast_lib::agent::native_agent`
+    pub target: String,
+    pub message: String,
+}
+
+/// 日志过滤条件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogFilter {
+    /// 最低级别，如 `warn` 只返回 WARN 和 ERROR
+    #[serde(default)]
+    pub level: Option<String>,
+    /// 模块前缀，如 `agent`、`server`、`agent::tools`
+    #[serde(default)]
+    pub module: Option<String>,
+}
+
+impl LogFilter {
+    /// 判断日志是否满足过滤条件（无法识别的级别不过滤）
+    pub fn matches(&self, line: &LogLine) -> bool {
+        if let Some(min) = self.level.as_deref().and_then(|l| Level::from_str(l).ok()) {
+            match Level::from_str(&line.level) {
+                Ok(level) if level > min => return false,
+                _ => {}
+            }
+        }
+        if let Some(module) = self.module.as_deref().filter(|m| !m.is_empty()) {
+            let module = module.strip_prefix(CRATE_TARGET_PREFIX).unwrap_or(module);
+            let target = line
+                .target
+                .strip_prefix(CRATE_TARGET_PREFIX)
+                .unwrap_or(&line.target);
+            if !(target == module || target.starts_with(&format!("{}::", module))) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// 实时日志环形缓冲（通过 [`LiveLogBuffer::layer`] 接入 tracing 订阅器）
+pub struct LiveLogBuffer {
+    capacity: usize,
+    lines: Mutex<VecDeque<LogLine>>,
+    next_seq: AtomicU64,
+    sender: broadcast::Sender<LogLine>,
+}
+
+impl LiveLogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(BROADCAST_CAPACITY);
+        Self {
+            capacity: capacity.max(1),
+            lines: Mutex::new(VecDeque::with_capacity(capacity)),
+            next_seq: AtomicU64::new(1),
+            sender,
+        }
+    }
+
+    /// 创建写入该缓冲的 tracing layer
+    pub fn layer(self: &Arc<Self>) -> LiveLogLayer {
+        LiveLogLayer {
+            buffer: self.clone(),
+            file: None,
+        }
+    }
+
+    /// 写入一条日志并推送给订阅方
+    pub fn push(&self, level: Level, target: &str, message: &str) -> LogLine {
+        let line = LogLine {
+            seq: self.next_seq.fetch_add(1, Ordering::Relaxed),
+            timestamp: Utc::now(),
+            level: level.to_string(),
+            target: target.to_string(),
+            message: sanitize_log_message(message),
+        };
+        {
+            let mut lines = self.lines.lock();
+            if lines.len() >= self.capacity {
+                lines.pop_front();
+            }
+            lines.push_back(line.clone());
+        }
+        // 没有订阅方时发送失败，忽略即可
+        let _ = self.sender.send(line.clone());
+        line
+    }
+
+    /// 返回满足过滤条件的最近 `limit` 条日志（按时间升序）
+    pub fn tail(&self, filter: &LogFilter, limit: usize) -> Vec<LogLine> {
+        let lines = self.lines.lock();
+        let mut matched: Vec<LogLine> = lines
+            .iter()
+            .rev()
+            .filter(|line| filter.matches(line))
+            .take(limit)
+            .cloned()
+            .collect();
+        matched.reverse();
+        matched
+    }
+
+    /// 订阅新写入的日志
+    pub fn subscribe(&self) -> broadcast::Receiver<LogLine> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for LiveLogBuffer {
+    fn default() -> Self {
+        Self::new(LIVE_LOG_CAPACITY)
+    }
+}
+
+/// 将 tracing 事件写入 [`LiveLogBuffer`] 的 layer
+pub struct LiveLogLayer {
+    buffer: Arc<LiveLogBuffer>,
+    file: Option<LogFileWriter>,
+}
+
+impl LiveLogLayer {
+    /// 同时将日志写入文件
+    pub fn with_file(mut self, writer: LogFileWriter) -> Self {
+        self.file = Some(writer);
+        self
+    }
+}
+
+impl<S: Subscriber> Layer<S> for LiveLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        let line = self
+            .buffer
+            .push(*metadata.level(), metadata.target(), &visitor.finish());
+        if let Some(file) = &self.file {
+            file.write(&line);
+        }
+    }
+}
+
+/// 拼接 `message` 字段和其余的 `key=value` 字段
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: Vec<String>,
+}
+
+impl MessageVisitor {
+    fn finish(self) -> String {
+        if self.fields.is_empty() {
+            return self.message;
+        }
+        let fields = self.fields.join(" ");
+        if self.message.is_empty() {
+            fields
+        } else {
+            format!("{} {}", self.message, fields)
+        }
+    }
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.fields.push(format!("{}={}", field.name(), value));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            self.fields.push(format!("{}={:?}", field.name(), value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn filter(level: Option<&str>, module: Option<&str>) -> LogFilter {
+        LogFilter {
+            level: level.map(str::to_string),
+            module: module.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_tail_filters_and_drops_oldest() {
+        let buffer = LiveLogBuffer::new(3);
+        buffer.push(Level::INFO, "proxycast_lib::server", "第一条");
+        buffer.push(Level::WARN, "proxycast_lib::agent::tools", "第二条");
+        buffer.push(Level::ERROR, "proxycast_lib::agent", "第三条");
+        buffer.push(Level::DEBUG, "proxycast_lib::agentx", "第四条");
+
+        // 容量为 3，最早的日志被丢弃
+        let all = buffer.tail(&LogFilter::default(), 10);
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].message, "第二条");
+        assert!(all.windows(2).all(|w| w[0].seq < w[1].seq));
+
+        let warn = buffer.tail(&filter(Some("warn"), None), 10);
+        assert_eq!(warn.len(), 2);
+
+        // 模块按路径前缀匹配，可省略 crate 前缀
+        let agent = buffer.tail(&filter(None, Some("agent")), 10);
+        assert_eq!(agent.len(), 2);
+        let tools = buffer.tail(&filter(None, Some("proxycast_lib::agent::tools")), 10);
+        assert_eq!(tools.len(), 1);
+
+        let latest = buffer.tail(&LogFilter::default(), 1);
+        assert_eq!(latest[0].message, "第四条");
+    }
+
+    #[test]
+    fn test_layer_records_events() {
+        let buffer = Arc::new(LiveLogBuffer::default());
+        let mut receiver = buffer.subscribe();
+        let subscriber = tracing_subscriber::registry().with(buffer.layer());
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(provider = "claude", "请求失败: api_key=sk-secret");
+        });
+
+        let lines = buffer.tail(&LogFilter::default(), 10);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].level, "WARN");
+        assert!(lines[0].message.starts_with("请求失败"));
+        assert!(lines[0].message.contains("provider=claude"));
+        assert!(!lines[0].message.contains("sk-secret"));
+        assert_eq!(receiver.try_recv().unwrap(), lines[0]);
+    }
+}
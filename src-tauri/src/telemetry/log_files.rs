@@ -0,0 +1,474 @@
+//! 后端日志文件
+//!
+//! 实时日志同时以 JSON Lines 写入 `~/.proxycast/logs/backend-YYYY-MM-DD.jsonl`：
+//! - 按天轮转，单个文件超过 `max_file_size_mb` 后切换到 `backend-YYYY-MM-DD.N.jsonl`
+//! - 超过保留天数的文件删除，总大小超过 `max_total_size_mb` 时从最早的文件开始删除
+//! - 由后台线程写入，打开文件失败后等待一段时间再重试
+//! - [`export_logs`] 将时间范围内的日志文件打包为 zip（不覆盖已有文件），便于提交问题报告
+
+use super::live_logs::LogLine;
+use super::types::TimeRange;
+use crate::config::LoggingConfig;
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, SyncSender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 后端日志文件名前缀
+const BACKEND_LOG_PREFIX: &str = "backend-";
+
+/// 后端日志文件扩展名
+const BACKEND_LOG_EXTENSION: &str = ".jsonl";
+
+/// 应用日志（`LogStore`）文件名前缀，导出时一并打包
+const APP_LOG_PREFIX: &str = "proxycast.log";
+
+const MB: u64 = 1024 * 1024;
+
+/// 默认日志目录（~/.proxycast/logs）
+pub fn default_log_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".proxycast")
+        .join("logs")
+}
+
+/// 日志文件配置
+#[derive(Debug, Clone)]
+pub struct LogFileConfig {
+    pub dir: PathBuf,
+    /// 单个文件大小上限（字节，0 表示不限制）
+    pub max_file_size: u64,
+    /// 文件总大小上限（字节，0 表示不限制）
+    pub max_total_size: u64,
+    pub retention_days: u32,
+}
+
+impl LogFileConfig {
+    pub fn from_logging(logging: &LoggingConfig) -> Self {
+        Self {
+            dir: default_log_dir(),
+            max_file_size: logging.max_file_size_mb.saturating_mul(MB),
+            max_total_size: logging.max_total_size_mb.saturating_mul(MB),
+            retention_days: logging.retention_days,
+        }
+    }
+}
+
+/// 写入队列容量（后台线程来不及写入时丢弃新日志）
+const WRITE_QUEUE_CAPACITY: usize = 4096;
+
+/// 打开日志文件失败后，等待多久再重试（期间的日志直接丢弃）
+const OPEN_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 当前写入的文件
+struct OpenFile {
+    date: NaiveDate,
+    index: u32,
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+/// 发给后台写入线程的命令
+enum WriteCommand {
+    Line { date: NaiveDate, json: String },
+    Flush(mpsc::Sender<()>),
+}
+
+/// 后端日志文件写入器
+///
+/// tracing layer 只把日志放入有界队列，由后台线程负责写入、轮转和清理，
+/// 磁盘变慢或写入失败时不会阻塞产生日志的线程
+pub struct LogFileWriter {
+    sender: SyncSender<WriteCommand>,
+}
+
+impl LogFileWriter {
+    /// 创建写入器，后台线程启动后先清理过期的日志文件
+    pub fn new(config: LogFileConfig) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(WRITE_QUEUE_CAPACITY);
+        // 线程创建失败时接收端被释放，之后的日志直接丢弃
+        let _ = thread::Builder::new()
+            .name("proxycast-log-writer".to_string())
+            .spawn(move || {
+                let _ = fs::create_dir_all(&config.dir);
+                prune_log_files(&config, None);
+                let mut sink = FileSink::new(config);
+                for command in receiver {
+                    match command {
+                        WriteCommand::Line { date, json } => sink.write(date, &json),
+                        WriteCommand::Flush(done) => {
+                            let _ = done.send(());
+                        }
+                    }
+                }
+            });
+        Self { sender }
+    }
+
+    /// 追加一条日志（队列已满或写入失败时忽略，不能再写 tracing 日志）
+    pub fn write(&self, line: &LogLine) {
+        let Ok(mut json) = serde_json::to_string(line) else {
+            return;
+        };
+        json.push('\n');
+        let date = line.timestamp.with_timezone(&Local).date_naive();
+        let _ = self.sender.try_send(WriteCommand::Line { date, json });
+    }
+
+    /// 等待已排队的日志写入文件
+    pub fn flush(&self) {
+        let (done, wait) = mpsc::channel();
+        if self.sender.send(WriteCommand::Flush(done)).is_ok() {
+            let _ = wait.recv();
+        }
+    }
+}
+
+/// 后台线程持有的文件状态
+struct FileSink {
+    config: LogFileConfig,
+    current: Option<OpenFile>,
+    /// 打开文件失败后，在此之前不再重试
+    retry_at: Option<Instant>,
+}
+
+impl FileSink {
+    fn new(config: LogFileConfig) -> Self {
+        Self {
+            config,
+            current: None,
+            retry_at: None,
+        }
+    }
+
+    fn write(&mut self, date: NaiveDate, json: &str) {
+        let len = json.len() as u64;
+        let rotate = match self.current.as_ref() {
+            Some(open) => {
+                open.date != date
+                    || (self.config.max_file_size > 0
+                        && open.size + len > self.config.max_file_size)
+            }
+            None => true,
+        };
+        if rotate {
+            if self.retry_at.is_some_and(|at| Instant::now() < at) {
+                return;
+            }
+            let index = match self.current.as_ref() {
+                Some(open) if open.date == date => open.index + 1,
+                _ => 0,
+            };
+            let rotated = self.current.is_some();
+            match self.open(date, index) {
+                Ok(open) => {
+                    self.current = Some(open);
+                    self.retry_at = None;
+                }
+                Err(_) => {
+                    self.current = None;
+                    self.retry_at = Some(Instant::now() + OPEN_RETRY_INTERVAL);
+                    return;
+                }
+            }
+            if rotated {
+                prune_log_files(
+                    &self.config,
+                    self.current.as_ref().map(|open| open.path.as_path()),
+                );
+            }
+        }
+        if let Some(open) = self.current.as_mut() {
+            if open.file.write_all(json.as_bytes()).is_ok() {
+                open.size += len;
+            }
+        }
+    }
+
+    /// 打开当天未写满的文件（从 `index` 开始查找，重启后继续追加到已有文件）
+    fn open(&self, date: NaiveDate, mut index: u32) -> io::Result<OpenFile> {
+        fs::create_dir_all(&self.config.dir)?;
+        loop {
+            let path = self.config.dir.join(log_file_name(date, index));
+            let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            if self.config.max_file_size == 0 || size < self.config.max_file_size {
+                let file = OpenOptions::new().create(true).append(true).open(&path)?;
+                return Ok(OpenFile {
+                    date,
+                    index,
+                    path,
+                    file,
+                    size,
+                });
+            }
+            index += 1;
+        }
+    }
+}
+
+/// 后端日志文件名：`backend-YYYY-MM-DD.jsonl`，同一天的后续文件为 `backend-YYYY-MM-DD.N.jsonl`
+fn log_file_name(date: NaiveDate, index: u32) -> String {
+    if index == 0 {
+        format!("{}{}{}", BACKEND_LOG_PREFIX, date, BACKEND_LOG_EXTENSION)
+    } else {
+        format!(
+            "{}{}.{}{}",
+            BACKEND_LOG_PREFIX, date, index, BACKEND_LOG_EXTENSION
+        )
+    }
+}
+
+/// 从文件名解析日期和序号（不是后端日志文件时返回 None）
+fn parse_log_file_name(name: &str) -> Option<(NaiveDate, u32)> {
+    let stem = name
+        .strip_prefix(BACKEND_LOG_PREFIX)?
+        .strip_suffix(BACKEND_LOG_EXTENSION)?;
+    let (date, index) = match stem.split_once('.') {
+        Some((date, index)) => (date, index.parse().ok()?),
+        None => (stem, 0),
+    };
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+    Some((date, index))
+}
+
+/// 列出目录下的后端日志文件，按日期和序号升序
+fn list_log_files(dir: &Path) -> Vec<(NaiveDate, u32, PathBuf, u64)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<_> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let (date, index) = parse_log_file_name(&entry.file_name().to_string_lossy())?;
+            let size = entry.metadata().ok().filter(|m| m.is_file())?.len();
+            Some((date, index, entry.path(), size))
+        })
+        .collect();
+    files.sort_by_key(|(date, index, _, _)| (*date, *index));
+    files
+}
+
+/// 删除超过保留天数的日志文件，总大小超过上限时从最早的文件开始删除（不删除 `keep`）
+fn prune_log_files(config: &LogFileConfig, keep: Option<&Path>) {
+    let cutoff = Local::now().date_naive() - chrono::Duration::days(config.retention_days as i64);
+    let mut remaining = Vec::new();
+    for (date, _, path, size) in list_log_files(&config.dir) {
+        if date < cutoff && keep != Some(path.as_path()) {
+            let _ = fs::remove_file(&path);
+        } else {
+            remaining.push((path, size));
+        }
+    }
+
+    if config.max_total_size == 0 {
+        return;
+    }
+    let mut total: u64 = remaining.iter().map(|(_, size)| size).sum();
+    for (path, size) in remaining {
+        if total <= config.max_total_size {
+            break;
+        }
+        if keep == Some(path.as_path()) {
+            continue;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total = total.saturating_sub(size);
+        }
+    }
+}
+
+/// 将日志目录中时间范围内的日志文件打包为 zip，返回打包的文件数
+///
+/// 后端日志按文件名中的日期筛选，应用日志（`proxycast.log*`）按修改时间筛选；
+/// 未指定范围时打包全部日志文件
+pub fn export_logs(dir: &Path, range: Option<&TimeRange>, dest: &Path) -> Result<usize, String> {
+    let mut files: Vec<(String, PathBuf)> = Vec::new();
+    for (date, _, path, _) in list_log_files(dir) {
+        let in_range = range.is_none_or(|range| {
+            date >= range.start.with_timezone(&Local).date_naive()
+                && date <= range.end.with_timezone(&Local).date_naive()
+        });
+        if in_range {
+            let name = path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            files.push((name, path));
+        }
+    }
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.starts_with(APP_LOG_PREFIX) {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let modified = metadata.modified().ok().map(DateTime::<Utc>::from);
+            let in_range = match (range, modified) {
+                (Some(range), Some(modified)) => modified >= range.start,
+                _ => true,
+            };
+            if metadata.is_file() && in_range {
+                files.push((name, entry.path()));
+            }
+        }
+    }
+    if files.is_empty() {
+        return Err("没有符合条件的日志文件".to_string());
+    }
+
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建导出目录失败: {}", e))?;
+    }
+    // 不覆盖已有文件
+    let file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(dest)
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::AlreadyExists => format!("导出文件已存在: {}", dest.display()),
+            _ => format!("创建导出文件失败: {}", e),
+        })?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::FileOptions::default();
+    for (name, path) in &files {
+        let mut input = File::open(path).map_err(|e| format!("读取日志文件失败: {}", e))?;
+        zip.start_file(name.as_str(), options)
+            .map_err(|e| format!("写入导出文件失败: {}", e))?;
+        io::copy(&mut input, &mut zip).map_err(|e| format!("写入导出文件失败: {}", e))?;
+    }
+    zip.finish()
+        .map_err(|e| format!("写入导出文件失败: {}", e))?;
+    Ok(files.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn line(timestamp: DateTime<Utc>, message: &str) -> LogLine {
+        LogLine {
+            seq: 1,
+            timestamp,
+            level: "INFO".to_string(),
+            target: "proxycast_lib::server".to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    fn config(dir: &Path, max_file_size: u64, max_total_size: u64) -> LogFileConfig {
+        LogFileConfig {
+            dir: dir.to_path_buf(),
+            max_file_size,
+            max_total_size,
+            retention_days: 7,
+        }
+    }
+
+    #[test]
+    fn test_file_name_roundtrip() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 17).unwrap();
+        assert_eq!(log_file_name(date, 0), "backend-2026-10-17.jsonl");
+        assert_eq!(log_file_name(date, 3), "backend-2026-10-17.3.jsonl");
+        assert_eq!(
+            parse_log_file_name("backend-2026-10-17.jsonl"),
+            Some((date, 0))
+        );
+        assert_eq!(
+            parse_log_file_name("backend-2026-10-17.3.jsonl"),
+            Some((date, 3))
+        );
+        assert_eq!(parse_log_file_name("proxycast.log"), None);
+    }
+
+    #[test]
+    fn test_rotates_by_size_and_day() {
+        let dir = TempDir::new().unwrap();
+        let writer = LogFileWriter::new(config(dir.path(), 150, 0));
+        let now = Utc::now();
+        for i in 0..4 {
+            writer.write(&line(now, &format!("第 {} 条日志", i)));
+        }
+        writer.flush();
+        // 每条约 120 字节，超过 150 字节后切换文件
+        let today = list_log_files(dir.path());
+        assert_eq!(today.len(), 4);
+        assert!(today.iter().all(|(date, _, _, _)| *date == today[0].0));
+
+        writer.write(&line(now + chrono::Duration::days(1), "第二天"));
+        writer.flush();
+        let files = list_log_files(dir.path());
+        assert_eq!(files.len(), 5);
+        assert_eq!(files[4].1, 0);
+        assert!(files[4].0 > files[0].0);
+    }
+
+    #[test]
+    fn test_prune_and_export() {
+        let dir = TempDir::new().unwrap();
+        let today = Local::now().date_naive();
+        let old = today - chrono::Duration::days(30);
+        let yesterday = today - chrono::Duration::days(1);
+        for (date, content) in [(old, "old"), (yesterday, "yesterday"), (today, "today")] {
+            fs::write(dir.path().join(log_file_name(date, 0)), content.repeat(10)).unwrap();
+        }
+        fs::write(dir.path().join("proxycast.log"), "app").unwrap();
+
+        // 超过保留天数的文件被删除，总大小超限时删除最早的文件
+        prune_log_files(&config(dir.path(), 0, 60), None);
+        let files = list_log_files(dir.path());
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].0, today);
+
+        let dest = dir.path().join("exports").join("logs.zip");
+        let range = TimeRange::last_hours(1);
+        assert_eq!(export_logs(dir.path(), Some(&range), &dest).unwrap(), 2);
+        let archive = zip::ZipArchive::new(File::open(&dest).unwrap()).unwrap();
+        let mut names: Vec<&str> = archive.file_names().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![log_file_name(today, 0).as_str(), "proxycast.log"]
+        );
+
+        // 不覆盖已有的导出文件
+        assert!(export_logs(dir.path(), None, &dest).is_err());
+
+        let empty = TempDir::new().unwrap();
+        assert!(export_logs(empty.path(), None, &dest).is_err());
+    }
+
+    #[test]
+    fn test_open_failure_backs_off() {
+        let dir = TempDir::new().unwrap();
+        // 日志目录被同名文件占用，无法打开日志文件
+        let blocked = dir.path().join("logs");
+        fs::write(&blocked, "").unwrap();
+        let mut sink = FileSink::new(config(&blocked, 0, 0));
+        let today = Local::now().date_naive();
+        sink.write(today, "first\n");
+        assert!(sink.current.is_none());
+        assert!(sink.retry_at.is_some());
+
+        // 等待重试期间即使目录恢复也不重试
+        fs::remove_file(&blocked).unwrap();
+        sink.write(today, "second\n");
+        assert!(sink.current.is_none());
+
+        sink.retry_at = Some(Instant::now());
+        sink.write(today, "third\n");
+        assert!(sink.retry_at.is_none());
+        let files = list_log_files(&blocked);
+        assert_eq!(files.len(), 1);
+        assert_eq!(fs::read_to_string(&files[0].2).unwrap(), "third\n");
+    }
+}
@@ -72,6 +72,19 @@ pub struct LogRotationConfig {
     pub max_file_size: u64,
     /// 是否启用文件日志
     pub enable_file_logging: bool,
+    /// 是否记录请求体和响应体
+    #[serde(default)]
+    pub capture_bodies: bool,
+    /// 请求体和响应体保留的最大字节数，超出部分截断
+    #[serde(default = "default_max_body_bytes")]
+    pub max_body_bytes: usize,
+}
+
+/// 请求体和响应体默认保留 4KB
+pub const DEFAULT_MAX_BODY_BYTES: usize = 4 * 1024;
+
+fn default_max_body_bytes() -> usize {
+    DEFAULT_MAX_BODY_BYTES
 }
 
 impl Default for LogRotationConfig {
@@ -81,10 +94,112 @@ impl Default for LogRotationConfig {
             retention_days: 7,
             max_file_size: 10 * 1024 * 1024, // 10MB
             enable_file_logging: true,
+            capture_bodies: false,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
         }
     }
 }
 
+/// 请求日志查询条件（所有条件为空时返回全部日志）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RequestLogQuery {
+    /// Provider
+    #[serde(default)]
+    pub provider: Option<ProviderType>,
+    /// 模型名称（包含匹配，不区分大小写）
+    #[serde(default)]
+    pub model: Option<String>,
+    /// 请求状态
+    #[serde(default)]
+    pub status: Option<RequestStatus>,
+    /// HTTP 方法
+    #[serde(default)]
+    pub method: Option<String>,
+    /// 请求路径（包含匹配）
+    #[serde(default)]
+    pub path: Option<String>,
+    /// 客户端 API Key ID
+    #[serde(default)]
+    pub client_key: Option<String>,
+    /// 开始时间（包含）
+    #[serde(default)]
+    pub start_time: Option<DateTime<Utc>>,
+    /// 结束时间（不包含）
+    #[serde(default)]
+    pub end_time: Option<DateTime<Utc>>,
+    /// 最小耗时（毫秒）
+    #[serde(default)]
+    pub min_duration_ms: Option<u64>,
+    /// 跳过的条数（分页）
+    #[serde(default)]
+    pub offset: Option<usize>,
+    /// 返回的最大条数
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+impl RequestLogQuery {
+    /// 检查日志是否满足查询条件
+    pub fn matches(&self, log: &RequestLog) -> bool {
+        if self.provider.is_some_and(|p| p != log.provider) {
+            return false;
+        }
+        if self.status.is_some_and(|s| s != log.status) {
+            return false;
+        }
+        if let Some(model) = &self.model {
+            if !log.model.to_lowercase().contains(&model.to_lowercase()) {
+                return false;
+            }
+        }
+        if let Some(method) = &self.method {
+            if !log
+                .method
+                .as_deref()
+                .is_some_and(|m| m.eq_ignore_ascii_case(method))
+            {
+                return false;
+            }
+        }
+        if let Some(path) = &self.path {
+            if !log
+                .path
+                .as_deref()
+                .is_some_and(|p| p.contains(path.as_str()))
+            {
+                return false;
+            }
+        }
+        if let Some(client_key) = &self.client_key {
+            if log.client_key.as_ref() != Some(client_key) {
+                return false;
+            }
+        }
+        if self.start_time.is_some_and(|t| log.timestamp < t) {
+            return false;
+        }
+        if self.end_time.is_some_and(|t| log.timestamp >= t) {
+            return false;
+        }
+        if self.min_duration_ms.is_some_and(|d| log.duration_ms < d) {
+            return false;
+        }
+        true
+    }
+}
+
+/// 按字符边界截断文本，超出时追加截断标记
+fn truncate_body(body: &str, max_bytes: usize) -> String {
+    if body.len() <= max_bytes {
+        return body.to_string();
+    }
+    let mut end = max_bytes;
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}…[已截断 {} 字节]", &body[..end], body.len() - end)
+}
+
 /// 请求日志记录器
 ///
 /// 管理请求日志的记录、存储和查询
@@ -153,6 +268,49 @@ impl RequestLogger {
         Ok(())
     }
 
+    /// 是否记录请求体和响应体
+    pub fn captures_bodies(&self) -> bool {
+        self.config.capture_bodies
+    }
+
+    /// 按配置截断请求体或响应体，未启用记录时返回 None
+    pub fn capture_body(&self, body: &str) -> Option<String> {
+        if !self.config.capture_bodies || body.is_empty() {
+            return None;
+        }
+        Some(truncate_body(body, self.config.max_body_bytes))
+    }
+
+    /// 更新内存中日志的 Token 使用量
+    ///
+    /// Token 通常在请求日志记录之后才统计出来，已写入文件的记录不会更新。
+    pub fn set_tokens(&self, id: &str, input: Option<u32>, output: Option<u32>) -> bool {
+        let mut logs = self.logs.write();
+        match logs.iter_mut().rev().find(|log| log.id == id) {
+            Some(log) => {
+                log.set_tokens(input, output);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 按条件查询日志（按时间倒序）
+    pub fn query(&self, query: &RequestLogQuery) -> Vec<RequestLog> {
+        let mut logs: Vec<RequestLog> = self
+            .logs
+            .read()
+            .iter()
+            .filter(|log| query.matches(log))
+            .cloned()
+            .collect();
+        logs.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+
+        let offset = query.offset.unwrap_or(0);
+        let limit = query.limit.unwrap_or(usize::MAX);
+        logs.into_iter().skip(offset).take(limit).collect()
+    }
+
     /// 获取所有内存中的日志
     pub fn get_all(&self) -> Vec<RequestLog> {
         self.logs.read().iter().cloned().collect()
@@ -0,0 +1,319 @@
+//! Prometheus 指标
+//!
+//! 在进程内累计 API 服务器的请求指标，由 `/metrics` 以 Prometheus 文本格式输出：
+//! - `proxycast_requests_total`：按 Provider、模型、状态统计的请求数
+//! - `proxycast_request_duration_seconds`：请求耗时直方图
+//! - `proxycast_stream_ttft_seconds`：流式请求从收到请求到输出第一块数据的时间
+//! - `proxycast_upstream_errors_total`：上游失败（失败、超时）次数
+//! - `proxycast_tokens_total`：按输入/输出统计的 Token 数
+//!
+//! 与 [`StatsAggregator`](super::StatsAggregator) 不同，指标只增不减、不按保留期清理，
+//! 服务器重启后归零（Prometheus 会自动处理计数器重置）。
+
+use super::types::RequestStatus;
+use crate::ProviderType;
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+/// 请求耗时直方图的桶（秒）
+const DURATION_BUCKETS: [f64; 11] = [
+    0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0,
+];
+/// 首 Token 时间直方图的桶（秒）
+const TTFT_BUCKETS: [f64; 10] = [0.1, 0.25, 0.5, 0.75, 1.0, 1.5, 2.5, 5.0, 10.0, 30.0];
+
+/// 按 Provider 和模型区分的标签
+type ModelLabels = (String, String);
+
+#[derive(Debug, Clone)]
+struct Histogram {
+    buckets: &'static [f64],
+    /// 每个桶的累计计数（不含 +Inf）
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(buckets: &'static [f64]) -> Self {
+        Self {
+            buckets,
+            counts: vec![0; buckets.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (bound, count) in self.buckets.iter().zip(self.counts.iter_mut()) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+struct MetricsInner {
+    requests: BTreeMap<(String, String, String), u64>,
+    durations: BTreeMap<ModelLabels, Histogram>,
+    ttft: BTreeMap<ModelLabels, Histogram>,
+    upstream_errors: BTreeMap<(String, String, String), u64>,
+    tokens: BTreeMap<(String, String, &'static str), u64>,
+}
+
+/// Prometheus 指标注册表
+#[derive(Debug, Default)]
+pub struct PrometheusMetrics {
+    inner: Mutex<MetricsInner>,
+}
+
+fn labels(provider: ProviderType, model: &str) -> ModelLabels {
+    (provider.to_string(), model.to_string())
+}
+
+impl PrometheusMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次请求（重试中的中间状态不计入）
+    pub fn record_request(
+        &self,
+        provider: ProviderType,
+        model: &str,
+        status: RequestStatus,
+        duration: Duration,
+    ) {
+        if status == RequestStatus::Retrying {
+            return;
+        }
+        let (provider, model) = labels(provider, model);
+        let mut inner = self.inner.lock();
+        *inner
+            .requests
+            .entry((provider.clone(), model.clone(), status.to_string()))
+            .or_default() += 1;
+        if matches!(status, RequestStatus::Failed | RequestStatus::Timeout) {
+            *inner
+                .upstream_errors
+                .entry((provider.clone(), model.clone(), status.to_string()))
+                .or_default() += 1;
+        }
+        inner
+            .durations
+            .entry((provider, model))
+            .or_insert_with(|| Histogram::new(&DURATION_BUCKETS))
+            .observe(duration.as_secs_f64());
+    }
+
+    /// 记录流式请求的首 Token 时间
+    pub fn record_ttft(&self, provider: ProviderType, model: &str, ttft: Duration) {
+        self.inner
+            .lock()
+            .ttft
+            .entry(labels(provider, model))
+            .or_insert_with(|| Histogram::new(&TTFT_BUCKETS))
+            .observe(ttft.as_secs_f64());
+    }
+
+    /// 记录 Token 使用量
+    pub fn record_tokens(&self, provider: ProviderType, model: &str, input: u32, output: u32) {
+        let (provider, model) = labels(provider, model);
+        let mut inner = self.inner.lock();
+        for (kind, value) in [("input", input), ("output", output)] {
+            *inner
+                .tokens
+                .entry((provider.clone(), model.clone(), kind))
+                .or_default() += value as u64;
+        }
+    }
+
+    /// 以 Prometheus 文本格式输出全部指标
+    pub fn render(&self) -> String {
+        let inner = self.inner.lock();
+        let mut out = String::new();
+
+        write_header(
+            &mut out,
+            "proxycast_requests_total",
+            "counter",
+            "Total API requests",
+        );
+        for ((provider, model, status), value) in &inner.requests {
+            let _ = writeln!(
+                out,
+                "proxycast_requests_total{{provider=\"{}\",model=\"{}\",status=\"{}\"}} {}",
+                escape(provider),
+                escape(model),
+                status,
+                value
+            );
+        }
+
+        write_histogram(
+            &mut out,
+            "proxycast_request_duration_seconds",
+            "API request latency in seconds",
+            &inner.durations,
+        );
+        write_histogram(
+            &mut out,
+            "proxycast_stream_ttft_seconds",
+            "Streaming time to first token in seconds",
+            &inner.ttft,
+        );
+
+        write_header(
+            &mut out,
+            "proxycast_upstream_errors_total",
+            "counter",
+            "Failed or timed out upstream requests",
+        );
+        for ((provider, model, kind), value) in &inner.upstream_errors {
+            let _ = writeln!(
+                out,
+                "proxycast_upstream_errors_total{{provider=\"{}\",model=\"{}\",kind=\"{}\"}} {}",
+                escape(provider),
+                escape(model),
+                kind,
+                value
+            );
+        }
+
+        write_header(
+            &mut out,
+            "proxycast_tokens_total",
+            "counter",
+            "Token usage by direction",
+        );
+        for ((provider, model, kind), value) in &inner.tokens {
+            let _ = writeln!(
+                out,
+                "proxycast_tokens_total{{provider=\"{}\",model=\"{}\",type=\"{}\"}} {}",
+                escape(provider),
+                escape(model),
+                kind,
+                value
+            );
+        }
+
+        out
+    }
+}
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn write_histogram(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    histograms: &BTreeMap<ModelLabels, Histogram>,
+) {
+    write_header(out, name, "histogram", help);
+    for ((provider, model), histogram) in histograms {
+        let labels = format!(
+            "provider=\"{}\",model=\"{}\"",
+            escape(provider),
+            escape(model)
+        );
+        for (bound, count) in histogram.buckets.iter().zip(&histogram.counts) {
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{},le=\"{}\"}} {}",
+                name, labels, bound, count
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{},le=\"+Inf\"}} {}",
+            name, labels, histogram.count
+        );
+        let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, histogram.sum);
+        let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, histogram.count);
+    }
+}
+
+/// 转义标签值中的反斜杠、引号和换行
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_metrics() {
+        let metrics = PrometheusMetrics::new();
+        metrics.record_request(
+            ProviderType::Claude,
+            "claude-sonnet-4-5",
+            RequestStatus::Success,
+            Duration::from_millis(800),
+        );
+        metrics.record_request(
+            ProviderType::Claude,
+            "claude-sonnet-4-5",
+            RequestStatus::Failed,
+            Duration::from_secs(3),
+        );
+        metrics.record_request(
+            ProviderType::Claude,
+            "claude-sonnet-4-5",
+            RequestStatus::Retrying,
+            Duration::from_secs(1),
+        );
+        metrics.record_ttft(
+            ProviderType::Claude,
+            "claude-sonnet-4-5",
+            Duration::from_millis(300),
+        );
+        metrics.record_tokens(ProviderType::Claude, "claude-sonnet-4-5", 120, 30);
+        metrics.record_tokens(ProviderType::Claude, "claude-sonnet-4-5", 80, 20);
+
+        let text = metrics.render();
+        let provider = ProviderType::Claude.to_string();
+        let labels = format!("provider=\"{}\",model=\"claude-sonnet-4-5\"", provider);
+        assert!(text.contains(&format!(
+            "proxycast_requests_total{{{},status=\"success\"}} 1",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "proxycast_upstream_errors_total{{{},kind=\"failed\"}} 1",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "proxycast_request_duration_seconds_bucket{{{},le=\"1\"}} 1",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "proxycast_request_duration_seconds_count{{{}}} 2",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "proxycast_stream_ttft_seconds_bucket{{{},le=\"0.5\"}} 1",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "proxycast_tokens_total{{{},type=\"input\"}} 200",
+            labels
+        )));
+        assert!(!text.contains("retrying"));
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -1,17 +1,27 @@
 //! 监控与日志模块
 //!
-//! 提供请求日志记录、统计聚合和 Token 追踪功能
+//! 提供请求日志记录、统计聚合、Token 追踪、Prometheus 指标、实时日志（缓冲与文件轮转）和 OpenTelemetry 链路追踪导出功能
 
+mod live_logs;
+mod log_files;
 mod logger;
+mod metrics;
+mod otlp;
 mod stats;
 mod tokens;
 mod types;
 
-pub use logger::{LogRotationConfig, LoggerError, RequestLogger};
+pub use live_logs::{LiveLogBuffer, LiveLogLayer, LogFilter, LogLine, LIVE_LOG_CAPACITY};
+pub use log_files::{default_log_dir, export_logs, LogFileConfig, LogFileWriter};
+pub use logger::{
+    LogRotationConfig, LoggerError, RequestLogQuery, RequestLogger, DEFAULT_MAX_BODY_BYTES,
+};
+pub use metrics::PrometheusMetrics;
+pub use otlp::{init_tracing, traces_endpoint, OtlpGuard};
 pub use stats::StatsAggregator;
 pub use tokens::{
-    ModelTokenStats, PeriodTokenStats, ProviderTokenStats, TokenSource, TokenStatsSummary,
-    TokenTracker, TokenUsageRecord,
+    ModelTokenStats, PeriodTokenStats, ProviderTokenStats, TokenEstimator, TokenSource,
+    TokenStatsSummary, TokenTracker, TokenUsageRecord,
 };
 pub use types::{ModelStats, ProviderStats, RequestLog, RequestStatus, StatsSummary, TimeRange};
 
@@ -0,0 +1,105 @@
+//! OpenTelemetry 链路追踪导出
+//!
+//! 启动时安装全局 tracing 订阅器（始终包含实时日志缓冲和日志文件），启用 `otlp.enabled` 后额外将
+//! Agent 对话、流式响应、工具调用和代理上游请求的 span（带 model / provider 属性）
+//! 通过 OTLP/HTTP 批量导出到 Jaeger、Tempo 等后端。修改配置后需重启应用生效。
+
+use super::live_logs::LiveLogLayer;
+use crate::config::OtlpConfig;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::trace::{Sampler, Tracer, TracerProvider};
+use opentelemetry_sdk::Resource;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// OTLP/HTTP 的 trace 路径
+const TRACES_PATH: &str = "/v1/traces";
+
+/// 导出器句柄，退出前调用 [`OtlpGuard::shutdown`] 刷新未导出的 span
+pub struct OtlpGuard {
+    provider: TracerProvider,
+}
+
+impl OtlpGuard {
+    pub fn shutdown(self) {
+        if let Err(e) = self.provider.shutdown() {
+            tracing::warn!("[OTLP] 关闭导出器失败: {}", e);
+        }
+    }
+}
+
+/// 补全 trace 导出地址（只填写 collector 地址时追加 `/v1/traces`）
+pub fn traces_endpoint(endpoint: &str) -> String {
+    let endpoint = endpoint.trim().trim_end_matches('/');
+    if endpoint.ends_with(TRACES_PATH) {
+        endpoint.to_string()
+    } else {
+        format!("{}{}", endpoint, TRACES_PATH)
+    }
+}
+
+/// 安装全局 tracing 订阅器，返回 OTLP 导出器句柄（未启用导出时为 None）
+///
+/// 实时日志（及日志文件）始终生效；OTLP 导出器创建失败时只跳过导出并返回错误原因。
+/// `level` 为 `logging.level`（无法识别时按 info 处理）。
+/// 需要在 tokio 运行时上下文中调用，批量导出任务运行在该运行时上
+pub fn init_tracing(
+    config: &OtlpConfig,
+    level: &str,
+    live_logs: LiveLogLayer,
+) -> (Option<OtlpGuard>, Option<String>) {
+    let (tracer, guard, export_error) = match build_tracer(config) {
+        Ok(Some((tracer, provider))) => (Some(tracer), Some(OtlpGuard { provider }), None),
+        Ok(None) => (None, None, None),
+        Err(e) => (None, None, Some(e)),
+    };
+
+    let level = level.parse().unwrap_or(tracing::Level::INFO);
+    let filter = tracing_subscriber::filter::Targets::new().with_target("proxycast_lib", level);
+    let otlp_layer = tracer.map(|tracer| tracing_opentelemetry::layer().with_tracer(tracer));
+    if let Err(e) = tracing_subscriber::registry()
+        .with(otlp_layer)
+        .with(live_logs)
+        .with(filter)
+        .try_init()
+    {
+        return (None, Some(format!("安装 tracing 订阅器失败: {}", e)));
+    }
+
+    if guard.is_some() {
+        tracing::info!(
+            "[OTLP] 链路追踪导出已启用: {}",
+            traces_endpoint(&config.endpoint)
+        );
+    }
+    (guard, export_error)
+}
+
+/// 创建 OTLP 导出的 tracer（未启用时返回 None）
+fn build_tracer(config: &OtlpConfig) -> Result<Option<(Tracer, TracerProvider)>, String> {
+    if !config.enabled {
+        return Ok(None);
+    }
+
+    let endpoint = traces_endpoint(&config.endpoint);
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()
+        .map_err(|e| format!("创建 OTLP 导出器失败: {}", e))?;
+
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, opentelemetry_sdk::runtime::Tokio)
+        .with_sampler(Sampler::TraceIdRatioBased(
+            config.sample_ratio.clamp(0.0, 1.0),
+        ))
+        .with_resource(Resource::new(vec![
+            KeyValue::new("service.name", config.service_name.clone()),
+            KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+        ]))
+        .build();
+    let tracer = provider.tracer("proxycast");
+    Ok(Some((tracer, provider)))
+}
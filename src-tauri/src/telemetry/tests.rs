@@ -3,7 +3,8 @@
 //! 使用 proptest 进行属性测试
 
 use crate::telemetry::{
-    LogRotationConfig, RequestLog, RequestLogger, RequestStatus, StatsAggregator, TimeRange,
+    LogRotationConfig, RequestLog, RequestLogQuery, RequestLogger, RequestStatus, StatsAggregator,
+    TimeRange,
 };
 use crate::ProviderType;
 use chrono::{Duration, Utc};
@@ -111,6 +112,8 @@ fn create_test_logger() -> RequestLogger {
         retention_days: 7,
         max_file_size: 10 * 1024 * 1024,
         enable_file_logging: false, // 测试时禁用文件日志
+        capture_bodies: false,
+        max_body_bytes: 4096,
     };
     RequestLogger::new(config).expect("Failed to create test logger")
 }
@@ -343,6 +346,8 @@ proptest! {
             retention_days: 7,
             max_file_size: 10 * 1024 * 1024,
             enable_file_logging: false,
+            capture_bodies: false,
+            max_body_bytes: 4096,
         };
         let logger = RequestLogger::new(config).expect("Failed to create logger");
 
@@ -492,6 +497,90 @@ fn test_stats_by_model() {
     assert_eq!(stats["model-b"].summary.total_requests, 1);
 }
 
+#[test]
+fn test_logger_query_filters() {
+    let logger = create_test_logger();
+
+    for (id, path, status, duration) in [
+        ("a", "/v1/chat/completions", RequestStatus::Success, 100),
+        ("b", "/v1/messages", RequestStatus::Failed, 2000),
+        ("c", "/v1/messages", RequestStatus::Success, 3000),
+    ] {
+        let mut log = RequestLog::new(
+            id.to_string(),
+            ProviderType::Kiro,
+            "claude-sonnet".to_string(),
+            false,
+        );
+        log.set_route("POST", path);
+        match status {
+            RequestStatus::Success => log.mark_success(duration, 200),
+            _ => log.mark_failed(duration, Some(500), "error".to_string()),
+        }
+        logger.record(log).expect("Failed to record log");
+    }
+
+    let query = RequestLogQuery {
+        path: Some("messages".to_string()),
+        min_duration_ms: Some(1000),
+        ..Default::default()
+    };
+    assert_eq!(logger.query(&query).len(), 2);
+
+    let query = RequestLogQuery {
+        path: Some("/v1/messages".to_string()),
+        status: Some(RequestStatus::Success),
+        method: Some("post".to_string()),
+        model: Some("SONNET".to_string()),
+        ..Default::default()
+    };
+    let logs = logger.query(&query);
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].id, "c");
+
+    let query = RequestLogQuery {
+        offset: Some(1),
+        limit: Some(1),
+        ..Default::default()
+    };
+    assert_eq!(logger.query(&query).len(), 1);
+}
+
+#[test]
+fn test_logger_set_tokens() {
+    let logger = create_test_logger();
+    let log = RequestLog::new(
+        "tokens".to_string(),
+        ProviderType::Kiro,
+        "model".to_string(),
+        false,
+    );
+    logger.record(log).expect("Failed to record log");
+
+    assert!(logger.set_tokens("tokens", Some(10), Some(5)));
+    assert!(!logger.set_tokens("missing", Some(1), None));
+    assert_eq!(logger.get_by_id("tokens").unwrap().total_tokens, Some(15));
+}
+
+#[test]
+fn test_logger_capture_body_truncates() {
+    let config = LogRotationConfig {
+        max_memory_logs: 10,
+        retention_days: 7,
+        max_file_size: 10 * 1024 * 1024,
+        enable_file_logging: false,
+        capture_bodies: true,
+        max_body_bytes: 4,
+    };
+    let logger = RequestLogger::new(config).expect("Failed to create logger");
+
+    assert_eq!(logger.capture_body("abc").as_deref(), Some("abc"));
+    // 截断位置落在多字节字符内部时回退到字符边界
+    let body = logger.capture_body("ab你好").unwrap();
+    assert!(body.starts_with("ab…"));
+    assert!(create_test_logger().capture_body("abc").is_none());
+}
+
 // ========== StatsAggregator 属性测试 ==========
 
 /// 创建测试用的统计聚合器
@@ -841,3 +930,17 @@ fn test_stats_aggregator_max_logs_limit() {
     // 验证日志数量不超过限制
     assert_eq!(aggregator.len(), 10);
 }
+
+#[test]
+fn test_otlp_traces_endpoint() {
+    use crate::telemetry::traces_endpoint;
+
+    assert_eq!(
+        traces_endpoint("http://localhost:4318"),
+        "http://localhost:4318/v1/traces"
+    );
+    assert_eq!(
+        traces_endpoint("http://tempo:4318/v1/traces/"),
+        "http://tempo:4318/v1/traces"
+    );
+}
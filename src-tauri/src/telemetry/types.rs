@@ -67,6 +67,21 @@ pub struct RequestLog {
     pub credential_id: Option<String>,
     /// 重试次数
     pub retry_count: u32,
+    /// HTTP 方法
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    /// 请求路径
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// 请求体（已截断，仅在启用请求体记录时保存）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<String>,
+    /// 响应体（已截断，仅在启用请求体记录时保存，流式响应不记录）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_body: Option<String>,
+    /// 发起请求的客户端 API Key ID（使用主 API Key 时为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<String>,
 }
 
 impl RequestLog {
@@ -87,6 +102,11 @@ impl RequestLog {
             is_streaming,
             credential_id: None,
             retry_count: 0,
+            method: None,
+            path: None,
+            request_body: None,
+            response_body: None,
+            client_key: None,
         }
     }
 
@@ -133,6 +153,12 @@ impl RequestLog {
         self.credential_id = Some(id);
     }
 
+    /// 设置请求方法和路径
+    pub fn set_route(&mut self, method: impl Into<String>, path: impl Into<String>) {
+        self.method = Some(method.into());
+        self.path = Some(path.into());
+    }
+
     /// 增加重试次数
     pub fn increment_retry(&mut self) {
         self.retry_count += 1;
@@ -601,6 +601,7 @@ mod tests {
             top_p: None,
             tool_choice: None,
             reasoning_effort: None,
+            stream_options: None,
         };
 
         let translator = OpenAiRequestTranslator::new();
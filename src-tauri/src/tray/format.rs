@@ -31,12 +31,13 @@ pub fn format_request_count(count: u64) -> String {
     format!("📊 今日请求: {count} 次")
 }
 
-/// 格式化 API 地址
+/// 格式化 API 地址（`scheme` 跟随 TLS 设置）
 ///
 /// # 示例输出
 /// - "http://127.0.0.1:8080"
-pub fn format_api_address(host: &str, port: u16) -> String {
-    format!("http://{host}:{port}")
+/// - "https://127.0.0.1:8080"
+pub fn format_api_address(scheme: &str, host: &str, port: u16) -> String {
+    format!("{scheme}://{host}:{port}")
 }
 
 #[cfg(test)]
@@ -82,7 +83,7 @@ mod tests {
             host in "[a-z0-9.]{1,50}",
             port in 1024u16..65535
         ) {
-            let address = format_api_address(&host, port);
+            let address = format_api_address("http", &host, port);
             let expected = format!("http://{host}:{port}");
             prop_assert_eq!(address, expected, "API 地址格式应为 http://{{host}}:{{port}}");
         }
@@ -114,7 +115,9 @@ mod tests {
 
     #[test]
     fn test_format_api_address() {
-        let address = format_api_address("127.0.0.1", 8080);
+        let address = format_api_address("http", "127.0.0.1", 8080);
         assert_eq!(address, "http://127.0.0.1:8080");
+        let address = format_api_address("https", "127.0.0.1", 8080);
+        assert_eq!(address, "https://127.0.0.1:8080");
     }
 }
@@ -259,7 +259,7 @@ pub fn build_tray_menu<R: Runtime>(
 /// - "host:port" -> (host, port)
 /// - "host" -> (host, 8080)
 /// - "" -> ("127.0.0.1", 8080)
-fn parse_server_address(address: &str) -> (String, u16) {
+pub(crate) fn parse_server_address(address: &str) -> (String, u16) {
     if address.is_empty() {
         return ("127.0.0.1".to_string(), 8080);
     }
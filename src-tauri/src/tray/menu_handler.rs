@@ -7,7 +7,8 @@
 //! - 4.1, 4.2, 4.3, 4.4: 快捷工具事件处理
 //! - 5.1, 5.2: 设置切换事件处理
 
-use super::menu::menu_ids;
+use super::format::format_api_address;
+use super::menu::{menu_ids, parse_server_address};
 use tauri::{AppHandle, Emitter, Manager, Runtime};
 use tauri_plugin_autostart::ManagerExt;
 use tracing::{debug, error, info, warn};
@@ -172,7 +173,13 @@ fn handle_copy_api_address<R: Runtime>(app: &AppHandle<R>) {
                 let state = tray_manager.get_state().await;
 
                 if state.server_running && !state.server_address.is_empty() {
-                    let api_address = format!("http://{}", state.server_address);
+                    // 协议跟随运行中服务器的 TLS 设置
+                    let scheme = match app_clone.try_state::<crate::AppState>() {
+                        Some(app_state) => app_state.read().await.listen_config().scheme(),
+                        None => "http",
+                    };
+                    let (host, port) = parse_server_address(&state.server_address);
+                    let api_address = format_api_address(scheme, &host, port);
 
                     // 使用剪贴板 API 复制地址
                     #[cfg(target_os = "macos")]
@@ -49,8 +49,11 @@ impl HeartbeatManager {
             .store(Self::current_timestamp(), Ordering::Relaxed);
     }
 
-    /// 检查是否超时
+    /// 检查是否超时（系统刚唤醒时不判定超时，等待客户端重新发送心跳）
     pub fn is_timed_out(&self) -> bool {
+        if crate::power::resumed_within(self.timeout) {
+            return false;
+        }
         let last = self.last_heartbeat.load(Ordering::Relaxed);
         let now = Self::current_timestamp();
         let elapsed = Duration::from_millis(now.saturating_sub(last));
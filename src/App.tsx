@@ -19,7 +19,7 @@ import { ConfigManagementPage } from "./components/config/ConfigManagementPage";
 import { FlowMonitorPage } from "./pages";
 import { ToolsPage } from "./components/tools/ToolsPage";
 import { BrowserInterceptorTool } from "./components/tools/browser-interceptor/BrowserInterceptorTool";
-import { AgentChatPage } from "./components/agent";
+import { AgentChatPage, QuickAskDialog } from "./components/agent";
 import { PluginUIRenderer } from "./components/plugins/PluginUIRenderer";
 import { PluginsPage } from "./components/plugins/PluginsPage";
 import { Toaster } from "./components/ui/sonner";
@@ -181,6 +181,7 @@ function App() {
     <AppContainer>
       <AppSidebar currentPage={currentPage} onNavigate={setCurrentPage} />
       <MainContent>{renderPage()}</MainContent>
+      <QuickAskDialog />
       <Toaster />
     </AppContainer>
   );
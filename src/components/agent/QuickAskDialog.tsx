@@ -0,0 +1,104 @@
+/**
+ * 快速提问弹窗
+ *
+ * 监听全局快捷键推送的 `quick-ask-open` 事件，弹出输入框单轮作答
+ */
+
+import React, { useCallback, useEffect, useRef, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { toast } from "sonner";
+import { Loader2, Zap } from "lucide-react";
+import { Modal, ModalBody, ModalFooter, ModalHeader } from "@/components/Modal";
+import { Button } from "@/components/ui/button";
+import { Textarea } from "@/components/ui/textarea";
+import {
+  QUICK_ASK_EVENT,
+  quickAsk,
+  type QuickAskResponse,
+} from "@/lib/api/agent";
+
+export const QuickAskDialog: React.FC = () => {
+  const [open, setOpen] = useState(false);
+  const [prompt, setPrompt] = useState("");
+  const [loading, setLoading] = useState(false);
+  const [answer, setAnswer] = useState<QuickAskResponse | null>(null);
+  const inputRef = useRef<HTMLTextAreaElement>(null);
+
+  useEffect(() => {
+    const unlisten = listen(QUICK_ASK_EVENT, () => {
+      setOpen(true);
+      // 等弹窗渲染后再聚焦输入框
+      setTimeout(() => inputRef.current?.focus(), 0);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  const handleClose = useCallback(() => setOpen(false), []);
+
+  const handleSubmit = useCallback(async () => {
+    const text = prompt.trim();
+    if (!text || loading) return;
+    setLoading(true);
+    try {
+      setAnswer(await quickAsk(text));
+      setPrompt("");
+    } catch (e) {
+      toast.error(`快速提问失败: ${e}`);
+    } finally {
+      setLoading(false);
+    }
+  }, [prompt, loading]);
+
+  const handleKeyDown = (e: React.KeyboardEvent<HTMLTextAreaElement>) => {
+    if (e.key === "Enter" && !e.shiftKey && !e.nativeEvent.isComposing) {
+      e.preventDefault();
+      handleSubmit();
+    }
+  };
+
+  return (
+    <Modal isOpen={open} onClose={handleClose} maxWidth="max-w-2xl">
+      <ModalHeader>
+        <span className="flex items-center gap-2">
+          <Zap className="h-4 w-4" />
+          快速提问
+        </span>
+      </ModalHeader>
+      <ModalBody className="flex flex-col gap-3">
+        <Textarea
+          ref={inputRef}
+          value={prompt}
+          onChange={(e) => setPrompt(e.target.value)}
+          onKeyDown={handleKeyDown}
+          placeholder="输入问题，Enter 发送，Shift+Enter 换行"
+          disabled={loading}
+          rows={3}
+        />
+        {answer && (
+          <div className="flex flex-col gap-1">
+            <pre className="max-h-80 overflow-auto whitespace-pre-wrap rounded bg-muted p-3 text-sm">
+              {answer.content}
+            </pre>
+            <div className="text-xs text-muted-foreground">
+              耗时 {answer.duration_ms} ms
+              {answer.scratch_session_id &&
+                `，已追加到草稿会话 ${answer.scratch_session_id}`}
+            </div>
+          </div>
+        )}
+      </ModalBody>
+      <ModalFooter>
+        <Button
+          size="sm"
+          disabled={loading || !prompt.trim()}
+          onClick={handleSubmit}
+        >
+          {loading && <Loader2 className="mr-1 h-4 w-4 animate-spin" />}
+          提问
+        </Button>
+      </ModalFooter>
+    </Modal>
+  );
+};
@@ -0,0 +1,241 @@
+/**
+ * 待审核请求提示
+ *
+ * 显示 Agent 提出、等待用户批准的请求（approve 权限模式下的工具调用、
+ * Skill 脚本运行、git_apply_patch 的补丁），通过后端推送的状态变化事件实时更新
+ */
+
+import React, { useCallback, useEffect, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { toast } from "sonner";
+import { ChevronRight, ShieldQuestion } from "lucide-react";
+import { cn } from "@/lib/utils";
+import { Button } from "@/components/ui/button";
+import {
+  PATCH_PROPOSAL_EVENT,
+  SCRIPT_REQUEST_EVENT,
+  TOOL_APPROVAL_EVENT,
+  listPatchProposals,
+  listScriptRequests,
+  listToolApprovals,
+  reviewPatchProposal,
+  reviewScriptRequest,
+  reviewToolApproval,
+  type PatchProposal,
+  type ScriptRunRequest,
+  type ToolApprovalRequest,
+} from "@/lib/api/agent";
+
+/** 待审核项 */
+interface ApprovalItem {
+  key: string;
+  /** 请求类型（如“补丁”） */
+  kind: string;
+  title: string;
+  /** 展开后显示的详情（等宽字体） */
+  detail?: string;
+  createdAt: string;
+  review: (approved: boolean) => Promise<unknown>;
+}
+
+/** 按 id 合并状态变化，只保留待批准的请求 */
+function upsertPending<T extends { id: string; status: string }>(
+  list: T[],
+  item: T,
+): T[] {
+  const rest = list.filter((existing) => existing.id !== item.id);
+  return item.status === "pending_approval" ? [...rest, item] : rest;
+}
+
+/** 待批准的补丁 */
+function usePendingPatches(): ApprovalItem[] {
+  const [patches, setPatches] = useState<PatchProposal[]>([]);
+
+  useEffect(() => {
+    let disposed = false;
+    const unlisten = listen<PatchProposal>(PATCH_PROPOSAL_EVENT, (event) => {
+      setPatches((prev) => upsertPending(prev, event.payload));
+    });
+    listPatchProposals()
+      .then((list) => {
+        if (!disposed) {
+          setPatches(list.filter((p) => p.status === "pending_approval"));
+        }
+      })
+      .catch((e) => console.error("[PendingApprovals] 加载补丁失败:", e));
+    return () => {
+      disposed = true;
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  return patches.map((patch) => ({
+    key: `patch:${patch.id}`,
+    kind: "补丁",
+    title: patch.description || patch.files.join(", "),
+    detail: `${patch.repo_path}\n\n${patch.patch}`,
+    createdAt: patch.created_at,
+    review: async (approved) => {
+      const result = await reviewPatchProposal(patch.id, approved);
+      if (result.status === "failed") {
+        toast.error(`补丁应用失败: ${result.error ?? "未知错误"}`);
+      } else if (result.status === "applied") {
+        toast.success("补丁已应用");
+      }
+    },
+  }));
+}
+
+/** 待批准的工具调用（approve 权限模式） */
+function usePendingToolCalls(): ApprovalItem[] {
+  const [requests, setRequests] = useState<ToolApprovalRequest[]>([]);
+
+  useEffect(() => {
+    let disposed = false;
+    const unlisten = listen<ToolApprovalRequest>(
+      TOOL_APPROVAL_EVENT,
+      (event) => {
+        setRequests((prev) => upsertPending(prev, event.payload));
+      },
+    );
+    listToolApprovals()
+      .then((list) => {
+        if (!disposed) {
+          setRequests(list.filter((r) => r.status === "pending_approval"));
+        }
+      })
+      .catch((e) => console.error("[PendingApprovals] 加载工具调用失败:", e));
+    return () => {
+      disposed = true;
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  return requests.map((request) => ({
+    key: `tool:${request.id}`,
+    kind: "工具调用",
+    title: request.tool_name,
+    detail: JSON.stringify(request.arguments, null, 2),
+    createdAt: request.created_at,
+    review: (approved) => reviewToolApproval(request.id, approved),
+  }));
+}
+
+/** 待批准的 Skill 脚本运行 */
+function usePendingScripts(): ApprovalItem[] {
+  const [requests, setRequests] = useState<ScriptRunRequest[]>([]);
+
+  useEffect(() => {
+    let disposed = false;
+    const unlisten = listen<ScriptRunRequest>(SCRIPT_REQUEST_EVENT, (event) => {
+      setRequests((prev) => upsertPending(prev, event.payload));
+    });
+    listScriptRequests()
+      .then((list) => {
+        if (!disposed) {
+          setRequests(list.filter((r) => r.status === "pending_approval"));
+        }
+      })
+      .catch((e) => console.error("[PendingApprovals] 加载脚本请求失败:", e));
+    return () => {
+      disposed = true;
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  return requests.map((request) => ({
+    key: `script:${request.id}`,
+    kind: "脚本",
+    title: `${request.skill}/${request.script}`,
+    detail: [request.script, ...request.args].join(" "),
+    createdAt: request.created_at,
+    review: (approved) => reviewScriptRequest(request.id, approved),
+  }));
+}
+
+const ApprovalRow: React.FC<{ item: ApprovalItem }> = ({ item }) => {
+  const [expanded, setExpanded] = useState(false);
+  const [busy, setBusy] = useState(false);
+
+  const handleReview = useCallback(
+    async (approved: boolean) => {
+      setBusy(true);
+      try {
+        await item.review(approved);
+      } catch (e) {
+        toast.error(`审核失败: ${e}`);
+      } finally {
+        setBusy(false);
+      }
+    },
+    [item],
+  );
+
+  return (
+    <div className="rounded-md border border-amber-300 bg-amber-50/60 px-3 py-2 text-sm dark:border-amber-700 dark:bg-amber-950/30">
+      <div className="flex items-center gap-2">
+        <button
+          type="button"
+          className="flex min-w-0 flex-1 items-center gap-1 text-left"
+          onClick={() => setExpanded(!expanded)}
+          disabled={!item.detail}
+        >
+          <ChevronRight
+            className={cn(
+              "h-4 w-4 shrink-0 transition-transform",
+              expanded && "rotate-90",
+              !item.detail && "invisible",
+            )}
+          />
+          <span className="shrink-0 font-medium">{item.kind}</span>
+          <span className="truncate text-muted-foreground">{item.title}</span>
+        </button>
+        <Button
+          size="sm"
+          variant="outline"
+          disabled={busy}
+          onClick={() => handleReview(false)}
+        >
+          拒绝
+        </Button>
+        <Button size="sm" disabled={busy} onClick={() => handleReview(true)}>
+          批准
+        </Button>
+      </div>
+      {expanded && item.detail && (
+        <pre className="mt-2 max-h-64 overflow-auto whitespace-pre-wrap rounded bg-muted p-2 font-mono text-xs">
+          {item.detail}
+        </pre>
+      )}
+    </div>
+  );
+};
+
+/**
+ * 待审核请求列表（没有待审核请求时不渲染）
+ */
+export const PendingApprovals: React.FC<{ className?: string }> = ({
+  className,
+}) => {
+  const toolCalls = usePendingToolCalls();
+  const scripts = usePendingScripts();
+  const patches = usePendingPatches();
+  const items = [...toolCalls, ...scripts, ...patches].sort((a, b) =>
+    a.createdAt.localeCompare(b.createdAt),
+  );
+  if (items.length === 0) {
+    return null;
+  }
+
+  return (
+    <div className={cn("flex flex-col gap-2 px-4 pb-2", className)}>
+      <div className="flex items-center gap-1 text-xs text-muted-foreground">
+        <ShieldQuestion className="h-3.5 w-3.5" />
+        {items.length} 个请求等待批准
+      </div>
+      {items.map((item) => (
+        <ApprovalRow key={item.key} item={item} />
+      ))}
+    </div>
+  );
+};
@@ -1,18 +1,18 @@
 import { useState, useEffect } from "react";
 import { toast } from "sonner";
-import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import {
   startAgentProcess,
   stopAgentProcess,
   getAgentProcessStatus,
   createAgentSession,
   sendAgentMessageStream,
+  formatAgentError,
   listAgentSessions,
   deleteAgentSession,
   parseStreamEvent,
   type AgentProcessStatus,
   type SessionInfo,
-  type StreamEvent,
+  type TaggedStreamEvent,
 } from "@/lib/api/agent";
 import { Message, MessageImage, ContentPart, PROVIDER_CONFIG } from "../types";
 
@@ -242,7 +242,8 @@ export function useAgentChat() {
 
     // 用于累积流式内容
     let accumulatedContent = "";
-    let unlisten: UnlistenFn | null = null;
+    // 停止处理本次请求的后续事件
+    let unlisten: (() => void) | null = null;
 
     /**
      * 辅助函数：更新 contentParts，支持交错显示
@@ -277,18 +278,20 @@ export function useAgentChat() {
         throw new Error("无法创建或获取会话");
       }
 
-      // 3. 创建唯一事件名称
-      const eventName = `agent_stream_${assistantMsgId}`;
-
-      // 4. 设置事件监听器（流式接收）
-      console.log(
-        `[AgentChat] 设置事件监听器: ${eventName}, sessionId: ${activeSessionId}`,
-      );
-      unlisten = await listen<StreamEvent>(eventName, (event) => {
-        console.log("[AgentChat] 收到事件:", eventName, event.payload);
-        const data = parseStreamEvent(event.payload);
+      // 3. 流式事件处理（通过本次请求的 Channel 接收）
+      console.log(`[AgentChat] 发送流式请求, sessionId: ${activeSessionId}`);
+      let listening = true;
+      unlisten = () => {
+        listening = false;
+      };
+      const onEvent = (payload: TaggedStreamEvent) => {
+        if (!listening) {
+          return;
+        }
+        console.log("[AgentChat] 收到事件:", payload.stream_id, payload);
+        const data = parseStreamEvent(payload);
         if (!data) {
-          console.warn("[AgentChat] 解析事件失败:", event.payload);
+          console.warn("[AgentChat] 解析事件失败:", payload);
           return;
         }
         console.log("[AgentChat] 解析后数据:", data);
@@ -355,6 +358,20 @@ export function useAgentChat() {
             }
             break;
 
+          case "model_drift":
+            // 模型快照变化，只提醒不中断
+            toast.warning(
+              `模型快照已变化: ${data.from} → ${data.to}（会话固定: ${data.pinned}）`,
+            );
+            break;
+
+          case "context_fallback":
+            // 提示词超过上下文窗口，本次请求改用更大上下文的模型
+            toast.info(
+              `上下文约 ${data.estimated_tokens} tokens，超过 ${data.from} 的窗口，本次改用 ${data.to}`,
+            );
+            break;
+
           case "error":
             // 错误处理
             toast.error(`响应错误: ${data.message}`);
@@ -458,9 +475,9 @@ export function useAgentChat() {
             break;
           }
         }
-      });
+      };
 
-      // 5. 发送流式请求（传递 sessionId 以保持上下文）
+      // 4. 发送流式请求（传递 sessionId 以保持上下文）
       const imagesToSend =
         images.length > 0
           ? images.map((img) => ({ data: img.data, media_type: img.mediaType }))
@@ -468,13 +485,13 @@ export function useAgentChat() {
 
       await sendAgentMessageStream(
         content,
-        eventName,
+        onEvent,
         activeSessionId, // 传递 sessionId 以保持上下文
         model || undefined,
         imagesToSend,
       );
     } catch (error) {
-      toast.error(`发送失败: ${error}`);
+      toast.error(`发送失败: ${formatAgentError(error)}`);
       // Remove the optimistic assistant message on failure
       setMessages((prev) => prev.filter((msg) => msg.id !== assistantMsgId));
       setIsSending(false);
@@ -13,6 +13,7 @@ import { ChatSettings } from "./components/ChatSettings";
 import { MessageList } from "./components/MessageList";
 import { Inputbar } from "./components/Inputbar";
 import { EmptyState } from "./components/EmptyState";
+import { PendingApprovals } from "./components/PendingApprovals";
 import type { MessageImage } from "./types";
 
 const PageContainer = styled.div`
@@ -141,6 +142,8 @@ export function AgentChatPage({
             />
           )}
 
+          <PendingApprovals />
+
           {hasMessages && (
             <Inputbar
               input={input}
@@ -1,2 +1,3 @@
 export { AgentChatPage } from "./AgentChatPage";
 export { AgentSkillsPanel } from "./AgentSkillsPanel";
+export { QuickAskDialog } from "./QuickAskDialog";
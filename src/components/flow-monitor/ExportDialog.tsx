@@ -115,6 +115,7 @@ export function ExportDialog({
   const [includeRaw, setIncludeRaw] = useState(true);
   const [includeStreamChunks, setIncludeStreamChunks] = useState(false);
   const [redactSensitive, setRedactSensitive] = useState(false);
+  const [includeProvenance, setIncludeProvenance] = useState(false);
   const [redactionRules, setRedactionRules] = useState<RedactionRule[]>(
     DEFAULT_REDACTION_RULES,
   );
@@ -140,6 +141,7 @@ export function ExportDialog({
         redaction_rules: redactSensitive
           ? redactionRules.filter((r) => r.enabled)
           : undefined,
+        include_provenance: includeProvenance,
       };
 
       let result;
@@ -176,6 +178,7 @@ export function ExportDialog({
     includeStreamChunks,
     redactSensitive,
     redactionRules,
+    includeProvenance,
     flowIds,
     filter,
     onClose,
@@ -260,6 +263,12 @@ export function ExportDialog({
                 label="包含流式 Chunks"
                 description="导出流式响应的原始 chunks（文件会更大）"
               />
+              <OptionCheckbox
+                checked={includeProvenance}
+                onChange={setIncludeProvenance}
+                label="嵌入来源信息"
+                description="在 Markdown/HAR 中写入模型、提供商、时间戳和 ProxyCast 版本，便于追溯 AI 生成内容"
+              />
             </div>
           </div>
 
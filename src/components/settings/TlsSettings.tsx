@@ -89,10 +89,10 @@ export function TlsSettings() {
     );
   }
 
-  const tlsSupported = false;
   const tls = config.server.tls;
-  const isConfigValid = !tls.enable || (tls.cert_path && tls.key_path);
-  const tlsUnsupportedEnabled = tls.enable && !tlsSupported;
+  // 证书和私钥需同时配置，或都留空使用自签名证书
+  const isConfigValid = !tls.enable || !tls.cert_path === !tls.key_path;
+  const useSelfSigned = tls.enable && !tls.cert_path && !tls.key_path;
 
   return (
     <div className="space-y-4">
@@ -123,16 +123,6 @@ export function TlsSettings() {
       )}
 
       <div className="p-4 rounded-lg border space-y-4">
-        {!tlsSupported && (
-          <div className="flex items-start gap-2 rounded-lg bg-yellow-50 dark:bg-yellow-900/20 p-3 text-sm text-yellow-700 dark:text-yellow-400">
-            <AlertTriangle className="h-4 w-4 shrink-0 mt-0.5" />
-            <span>
-              当前版本暂不支持 TLS。启用后服务将无法启动，请使用反向代理或 TLS
-              终止。
-            </span>
-          </div>
-        )}
-
         {/* 启用开关 */}
         <label className="flex items-center justify-between p-3 rounded-lg border cursor-pointer hover:bg-muted/50">
           <div>
@@ -144,25 +134,15 @@ export function TlsSettings() {
           <input
             type="checkbox"
             checked={tls.enable}
-            onChange={(e) => {
-              if (!tlsSupported && e.target.checked) {
-                return;
-              }
-              updateTls({ enable: e.target.checked });
-            }}
-            disabled={!tlsSupported && !tls.enable}
+            onChange={(e) => updateTls({ enable: e.target.checked })}
             className="w-4 h-4 rounded border-gray-300"
           />
         </label>
 
         {/* 证书路径 */}
-        <div
-          className={
-            tls.enable && tlsSupported ? "" : "opacity-50 pointer-events-none"
-          }
-        >
+        <div className={tls.enable ? "" : "opacity-50 pointer-events-none"}>
           <label className="block text-sm font-medium mb-1.5">
-            证书文件路径 {tls.enable && <span className="text-red-500">*</span>}
+            证书文件路径
           </label>
           <div className="flex gap-2">
             <input
@@ -182,18 +162,14 @@ export function TlsSettings() {
             </button>
           </div>
           <p className="text-xs text-muted-foreground mt-1">
-            PEM 格式的 SSL/TLS 证书文件
+            PEM 格式的 SSL/TLS 证书文件，留空则使用自签名证书
           </p>
         </div>
 
         {/* 私钥路径 */}
-        <div
-          className={
-            tls.enable && tlsSupported ? "" : "opacity-50 pointer-events-none"
-          }
-        >
+        <div className={tls.enable ? "" : "opacity-50 pointer-events-none"}>
           <label className="block text-sm font-medium mb-1.5">
-            私钥文件路径 {tls.enable && <span className="text-red-500">*</span>}
+            私钥文件路径
           </label>
           <div className="flex gap-2">
             <input
@@ -218,18 +194,24 @@ export function TlsSettings() {
         </div>
 
         {/* 警告提示 */}
-        {tls.enable && !isConfigValid && (
+        {!isConfigValid && (
           <div className="flex items-start gap-2 rounded-lg bg-yellow-50 dark:bg-yellow-900/20 p-3 text-sm text-yellow-700 dark:text-yellow-400">
             <AlertTriangle className="h-4 w-4 shrink-0 mt-0.5" />
-            <span>启用 TLS 需要同时配置证书和私钥文件路径</span>
+            <span>证书和私钥文件路径需要同时配置，或都留空使用自签名证书</span>
+          </div>
+        )}
+        {useSelfSigned && (
+          <div className="flex items-start gap-2 rounded-lg bg-blue-50 dark:bg-blue-900/20 p-3 text-sm text-blue-700 dark:text-blue-400">
+            <Shield className="h-4 w-4 shrink-0 mt-0.5" />
+            <span>
+              将使用自签名证书（首次启动时生成于 ~/.proxycast/tls/），客户端需信任该证书或关闭证书校验
+            </span>
           </div>
         )}
 
         <button
           onClick={handleSave}
-          disabled={
-            saving || tlsUnsupportedEnabled || (tls.enable && !isConfigValid)
-          }
+          disabled={saving || !isConfigValid}
           className="w-full px-4 py-2 rounded-lg bg-primary text-primary-foreground text-sm font-medium hover:bg-primary/90 disabled:opacity-50"
         >
           {saving ? "保存中..." : "保存 TLS 设置"}
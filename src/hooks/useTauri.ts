@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
+import type { TimeRangeParam } from "@/lib/api/telemetry";
 
 export interface ServerStatus {
   running: boolean;
@@ -15,6 +16,111 @@ export interface TlsConfig {
   key_path: string | null;
 }
 
+// Rate Limit Configuration（按客户端 API Key 限流，0 表示不限制）
+export interface RateLimitConfig {
+  enabled: boolean;
+  requests_per_minute: number;
+  tokens_per_minute: number;
+}
+
+// Token 预算：按客户端 Key / Provider 限制每日或每月的 Token 用量或费用（0 表示不限制）
+export type BudgetPeriod = "daily" | "monthly";
+
+export interface TokenBudgetLimit {
+  name: string;
+  /** 客户端 Key ID，为空表示所有请求共用 */
+  client_key?: string;
+  /** Provider，为空表示所有 Provider 共用 */
+  provider?: string;
+  period: BudgetPeriod;
+  max_tokens: number;
+  max_cost_usd: number;
+}
+
+/** 模型单价（美元 / 百万 Token），model 支持 `*` 后缀通配 */
+export interface ModelPrice {
+  model: string;
+  input_per_million: number;
+  output_per_million: number;
+}
+
+export interface TokenBudgetConfig {
+  enabled: boolean;
+  limits?: TokenBudgetLimit[];
+  prices?: ModelPrice[];
+}
+
+// Provider 并发限制：每个 Provider 独立计数，名额用完时排队，超时返回 503
+export interface ProviderConcurrencyConfig {
+  enabled: boolean;
+  /** 未单独配置的 Provider 的最大并发数（0 表示不限制） */
+  default_limit: number;
+  /** 按 Provider 类型单独配置的最大并发数 */
+  limits?: Record<string, number>;
+  queue_timeout_secs: number;
+}
+
+// HMAC 请求签名：局域网客户端用共享密钥对请求签名
+export interface RequestSigningConfig {
+  enabled: boolean;
+  secret: string;
+  /** 允许的时间戳偏差（秒） */
+  max_skew_secs: number;
+  /** 本机请求是否也要求签名 */
+  require_for_loopback: boolean;
+}
+
+// 浏览器跨域访问（CORS）：默认只允许 localhost / 127.0.0.1 页面
+export interface CorsConfig {
+  enabled: boolean;
+  /** 允许的来源，`http://host:*` 匹配任意端口，`*` 匹配所有来源 */
+  allowed_origins: string[];
+  allowed_methods: string[];
+  /** 允许的请求头，`*` 表示允许所有请求头 */
+  allowed_headers: string[];
+  max_age_secs: number;
+}
+
+// 响应缓存：TTL 内相同的非流式请求直接返回缓存的响应
+export interface ResponseCacheConfig {
+  enabled: boolean;
+  ttl_secs: number;
+  max_entries: number;
+}
+
+// 请求中间件：转发上游前按顺序执行，可改写请求头、注入系统提示词、删除字段或拦截请求
+export type RequestMiddlewareAction =
+  | { type: "set_header"; header: string; value: string }
+  | { type: "remove_header"; header: string }
+  | {
+      type: "inject_system_prompt";
+      prompt: string;
+      position?: "prepend" | "append";
+    }
+  | { type: "strip_fields"; fields: string[] }
+  | { type: "block"; status?: number; message?: string };
+
+export type RequestMiddlewareConfig = RequestMiddlewareAction & {
+  name?: string;
+  enabled?: boolean;
+  /** 只作用于这些路由前缀，为空表示不限制 */
+  paths?: string[];
+  /** 只作用于这些模型，支持 `*` 后缀通配 */
+  models?: string[];
+};
+
+// 客户端 API Key（allowed_models 支持 `*` 后缀通配，空数组表示不限制）
+export interface ClientApiKey {
+  id: string;
+  name: string;
+  key: string;
+  allowed_models?: string[];
+  /** 允许访问的路由前缀 */
+  allowed_routes?: string[];
+  created_at: string;
+  revoked: boolean;
+}
+
 // Remote Management Configuration
 export interface RemoteManagementConfig {
   allow_remote: boolean;
@@ -114,6 +220,16 @@ export interface Config {
     port: number;
     api_key: string;
     tls: TlsConfig;
+    rate_limit?: RateLimitConfig;
+    client_keys?: ClientApiKey[];
+    response_cache?: ResponseCacheConfig;
+    request_middlewares?: RequestMiddlewareConfig[];
+    /** 允许访问的客户端 IP 或 CIDR，监听非本机地址时必填 */
+    allowed_ips?: string[];
+    token_budget?: TokenBudgetConfig;
+    provider_concurrency?: ProviderConcurrencyConfig;
+    request_signing?: RequestSigningConfig;
+    cors?: CorsConfig;
   };
   providers: {
     kiro: {
@@ -148,6 +264,318 @@ export interface Config {
   proxy_url: string | null;
   /** 关闭时最小化到托盘（而不是退出应用） */
   minimize_to_tray: boolean;
+  /** 后台任务模型（摘要、标题、建议、记忆提取） */
+  background_model?: BackgroundModelConfig;
+  image_processing?: ImageProcessingConfig;
+  /** Agent 备用端点（按顺序故障转移） */
+  agent_fallbacks?: AgentFallbackEndpoint[];
+  /** 凭证池轮换与冷却配置 */
+  key_rotation?: KeyRotationConfig;
+  /** Agent 会话配额 */
+  session_quota?: SessionQuotaConfig;
+  /** Agent 会话记录 */
+  transcripts?: TranscriptConfig;
+  /** Agent 上下文超限时的模型回退 */
+  context_fallback?: ContextFallbackConfig;
+  /** Agent 会话同步（WebDAV / S3） */
+  session_sync?: SessionSyncConfig;
+  /** 流式文本合并（减少推送到前端的事件数） */
+  stream_coalesce?: StreamCoalesceConfig;
+  /** 快速提问（修改快捷键后需重启） */
+  quick_ask?: QuickAskConfig;
+  /** OpenTelemetry 链路追踪导出（修改后需重启） */
+  otlp?: OtlpConfig;
+  /** 系统休眠/唤醒处理 */
+  sleep_resume?: SleepResumeConfig;
+  /** Provider 配置档案 */
+  provider_profiles?: ProviderProfilesConfig;
+  /** 系统钥匙串：启用后 API Key 保存在钥匙串中，配置文件只保存引用 */
+  keychain?: KeychainConfig;
+  /** Agent 定时任务（按 cron 表达式在后台执行） */
+  scheduled_tasks?: ScheduledTaskConfig[];
+  /** 用户自定义的命令工具（Agent 自动注册） */
+  custom_tools?: CustomToolConfig[];
+  /** WASM 工具插件 */
+  wasm_plugins?: WasmPluginsConfig;
+  /** OCR 工具（本地 tesseract） */
+  ocr?: OcrConfig;
+  /** 代码解释器工具（run_code） */
+  code_interpreter?: CodeInterpreterConfig;
+  /** SQL 连接档案（Agent 只读查询工具） */
+  sql_connections?: SqlConnectionConfig[];
+  /** 无头浏览器工具 */
+  browser?: BrowserToolConfig;
+  /** Agent 工具权限模式（会话可单独覆盖） */
+  permission_mode?: PermissionMode;
+  /** 将 ProxyCast 作为 MCP 服务端暴露给其他 MCP 客户端 */
+  mcp_host?: McpHostConfig;
+  /** 向量化与知识库检索 */
+  embeddings?: EmbeddingsConfig;
+}
+
+/**
+ * Agent 工具权限模式：auto 直接执行，approve 写入/执行类工具需批准，read-only 只允许只读工具
+ */
+export type PermissionMode = "auto" | "approve" | "read-only";
+
+export interface KeychainConfig {
+  enabled: boolean;
+}
+
+export interface ScheduledTaskConfig {
+  /** 任务名称（唯一） */
+  name: string;
+  /** 发送给 Agent 的提示词 */
+  prompt: string;
+  /** cron 表达式（分 时 日 月 周，本地时间），例如 "0 9 * * 1-5" */
+  cron: string;
+  /** 使用的 Provider 配置档案（为空时使用当前激活的档案） */
+  profile?: string | null;
+  /** 使用的模型 */
+  model?: string | null;
+  enabled: boolean;
+  /** 执行完成后发送系统通知 */
+  notify: boolean;
+}
+
+export interface CustomToolConfig {
+  /** 工具名称（唯一，只能包含字母、数字、_ 和 -） */
+  name: string;
+  /** 工具描述（供模型理解用途） */
+  description: string;
+  /** 参数 JSON Schema（type: object） */
+  parameters?: Record<string, unknown> | null;
+  /** 命令模板，参数通过 {{参数名}} 代入（自动转义） */
+  command: string;
+  /** 工作目录（为空时使用用户 home 目录） */
+  working_dir?: string | null;
+  /** 超时时间（秒） */
+  timeout_secs?: number | null;
+  enabled: boolean;
+}
+
+export interface WasmPluginsConfig {
+  enabled: boolean;
+  /** 插件目录（为空时使用 ~/.proxycast/plugins） */
+  dir?: string | null;
+  /** 按插件文件名（不含 .wasm）配置的权限 */
+  plugins?: Record<string, WasmPluginPermissions>;
+}
+
+export interface WasmPluginPermissions {
+  enabled: boolean;
+  /** 允许访问的目录 */
+  dirs?: { host: string; guest?: string | null; writable: boolean }[];
+  /** 传给插件的环境变量 */
+  env?: Record<string, string>;
+  /** 单次调用超时（秒），默认 30 */
+  timeout_secs?: number | null;
+  /** 内存上限（MB），默认 64 */
+  max_memory_mb?: number | null;
+}
+
+export interface McpHostConfig {
+  enabled: boolean;
+  /** 监听端口（仅监听 127.0.0.1），默认 8997 */
+  port: number;
+}
+
+export interface EmbeddingsConfig {
+  /** 向量化模型，默认 text-embedding-3-small */
+  model: string;
+}
+
+export interface ProviderProfilesConfig {
+  /** 默认使用的档案名称 */
+  active?: string | null;
+  profiles: ProviderProfile[];
+}
+
+export interface ProviderProfile {
+  name: string;
+  /** Provider 类型，决定使用的协议 */
+  provider: string;
+  /** API 基础地址（不含 /v1） */
+  base_url: string;
+  /** 导出时可排除 */
+  api_key: string;
+  default_model?: string | null;
+  temperature?: number | null;
+  max_tokens?: number | null;
+  top_p?: number | null;
+}
+
+export interface SleepResumeConfig {
+  /** 休眠期间到期的后续任务：立即执行一次 / 按休眠时长顺延 / 跳过 */
+  missed_task_policy: "run_once" | "shift" | "skip";
+}
+
+/** 系统唤醒事件（事件名 system-resume） */
+export interface SystemResumeEvent {
+  /** 估算的休眠时长（秒） */
+  slept_secs: number;
+  resumed_at: string;
+}
+
+export interface SessionQuotaConfig {
+  /** 最大会话数（0 表示不限制） */
+  max_sessions: number;
+  /** 所有会话保存的最大消息总数（0 表示不限制） */
+  max_messages: number;
+  /** 提醒阈值（百分比） */
+  warning_percent: number;
+}
+
+export interface OtlpConfig {
+  enabled: boolean;
+  /** OTLP/HTTP Collector 地址，如 http://localhost:4318 */
+  endpoint: string;
+  /** 上报的 service.name */
+  service_name: string;
+  /** 采样比例（0.0 - 1.0） */
+  sample_ratio: number;
+}
+
+export interface TranscriptConfig {
+  /** 是否将会话消息写入 ~/.proxycast/transcripts/<session_id>.jsonl */
+  enabled: boolean;
+}
+
+export interface ContextFallbackConfig {
+  enabled: boolean;
+  /** 候选模型（按优先级排列） */
+  models: string[];
+  /** 为模型输出预留的 token 数 */
+  reserve_output_tokens: number;
+  /** 自定义模型上下文窗口（模型名 -> token 数） */
+  context_windows: Record<string, number>;
+  /** 上游返回上下文超长错误时压缩历史并重试一次 */
+  compact_on_overflow?: boolean;
+}
+
+export interface QuickAskConfig {
+  enabled: boolean;
+  /** 全局快捷键（如 CommandOrControl+Shift+Space） */
+  shortcut: string;
+  /** 使用的模型（未设置时使用 Agent 默认模型） */
+  model?: string;
+  system_prompt: string;
+  max_tokens: number;
+  /** 是否把问答追加到草稿会话 */
+  scratch_session: boolean;
+  /** 草稿会话保留的最大消息数 */
+  scratch_max_messages: number;
+}
+
+export interface CodeInterpreterConfig {
+  enabled: boolean;
+  /** Python 解释器路径 */
+  python_path: string;
+  /** Node 可执行文件路径 */
+  node_path: string;
+  timeout_secs: number;
+  /** 内存上限（MB） */
+  memory_limit_mb: number;
+  /** 是否允许访问网络 */
+  allow_network: boolean;
+}
+
+export interface SqlConnectionConfig {
+  /** 连接名称（唯一） */
+  name: string;
+  driver: "sqlite" | "postgres" | "mysql";
+  /** SQLite 为数据库文件路径，Postgres / MySQL 为连接 URL */
+  url: string;
+  /** 数据库用途说明（供模型选择连接） */
+  description?: string;
+  /** 单次查询最多返回的行数 */
+  max_rows?: number;
+  timeout_secs?: number;
+  enabled?: boolean;
+}
+
+export interface BrowserToolConfig {
+  enabled: boolean;
+  /** Chrome / Chromium 可执行文件路径（为空时自动查找） */
+  executable?: string;
+  headless: boolean;
+  /** 单次操作超时（秒） */
+  timeout_secs: number;
+  /** 是否允许访问本机和内网地址（默认禁止） */
+  allow_private_network?: boolean;
+}
+
+export interface OcrConfig {
+  enabled: boolean;
+  /** tesseract 可执行文件路径 */
+  tesseract_path: string;
+  /** 识别语言（如 chi_sim+eng） */
+  languages: string;
+  timeout_secs: number;
+  /** 提供 ocr 工具时是否仍将图片发送给模型 */
+  send_images?: boolean;
+}
+
+export interface StreamCoalesceConfig {
+  enabled: boolean;
+  /** 最长缓冲时间（毫秒） */
+  flush_interval_ms: number;
+  /** 缓冲达到该字符数时立即推送 */
+  min_chunk_chars: number;
+}
+
+export interface SessionSyncConfig {
+  enabled: boolean;
+  backend: "webdav" | "s3";
+  /** WebDAV 目录 URL 或 S3 服务地址 */
+  endpoint: string;
+  /** S3 存储桶 */
+  bucket: string;
+  /** S3 区域 */
+  region: string;
+  /** WebDAV 用户名 / S3 Access Key ID */
+  username: string;
+  /** WebDAV 密码 / S3 Secret Access Key */
+  password: string;
+  /** 远端路径前缀 */
+  prefix: string;
+  /** 自动同步间隔（分钟，0 表示只手动同步） */
+  interval_minutes: number;
+}
+
+export interface KeyRotationConfig {
+  strategy: "weighted" | "round_robin" | "least_recently_used";
+  /** 限流（429）冷却时间（秒） */
+  rate_limit_cooldown_secs: number;
+  /** 认证失败（401/403）冷却时间（秒） */
+  auth_cooldown_secs: number;
+}
+
+export interface BackgroundModelConfig {
+  /** 模型名称（为空时使用对话模型） */
+  model?: string | null;
+  max_tokens: number;
+  temperature?: number | null;
+}
+
+export interface ImageProcessingConfig {
+  enabled: boolean;
+  /** 最长边上限（像素） */
+  max_dimension: number;
+  format: "jpeg" | "webp";
+  /** JPEG 质量（1-100） */
+  quality: number;
+}
+
+export interface AgentFallbackEndpoint {
+  name: string;
+  /** API 基础地址（不含 /v1） */
+  base_url: string;
+  api_key: string;
+  /** Provider 类型，决定流式请求使用的协议 */
+  provider: string;
+  /** 覆盖模型名称 */
+  model?: string | null;
 }
 
 export interface LogEntry {
@@ -156,6 +584,30 @@ export interface LogEntry {
   message: string;
 }
 
+/** 实时日志推送事件名 */
+export const LOG_LINE_EVENT = "log_line";
+
+/**
+ * 实时后端日志（logs_tail 返回，事件 log_line 推送）
+ */
+export interface LogLine {
+  /** 递增序号，可用于去重 */
+  seq: number;
+  timestamp: string;
+  /** ERROR / WARN / INFO / DEBUG / TRACE */
+  level: string;
+  /** 模块路径，如 proxycast_lib::agent::native_agent */
+  target: string;
+  message: string;
+}
+
+export interface LogFilter {
+  /** 最低级别，如 "warn" 只返回 WARN 和 ERROR */
+  level?: string;
+  /** 模块前缀，如 "agent"、"server"、"agent::tools" */
+  module?: string;
+}
+
 export async function startServer(): Promise<string> {
   return invoke("start_server");
 }
@@ -168,6 +620,266 @@ export async function getServerStatus(): Promise<ServerStatus> {
   return invoke("get_server_status");
 }
 
+export interface ClientKeyUsage {
+  requests: number;
+  /** 被拒绝的请求数（已吊销或超出范围） */
+  rejected: number;
+  last_used_at?: string | null;
+}
+
+export interface ClientKeyInfo {
+  id: string;
+  name: string;
+  /** 掩码后的 Key */
+  key_preview: string;
+  allowed_models: string[];
+  allowed_routes: string[];
+  created_at: string;
+  revoked: boolean;
+  /** 本次运行期间的使用统计 */
+  usage: ClientKeyUsage;
+}
+
+export async function listClientKeys(): Promise<ClientKeyInfo[]> {
+  return invoke("list_client_keys");
+}
+
+/**
+ * 创建客户端 API Key（返回值包含完整 Key，仅此一次）
+ */
+export async function createClientKey(
+  name: string,
+  allowedModels?: string[],
+  allowedRoutes?: string[],
+): Promise<ClientApiKey> {
+  return invoke("create_client_key", { name, allowedModels, allowedRoutes });
+}
+
+export async function revokeClientKey(id: string): Promise<boolean> {
+  return invoke("revoke_client_key", { id });
+}
+
+export interface ResponseCacheStats {
+  enabled: boolean;
+  entries: number;
+  hits: number;
+  misses: number;
+  /** 命中率（0-1） */
+  hit_rate: number;
+  ttl_secs: number;
+  max_entries: number;
+}
+
+export async function getResponseCacheStats(): Promise<ResponseCacheStats> {
+  return invoke("get_response_cache_stats");
+}
+
+/**
+ * 清空响应缓存，返回清除的条目数
+ */
+export async function clearResponseCache(): Promise<number> {
+  return invoke("clear_response_cache");
+}
+
+export interface TokenBudgetStatus {
+  name: string;
+  client_key: string | null;
+  provider: string | null;
+  period: BudgetPeriod;
+  /** 当前周期起始日期（YYYY-MM-DD） */
+  period_start: string;
+  used_tokens: number;
+  max_tokens: number;
+  /** 不限制 Token 时为 null */
+  remaining_tokens: number | null;
+  used_cost_usd: number;
+  max_cost_usd: number;
+  /** 不限制费用时为 null */
+  remaining_cost_usd: number | null;
+  exhausted: boolean;
+}
+
+/**
+ * 获取所有 Token 预算在当前周期的用量和剩余额度
+ */
+export async function getTokenBudgetStatus(): Promise<TokenBudgetStatus[]> {
+  return invoke("get_token_budget_status");
+}
+
+/** 用量来源 */
+export type UsageSource = "native_agent" | "goose_agent" | "proxy";
+
+/**
+ * 统一用量记录（Agent 对话和 API Server 转发的请求）
+ */
+export interface UsageEvent {
+  id: string;
+  timestamp: string;
+  source: UsageSource;
+  provider?: string;
+  provider_type?: string;
+  /** API Server 客户端 Key ID */
+  client_key?: string;
+  model: string;
+  input_tokens: number;
+  output_tokens: number;
+  /** 按 token_budget.prices 单价计算，未配置单价时为 0 */
+  cost_usd: number;
+  latency_ms?: number;
+  session_id?: string;
+  request_id?: string;
+}
+
+export interface UsageQuery {
+  source?: UsageSource;
+  model?: string;
+  session_id?: string;
+  /** ISO 8601 */
+  start?: string;
+  end?: string;
+  /** 最多返回的记录数（仅 listUsageEvents） */
+  limit?: number;
+}
+
+export interface UsageTotals {
+  requests: number;
+  input_tokens: number;
+  output_tokens: number;
+  cost_usd: number;
+  avg_latency_ms?: number;
+}
+
+export interface UsageSummary {
+  total: UsageTotals;
+  by_source: Partial<Record<UsageSource, UsageTotals>>;
+  by_model: Record<string, UsageTotals>;
+}
+
+/**
+ * 查询用量明细（按时间倒序，保存在内存中，最多保留最近 10000 条）
+ */
+export async function listUsageEvents(query?: UsageQuery): Promise<UsageEvent[]> {
+  return invoke("list_usage_events", { query });
+}
+
+/**
+ * 按来源和模型汇总用量
+ */
+export async function getUsageSummary(query?: UsageQuery): Promise<UsageSummary> {
+  return invoke("get_usage_summary", { query });
+}
+
+export interface UpstreamProbe {
+  provider: string;
+  /** 探测的 URL */
+  endpoint: string;
+  credential_uuid: string;
+  credential_name: string | null;
+  status: "up" | "down";
+  latency_ms: number;
+  /** 请求失败时为 null */
+  http_status: number | null;
+  error: string | null;
+}
+
+export interface UpstreamHealthReport {
+  status: "healthy" | "degraded" | "unhealthy";
+  version: string;
+  checked_at: string;
+  upstreams: UpstreamProbe[];
+}
+
+/**
+ * 探测所有上游 Provider 的状态和延迟（与 /healthz?probe=true 相同）
+ */
+export async function probeUpstreamHealth(): Promise<UpstreamHealthReport> {
+  return invoke("probe_upstream_health");
+}
+
+// 压测负载类型（streaming 为短消息 + 流式响应）
+export type PayloadProfile = "small" | "medium" | "large" | "streaming";
+
+export interface LatencySummary {
+  min_ms: number;
+  mean_ms: number;
+  p50_ms: number;
+  p90_ms: number;
+  p99_ms: number;
+  max_ms: number;
+}
+
+export interface LoadTestReport {
+  requests: number;
+  concurrency: number;
+  payload_profile: PayloadProfile;
+  succeeded: number;
+  failed: number;
+  duration_ms: number;
+  requests_per_second: number;
+  /** 成功请求的延迟统计 */
+  latency: LatencySummary;
+  /** 失败原因示例 */
+  errors: string[];
+}
+
+/**
+ * 通过本地服务器向模拟上游发送合成流量，测试吞吐量和延迟
+ * （requests 最多 10000，concurrency 最多 256，服务器需已启动）
+ */
+export async function proxyLoadtest(
+  requests: number,
+  concurrency: number,
+  payloadProfile?: PayloadProfile,
+): Promise<LoadTestReport> {
+  return invoke("proxy_loadtest", { requests, concurrency, payloadProfile });
+}
+
+export interface BenchmarkEntry {
+  credential_uuid: string;
+  credential_name: string | null;
+  provider: string;
+  model: string;
+  iterations: number;
+  succeeded: number;
+  failed: number;
+  /** 错误率（0 - 1） */
+  error_rate: number;
+  /** 首 Token 延迟统计 */
+  ttft: LatencySummary;
+  /** 总延迟统计 */
+  latency: LatencySummary;
+  /** 平均输出速度（tokens/s） */
+  tokens_per_second: number;
+  errors: string[];
+}
+
+export interface BenchmarkReport {
+  prompt: string;
+  iterations: number;
+  started_at: string;
+  duration_ms: number;
+  /** 按平均首 Token 延迟升序排列 */
+  entries: BenchmarkEntry[];
+}
+
+/**
+ * 对已启用的 Provider 凭证运行延迟基准测试（经本地服务器转发，会消耗真实额度）
+ * （iterations 最多 20，请求总数最多 500，服务器需已启动）
+ */
+export async function providerBenchmark(
+  models: string[],
+  iterations: number,
+  prompt?: string,
+  credentialUuids?: string[],
+): Promise<BenchmarkReport> {
+  return invoke("provider_benchmark", {
+    models,
+    prompt,
+    iterations,
+    credentialUuids,
+  });
+}
+
 export async function getConfig(): Promise<Config> {
   return invoke("get_config");
 }
@@ -176,6 +888,20 @@ export async function saveConfig(config: Config): Promise<void> {
   return invoke("save_config", { config });
 }
 
+export interface ConfigReloadReport {
+  /** 服务器是否正在运行（未运行时在下次启动时生效） */
+  running: boolean;
+  /** 监听地址、端口或 TLS 已变更，需要重启服务器 */
+  restart_required: boolean;
+}
+
+/**
+ * 从配置文件重新加载配置并应用到运行中的服务器
+ */
+export async function reloadServerConfig(): Promise<ConfigReloadReport> {
+  return invoke("server_reload_config");
+}
+
 export async function getDefaultProvider(): Promise<string> {
   return invoke("get_default_provider");
 }
@@ -208,6 +934,35 @@ export async function clearLogs(): Promise<void> {
   }
 }
 
+/**
+ * 获取最近的后端日志（按时间升序），新日志通过 log_line 事件推送
+ */
+export async function logsTail(
+  filter?: LogFilter,
+  limit?: number,
+): Promise<LogLine[]> {
+  return invoke("logs_tail", { filter, limit });
+}
+
+export interface LogExportResult {
+  /** 导出的 zip 文件路径 */
+  path: string;
+  /** 打包的日志文件数 */
+  files: number;
+}
+
+/**
+ * 将时间范围内的日志文件打包为 zip（用于问题报告），未指定范围时打包全部日志
+ *
+ * 导出路径由用户在保存对话框中选择（不覆盖已有文件），取消时返回 null
+ * @param range 预设范围（"1h" / "24h" / "7d" / "30d"）或 ISO 8601 起止时间
+ */
+export async function logsExport(
+  range?: TimeRangeParam,
+): Promise<LogExportResult | null> {
+  return invoke("logs_export", { range });
+}
+
 export interface TestResult {
   success: boolean;
   status: number;
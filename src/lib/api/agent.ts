@@ -5,7 +5,8 @@
  * 支持流式输出和工具调用
  */
 
-import { invoke } from "@tauri-apps/api/core";
+import { Channel, invoke } from "@tauri-apps/api/core";
+import type { PermissionMode, ScheduledTaskConfig } from "@/hooks/useTauri";
 
 // ============================================================
 // 流式事件类型 (Requirements: 9.1, 9.2, 9.3)
@@ -33,6 +34,16 @@ export interface ToolExecutionResult {
   output: string;
   /** 错误信息（如果失败） */
   error?: string;
+  /** 超时或被取消时的中断原因 */
+  interrupted?: "timeout" | "cancelled";
+  /** 工具生成的文件（如 run_code 的输出文件） */
+  artifacts?: {
+    name: string;
+    media_type: string;
+    size: number;
+    /** 本地文件路径 */
+    path?: string;
+  }[];
 }
 
 /**
@@ -45,7 +56,28 @@ export type StreamEvent =
   | StreamEventToolEnd
   | StreamEventDone
   | StreamEventFinalDone
-  | StreamEventError;
+  | StreamEventError
+  | StreamEventModelDrift
+  | StreamEventContextFallback
+  | StreamEventContextUsage
+  | StreamEventContextCompacted
+  | StreamEventCancelled;
+
+/**
+ * 带流 ID 的流式事件（sendAgentMessageStream 的回调收到的事件）
+ */
+export type TaggedStreamEvent = StreamEvent & { stream_id: string };
+
+/**
+ * 进行中的流式对话
+ */
+export interface StreamInfo {
+  stream_id: string;
+  session_id?: string;
+  model?: string;
+  /** 开始时间 */
+  started_at: string;
+}
 
 /**
  * 文本增量事件
@@ -100,6 +132,8 @@ export interface StreamEventFinalDone {
   type: "final_done";
   /** Token 使用量（可选） */
   usage?: TokenUsage;
+  /** 实际处理请求的端点名称（发生故障转移时为备用端点） */
+  served_by?: string;
 }
 
 /**
@@ -109,6 +143,161 @@ export interface StreamEventError {
   type: "error";
   /** 错误信息 */
   message: string;
+  /** 翻译后的 Provider 错误（错误码与建议操作） */
+  error?: ProviderErrorDetail;
+}
+
+/**
+ * 模型快照漂移事件（会话固定的模型被路由到了不同的快照）
+ */
+export interface StreamEventModelDrift {
+  type: "model_drift";
+  /** 请求的模型 */
+  requested: string;
+  /** 会话固定的快照 */
+  pinned: string;
+  /** 变化前的模型 */
+  from: string;
+  /** 变化后的模型 */
+  to: string;
+}
+
+/**
+ * 上下文回退事件（提示词超过会话模型的上下文窗口，本次请求改用更大上下文的模型）
+ */
+export interface StreamEventContextFallback {
+  type: "context_fallback";
+  /** 会话模型 */
+  from: string;
+  /** 本次请求实际使用的模型 */
+  to: string;
+  /** 估算的提示词 token 数 */
+  estimated_tokens: number;
+  /** 会话模型的上下文窗口 */
+  context_window: number;
+}
+
+/**
+ * 上下文压缩事件（上游返回上下文超长错误，已丢弃最早的对话轮次并自动重试）
+ */
+export interface StreamEventContextCompacted {
+  type: "context_compacted";
+  /** 本次请求中省略的最早消息数（会话中保存的历史不变） */
+  removed_messages: number;
+  /** 压缩后估算的提示词 token 数 */
+  estimated_tokens: number;
+}
+
+/**
+ * 上下文用量事件（每次请求模型前推送，模型上下文窗口未知时不推送）
+ */
+export interface StreamEventContextUsage {
+  type: "context_usage";
+  /** 估算的提示词 token 数 */
+  used_tokens: number;
+  /** 本次请求所用模型的上下文窗口 */
+  max_tokens: number;
+}
+
+/**
+ * 中断生成时部分回复的处理方式
+ * - keep: 保留已生成内容并写入历史（标记为 truncated）
+ * - discard: 丢弃本轮对话
+ */
+export type CancelMode = "keep" | "discard";
+
+/**
+ * 已取消事件（之后不会再有事件）
+ */
+export interface StreamEventCancelled {
+  type: "cancelled";
+  mode: CancelMode;
+}
+
+/**
+ * Provider 错误类型
+ */
+export type AgentErrorCode =
+  | "invalid_api_key"
+  | "insufficient_quota"
+  | "model_not_found"
+  | "context_length_exceeded"
+  | "content_filter"
+  | "overloaded"
+  | "rate_limit"
+  | "unknown";
+
+/**
+ * 建议操作（用于渲染一键修复按钮）
+ */
+export type AgentErrorAction =
+  | "open_settings"
+  | "switch_model"
+  | "compact_session"
+  | "retry";
+
+/**
+ * 翻译后的 Provider 错误
+ */
+export interface ProviderErrorDetail {
+  code: AgentErrorCode;
+  status?: number;
+  /** 上游返回的原始错误信息 */
+  message: string;
+  /** 面向用户的处理建议 */
+  suggestion: string;
+  actions: AgentErrorAction[];
+}
+
+/**
+ * Agent 命令错误码
+ */
+export type AgentErrorKind =
+  | "not_initialized"
+  | "server_not_running"
+  | "missing_api_key"
+  | "upstream"
+  | "network"
+  | "parse"
+  | "session_not_found"
+  | "cancelled"
+  | "invalid_request"
+  | "other";
+
+/**
+ * Agent 命令返回的错误（invoke 被拒绝时的值）
+ */
+export interface AgentError {
+  code: AgentErrorKind;
+  /** 错误信息（中文，可直接展示） */
+  message: string;
+  /** 上游 HTTP 状态码（code 为 upstream 且上游返回错误状态码时；流式响应中途出错时为空） */
+  status?: number;
+  /** 翻译后的 Provider 错误（code 为 upstream 时） */
+  detail?: ProviderErrorDetail;
+  /** 不存在的会话 ID（code 为 session_not_found 时） */
+  session_id?: string;
+}
+
+/**
+ * 判断 invoke 抛出的错误是否为 AgentError
+ */
+export function isAgentError(error: unknown): error is AgentError {
+  return (
+    typeof error === "object" &&
+    error !== null &&
+    "code" in error &&
+    "message" in error
+  );
+}
+
+/**
+ * 提取错误信息（兼容仍返回字符串错误的命令）
+ */
+export function formatAgentError(error: unknown): string {
+  if (isAgentError(error)) return error.message;
+  if (error instanceof Error) return error.message;
+  return String(error);
 }
 
 /**
@@ -172,12 +361,46 @@ export function parseStreamEvent(data: unknown): StreamEvent | null {
       return {
         type: "final_done",
         usage: event.usage as TokenUsage | undefined,
+        served_by: event.served_by as string | undefined,
       };
     case "error":
       return {
         type: "error",
         message: (event.message as string) || "Unknown error",
       };
+    case "model_drift":
+      return {
+        type: "model_drift",
+        requested: (event.requested as string) || "",
+        pinned: (event.pinned as string) || "",
+        from: (event.from as string) || "",
+        to: (event.to as string) || "",
+      };
+    case "context_fallback":
+      return {
+        type: "context_fallback",
+        from: (event.from as string) || "",
+        to: (event.to as string) || "",
+        estimated_tokens: (event.estimated_tokens as number) || 0,
+        context_window: (event.context_window as number) || 0,
+      };
+    case "context_compacted":
+      return {
+        type: "context_compacted",
+        removed_messages: (event.removed_messages as number) || 0,
+        estimated_tokens: (event.estimated_tokens as number) || 0,
+      };
+    case "context_usage":
+      return {
+        type: "context_usage",
+        used_tokens: (event.used_tokens as number) || 0,
+        max_tokens: (event.max_tokens as number) || 0,
+      };
+    case "cancelled":
+      return {
+        type: "cancelled",
+        mode: (event.mode as CancelMode) || "keep",
+      };
     default:
       return null;
   }
@@ -213,14 +436,174 @@ export interface SessionInfo {
   created_at: string;
   last_activity: string;
   messages_count: number;
+  /** 固定的模型快照 */
+  model_pin?: ModelPin;
+  /** 模型快照变化记录 */
+  model_changes: ModelChange[];
+  /** 会话标签 */
+  tags: string[];
+  /** 使用的 Provider 配置档案（为空时使用激活的档案） */
+  profile?: string;
+  /** 所在文件夹（为空时位于根目录） */
+  folder?: string;
+  /** 关联的 git 仓库（设置后 Agent 可使用 git 工具） */
+  repo_path?: string;
+  /** http_request 工具允许访问的域名 */
+  http_domains: string[];
+  /** 工具权限模式（为空时使用全局配置） */
+  permission_mode?: PermissionMode;
+  /** 是否已归档 */
+  archived: boolean;
+  /** 是否置顶 */
+  pinned: boolean;
+}
+
+/**
+ * 会话列表筛选条件（除归档状态外，未设置的条件不参与筛选）
+ */
+export interface SessionFilter {
+  /** 包含全部指定标签 */
+  tags?: string[];
+  /** 所在文件夹（空字符串表示根目录） */
+  folder?: string;
+  /** 是否已归档（未设置时只返回未归档的会话） */
+  archived?: boolean;
+  pinned?: boolean;
+}
+
+/**
+ * 会话元数据修改（未设置的字段保持不变）
+ */
+export interface SessionMetaUpdate {
+  /** 替换全部标签 */
+  tags?: string[];
+  /** 移动到文件夹（空字符串表示移回根目录） */
+  folder?: string;
+  archived?: boolean;
+  pinned?: boolean;
+}
+
+/**
+ * 会话文件夹
+ */
+export interface SessionFolder {
+  name: string;
+  /** 文件夹中的会话数 */
+  sessions: number;
+}
+
+/**
+ * 会话固定的模型快照
+ */
+export interface ModelPin {
+  /** 请求时使用的模型名（可能是别名） */
+  requested: string;
+  snapshot: string;
+  /** 最近一次响应中上游报告的模型 */
+  last_resolved: string;
+  pinned_at: string;
+}
+
+/**
+ * 模型快照变化记录
+ */
+export interface ModelChange {
+  requested: string;
+  pinned: string;
+  from: string;
+  to: string;
+  changed_at: string;
 }
 
 /**
- * 图片输入
+ * 图片输入（data 与 path 二选一，传 path 时由后端读取、缩放并编码）
  */
 export interface ImageInput {
-  data: string;
-  media_type: string;
+  data?: string;
+  media_type?: string;
+  /** 本地图片路径 */
+  path?: string;
+  /**
+   * 识别精度（OpenAI vision 的 detail 参数）
+   *
+   * low 消耗的 token 最少；缺省时由 Provider 决定，Anthropic 等协议会忽略
+   */
+  detail?: ImageDetail;
+}
+
+/** 图片识别精度 */
+export type ImageDetail = "low" | "high" | "auto";
+
+/**
+ * 剪贴板中的一项内容（按原始顺序传入）
+ */
+export type PasteItem =
+  | { type: "text"; text: string }
+  | {
+      type: "image";
+      /** base64 编码的图片字节 */
+      data: string;
+      name?: string;
+    };
+
+/**
+ * 消息草稿中的内容片段
+ */
+export type DraftPart =
+  | { type: "text"; text: string }
+  | { type: "image"; path: string; media_type: string; name?: string };
+
+/**
+ * 由粘贴内容组成的消息草稿
+ */
+export interface MessageDraft {
+  /** 按顺序排列的内容片段（相邻文本已合并） */
+  parts: DraftPart[];
+  /** 所有文本片段拼接后的消息文本 */
+  text: string;
+  /** 随消息发送的图片（只带 path） */
+  images: ImageInput[];
+}
+
+/**
+ * 处理剪贴板粘贴内容，图片保存到本地并返回消息草稿
+ */
+export async function prepareAgentPaste(
+  items: PasteItem[],
+): Promise<MessageDraft> {
+  return await invoke("native_agent_prepare_paste", { payload: { items } });
+}
+
+/**
+ * 音频输入（发送给支持音频输入的模型，如 gpt-4o-audio-preview）
+ *
+ * 传 path 时由后端读取文件；格式按文件头识别，支持 wav 和 mp3
+ */
+export interface AudioInput {
+  /** base64 编码的音频数据 */
+  data?: string;
+  format?: "wav" | "mp3";
+  /** 本地音频路径 */
+  path?: string;
+}
+
+/**
+ * 文件附件（PDF、文本、CSV 等，path 与 data 二选一）
+ */
+export interface AttachmentInput {
+  name: string;
+  /** 本地文件路径 */
+  path?: string;
+  /** base64 编码的文件内容 */
+  data?: string;
+  /** MIME 类型（缺省时按扩展名推断） */
+  media_type?: string;
+  /**
+   * 是否以原生文档块发送（仅 PDF）
+   *
+   * 缺省时 Claude、Gemini 模型直接接收 PDF，其他模型提取文本；false 强制提取文本
+   */
+  native?: boolean;
 }
 
 /**
@@ -270,6 +653,19 @@ export async function createAgentSession(
   });
 }
 
+/**
+ * 从会话生成 Skill
+ *
+ * 使用后台模型把会话提炼为 SKILL.md，写入 ~/.proxycast/skills/<name>/，
+ * 并加入该会话的 Skills 提示词。名称只能包含小写字母、数字和连字符。
+ */
+export async function createSkillFromSession(
+  sessionId: string,
+  name: string,
+): Promise<SkillInfo> {
+  return await invoke("skill_create_from_session", { sessionId, name });
+}
+
 /**
  * 发送消息到 Agent（支持连续对话）- 非流式版本
  */
@@ -294,39 +690,233 @@ export async function sendAgentMessage(
 /**
  * 发送消息到 Agent（流式版本）
  *
- * 通过 Tauri 事件接收响应流，需要配合 listen() 使用：
+ * 响应流通过本次请求专用的 Channel 推送给 `onEvent`，每个事件带有 `stream_id`：
  * @example
  * ```typescript
- * const unlisten = await listen<StreamEvent>(eventName, (event) => {
- *   const data = event.payload;
- *   if (data.type === "text_delta") {
+ * const streamId = await sendAgentMessageStream(message, (event) => {
+ *   if (event.type === "text_delta") {
  *     // 处理文本增量
  *   }
- * });
- * await sendAgentMessageStream(message, eventName, sessionId);
+ * }, sessionId);
  * ```
+ *
+ * @returns 流 ID（中断对话时传给 cancelAgentStream）
  */
 export async function sendAgentMessageStream(
   message: string,
-  eventName: string,
+  onEvent: (event: TaggedStreamEvent) => void,
   sessionId?: string,
   model?: string,
   images?: ImageInput[],
-): Promise<void> {
+  retrievalCollection?: string,
+  attachments?: AttachmentInput[],
+  audio?: AudioInput[],
+): Promise<string> {
+  const channel = new Channel<TaggedStreamEvent>();
+  channel.onmessage = onEvent;
   return await invoke("native_agent_chat_stream", {
     message,
-    eventName,
+    onEvent: channel,
     sessionId,
     model,
     images,
+    audio,
+    attachments,
+    retrievalCollection,
+  });
+}
+
+/**
+ * 中断流式对话
+ *
+ * @param streamId - sendAgentMessageStream 返回的流 ID
+ * @param mode - 部分回复的处理方式
+ * @returns 是否找到进行中的对话
+ */
+export async function cancelAgentStream(
+  streamId: string,
+  mode: CancelMode,
+): Promise<boolean> {
+  return await invoke("native_agent_cancel_stream", { streamId, mode });
+}
+
+/**
+ * 列出进行中的流式对话（含后台任务）
+ */
+export async function listAgentStreams(): Promise<StreamInfo[]> {
+  return await invoke("native_agent_list_streams");
+}
+
+/**
+ * 查询流式对话，已结束时返回 null
+ */
+export async function getAgentStream(
+  streamId: string,
+): Promise<StreamInfo | null> {
+  return await invoke("native_agent_get_stream", { streamId });
+}
+
+/**
+ * 取消运行中的单个工具调用（对话继续，Agent 收到"已取消"的工具结果）
+ *
+ * @param toolId - tool_start 事件中的工具调用 ID
+ * @param sessionId - 工具调用所属的会话（工具调用 ID 只在会话内唯一；无会话的对话不传）
+ * @returns 是否找到运行中的工具调用
+ */
+export async function cancelAgentTool(
+  toolId: string,
+  sessionId?: string,
+): Promise<boolean> {
+  return await invoke("native_agent_cancel_tool", { sessionId, toolId });
+}
+
+/**
+ * WASM 插件加载状态
+ */
+export interface WasmPluginInfo {
+  /** 插件文件名（不含 .wasm），配置权限时使用 */
+  id: string;
+  path: string;
+  enabled: boolean;
+  /** 加载成功时插件提供的工具 */
+  manifest?: {
+    name: string;
+    description: string;
+    parameters: Record<string, unknown>;
+  };
+  /** 加载失败原因 */
+  error?: string;
+}
+
+/**
+ * 获取 WASM 插件的加载状态
+ */
+export async function listWasmPlugins(): Promise<WasmPluginInfo[]> {
+  return await invoke("native_agent_list_wasm_plugins");
+}
+
+/**
+ * 重新扫描插件目录并加载 WASM 插件
+ */
+export async function reloadWasmPlugins(): Promise<WasmPluginInfo[]> {
+  return await invoke("native_agent_reload_wasm_plugins");
+}
+
+/**
+ * 图片生成结果
+ */
+export interface ImageGenerationResult {
+  /** 保存到本地的图片路径 */
+  path: string;
+  /** 模型改写后的提示词 */
+  revised_prompt?: string;
+  /** 使用的模型 */
+  model: string;
+}
+
+/**
+ * 生成图片（通过 ProxyCast 服务器的 /v1/images/generations）
+ */
+export async function generateImage(
+  prompt: string,
+  size?: string,
+  model?: string,
+): Promise<ImageGenerationResult> {
+  return await invoke("native_agent_generate_image", {
+    prompt,
+    size,
+    model,
+  });
+}
+
+/**
+ * 设置工具严格模式（strict function calling + 参数 Schema 校验）
+ */
+export async function setAgentStrictTools(enabled: boolean): Promise<void> {
+  return await invoke("native_agent_set_strict_tools", { enabled });
+}
+
+/**
+ * 设置会话使用的 Provider 配置档案（传 undefined 使用激活的档案）
+ */
+export async function setSessionProfile(
+  sessionId: string,
+  profile?: string,
+): Promise<boolean> {
+  return await invoke("native_agent_set_session_profile", {
+    sessionId,
+    profile,
+  });
+}
+
+/**
+ * 设置会话关联的 git 仓库（repoPath 为空时取消关联），返回规范化后的仓库根目录
+ */
+export async function setSessionRepo(
+  sessionId: string,
+  repoPath?: string,
+): Promise<string | null> {
+  return await invoke("native_agent_set_session_repo", {
+    sessionId,
+    repoPath,
   });
 }
 
 /**
- * 获取会话列表
+ * 设置会话 http_request 工具允许访问的域名（如 api.example.com、*.example.com），返回规范化后的白名单
+ *
+ * 解析到本机或内网地址的域名即使在白名单内也不允许访问
+ */
+export async function setSessionHttpDomains(
+  sessionId: string,
+  domains: string[],
+): Promise<string[]> {
+  return await invoke("native_agent_set_session_http_domains", {
+    sessionId,
+    domains,
+  });
+}
+
+/**
+ * 设置会话的工具权限模式（传 null 使用全局配置）
+ */
+export async function setSessionPermissionMode(
+  sessionId: string,
+  mode: PermissionMode | null,
+): Promise<void> {
+  await invoke("native_agent_set_session_permission_mode", {
+    sessionId,
+    mode,
+  });
+}
+
+/**
+ * 获取会话列表（按 filter 筛选，默认不含已归档的会话，置顶的会话在前）
  */
-export async function listAgentSessions(): Promise<SessionInfo[]> {
-  return await invoke("agent_list_sessions");
+export async function listAgentSessions(
+  filter?: SessionFilter,
+): Promise<SessionInfo[]> {
+  return await invoke("agent_list_sessions", { filter });
+}
+
+/**
+ * 修改会话标签、文件夹、置顶和归档状态
+ */
+export async function updateSessionMeta(
+  sessionId: string,
+  update: SessionMetaUpdate,
+): Promise<void> {
+  return await invoke("native_agent_update_session_meta", {
+    sessionId,
+    update,
+  });
+}
+
+/**
+ * 列出会话文件夹及其中的会话数
+ */
+export async function listSessionFolders(): Promise<SessionFolder[]> {
+  return await invoke("native_agent_list_session_folders");
 }
 
 /**
@@ -347,6 +937,869 @@ export async function deleteAgentSession(sessionId: string): Promise<void> {
   });
 }
 
+/**
+ * 长期记忆
+ */
+export interface AgentMemory {
+  id: string;
+  content: string;
+  /** manual: 手动添加；tool: save_memory 工具；auto: 自动提取 */
+  source: "manual" | "tool" | "auto";
+  /** 保存记忆的会话 */
+  session_id?: string;
+  /** 是否已审核（模型保存的记忆默认未审核） */
+  reviewed: boolean;
+  created_at: string;
+  updated_at: string;
+}
+
+/**
+ * 获取长期记忆（unreviewedOnly 为 true 时只返回模型保存且未审核的记忆）
+ */
+export async function listMemories(
+  unreviewedOnly?: boolean,
+): Promise<AgentMemory[]> {
+  return await invoke("memory_list", { unreviewedOnly });
+}
+
+/**
+ * 手动添加记忆（内容重复时返回 null）
+ */
+export async function addMemory(content: string): Promise<AgentMemory | null> {
+  return await invoke("memory_add", { content });
+}
+
+/**
+ * 编辑记忆
+ */
+export async function updateMemory(
+  id: string,
+  content: string,
+): Promise<boolean> {
+  return await invoke("memory_update", { id, content });
+}
+
+/**
+ * 删除记忆
+ */
+export async function deleteMemory(id: string): Promise<boolean> {
+  return await invoke("memory_delete", { id });
+}
+
+/**
+ * 审核模型保存的记忆（keep 为 false 时删除）
+ */
+export async function reviewMemory(id: string, keep: boolean): Promise<boolean> {
+  return await invoke("memory_review", { id, keep });
+}
+
+/**
+ * 知识库文件导入结果
+ */
+export interface KnowledgeIngestResult {
+  path: string;
+  /** 写入的文本块数量 */
+  chunks: number;
+  /** 失败原因 */
+  error?: string;
+}
+
+/**
+ * 导入文件到知识库集合（支持 PDF、Markdown、纯文本）
+ */
+export async function ingestKnowledgeFiles(
+  collection: string,
+  paths: string[],
+  model?: string,
+): Promise<KnowledgeIngestResult[]> {
+  return await invoke("knowledge_ingest_files", { collection, paths, model });
+}
+
+/**
+ * 将知识库集合关联到会话（传 undefined 取消关联）
+ */
+export async function attachKnowledgeCollection(
+  sessionId: string,
+  collection?: string,
+): Promise<boolean> {
+  return await invoke("knowledge_attach_collection", { sessionId, collection });
+}
+
+/**
+ * 会话压缩建议（后台分析器通过 `agent-session-lint` 事件推送）
+ */
+export interface SessionLintSuggestion {
+  session_id: string;
+  /** stale_context: 话题转移；token_growth: 每轮 token 快速增长 */
+  kind: "stale_context" | "token_growth";
+  message: string;
+  /** 预计节省的 token 比例（0-100） */
+  estimated_savings_pct: number;
+  /** 建议压缩的早期消息数 */
+  compactable_messages: number;
+}
+
+/**
+ * 分析会话并获取压缩建议
+ */
+export async function lintAgentSession(
+  sessionId: string,
+): Promise<SessionLintSuggestion[]> {
+  return await invoke("native_agent_lint_session", { sessionId });
+}
+
+/**
+ * 会话配额状态（事件 agent-session-quota 推送同样的结构）
+ */
+export interface SessionQuotaStatus {
+  level: "normal" | "warning" | "exceeded";
+  sessions: number;
+  /** 最大会话数（0 表示不限制） */
+  max_sessions: number;
+  messages: number;
+  /** 最大消息总数（0 表示不限制） */
+  max_messages: number;
+  /** 建议归档的会话 ID（按最后活动时间从旧到新） */
+  archive_suggestions: string[];
+  message?: string;
+}
+
+/**
+ * 获取会话配额使用情况和归档建议
+ */
+export async function getAgentSessionQuota(): Promise<SessionQuotaStatus> {
+  return await invoke("native_agent_get_session_quota");
+}
+
+/**
+ * 归档会话（写入 ~/.proxycast/sessions/archive 后移除），返回归档文件路径
+ */
+export async function archiveAgentSessions(
+  sessionIds: string[],
+): Promise<string[]> {
+  return await invoke("native_agent_archive_sessions", { sessionIds });
+}
+
+/** 会话记录中的一条消息 */
+export interface TranscriptEntry {
+  timestamp: string;
+  role: "user" | "assistant" | "tool";
+  content: string;
+  model?: string;
+  usage?: TokenUsage;
+  tool_calls?: {
+    id: string;
+    type: string;
+    function: { name: string; arguments: string };
+  }[];
+  tool_call_id?: string;
+  attachments?: {
+    name: string;
+    media_type: string;
+    size: number;
+    tokens: number;
+    truncated: boolean;
+  }[];
+}
+
+/**
+ * 读取会话记录（需在配置中启用 transcripts）
+ */
+export async function getAgentTranscript(
+  sessionId: string,
+): Promise<TranscriptEntry[]> {
+  return await invoke("native_agent_get_transcript", { sessionId });
+}
+
+/**
+ * 导出会话记录（未指定路径时写入 ~/.proxycast/transcripts/exports），返回文件路径
+ */
+export async function exportAgentTranscript(
+  sessionId: string,
+  path?: string,
+): Promise<string> {
+  return await invoke("native_agent_export_transcript", { sessionId, path });
+}
+
+/** 会话导出格式 */
+export type SessionExportFormat = "markdown" | "html";
+
+/**
+ * 导出会话为 Markdown 或单文件 HTML（未指定路径时写入 ~/.proxycast/sessions/exports），返回文件路径
+ * @param includeStats - 附带消息数和 token 统计
+ */
+export async function exportAgentSession(
+  sessionId: string,
+  format: SessionExportFormat,
+  path?: string,
+  includeStats?: boolean,
+): Promise<string> {
+  return await invoke("native_agent_export_session", {
+    sessionId,
+    format,
+    path,
+    includeStats,
+  });
+}
+
+/** 批量操作进度事件名 */
+export const SESSION_BULK_PROGRESS_EVENT = "agent-session-bulk-progress";
+
+/**
+ * 批量操作进度（事件 agent-session-bulk-progress 推送）
+ */
+export interface SessionBulkProgress {
+  operation: "delete" | "tag" | "export";
+  /** 刚处理完的会话 */
+  session_id: string;
+  processed: number;
+  total: number;
+}
+
+export interface SessionBulkExportResult {
+  /** 导出文件路径 */
+  path: string;
+  /** 导出的会话数 */
+  sessions: number;
+}
+
+/**
+ * 批量删除会话（任一会话不存在时整批失败），返回删除的会话数
+ */
+export async function bulkDeleteSessions(ids: string[]): Promise<number> {
+  return await invoke("sessions_bulk_delete", { ids });
+}
+
+/**
+ * 批量添加/移除会话标签，返回标签有变化的会话数
+ */
+export async function bulkTagSessions(
+  ids: string[],
+  add: string[] = [],
+  remove: string[] = [],
+): Promise<number> {
+  return await invoke("sessions_bulk_tag", { ids, add, remove });
+}
+
+/**
+ * 批量导出会话到 JSON 文件（未指定路径时写入 ~/.proxycast/sessions/exports）
+ */
+export async function bulkExportSessions(
+  ids: string[],
+  path?: string,
+): Promise<SessionBulkExportResult> {
+  return await invoke("sessions_bulk_export", { ids, path });
+}
+
+/**
+ * 会话同步结果
+ */
+export interface SessionSyncReport {
+  /** 推送的会话数 */
+  pushed: number;
+  /** 拉取的会话数 */
+  pulled: number;
+  /** 两边一致的会话数 */
+  unchanged: number;
+  /** 因其他设备同时写入而放弃推送的会话数（下次同步再处理） */
+  conflicts: number;
+  synced_at: string;
+}
+
+/**
+ * 立即与配置的 WebDAV / S3 存储同步会话
+ */
+export async function syncSessionsNow(): Promise<SessionSyncReport> {
+  return await invoke("session_sync_now");
+}
+
+/**
+ * Agent 计划的后续任务（事件 agent-followup 推送同样的结构）
+ */
+export interface ScheduledFollowup {
+  id: string;
+  session_id: string;
+  /** 到期后发送到会话的任务内容 */
+  prompt: string;
+  created_at: string;
+  due_at: string;
+  status:
+    | "pending_approval"
+    | "scheduled"
+    | "running"
+    | "completed"
+    | "failed"
+    | "rejected"
+    | "cancelled";
+  /** Agent 的最终回复 */
+  result?: string;
+  error?: string;
+}
+
+/**
+ * 列出 Agent 计划的后续任务（可按会话过滤）
+ */
+export async function listScheduledFollowups(
+  sessionId?: string,
+): Promise<ScheduledFollowup[]> {
+  return await invoke("native_agent_list_scheduled_followups", { sessionId });
+}
+
+/**
+ * 批准或拒绝后续任务
+ */
+export async function reviewScheduledFollowup(
+  id: string,
+  approved: boolean,
+): Promise<ScheduledFollowup> {
+  return await invoke("native_agent_review_scheduled_followup", {
+    id,
+    approved,
+  });
+}
+
+/**
+ * 取消尚未执行的后续任务
+ */
+export async function cancelScheduledFollowup(
+  id: string,
+): Promise<ScheduledFollowup> {
+  return await invoke("native_agent_cancel_scheduled_followup", { id });
+}
+
+/**
+ * 补丁状态变化事件（载荷为 PatchProposal）
+ */
+export const PATCH_PROPOSAL_EVENT = "agent-patch-proposal";
+
+/**
+ * Agent 通过 git_apply_patch 提出的补丁
+ */
+export interface PatchProposal {
+  id: string;
+  session_id: string;
+  repo_path: string;
+  /** unified diff */
+  patch: string;
+  description: string;
+  /** 补丁涉及的文件 */
+  files: string[];
+  created_at: string;
+  status: "pending_approval" | "applying" | "applied" | "failed" | "rejected";
+  error?: string;
+}
+
+/**
+ * 列出 Agent 提出的补丁（可按会话过滤）
+ */
+export async function listPatchProposals(
+  sessionId?: string,
+): Promise<PatchProposal[]> {
+  return await invoke("native_agent_list_patch_proposals", { sessionId });
+}
+
+/**
+ * 批准（应用到仓库）或拒绝补丁
+ */
+export async function reviewPatchProposal(
+  id: string,
+  approved: boolean,
+): Promise<PatchProposal> {
+  return await invoke("native_agent_review_patch_proposal", {
+    id,
+    approved,
+  });
+}
+
+/**
+ * Skill 脚本运行请求状态变化事件（载荷为 ScriptRunRequest）
+ */
+export const SCRIPT_REQUEST_EVENT = "agent-script-request";
+
+/**
+ * Skill 脚本运行请求（run_skill_script 工具运行前等待批准）
+ */
+export interface ScriptRunRequest {
+  id: string;
+  session_id: string;
+  skill: string;
+  /** 脚本路径（相对 Skill 目录） */
+  script: string;
+  args: string[];
+  created_at: string;
+  status: "pending_approval" | "approved" | "rejected" | "expired";
+}
+
+/**
+ * 列出 Skill 脚本运行请求（可按会话过滤）
+ */
+export async function listScriptRequests(
+  sessionId?: string,
+): Promise<ScriptRunRequest[]> {
+  return await invoke("native_agent_list_script_requests", { sessionId });
+}
+
+/**
+ * 批准或拒绝 Skill 脚本运行
+ */
+export async function reviewScriptRequest(
+  id: string,
+  approved: boolean,
+): Promise<ScriptRunRequest> {
+  return await invoke("native_agent_review_script_request", {
+    id,
+    approved,
+  });
+}
+
+/**
+ * 工具调用请求状态变化事件（载荷为 ToolApprovalRequest）
+ */
+export const TOOL_APPROVAL_EVENT = "agent-tool-approval";
+
+/**
+ * 工具调用请求（approve 权限模式下写入/执行类工具执行前等待批准）
+ */
+export interface ToolApprovalRequest {
+  id: string;
+  session_id?: string;
+  tool_name: string;
+  /** 调用参数 */
+  arguments: Record<string, unknown>;
+  created_at: string;
+  status: "pending_approval" | "approved" | "rejected" | "expired";
+}
+
+/**
+ * 列出工具调用请求（可按会话过滤）
+ */
+export async function listToolApprovals(
+  sessionId?: string,
+): Promise<ToolApprovalRequest[]> {
+  return await invoke("native_agent_list_tool_approvals", { sessionId });
+}
+
+/**
+ * 批准或拒绝工具调用
+ */
+export async function reviewToolApproval(
+  id: string,
+  approved: boolean,
+): Promise<ToolApprovalRequest> {
+  return await invoke("native_agent_review_tool_approval", {
+    id,
+    approved,
+  });
+}
+
+/**
+ * 定时任务及其调度状态
+ */
+export interface ScheduledTaskStatus extends ScheduledTaskConfig {
+  /** 下一次计划执行时间（已禁用或表达式无效时为空） */
+  next_run: string | null;
+  running: boolean;
+  /** cron 表达式错误 */
+  error?: string;
+}
+
+/**
+ * 定时任务执行记录（事件 agent-scheduled-task 推送同样的结构）
+ */
+export interface ScheduledTaskRun {
+  id: string;
+  task_name: string;
+  trigger: "schedule" | "missed" | "manual";
+  status: "running" | "completed" | "failed";
+  started_at: string;
+  finished_at: string | null;
+  /** Agent 的最终回复 */
+  output: string | null;
+  error: string | null;
+}
+
+/**
+ * 列出定时任务及下一次执行时间
+ */
+export async function listScheduledTasks(): Promise<ScheduledTaskStatus[]> {
+  return await invoke("native_agent_list_scheduled_tasks");
+}
+
+/**
+ * 立即执行定时任务，返回执行中的记录
+ */
+export async function runScheduledTask(
+  name: string,
+): Promise<ScheduledTaskRun> {
+  return await invoke("native_agent_run_scheduled_task", { name });
+}
+
+/**
+ * 获取定时任务的执行记录（按开始时间倒序）
+ */
+export async function listScheduledTaskRuns(
+  taskName?: string,
+  limit?: number,
+): Promise<ScheduledTaskRun[]> {
+  return await invoke("native_agent_list_scheduled_task_runs", {
+    taskName,
+    limit,
+  });
+}
+
+/**
+ * 后台 Agent 任务
+ */
+export interface AgentJob {
+  id: string;
+  /** 执行任务的会话 */
+  session_id: string;
+  message: string;
+  model: string | null;
+  status: "running" | "completed" | "failed" | "cancelled";
+  created_at: string;
+  finished_at: string | null;
+  /** 已生成的文本；运行中为部分输出，完成后为最终回复 */
+  output: string;
+  /** 已开始的工具调用数 */
+  tool_calls: number;
+  /** 正在执行的工具 */
+  current_tool?: string;
+  error?: string;
+}
+
+/**
+ * 推送到 `job://{id}` 的事件
+ */
+export type AgentJobEvent =
+  | { kind: "stream"; event: StreamEvent }
+  | { kind: "progress"; job: AgentJob };
+
+/**
+ * 后台任务的事件名
+ */
+export function agentJobEventName(id: string): string {
+  return `job://${id}`;
+}
+
+/**
+ * 提交后台任务（未指定会话时新建会话）
+ *
+ * 传入 `onEvent` 时从任务开始就能收到全部事件；`job://{id}` 事件只能在拿到任务 ID 后订阅，
+ * 其他窗口订阅后应先调用 getAgentTask 同步当前状态
+ *
+ * @example
+ * ```typescript
+ * const job = await submitAgentTask("重构 parser 模块", (event) => {
+ *   if (event.kind === "progress" && event.job.status !== "running") {
+ *     // 任务结束
+ *   }
+ * });
+ * ```
+ */
+export async function submitAgentTask(
+  message: string,
+  onEvent?: (event: AgentJobEvent) => void,
+  sessionId?: string,
+  model?: string,
+): Promise<AgentJob> {
+  let channel: Channel<AgentJobEvent> | undefined;
+  if (onEvent) {
+    channel = new Channel<AgentJobEvent>();
+    channel.onmessage = onEvent;
+  }
+  return await invoke("agent_submit_task", {
+    message,
+    sessionId,
+    model,
+    onEvent: channel,
+  });
+}
+
+/**
+ * 列出后台任务（按创建时间倒序）
+ */
+export async function listAgentTasks(): Promise<AgentJob[]> {
+  return await invoke("agent_list_tasks");
+}
+
+/**
+ * 获取后台任务详情
+ */
+export async function getAgentTask(id: string): Promise<AgentJob> {
+  return await invoke("agent_get_task", { id });
+}
+
+/**
+ * 取消运行中的后台任务
+ *
+ * @returns 是否找到运行中的任务
+ */
+export async function cancelAgentTask(
+  id: string,
+  mode?: CancelMode,
+): Promise<boolean> {
+  return await invoke("agent_cancel_task", { id, mode });
+}
+
+/** 全局快捷键按下时后端推送的事件名 */
+export const QUICK_ASK_EVENT = "quick-ask-open";
+
+/**
+ * 快速提问结果
+ */
+export interface QuickAskResponse {
+  content: string;
+  duration_ms: number;
+  /** 问答已追加到的草稿会话 */
+  scratch_session_id?: string;
+}
+
+/**
+ * 快速提问：单轮作答（不带会话历史和工具）
+ *
+ * 全局快捷键按下时后端推送 {@link QUICK_ASK_EVENT} 事件，前端据此弹出输入框
+ */
+export async function quickAsk(
+  prompt: string,
+  model?: string,
+): Promise<QuickAskResponse> {
+  return await invoke("quick_ask", { prompt, model });
+}
+
+/**
+ * 批量执行进度（事件 agent-batch-progress 推送）
+ */
+export interface BatchProgress {
+  batch_id: string;
+  /** 刚完成的行号（从 0 开始） */
+  index: number;
+  completed: number;
+  total: number;
+  success: boolean;
+  error?: string;
+}
+
+/**
+ * 批量执行结果
+ */
+export interface BatchRunResult {
+  batch_id: string;
+  total: number;
+  succeeded: number;
+  failed: number;
+  /** 结果文件路径 */
+  output_path: string;
+}
+
+export interface BatchRunOptions {
+  /** 直接传入的输入行（对象按列名填充 `{{列名}}`，字符串填充 `{{input}}`） */
+  inputs?: Array<Record<string, unknown> | string>;
+  /** CSV（首行为表头）、JSON 数组或 JSONL 文件路径 */
+  inputPath?: string;
+  model?: string;
+  /** 并发数（默认 4，最多 8） */
+  concurrency?: number;
+  /** 结果文件路径（.csv 写 CSV，否则写 JSONL；默认 ~/.proxycast/batch） */
+  outputPath?: string;
+}
+
+/**
+ * 批量提示词：对每行输入渲染模板并执行，结果写入文件
+ */
+export async function runAgentBatch(
+  template: string,
+  options: BatchRunOptions,
+): Promise<BatchRunResult> {
+  return await invoke("agent_batch_run", { template, ...options });
+}
+
+/**
+ * 非流式聊天响应
+ */
+export interface NativeChatResponse {
+  content: string;
+  model: string;
+  usage?: TokenUsage;
+  success: boolean;
+  error?: string;
+  error_detail?: ProviderErrorDetail;
+  /** 实际处理请求的端点名称 */
+  served_by?: string;
+  /** 提示词超过上下文窗口时改用的模型 */
+  context_fallback?: ContextFallback;
+}
+
+/**
+ * 上下文回退记录
+ */
+export interface ContextFallback {
+  from: string;
+  to: string;
+  estimated_tokens: number;
+  context_window: number;
+}
+
+/**
+ * 编辑会话中指定消息的文本内容
+ */
+export async function editAgentMessage(
+  sessionId: string,
+  index: number,
+  newContent: string,
+): Promise<void> {
+  return await invoke("native_agent_edit_message", {
+    sessionId,
+    index,
+    newContent,
+  });
+}
+
+/**
+ * 删除会话中指定消息，返回实际删除的消息数（含关联的工具结果）
+ */
+export async function deleteAgentMessage(
+  sessionId: string,
+  index: number,
+): Promise<number> {
+  return await invoke("native_agent_delete_message", { sessionId, index });
+}
+
+/**
+ * 丢弃最后一轮回答并重新生成
+ *
+ * 与 sendAgentMessageStream 相同，新回答通过 `onEvent` 流式推送（含工具调用）；
+ * 请求失败或以 discard 方式中断时恢复原回答
+ *
+ * @returns 流 ID（中断时传给 cancelAgentStream）
+ */
+export async function regenerateAgentResponse(
+  sessionId: string,
+  onEvent: (event: TaggedStreamEvent) => void,
+  model?: string,
+): Promise<string> {
+  const channel = new Channel<TaggedStreamEvent>();
+  channel.onmessage = onEvent;
+  return await invoke("native_agent_regenerate", {
+    sessionId,
+    onEvent: channel,
+    model,
+  });
+}
+
+/**
+ * 请求预览（按实际发送流程组装但不发送）
+ */
+export interface RequestPreview {
+  /** 请求发往的接口路径（/v1/chat/completions 或 /v1/messages） */
+  endpoint: string;
+  /** 将发送给模型的请求体（按 Provider 协议组装：OpenAI 或 Anthropic 格式） */
+  request: Record<string, unknown>;
+  /** 估算的提示词 token 数 */
+  estimated_tokens: number;
+  /** 模型的上下文窗口（未知模型为空） */
+  context_window?: number;
+  /** 上下文超限时本次请求将改用的模型 */
+  context_fallback?: ContextFallback;
+}
+
+/**
+ * 预览发送消息时模型实际收到的请求（含检索上下文、长期记忆、图片、附件和工具定义），不发送也不修改会话
+ */
+export async function previewAgentRequest(
+  sessionId: string,
+  message: string,
+  model?: string,
+  retrievalCollection?: string,
+  images?: ImageInput[],
+  attachments?: AttachmentInput[],
+  audio?: AudioInput[],
+): Promise<RequestPreview> {
+  return await invoke("native_agent_preview_request", {
+    sessionId,
+    message,
+    model,
+    retrievalCollection,
+    images,
+    audio,
+    attachments,
+  });
+}
+
+/**
+ * 回放参数覆盖（未设置的字段沿用原会话配置）
+ */
+export interface ReplayOverrides {
+  model?: string;
+  temperature?: number;
+  system_prompt?: string;
+}
+
+/**
+ * 对话轮次回放结果
+ */
+export interface ReplayResult {
+  session_id: string;
+  turn_id: number;
+  model: string;
+  original?: string;
+  content: string;
+  usage?: TokenUsage;
+  /** 实际处理请求的端点名称 */
+  served_by?: string;
+}
+
+/**
+ * 使用不同参数回放会话中的某一轮，返回新回答用于对比（不修改原会话）
+ */
+export async function replayAgentTurn(
+  sessionId: string,
+  turnId: number,
+  overrides?: ReplayOverrides,
+): Promise<ReplayResult> {
+  return await invoke("native_agent_replay_turn", {
+    sessionId,
+    turnId,
+    overrides,
+  });
+}
+
+/**
+ * 使用后台模型生成会话摘要
+ */
+export async function summarizeAgentSession(
+  sessionId: string,
+): Promise<string> {
+  return await invoke("native_agent_summarize_session", { sessionId });
+}
+
+/**
+ * 使用后台模型生成会话标题
+ */
+export async function generateAgentSessionTitle(
+  sessionId: string,
+): Promise<string> {
+  return await invoke("native_agent_generate_title", { sessionId });
+}
+
+/**
+ * 使用后台模型生成后续问题建议
+ */
+export async function suggestAgentFollowUps(
+  sessionId: string,
+): Promise<string[]> {
+  return await invoke("native_agent_suggest_followups", { sessionId });
+}
+
+/**
+ * 使用后台模型从会话中提取长期记忆，返回新增的记忆
+ */
+export async function extractAgentMemories(
+  sessionId: string,
+): Promise<AgentMemory[]> {
+  return await invoke("native_agent_extract_memories", { sessionId });
+}
+
 // ============================================================
 // Goose Agent API (基于 Goose 框架的完整 Agent 实现)
 // ============================================================
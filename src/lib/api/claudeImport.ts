@@ -0,0 +1,40 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** 导入选项（缺省全部导入） */
+export interface ClaudeImportOptions {
+  skills?: boolean;
+  mcp_servers?: boolean;
+  prompts?: boolean;
+}
+
+export interface ClaudePromptSource {
+  id: string;
+  name: string;
+  /** CLAUDE.md 路径 */
+  path: string;
+}
+
+/** 扫描结果（导入前预览） */
+export interface ClaudeImportPlan {
+  skills: string[];
+  mcp_servers: string[];
+  prompts: ClaudePromptSource[];
+}
+
+export interface ClaudeImportReport {
+  skills_imported: string[];
+  mcp_servers_imported: string[];
+  prompts_imported: string[];
+  /** 已存在而跳过的条目 */
+  skipped: string[];
+  errors: string[];
+}
+
+export const claudeImportApi = {
+  /** 扫描 ~/.claude 中可导入的 Skills、MCP 服务器和 CLAUDE.md */
+  scan: (): Promise<ClaudeImportPlan> => invoke("scan_claude_config"),
+
+  /** 导入 Claude Code 配置，已存在的条目会被跳过 */
+  import: (options?: ClaudeImportOptions): Promise<ClaudeImportReport> =>
+    invoke("import_claude_config", { options }),
+};
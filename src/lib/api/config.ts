@@ -34,11 +34,35 @@ export interface RoutingRuleConfig {
   priority: number;
 }
 
+export interface ModelRouteConditions {
+  headers?: Record<string, string>;
+  client_keys?: string[];
+  min_prompt_tokens?: number;
+  max_prompt_tokens?: number;
+}
+
+export interface ModelRouteTarget {
+  provider: string;
+  model?: string;
+  weight: number;
+}
+
+/** 多目标选择策略：weighted 按权重随机，fastest 选择近期最快的健康目标 */
+export type ModelRouteStrategy = "weighted" | "fastest";
+
+export interface ModelRouteConfig {
+  model: string;
+  conditions?: ModelRouteConditions;
+  strategy?: ModelRouteStrategy;
+  targets: ModelRouteTarget[];
+}
+
 export interface RoutingConfig {
   default_provider: string;
   rules: RoutingRuleConfig[];
   model_aliases: Record<string, string>;
   exclusions: Record<string, string[]>;
+  model_routes?: ModelRouteConfig[];
 }
 
 export interface RetrySettings {
@@ -53,6 +77,10 @@ export interface LoggingConfig {
   level: string;
   retention_days: number;
   include_request_body: boolean;
+  /** 单个后端日志文件大小上限（MB，0 表示不限制） */
+  max_file_size_mb: number;
+  /** 后端日志文件总大小上限（MB，0 表示不限制） */
+  max_total_size_mb: number;
 }
 
 // Credential pool types
@@ -128,6 +156,20 @@ export interface ImportResult {
   warnings: string[];
 }
 
+// Provider profile merge summary
+export interface ProfileMergeSummary {
+  added: string[];
+  replaced: string[];
+  skipped: string[];
+}
+
+// Provider profile import result
+export interface ProfileImportResult {
+  config: Config;
+  summary: ProfileMergeSummary;
+  warnings: string[];
+}
+
 // Config path info
 export interface ConfigPathInfo {
   yaml_path: string;
@@ -172,6 +214,28 @@ export const configApi = {
     return invoke("import_config", { currentConfig, yamlContent, merge });
   },
 
+  // Export provider profiles to JSON (all profiles when names is omitted)
+  async exportProviderProfiles(
+    config: Config,
+    includeKeys: boolean,
+    names?: string[],
+  ): Promise<ExportResult> {
+    return invoke("export_provider_profiles", { config, names, includeKeys });
+  },
+
+  // Import provider profiles from JSON; call saveConfig with the result
+  async importProviderProfiles(
+    currentConfig: Config,
+    jsonContent: string,
+    overwrite: boolean,
+  ): Promise<ProfileImportResult> {
+    return invoke("import_provider_profiles", {
+      currentConfig,
+      jsonContent,
+      overwrite,
+    });
+  },
+
   // Import bundle (JSON bundle or YAML config)
   async importBundle(
     currentConfig: Config,
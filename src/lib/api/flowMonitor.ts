@@ -569,6 +569,8 @@ export interface ExportOptions {
   redact_sensitive?: boolean;
   redaction_rules?: RedactionRule[];
   compress?: boolean;
+  /** 嵌入来源信息（Markdown front-matter / HAR 注释） */
+  include_provenance?: boolean;
 }
 
 /**
@@ -802,6 +804,7 @@ export const flowMonitorApi = {
         include_raw: options.include_raw ?? true,
         include_stream_chunks: options.include_stream_chunks ?? false,
         redact_sensitive: options.redact_sensitive ?? false,
+        include_provenance: options.include_provenance ?? false,
         flow_ids: null,
       },
     });
@@ -975,6 +978,7 @@ export const flowMonitorApi = {
         include_raw: options.include_raw ?? true,
         include_stream_chunks: options.include_stream_chunks ?? false,
         redact_sensitive: options.redact_sensitive ?? false,
+        include_provenance: options.include_provenance ?? false,
         flow_ids: ids,
       },
     });
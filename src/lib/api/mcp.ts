@@ -16,6 +16,43 @@ export interface McpServer {
   created_at?: number;
 }
 
+/** ProxyCast 自身作为 MCP 服务端的运行状态 */
+export interface McpHostStatus {
+  running: boolean;
+  port: number | null;
+  /** SSE 客户端连接数 */
+  sse_clients: number;
+}
+
+/** stdio MCP 服务器进程状态 */
+export type McpProcessState =
+  | "starting"
+  | "running"
+  | "unhealthy"
+  | "restarting"
+  | "stopped"
+  | "failed";
+
+export interface McpServerStatus {
+  id: string;
+  name: string;
+  state: McpProcessState;
+  pid: number | null;
+  /** 自动重启次数 */
+  restart_count: number;
+  started_at: string | null;
+  /** 上次退出原因 */
+  last_exit: string | null;
+  last_error: string | null;
+}
+
+/** MCP 服务器的 `tools/list` 返回的工具 */
+export interface McpToolInfo {
+  name: string;
+  description?: string;
+  inputSchema?: Record<string, unknown>;
+}
+
 export const mcpApi = {
   getServers: (): Promise<McpServer[]> => invoke("get_mcp_servers"),
 
@@ -40,4 +77,27 @@ export const mcpApi = {
 
   /** 同步所有 MCP 配置到实际配置文件 */
   syncAllToLive: (): Promise<void> => invoke("sync_all_mcp_to_live"),
+
+  /** 启动 stdio MCP 服务器进程 */
+  startServer: (id: string): Promise<McpServerStatus> =>
+    invoke("mcp_server_start", { id }),
+
+  /** 停止 MCP 服务器进程 */
+  stopServer: (id: string): Promise<boolean> =>
+    invoke("mcp_server_stop", { id }),
+
+  /** 重启 MCP 服务器进程 */
+  restartServer: (id: string): Promise<McpServerStatus> =>
+    invoke("mcp_server_restart", { id }),
+
+  /** 列出 MCP 服务器提供的工具（未运行时自动启动） */
+  listTools: (id: string): Promise<McpToolInfo[]> =>
+    invoke("mcp_server_list_tools", { id }),
+
+  /** MCP 服务器进程状态（不传 id 时返回所有已启动的进程） */
+  serverStatus: (id?: string): Promise<McpServerStatus[]> =>
+    invoke("mcp_server_status", { id }),
+
+  /** ProxyCast MCP 服务端状态 */
+  hostStatus: (): Promise<McpHostStatus> => invoke("mcp_host_status"),
 };
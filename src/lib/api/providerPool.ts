@@ -172,6 +172,27 @@ export interface HealthCheckResult {
   duration_ms: number;
 }
 
+// Credential cool-down (after upstream 429/401/403)
+export interface CredentialCooldown {
+  status: number;
+  until: string;
+  reason: string;
+}
+
+// Key health in the pool
+export interface CredentialHealth {
+  uuid: string;
+  name?: string;
+  is_healthy: boolean;
+  is_disabled: boolean;
+  usage_count: number;
+  error_count: number;
+  last_used?: string;
+  last_error_message?: string;
+  cooldown?: CredentialCooldown;
+  available: boolean;
+}
+
 // OAuth status
 export interface OAuthStatus {
   has_access_token: boolean;
@@ -273,6 +294,18 @@ export const providerPoolApi = {
     return invoke("reset_provider_pool_health", { providerType });
   },
 
+  // Get key health (including cool-down) for all credentials of a type
+  async getKeyHealth(
+    providerType: PoolProviderType,
+  ): Promise<CredentialHealth[]> {
+    return invoke("get_provider_pool_key_health", { providerType });
+  },
+
+  // Clear a credential's cool-down
+  async clearCooldown(uuid: string): Promise<boolean> {
+    return invoke("clear_provider_pool_cooldown", { uuid });
+  },
+
   // Check health of a single credential
   async checkCredentialHealth(uuid: string): Promise<HealthCheckResult> {
     return invoke("check_provider_pool_credential_health", { uuid });
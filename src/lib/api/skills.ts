@@ -10,6 +10,33 @@ export interface Skill {
   repoOwner?: string;
   repoName?: string;
   repoBranch?: string;
+  /** SKILL.md frontmatter 中的版本号 */
+  version?: string;
+}
+
+/** 已安装技能的更新检查结果 */
+export interface SkillUpdateInfo {
+  directory: string;
+  name: string;
+  /** 更新来源：git（技能目录是 git 仓库）或 registry（技能仓库列表） */
+  source: "git" | "registry";
+  installedVersion?: string | null;
+  latestVersion?: string | null;
+  updateAvailable: boolean;
+  /** 新版本的更新说明 */
+  changelog?: string | null;
+}
+
+/** 技能使用统计 */
+export interface SkillUsageStats {
+  directory: string;
+  /** 是否仍安装在 ~/.proxycast/skills */
+  installed: boolean;
+  /** 模型读取 SKILL.md 或在回复中引用的次数 */
+  referenceCount: number;
+  /** 通过 run_skill_script 调用的次数 */
+  invokeCount: number;
+  lastUsedAt?: string | null;
 }
 
 export interface SkillRepo {
@@ -37,6 +64,30 @@ export const skillsApi = {
     return invoke("uninstall_skill_for_app", { app, directory });
   },
 
+  /**
+   * 检查已安装技能的更新
+   */
+  async checkUpdates(app: AppType = "claude"): Promise<SkillUpdateInfo[]> {
+    return invoke("skill_check_updates", { app });
+  },
+
+  /**
+   * 更新技能到最新版本，返回更新说明
+   */
+  async update(
+    name: string,
+    app: AppType = "claude",
+  ): Promise<SkillUpdateInfo> {
+    return invoke("skill_update", { app, name });
+  },
+
+  /**
+   * 获取 ProxyCast Skills 的使用统计（从未使用的排在最前）
+   */
+  async getStats(): Promise<SkillUsageStats[]> {
+    return invoke("skill_stats");
+  },
+
   async getRepos(): Promise<SkillRepo[]> {
     return invoke("get_skill_repos");
   },
@@ -24,6 +24,31 @@ export interface RequestLog {
   is_streaming: boolean;
   credential_id?: string;
   retry_count: number;
+  method?: string;
+  path?: string;
+  /** 截断后的请求体（需在日志设置中启用请求体记录） */
+  request_body?: string;
+  /** 截断后的响应体（流式响应不记录） */
+  response_body?: string;
+  /** 发起请求的客户端 API Key ID */
+  client_key?: string;
+}
+
+export interface RequestLogQuery {
+  provider?: string;
+  /** 模型名称（包含匹配） */
+  model?: string;
+  status?: RequestStatus;
+  method?: string;
+  /** 请求路径（包含匹配） */
+  path?: string;
+  /** 客户端 API Key ID */
+  client_key?: string;
+  start_time?: string;
+  end_time?: string;
+  min_duration_ms?: number;
+  offset?: number;
+  limit?: number;
 }
 
 export interface StatsSummary {
@@ -135,6 +160,12 @@ export async function getRequestLogs(params?: {
   return invoke("get_request_logs", params || {});
 }
 
+export async function queryRequestLogs(
+  query: RequestLogQuery,
+): Promise<RequestLog[]> {
+  return invoke("query_request_logs", { query });
+}
+
 export async function getRequestLogDetail(
   id: string,
 ): Promise<RequestLog | null> {